@@ -16,9 +16,11 @@ pub(crate) use rpc_client::*;
 use settings_commands::{
     app_close_to_tray_on_close_get, app_close_to_tray_on_close_set, app_settings_get,
     app_settings_set, service_gateway_background_tasks_get, service_gateway_background_tasks_set,
+    service_gateway_cooldowns_clear, service_gateway_cooldowns_list,
     service_gateway_header_policy_get, service_gateway_header_policy_set,
     service_gateway_manual_account_clear, service_gateway_manual_account_get,
-    service_gateway_manual_account_set, service_gateway_route_strategy_get,
+    service_gateway_manual_account_set, service_gateway_model_entitlements_clear,
+    service_gateway_model_entitlements_list, service_gateway_route_strategy_get,
     service_gateway_route_strategy_set, service_gateway_upstream_proxy_get,
     service_gateway_upstream_proxy_set, service_listen_config_get, service_listen_config_set,
     sync_window_runtime_state_from_settings,
@@ -309,6 +311,132 @@ async fn service_requestlog_clear(addr: Option<String>) -> Result<serde_json::Va
     rpc_call_in_background("requestlog/clear", addr, None).await
 }
 
+#[tauri::command]
+async fn service_model_alias_list(
+    addr: Option<String>,
+    key_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "keyId": key_id });
+    rpc_call_in_background("modelAlias/list", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_model_alias_save(
+    addr: Option<String>,
+    item: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("modelAlias/save", addr, Some(item)).await
+}
+
+#[tauri::command]
+async fn service_model_alias_delete(
+    addr: Option<String>,
+    id: i64,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "id": id });
+    rpc_call_in_background("modelAlias/delete", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_model_fallback_list(
+    addr: Option<String>,
+    key_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "keyId": key_id });
+    rpc_call_in_background("modelFallback/list", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_model_fallback_save(
+    addr: Option<String>,
+    item: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("modelFallback/save", addr, Some(item)).await
+}
+
+#[tauri::command]
+async fn service_model_fallback_delete(
+    addr: Option<String>,
+    id: i64,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "id": id });
+    rpc_call_in_background("modelFallback/delete", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_provider_list(addr: Option<String>) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("provider/list", addr, None).await
+}
+
+#[tauri::command]
+async fn service_provider_save(
+    addr: Option<String>,
+    item: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("provider/save", addr, Some(item)).await
+}
+
+#[tauri::command]
+async fn service_provider_delete(
+    addr: Option<String>,
+    id: String,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "id": id });
+    rpc_call_in_background("provider/delete", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_pricing_list(addr: Option<String>) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("pricing/list", addr, None).await
+}
+
+#[tauri::command]
+async fn service_pricing_upsert(
+    addr: Option<String>,
+    item: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("pricing/upsert", addr, Some(item)).await
+}
+
+#[tauri::command]
+async fn service_pricing_delete(
+    addr: Option<String>,
+    model_pattern: String,
+    match_type: Option<String>,
+    input_tokens_above: Option<i64>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({
+      "modelPattern": model_pattern,
+      "matchType": match_type,
+      "inputTokensAbove": input_tokens_above
+    });
+    rpc_call_in_background("pricing/delete", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_pricing_export(addr: Option<String>) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("pricing/export", addr, None).await
+}
+
+#[tauri::command]
+async fn service_pricing_import(
+    addr: Option<String>,
+    json: String,
+    replace: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "json": json, "replace": replace.unwrap_or(false) });
+    rpc_call_in_background("pricing/import", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_pricing_recompute(
+    addr: Option<String>,
+    since: Option<i64>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "since": since });
+    rpc_call_in_background("pricing/recompute", addr, Some(params)).await
+}
+
 #[tauri::command]
 async fn service_requestlog_today_summary(
     addr: Option<String>,
@@ -377,6 +505,46 @@ async fn service_apikey_read_secret(
     rpc_call_in_background("apikey/readSecret", addr, Some(params)).await
 }
 
+#[tauri::command]
+async fn service_apikey_read_budget(
+    addr: Option<String>,
+    key_id: String,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "id": key_id });
+    rpc_call_in_background("apikey/readBudget", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_apikey_update_budget(
+    addr: Option<String>,
+    key_id: String,
+    period: String,
+    token_limit: Option<i64>,
+    cost_limit_usd: Option<f64>,
+    warn_percent: Option<f64>,
+    hard_block: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({
+      "id": key_id,
+      "period": period,
+      "tokenLimit": token_limit,
+      "costLimitUsd": cost_limit_usd,
+      "warnPercent": warn_percent,
+      "hardBlock": hard_block
+    });
+    rpc_call_in_background("apikey/updateBudget", addr, Some(params)).await
+}
+
+#[tauri::command]
+async fn service_apikey_reset_budget(
+    addr: Option<String>,
+    key_id: String,
+    period: String,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "id": key_id, "period": period });
+    rpc_call_in_background("apikey/resetBudget", addr, Some(params)).await
+}
+
 #[tauri::command]
 async fn service_apikey_create(
     addr: Option<String>,
@@ -386,6 +554,11 @@ async fn service_apikey_create(
     protocol_type: Option<String>,
     upstream_base_url: Option<String>,
     static_headers_json: Option<String>,
+    account_group: Option<String>,
+    rpm_limit: Option<i64>,
+    tpm_limit: Option<i64>,
+    max_concurrency: Option<i64>,
+    hedge_delay_ms: Option<i64>,
 ) -> Result<serde_json::Value, String> {
     let params = serde_json::json!({
       "name": name,
@@ -394,6 +567,11 @@ async fn service_apikey_create(
       "protocolType": protocol_type,
       "upstreamBaseUrl": upstream_base_url,
       "staticHeadersJson": static_headers_json,
+      "accountGroup": account_group,
+      "rpmLimit": rpm_limit,
+      "tpmLimit": tpm_limit,
+      "maxConcurrency": max_concurrency,
+      "hedgeDelayMs": hedge_delay_ms,
     });
     rpc_call_in_background("apikey/create", addr, Some(params)).await
 }
@@ -416,6 +594,11 @@ async fn service_apikey_update_model(
     protocol_type: Option<String>,
     upstream_base_url: Option<String>,
     static_headers_json: Option<String>,
+    account_group: Option<String>,
+    rpm_limit: Option<i64>,
+    tpm_limit: Option<i64>,
+    max_concurrency: Option<i64>,
+    hedge_delay_ms: Option<i64>,
 ) -> Result<serde_json::Value, String> {
     let params = serde_json::json!({
       "id": key_id,
@@ -424,6 +607,11 @@ async fn service_apikey_update_model(
       "protocolType": protocol_type,
       "upstreamBaseUrl": upstream_base_url,
       "staticHeadersJson": static_headers_json,
+      "accountGroup": account_group,
+      "rpmLimit": rpm_limit,
+      "tpmLimit": tpm_limit,
+      "maxConcurrency": max_concurrency,
+      "hedgeDelayMs": hedge_delay_ms,
     });
     rpc_call_in_background("apikey/updateModel", addr, Some(params)).await
 }
@@ -581,11 +769,30 @@ pub fn run() {
             service_requestlog_list,
             service_requestlog_clear,
             service_requestlog_today_summary,
+            service_model_alias_list,
+            service_model_alias_save,
+            service_model_alias_delete,
+            service_model_fallback_list,
+            service_model_fallback_save,
+            service_model_fallback_delete,
+            service_provider_list,
+            service_provider_save,
+            service_provider_delete,
+            service_pricing_list,
+            service_pricing_upsert,
+            service_pricing_delete,
+            service_pricing_export,
+            service_pricing_import,
+            service_pricing_recompute,
             service_gateway_route_strategy_get,
             service_gateway_route_strategy_set,
             service_gateway_manual_account_get,
             service_gateway_manual_account_set,
             service_gateway_manual_account_clear,
+            service_gateway_model_entitlements_list,
+            service_gateway_model_entitlements_clear,
+            service_gateway_cooldowns_list,
+            service_gateway_cooldowns_clear,
             service_gateway_header_policy_get,
             service_gateway_header_policy_set,
             service_gateway_background_tasks_get,
@@ -597,6 +804,9 @@ pub fn run() {
             service_login_complete,
             service_apikey_list,
             service_apikey_read_secret,
+            service_apikey_read_budget,
+            service_apikey_update_budget,
+            service_apikey_reset_budget,
             service_apikey_create,
             service_apikey_models,
             service_apikey_update_model,
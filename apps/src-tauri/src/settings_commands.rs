@@ -84,6 +84,39 @@ pub async fn service_gateway_manual_account_clear(
     rpc_call_in_background("gateway/manualAccount/clear", addr, None).await
 }
 
+#[tauri::command]
+pub async fn service_gateway_model_entitlements_list(
+    addr: Option<String>,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("gateway/modelEntitlements/list", addr, None).await
+}
+
+#[tauri::command]
+pub async fn service_gateway_model_entitlements_clear(
+    addr: Option<String>,
+    account_id: Option<String>,
+    model: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "accountId": account_id, "model": model });
+    rpc_call_in_background("gateway/modelEntitlements/clear", addr, Some(params)).await
+}
+
+#[tauri::command]
+pub async fn service_gateway_cooldowns_list(
+    addr: Option<String>,
+) -> Result<serde_json::Value, String> {
+    rpc_call_in_background("gateway/cooldowns/list", addr, None).await
+}
+
+#[tauri::command]
+pub async fn service_gateway_cooldowns_clear(
+    addr: Option<String>,
+    account_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let params = serde_json::json!({ "accountId": account_id });
+    rpc_call_in_background("gateway/cooldowns/clear", addr, Some(params)).await
+}
+
 #[tauri::command]
 pub async fn service_gateway_header_policy_get(
     addr: Option<String>,
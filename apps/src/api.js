@@ -444,6 +444,122 @@ export async function serviceRequestLogClear() {
   return invoke("service_requestlog_clear", withAddr());
 }
 
+// 模型别名
+export async function serviceModelAliasList(keyId) {
+  const params = keyId ? { keyId } : undefined;
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelAlias/list", params);
+  }
+  return invoke("service_model_alias_list", withAddr({ keyId: keyId || null }));
+}
+
+export async function serviceModelAliasSave(item) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelAlias/save", item);
+  }
+  return invoke("service_model_alias_save", withAddr({ item }));
+}
+
+export async function serviceModelAliasDelete(id) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelAlias/delete", { id });
+  }
+  return invoke("service_model_alias_delete", withAddr({ id }));
+}
+
+// 模型降级链
+export async function serviceModelFallbackList(keyId) {
+  const params = keyId ? { keyId } : undefined;
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelFallback/list", params);
+  }
+  return invoke("service_model_fallback_list", withAddr({ keyId: keyId || null }));
+}
+
+export async function serviceModelFallbackSave(item) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelFallback/save", item);
+  }
+  return invoke("service_model_fallback_save", withAddr({ item }));
+}
+
+export async function serviceModelFallbackDelete(id) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("modelFallback/delete", { id });
+  }
+  return invoke("service_model_fallback_delete", withAddr({ id }));
+}
+
+// 第三方服务商账号
+export async function serviceProviderList() {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("provider/list");
+  }
+  return invoke("service_provider_list", withAddr());
+}
+
+export async function serviceProviderSave(item) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("provider/save", item);
+  }
+  return invoke("service_provider_save", withAddr({ item }));
+}
+
+export async function serviceProviderDelete(id) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("provider/delete", { id });
+  }
+  return invoke("service_provider_delete", withAddr({ id }));
+}
+
+// 模型价目
+export async function servicePricingList() {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/list");
+  }
+  return invoke("service_pricing_list", withAddr());
+}
+
+export async function servicePricingUpsert(item) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/upsert", item);
+  }
+  return invoke("service_pricing_upsert", withAddr({ item }));
+}
+
+export async function servicePricingDelete(modelPattern, matchType, inputTokensAbove) {
+  const params = {
+    modelPattern,
+    matchType: matchType || null,
+    inputTokensAbove: inputTokensAbove ?? null,
+  };
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/delete", params);
+  }
+  return invoke("service_pricing_delete", withAddr(params));
+}
+
+export async function servicePricingExport() {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/export");
+  }
+  return invoke("service_pricing_export", withAddr());
+}
+
+export async function servicePricingImport(json, replace = false) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/import", { json, replace });
+  }
+  return invoke("service_pricing_import", withAddr({ json, replace }));
+}
+
+export async function servicePricingRecompute(since = null) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("pricing/recompute", { since });
+  }
+  return invoke("service_pricing_recompute", withAddr({ since }));
+}
+
 export async function serviceRequestLogTodaySummary() {
   if (!isTauriRuntime()) {
     return rpcInvoke("requestlog/today_summary");
@@ -486,6 +602,39 @@ export async function serviceGatewayManualAccountClear() {
   return invoke("service_gateway_manual_account_clear", withAddr());
 }
 
+export async function serviceGatewayModelEntitlementsList() {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("gateway/modelEntitlements/list");
+  }
+  return invoke("service_gateway_model_entitlements_list", withAddr());
+}
+
+export async function serviceGatewayModelEntitlementsClear(accountId, model) {
+  const params = {
+    accountId: accountId || null,
+    model: model || null,
+  };
+  if (!isTauriRuntime()) {
+    return rpcInvoke("gateway/modelEntitlements/clear", params);
+  }
+  return invoke("service_gateway_model_entitlements_clear", withAddr(params));
+}
+
+export async function serviceGatewayCooldownsList() {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("gateway/cooldowns/list");
+  }
+  return invoke("service_gateway_cooldowns_list", withAddr());
+}
+
+export async function serviceGatewayCooldownsClear(accountId) {
+  const params = { accountId: accountId || null };
+  if (!isTauriRuntime()) {
+    return rpcInvoke("gateway/cooldowns/clear", params);
+  }
+  return invoke("service_gateway_cooldowns_clear", withAddr(params));
+}
+
 export async function serviceGatewayHeaderPolicyGet() {
   if (!isTauriRuntime()) {
     return rpcInvoke("gateway/headerPolicy/get");
@@ -579,6 +728,34 @@ export async function serviceApiKeyReadSecret(keyId) {
   return invoke("service_apikey_read_secret", withAddr({ keyId }));
 }
 
+export async function serviceApiKeyReadBudget(keyId) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("apikey/readBudget", { id: keyId });
+  }
+  return invoke("service_apikey_read_budget", withAddr({ keyId }));
+}
+
+export async function serviceApiKeyUpdateBudget(keyId, period, budget = {}) {
+  const limits = {
+    period,
+    tokenLimit: budget.tokenLimit ?? null,
+    costLimitUsd: budget.costLimitUsd ?? null,
+    warnPercent: budget.warnPercent ?? null,
+    hardBlock: budget.hardBlock ?? null,
+  };
+  if (!isTauriRuntime()) {
+    return rpcInvoke("apikey/updateBudget", { id: keyId, ...limits });
+  }
+  return invoke("service_apikey_update_budget", withAddr({ keyId, ...limits }));
+}
+
+export async function serviceApiKeyResetBudget(keyId, period) {
+  if (!isTauriRuntime()) {
+    return rpcInvoke("apikey/resetBudget", { id: keyId, period });
+  }
+  return invoke("service_apikey_reset_budget", withAddr({ keyId, period }));
+}
+
 export async function serviceApiKeyCreate(name, modelSlug, reasoningEffort, profile = {}) {
   const params = {
     name,
@@ -587,6 +764,11 @@ export async function serviceApiKeyCreate(name, modelSlug, reasoningEffort, prof
     protocolType: profile.protocolType || null,
     upstreamBaseUrl: profile.upstreamBaseUrl || null,
     staticHeadersJson: profile.staticHeadersJson || null,
+    accountGroup: profile.accountGroup || null,
+    rpmLimit: profile.rpmLimit ?? null,
+    tpmLimit: profile.tpmLimit ?? null,
+    maxConcurrency: profile.maxConcurrency ?? null,
+    hedgeDelayMs: profile.hedgeDelayMs ?? null,
   };
   if (!isTauriRuntime()) {
     return rpcInvoke("apikey/create", params);
@@ -610,6 +792,11 @@ export async function serviceApiKeyUpdateModel(keyId, modelSlug, reasoningEffort
     protocolType: profile.protocolType || null,
     upstreamBaseUrl: profile.upstreamBaseUrl || null,
     staticHeadersJson: profile.staticHeadersJson || null,
+    accountGroup: profile.accountGroup || null,
+    rpmLimit: profile.rpmLimit ?? null,
+    tpmLimit: profile.tpmLimit ?? null,
+    maxConcurrency: profile.maxConcurrency ?? null,
+    hedgeDelayMs: profile.hedgeDelayMs ?? null,
   };
   if (!isTauriRuntime()) {
     return rpcInvoke("apikey/updateModel", params);
@@ -622,6 +809,11 @@ export async function serviceApiKeyUpdateModel(keyId, modelSlug, reasoningEffort
     protocolType: profile.protocolType || null,
     upstreamBaseUrl: profile.upstreamBaseUrl || null,
     staticHeadersJson: profile.staticHeadersJson || null,
+    accountGroup: profile.accountGroup || null,
+    rpmLimit: profile.rpmLimit ?? null,
+    tpmLimit: profile.tpmLimit ?? null,
+    maxConcurrency: profile.maxConcurrency ?? null,
+    hedgeDelayMs: profile.hedgeDelayMs ?? null,
   }));
 }
 
@@ -63,11 +63,18 @@ export function createApiKeyActions({
       const reasoningEffort = modelSlug ? (dom.inputApiKeyReasoning.value || null) : null;
       const protocolType = dom.inputApiKeyProtocol?.value || "openai_compat";
       const isAzureProtocol = protocolType === "azure_openai";
-      const upstreamBaseUrl = isAzureProtocol ? (dom.inputApiKeyEndpoint?.value.trim() || null) : null;
-      const azureApiKey = isAzureProtocol ? (dom.inputApiKeyAzureApiKey?.value.trim() || null) : null;
-      const staticHeadersJson = isAzureProtocol && azureApiKey
-        ? JSON.stringify({ "api-key": azureApiKey })
+      const isAnthropicProtocol = protocolType === "anthropic_native";
+      const hasUpstreamFields = isAzureProtocol || isAnthropicProtocol;
+      const upstreamBaseUrl = hasUpstreamFields ? (dom.inputApiKeyEndpoint?.value.trim() || null) : null;
+      const upstreamApiKey = hasUpstreamFields ? (dom.inputApiKeyAzureApiKey?.value.trim() || null) : null;
+      const staticHeadersJson = upstreamApiKey
+        ? JSON.stringify(isAzureProtocol ? { "api-key": upstreamApiKey } : { "x-api-key": upstreamApiKey })
         : null;
+      const accountGroup = dom.inputApiKeyAccountGroup?.value.trim() || null;
+      const readLimit = (input) => {
+        const value = Number.parseInt(input?.value || "", 10);
+        return Number.isFinite(value) && value > 0 ? value : null;
+      };
       const res = await api.serviceApiKeyCreate(
         dom.inputApiKeyName.value.trim() || null,
         modelSlug,
@@ -76,6 +83,11 @@ export function createApiKeyActions({
           protocolType,
           upstreamBaseUrl,
           staticHeadersJson,
+          accountGroup,
+          rpmLimit: readLimit(dom.inputApiKeyRpmLimit),
+          tpmLimit: readLimit(dom.inputApiKeyTpmLimit),
+          maxConcurrency: readLimit(dom.inputApiKeyMaxConcurrency),
+          hedgeDelayMs: readLimit(dom.inputApiKeyHedgeDelayMs),
         },
       );
       if (res && res.error) {
@@ -147,6 +159,7 @@ export function createApiKeyActions({
       protocolType: item.protocolType || "openai_compat",
       upstreamBaseUrl: item.upstreamBaseUrl || null,
       staticHeadersJson: item.staticHeadersJson || null,
+      accountGroup: item.accountGroup || null,
     });
     if (res && res.ok === false) {
       showToast(res.error || "模型配置保存失败", "error");
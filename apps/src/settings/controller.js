@@ -13,6 +13,7 @@ import { normalizeUpstreamProxyUrl as defaultNormalizeUpstreamProxyUrl } from ".
 
 const ROUTE_STRATEGY_ORDERED = "ordered";
 const ROUTE_STRATEGY_BALANCED = "balanced";
+const ROUTE_STRATEGY_QUOTA_WEIGHTED = "quota_weighted";
 const SERVICE_LISTEN_MODE_LOOPBACK = "loopback";
 const SERVICE_LISTEN_MODE_ALL_INTERFACES = "all_interfaces";
 const UI_LOW_TRANSPARENCY_BODY_CLASS = "cm-low-transparency";
@@ -52,11 +53,21 @@ function defaultNormalizeRouteStrategy(strategy) {
   if (["balanced", "round_robin", "round-robin", "rr"].includes(raw)) {
     return ROUTE_STRATEGY_BALANCED;
   }
+  if (["quota_weighted", "quota-weighted", "quota", "headroom"].includes(raw)) {
+    return ROUTE_STRATEGY_QUOTA_WEIGHTED;
+  }
   return ROUTE_STRATEGY_ORDERED;
 }
 
 function defaultRouteStrategyLabel(strategy) {
-  return defaultNormalizeRouteStrategy(strategy) === ROUTE_STRATEGY_BALANCED ? "均衡轮询" : "顺序优先";
+  const normalized = defaultNormalizeRouteStrategy(strategy);
+  if (normalized === ROUTE_STRATEGY_BALANCED) {
+    return "均衡轮询";
+  }
+  if (normalized === ROUTE_STRATEGY_QUOTA_WEIGHTED) {
+    return "额度优先";
+  }
+  return "顺序优先";
 }
 
 function defaultNormalizeServiceListenMode(value) {
@@ -658,8 +669,11 @@ export function createSettingsController(deps = {}) {
       return;
     }
     let hintText = "按账号顺序优先请求，优先使用可用账号（不可用账号不会参与选路）。";
-    if (normalizeRouteStrategy(strategy) === ROUTE_STRATEGY_BALANCED) {
+    const normalized = normalizeRouteStrategy(strategy);
+    if (normalized === ROUTE_STRATEGY_BALANCED) {
       hintText = "按密钥 + 模型均衡轮询起点，优先使用可用账号（不可用账号不会参与选路）。";
+    } else if (normalized === ROUTE_STRATEGY_QUOTA_WEIGHTED) {
+      hintText = "按最新用量快照的剩余额度排序，额度相同时优先窗口更快重置的账号（不可用账号不会参与选路）。";
     }
     dom.routeStrategyHint.title = hintText;
     dom.routeStrategyHint.setAttribute("aria-label", `网关选路策略说明：${hintText}`);
@@ -158,6 +158,11 @@ export const dom = {
   apiKeyAzureFields: byId("apiKeyAzureFields"),
   inputApiKeyEndpoint: byId("inputApiKeyEndpoint"),
   inputApiKeyAzureApiKey: byId("inputApiKeyAzureApiKey"),
+  inputApiKeyAccountGroup: byId("inputApiKeyAccountGroup"),
+  inputApiKeyRpmLimit: byId("inputApiKeyRpmLimit"),
+  inputApiKeyTpmLimit: byId("inputApiKeyTpmLimit"),
+  inputApiKeyMaxConcurrency: byId("inputApiKeyMaxConcurrency"),
+  inputApiKeyHedgeDelayMs: byId("inputApiKeyHedgeDelayMs"),
   inputApiKeyModel: byId("inputApiKeyModel"),
   inputApiKeyReasoning: byId("inputApiKeyReasoning"),
   apiKeyValue: byId("apiKeyValue"),
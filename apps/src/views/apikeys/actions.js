@@ -27,10 +27,23 @@ function populateReasoningSelect(force = false) {
 function syncApiKeyProtocolFields() {
   const protocolType = dom.inputApiKeyProtocol?.value || "openai_compat";
   const isAzureProtocol = protocolType === "azure_openai";
+  const isAnthropicProtocol = protocolType === "anthropic_native";
+  const hasUpstreamFields = isAzureProtocol || isAnthropicProtocol;
   if (dom.apiKeyAzureFields) {
-    dom.apiKeyAzureFields.hidden = !isAzureProtocol;
+    dom.apiKeyAzureFields.hidden = !hasUpstreamFields;
   }
-  if (!isAzureProtocol) {
+  // Claude 兼容的接入地址可留空：留空时仍转换为 Codex 请求，填写后直连 Anthropic 上游。
+  if (dom.inputApiKeyEndpoint) {
+    dom.inputApiKeyEndpoint.placeholder = isAnthropicProtocol
+      ? "可选，例如：https://api.anthropic.com"
+      : "例如：https://your-resource.openai.azure.com";
+  }
+  if (dom.inputApiKeyAzureApiKey) {
+    dom.inputApiKeyAzureApiKey.placeholder = isAnthropicProtocol
+      ? "例如：sk-ant-..."
+      : "例如：your-azure-key";
+  }
+  if (!hasUpstreamFields) {
     if (dom.inputApiKeyEndpoint) {
       dom.inputApiKeyEndpoint.value = "";
     }
@@ -78,6 +91,19 @@ export function openApiKeyModal() {
   if (dom.inputApiKeyAzureApiKey) {
     dom.inputApiKeyAzureApiKey.value = "";
   }
+  if (dom.inputApiKeyAccountGroup) {
+    dom.inputApiKeyAccountGroup.value = "";
+  }
+  [
+    dom.inputApiKeyRpmLimit,
+    dom.inputApiKeyTpmLimit,
+    dom.inputApiKeyMaxConcurrency,
+    dom.inputApiKeyHedgeDelayMs,
+  ].forEach((input) => {
+    if (input) {
+      input.value = "";
+    }
+  });
   syncApiKeyProtocolFields();
   populateApiKeyModelSelect();
   if (dom.inputApiKeyModel) {
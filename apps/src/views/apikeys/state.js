@@ -16,6 +16,9 @@ export function getProtocolProfileLabel(protocolType) {
   if (protocolType === "azure_openai") {
     return "Azure OpenAI 兼容";
   }
+  if (protocolType === "gemini_native") {
+    return "Gemini 兼容";
+  }
   return protocolType === "anthropic_native"
     ? "Claude Code 兼容"
     : "OpenAI 兼容";
@@ -300,6 +300,11 @@ function createRequestLogRow(item, index) {
   const cellModel = document.createElement("td");
   cellModel.className = "requestlog-col requestlog-col-model";
   cellModel.textContent = item.model || "-";
+  if (item.requestedModel && item.requestedModel !== item.model) {
+    // 模型被别名或 Key 固定模型改写时，同时展示客户端请求的原始模型
+    cellModel.textContent = `${item.requestedModel} → ${item.model || "-"}`;
+    cellModel.title = cellModel.textContent;
+  }
   row.appendChild(cellModel);
 
   const cellEffort = document.createElement("td");
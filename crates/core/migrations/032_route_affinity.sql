@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS route_affinity (
+  affinity_key TEXT PRIMARY KEY,
+  account_id TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_route_affinity_updated_at
+  ON route_affinity(updated_at DESC);
@@ -0,0 +1,5 @@
+ALTER TABLE api_key_profiles ADD COLUMN rpm_limit INTEGER;
+
+ALTER TABLE api_key_profiles ADD COLUMN tpm_limit INTEGER;
+
+ALTER TABLE api_key_profiles ADD COLUMN max_concurrency INTEGER;
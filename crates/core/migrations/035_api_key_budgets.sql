@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS api_key_budgets (
+  key_id TEXT NOT NULL,
+  period TEXT NOT NULL CHECK (period IN ('daily', 'weekly', 'monthly')),
+  token_limit INTEGER,
+  cost_limit_usd REAL,
+  warn_percent REAL NOT NULL DEFAULT 80,
+  hard_block INTEGER NOT NULL DEFAULT 1,
+  reset_at INTEGER,
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (key_id, period)
+);
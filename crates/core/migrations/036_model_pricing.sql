@@ -0,0 +1,38 @@
+CREATE TABLE IF NOT EXISTS model_pricing (
+  model_pattern TEXT NOT NULL,
+  match_type TEXT NOT NULL DEFAULT 'prefix' CHECK (match_type IN ('exact', 'prefix')),
+  input_tokens_above INTEGER NOT NULL DEFAULT 0,
+  input_price_per_1m REAL NOT NULL,
+  cached_input_price_per_1m REAL,
+  output_price_per_1m REAL NOT NULL,
+  reasoning_output_price_per_1m REAL,
+  note TEXT,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (model_pattern, match_type, input_tokens_above)
+);
+
+-- 默认价格（USD / 1M tokens），与此前内置价目表保持一致。
+INSERT OR IGNORE INTO model_pricing (
+  model_pattern, match_type, input_tokens_above,
+  input_price_per_1m, cached_input_price_per_1m, output_price_per_1m,
+  reasoning_output_price_per_1m, note, updated_at
+) VALUES
+  ('gpt-5.4-pro', 'prefix', 0, 30.0, NULL, 180.0, NULL, 'no cached input discount', strftime('%s', 'now')),
+  ('gpt-5.4-pro', 'prefix', 272000, 60.0, NULL, 270.0, NULL, 'long context tier', strftime('%s', 'now')),
+  ('gpt-5.4', 'prefix', 0, 2.5, 0.25, 15.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.4', 'prefix', 272000, 5.0, 0.5, 22.5, NULL, 'long context tier', strftime('%s', 'now')),
+  ('gpt-5.3-codex', 'prefix', 0, 1.75, 0.175, 14.0, NULL, 'priced as gpt-5.2-codex until published', strftime('%s', 'now')),
+  ('gpt-5.2-codex', 'prefix', 0, 1.75, 0.175, 14.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.2', 'prefix', 0, 1.75, 0.175, 14.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.1-codex-mini', 'prefix', 0, 0.25, 0.025, 2.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.1-codex-max', 'prefix', 0, 1.25, 0.125, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.1-codex', 'prefix', 0, 1.25, 0.125, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5.1', 'prefix', 0, 1.25, 0.125, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5-codex', 'prefix', 0, 1.25, 0.125, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-5', 'prefix', 0, 1.25, 0.125, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-4.1', 'prefix', 0, 2.0, NULL, 8.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-4o', 'prefix', 0, 2.5, NULL, 10.0, NULL, NULL, strftime('%s', 'now')),
+  ('gpt-4', 'prefix', 0, 30.0, NULL, 60.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3-7', 'prefix', 0, 3.0, NULL, 15.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3-5', 'prefix', 0, 3.0, NULL, 15.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3', 'prefix', 0, 3.0, NULL, 15.0, NULL, NULL, strftime('%s', 'now'));
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS model_alias_rules (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  key_id TEXT,
+  sort INTEGER NOT NULL DEFAULT 0,
+  match_kind TEXT NOT NULL DEFAULT 'glob' CHECK (match_kind IN ('glob', 'regex')),
+  pattern TEXT NOT NULL,
+  target_model TEXT NOT NULL,
+  reasoning_effort TEXT,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_model_alias_rules_key_sort
+  ON model_alias_rules(key_id, sort, id);
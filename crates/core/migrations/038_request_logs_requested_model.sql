@@ -0,0 +1 @@
+ALTER TABLE request_logs ADD COLUMN requested_model TEXT;
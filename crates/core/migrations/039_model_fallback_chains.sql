@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS model_fallback_chains (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  key_id TEXT,
+  model TEXT NOT NULL,
+  fallback_models_json TEXT NOT NULL DEFAULT '[]',
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_model_fallback_chains_key_model
+  ON model_fallback_chains(key_id, model);
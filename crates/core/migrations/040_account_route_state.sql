@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS account_cooldowns (
+  account_id TEXT PRIMARY KEY,
+  cooldown_until INTEGER NOT NULL DEFAULT 0,
+  reason TEXT NOT NULL DEFAULT '',
+  offense_count INTEGER NOT NULL DEFAULT 0,
+  offense_last_at INTEGER NOT NULL DEFAULT 0,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS account_route_quality (
+  account_id TEXT PRIMARY KEY,
+  success_2xx INTEGER NOT NULL DEFAULT 0,
+  challenge_403 INTEGER NOT NULL DEFAULT 0,
+  throttle_429 INTEGER NOT NULL DEFAULT 0,
+  upstream_5xx INTEGER NOT NULL DEFAULT 0,
+  upstream_4xx INTEGER NOT NULL DEFAULT 0,
+  health_score INTEGER NOT NULL DEFAULT 100,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_route_quality_updated_at
+  ON account_route_quality(updated_at DESC);
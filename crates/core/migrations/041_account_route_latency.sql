@@ -0,0 +1,2 @@
+ALTER TABLE account_route_quality ADD COLUMN ttfb_ewma_ms INTEGER;
+ALTER TABLE account_route_quality ADD COLUMN total_ewma_ms INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE api_key_profiles ADD COLUMN hedge_delay_ms INTEGER;
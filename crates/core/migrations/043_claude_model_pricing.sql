@@ -0,0 +1,14 @@
+-- Claude 系列默认价格（USD / 1M tokens），供直连 Anthropic 的 Key 计费；cached 列为 cache read 单价。
+INSERT OR IGNORE INTO model_pricing (
+  model_pattern, match_type, input_tokens_above,
+  input_price_per_1m, cached_input_price_per_1m, output_price_per_1m,
+  reasoning_output_price_per_1m, note, updated_at
+) VALUES
+  ('claude-opus-4-5', 'prefix', 0, 5.0, 0.5, 25.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-opus-4', 'prefix', 0, 15.0, 1.5, 75.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-sonnet-4', 'prefix', 0, 3.0, 0.3, 15.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-sonnet-4', 'prefix', 200000, 6.0, 0.6, 22.5, NULL, 'long context tier', strftime('%s', 'now')),
+  ('claude-haiku-4-5', 'prefix', 0, 1.0, 0.1, 5.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3-5-haiku', 'prefix', 0, 0.8, 0.08, 4.0, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3-haiku', 'prefix', 0, 0.25, 0.03, 1.25, NULL, NULL, strftime('%s', 'now')),
+  ('claude-3-opus', 'prefix', 0, 15.0, 1.5, 75.0, NULL, NULL, strftime('%s', 'now'));
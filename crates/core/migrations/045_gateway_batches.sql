@@ -0,0 +1,62 @@
+CREATE TABLE IF NOT EXISTS gateway_files (
+  id TEXT PRIMARY KEY,
+  key_id TEXT NOT NULL,
+  filename TEXT NOT NULL,
+  purpose TEXT NOT NULL,
+  bytes INTEGER NOT NULL,
+  created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_gateway_files_key_created
+  ON gateway_files(key_id, created_at);
+
+CREATE TABLE IF NOT EXISTS gateway_batches (
+  id TEXT PRIMARY KEY,
+  key_id TEXT NOT NULL,
+  endpoint TEXT NOT NULL,
+  input_file_id TEXT NOT NULL,
+  completion_window TEXT NOT NULL,
+  status TEXT NOT NULL,
+  output_file_id TEXT,
+  error_file_id TEXT,
+  metadata_json TEXT,
+  errors_json TEXT,
+  total_count INTEGER NOT NULL DEFAULT 0,
+  completed_count INTEGER NOT NULL DEFAULT 0,
+  failed_count INTEGER NOT NULL DEFAULT 0,
+  created_at INTEGER NOT NULL,
+  in_progress_at INTEGER,
+  expires_at INTEGER NOT NULL,
+  finalizing_at INTEGER,
+  completed_at INTEGER,
+  failed_at INTEGER,
+  expired_at INTEGER,
+  cancelling_at INTEGER,
+  cancelled_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_gateway_batches_key_created
+  ON gateway_batches(key_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_gateway_batches_status_created
+  ON gateway_batches(status, created_at);
+
+CREATE TABLE IF NOT EXISTS gateway_batch_items (
+  batch_id TEXT NOT NULL,
+  line_index INTEGER NOT NULL,
+  custom_id TEXT NOT NULL,
+  method TEXT NOT NULL,
+  url TEXT NOT NULL,
+  body TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  attempts INTEGER NOT NULL DEFAULT 0,
+  response_status INTEGER,
+  request_id TEXT,
+  response_body TEXT,
+  error_json TEXT,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (batch_id, line_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_gateway_batch_items_status
+  ON gateway_batch_items(batch_id, status, line_index);
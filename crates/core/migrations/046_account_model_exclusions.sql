@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS account_model_exclusions (
+  account_id TEXT NOT NULL,
+  model TEXT NOT NULL,
+  status_code INTEGER NOT NULL DEFAULT 0,
+  reason TEXT NOT NULL DEFAULT '',
+  learned_at INTEGER NOT NULL,
+  expires_at INTEGER NOT NULL,
+  PRIMARY KEY (account_id, model)
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_model_exclusions_expires_at
+  ON account_model_exclusions(expires_at);
@@ -65,6 +65,7 @@ fn request_log_summary_serialization_includes_trace_route_fields() {
         adapted_path: Some("/v1/responses".to_string()),
         method: "POST".to_string(),
         model: Some("gpt-5.3-codex".to_string()),
+        requested_model: Some("codex-latest".to_string()),
         reasoning_effort: Some("high".to_string()),
         response_adapter: Some("OpenAIChatCompletionsJson".to_string()),
         upstream_url: Some("https://api.openai.com/v1".to_string()),
@@ -88,6 +89,7 @@ fn request_log_summary_serialization_includes_trace_route_fields() {
         "responseAdapter",
         "requestPath",
         "upstreamUrl",
+        "requestedModel",
     ] {
         assert!(obj.contains_key(key), "missing key: {key}");
     }
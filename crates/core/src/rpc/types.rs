@@ -138,6 +138,11 @@ pub struct ApiKeySummary {
     pub auth_scheme: String,
     pub upstream_base_url: Option<String>,
     pub static_headers_json: Option<String>,
+    pub account_group: Option<String>,
+    pub rpm_limit: Option<i64>,
+    pub tpm_limit: Option<i64>,
+    pub max_concurrency: Option<i64>,
+    pub hedge_delay_ms: Option<i64>,
     pub status: String,
     pub created_at: i64,
     pub last_used_at: Option<i64>,
@@ -148,6 +153,168 @@ pub struct ApiKeyListResult {
     pub items: Vec<ApiKeySummary>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingItem {
+    pub model_pattern: String,
+    #[serde(default)]
+    pub match_type: Option<String>,
+    #[serde(default)]
+    pub input_tokens_above: Option<i64>,
+    pub input_price_per_1m: f64,
+    #[serde(default)]
+    pub cached_input_price_per_1m: Option<f64>,
+    pub output_price_per_1m: f64,
+    #[serde(default)]
+    pub reasoning_output_price_per_1m: Option<f64>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelPricingListResult {
+    pub items: Vec<ModelPricingItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelAliasRuleItem {
+    #[serde(default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub key_id: Option<String>,
+    #[serde(default)]
+    pub sort: Option<i64>,
+    #[serde(default)]
+    pub match_kind: Option<String>,
+    pub pattern: String,
+    pub target_model: String,
+    #[serde(default)]
+    pub reasoning_effort: Option<String>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelAliasRuleListResult {
+    pub items: Vec<ModelAliasRuleItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFallbackChainItem {
+    #[serde(default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub key_id: Option<String>,
+    pub model: String,
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelFallbackChainListResult {
+    pub items: Vec<ModelFallbackChainItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderAccountItem {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub label: String,
+    pub base_url: String,
+    /// 仅保存时传入；列表不回传明文，留空表示沿用已保存的 Key。
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub has_api_key: Option<bool>,
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub group_name: Option<String>,
+    #[serde(default)]
+    pub sort: Option<i64>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProviderAccountListResult {
+    pub items: Vec<ProviderAccountItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountModelExclusionItem {
+    pub account_id: String,
+    pub model: String,
+    pub status_code: u16,
+    pub reason: String,
+    pub learned_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountModelExclusionListResult {
+    pub items: Vec<AccountModelExclusionItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCooldownItem {
+    pub account_id: String,
+    pub cooldown_until: i64,
+    pub remaining_secs: i64,
+    pub reason: String,
+    pub offense_count: u32,
+    pub offense_last_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountCooldownListResult {
+    pub items: Vec<AccountCooldownItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyBudgetStatus {
+    pub key_id: String,
+    pub period: String,
+    pub token_limit: Option<i64>,
+    pub cost_limit_usd: Option<f64>,
+    pub warn_percent: f64,
+    pub hard_block: bool,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub reset_at: Option<i64>,
+    pub used_tokens: i64,
+    pub used_cost_usd: f64,
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyBudgetListResult {
+    pub items: Vec<ApiKeyBudgetStatus>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiKeyCreateResult {
@@ -185,6 +352,8 @@ pub struct RequestLogSummary {
     pub adapted_path: Option<String>,
     pub method: String,
     pub model: Option<String>,
+    #[serde(default)]
+    pub requested_model: Option<String>,
     pub reasoning_effort: Option<String>,
     pub response_adapter: Option<String>,
     pub upstream_url: Option<String>,
@@ -0,0 +1,226 @@
+use rusqlite::{params, Row};
+
+use super::{
+    AccountCooldownRecord, AccountModelExclusionRecord, AccountRouteQualityRecord, Storage,
+};
+
+impl Storage {
+    pub fn upsert_account_cooldown(&self, record: &AccountCooldownRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO account_cooldowns (account_id, cooldown_until, reason, offense_count, offense_last_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(account_id) DO UPDATE SET
+               cooldown_until = excluded.cooldown_until,
+               reason = excluded.reason,
+               offense_count = excluded.offense_count,
+               offense_last_at = excluded.offense_last_at,
+               updated_at = excluded.updated_at",
+            params![
+                record.account_id,
+                record.cooldown_until,
+                record.reason,
+                i64::from(record.offense_count),
+                record.offense_last_at,
+                record.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_account_cooldown(&self, account_id: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM account_cooldowns WHERE account_id = ?1",
+            [account_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_account_cooldowns(&self) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM account_cooldowns", [])
+    }
+
+    /// 读取冷却未结束或 429 记录仍在遗忘窗口内的账号；`offense_since` 之前的违规次数视为已失效。
+    pub fn list_account_cooldowns(
+        &self,
+        now: i64,
+        offense_since: i64,
+    ) -> rusqlite::Result<Vec<AccountCooldownRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT account_id, cooldown_until, reason, offense_count, offense_last_at, updated_at
+             FROM account_cooldowns
+             WHERE cooldown_until > ?1 OR (offense_count > 0 AND offense_last_at >= ?2)
+             ORDER BY account_id ASC",
+        )?;
+        let mut rows = stmt.query(params![now, offense_since])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_account_cooldown_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// 删除冷却已结束且违规记录已过遗忘窗口的行。
+    pub fn prune_account_cooldowns(&self, now: i64, offense_since: i64) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "DELETE FROM account_cooldowns
+             WHERE cooldown_until <= ?1 AND (offense_count = 0 OR offense_last_at < ?2)",
+            params![now, offense_since],
+        )
+    }
+
+    pub fn upsert_account_route_quality(
+        &self,
+        record: &AccountRouteQualityRecord,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO account_route_quality (account_id, success_2xx, challenge_403, throttle_429, upstream_5xx, upstream_4xx, health_score, ttfb_ewma_ms, total_ewma_ms, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(account_id) DO UPDATE SET
+               success_2xx = excluded.success_2xx,
+               challenge_403 = excluded.challenge_403,
+               throttle_429 = excluded.throttle_429,
+               upstream_5xx = excluded.upstream_5xx,
+               upstream_4xx = excluded.upstream_4xx,
+               health_score = excluded.health_score,
+               ttfb_ewma_ms = excluded.ttfb_ewma_ms,
+               total_ewma_ms = excluded.total_ewma_ms,
+               updated_at = excluded.updated_at",
+            params![
+                record.account_id,
+                i64::from(record.success_2xx),
+                i64::from(record.challenge_403),
+                i64::from(record.throttle_429),
+                i64::from(record.upstream_5xx),
+                i64::from(record.upstream_4xx),
+                record.health_score,
+                record.ttfb_ewma_ms,
+                record.total_ewma_ms,
+                record.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 读取 `updated_since` 之后仍在统计窗口内的路由质量记录。
+    pub fn list_account_route_quality(
+        &self,
+        updated_since: i64,
+    ) -> rusqlite::Result<Vec<AccountRouteQualityRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT account_id, success_2xx, challenge_403, throttle_429, upstream_5xx, upstream_4xx, health_score, ttfb_ewma_ms, total_ewma_ms, updated_at
+             FROM account_route_quality
+             WHERE updated_at >= ?1
+             ORDER BY account_id ASC",
+        )?;
+        let mut rows = stmt.query([updated_since])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(AccountRouteQualityRecord {
+                account_id: row.get(0)?,
+                success_2xx: read_counter(row, 1)?,
+                challenge_403: read_counter(row, 2)?,
+                throttle_429: read_counter(row, 3)?,
+                upstream_5xx: read_counter(row, 4)?,
+                upstream_4xx: read_counter(row, 5)?,
+                health_score: row.get(6)?,
+                ttfb_ewma_ms: row.get(7)?,
+                total_ewma_ms: row.get(8)?,
+                updated_at: row.get(9)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn prune_account_route_quality(&self, updated_before: i64) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "DELETE FROM account_route_quality WHERE updated_at < ?1",
+            [updated_before],
+        )
+    }
+
+    pub fn upsert_account_model_exclusion(
+        &self,
+        record: &AccountModelExclusionRecord,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO account_model_exclusions (account_id, model, status_code, reason, learned_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(account_id, model) DO UPDATE SET
+               status_code = excluded.status_code,
+               reason = excluded.reason,
+               learned_at = excluded.learned_at,
+               expires_at = excluded.expires_at",
+            params![
+                record.account_id,
+                record.model,
+                i64::from(record.status_code),
+                record.reason,
+                record.learned_at,
+                record.expires_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_account_model_exclusion(
+        &self,
+        account_id: &str,
+        model: &str,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM account_model_exclusions WHERE account_id = ?1 AND model = ?2",
+            params![account_id, model],
+        )?;
+        Ok(())
+    }
+
+    /// 读取 `now` 时仍未过期的 (账号, 模型) 排除记录。
+    pub fn list_account_model_exclusions(
+        &self,
+        now: i64,
+    ) -> rusqlite::Result<Vec<AccountModelExclusionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT account_id, model, status_code, reason, learned_at, expires_at
+             FROM account_model_exclusions
+             WHERE expires_at > ?1
+             ORDER BY account_id ASC, model ASC",
+        )?;
+        let mut rows = stmt.query([now])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let status_code: i64 = row.get(2)?;
+            out.push(AccountModelExclusionRecord {
+                account_id: row.get(0)?,
+                model: row.get(1)?,
+                status_code: status_code.clamp(0, i64::from(u16::MAX)) as u16,
+                reason: row.get(3)?,
+                learned_at: row.get(4)?,
+                expires_at: row.get(5)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn prune_account_model_exclusions(&self, now: i64) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "DELETE FROM account_model_exclusions WHERE expires_at <= ?1",
+            [now],
+        )
+    }
+}
+
+fn read_counter(row: &Row<'_>, idx: usize) -> rusqlite::Result<u32> {
+    let value: i64 = row.get(idx)?;
+    Ok(value.clamp(0, i64::from(u32::MAX)) as u32)
+}
+
+fn map_account_cooldown_row(row: &Row<'_>) -> rusqlite::Result<AccountCooldownRecord> {
+    Ok(AccountCooldownRecord {
+        account_id: row.get(0)?,
+        cooldown_until: row.get(1)?,
+        reason: row.get(2)?,
+        offense_count: read_counter(row, 3)?,
+        offense_last_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
@@ -1,6 +1,6 @@
 use rusqlite::{params_from_iter, types::Value, Result, Row};
 
-use super::{now_ts, Account, Storage, Token};
+use super::{now_ts, Account, ProviderAccount, Storage, Token};
 
 #[derive(Clone, Copy)]
 enum AccountUsageQueryMode {
@@ -142,6 +142,12 @@ impl Storage {
         while let Some(row) = rows.next()? {
             out.push(map_gateway_candidate_row(row)?);
         }
+        // 中文注释：服务商账号固定排在 OAuth 账号之后，只在 OAuth 账号都失败后才作为溢出候选。
+        out.extend(
+            self.list_active_provider_accounts()?
+                .iter()
+                .map(ProviderAccount::to_gateway_candidate),
+        );
         Ok(out)
     }
 
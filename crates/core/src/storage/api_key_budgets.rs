@@ -0,0 +1,126 @@
+use rusqlite::{params, Result, Row};
+
+use super::{ApiKeyBudget, Storage};
+
+const API_KEY_BUDGET_SELECT_SQL: &str = "SELECT
+    key_id,
+    period,
+    token_limit,
+    cost_limit_usd,
+    warn_percent,
+    hard_block,
+    reset_at,
+    created_at,
+    updated_at
+ FROM api_key_budgets";
+
+impl Storage {
+    pub fn upsert_api_key_budget(&self, budget: &ApiKeyBudget) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO api_key_budgets (
+                key_id, period, token_limit, cost_limit_usd, warn_percent, hard_block,
+                reset_at, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(key_id, period) DO UPDATE SET
+               token_limit = excluded.token_limit,
+               cost_limit_usd = excluded.cost_limit_usd,
+               warn_percent = excluded.warn_percent,
+               hard_block = excluded.hard_block,
+               updated_at = excluded.updated_at",
+            params![
+                budget.key_id,
+                budget.period,
+                budget.token_limit,
+                budget.cost_limit_usd,
+                budget.warn_percent,
+                budget.hard_block,
+                budget.reset_at,
+                budget.created_at,
+                budget.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_api_key_budgets(&self, key_id: &str) -> Result<Vec<ApiKeyBudget>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{API_KEY_BUDGET_SELECT_SQL} WHERE key_id = ?1 ORDER BY period"
+        ))?;
+        let mut rows = stmt.query([key_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_api_key_budget_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn delete_api_key_budget(&self, key_id: &str, period: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM api_key_budgets WHERE key_id = ?1 AND period = ?2",
+            (key_id, period),
+        )?;
+        Ok(())
+    }
+
+    /// 手动重置某个周期：之后只统计 `reset_at` 之后的消耗，直到进入下一个自然周期。
+    pub fn reset_api_key_budget(&self, key_id: &str, period: &str, reset_at: i64) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE api_key_budgets SET reset_at = ?1, updated_at = ?1
+             WHERE key_id = ?2 AND period = ?3",
+            (reset_at, key_id, period),
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// 汇总某个平台 Key 在时间区间内的 token 与费用消耗，返回 `(tokens, cost_usd)`。
+    pub fn summarize_key_spend_between(
+        &self,
+        key_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<(i64, f64)> {
+        self.conn.query_row(
+            "SELECT
+                IFNULL(SUM(COALESCE(total_tokens, IFNULL(input_tokens, 0) + IFNULL(output_tokens, 0))), 0),
+                IFNULL(SUM(estimated_cost_usd), 0.0)
+             FROM request_token_stats
+             WHERE key_id = ?1 AND created_at >= ?2 AND created_at < ?3",
+            (key_id, start_ts, end_ts),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    /// 与 `summarize_key_spend_between` 相同，另外返回同一快照下已写入的最大 `request_log_id`；
+    /// 调用方在内存里累加后续记录时，据此跳过已经计入汇总的记录。
+    pub fn summarize_key_spend_with_watermark(
+        &self,
+        key_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<(i64, f64, i64)> {
+        self.conn.query_row(
+            "SELECT
+                IFNULL(SUM(COALESCE(total_tokens, IFNULL(input_tokens, 0) + IFNULL(output_tokens, 0))), 0),
+                IFNULL(SUM(estimated_cost_usd), 0.0),
+                (SELECT IFNULL(MAX(request_log_id), 0) FROM request_token_stats)
+             FROM request_token_stats
+             WHERE key_id = ?1 AND created_at >= ?2 AND created_at < ?3",
+            (key_id, start_ts, end_ts),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+    }
+}
+
+fn map_api_key_budget_row(row: &Row<'_>) -> Result<ApiKeyBudget> {
+    Ok(ApiKeyBudget {
+        key_id: row.get(0)?,
+        period: row.get(1)?,
+        token_limit: row.get(2)?,
+        cost_limit_usd: row.get(3)?,
+        warn_percent: row.get(4)?,
+        hard_block: row.get::<_, i64>(5)? != 0,
+        reset_at: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
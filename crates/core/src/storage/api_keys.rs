@@ -12,6 +12,11 @@ const API_KEY_SELECT_SQL: &str = "SELECT
     COALESCE(p.auth_scheme, 'authorization_bearer') AS auth_scheme,
     p.upstream_base_url,
     p.static_headers_json,
+    p.account_group,
+    p.rpm_limit,
+    p.tpm_limit,
+    p.max_concurrency,
+    p.hedge_delay_ms,
     k.key_hash,
     k.status,
     k.created_at,
@@ -35,8 +40,8 @@ impl Storage {
             ),
         )?;
         self.conn.execute(
-            "INSERT INTO api_key_profiles (key_id, client_type, protocol_type, auth_scheme, upstream_base_url, static_headers_json, default_model, reasoning_effort, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "INSERT INTO api_key_profiles (key_id, client_type, protocol_type, auth_scheme, upstream_base_url, static_headers_json, default_model, reasoning_effort, account_group, rpm_limit, tpm_limit, max_concurrency, hedge_delay_ms, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
              ON CONFLICT(key_id) DO UPDATE SET
                client_type = excluded.client_type,
                protocol_type = excluded.protocol_type,
@@ -45,6 +50,11 @@ impl Storage {
                static_headers_json = excluded.static_headers_json,
                default_model = excluded.default_model,
                reasoning_effort = excluded.reasoning_effort,
+               account_group = excluded.account_group,
+               rpm_limit = excluded.rpm_limit,
+               tpm_limit = excluded.tpm_limit,
+               max_concurrency = excluded.max_concurrency,
+               hedge_delay_ms = excluded.hedge_delay_ms,
                updated_at = excluded.updated_at",
             (
                 &key.id,
@@ -55,6 +65,11 @@ impl Storage {
                 &key.static_headers_json,
                 &key.model_slug,
                 &key.reasoning_effort,
+                &key.account_group,
+                key.rpm_limit,
+                key.tpm_limit,
+                key.max_concurrency,
+                key.hedge_delay_ms,
                 key.created_at,
                 now_ts(),
             ),
@@ -227,9 +242,57 @@ impl Storage {
         Ok(())
     }
 
+    pub fn update_api_key_account_group(
+        &self,
+        key_id: &str,
+        account_group: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE api_key_profiles SET account_group = ?1, updated_at = ?2 WHERE key_id = ?3",
+            (account_group, now_ts(), key_id),
+        )?;
+        Ok(())
+    }
+
+    pub fn update_api_key_rate_limits(
+        &self,
+        key_id: &str,
+        rpm_limit: Option<i64>,
+        tpm_limit: Option<i64>,
+        max_concurrency: Option<i64>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE api_key_profiles
+             SET rpm_limit = ?1, tpm_limit = ?2, max_concurrency = ?3, updated_at = ?4
+             WHERE key_id = ?5",
+            (rpm_limit, tpm_limit, max_concurrency, now_ts(), key_id),
+        )?;
+        Ok(())
+    }
+
+    pub fn update_api_key_hedge_delay(
+        &self,
+        key_id: &str,
+        hedge_delay_ms: Option<i64>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE api_key_profiles SET hedge_delay_ms = ?1, updated_at = ?2 WHERE key_id = ?3",
+            (hedge_delay_ms, now_ts(), key_id),
+        )?;
+        Ok(())
+    }
+
     pub fn delete_api_key(&self, key_id: &str) -> Result<()> {
         self.conn
             .execute("DELETE FROM api_key_secrets WHERE key_id = ?1", [key_id])?;
+        self.conn
+            .execute("DELETE FROM api_key_budgets WHERE key_id = ?1", [key_id])?;
+        self.conn
+            .execute("DELETE FROM model_alias_rules WHERE key_id = ?1", [key_id])?;
+        self.conn.execute(
+            "DELETE FROM model_fallback_chains WHERE key_id = ?1",
+            [key_id],
+        )?;
         self.conn
             .execute("DELETE FROM api_keys WHERE id = ?1", [key_id])?;
         Ok(())
@@ -270,13 +333,30 @@ impl Storage {
         Ok(())
     }
 
+    pub(super) fn ensure_api_key_account_group_column(&self) -> Result<()> {
+        self.ensure_column("api_key_profiles", "account_group", "TEXT")?;
+        Ok(())
+    }
+
+    pub(super) fn ensure_api_key_rate_limit_columns(&self) -> Result<()> {
+        self.ensure_column("api_key_profiles", "rpm_limit", "INTEGER")?;
+        self.ensure_column("api_key_profiles", "tpm_limit", "INTEGER")?;
+        self.ensure_column("api_key_profiles", "max_concurrency", "INTEGER")?;
+        Ok(())
+    }
+
+    pub(super) fn ensure_api_key_hedge_delay_column(&self) -> Result<()> {
+        self.ensure_column("api_key_profiles", "hedge_delay_ms", "INTEGER")?;
+        Ok(())
+    }
+
     pub(super) fn ensure_api_key_profiles_table(&self) -> Result<()> {
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS api_key_profiles (
                 key_id TEXT PRIMARY KEY REFERENCES api_keys(id) ON DELETE CASCADE,
                 client_type TEXT NOT NULL CHECK (client_type IN ('codex', 'claude_code')),
-                protocol_type TEXT NOT NULL CHECK (protocol_type IN ('openai_compat', 'anthropic_native', 'azure_openai')),
-                auth_scheme TEXT NOT NULL CHECK (auth_scheme IN ('authorization_bearer', 'x_api_key', 'api_key')),
+                protocol_type TEXT NOT NULL CHECK (protocol_type IN ('openai_compat', 'anthropic_native', 'azure_openai', 'gemini_native')),
+                auth_scheme TEXT NOT NULL CHECK (auth_scheme IN ('authorization_bearer', 'x_api_key', 'api_key', 'x_goog_api_key')),
                 upstream_base_url TEXT,
                 static_headers_json TEXT,
                 default_model TEXT,
@@ -353,9 +433,14 @@ fn map_api_key_row(row: &Row<'_>) -> Result<ApiKey> {
         auth_scheme: row.get(6)?,
         upstream_base_url: row.get(7)?,
         static_headers_json: row.get(8)?,
-        key_hash: row.get(9)?,
-        status: row.get(10)?,
-        created_at: row.get(11)?,
-        last_used_at: row.get(12)?,
+        account_group: row.get(9)?,
+        rpm_limit: row.get(10)?,
+        tpm_limit: row.get(11)?,
+        max_concurrency: row.get(12)?,
+        hedge_delay_ms: row.get(13)?,
+        key_hash: row.get(14)?,
+        status: row.get(15)?,
+        created_at: row.get(16)?,
+        last_used_at: row.get(17)?,
     })
 }
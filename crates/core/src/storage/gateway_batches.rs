@@ -0,0 +1,432 @@
+use rusqlite::{params, params_from_iter, Result, Row};
+
+use super::{GatewayBatch, GatewayBatchItem, GatewayFile, Storage};
+
+const GATEWAY_FILE_SELECT_SQL: &str = "SELECT
+    id,
+    key_id,
+    filename,
+    purpose,
+    bytes,
+    created_at
+ FROM gateway_files";
+
+const GATEWAY_BATCH_SELECT_SQL: &str = "SELECT
+    id,
+    key_id,
+    endpoint,
+    input_file_id,
+    completion_window,
+    status,
+    output_file_id,
+    error_file_id,
+    metadata_json,
+    errors_json,
+    total_count,
+    completed_count,
+    failed_count,
+    created_at,
+    in_progress_at,
+    expires_at,
+    finalizing_at,
+    completed_at,
+    failed_at,
+    expired_at,
+    cancelling_at,
+    cancelled_at
+ FROM gateway_batches";
+
+const GATEWAY_BATCH_ITEM_SELECT_SQL: &str = "SELECT
+    batch_id,
+    line_index,
+    custom_id,
+    method,
+    url,
+    body,
+    status,
+    attempts,
+    response_status,
+    request_id,
+    response_body,
+    error_json,
+    updated_at
+ FROM gateway_batch_items";
+
+/// 仍需要后台执行器处理的批次状态。
+const ACTIVE_GATEWAY_BATCH_STATUSES: [&str; 4] =
+    ["validating", "in_progress", "finalizing", "cancelling"];
+
+/// 状态与记录进入该状态时间的列一一对应；未知状态不写时间戳。
+fn batch_status_timestamp_column(status: &str) -> Option<&'static str> {
+    match status {
+        "in_progress" => Some("in_progress_at"),
+        "finalizing" => Some("finalizing_at"),
+        "completed" => Some("completed_at"),
+        "failed" => Some("failed_at"),
+        "expired" => Some("expired_at"),
+        "cancelling" => Some("cancelling_at"),
+        "cancelled" => Some("cancelled_at"),
+        _ => None,
+    }
+}
+
+impl Storage {
+    pub fn insert_gateway_file(&self, file: &GatewayFile) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO gateway_files (id, key_id, filename, purpose, bytes, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                file.id,
+                file.key_id,
+                file.filename,
+                file.purpose,
+                file.bytes,
+                file.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn find_gateway_file(&self, id: &str) -> Result<Option<GatewayFile>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{GATEWAY_FILE_SELECT_SQL} WHERE id = ?1 LIMIT 1"))?;
+        let mut rows = stmt.query([id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_gateway_file_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_gateway_files(
+        &self,
+        key_id: &str,
+        purpose: Option<&str>,
+    ) -> Result<Vec<GatewayFile>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{GATEWAY_FILE_SELECT_SQL}
+             WHERE key_id = ?1 AND (?2 IS NULL OR purpose = ?2)
+             ORDER BY created_at DESC, id DESC"
+        ))?;
+        let mut rows = stmt.query(params![key_id, purpose])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_gateway_file_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn delete_gateway_file(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM gateway_files WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    /// 批次与全部请求行在同一事务内写入，避免执行器看到只写了一半的批次。
+    pub fn insert_gateway_batch(
+        &self,
+        batch: &GatewayBatch,
+        items: &[GatewayBatchItem],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO gateway_batches (
+                id, key_id, endpoint, input_file_id, completion_window, status,
+                output_file_id, error_file_id, metadata_json, errors_json,
+                total_count, completed_count, failed_count, created_at, in_progress_at,
+                expires_at, finalizing_at, completed_at, failed_at, expired_at,
+                cancelling_at, cancelled_at
+             ) VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11,
+                ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22
+             )",
+            params![
+                batch.id,
+                batch.key_id,
+                batch.endpoint,
+                batch.input_file_id,
+                batch.completion_window,
+                batch.status,
+                batch.output_file_id,
+                batch.error_file_id,
+                batch.metadata_json,
+                batch.errors_json,
+                batch.total_count,
+                batch.completed_count,
+                batch.failed_count,
+                batch.created_at,
+                batch.in_progress_at,
+                batch.expires_at,
+                batch.finalizing_at,
+                batch.completed_at,
+                batch.failed_at,
+                batch.expired_at,
+                batch.cancelling_at,
+                batch.cancelled_at,
+            ],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO gateway_batch_items (
+                    batch_id, line_index, custom_id, method, url, body, status, attempts,
+                    response_status, request_id, response_body, error_json, updated_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for item in items {
+                stmt.execute(params![
+                    item.batch_id,
+                    item.line_index,
+                    item.custom_id,
+                    item.method,
+                    item.url,
+                    item.body,
+                    item.status,
+                    item.attempts,
+                    item.response_status,
+                    item.request_id,
+                    item.response_body,
+                    item.error_json,
+                    item.updated_at,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn find_gateway_batch(&self, id: &str) -> Result<Option<GatewayBatch>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{GATEWAY_BATCH_SELECT_SQL} WHERE id = ?1 LIMIT 1"))?;
+        let mut rows = stmt.query([id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_gateway_batch_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_gateway_batches(&self, key_id: &str, limit: i64) -> Result<Vec<GatewayBatch>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{GATEWAY_BATCH_SELECT_SQL}
+             WHERE key_id = ?1
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?2"
+        ))?;
+        let mut rows = stmt.query(params![key_id, limit.max(1)])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_gateway_batch_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// 按创建顺序列出未结束的批次，供后台执行器（含重启后恢复）逐个处理。
+    pub fn list_active_gateway_batches(&self) -> Result<Vec<GatewayBatch>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{GATEWAY_BATCH_SELECT_SQL}
+             WHERE status IN (?1, ?2, ?3, ?4)
+             ORDER BY created_at ASC, id ASC"
+        ))?;
+        let mut rows = stmt.query(params_from_iter(ACTIVE_GATEWAY_BATCH_STATUSES.iter()))?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_gateway_batch_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// 仅当批次当前处于 `from` 之一时切换到 `to`，并记录对应时间戳；返回是否发生了切换。
+    pub fn transition_gateway_batch(
+        &self,
+        id: &str,
+        from: &[&str],
+        to: &str,
+        at: i64,
+    ) -> Result<bool> {
+        let Some(current) = self.find_gateway_batch(id)? else {
+            return Ok(false);
+        };
+        if !from.contains(&current.status.as_str()) {
+            return Ok(false);
+        }
+        let updated = match batch_status_timestamp_column(to) {
+            Some(column) => self.conn.execute(
+                &format!(
+                    "UPDATE gateway_batches SET status = ?1, {column} = ?2
+                     WHERE id = ?3 AND status = ?4"
+                ),
+                params![to, at, id, current.status],
+            )?,
+            None => self.conn.execute(
+                "UPDATE gateway_batches SET status = ?1 WHERE id = ?2 AND status = ?3",
+                params![to, id, current.status],
+            )?,
+        };
+        Ok(updated > 0)
+    }
+
+    /// 写入最终状态与结果文件 id。
+    pub fn complete_gateway_batch(
+        &self,
+        id: &str,
+        status: &str,
+        output_file_id: Option<&str>,
+        error_file_id: Option<&str>,
+        at: i64,
+    ) -> Result<()> {
+        let column = batch_status_timestamp_column(status).unwrap_or("completed_at");
+        self.conn.execute(
+            &format!(
+                "UPDATE gateway_batches
+                 SET status = ?1, output_file_id = ?2, error_file_id = ?3, {column} = ?4
+                 WHERE id = ?5"
+            ),
+            params![status, output_file_id, error_file_id, at, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn next_pending_gateway_batch_item(
+        &self,
+        batch_id: &str,
+    ) -> Result<Option<GatewayBatchItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{GATEWAY_BATCH_ITEM_SELECT_SQL}
+             WHERE batch_id = ?1 AND status = 'pending'
+             ORDER BY line_index ASC
+             LIMIT 1"
+        ))?;
+        let mut rows = stmt.query([batch_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_gateway_batch_item_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_gateway_batch_items(&self, batch_id: &str) -> Result<Vec<GatewayBatchItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{GATEWAY_BATCH_ITEM_SELECT_SQL} WHERE batch_id = ?1 ORDER BY line_index ASC"
+        ))?;
+        let mut rows = stmt.query([batch_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_gateway_batch_item_row(row)?);
+        }
+        Ok(out)
+    }
+
+    /// 回写单行执行状态，并在同一事务里重新统计批次的 completed / failed 计数。
+    pub fn update_gateway_batch_item(&self, item: &GatewayBatchItem) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE gateway_batch_items
+             SET status = ?1,
+                 attempts = ?2,
+                 response_status = ?3,
+                 request_id = ?4,
+                 response_body = ?5,
+                 error_json = ?6,
+                 updated_at = ?7
+             WHERE batch_id = ?8 AND line_index = ?9",
+            params![
+                item.status,
+                item.attempts,
+                item.response_status,
+                item.request_id,
+                item.response_body,
+                item.error_json,
+                item.updated_at,
+                item.batch_id,
+                item.line_index,
+            ],
+        )?;
+        tx.execute(
+            "UPDATE gateway_batches
+             SET completed_count = (
+                    SELECT COUNT(1) FROM gateway_batch_items
+                    WHERE batch_id = ?1 AND status = 'completed'
+                 ),
+                 failed_count = (
+                    SELECT COUNT(1) FROM gateway_batch_items
+                    WHERE batch_id = ?1 AND status = 'failed'
+                 )
+             WHERE id = ?1",
+            [&item.batch_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 批次被取消或过期时，把剩余未执行的行一次性标记为终态。
+    pub fn close_pending_gateway_batch_items(
+        &self,
+        batch_id: &str,
+        status: &str,
+        error_json: &str,
+        at: i64,
+    ) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE gateway_batch_items
+             SET status = ?1, error_json = ?2, updated_at = ?3
+             WHERE batch_id = ?4 AND status = 'pending'",
+            params![status, error_json, at, batch_id],
+        )
+    }
+}
+
+fn map_gateway_file_row(row: &Row<'_>) -> Result<GatewayFile> {
+    Ok(GatewayFile {
+        id: row.get(0)?,
+        key_id: row.get(1)?,
+        filename: row.get(2)?,
+        purpose: row.get(3)?,
+        bytes: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn map_gateway_batch_row(row: &Row<'_>) -> Result<GatewayBatch> {
+    Ok(GatewayBatch {
+        id: row.get(0)?,
+        key_id: row.get(1)?,
+        endpoint: row.get(2)?,
+        input_file_id: row.get(3)?,
+        completion_window: row.get(4)?,
+        status: row.get(5)?,
+        output_file_id: row.get(6)?,
+        error_file_id: row.get(7)?,
+        metadata_json: row.get(8)?,
+        errors_json: row.get(9)?,
+        total_count: row.get(10)?,
+        completed_count: row.get(11)?,
+        failed_count: row.get(12)?,
+        created_at: row.get(13)?,
+        in_progress_at: row.get(14)?,
+        expires_at: row.get(15)?,
+        finalizing_at: row.get(16)?,
+        completed_at: row.get(17)?,
+        failed_at: row.get(18)?,
+        expired_at: row.get(19)?,
+        cancelling_at: row.get(20)?,
+        cancelled_at: row.get(21)?,
+    })
+}
+
+fn map_gateway_batch_item_row(row: &Row<'_>) -> Result<GatewayBatchItem> {
+    Ok(GatewayBatchItem {
+        batch_id: row.get(0)?,
+        line_index: row.get(1)?,
+        custom_id: row.get(2)?,
+        method: row.get(3)?,
+        url: row.get(4)?,
+        body: row.get(5)?,
+        status: row.get(6)?,
+        attempts: row.get(7)?,
+        response_status: row.get(8)?,
+        request_id: row.get(9)?,
+        response_body: row.get(10)?,
+        error_json: row.get(11)?,
+        updated_at: row.get(12)?,
+    })
+}
@@ -3,17 +3,27 @@ use std::path::Path;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+mod account_route_state;
 mod accounts;
+mod api_key_budgets;
 mod api_keys;
 mod events;
+mod gateway_batches;
+mod model_alias_rules;
+mod model_fallback_chains;
 mod model_options;
+mod model_pricing;
+mod provider_accounts;
 mod request_log_query;
 mod request_logs;
 mod request_token_stats;
+mod route_affinity;
 mod settings;
 mod tokens;
 mod usage;
 
+pub use provider_accounts::PROVIDER_ACCOUNT_ISSUER;
+
 #[derive(Debug, Clone)]
 pub struct Account {
     pub id: String,
@@ -83,6 +93,7 @@ pub struct RequestLog {
     pub adapted_path: Option<String>,
     pub method: String,
     pub model: Option<String>,
+    pub requested_model: Option<String>,
     pub reasoning_effort: Option<String>,
     pub response_adapter: Option<String>,
     pub upstream_url: Option<String>,
@@ -132,12 +143,149 @@ pub struct ApiKey {
     pub auth_scheme: String,
     pub upstream_base_url: Option<String>,
     pub static_headers_json: Option<String>,
+    pub account_group: Option<String>,
+    pub rpm_limit: Option<i64>,
+    pub tpm_limit: Option<i64>,
+    pub max_concurrency: Option<i64>,
+    pub hedge_delay_ms: Option<i64>,
     pub key_hash: String,
     pub status: String,
     pub created_at: i64,
     pub last_used_at: Option<i64>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyBudget {
+    pub key_id: String,
+    pub period: String,
+    pub token_limit: Option<i64>,
+    pub cost_limit_usd: Option<f64>,
+    pub warn_percent: f64,
+    pub hard_block: bool,
+    pub reset_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelAliasRule {
+    pub id: i64,
+    pub key_id: Option<String>,
+    pub sort: i64,
+    pub match_kind: String,
+    pub pattern: String,
+    pub target_model: String,
+    pub reasoning_effort: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelFallbackChain {
+    pub id: i64,
+    pub key_id: Option<String>,
+    pub model: String,
+    pub fallback_models_json: String,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 本地 Batch API 的文件元数据；文件内容存放在数据库目录下，按 id 命名。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayFile {
+    pub id: String,
+    pub key_id: String,
+    pub filename: String,
+    pub purpose: String,
+    pub bytes: i64,
+    pub created_at: i64,
+}
+
+/// 本地 Batch API 的批次，字段与 OpenAI Batch 对象的状态与时间戳一一对应。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayBatch {
+    pub id: String,
+    pub key_id: String,
+    pub endpoint: String,
+    pub input_file_id: String,
+    pub completion_window: String,
+    pub status: String,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub metadata_json: Option<String>,
+    pub errors_json: Option<String>,
+    pub total_count: i64,
+    pub completed_count: i64,
+    pub failed_count: i64,
+    pub created_at: i64,
+    pub in_progress_at: Option<i64>,
+    pub expires_at: i64,
+    pub finalizing_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub failed_at: Option<i64>,
+    pub expired_at: Option<i64>,
+    pub cancelling_at: Option<i64>,
+    pub cancelled_at: Option<i64>,
+}
+
+/// 批次中的一行请求；执行结果落库后才算完成，服务重启时仍为 pending 的行会被重新执行。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayBatchItem {
+    pub batch_id: String,
+    pub line_index: i64,
+    pub custom_id: String,
+    pub method: String,
+    pub url: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub request_id: Option<String>,
+    pub response_body: Option<String>,
+    pub error_json: Option<String>,
+    pub updated_at: i64,
+}
+
+/// 第三方 OpenAI 兼容服务商账号：固定 base URL + 静态 API Key，作为 OAuth 账号之后的溢出候选。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderAccount {
+    pub id: String,
+    pub label: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub models_json: String,
+    pub group_name: Option<String>,
+    pub sort: i64,
+    pub status: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPricing {
+    pub model_pattern: String,
+    pub match_type: String,
+    pub input_tokens_above: i64,
+    pub input_price_per_1m: f64,
+    pub cached_input_price_per_1m: Option<f64>,
+    pub output_price_per_1m: f64,
+    pub reasoning_output_price_per_1m: Option<f64>,
+    pub note: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestTokenStatCostRow {
+    pub id: i64,
+    pub model: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub cached_input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_output_tokens: Option<i64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ModelOptionsCacheRecord {
     pub scope: String,
@@ -145,6 +293,47 @@ pub struct ModelOptionsCacheRecord {
     pub updated_at: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct RouteAffinityRecord {
+    pub affinity_key: String,
+    pub account_id: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountCooldownRecord {
+    pub account_id: String,
+    pub cooldown_until: i64,
+    pub reason: String,
+    pub offense_count: u32,
+    pub offense_last_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRouteQualityRecord {
+    pub account_id: String,
+    pub success_2xx: u32,
+    pub challenge_403: u32,
+    pub throttle_429: u32,
+    pub upstream_5xx: u32,
+    pub upstream_4xx: u32,
+    pub health_score: i32,
+    pub ttfb_ewma_ms: Option<i64>,
+    pub total_ewma_ms: Option<i64>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountModelExclusionRecord {
+    pub account_id: String,
+    pub model: String,
+    pub status_code: u16,
+    pub reason: String,
+    pub learned_at: i64,
+    pub expires_at: i64,
+}
+
 #[derive(Debug)]
 pub struct Storage {
     conn: Connection,
@@ -309,6 +498,74 @@ impl Storage {
             "030_accounts_scale_indexes",
             include_str!("../../migrations/030_accounts_scale_indexes.sql"),
         )?;
+        self.apply_sql_migration(
+            "031_api_key_profiles_constraints_gemini",
+            include_str!("../../migrations/031_api_key_profiles_constraints_gemini.sql"),
+        )?;
+        self.apply_sql_migration(
+            "032_route_affinity",
+            include_str!("../../migrations/032_route_affinity.sql"),
+        )?;
+        self.apply_sql_or_compat_migration(
+            "033_api_key_account_group",
+            include_str!("../../migrations/033_api_key_account_group.sql"),
+            |s| s.ensure_api_key_account_group_column(),
+        )?;
+        self.apply_sql_or_compat_migration(
+            "034_api_key_rate_limits",
+            include_str!("../../migrations/034_api_key_rate_limits.sql"),
+            |s| s.ensure_api_key_rate_limit_columns(),
+        )?;
+        self.apply_sql_migration(
+            "035_api_key_budgets",
+            include_str!("../../migrations/035_api_key_budgets.sql"),
+        )?;
+        self.apply_sql_migration(
+            "036_model_pricing",
+            include_str!("../../migrations/036_model_pricing.sql"),
+        )?;
+        self.apply_sql_migration(
+            "037_model_alias_rules",
+            include_str!("../../migrations/037_model_alias_rules.sql"),
+        )?;
+        self.apply_sql_or_compat_migration(
+            "038_request_logs_requested_model",
+            include_str!("../../migrations/038_request_logs_requested_model.sql"),
+            |s| s.ensure_request_log_requested_model_column(),
+        )?;
+        self.apply_sql_migration(
+            "039_model_fallback_chains",
+            include_str!("../../migrations/039_model_fallback_chains.sql"),
+        )?;
+        self.apply_sql_migration(
+            "040_account_route_state",
+            include_str!("../../migrations/040_account_route_state.sql"),
+        )?;
+        self.apply_sql_migration(
+            "041_account_route_latency",
+            include_str!("../../migrations/041_account_route_latency.sql"),
+        )?;
+        self.apply_sql_or_compat_migration(
+            "042_api_key_hedging",
+            include_str!("../../migrations/042_api_key_hedging.sql"),
+            |s| s.ensure_api_key_hedge_delay_column(),
+        )?;
+        self.apply_sql_migration(
+            "043_claude_model_pricing",
+            include_str!("../../migrations/043_claude_model_pricing.sql"),
+        )?;
+        self.apply_sql_migration(
+            "044_provider_accounts",
+            include_str!("../../migrations/044_provider_accounts.sql"),
+        )?;
+        self.apply_sql_migration(
+            "045_gateway_batches",
+            include_str!("../../migrations/045_gateway_batches.sql"),
+        )?;
+        self.apply_sql_migration(
+            "046_account_model_exclusions",
+            include_str!("../../migrations/046_account_model_exclusions.sql"),
+        )?;
         self.ensure_request_token_stats_table()?;
         Ok(())
     }
@@ -0,0 +1,137 @@
+use rusqlite::{params, Result, Row};
+
+use super::{ModelAliasRule, Storage};
+
+const MODEL_ALIAS_RULE_SELECT_SQL: &str = "SELECT
+    id,
+    key_id,
+    sort,
+    match_kind,
+    pattern,
+    target_model,
+    reasoning_effort,
+    enabled,
+    created_at,
+    updated_at
+ FROM model_alias_rules";
+
+impl Storage {
+    /// 列出别名规则；`key_id` 为 None 时返回全部规则（全局在前），否则只返回该 Key 的规则。
+    pub fn list_model_alias_rules(&self, key_id: Option<&str>) -> Result<Vec<ModelAliasRule>> {
+        let mut out = Vec::new();
+        match key_id {
+            Some(key_id) => {
+                let mut stmt = self.conn.prepare(&format!(
+                    "{MODEL_ALIAS_RULE_SELECT_SQL} WHERE key_id = ?1 ORDER BY sort, id"
+                ))?;
+                let mut rows = stmt.query([key_id])?;
+                while let Some(row) = rows.next()? {
+                    out.push(map_model_alias_rule_row(row)?);
+                }
+            }
+            None => {
+                let mut stmt = self.conn.prepare(&format!(
+                    "{MODEL_ALIAS_RULE_SELECT_SQL} ORDER BY key_id IS NOT NULL, key_id, sort, id"
+                ))?;
+                let mut rows = stmt.query([])?;
+                while let Some(row) = rows.next()? {
+                    out.push(map_model_alias_rule_row(row)?);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// 读取对某个平台 Key 生效的启用规则：Key 专属规则优先于全局规则，同组内按 sort 排序。
+    pub fn list_active_model_alias_rules_for_key(
+        &self,
+        key_id: &str,
+    ) -> Result<Vec<ModelAliasRule>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MODEL_ALIAS_RULE_SELECT_SQL}
+             WHERE enabled = 1 AND (key_id = ?1 OR key_id IS NULL)
+             ORDER BY key_id IS NULL, sort, id"
+        ))?;
+        let mut rows = stmt.query([key_id])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_model_alias_rule_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn find_model_alias_rule(&self, id: i64) -> Result<Option<ModelAliasRule>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{MODEL_ALIAS_RULE_SELECT_SQL} WHERE id = ?1"))?;
+        let mut rows = stmt.query([id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_model_alias_rule_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert_model_alias_rule(&self, rule: &ModelAliasRule) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO model_alias_rules (
+                key_id, sort, match_kind, pattern, target_model, reasoning_effort,
+                enabled, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                rule.key_id,
+                rule.sort,
+                rule.match_kind,
+                rule.pattern,
+                rule.target_model,
+                rule.reasoning_effort,
+                rule.enabled,
+                rule.created_at,
+                rule.updated_at,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_model_alias_rule(&self, rule: &ModelAliasRule) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE model_alias_rules SET
+                key_id = ?1, sort = ?2, match_kind = ?3, pattern = ?4, target_model = ?5,
+                reasoning_effort = ?6, enabled = ?7, updated_at = ?8
+             WHERE id = ?9",
+            params![
+                rule.key_id,
+                rule.sort,
+                rule.match_kind,
+                rule.pattern,
+                rule.target_model,
+                rule.reasoning_effort,
+                rule.enabled,
+                rule.updated_at,
+                rule.id,
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete_model_alias_rule(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM model_alias_rules WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+}
+
+fn map_model_alias_rule_row(row: &Row<'_>) -> Result<ModelAliasRule> {
+    Ok(ModelAliasRule {
+        id: row.get(0)?,
+        key_id: row.get(1)?,
+        sort: row.get(2)?,
+        match_kind: row.get(3)?,
+        pattern: row.get(4)?,
+        target_model: row.get(5)?,
+        reasoning_effort: row.get(6)?,
+        enabled: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
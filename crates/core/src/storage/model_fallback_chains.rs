@@ -0,0 +1,137 @@
+use rusqlite::{params, Result, Row};
+
+use super::{ModelFallbackChain, Storage};
+
+const MODEL_FALLBACK_CHAIN_SELECT_SQL: &str = "SELECT
+    id,
+    key_id,
+    model,
+    fallback_models_json,
+    enabled,
+    created_at,
+    updated_at
+ FROM model_fallback_chains";
+
+impl Storage {
+    /// 列出降级链；`key_id` 为 None 时返回全部（全局在前），否则只返回该 Key 的降级链。
+    pub fn list_model_fallback_chains(
+        &self,
+        key_id: Option<&str>,
+    ) -> Result<Vec<ModelFallbackChain>> {
+        let mut out = Vec::new();
+        match key_id {
+            Some(key_id) => {
+                let mut stmt = self.conn.prepare(&format!(
+                    "{MODEL_FALLBACK_CHAIN_SELECT_SQL} WHERE key_id = ?1 ORDER BY model, id"
+                ))?;
+                let mut rows = stmt.query([key_id])?;
+                while let Some(row) = rows.next()? {
+                    out.push(map_model_fallback_chain_row(row)?);
+                }
+            }
+            None => {
+                let mut stmt = self.conn.prepare(&format!(
+                    "{MODEL_FALLBACK_CHAIN_SELECT_SQL} ORDER BY key_id IS NOT NULL, key_id, model, id"
+                ))?;
+                let mut rows = stmt.query([])?;
+                while let Some(row) = rows.next()? {
+                    out.push(map_model_fallback_chain_row(row)?);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// 查找对某个平台 Key 生效的降级链：Key 专属配置优先，其次全局配置；模型名忽略大小写。
+    pub fn find_active_model_fallback_chain(
+        &self,
+        key_id: &str,
+        model: &str,
+    ) -> Result<Option<ModelFallbackChain>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MODEL_FALLBACK_CHAIN_SELECT_SQL}
+             WHERE enabled = 1
+               AND (key_id = ?1 OR key_id IS NULL)
+               AND model = ?2 COLLATE NOCASE
+             ORDER BY key_id IS NULL, id
+             LIMIT 1"
+        ))?;
+        let mut rows = stmt.query((key_id, model))?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_model_fallback_chain_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 按作用域与模型查找降级链（不区分启用状态），用于保存时去重。
+    pub fn find_model_fallback_chain_by_scope(
+        &self,
+        key_id: Option<&str>,
+        model: &str,
+    ) -> Result<Option<ModelFallbackChain>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MODEL_FALLBACK_CHAIN_SELECT_SQL}
+             WHERE IFNULL(key_id, '') = IFNULL(?1, '') AND model = ?2 COLLATE NOCASE
+             ORDER BY id
+             LIMIT 1"
+        ))?;
+        let mut rows = stmt.query(params![key_id, model])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_model_fallback_chain_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert_model_fallback_chain(&self, chain: &ModelFallbackChain) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO model_fallback_chains (
+                key_id, model, fallback_models_json, enabled, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                chain.key_id,
+                chain.model,
+                chain.fallback_models_json,
+                chain.enabled,
+                chain.created_at,
+                chain.updated_at,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_model_fallback_chain(&self, chain: &ModelFallbackChain) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE model_fallback_chains SET
+                key_id = ?1, model = ?2, fallback_models_json = ?3, enabled = ?4, updated_at = ?5
+             WHERE id = ?6",
+            params![
+                chain.key_id,
+                chain.model,
+                chain.fallback_models_json,
+                chain.enabled,
+                chain.updated_at,
+                chain.id,
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete_model_fallback_chain(&self, id: i64) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM model_fallback_chains WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+}
+
+fn map_model_fallback_chain_row(row: &Row<'_>) -> Result<ModelFallbackChain> {
+    Ok(ModelFallbackChain {
+        id: row.get(0)?,
+        key_id: row.get(1)?,
+        model: row.get(2)?,
+        fallback_models_json: row.get(3)?,
+        enabled: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
@@ -0,0 +1,151 @@
+use rusqlite::{params, Result, Row};
+
+use super::{ModelPricing, RequestTokenStatCostRow, Storage};
+
+const MODEL_PRICING_SELECT_SQL: &str = "SELECT
+    model_pattern,
+    match_type,
+    input_tokens_above,
+    input_price_per_1m,
+    cached_input_price_per_1m,
+    output_price_per_1m,
+    reasoning_output_price_per_1m,
+    note,
+    updated_at
+ FROM model_pricing";
+
+const MODEL_PRICING_UPSERT_SQL: &str = "INSERT INTO model_pricing (
+    model_pattern, match_type, input_tokens_above,
+    input_price_per_1m, cached_input_price_per_1m, output_price_per_1m,
+    reasoning_output_price_per_1m, note, updated_at
+ ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+ ON CONFLICT(model_pattern, match_type, input_tokens_above) DO UPDATE SET
+   input_price_per_1m = excluded.input_price_per_1m,
+   cached_input_price_per_1m = excluded.cached_input_price_per_1m,
+   output_price_per_1m = excluded.output_price_per_1m,
+   reasoning_output_price_per_1m = excluded.reasoning_output_price_per_1m,
+   note = excluded.note,
+   updated_at = excluded.updated_at";
+
+impl Storage {
+    pub fn list_model_pricing(&self) -> Result<Vec<ModelPricing>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MODEL_PRICING_SELECT_SQL} ORDER BY model_pattern, match_type, input_tokens_above"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_model_pricing_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn upsert_model_pricing(&self, pricing: &ModelPricing) -> Result<()> {
+        self.conn
+            .execute(MODEL_PRICING_UPSERT_SQL, model_pricing_params(pricing))?;
+        Ok(())
+    }
+
+    pub fn delete_model_pricing(
+        &self,
+        model_pattern: &str,
+        match_type: &str,
+        input_tokens_above: i64,
+    ) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM model_pricing
+             WHERE model_pattern = ?1 AND match_type = ?2 AND input_tokens_above = ?3",
+            (model_pattern, match_type, input_tokens_above),
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// 批量导入价目；`replace` 为 true 时先清空现有价目，整体在一个事务内完成。
+    pub fn import_model_pricing(&self, items: &[ModelPricing], replace: bool) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        if replace {
+            tx.execute("DELETE FROM model_pricing", [])?;
+        }
+        {
+            let mut stmt = tx.prepare(MODEL_PRICING_UPSERT_SQL)?;
+            for item in items {
+                stmt.execute(model_pricing_params(item))?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 按 id 分页读取 token 统计，用于价目调整后重算历史费用。
+    pub fn list_request_token_stats_for_cost(
+        &self,
+        after_id: i64,
+        since_ts: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<RequestTokenStatCostRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, model, input_tokens, cached_input_tokens, output_tokens, reasoning_output_tokens
+             FROM request_token_stats
+             WHERE id > ?1 AND (?2 IS NULL OR created_at >= ?2)
+             ORDER BY id ASC
+             LIMIT ?3",
+        )?;
+        let mut rows = stmt.query(params![after_id, since_ts, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(RequestTokenStatCostRow {
+                id: row.get(0)?,
+                model: row.get(1)?,
+                input_tokens: row.get(2)?,
+                cached_input_tokens: row.get(3)?,
+                output_tokens: row.get(4)?,
+                reasoning_output_tokens: row.get(5)?,
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn update_request_token_stat_costs(&self, costs: &[(i64, Option<f64>)]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE request_token_stats SET estimated_cost_usd = ?1
+                 WHERE id = ?2 AND estimated_cost_usd IS NOT ?1",
+            )?;
+            for (id, cost) in costs {
+                updated += stmt.execute(params![cost, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+}
+
+fn model_pricing_params(pricing: &ModelPricing) -> impl rusqlite::Params + '_ {
+    (
+        pricing.model_pattern.as_str(),
+        pricing.match_type.as_str(),
+        pricing.input_tokens_above,
+        pricing.input_price_per_1m,
+        pricing.cached_input_price_per_1m,
+        pricing.output_price_per_1m,
+        pricing.reasoning_output_price_per_1m,
+        pricing.note.as_deref(),
+        pricing.updated_at,
+    )
+}
+
+fn map_model_pricing_row(row: &Row<'_>) -> Result<ModelPricing> {
+    Ok(ModelPricing {
+        model_pattern: row.get(0)?,
+        match_type: row.get(1)?,
+        input_tokens_above: row.get(2)?,
+        input_price_per_1m: row.get(3)?,
+        cached_input_price_per_1m: row.get(4)?,
+        output_price_per_1m: row.get(5)?,
+        reasoning_output_price_per_1m: row.get(6)?,
+        note: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
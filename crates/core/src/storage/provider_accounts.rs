@@ -0,0 +1,143 @@
+use rusqlite::{params, Result, Row};
+
+use super::{Account, ProviderAccount, Storage, Token};
+
+/// 服务商账号映射成网关候选时使用的 issuer，网关据此区分 OAuth 账号与服务商账号。
+pub const PROVIDER_ACCOUNT_ISSUER: &str = "provider";
+
+const PROVIDER_ACCOUNT_SELECT_SQL: &str = "SELECT
+    id,
+    label,
+    base_url,
+    api_key,
+    models_json,
+    group_name,
+    sort,
+    status,
+    created_at,
+    updated_at
+ FROM provider_accounts";
+
+impl Storage {
+    pub fn list_provider_accounts(&self) -> Result<Vec<ProviderAccount>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{PROVIDER_ACCOUNT_SELECT_SQL} ORDER BY sort ASC, updated_at DESC"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_provider_account_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn list_active_provider_accounts(&self) -> Result<Vec<ProviderAccount>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{PROVIDER_ACCOUNT_SELECT_SQL}
+             WHERE LOWER(TRIM(COALESCE(status, ''))) = 'active'
+             ORDER BY sort ASC, updated_at DESC"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(map_provider_account_row(row)?);
+        }
+        Ok(out)
+    }
+
+    pub fn find_provider_account(&self, id: &str) -> Result<Option<ProviderAccount>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{PROVIDER_ACCOUNT_SELECT_SQL} WHERE id = ?1 LIMIT 1"
+        ))?;
+        let mut rows = stmt.query([id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(map_provider_account_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn upsert_provider_account(&self, provider: &ProviderAccount) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO provider_accounts (
+                id, label, base_url, api_key, models_json, group_name, sort, status, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                label = excluded.label,
+                base_url = excluded.base_url,
+                api_key = excluded.api_key,
+                models_json = excluded.models_json,
+                group_name = excluded.group_name,
+                sort = excluded.sort,
+                status = excluded.status,
+                updated_at = excluded.updated_at",
+            params![
+                provider.id,
+                provider.label,
+                provider.base_url,
+                provider.api_key,
+                provider.models_json,
+                provider.group_name,
+                provider.sort,
+                provider.status,
+                provider.created_at,
+                provider.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_provider_account(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM provider_accounts WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+}
+
+impl ProviderAccount {
+    /// 映射成网关候选：API Key 放在 access_token，id_token / refresh_token 留空，不参与刷新。
+    pub fn to_gateway_candidate(&self) -> (Account, Token) {
+        let account = Account {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            issuer: PROVIDER_ACCOUNT_ISSUER.to_string(),
+            chatgpt_account_id: None,
+            workspace_id: None,
+            group_name: self.group_name.clone(),
+            sort: self.sort,
+            status: self.status.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        };
+        let token = Token {
+            account_id: self.id.clone(),
+            id_token: String::new(),
+            access_token: self.api_key.clone(),
+            refresh_token: String::new(),
+            api_key_access_token: None,
+            last_refresh: self.updated_at,
+        };
+        (account, token)
+    }
+}
+
+impl Account {
+    pub fn is_provider_account(&self) -> bool {
+        self.issuer == PROVIDER_ACCOUNT_ISSUER
+    }
+}
+
+fn map_provider_account_row(row: &Row<'_>) -> Result<ProviderAccount> {
+    Ok(ProviderAccount {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        base_url: row.get(2)?,
+        api_key: row.get(3)?,
+        models_json: row.get(4)?,
+        group_name: row.get(5)?,
+        sort: row.get(6)?,
+        status: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
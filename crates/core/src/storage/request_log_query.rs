@@ -43,6 +43,9 @@ fn parse_prefixed_request_log_query(raw: &str) -> Option<RequestLogQuery> {
         "adapted" | "adapted_path" => Some(parse_field_query("adapted_path", is_exact, needle)),
         "method" => Some(parse_field_query("method", is_exact, needle)),
         "model" => Some(parse_field_query("model", is_exact, needle)),
+        "requested" | "requested_model" => {
+            Some(parse_field_query("requested_model", is_exact, needle))
+        }
         "reasoning" | "reason" => Some(parse_field_query("reasoning_effort", is_exact, needle)),
         "adapter" => Some(parse_field_query("response_adapter", is_exact, needle)),
         "error" => Some(parse_field_query("error", is_exact, needle)),
@@ -39,8 +39,8 @@ impl Storage {
         self.conn.execute(
             "INSERT INTO request_logs (
                 trace_id, key_id, account_id, request_path, original_path, adapted_path,
-                method, model, reasoning_effort, response_adapter, upstream_url, status_code, error, created_at
-             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                method, model, requested_model, reasoning_effort, response_adapter, upstream_url, status_code, error, created_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             (
                 &log.trace_id,
                 &log.key_id,
@@ -50,6 +50,7 @@ impl Storage {
                 &log.adapted_path,
                 &log.method,
                 &log.model,
+                &log.requested_model,
                 &log.reasoning_effort,
                 &log.response_adapter,
                 &log.upstream_url,
@@ -70,8 +71,8 @@ impl Storage {
         tx.execute(
             "INSERT INTO request_logs (
                 trace_id, key_id, account_id, request_path, original_path, adapted_path,
-                method, model, reasoning_effort, response_adapter, upstream_url, status_code, error, created_at
-             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                method, model, requested_model, reasoning_effort, response_adapter, upstream_url, status_code, error, created_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             (
                 &log.trace_id,
                 &log.key_id,
@@ -81,6 +82,7 @@ impl Storage {
                 &log.adapted_path,
                 &log.method,
                 &log.model,
+                &log.requested_model,
                 &log.reasoning_effort,
                 &log.response_adapter,
                 &log.upstream_url,
@@ -130,7 +132,7 @@ impl Storage {
                 let mut stmt = self.conn.prepare(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -147,7 +149,7 @@ impl Storage {
                 let sql = format!(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -166,7 +168,7 @@ impl Storage {
                 let sql = format!(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -185,7 +187,7 @@ impl Storage {
                 let mut stmt = self.conn.prepare(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -203,7 +205,7 @@ impl Storage {
                 let mut stmt = self.conn.prepare(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -221,7 +223,7 @@ impl Storage {
                 let mut stmt = self.conn.prepare(
                     "SELECT
                         r.trace_id, r.key_id, r.account_id, r.request_path, r.original_path, r.adapted_path,
-                        r.method, r.model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
+                        r.method, r.model, r.requested_model, r.reasoning_effort, r.response_adapter, r.upstream_url, r.status_code,
                         t.input_tokens, t.cached_input_tokens, t.output_tokens, t.total_tokens, t.reasoning_output_tokens, t.estimated_cost_usd,
                         r.error, r.created_at
                      FROM request_logs r
@@ -232,6 +234,7 @@ impl Storage {
                         OR r.method LIKE ?1
                         OR IFNULL(r.account_id,'') LIKE ?1
                         OR IFNULL(r.model,'') LIKE ?1
+                        OR IFNULL(r.requested_model,'') LIKE ?1
                         OR IFNULL(r.reasoning_effort,'') LIKE ?1
                         OR IFNULL(r.response_adapter,'') LIKE ?1
                         OR IFNULL(r.error,'') LIKE ?1
@@ -284,6 +287,7 @@ impl Storage {
                 adapted_path TEXT,
                 method TEXT NOT NULL,
                 model TEXT,
+                requested_model TEXT,
                 reasoning_effort TEXT,
                 response_adapter TEXT,
                 upstream_url TEXT,
@@ -319,6 +323,11 @@ impl Storage {
         Ok(())
     }
 
+    pub(super) fn ensure_request_log_requested_model_column(&self) -> Result<()> {
+        self.ensure_column("request_logs", "requested_model", "TEXT")?;
+        Ok(())
+    }
+
     pub(super) fn ensure_request_log_trace_context_columns(&self) -> Result<()> {
         self.ensure_column("request_logs", "trace_id", "TEXT")?;
         self.ensure_column("request_logs", "original_path", "TEXT")?;
@@ -402,18 +411,19 @@ fn map_request_log_row(row: &Row<'_>) -> Result<RequestLog> {
         adapted_path: row.get(5)?,
         method: row.get(6)?,
         model: row.get(7)?,
-        reasoning_effort: row.get(8)?,
-        response_adapter: row.get(9)?,
-        upstream_url: row.get(10)?,
-        status_code: row.get(11)?,
-        input_tokens: row.get(12)?,
-        cached_input_tokens: row.get(13)?,
-        output_tokens: row.get(14)?,
-        total_tokens: row.get(15)?,
-        reasoning_output_tokens: row.get(16)?,
-        estimated_cost_usd: row.get(17)?,
-        error: row.get(18)?,
-        created_at: row.get(19)?,
+        requested_model: row.get(8)?,
+        reasoning_effort: row.get(9)?,
+        response_adapter: row.get(10)?,
+        upstream_url: row.get(11)?,
+        status_code: row.get(12)?,
+        input_tokens: row.get(13)?,
+        cached_input_tokens: row.get(14)?,
+        output_tokens: row.get(15)?,
+        total_tokens: row.get(16)?,
+        reasoning_output_tokens: row.get(17)?,
+        estimated_cost_usd: row.get(18)?,
+        error: row.get(19)?,
+        created_at: row.get(20)?,
     })
 }
 
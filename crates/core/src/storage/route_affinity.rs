@@ -0,0 +1,85 @@
+use rusqlite::params;
+
+use super::{RouteAffinityRecord, Storage};
+
+impl Storage {
+    pub fn upsert_route_affinity(
+        &self,
+        affinity_key: &str,
+        account_id: &str,
+        updated_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO route_affinity (affinity_key, account_id, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(affinity_key) DO UPDATE SET
+               account_id = excluded.account_id,
+               updated_at = excluded.updated_at",
+            params![affinity_key, account_id, updated_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_route_affinity(&self, affinity_key: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM route_affinity WHERE affinity_key = ?1",
+            [affinity_key],
+        )?;
+        Ok(())
+    }
+
+    /// 按最近使用时间倒序读取粘性路由记录；`updated_since` 为 None 时不过滤时间，
+    /// `limit` 为 None 时不限条数。
+    pub fn list_route_affinities(
+        &self,
+        updated_since: Option<i64>,
+        limit: Option<usize>,
+    ) -> rusqlite::Result<Vec<RouteAffinityRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT affinity_key, account_id, updated_at
+             FROM route_affinity
+             WHERE ?1 IS NULL OR updated_at >= ?1
+             ORDER BY updated_at DESC
+             LIMIT ?2",
+        )?;
+        // 中文注释：SQLite 的 LIMIT 取负数表示不限条数。
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let mut rows = stmt.query(params![updated_since, limit])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(RouteAffinityRecord {
+                affinity_key: row.get(0)?,
+                account_id: row.get(1)?,
+                updated_at: row.get(2)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// 删除 `updated_before` 之前的记录，并只保留最近使用的 `keep` 条；对应参数为 None 时跳过该项清理。
+    pub fn prune_route_affinities(
+        &self,
+        updated_before: Option<i64>,
+        keep: Option<usize>,
+    ) -> rusqlite::Result<usize> {
+        let mut removed = 0;
+        if let Some(updated_before) = updated_before {
+            removed += self.conn.execute(
+                "DELETE FROM route_affinity WHERE updated_at < ?1",
+                [updated_before],
+            )?;
+        }
+        if let Some(keep) = keep {
+            removed += self.conn.execute(
+                "DELETE FROM route_affinity
+                 WHERE affinity_key NOT IN (
+                   SELECT affinity_key FROM route_affinity
+                   ORDER BY updated_at DESC
+                   LIMIT ?1
+                 )",
+                [keep as i64],
+            )?;
+        }
+        Ok(removed)
+    }
+}
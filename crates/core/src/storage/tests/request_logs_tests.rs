@@ -62,6 +62,7 @@ fn insert_request_log_with_token_stat_is_visible_via_join() {
         adapted_path: Some("/v1/responses".to_string()),
         method: "POST".to_string(),
         model: Some("gpt-5".to_string()),
+        requested_model: None,
         reasoning_effort: Some("medium".to_string()),
         response_adapter: Some("OpenAIChatCompletionsJson".to_string()),
         upstream_url: Some("https://example.test".to_string()),
@@ -134,6 +135,7 @@ fn token_stat_failure_still_commits_request_log() {
         adapted_path: Some("/v1/responses".to_string()),
         method: "POST".to_string(),
         model: Some("gpt-5".to_string()),
+        requested_model: None,
         reasoning_effort: None,
         response_adapter: Some("Passthrough".to_string()),
         upstream_url: None,
@@ -1,5 +1,8 @@
 use codexmanager_core::storage::{
-    now_ts, Account, ApiKey, RequestLog, RequestTokenStat, Storage, Token, UsageSnapshotRecord,
+    now_ts, Account, AccountCooldownRecord, AccountModelExclusionRecord, AccountRouteQualityRecord,
+    ApiKey, ApiKeyBudget, GatewayBatch, GatewayBatchItem, GatewayFile, ModelAliasRule,
+    ModelFallbackChain, ModelPricing, ProviderAccount, RequestLog, RequestTokenStat, Storage,
+    Token, UsageSnapshotRecord,
 };
 
 #[test]
@@ -448,6 +451,272 @@ fn storage_gateway_candidates_exclude_unavailable_or_missing_token_accounts() {
     assert_eq!(candidate_ids, vec!["acc-ready", "acc-no-snapshot"]);
 }
 
+#[test]
+fn storage_gateway_candidates_append_active_providers_after_oauth_accounts() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    let now = now_ts();
+
+    storage
+        .insert_account(&Account {
+            id: "acc-oauth".to_string(),
+            label: "oauth".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: None,
+            workspace_id: None,
+            group_name: None,
+            // 中文注释：OAuth 账号 sort 比服务商大，仍应排在服务商前面。
+            sort: 10,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc-oauth".to_string(),
+            id_token: "id".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            api_key_access_token: None,
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let provider = |id: &str, sort: i64, status: &str| ProviderAccount {
+        id: id.to_string(),
+        label: id.to_string(),
+        base_url: "https://api.deepseek.com/v1".to_string(),
+        api_key: format!("sk-{id}"),
+        models_json: r#"["deepseek-chat"]"#.to_string(),
+        group_name: Some("overflow".to_string()),
+        sort,
+        status: status.to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+    for item in [
+        provider("prov-b", 2, "active"),
+        provider("prov-a", 1, "active"),
+        provider("prov-off", 0, "disabled"),
+    ] {
+        storage
+            .upsert_provider_account(&item)
+            .expect("upsert provider");
+    }
+
+    let candidates = storage
+        .list_gateway_candidates()
+        .expect("list gateway candidates");
+    let candidate_ids = candidates
+        .iter()
+        .map(|(account, _)| account.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(candidate_ids, vec!["acc-oauth", "prov-a", "prov-b"]);
+    let (account, token) = &candidates[1];
+    assert!(account.is_provider_account());
+    assert!(!candidates[0].0.is_provider_account());
+    assert_eq!(account.group_name.as_deref(), Some("overflow"));
+    assert_eq!(token.access_token, "sk-prov-a");
+
+    let mut updated = provider("prov-a", 1, "active");
+    updated.api_key = "sk-rotated".to_string();
+    storage
+        .upsert_provider_account(&updated)
+        .expect("update provider");
+    assert_eq!(
+        storage
+            .find_provider_account("prov-a")
+            .expect("find provider")
+            .expect("provider exists")
+            .api_key,
+        "sk-rotated"
+    );
+    assert_eq!(storage.list_provider_accounts().expect("list").len(), 3);
+    assert!(storage
+        .delete_provider_account("prov-a")
+        .expect("delete provider"));
+    assert_eq!(
+        storage
+            .list_active_provider_accounts()
+            .expect("list active")
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn storage_gateway_batches_track_items_counts_and_transitions() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    let now = now_ts();
+
+    let file = |id: &str, purpose: &str, created_at: i64| GatewayFile {
+        id: id.to_string(),
+        key_id: "gk_1".to_string(),
+        filename: format!("{id}.jsonl"),
+        purpose: purpose.to_string(),
+        bytes: 42,
+        created_at,
+    };
+    storage
+        .insert_gateway_file(&file("file-in", "batch", now))
+        .expect("insert input file");
+    storage
+        .insert_gateway_file(&file("file-out", "batch_output", now + 1))
+        .expect("insert output file");
+    let batch_files = storage
+        .list_gateway_files("gk_1", Some("batch"))
+        .expect("list batch files");
+    assert_eq!(batch_files, vec![file("file-in", "batch", now)]);
+    assert_eq!(
+        storage
+            .list_gateway_files("gk_1", None)
+            .expect("list files")
+            .len(),
+        2
+    );
+    assert!(storage
+        .list_gateway_files("gk_other", None)
+        .expect("list other files")
+        .is_empty());
+
+    let batch = GatewayBatch {
+        id: "batch_1".to_string(),
+        key_id: "gk_1".to_string(),
+        endpoint: "/v1/embeddings".to_string(),
+        input_file_id: "file-in".to_string(),
+        completion_window: "24h".to_string(),
+        status: "validating".to_string(),
+        output_file_id: None,
+        error_file_id: None,
+        metadata_json: None,
+        errors_json: None,
+        total_count: 3,
+        completed_count: 0,
+        failed_count: 0,
+        created_at: now,
+        in_progress_at: None,
+        expires_at: now + 86_400,
+        finalizing_at: None,
+        completed_at: None,
+        failed_at: None,
+        expired_at: None,
+        cancelling_at: None,
+        cancelled_at: None,
+    };
+    let items = (0..3)
+        .map(|index| GatewayBatchItem {
+            batch_id: "batch_1".to_string(),
+            line_index: index,
+            custom_id: format!("req-{index}"),
+            method: "POST".to_string(),
+            url: "/v1/embeddings".to_string(),
+            body: r#"{"model":"text-embedding-3-small","input":"hi"}"#.to_string(),
+            status: "pending".to_string(),
+            attempts: 0,
+            response_status: None,
+            request_id: None,
+            response_body: None,
+            error_json: None,
+            updated_at: now,
+        })
+        .collect::<Vec<_>>();
+    storage
+        .insert_gateway_batch(&batch, &items)
+        .expect("insert batch");
+    assert_eq!(
+        storage
+            .list_active_gateway_batches()
+            .expect("list active batches")
+            .len(),
+        1
+    );
+
+    assert!(storage
+        .transition_gateway_batch("batch_1", &["validating"], "in_progress", now + 5)
+        .expect("start batch"));
+    // 中文注释：状态不匹配时不做切换，用来防止取消与执行器并发时互相覆盖。
+    assert!(!storage
+        .transition_gateway_batch("batch_1", &["validating"], "in_progress", now + 6)
+        .expect("repeat start"));
+
+    let mut first = storage
+        .next_pending_gateway_batch_item("batch_1")
+        .expect("next item")
+        .expect("pending item");
+    assert_eq!(first.custom_id, "req-0");
+    first.status = "completed".to_string();
+    first.attempts = 1;
+    first.response_status = Some(200);
+    first.response_body = Some("{}".to_string());
+    storage
+        .update_gateway_batch_item(&first)
+        .expect("complete first item");
+    let mut second = storage
+        .next_pending_gateway_batch_item("batch_1")
+        .expect("next item")
+        .expect("pending item");
+    assert_eq!(second.custom_id, "req-1");
+    second.status = "failed".to_string();
+    second.response_status = Some(400);
+    storage
+        .update_gateway_batch_item(&second)
+        .expect("fail second item");
+
+    assert!(storage
+        .transition_gateway_batch("batch_1", &["in_progress"], "cancelling", now + 7)
+        .expect("cancel batch"));
+    assert_eq!(
+        storage
+            .close_pending_gateway_batch_items("batch_1", "cancelled", "{}", now + 8)
+            .expect("close pending"),
+        1
+    );
+    assert!(storage
+        .next_pending_gateway_batch_item("batch_1")
+        .expect("next item")
+        .is_none());
+    storage
+        .complete_gateway_batch("batch_1", "cancelled", Some("file-out"), None, now + 9)
+        .expect("finish batch");
+
+    let stored = storage
+        .find_gateway_batch("batch_1")
+        .expect("find batch")
+        .expect("batch exists");
+    assert_eq!(stored.status, "cancelled");
+    assert_eq!(stored.completed_count, 1);
+    assert_eq!(stored.failed_count, 1);
+    assert_eq!(stored.in_progress_at, Some(now + 5));
+    assert_eq!(stored.cancelling_at, Some(now + 7));
+    assert_eq!(stored.cancelled_at, Some(now + 9));
+    assert_eq!(stored.output_file_id.as_deref(), Some("file-out"));
+    assert!(storage
+        .list_active_gateway_batches()
+        .expect("list active batches")
+        .is_empty());
+    let statuses = storage
+        .list_gateway_batch_items("batch_1")
+        .expect("list items")
+        .into_iter()
+        .map(|item| item.status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec!["completed", "failed", "cancelled"]);
+    assert_eq!(
+        storage
+            .list_gateway_batches("gk_1", 10)
+            .expect("list batches")
+            .len(),
+        1
+    );
+    assert!(storage.delete_gateway_file("file-in").expect("delete file"));
+    assert!(storage
+        .find_gateway_file("file-in")
+        .expect("find file")
+        .is_none());
+}
+
 #[test]
 fn latest_usage_snapshots_break_ties_by_latest_id() {
     let storage = Storage::open_in_memory().expect("open in memory");
@@ -526,6 +795,7 @@ fn request_logs_support_prefixed_query_filters() {
             adapted_path: Some("/v1/responses".to_string()),
             method: "POST".to_string(),
             model: Some("gpt-5.1".to_string()),
+            requested_model: None,
             reasoning_effort: Some("low".to_string()),
             response_adapter: Some("OpenAIChatCompletionsJson".to_string()),
             upstream_url: Some("https://chatgpt.com/backend-api/codex/v1/responses".to_string()),
@@ -551,6 +821,7 @@ fn request_logs_support_prefixed_query_filters() {
             adapted_path: Some("/v1/responses".to_string()),
             method: "POST".to_string(),
             model: Some("gpt-5.1".to_string()),
+            requested_model: None,
             reasoning_effort: Some("low".to_string()),
             response_adapter: Some("Passthrough".to_string()),
             upstream_url: Some("https://chatgpt.com/backend-api/codex/v1/responses".to_string()),
@@ -576,6 +847,7 @@ fn request_logs_support_prefixed_query_filters() {
             adapted_path: Some("/v1/models".to_string()),
             method: "GET".to_string(),
             model: Some("gpt-4.1".to_string()),
+            requested_model: None,
             reasoning_effort: Some("xhigh".to_string()),
             response_adapter: None,
             upstream_url: Some("https://api.openai.com/v1/models".to_string()),
@@ -663,6 +935,7 @@ fn request_log_today_summary_reads_from_token_stats_table() {
             adapted_path: Some("/v1/responses".to_string()),
             method: "POST".to_string(),
             model: Some("gpt-5.3-codex".to_string()),
+            requested_model: None,
             reasoning_effort: Some("high".to_string()),
             response_adapter: Some("Passthrough".to_string()),
             upstream_url: Some("https://chatgpt.com/backend-api/codex/responses".to_string()),
@@ -721,6 +994,7 @@ fn insert_request_log_with_token_stat_writes_both_tables_in_one_call() {
                 adapted_path: Some("/v1/responses".to_string()),
                 method: "POST".to_string(),
                 model: Some("gpt-5.3-codex".to_string()),
+                requested_model: None,
                 reasoning_effort: Some("high".to_string()),
                 response_adapter: Some("Passthrough".to_string()),
                 upstream_url: Some("https://chatgpt.com/backend-api/codex/responses".to_string()),
@@ -784,6 +1058,7 @@ fn clear_request_logs_keeps_token_stats_for_usage_summary() {
             adapted_path: Some("/v1/responses".to_string()),
             method: "POST".to_string(),
             model: Some("gpt-5.3-codex".to_string()),
+            requested_model: None,
             reasoning_effort: Some("high".to_string()),
             response_adapter: Some("Passthrough".to_string()),
             upstream_url: Some("https://chatgpt.com/backend-api/codex/responses".to_string()),
@@ -897,6 +1172,11 @@ fn storage_api_keys_include_profile_fields() {
             auth_scheme: "x_api_key".to_string(),
             upstream_base_url: Some("https://api.anthropic.com".to_string()),
             static_headers_json: Some("{\"anthropic-version\":\"2023-06-01\"}".to_string()),
+            account_group: Some("team-a,team-b".to_string()),
+            rpm_limit: Some(60),
+            tpm_limit: Some(100_000),
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: "hash-1".to_string(),
             status: "active".to_string(),
             created_at: now_ts(),
@@ -914,6 +1194,68 @@ fn storage_api_keys_include_profile_fields() {
     assert_eq!(key.protocol_type, "anthropic_native");
     assert_eq!(key.auth_scheme, "x_api_key");
     assert_eq!(key.model_slug.as_deref(), Some("claude-sonnet-4"));
+    assert_eq!(key.account_group.as_deref(), Some("team-a,team-b"));
+    assert_eq!(key.rpm_limit, Some(60));
+    assert_eq!(key.tpm_limit, Some(100_000));
+    assert_eq!(key.max_concurrency, None);
+
+    storage
+        .update_api_key_account_group("key-1", None)
+        .expect("clear account group");
+    let key = storage
+        .find_api_key_by_id("key-1")
+        .expect("find key")
+        .expect("key exists");
+    assert_eq!(key.account_group, None);
+
+    storage
+        .update_api_key_rate_limits("key-1", None, Some(5_000), Some(2))
+        .expect("update rate limits");
+    let key = storage
+        .find_api_key_by_id("key-1")
+        .expect("find key")
+        .expect("key exists");
+    assert_eq!(key.rpm_limit, None);
+    assert_eq!(key.tpm_limit, Some(5_000));
+    assert_eq!(key.max_concurrency, Some(2));
+}
+
+#[test]
+fn storage_api_keys_accept_gemini_native_profile() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    storage
+        .insert_api_key(&ApiKey {
+            id: "key-gemini-1".to_string(),
+            name: Some("gemini".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "gemini_native".to_string(),
+            auth_scheme: "x_goog_api_key".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: "hash-gemini-1".to_string(),
+            status: "active".to_string(),
+            created_at: now_ts(),
+            last_used_at: None,
+        })
+        .expect("insert key");
+
+    let key = storage
+        .list_api_keys()
+        .expect("list keys")
+        .into_iter()
+        .find(|item| item.id == "key-gemini-1")
+        .expect("key exists");
+    assert_eq!(key.protocol_type, "gemini_native");
+    assert_eq!(key.auth_scheme, "x_goog_api_key");
 }
 
 #[test]
@@ -932,6 +1274,11 @@ fn storage_can_roundtrip_api_key_secret() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: "hash-secret-1".to_string(),
             status: "active".to_string(),
             created_at: now_ts(),
@@ -954,3 +1301,539 @@ fn storage_can_roundtrip_api_key_secret() {
         .expect("load removed secret");
     assert!(removed.is_none());
 }
+
+#[test]
+fn storage_route_affinity_upsert_list_and_prune() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    storage
+        .upsert_route_affinity("aff-1", "acc-1", 100)
+        .expect("insert affinity");
+    storage
+        .upsert_route_affinity("aff-1", "acc-2", 200)
+        .expect("rebind affinity");
+    storage
+        .upsert_route_affinity("aff-2", "acc-1", 150)
+        .expect("insert second affinity");
+    storage
+        .upsert_route_affinity("aff-3", "acc-3", 50)
+        .expect("insert stale affinity");
+
+    let items = storage
+        .list_route_affinities(Some(100), Some(10))
+        .expect("list");
+    let keys = items
+        .iter()
+        .map(|item| (item.affinity_key.as_str(), item.account_id.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec![("aff-1", "acc-2"), ("aff-2", "acc-1")]);
+
+    let removed = storage
+        .prune_route_affinities(Some(100), Some(1))
+        .expect("prune");
+    assert_eq!(removed, 2);
+    let items = storage
+        .list_route_affinities(None, None)
+        .expect("list after prune");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].affinity_key, "aff-1");
+
+    storage.delete_route_affinity("aff-1").expect("delete");
+    assert!(storage
+        .list_route_affinities(None, None)
+        .expect("list after delete")
+        .is_empty());
+}
+
+#[test]
+fn storage_route_affinity_unbounded_settings_keep_rows() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    for (key, updated_at) in [("aff-old", 10), ("aff-mid", 100), ("aff-new", 200)] {
+        storage
+            .upsert_route_affinity(key, "acc-1", updated_at)
+            .expect("insert affinity");
+    }
+
+    // 中文注释：容量 0 与 TTL 0 都表示不限制，不能被翻译成 LIMIT 0 或“早于现在即过期”。
+    assert_eq!(
+        storage
+            .prune_route_affinities(None, None)
+            .expect("prune unbounded"),
+        0
+    );
+    assert_eq!(
+        storage
+            .list_route_affinities(None, None)
+            .expect("list unbounded")
+            .len(),
+        3
+    );
+    assert_eq!(
+        storage
+            .prune_route_affinities(None, Some(2))
+            .expect("prune by capacity only"),
+        1
+    );
+    assert_eq!(
+        storage
+            .prune_route_affinities(Some(150), None)
+            .expect("prune by age only"),
+        1
+    );
+    let items = storage
+        .list_route_affinities(None, None)
+        .expect("list after prune");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].affinity_key, "aff-new");
+}
+
+#[test]
+fn storage_account_route_state_roundtrip_and_prune() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    let cooldown = |account_id: &str, until: i64, offense_count: u32, offense_last_at: i64| {
+        AccountCooldownRecord {
+            account_id: account_id.to_string(),
+            cooldown_until: until,
+            reason: "rate_limited".to_string(),
+            offense_count,
+            offense_last_at,
+            updated_at: 100,
+        }
+    };
+    storage
+        .upsert_account_cooldown(&cooldown("acc-active", 500, 2, 100))
+        .expect("insert active cooldown");
+    storage
+        .upsert_account_cooldown(&cooldown("acc-offense", 50, 1, 90))
+        .expect("insert offense-only cooldown");
+    storage
+        .upsert_account_cooldown(&cooldown("acc-stale", 50, 1, 10))
+        .expect("insert stale cooldown");
+
+    let items = storage.list_account_cooldowns(100, 80).expect("list");
+    let ids = items
+        .iter()
+        .map(|item| item.account_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["acc-active", "acc-offense"]);
+    assert_eq!(items[0].offense_count, 2);
+
+    assert_eq!(storage.prune_account_cooldowns(100, 80).expect("prune"), 1);
+    storage
+        .delete_account_cooldown("acc-offense")
+        .expect("delete cooldown");
+    assert_eq!(storage.clear_account_cooldowns().expect("clear"), 1);
+    assert!(storage
+        .list_account_cooldowns(0, 0)
+        .expect("list after clear")
+        .is_empty());
+
+    let quality = AccountRouteQualityRecord {
+        account_id: "acc-q".to_string(),
+        success_2xx: 3,
+        challenge_403: 0,
+        throttle_429: 2,
+        upstream_5xx: 1,
+        upstream_4xx: 0,
+        health_score: 72,
+        ttfb_ewma_ms: Some(850),
+        total_ewma_ms: None,
+        updated_at: 200,
+    };
+    storage
+        .upsert_account_route_quality(&quality)
+        .expect("insert quality");
+    assert_eq!(
+        storage
+            .list_account_route_quality(150)
+            .expect("list quality"),
+        vec![quality.clone()]
+    );
+    assert!(storage
+        .list_account_route_quality(201)
+        .expect("list newer quality")
+        .is_empty());
+    assert_eq!(
+        storage
+            .prune_account_route_quality(201)
+            .expect("prune quality"),
+        1
+    );
+
+    let exclusion = |account_id: &str, model: &str, expires_at: i64| AccountModelExclusionRecord {
+        account_id: account_id.to_string(),
+        model: model.to_string(),
+        status_code: 400,
+        reason: "not supported on your plan".to_string(),
+        learned_at: 100,
+        expires_at,
+    };
+    for record in [
+        exclusion("acc-a", "gpt-5.4-pro", 500),
+        exclusion("acc-a", "o3-pro", 150),
+        exclusion("acc-b", "gpt-5.4-pro", 90),
+    ] {
+        storage
+            .upsert_account_model_exclusion(&record)
+            .expect("insert exclusion");
+    }
+    storage
+        .upsert_account_model_exclusion(&exclusion("acc-a", "o3-pro", 600))
+        .expect("refresh exclusion");
+    assert_eq!(
+        storage
+            .list_account_model_exclusions(100)
+            .expect("list exclusions"),
+        vec![
+            exclusion("acc-a", "gpt-5.4-pro", 500),
+            exclusion("acc-a", "o3-pro", 600),
+        ]
+    );
+    assert_eq!(
+        storage
+            .prune_account_model_exclusions(100)
+            .expect("prune exclusions"),
+        1
+    );
+    storage
+        .delete_account_model_exclusion("acc-a", "o3-pro")
+        .expect("delete exclusion");
+    assert_eq!(
+        storage
+            .list_account_model_exclusions(100)
+            .expect("list after delete"),
+        vec![exclusion("acc-a", "gpt-5.4-pro", 500)]
+    );
+}
+
+#[test]
+fn storage_api_key_budgets_roundtrip_and_summarize_spend() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    let budget = ApiKeyBudget {
+        key_id: "key-budget-1".to_string(),
+        period: "daily".to_string(),
+        token_limit: Some(1_000),
+        cost_limit_usd: Some(2.5),
+        warn_percent: 75.0,
+        hard_block: true,
+        reset_at: None,
+        created_at: 100,
+        updated_at: 100,
+    };
+    storage
+        .upsert_api_key_budget(&budget)
+        .expect("upsert budget");
+    assert_eq!(
+        storage
+            .list_api_key_budgets("key-budget-1")
+            .expect("list budgets"),
+        vec![budget]
+    );
+
+    for (idx, (key_id, tokens, cost, created_at)) in [
+        ("key-budget-1", Some(300), Some(0.5), 150),
+        ("key-budget-1", None, Some(0.25), 160),
+        ("key-budget-1", Some(900), Some(9.0), 400),
+        ("key-other", Some(5_000), Some(5.0), 150),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        storage
+            .insert_request_token_stat(&RequestTokenStat {
+                request_log_id: idx as i64 + 1,
+                key_id: Some(key_id.to_string()),
+                account_id: None,
+                model: None,
+                input_tokens: Some(40),
+                cached_input_tokens: None,
+                output_tokens: Some(60),
+                total_tokens: tokens,
+                reasoning_output_tokens: None,
+                estimated_cost_usd: cost,
+                created_at,
+            })
+            .expect("insert token stat");
+    }
+
+    let (tokens, cost) = storage
+        .summarize_key_spend_between("key-budget-1", 100, 200)
+        .expect("summarize spend");
+    assert_eq!(tokens, 400);
+    assert!((cost - 0.75).abs() < 1e-9);
+    let (tokens, cost, watermark) = storage
+        .summarize_key_spend_with_watermark("key-budget-1", 100, 200)
+        .expect("summarize spend with watermark");
+    assert_eq!(tokens, 400);
+    assert!((cost - 0.75).abs() < 1e-9);
+    assert_eq!(watermark, 4);
+
+    assert!(storage
+        .reset_api_key_budget("key-budget-1", "daily", 180)
+        .expect("reset budget"));
+    assert!(!storage
+        .reset_api_key_budget("key-budget-1", "monthly", 180)
+        .expect("reset missing budget"));
+    let reloaded = storage
+        .list_api_key_budgets("key-budget-1")
+        .expect("list budgets");
+    assert_eq!(reloaded[0].reset_at, Some(180));
+
+    storage
+        .delete_api_key_budget("key-budget-1", "daily")
+        .expect("delete budget");
+    assert!(storage
+        .list_api_key_budgets("key-budget-1")
+        .expect("list budgets")
+        .is_empty());
+}
+
+#[test]
+fn storage_model_pricing_seeds_crud_and_cost_recompute_rows() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+
+    let seeded = storage.list_model_pricing().expect("list pricing");
+    let gpt54_tiers = seeded
+        .iter()
+        .filter(|item| item.model_pattern == "gpt-5.4" && item.match_type == "prefix")
+        .map(|item| item.input_tokens_above)
+        .collect::<Vec<_>>();
+    assert_eq!(gpt54_tiers, vec![0, 272_000]);
+
+    let custom = ModelPricing {
+        model_pattern: "my-model".to_string(),
+        match_type: "exact".to_string(),
+        input_tokens_above: 0,
+        input_price_per_1m: 1.0,
+        cached_input_price_per_1m: Some(0.1),
+        output_price_per_1m: 4.0,
+        reasoning_output_price_per_1m: Some(8.0),
+        note: Some("custom".to_string()),
+        updated_at: 10,
+    };
+    storage
+        .upsert_model_pricing(&custom)
+        .expect("upsert pricing");
+    let updated = ModelPricing {
+        output_price_per_1m: 5.0,
+        updated_at: 20,
+        ..custom.clone()
+    };
+    storage
+        .upsert_model_pricing(&updated)
+        .expect("update pricing");
+    let stored = storage
+        .list_model_pricing()
+        .expect("list pricing")
+        .into_iter()
+        .find(|item| item.model_pattern == "my-model")
+        .expect("custom pricing");
+    assert_eq!(stored, updated);
+
+    assert!(storage
+        .delete_model_pricing("my-model", "exact", 0)
+        .expect("delete pricing"));
+    assert!(!storage
+        .delete_model_pricing("my-model", "exact", 0)
+        .expect("delete missing pricing"));
+
+    storage
+        .import_model_pricing(std::slice::from_ref(&custom), true)
+        .expect("replace pricing");
+    assert_eq!(
+        storage.list_model_pricing().expect("list pricing"),
+        vec![custom]
+    );
+
+    for (log_id, created_at) in [(1, 100), (2, 200), (3, 300)] {
+        storage
+            .insert_request_token_stat(&RequestTokenStat {
+                request_log_id: log_id,
+                key_id: None,
+                account_id: None,
+                model: Some("my-model".to_string()),
+                input_tokens: Some(1_000),
+                cached_input_tokens: None,
+                output_tokens: Some(100),
+                total_tokens: Some(1_100),
+                reasoning_output_tokens: None,
+                estimated_cost_usd: None,
+                created_at,
+            })
+            .expect("insert token stat");
+    }
+    let rows = storage
+        .list_request_token_stats_for_cost(0, Some(200), 10)
+        .expect("list cost rows");
+    assert_eq!(rows.len(), 2);
+    let first_page = storage
+        .list_request_token_stats_for_cost(0, None, 1)
+        .expect("list first page");
+    let next_page = storage
+        .list_request_token_stats_for_cost(first_page[0].id, None, 10)
+        .expect("list next page");
+    assert_eq!(next_page.len(), 2);
+
+    let costs = rows
+        .iter()
+        .map(|row| (row.id, Some(0.5)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        storage
+            .update_request_token_stat_costs(&costs)
+            .expect("update costs"),
+        2
+    );
+    // 中文注释：费用未变化的行不计入更新数。
+    assert_eq!(
+        storage
+            .update_request_token_stat_costs(&costs)
+            .expect("update costs again"),
+        0
+    );
+}
+
+#[test]
+fn storage_model_alias_rules_order_key_rules_before_global() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    let now = now_ts();
+    let rule = |key_id: Option<&str>, sort: i64, pattern: &str, enabled: bool| ModelAliasRule {
+        id: 0,
+        key_id: key_id.map(str::to_string),
+        sort,
+        match_kind: "glob".to_string(),
+        pattern: pattern.to_string(),
+        target_model: "gpt-5.1".to_string(),
+        reasoning_effort: None,
+        enabled,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let global_id = storage
+        .insert_model_alias_rule(&rule(None, 0, "global-*", true))
+        .expect("insert global rule");
+    storage
+        .insert_model_alias_rule(&rule(Some("gk_1"), 20, "key-late", true))
+        .expect("insert key rule");
+    storage
+        .insert_model_alias_rule(&rule(Some("gk_1"), 10, "key-early", true))
+        .expect("insert key rule");
+    storage
+        .insert_model_alias_rule(&rule(Some("gk_1"), 0, "key-disabled", false))
+        .expect("insert disabled rule");
+    storage
+        .insert_model_alias_rule(&rule(Some("gk_2"), 0, "other-key", true))
+        .expect("insert other key rule");
+
+    let active = storage
+        .list_active_model_alias_rules_for_key("gk_1")
+        .expect("list active rules");
+    let patterns = active
+        .iter()
+        .map(|item| item.pattern.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(patterns, vec!["key-early", "key-late", "global-*"]);
+    assert_eq!(
+        storage
+            .list_model_alias_rules(Some("gk_1"))
+            .expect("list key rules")
+            .len(),
+        3
+    );
+    assert_eq!(
+        storage
+            .list_model_alias_rules(None)
+            .expect("list all rules")
+            .len(),
+        5
+    );
+
+    let mut global = storage
+        .find_model_alias_rule(global_id)
+        .expect("find rule")
+        .expect("global rule exists");
+    global.target_model = "gpt-5.1-codex".to_string();
+    global.reasoning_effort = Some("high".to_string());
+    assert!(storage
+        .update_model_alias_rule(&global)
+        .expect("update rule"));
+    let updated = storage
+        .find_model_alias_rule(global_id)
+        .expect("find rule")
+        .expect("global rule exists");
+    assert_eq!(updated.target_model, "gpt-5.1-codex");
+    assert_eq!(updated.reasoning_effort.as_deref(), Some("high"));
+
+    assert!(storage
+        .delete_model_alias_rule(global_id)
+        .expect("delete rule"));
+    assert!(!storage
+        .delete_model_alias_rule(global_id)
+        .expect("delete again"));
+}
+
+#[test]
+fn storage_model_fallback_chain_prefers_key_scope() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    let now = now_ts();
+    let chain = |key_id: Option<&str>, fallback: &str, enabled: bool| ModelFallbackChain {
+        id: 0,
+        key_id: key_id.map(str::to_string),
+        model: "gpt-5.4".to_string(),
+        fallback_models_json: format!("[\"{fallback}\"]"),
+        enabled,
+        created_at: now,
+        updated_at: now,
+    };
+
+    storage
+        .insert_model_fallback_chain(&chain(None, "global-fallback", true))
+        .expect("insert global chain");
+    let key_chain_id = storage
+        .insert_model_fallback_chain(&chain(Some("gk_1"), "key-fallback", true))
+        .expect("insert key chain");
+
+    let resolved = storage
+        .find_active_model_fallback_chain("gk_1", "GPT-5.4")
+        .expect("find chain")
+        .expect("key chain exists");
+    assert_eq!(resolved.id, key_chain_id);
+    let resolved = storage
+        .find_active_model_fallback_chain("gk_2", "gpt-5.4")
+        .expect("find chain")
+        .expect("global chain exists");
+    assert_eq!(resolved.fallback_models_json, r#"["global-fallback"]"#);
+
+    let mut disabled = chain(Some("gk_1"), "key-fallback", false);
+    disabled.id = key_chain_id;
+    assert!(storage
+        .update_model_fallback_chain(&disabled)
+        .expect("disable key chain"));
+    let resolved = storage
+        .find_active_model_fallback_chain("gk_1", "gpt-5.4")
+        .expect("find chain")
+        .expect("global chain exists");
+    assert!(resolved.key_id.is_none());
+
+    assert!(storage
+        .delete_model_fallback_chain(key_chain_id)
+        .expect("delete chain"));
+    assert_eq!(
+        storage
+            .list_model_fallback_chains(None)
+            .expect("list chains")
+            .len(),
+        1
+    );
+}
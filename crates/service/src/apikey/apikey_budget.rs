@@ -0,0 +1,400 @@
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, TimeZone};
+use codexmanager_core::rpc::types::ApiKeyBudgetStatus;
+use codexmanager_core::storage::{now_ts, ApiKeyBudget, Storage};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::storage_helpers::open_storage;
+
+pub(crate) const BUDGET_PERIOD_DAILY: &str = "daily";
+pub(crate) const BUDGET_PERIOD_WEEKLY: &str = "weekly";
+pub(crate) const BUDGET_PERIOD_MONTHLY: &str = "monthly";
+const DEFAULT_BUDGET_WARN_PERCENT: f64 = 80.0;
+
+pub(crate) const BUDGET_STATE_OK: &str = "ok";
+pub(crate) const BUDGET_STATE_WARNING: &str = "warning";
+pub(crate) const BUDGET_STATE_EXCEEDED: &str = "exceeded";
+
+// 中文注释：软告警每个 Key/周期只记录一次，键为 key_id|period，值为已告警周期的起点。
+static BUDGET_WARNINGS_LOGGED: OnceLock<Mutex<HashMap<String, i64>>> = OnceLock::new();
+// 中文注释：转发前校验用的累计花费，键为 key_id|period；请求日志落库后原地累加，
+// 只有周期滚动、手动重置或历史费用重算后才重新汇总。
+static KEY_SPEND_TOTALS: OnceLock<Mutex<HashMap<String, KeySpendTotal>>> = OnceLock::new();
+
+/// 某个 Key 在一个预算窗口 `[window_start, window_end)` 内的累计花费。
+struct KeySpendTotal {
+    db_path: String,
+    window_start: i64,
+    window_end: i64,
+    // 中文注释：汇总快照里已包含的最大 request_log_id，之后的记录才需要累加。
+    last_request_log_id: i64,
+    used_tokens: i64,
+    used_cost_usd: f64,
+}
+
+fn key_spend_slot(key_id: &str, period: &str) -> String {
+    format!("{key_id}|{period}")
+}
+
+pub(crate) fn normalize_budget_period(raw: &str) -> Result<&'static str, String> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "daily" | "day" => Ok(BUDGET_PERIOD_DAILY),
+        "weekly" | "week" => Ok(BUDGET_PERIOD_WEEKLY),
+        "monthly" | "month" => Ok(BUDGET_PERIOD_MONTHLY),
+        _ => Err(format!("invalid budget period: {raw}")),
+    }
+}
+
+fn local_midnight_ts(date: NaiveDate) -> i64 {
+    let Some(naive) = date.and_hms_opt(0, 0, 0) else {
+        return 0;
+    };
+    match Local.from_local_datetime(&naive) {
+        LocalResult::Single(value) => value.timestamp(),
+        LocalResult::Ambiguous(a, b) => a.timestamp().min(b.timestamp()),
+        // 中文注释：夏令时跳变导致零点不存在时，按 UTC 偏移近似，避免整段周期失效。
+        LocalResult::None => naive.and_utc().timestamp(),
+    }
+}
+
+/// 计算自然周期的本地时间边界 `[start, end)`：日从零点起，周从周一起，月从 1 号起。
+pub(crate) fn budget_period_bounds(period: &str, now: DateTime<Local>) -> (i64, i64) {
+    let today = now.date_naive();
+    let (start, end) = match period {
+        BUDGET_PERIOD_WEEKLY => {
+            let start = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+            (start, start + Duration::days(7))
+        }
+        BUDGET_PERIOD_MONTHLY => {
+            let start = today.with_day(1).unwrap_or(today);
+            let end = if start.month() == 12 {
+                NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+            }
+            .unwrap_or(start + Duration::days(31));
+            (start, end)
+        }
+        _ => (today, today + Duration::days(1)),
+    };
+    let start_ts = local_midnight_ts(start);
+    (start_ts, local_midnight_ts(end).max(start_ts))
+}
+
+/// 按已用量判断预算状态；token 与费用任一维度达到阈值即生效。
+pub(crate) fn evaluate_budget_state(
+    budget: &ApiKeyBudget,
+    used_tokens: i64,
+    used_cost_usd: f64,
+) -> &'static str {
+    let ratios = [
+        budget
+            .token_limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| used_tokens.max(0) as f64 / limit as f64),
+        budget
+            .cost_limit_usd
+            .filter(|limit| *limit > 0.0)
+            .map(|limit| used_cost_usd.max(0.0) / limit),
+    ];
+    let ratio = ratios.into_iter().flatten().fold(0.0_f64, f64::max);
+    if ratio >= 1.0 {
+        BUDGET_STATE_EXCEEDED
+    } else if ratio * 100.0 >= budget.warn_percent {
+        BUDGET_STATE_WARNING
+    } else {
+        BUDGET_STATE_OK
+    }
+}
+
+/// 读取转发前校验用的累计花费：窗口未变时直接用内存累计值，否则重新汇总。
+fn cached_key_spend(
+    storage: &Storage,
+    key_id: &str,
+    period: &str,
+    window_start: i64,
+    window_end: i64,
+) -> Result<(i64, f64), String> {
+    let db_path = std::env::var("CODEXMANAGER_DB_PATH").unwrap_or_default();
+    let lock = KEY_SPEND_TOTALS.get_or_init(|| Mutex::new(HashMap::new()));
+    // 中文注释：重新汇总时持锁，保证汇总快照与之后的原地累加之间不会漏记或重复记。
+    let mut totals = crate::lock_utils::lock_recover(lock, "api_key_spend_totals");
+    let slot = key_spend_slot(key_id, period);
+    if let Some(total) = totals.get(&slot).filter(|total| {
+        total.db_path == db_path
+            && total.window_start == window_start
+            && total.window_end == window_end
+    }) {
+        return Ok((total.used_tokens, total.used_cost_usd));
+    }
+    let (used_tokens, used_cost_usd, last_request_log_id) = storage
+        .summarize_key_spend_with_watermark(key_id, window_start, window_end)
+        .map_err(|err| format!("summarize api key spend failed: {err}"))?;
+    totals.insert(
+        slot,
+        KeySpendTotal {
+            db_path,
+            window_start,
+            window_end,
+            last_request_log_id,
+            used_tokens,
+            used_cost_usd,
+        },
+    );
+    Ok((used_tokens, used_cost_usd))
+}
+
+/// 请求日志落库后调用：把这条记录的用量累加到该 Key 各预算窗口的内存累计值上。
+pub(crate) fn record_key_spend(
+    key_id: &str,
+    request_log_id: i64,
+    created_at: i64,
+    tokens: i64,
+    cost_usd: Option<f64>,
+) {
+    let Some(lock) = KEY_SPEND_TOTALS.get() else {
+        return;
+    };
+    let db_path = std::env::var("CODEXMANAGER_DB_PATH").unwrap_or_default();
+    let mut totals = crate::lock_utils::lock_recover(lock, "api_key_spend_totals");
+    for period in [
+        BUDGET_PERIOD_DAILY,
+        BUDGET_PERIOD_WEEKLY,
+        BUDGET_PERIOD_MONTHLY,
+    ] {
+        let Some(total) = totals.get_mut(&key_spend_slot(key_id, period)) else {
+            continue;
+        };
+        if total.db_path != db_path
+            || request_log_id <= total.last_request_log_id
+            || created_at < total.window_start
+            || created_at >= total.window_end
+        {
+            continue;
+        }
+        total.last_request_log_id = request_log_id;
+        total.used_tokens += tokens;
+        total.used_cost_usd += cost_usd.unwrap_or(0.0);
+    }
+}
+
+/// 历史费用被改写（价目重算）后调用，下一次校验重新汇总。
+pub(crate) fn invalidate_key_spend_totals() {
+    if let Some(lock) = KEY_SPEND_TOTALS.get() {
+        crate::lock_utils::lock_recover(lock, "api_key_spend_totals").clear();
+    }
+}
+
+fn budget_window(budget: &ApiKeyBudget, now: DateTime<Local>) -> (i64, i64) {
+    let (period_start, period_end) = budget_period_bounds(&budget.period, now);
+    // 中文注释：手动重置只在当前周期内生效，进入下一个自然周期后 reset_at 自然落在起点之前。
+    let effective_start = budget
+        .reset_at
+        .filter(|reset_at| *reset_at > period_start)
+        .unwrap_or(period_start);
+    (effective_start, period_end)
+}
+
+fn budget_status(
+    storage: &Storage,
+    budget: &ApiKeyBudget,
+    now: DateTime<Local>,
+) -> Result<ApiKeyBudgetStatus, String> {
+    let (effective_start, period_end) = budget_window(budget, now);
+    let (used_tokens, used_cost_usd) = storage
+        .summarize_key_spend_between(&budget.key_id, effective_start, period_end)
+        .map_err(|err| format!("summarize api key spend failed: {err}"))?;
+    Ok(budget_status_from_spend(
+        budget,
+        effective_start,
+        period_end,
+        used_tokens,
+        used_cost_usd,
+    ))
+}
+
+fn budget_status_from_spend(
+    budget: &ApiKeyBudget,
+    effective_start: i64,
+    period_end: i64,
+    used_tokens: i64,
+    used_cost_usd: f64,
+) -> ApiKeyBudgetStatus {
+    ApiKeyBudgetStatus {
+        key_id: budget.key_id.clone(),
+        period: budget.period.clone(),
+        token_limit: budget.token_limit,
+        cost_limit_usd: budget.cost_limit_usd,
+        warn_percent: budget.warn_percent,
+        hard_block: budget.hard_block,
+        period_start: effective_start,
+        period_end,
+        reset_at: budget.reset_at,
+        used_tokens,
+        used_cost_usd,
+        state: evaluate_budget_state(budget, used_tokens, used_cost_usd).to_string(),
+    }
+}
+
+pub(crate) fn read_api_key_budgets(key_id: &str) -> Result<Vec<ApiKeyBudgetStatus>, String> {
+    if key_id.is_empty() {
+        return Err("missing id".to_string());
+    }
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let now = Local::now();
+    storage
+        .list_api_key_budgets(key_id)
+        .map_err(|err| err.to_string())?
+        .iter()
+        .map(|budget| budget_status(&storage, budget, now))
+        .collect()
+}
+
+pub(crate) fn update_api_key_budget(
+    key_id: &str,
+    period: &str,
+    token_limit: Option<i64>,
+    cost_limit_usd: Option<f64>,
+    warn_percent: Option<f64>,
+    hard_block: Option<bool>,
+) -> Result<(), String> {
+    if key_id.is_empty() {
+        return Err("missing id".to_string());
+    }
+    let period = normalize_budget_period(period)?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    if storage
+        .find_api_key_by_id(key_id)
+        .map_err(|err| err.to_string())?
+        .is_none()
+    {
+        return Err("api key not found".to_string());
+    }
+    let token_limit = token_limit.filter(|value| *value > 0);
+    let cost_limit_usd = cost_limit_usd.filter(|value| value.is_finite() && *value > 0.0);
+    // 中文注释：两个额度都为空（或传 0）视为取消该周期预算。
+    if token_limit.is_none() && cost_limit_usd.is_none() {
+        return storage
+            .delete_api_key_budget(key_id, period)
+            .map_err(|err| err.to_string());
+    }
+    let now = now_ts();
+    storage
+        .upsert_api_key_budget(&ApiKeyBudget {
+            key_id: key_id.to_string(),
+            period: period.to_string(),
+            token_limit,
+            cost_limit_usd,
+            warn_percent: warn_percent
+                .filter(|value| value.is_finite())
+                .map(|value| value.clamp(1.0, 100.0))
+                .unwrap_or(DEFAULT_BUDGET_WARN_PERCENT),
+            hard_block: hard_block.unwrap_or(true),
+            reset_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) fn reset_api_key_budget(key_id: &str, period: &str) -> Result<(), String> {
+    if key_id.is_empty() {
+        return Err("missing id".to_string());
+    }
+    let period = normalize_budget_period(period)?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let updated = storage
+        .reset_api_key_budget(key_id, period, now_ts())
+        .map_err(|err| err.to_string())?;
+    if !updated {
+        return Err("api key budget not found".to_string());
+    }
+    if let Some(lock) = KEY_SPEND_TOTALS.get() {
+        crate::lock_utils::lock_recover(lock, "api_key_spend_totals")
+            .remove(&key_spend_slot(key_id, period));
+    }
+    Ok(())
+}
+
+/// 转发前校验平台 Key 预算；超出且开启硬拦截时返回对应周期的状态，软告警只记日志。
+pub(crate) fn check_key_budget_before_dispatch(
+    storage: &Storage,
+    key_id: &str,
+) -> Result<(), Box<ApiKeyBudgetStatus>> {
+    let budgets = match storage.list_api_key_budgets(key_id) {
+        Ok(items) => items,
+        Err(err) => {
+            // 中文注释：预算读取失败时放行，避免统计表异常把所有请求挡住。
+            log::warn!("load api key budgets failed: key_id={key_id} err={err}");
+            return Ok(());
+        }
+    };
+    if budgets.is_empty() {
+        return Ok(());
+    }
+    let now = Local::now();
+    for budget in &budgets {
+        let (effective_start, period_end) = budget_window(budget, now);
+        let status =
+            match cached_key_spend(storage, key_id, &budget.period, effective_start, period_end) {
+                Ok((used_tokens, used_cost_usd)) => budget_status_from_spend(
+                    budget,
+                    effective_start,
+                    period_end,
+                    used_tokens,
+                    used_cost_usd,
+                ),
+                Err(err) => {
+                    log::warn!("{err}: key_id={key_id}");
+                    continue;
+                }
+            };
+        match status.state.as_str() {
+            BUDGET_STATE_EXCEEDED if status.hard_block => return Err(Box::new(status)),
+            BUDGET_STATE_EXCEEDED | BUDGET_STATE_WARNING => log_budget_warning_once(&status),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn log_budget_warning_once(status: &ApiKeyBudgetStatus) {
+    let lock = BUDGET_WARNINGS_LOGGED.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut logged = crate::lock_utils::lock_recover(lock, "api_key_budget_warnings");
+    let slot = format!("{}|{}", status.key_id, status.period);
+    if logged.get(&slot) == Some(&status.period_start) {
+        return;
+    }
+    logged.insert(slot, status.period_start);
+    log::warn!(
+        "api key budget warning: key_id={} period={} state={} used_tokens={} used_cost_usd={:.4} token_limit={:?} cost_limit_usd={:?}",
+        status.key_id,
+        status.period,
+        status.state,
+        status.used_tokens,
+        status.used_cost_usd,
+        status.token_limit,
+        status.cost_limit_usd
+    );
+}
+
+/// 超预算拒绝原因，前缀用于错误码归类。
+pub(crate) fn budget_exceeded_message(status: &ApiKeyBudgetStatus) -> String {
+    let mut parts = Vec::new();
+    if let Some(limit) = status.token_limit {
+        parts.push(format!("tokens {}/{}", status.used_tokens, limit));
+    }
+    if let Some(limit) = status.cost_limit_usd {
+        parts.push(format!("cost ${:.4}/${:.4}", status.used_cost_usd, limit));
+    }
+    format!(
+        "api key budget exceeded: {} budget ({}), resets at {}",
+        status.period,
+        parts.join(", "),
+        status.period_end
+    )
+}
+
+#[cfg(test)]
+#[path = "tests/apikey_budget_tests.rs"]
+mod tests;
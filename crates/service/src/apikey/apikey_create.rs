@@ -2,14 +2,16 @@ use codexmanager_core::rpc::types::ApiKeyCreateResult;
 use codexmanager_core::storage::{now_ts, ApiKey};
 
 use crate::apikey_profile::{
-    normalize_protocol_type, normalize_static_headers_json, normalize_upstream_base_url,
-    profile_from_protocol,
+    normalize_account_group, normalize_hedge_delay_ms, normalize_protocol_type,
+    normalize_rate_limit, normalize_static_headers_json, normalize_upstream_base_url,
+    profile_from_protocol, ApiKeyRateLimitParams,
 };
 use crate::reasoning_effort::normalize_reasoning_effort_owned;
 use crate::storage_helpers::{
     generate_key_id, generate_platform_key, hash_platform_key, open_storage,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_api_key(
     name: Option<String>,
     model_slug: Option<String>,
@@ -17,6 +19,9 @@ pub(crate) fn create_api_key(
     protocol_type: Option<String>,
     upstream_base_url: Option<String>,
     static_headers_json: Option<String>,
+    account_group: Option<String>,
+    rate_limits: ApiKeyRateLimitParams,
+    hedge_delay_ms: Option<i64>,
 ) -> Result<ApiKeyCreateResult, String> {
     // 创建平台 Key 并写入存储
     let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
@@ -37,6 +42,11 @@ pub(crate) fn create_api_key(
         auth_scheme,
         upstream_base_url,
         static_headers_json,
+        account_group: normalize_account_group(account_group),
+        rpm_limit: normalize_rate_limit(rate_limits.rpm_limit),
+        tpm_limit: normalize_rate_limit(rate_limits.tpm_limit),
+        max_concurrency: normalize_rate_limit(rate_limits.max_concurrency),
+        hedge_delay_ms: normalize_hedge_delay_ms(hedge_delay_ms),
         key_hash,
         status: "active".to_string(),
         created_at: now_ts(),
@@ -20,6 +20,11 @@ pub(crate) fn read_api_keys() -> Result<Vec<ApiKeySummary>, String> {
             auth_scheme: key.auth_scheme,
             upstream_base_url: key.upstream_base_url,
             static_headers_json: key.static_headers_json,
+            account_group: key.account_group,
+            rpm_limit: key.rpm_limit,
+            tpm_limit: key.tpm_limit,
+            max_concurrency: key.max_concurrency,
+            hedge_delay_ms: key.hedge_delay_ms,
             status: key.status,
             created_at: key.created_at,
             last_used_at: key.last_used_at,
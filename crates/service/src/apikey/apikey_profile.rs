@@ -2,9 +2,11 @@ pub(crate) const CLIENT_CODEX: &str = "codex";
 pub(crate) const PROTOCOL_OPENAI_COMPAT: &str = "openai_compat";
 pub(crate) const PROTOCOL_ANTHROPIC_NATIVE: &str = "anthropic_native";
 pub(crate) const PROTOCOL_AZURE_OPENAI: &str = "azure_openai";
+pub(crate) const PROTOCOL_GEMINI_NATIVE: &str = "gemini_native";
 pub(crate) const AUTH_BEARER: &str = "authorization_bearer";
 pub(crate) const AUTH_X_API_KEY: &str = "x_api_key";
 pub(crate) const AUTH_API_KEY: &str = "api_key";
+pub(crate) const AUTH_X_GOOG_API_KEY: &str = "x_goog_api_key";
 
 fn normalize_key(value: &str) -> String {
     value.trim().to_ascii_lowercase().replace('-', "_")
@@ -16,6 +18,7 @@ pub(crate) fn normalize_protocol_type(value: Option<String>) -> Result<String, S
             "openai" | "openai_compat" => Ok(PROTOCOL_OPENAI_COMPAT.to_string()),
             "anthropic" | "anthropic_native" => Ok(PROTOCOL_ANTHROPIC_NATIVE.to_string()),
             "azure" | "azure_openai" => Ok(PROTOCOL_AZURE_OPENAI.to_string()),
+            "gemini" | "gemini_native" => Ok(PROTOCOL_GEMINI_NATIVE.to_string()),
             other => Err(format!("unsupported protocol type: {other}")),
         },
         None => Ok(PROTOCOL_OPENAI_COMPAT.to_string()),
@@ -30,6 +33,8 @@ pub(crate) fn profile_from_protocol(
         AUTH_X_API_KEY.to_string()
     } else if protocol == PROTOCOL_AZURE_OPENAI {
         AUTH_API_KEY.to_string()
+    } else if protocol == PROTOCOL_GEMINI_NATIVE {
+        AUTH_X_GOOG_API_KEY.to_string()
     } else {
         AUTH_BEARER.to_string()
     };
@@ -52,6 +57,15 @@ pub(crate) fn normalize_upstream_base_url(value: Option<String>) -> Result<Optio
     Ok(Some(trimmed))
 }
 
+/// anthropic_native Key 配置了上游地址时，`/v1/messages` 原样转发给该 Anthropic 上游，不再改写成 Codex 请求。
+pub(crate) fn is_anthropic_passthrough(
+    protocol_type: &str,
+    upstream_base_url: Option<&str>,
+) -> bool {
+    protocol_type == PROTOCOL_ANTHROPIC_NATIVE
+        && upstream_base_url.is_some_and(|value| !value.trim().is_empty())
+}
+
 pub(crate) fn normalize_static_headers_json(
     value: Option<String>,
 ) -> Result<Option<String>, String> {
@@ -79,3 +93,58 @@ pub(crate) fn normalize_static_headers_json(
     }
     Ok(Some(trimmed.to_string()))
 }
+
+/// 规范化 Key 绑定的账号分组：逗号分隔，去空白、去重；空串视为不限制分组。
+pub(crate) fn normalize_account_group(value: Option<String>) -> Option<String> {
+    let raw = value?;
+    let mut groups: Vec<&str> = Vec::new();
+    for group in raw.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        if !groups.iter().any(|item| item.eq_ignore_ascii_case(group)) {
+            groups.push(group);
+        }
+    }
+    if groups.is_empty() {
+        None
+    } else {
+        Some(groups.join(","))
+    }
+}
+
+pub(crate) fn parse_account_groups(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 平台 Key 的限流配置；字段为 None 表示不限制。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ApiKeyRateLimitParams {
+    pub(crate) rpm_limit: Option<i64>,
+    pub(crate) tpm_limit: Option<i64>,
+    pub(crate) max_concurrency: Option<i64>,
+}
+
+impl ApiKeyRateLimitParams {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rpm_limit.is_none() && self.tpm_limit.is_none() && self.max_concurrency.is_none()
+    }
+}
+
+// 中文注释：对冲延迟过长就失去了降低尾延迟的意义，超过上限按上限处理。
+const HEDGE_DELAY_MAX_MS: i64 = 60_000;
+
+/// 对冲延迟小于等于 0 视为关闭对冲。
+pub(crate) fn normalize_hedge_delay_ms(value: Option<i64>) -> Option<i64> {
+    value.filter(|v| *v > 0).map(|v| v.min(HEDGE_DELAY_MAX_MS))
+}
+
+/// 限流值小于等于 0 视为不限制。
+pub(crate) fn normalize_rate_limit(value: Option<i64>) -> Option<i64> {
+    value.filter(|v| *v > 0)
+}
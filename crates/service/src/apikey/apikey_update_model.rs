@@ -1,10 +1,12 @@
 use crate::apikey_profile::{
-    normalize_protocol_type, normalize_static_headers_json, normalize_upstream_base_url,
-    profile_from_protocol,
+    normalize_account_group, normalize_hedge_delay_ms, normalize_protocol_type,
+    normalize_rate_limit, normalize_static_headers_json, normalize_upstream_base_url,
+    profile_from_protocol, ApiKeyRateLimitParams,
 };
 use crate::reasoning_effort::normalize_reasoning_effort;
 use crate::storage_helpers::open_storage;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn update_api_key_model(
     key_id: &str,
     model_slug: Option<String>,
@@ -12,6 +14,9 @@ pub(crate) fn update_api_key_model(
     protocol_type: Option<String>,
     upstream_base_url: Option<String>,
     static_headers_json: Option<String>,
+    account_group: Option<String>,
+    rate_limits: ApiKeyRateLimitParams,
+    hedge_delay_ms: Option<i64>,
 ) -> Result<(), String> {
     if key_id.is_empty() {
         return Err("key id required".to_string());
@@ -62,5 +67,37 @@ pub(crate) fn update_api_key_model(
             )
             .map_err(|e| e.to_string())?;
     }
+    if account_group.is_some() {
+        // 中文注释：传空串表示解除分组绑定，未传该字段则保持原值。
+        let normalized_account_group = normalize_account_group(account_group);
+        storage
+            .update_api_key_account_group(key_id, normalized_account_group.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+    if !rate_limits.is_empty() {
+        // 中文注释：只覆盖本次传入的限流项；传 0 表示取消该项限制。
+        let current = storage
+            .find_api_key_by_id(key_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "api key not found".to_string())?;
+        let merge = |next: Option<i64>, current: Option<i64>| match next {
+            Some(value) => normalize_rate_limit(Some(value)),
+            None => current,
+        };
+        storage
+            .update_api_key_rate_limits(
+                key_id,
+                merge(rate_limits.rpm_limit, current.rpm_limit),
+                merge(rate_limits.tpm_limit, current.tpm_limit),
+                merge(rate_limits.max_concurrency, current.max_concurrency),
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    if hedge_delay_ms.is_some() {
+        // 中文注释：传 0 表示关闭对冲，未传该字段则保持原值。
+        storage
+            .update_api_key_hedge_delay(key_id, normalize_hedge_delay_ms(hedge_delay_ms))
+            .map_err(|e| e.to_string())?;
+    }
     Ok(())
 }
@@ -0,0 +1,106 @@
+use super::{
+    budget_exceeded_message, budget_period_bounds, evaluate_budget_state, normalize_budget_period,
+    BUDGET_STATE_EXCEEDED, BUDGET_STATE_OK, BUDGET_STATE_WARNING,
+};
+use chrono::{Datelike, Local, TimeZone, Timelike};
+use codexmanager_core::rpc::types::ApiKeyBudgetStatus;
+use codexmanager_core::storage::ApiKeyBudget;
+
+fn budget(token_limit: Option<i64>, cost_limit_usd: Option<f64>) -> ApiKeyBudget {
+    ApiKeyBudget {
+        key_id: "gk_budget".to_string(),
+        period: "daily".to_string(),
+        token_limit,
+        cost_limit_usd,
+        warn_percent: 80.0,
+        hard_block: true,
+        reset_at: None,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn normalize_budget_period_accepts_aliases() {
+    assert_eq!(normalize_budget_period(" Daily "), Ok("daily"));
+    assert_eq!(normalize_budget_period("week"), Ok("weekly"));
+    assert_eq!(normalize_budget_period("MONTH"), Ok("monthly"));
+    assert!(normalize_budget_period("yearly").is_err());
+}
+
+#[test]
+fn evaluate_budget_state_uses_highest_ratio() {
+    let tokens_only = budget(Some(1_000), None);
+    assert_eq!(
+        evaluate_budget_state(&tokens_only, 100, 99.0),
+        BUDGET_STATE_OK
+    );
+    assert_eq!(
+        evaluate_budget_state(&tokens_only, 800, 0.0),
+        BUDGET_STATE_WARNING
+    );
+    assert_eq!(
+        evaluate_budget_state(&tokens_only, 1_000, 0.0),
+        BUDGET_STATE_EXCEEDED
+    );
+
+    let both = budget(Some(1_000_000), Some(2.0));
+    assert_eq!(evaluate_budget_state(&both, 10, 1.7), BUDGET_STATE_WARNING);
+    assert_eq!(evaluate_budget_state(&both, 10, 2.5), BUDGET_STATE_EXCEEDED);
+}
+
+#[test]
+fn budget_period_bounds_follow_local_calendar() {
+    // 2026-03-18 是周三。
+    let now = Local
+        .with_ymd_and_hms(2026, 3, 18, 15, 30, 0)
+        .single()
+        .expect("local time");
+
+    let (start, end) = budget_period_bounds("daily", now);
+    let start_dt = Local.timestamp_opt(start, 0).single().expect("start");
+    assert_eq!((start_dt.day(), start_dt.hour()), (18, 0));
+    assert_eq!(end - start, 24 * 60 * 60);
+
+    let (start, end) = budget_period_bounds("weekly", now);
+    let start_dt = Local.timestamp_opt(start, 0).single().expect("start");
+    let end_dt = Local.timestamp_opt(end, 0).single().expect("end");
+    assert_eq!(start_dt.day(), 16);
+    assert_eq!(end_dt.day(), 23);
+
+    let (start, end) = budget_period_bounds("monthly", now);
+    let start_dt = Local.timestamp_opt(start, 0).single().expect("start");
+    let end_dt = Local.timestamp_opt(end, 0).single().expect("end");
+    assert_eq!((start_dt.month(), start_dt.day()), (3, 1));
+    assert_eq!((end_dt.month(), end_dt.day()), (4, 1));
+
+    let december = Local
+        .with_ymd_and_hms(2026, 12, 5, 8, 0, 0)
+        .single()
+        .expect("local time");
+    let (_, end) = budget_period_bounds("monthly", december);
+    let end_dt = Local.timestamp_opt(end, 0).single().expect("end");
+    assert_eq!((end_dt.year(), end_dt.month(), end_dt.day()), (2027, 1, 1));
+}
+
+#[test]
+fn budget_exceeded_message_lists_both_limits() {
+    let status = ApiKeyBudgetStatus {
+        key_id: "gk_budget".to_string(),
+        period: "monthly".to_string(),
+        token_limit: Some(1_000),
+        cost_limit_usd: Some(5.0),
+        warn_percent: 80.0,
+        hard_block: true,
+        period_start: 100,
+        period_end: 200,
+        reset_at: None,
+        used_tokens: 1_200,
+        used_cost_usd: 1.5,
+        state: "exceeded".to_string(),
+    };
+    let message = budget_exceeded_message(&status);
+    assert!(message.starts_with("api key budget exceeded: monthly budget"));
+    assert!(message.contains("tokens 1200/1000"));
+    assert!(message.contains("cost $1.5000/$5.0000"));
+}
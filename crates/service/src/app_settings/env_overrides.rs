@@ -32,6 +32,15 @@ const APP_SETTINGS_ENV_RESERVED_KEYS: &[&str] = &[
     "CODEXMANAGER_HTTP_WORKER_MIN",
     "CODEXMANAGER_HTTP_STREAM_WORKER_FACTOR",
     "CODEXMANAGER_HTTP_STREAM_WORKER_MIN",
+    "CODEXMANAGER_COOLDOWN_DEFAULT_SECS",
+    "CODEXMANAGER_COOLDOWN_NETWORK_SECS",
+    "CODEXMANAGER_COOLDOWN_4XX_SECS",
+    "CODEXMANAGER_COOLDOWN_5XX_SECS",
+    "CODEXMANAGER_COOLDOWN_CHALLENGE_SECS",
+    "CODEXMANAGER_COOLDOWN_429_LADDER_SECS",
+    "CODEXMANAGER_COOLDOWN_429_FORGET_AFTER_SECS",
+    "CODEXMANAGER_COOLDOWN_429_SOURCE",
+    "CODEXMANAGER_COOLDOWN_429_MAX_SECS",
 ];
 
 pub(crate) fn env_override_reserved_keys() -> &'static [&'static str] {
@@ -3,6 +3,7 @@ use serde_json::Value;
 
 pub(crate) const ERROR_CODE_HEADER_NAME: &str = "X-CodexManager-Error-Code";
 pub(crate) const TRACE_ID_HEADER_NAME: &str = "X-CodexManager-Trace-Id";
+pub(crate) const SERVED_MODEL_HEADER_NAME: &str = "X-CodexManager-Served-Model";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum ErrorCode {
@@ -20,9 +21,14 @@ pub(crate) enum ErrorCode {
     UpstreamNotFound,
     UpstreamNonSuccess,
     NoAvailableAccount,
+    KeyRateLimited,
+    KeyBudgetExceeded,
+    AdmissionQueueRejected,
     CandidateResolveFailed,
     ResponseWriteFailed,
     StreamInterrupted,
+    ClientCancelled,
+    BatchDeferred,
 }
 
 impl ErrorCode {
@@ -42,9 +48,14 @@ impl ErrorCode {
             Self::UpstreamNotFound => "upstream_not_found",
             Self::UpstreamNonSuccess => "upstream_non_success",
             Self::NoAvailableAccount => "no_available_account",
+            Self::KeyRateLimited => "key_rate_limited",
+            Self::KeyBudgetExceeded => "key_budget_exceeded",
+            Self::AdmissionQueueRejected => "admission_queue_rejected",
             Self::CandidateResolveFailed => "candidate_resolve_failed",
             Self::ResponseWriteFailed => "response_write_failed",
             Self::StreamInterrupted => "stream_interrupted",
+            Self::ClientCancelled => "client_cancelled",
+            Self::BatchDeferred => "batch_deferred",
         }
     }
 }
@@ -87,9 +98,20 @@ pub(crate) fn classify_message(message: &str) -> ErrorCode {
     if normalized == "upstream non-success" {
         return ErrorCode::UpstreamNonSuccess;
     }
-    if normalized == "no available account" {
+    if normalized == "no available account"
+        || normalized.starts_with("no available account in group(s)")
+    {
         return ErrorCode::NoAvailableAccount;
     }
+    if normalized.starts_with("api key rate limit exceeded") {
+        return ErrorCode::KeyRateLimited;
+    }
+    if normalized.starts_with("api key budget exceeded") {
+        return ErrorCode::KeyBudgetExceeded;
+    }
+    if normalized.starts_with("admission queue ") {
+        return ErrorCode::AdmissionQueueRejected;
+    }
     if normalized.starts_with("candidate resolve failed:") {
         return ErrorCode::CandidateResolveFailed;
     }
@@ -99,6 +121,12 @@ pub(crate) fn classify_message(message: &str) -> ErrorCode {
     if normalized == "stream disconnected before completion" {
         return ErrorCode::StreamInterrupted;
     }
+    if normalized == "client_cancelled" {
+        return ErrorCode::ClientCancelled;
+    }
+    if normalized.starts_with("batch deferred") {
+        return ErrorCode::BatchDeferred;
+    }
     if normalized.starts_with("invalid upstream ")
         || (normalized.contains("serialize") && normalized.contains("json"))
         || normalized.contains("sse bytes")
@@ -176,5 +204,25 @@ mod tests {
             classify_message("claude request body must be an object"),
             ErrorCode::InvalidRequestPayload
         );
+        assert_eq!(
+            classify_message("no available account in group(s) TEAM-B for this api key"),
+            ErrorCode::NoAvailableAccount
+        );
+        assert_eq!(
+            classify_message("api key budget exceeded: daily token budget 1000 used 1200"),
+            ErrorCode::KeyBudgetExceeded
+        );
+        assert_eq!(
+            classify_message("admission queue full"),
+            ErrorCode::AdmissionQueueRejected
+        );
+        assert_eq!(
+            classify_message("client_cancelled"),
+            ErrorCode::ClientCancelled
+        );
+        assert_eq!(
+            classify_message("batch deferred: no account with spare quota"),
+            ErrorCode::BatchDeferred
+        );
     }
 }
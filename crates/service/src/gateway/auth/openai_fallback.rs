@@ -170,5 +170,14 @@ pub(super) fn try_openai_fallback(
     };
     let duration_ms = super::duration_to_millis(attempt_started_at.elapsed());
     super::metrics::record_gateway_upstream_attempt(duration_ms, false);
+    if resp.status().is_success() {
+        let proxy = super::upstream_proxy_for_account(account.id.as_str());
+        super::record_route_latency(
+            account.id.as_str(),
+            proxy.as_deref(),
+            super::RouteLatencyKind::FirstByte,
+            duration_ms,
+        );
+    }
     Ok(Some(resp))
 }
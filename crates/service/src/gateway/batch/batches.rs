@@ -0,0 +1,425 @@
+use codexmanager_core::storage::{now_ts, GatewayBatch, GatewayBatchItem};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+use super::files::{find_owned_file, read_file_content, BATCH_INPUT_PURPOSE};
+use super::{BatchApiContext, BatchApiResponse};
+
+const BATCH_COMPLETION_WINDOW: &str = "24h";
+const BATCH_COMPLETION_WINDOW_SECS: i64 = 24 * 60 * 60;
+const BATCH_MAX_REQUESTS: usize = 50_000;
+const BATCH_MAX_REPORTED_ERRORS: usize = 100;
+const BATCH_ENDPOINTS: [&str; 4] = [
+    "/v1/responses",
+    "/v1/chat/completions",
+    "/v1/embeddings",
+    "/v1/completions",
+];
+
+/// 输入文件中通过校验的一行请求。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct BatchInputLine {
+    pub(super) custom_id: String,
+    pub(super) method: String,
+    pub(super) url: String,
+    pub(super) body: String,
+}
+
+/// 校验失败的原因，对应 OpenAI Batch 对象 `errors.data` 中的一项；`line` 从 1 开始。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct BatchInputError {
+    pub(super) code: &'static str,
+    pub(super) message: String,
+    pub(super) param: Option<&'static str>,
+    pub(super) line: Option<usize>,
+}
+
+impl BatchInputError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            param: None,
+            line: None,
+        }
+    }
+
+    fn at(mut self, line: usize, param: Option<&'static str>) -> Self {
+        self.line = Some(line);
+        self.param = param;
+        self
+    }
+
+    fn to_value(&self) -> Value {
+        json!({
+            "code": self.code,
+            "message": self.message.as_str(),
+            "param": self.param,
+            "line": self.line,
+        })
+    }
+}
+
+fn parse_batch_input_line(
+    raw: &str,
+    line: usize,
+    endpoint: &str,
+    seen_custom_ids: &mut HashSet<String>,
+) -> Result<BatchInputLine, BatchInputError> {
+    let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(raw) else {
+        return Err(
+            BatchInputError::new("invalid_json_line", "line is not a valid JSON object")
+                .at(line, None),
+        );
+    };
+    let custom_id = obj
+        .get("custom_id")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            BatchInputError::new("missing_custom_id", "custom_id is required")
+                .at(line, Some("custom_id"))
+        })?;
+    if !seen_custom_ids.insert(custom_id.to_string()) {
+        return Err(BatchInputError::new(
+            "duplicate_custom_id",
+            format!("custom_id '{custom_id}' is used by more than one line"),
+        )
+        .at(line, Some("custom_id")));
+    }
+    let method = obj
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !method.eq_ignore_ascii_case("POST") {
+        return Err(
+            BatchInputError::new("invalid_method", "method must be POST").at(line, Some("method")),
+        );
+    }
+    let url = obj.get("url").and_then(Value::as_str).unwrap_or_default();
+    if url != endpoint {
+        return Err(BatchInputError::new(
+            "mismatched_url",
+            format!("url must match the batch endpoint {endpoint}"),
+        )
+        .at(line, Some("url")));
+    }
+    let Some(body) = obj.get("body").filter(|body| body.is_object()) else {
+        return Err(
+            BatchInputError::new("invalid_body", "body must be a JSON object")
+                .at(line, Some("body")),
+        );
+    };
+    // 中文注释：批处理结果按整段 JSON 写进输出文件，无法承载流式响应。
+    if body.get("stream").and_then(Value::as_bool) == Some(true) {
+        return Err(BatchInputError::new(
+            "streaming_unsupported",
+            "streaming requests are not supported in a batch",
+        )
+        .at(line, Some("body.stream")));
+    }
+    Ok(BatchInputLine {
+        custom_id: custom_id.to_string(),
+        method: "POST".to_string(),
+        url: url.to_string(),
+        body: body.to_string(),
+    })
+}
+
+/// 逐行校验 JSONL 输入文件；任何一行不合法都会让整个批次直接进入 failed。
+pub(super) fn parse_batch_input(
+    content: &[u8],
+    endpoint: &str,
+) -> Result<Vec<BatchInputLine>, Vec<BatchInputError>> {
+    let Ok(text) = std::str::from_utf8(content) else {
+        return Err(vec![BatchInputError::new(
+            "invalid_file_format",
+            "input file must be UTF-8 encoded JSONL",
+        )]);
+    };
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen_custom_ids = HashSet::new();
+    for (index, raw) in text.lines().enumerate() {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        match parse_batch_input_line(raw, index + 1, endpoint, &mut seen_custom_ids) {
+            Ok(line) => lines.push(line),
+            Err(err) if errors.len() < BATCH_MAX_REPORTED_ERRORS => errors.push(err),
+            Err(_) => {}
+        }
+    }
+    if lines.is_empty() && errors.is_empty() {
+        errors.push(BatchInputError::new(
+            "empty_file",
+            "input file does not contain any requests",
+        ));
+    }
+    if lines.len() + errors.len() > BATCH_MAX_REQUESTS {
+        errors.push(BatchInputError::new(
+            "too_many_requests",
+            format!("a batch can contain at most {BATCH_MAX_REQUESTS} requests"),
+        ));
+    }
+    if errors.is_empty() {
+        Ok(lines)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_stored_json(raw: Option<&str>) -> Value {
+    raw.and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .unwrap_or(Value::Null)
+}
+
+pub(super) fn batch_object(batch: &GatewayBatch) -> Value {
+    json!({
+        "id": batch.id.as_str(),
+        "object": "batch",
+        "endpoint": batch.endpoint.as_str(),
+        "errors": parse_stored_json(batch.errors_json.as_deref()),
+        "input_file_id": batch.input_file_id.as_str(),
+        "completion_window": batch.completion_window.as_str(),
+        "status": batch.status.as_str(),
+        "output_file_id": batch.output_file_id.as_deref(),
+        "error_file_id": batch.error_file_id.as_deref(),
+        "created_at": batch.created_at,
+        "in_progress_at": batch.in_progress_at,
+        "expires_at": batch.expires_at,
+        "finalizing_at": batch.finalizing_at,
+        "completed_at": batch.completed_at,
+        "failed_at": batch.failed_at,
+        "expired_at": batch.expired_at,
+        "cancelling_at": batch.cancelling_at,
+        "cancelled_at": batch.cancelled_at,
+        "request_counts": {
+            "total": batch.total_count,
+            "completed": batch.completed_count,
+            "failed": batch.failed_count,
+        },
+        "metadata": parse_stored_json(batch.metadata_json.as_deref()),
+    })
+}
+
+fn find_owned_batch(
+    ctx: &BatchApiContext<'_>,
+    batch_id: &str,
+) -> Result<GatewayBatch, BatchApiResponse> {
+    match ctx.storage.find_gateway_batch(batch_id) {
+        Ok(Some(batch)) if batch.key_id == ctx.key_id => Ok(batch),
+        Ok(_) => Err(BatchApiResponse::not_found("batch", batch_id)),
+        Err(err) => Err(BatchApiResponse::storage_error(err)),
+    }
+}
+
+fn parse_batch_metadata(value: Option<&Value>) -> Result<Option<String>, String> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(obj)) if obj.values().all(Value::is_string) => {
+            Ok(Some(Value::Object(obj.clone()).to_string()))
+        }
+        Some(_) => Err("metadata must be an object of string values".to_string()),
+    }
+}
+
+fn required_str(obj: &Map<String, Value>, name: &str) -> Result<String, BatchApiResponse> {
+    obj.get(name)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            BatchApiResponse::error(
+                400,
+                "missing_required_parameter",
+                format!("{name} is required"),
+            )
+        })
+}
+
+pub(super) fn create_batch(ctx: &BatchApiContext<'_>) -> BatchApiResponse {
+    match create_gateway_batch(ctx) {
+        Ok(batch) => BatchApiResponse::json(200, batch_object(&batch)),
+        Err(response) => response,
+    }
+}
+
+fn create_gateway_batch(ctx: &BatchApiContext<'_>) -> Result<GatewayBatch, BatchApiResponse> {
+    let Ok(Value::Object(obj)) = serde_json::from_slice::<Value>(ctx.body) else {
+        return Err(BatchApiResponse::error(
+            400,
+            "invalid_request",
+            "batch request body must be a JSON object",
+        ));
+    };
+    let input_file_id = required_str(&obj, "input_file_id")?;
+    let endpoint = required_str(&obj, "endpoint")?;
+    let completion_window = required_str(&obj, "completion_window")?;
+    if !BATCH_ENDPOINTS.contains(&endpoint.as_str()) {
+        return Err(BatchApiResponse::error(
+            400,
+            "invalid_endpoint",
+            format!(
+                "unsupported batch endpoint {endpoint}, expected one of {}",
+                BATCH_ENDPOINTS.join(", ")
+            ),
+        ));
+    }
+    if completion_window != BATCH_COMPLETION_WINDOW {
+        return Err(BatchApiResponse::error(
+            400,
+            "invalid_completion_window",
+            format!("completion_window must be {BATCH_COMPLETION_WINDOW}"),
+        ));
+    }
+    let metadata_json = parse_batch_metadata(obj.get("metadata"))
+        .map_err(|err| BatchApiResponse::error(400, "invalid_metadata", err))?;
+    let file = find_owned_file(ctx, &input_file_id)?;
+    if file.purpose != BATCH_INPUT_PURPOSE {
+        return Err(BatchApiResponse::error(
+            400,
+            "invalid_input_file",
+            format!("file {input_file_id} was not uploaded with purpose 'batch'"),
+        ));
+    }
+    let content = read_file_content(&file.id)
+        .map_err(|err| BatchApiResponse::error(500, "file_read_failed", err))?;
+
+    let now = now_ts();
+    let mut batch = GatewayBatch {
+        id: crate::storage_helpers::generate_batch_id(),
+        key_id: ctx.key_id.to_string(),
+        endpoint,
+        input_file_id: file.id,
+        completion_window,
+        status: "validating".to_string(),
+        output_file_id: None,
+        error_file_id: None,
+        metadata_json,
+        errors_json: None,
+        total_count: 0,
+        completed_count: 0,
+        failed_count: 0,
+        created_at: now,
+        in_progress_at: None,
+        expires_at: now + BATCH_COMPLETION_WINDOW_SECS,
+        finalizing_at: None,
+        completed_at: None,
+        failed_at: None,
+        expired_at: None,
+        cancelling_at: None,
+        cancelled_at: None,
+    };
+    let items = match parse_batch_input(&content, &batch.endpoint) {
+        Ok(lines) => {
+            batch.total_count = lines.len() as i64;
+            lines
+                .into_iter()
+                .enumerate()
+                .map(|(index, line)| GatewayBatchItem {
+                    batch_id: batch.id.clone(),
+                    line_index: index as i64,
+                    custom_id: line.custom_id,
+                    method: line.method,
+                    url: line.url,
+                    body: line.body,
+                    status: "pending".to_string(),
+                    attempts: 0,
+                    response_status: None,
+                    request_id: None,
+                    response_body: None,
+                    error_json: None,
+                    updated_at: now,
+                })
+                .collect::<Vec<_>>()
+        }
+        Err(errors) => {
+            batch.status = "failed".to_string();
+            batch.failed_at = Some(now);
+            batch.errors_json = Some(
+                json!({
+                    "object": "list",
+                    "data": errors.iter().map(BatchInputError::to_value).collect::<Vec<_>>(),
+                })
+                .to_string(),
+            );
+            Vec::new()
+        }
+    };
+    ctx.storage
+        .insert_gateway_batch(&batch, &items)
+        .map_err(BatchApiResponse::storage_error)?;
+    if !items.is_empty() {
+        super::runner::wake_batch_runner();
+    }
+    Ok(batch)
+}
+
+pub(super) fn list_batches(ctx: &BatchApiContext<'_>) -> BatchApiResponse {
+    let limit = super::list_limit(ctx.path);
+    let mut batches = match ctx.storage.list_gateway_batches(ctx.key_id, limit + 1) {
+        Ok(batches) => batches,
+        Err(err) => return BatchApiResponse::storage_error(err),
+    };
+    let has_more = batches.len() as i64 > limit;
+    batches.truncate(limit as usize);
+    let mut out = Map::new();
+    out.insert("object".to_string(), json!("list"));
+    out.insert(
+        "data".to_string(),
+        Value::Array(batches.iter().map(batch_object).collect()),
+    );
+    out.insert(
+        "first_id".to_string(),
+        json!(batches.first().map(|batch| batch.id.as_str())),
+    );
+    out.insert(
+        "last_id".to_string(),
+        json!(batches.last().map(|batch| batch.id.as_str())),
+    );
+    out.insert("has_more".to_string(), Value::Bool(has_more));
+    BatchApiResponse::json(200, Value::Object(out))
+}
+
+pub(super) fn retrieve_batch(ctx: &BatchApiContext<'_>, batch_id: &str) -> BatchApiResponse {
+    match find_owned_batch(ctx, batch_id) {
+        Ok(batch) => BatchApiResponse::json(200, batch_object(&batch)),
+        Err(response) => response,
+    }
+}
+
+pub(super) fn cancel_batch(ctx: &BatchApiContext<'_>, batch_id: &str) -> BatchApiResponse {
+    let batch = match find_owned_batch(ctx, batch_id) {
+        Ok(batch) => batch,
+        Err(response) => return response,
+    };
+    match batch.status.as_str() {
+        "validating" | "in_progress" => {
+            match ctx.storage.transition_gateway_batch(
+                &batch.id,
+                &["validating", "in_progress"],
+                "cancelling",
+                now_ts(),
+            ) {
+                Ok(true) => super::runner::wake_batch_runner(),
+                Ok(false) => {}
+                Err(err) => return BatchApiResponse::storage_error(err),
+            }
+            // 中文注释：切换失败说明执行器刚好推进了状态，按最新状态返回即可。
+            retrieve_batch(ctx, &batch.id)
+        }
+        "cancelling" | "cancelled" => BatchApiResponse::json(200, batch_object(&batch)),
+        status => BatchApiResponse::error(
+            409,
+            "invalid_batch_status",
+            format!("batch {batch_id} cannot be cancelled in status {status}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/batches_tests.rs"]
+mod tests;
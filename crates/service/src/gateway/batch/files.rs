@@ -0,0 +1,312 @@
+use codexmanager_core::storage::{now_ts, GatewayFile, Storage};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use super::{BatchApiContext, BatchApiResponse};
+
+pub(super) const BATCH_INPUT_PURPOSE: &str = "batch";
+pub(super) const BATCH_OUTPUT_PURPOSE: &str = "batch_output";
+const BATCH_FILES_DIR_NAME: &str = "batch-files";
+const DEFAULT_UPLOAD_FILENAME: &str = "upload.jsonl";
+
+/// 文件内容与数据库放在同一目录下，元数据写 gateway_files 表。
+fn batch_files_dir() -> Result<PathBuf, String> {
+    let db_path = std::env::var("CODEXMANAGER_DB_PATH")
+        .map_err(|_| "CODEXMANAGER_DB_PATH not set".to_string())?;
+    let parent = PathBuf::from(db_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    Ok(parent.join(BATCH_FILES_DIR_NAME))
+}
+
+fn file_content_path(file_id: &str) -> Result<PathBuf, String> {
+    let valid = !file_id.is_empty()
+        && file_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if !valid {
+        return Err(format!("invalid file id: {file_id}"));
+    }
+    Ok(batch_files_dir()?.join(file_id))
+}
+
+pub(super) fn read_file_content(file_id: &str) -> Result<Vec<u8>, String> {
+    let path = file_content_path(file_id)?;
+    std::fs::read(&path).map_err(|err| format!("read file {file_id} failed: {err}"))
+}
+
+fn remove_file_content(file_id: &str) {
+    let Ok(path) = file_content_path(file_id) else {
+        return;
+    };
+    if let Err(err) = std::fs::remove_file(&path) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            log::warn!(
+                "event=gateway_batch_file_remove_failed file_id={} err={}",
+                file_id,
+                err
+            );
+        }
+    }
+}
+
+/// 先落盘再写元数据；元数据写入失败时清理已写出的内容，避免留下孤儿文件。
+pub(super) fn store_file(
+    storage: &Storage,
+    key_id: &str,
+    filename: &str,
+    purpose: &str,
+    content: &[u8],
+) -> Result<GatewayFile, String> {
+    let file = GatewayFile {
+        id: crate::storage_helpers::generate_batch_file_id(),
+        key_id: key_id.to_string(),
+        filename: filename.to_string(),
+        purpose: purpose.to_string(),
+        bytes: content.len() as i64,
+        created_at: now_ts(),
+    };
+    let path = file_content_path(&file.id)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|err| format!("create batch file dir failed: {err}"))?;
+    }
+    std::fs::write(&path, content)
+        .map_err(|err| format!("write file {} failed: {err}", file.id))?;
+    if let Err(err) = storage.insert_gateway_file(&file) {
+        remove_file_content(&file.id);
+        return Err(format!("storage write failed: {err}"));
+    }
+    Ok(file)
+}
+
+pub(super) fn file_object(file: &GatewayFile) -> Value {
+    json!({
+        "id": file.id.as_str(),
+        "object": "file",
+        "bytes": file.bytes,
+        "created_at": file.created_at,
+        "filename": file.filename.as_str(),
+        "purpose": file.purpose.as_str(),
+        "status": "processed",
+        "expires_at": null,
+    })
+}
+
+/// multipart/form-data 中的一个字段；文件字段带 filename。
+#[derive(Debug)]
+pub(super) struct MultipartPart<'a> {
+    pub(super) name: String,
+    pub(super) filename: Option<String>,
+    pub(super) data: &'a [u8],
+}
+
+pub(super) fn multipart_boundary(content_type: &str) -> Option<String> {
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("boundary") {
+            return None;
+        }
+        let value = value.trim().trim_matches('"');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
+
+fn parse_content_disposition(headers: &str) -> Option<(String, Option<String>)> {
+    let line = headers.split("\r\n").find(|line| {
+        line.to_ascii_lowercase()
+            .starts_with("content-disposition:")
+    })?;
+    let (_, value) = line.split_once(':')?;
+    let mut name = None;
+    let mut filename = None;
+    for param in value.split(';').skip(1) {
+        let Some((key, raw)) = param.split_once('=') else {
+            continue;
+        };
+        let raw = raw.trim().trim_matches('"').to_string();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => name = Some(raw),
+            "filename" => filename = Some(raw),
+            _ => {}
+        }
+    }
+    Some((name.filter(|name| !name.is_empty())?, filename))
+}
+
+pub(super) fn parse_multipart_form<'a>(
+    body: &'a [u8],
+    boundary: &str,
+) -> Result<Vec<MultipartPart<'a>>, String> {
+    let delimiter = format!("--{boundary}");
+    let delimiter = delimiter.as_bytes();
+    let part_delimiter = [b"\r\n".as_slice(), delimiter].concat();
+    let mut cursor = find_bytes(body, delimiter, 0)
+        .ok_or_else(|| "multipart boundary not found".to_string())?
+        + delimiter.len();
+    let mut parts = Vec::new();
+    loop {
+        if body.get(cursor..cursor + 2) == Some(b"--") {
+            break;
+        }
+        if body.get(cursor..cursor + 2) != Some(b"\r\n") {
+            return Err("malformed multipart body".to_string());
+        }
+        cursor += 2;
+        let headers_end = find_bytes(body, b"\r\n\r\n", cursor)
+            .ok_or_else(|| "malformed multipart part headers".to_string())?;
+        let headers = std::str::from_utf8(&body[cursor..headers_end])
+            .map_err(|_| "malformed multipart part headers".to_string())?;
+        let (name, filename) = parse_content_disposition(headers)
+            .ok_or_else(|| "multipart part is missing a form-data name".to_string())?;
+        let data_start = headers_end + 4;
+        let data_end = find_bytes(body, &part_delimiter, data_start)
+            .ok_or_else(|| "multipart body is truncated".to_string())?;
+        parts.push(MultipartPart {
+            name,
+            filename,
+            data: &body[data_start..data_end],
+        });
+        cursor = data_end + part_delimiter.len();
+    }
+    Ok(parts)
+}
+
+fn upload_filename(filename: Option<&str>) -> String {
+    filename
+        .map(|name| name.rsplit(['/', '\\']).next().unwrap_or(name).trim())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_UPLOAD_FILENAME)
+        .to_string()
+}
+
+pub(super) fn find_owned_file(
+    ctx: &BatchApiContext<'_>,
+    file_id: &str,
+) -> Result<GatewayFile, BatchApiResponse> {
+    match ctx.storage.find_gateway_file(file_id) {
+        Ok(Some(file)) if file.key_id == ctx.key_id => Ok(file),
+        Ok(_) => Err(BatchApiResponse::not_found("file", file_id)),
+        Err(err) => Err(BatchApiResponse::storage_error(err)),
+    }
+}
+
+pub(super) fn upload_file(ctx: &BatchApiContext<'_>) -> BatchApiResponse {
+    let Some(boundary) = ctx.content_type.and_then(multipart_boundary) else {
+        return BatchApiResponse::error(
+            400,
+            "invalid_request",
+            "file upload must be multipart/form-data",
+        );
+    };
+    let parts = match parse_multipart_form(ctx.body, &boundary) {
+        Ok(parts) => parts,
+        Err(err) => return BatchApiResponse::error(400, "invalid_request", err),
+    };
+    let purpose = parts
+        .iter()
+        .find(|part| part.name == "purpose")
+        .map(|part| String::from_utf8_lossy(part.data).trim().to_string())
+        .unwrap_or_default();
+    // 中文注释：本地只模拟 Batch API，其它用途（assistants / fine-tune 等）的文件没有消费方。
+    if purpose != BATCH_INPUT_PURPOSE {
+        return BatchApiResponse::error(
+            400,
+            "invalid_purpose",
+            format!("unsupported file purpose '{purpose}', only 'batch' is supported"),
+        );
+    }
+    let Some(file_part) = parts.iter().find(|part| part.name == "file") else {
+        return BatchApiResponse::error(400, "invalid_request", "file field is required");
+    };
+    if file_part.data.is_empty() {
+        return BatchApiResponse::error(400, "invalid_request", "file must not be empty");
+    }
+    match store_file(
+        ctx.storage,
+        ctx.key_id,
+        &upload_filename(file_part.filename.as_deref()),
+        BATCH_INPUT_PURPOSE,
+        file_part.data,
+    ) {
+        Ok(file) => BatchApiResponse::json(200, file_object(&file)),
+        Err(err) => BatchApiResponse::error(500, "file_write_failed", err),
+    }
+}
+
+pub(super) fn list_files(ctx: &BatchApiContext<'_>) -> BatchApiResponse {
+    let purpose = super::query_param(ctx.path, "purpose");
+    match ctx
+        .storage
+        .list_gateway_files(ctx.key_id, purpose.as_deref())
+    {
+        Ok(files) => BatchApiResponse::json(
+            200,
+            json!({
+                "object": "list",
+                "data": files.iter().map(file_object).collect::<Vec<_>>(),
+                "has_more": false,
+            }),
+        ),
+        Err(err) => BatchApiResponse::storage_error(err),
+    }
+}
+
+pub(super) fn retrieve_file(ctx: &BatchApiContext<'_>, file_id: &str) -> BatchApiResponse {
+    match find_owned_file(ctx, file_id) {
+        Ok(file) => BatchApiResponse::json(200, file_object(&file)),
+        Err(response) => response,
+    }
+}
+
+pub(super) fn file_content(ctx: &BatchApiContext<'_>, file_id: &str) -> BatchApiResponse {
+    let file = match find_owned_file(ctx, file_id) {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+    match read_file_content(&file.id) {
+        Ok(body) => BatchApiResponse {
+            status_code: 200,
+            body,
+            content_type: "application/octet-stream",
+            error: None,
+        },
+        Err(err) => BatchApiResponse::error(500, "file_read_failed", err),
+    }
+}
+
+pub(super) fn delete_file(ctx: &BatchApiContext<'_>, file_id: &str) -> BatchApiResponse {
+    let file = match find_owned_file(ctx, file_id) {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+    if let Err(err) = ctx.storage.delete_gateway_file(&file.id) {
+        return BatchApiResponse::storage_error(err);
+    }
+    remove_file_content(&file.id);
+    BatchApiResponse::json(
+        200,
+        json!({
+            "id": file.id.as_str(),
+            "object": "file",
+            "deleted": true,
+        }),
+    )
+}
+
+#[cfg(test)]
+#[path = "tests/files_tests.rs"]
+mod tests;
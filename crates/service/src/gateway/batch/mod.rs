@@ -0,0 +1,262 @@
+use codexmanager_core::storage::{now_ts, Account, Storage, Token};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tiny_http::{Request, Response};
+
+mod batches;
+mod files;
+mod runner;
+
+pub(super) use runner::batch_item_key_id;
+pub(crate) use runner::ensure_batch_runner;
+
+// Env:
+// - CODEXMANAGER_BATCH_MAX_USED_PERCENT (default: 80)
+// Batch items only run on OAuth accounts whose tightest usage window is at or below this percentage.
+const BATCH_MAX_USED_PERCENT_ENV: &str = "CODEXMANAGER_BATCH_MAX_USED_PERCENT";
+const DEFAULT_BATCH_MAX_USED_PERCENT: u64 = 80;
+static BATCH_MAX_USED_PERCENT: AtomicU64 = AtomicU64::new(DEFAULT_BATCH_MAX_USED_PERCENT);
+
+pub(super) const BATCH_DEFERRED_MESSAGE: &str = "batch deferred: no account with spare quota";
+
+pub(super) fn reload_from_env() {
+    BATCH_MAX_USED_PERCENT.store(
+        std::env::var(BATCH_MAX_USED_PERCENT_ENV)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .map(|value| value.min(100))
+            .unwrap_or(DEFAULT_BATCH_MAX_USED_PERCENT),
+        Ordering::Relaxed,
+    );
+}
+
+fn batch_max_used_percent() -> u64 {
+    BATCH_MAX_USED_PERCENT.load(Ordering::Relaxed)
+}
+
+/// 批处理请求只占用空闲且额度充足的 OAuth 账号：服务商溢出层、冷却中、正在处理请求的账号都跳过；
+/// 没有用量快照的账号视为额度充足。
+pub(super) fn retain_batch_candidates(candidates: Vec<(Account, Token)>) -> Vec<(Account, Token)> {
+    let snapshots = super::selection::latest_usage_snapshots();
+    let min_headroom = 100.0 - batch_max_used_percent() as f64;
+    let now = now_ts();
+    candidates
+        .into_iter()
+        .filter(|(account, _)| {
+            !account.is_provider_account()
+                && !super::is_account_in_cooldown(&account.id)
+                && super::account_inflight_count(&account.id) == 0
+                && snapshots
+                    .get(&account.id)
+                    .and_then(|snapshot| super::route_hint::quota_headroom(snapshot, now))
+                    .is_none_or(|(headroom, _)| headroom >= min_headroom)
+        })
+        .collect()
+}
+
+/// 本地应答的 Batch API 端点：`/v1/files` 与 `/v1/batches`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchApiRoute<'a> {
+    UploadFile,
+    ListFiles,
+    RetrieveFile(&'a str),
+    FileContent(&'a str),
+    DeleteFile(&'a str),
+    CreateBatch,
+    ListBatches,
+    RetrieveBatch(&'a str),
+    CancelBatch(&'a str),
+}
+
+fn resolve_batch_api_route<'a>(request_method: &str, path: &'a str) -> Option<BatchApiRoute<'a>> {
+    let path = path.split('?').next().unwrap_or(path);
+    let method = request_method.to_ascii_uppercase();
+    if let Some(rest) = path.strip_prefix("/v1/files") {
+        let segments = rest
+            .strip_prefix('/')
+            .map(|rest| rest.split('/').collect::<Vec<_>>())
+            .unwrap_or_default();
+        return match (method.as_str(), rest, segments.as_slice()) {
+            ("POST", "", _) => Some(BatchApiRoute::UploadFile),
+            ("GET", "", _) => Some(BatchApiRoute::ListFiles),
+            ("GET", _, [id]) if !id.is_empty() => Some(BatchApiRoute::RetrieveFile(id)),
+            ("DELETE", _, [id]) if !id.is_empty() => Some(BatchApiRoute::DeleteFile(id)),
+            ("GET", _, [id, "content"]) if !id.is_empty() => Some(BatchApiRoute::FileContent(id)),
+            _ => None,
+        };
+    }
+    if let Some(rest) = path.strip_prefix("/v1/batches") {
+        let segments = rest
+            .strip_prefix('/')
+            .map(|rest| rest.split('/').collect::<Vec<_>>())
+            .unwrap_or_default();
+        return match (method.as_str(), rest, segments.as_slice()) {
+            ("POST", "", _) => Some(BatchApiRoute::CreateBatch),
+            ("GET", "", _) => Some(BatchApiRoute::ListBatches),
+            ("GET", _, [id]) if !id.is_empty() => Some(BatchApiRoute::RetrieveBatch(id)),
+            ("POST", _, [id, "cancel"]) if !id.is_empty() => Some(BatchApiRoute::CancelBatch(id)),
+            _ => None,
+        };
+    }
+    None
+}
+
+fn query_param(path: &str, name: &str) -> Option<String> {
+    let (_, query) = path.split_once('?')?;
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn list_limit(path: &str) -> i64 {
+    query_param(path, "limit")
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(20)
+        .clamp(1, 100)
+}
+
+/// 单个 Batch API 请求的上下文：Key、原始路径与请求体。
+struct BatchApiContext<'a> {
+    storage: &'a Storage,
+    key_id: &'a str,
+    path: &'a str,
+    body: &'a [u8],
+    content_type: Option<&'a str>,
+}
+
+/// 本地生成的应答；`error` 只用于请求日志与 trace。
+struct BatchApiResponse {
+    status_code: u16,
+    body: Vec<u8>,
+    content_type: &'static str,
+    error: Option<String>,
+}
+
+impl BatchApiResponse {
+    fn json(status_code: u16, value: Value) -> Self {
+        Self {
+            status_code,
+            body: serde_json::to_vec(&value).unwrap_or_default(),
+            content_type: "application/json",
+            error: None,
+        }
+    }
+
+    fn error(status_code: u16, code: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        let mut response = Self::json(
+            status_code,
+            json!({
+                "error": {
+                    "message": message.as_str(),
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": code,
+                }
+            }),
+        );
+        response.error = Some(message);
+        response
+    }
+
+    fn not_found(kind: &str, id: &str) -> Self {
+        Self::error(404, "not_found", format!("no such {kind}: {id}"))
+    }
+
+    fn storage_error(err: impl std::fmt::Display) -> Self {
+        Self::error(500, "storage_error", format!("storage error: {err}"))
+    }
+}
+
+fn dispatch_batch_api(route: BatchApiRoute<'_>, ctx: &BatchApiContext<'_>) -> BatchApiResponse {
+    match route {
+        BatchApiRoute::UploadFile => files::upload_file(ctx),
+        BatchApiRoute::ListFiles => files::list_files(ctx),
+        BatchApiRoute::RetrieveFile(id) => files::retrieve_file(ctx, id),
+        BatchApiRoute::FileContent(id) => files::file_content(ctx, id),
+        BatchApiRoute::DeleteFile(id) => files::delete_file(ctx, id),
+        BatchApiRoute::CreateBatch => batches::create_batch(ctx),
+        BatchApiRoute::ListBatches => batches::list_batches(ctx),
+        BatchApiRoute::RetrieveBatch(id) => batches::retrieve_batch(ctx, id),
+        BatchApiRoute::CancelBatch(id) => batches::cancel_batch(ctx, id),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn maybe_respond_batch_api(
+    request: Request,
+    trace_id: &str,
+    key_id: &str,
+    protocol_type: &str,
+    original_path: &str,
+    path: &str,
+    response_adapter: super::ResponseAdapter,
+    request_method: &str,
+    body: &[u8],
+    storage: &Storage,
+) -> Result<Option<Request>, String> {
+    // 中文注释：Batch API 是 OpenAI 协议的端点，其它协议的 Key 仍按原路径转发。
+    if protocol_type != crate::apikey_profile::PROTOCOL_OPENAI_COMPAT {
+        return Ok(Some(request));
+    }
+    let Some(route) = resolve_batch_api_route(request_method, original_path) else {
+        return Ok(Some(request));
+    };
+    let content_type = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.as_str().to_string());
+    let ctx = BatchApiContext {
+        storage,
+        key_id,
+        path: original_path,
+        body,
+        content_type: content_type.as_deref(),
+    };
+    let output = dispatch_batch_api(route, &ctx);
+
+    let error = output.error.as_deref();
+    super::trace_log::log_attempt_result(trace_id, "-", None, output.status_code, error);
+    super::trace_log::log_request_final(trace_id, output.status_code, None, None, error, 0);
+    super::record_gateway_request_outcome(path, output.status_code, Some(protocol_type));
+    super::write_request_log(
+        storage,
+        super::request_log::RequestLogTraceContext {
+            trace_id: Some(trace_id),
+            original_path: Some(original_path),
+            adapted_path: Some(path),
+            response_adapter: Some(response_adapter),
+            requested_model: None,
+        },
+        Some(key_id),
+        None,
+        path,
+        request_method,
+        None,
+        None,
+        None,
+        Some(output.status_code),
+        super::request_log::RequestLogUsage::default(),
+        error,
+    );
+    let response = super::error_response::with_trace_id_header(
+        Response::from_data(output.body)
+            .with_status_code(output.status_code)
+            .with_header(
+                tiny_http::Header::from_bytes(
+                    b"content-type".as_slice(),
+                    output.content_type.as_bytes(),
+                )
+                .map_err(|_| "build content-type header failed".to_string())?,
+            ),
+        Some(trace_id),
+    );
+    let _ = request.respond(response);
+    Ok(None)
+}
+
+#[cfg(test)]
+#[path = "tests/batch_tests.rs"]
+mod tests;
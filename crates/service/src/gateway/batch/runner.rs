@@ -0,0 +1,440 @@
+use codexmanager_core::storage::{now_ts, GatewayBatch, GatewayBatchItem, Storage};
+use rand::RngCore;
+use serde_json::{json, Value};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tiny_http::Request;
+
+use super::files::{store_file, BATCH_OUTPUT_PURPOSE};
+use crate::error_codes::{ErrorCode, ERROR_CODE_HEADER_NAME, TRACE_ID_HEADER_NAME};
+
+const BATCH_TOKEN_HEADER_NAME: &str = "X-CodexManager-Batch-Token";
+const BATCH_KEY_ID_HEADER_NAME: &str = "X-CodexManager-Batch-Key-Id";
+const BATCH_ITEM_MAX_ATTEMPTS: i64 = 3;
+// 中文注释：新建与取消批次都会主动唤醒执行器，空闲等待只用于兜底检查过期。
+const BATCH_RUNNER_IDLE_WAIT: Duration = Duration::from_secs(30);
+const BATCH_RUNNER_DEFER_WAIT: Duration = Duration::from_secs(2);
+
+static BATCH_RUNNER_STARTED: OnceLock<()> = OnceLock::new();
+static BATCH_RUNNER_WAKE: OnceLock<BatchRunnerWake> = OnceLock::new();
+static BATCH_TOKEN: OnceLock<String> = OnceLock::new();
+
+struct BatchRunnerWake {
+    pending: Mutex<bool>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunnerStep {
+    Idle,
+    Progress,
+    Deferred,
+}
+
+fn runner_wake() -> &'static BatchRunnerWake {
+    BATCH_RUNNER_WAKE.get_or_init(|| BatchRunnerWake {
+        pending: Mutex::new(false),
+        changed: Condvar::new(),
+    })
+}
+
+fn batch_token() -> &'static str {
+    BATCH_TOKEN.get_or_init(|| {
+        let mut buf = [0u8; 16];
+        rand::rngs::OsRng.fill_bytes(&mut buf);
+        buf.iter().map(|b| format!("{b:02x}")).collect()
+    })
+}
+
+/// 启动后台批处理执行器（只启动一次）；服务重启后由它继续执行库里未完成的批次。
+pub(crate) fn ensure_batch_runner() {
+    BATCH_RUNNER_STARTED.get_or_init(|| {
+        let _ = thread::spawn(batch_runner_loop);
+    });
+}
+
+pub(super) fn wake_batch_runner() {
+    ensure_batch_runner();
+    let wake = runner_wake();
+    *crate::lock_utils::lock_recover(&wake.pending, "batch_runner_wake") = true;
+    wake.changed.notify_all();
+}
+
+fn wait_for_wake(timeout: Duration) {
+    let wake = runner_wake();
+    let pending = crate::lock_utils::lock_recover(&wake.pending, "batch_runner_wake");
+    let mut pending = match wake
+        .changed
+        .wait_timeout_while(pending, timeout, |pending| !*pending)
+    {
+        Ok((guard, _)) => guard,
+        Err(poisoned) => {
+            log::warn!("event=lock_poisoned lock=batch_runner_wake action=recover");
+            poisoned.into_inner().0
+        }
+    };
+    *pending = false;
+}
+
+/// 执行器回环调用网关时携带进程内令牌与 key id；令牌匹配时返回应代为鉴权的 key id。
+pub(in super::super) fn batch_item_key_id(request: &Request) -> Option<String> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    };
+    let token = BATCH_TOKEN.get()?;
+    if header(BATCH_TOKEN_HEADER_NAME)? != token.as_str() {
+        return None;
+    }
+    header(BATCH_KEY_ID_HEADER_NAME)
+        .map(str::trim)
+        .filter(|key_id| !key_id.is_empty())
+        .map(str::to_string)
+}
+
+struct BatchItemResponse {
+    status_code: u16,
+    error_code: Option<String>,
+    request_id: Option<String>,
+    body: String,
+}
+
+/// 批处理请求经本机回环端口重新进入网关，复用完整的鉴权、改写、选路与日志流程。
+struct BatchExecutor {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl BatchExecutor {
+    fn start() -> Result<Self, String> {
+        let server = tiny_http::Server::http("127.0.0.1:0")
+            .map_err(|err| format!("start batch executor failed: {err}"))?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.to_string())
+            .ok_or_else(|| "batch executor addr missing".to_string())?;
+        let client = reqwest::blocking::Client::builder()
+            .no_proxy()
+            .timeout(None)
+            .build()
+            .map_err(|err| format!("build batch executor client failed: {err}"))?;
+        let _ = thread::spawn(move || {
+            for request in server.incoming_requests() {
+                if let Err(err) = super::super::handle_gateway_request(request) {
+                    log::warn!("event=gateway_batch_request_error err={}", err);
+                }
+            }
+        });
+        Ok(Self {
+            base_url: format!("http://{addr}"),
+            client,
+        })
+    }
+
+    fn send(&self, key_id: &str, item: &GatewayBatchItem) -> Result<BatchItemResponse, String> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, item.url))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(BATCH_TOKEN_HEADER_NAME, batch_token())
+            .header(BATCH_KEY_ID_HEADER_NAME, key_id)
+            .body(item.body.clone())
+            .send()
+            .map_err(|err| format!("batch request failed: {err}"))?;
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let status_code = response.status().as_u16();
+        let error_code = header(ERROR_CODE_HEADER_NAME);
+        let request_id = header(TRACE_ID_HEADER_NAME);
+        let body = response
+            .text()
+            .map_err(|err| format!("read batch response failed: {err}"))?;
+        Ok(BatchItemResponse {
+            status_code,
+            error_code,
+            request_id,
+            body,
+        })
+    }
+}
+
+fn batch_runner_loop() {
+    let mut executor = None;
+    loop {
+        let step = if crate::shutdown_requested() {
+            RunnerStep::Idle
+        } else {
+            match crate::storage_helpers::open_storage() {
+                Some(storage) => run_batch_step(&storage, &mut executor).unwrap_or_else(|err| {
+                    log::warn!("event=gateway_batch_runner_failed err={}", err);
+                    RunnerStep::Deferred
+                }),
+                None => RunnerStep::Idle,
+            }
+        };
+        match step {
+            RunnerStep::Progress => {}
+            RunnerStep::Deferred => wait_for_wake(BATCH_RUNNER_DEFER_WAIT),
+            RunnerStep::Idle => wait_for_wake(BATCH_RUNNER_IDLE_WAIT),
+        }
+    }
+}
+
+/// 按创建顺序推进最早的一个未完成批次，每次最多执行一行请求。
+fn run_batch_step(
+    storage: &Storage,
+    executor: &mut Option<BatchExecutor>,
+) -> Result<RunnerStep, String> {
+    let batches = storage
+        .list_active_gateway_batches()
+        .map_err(|err| format!("list active batches failed: {err}"))?;
+    for batch in batches {
+        let step = advance_batch(storage, &batch, executor)?;
+        if step != RunnerStep::Idle {
+            return Ok(step);
+        }
+    }
+    Ok(RunnerStep::Idle)
+}
+
+fn advance_batch(
+    storage: &Storage,
+    batch: &GatewayBatch,
+    executor: &mut Option<BatchExecutor>,
+) -> Result<RunnerStep, String> {
+    let now = now_ts();
+    match batch.status.as_str() {
+        "validating" => {
+            storage
+                .transition_gateway_batch(&batch.id, &["validating"], "in_progress", now)
+                .map_err(|err| format!("start batch {} failed: {err}", batch.id))?;
+        }
+        "cancelling" => {
+            close_pending_items(storage, batch, "cancelled", "batch_cancelled")?;
+            finalize_batch(storage, batch, "cancelled")?;
+        }
+        "finalizing" => {
+            let status = resolve_finalizing_status(storage, batch)?;
+            finalize_batch(storage, batch, status)?;
+        }
+        _ if now >= batch.expires_at => {
+            close_pending_items(storage, batch, "expired", "batch_expired")?;
+            finalize_batch(storage, batch, "expired")?;
+        }
+        _ => {
+            let item = storage
+                .next_pending_gateway_batch_item(&batch.id)
+                .map_err(|err| format!("read batch {} items failed: {err}", batch.id))?;
+            match item {
+                Some(item) => return run_batch_item(storage, batch, item, executor),
+                None => finalize_batch(storage, batch, "completed")?,
+            }
+        }
+    }
+    Ok(RunnerStep::Progress)
+}
+
+fn close_pending_items(
+    storage: &Storage,
+    batch: &GatewayBatch,
+    status: &str,
+    code: &str,
+) -> Result<(), String> {
+    let error = json!({
+        "code": code,
+        "message": format!("batch was {status} before this request ran"),
+    });
+    storage
+        .close_pending_gateway_batch_items(&batch.id, status, &error.to_string(), now_ts())
+        .map(|_| ())
+        .map_err(|err| format!("close batch {} items failed: {err}", batch.id))
+}
+
+/// 上次停在 finalizing（例如写结果文件时进程退出）的批次，按已落库的状态还原最终结果。
+fn resolve_finalizing_status(
+    storage: &Storage,
+    batch: &GatewayBatch,
+) -> Result<&'static str, String> {
+    if batch.cancelling_at.is_some() {
+        return Ok("cancelled");
+    }
+    let items = storage
+        .list_gateway_batch_items(&batch.id)
+        .map_err(|err| format!("read batch {} items failed: {err}", batch.id))?;
+    if items.iter().any(|item| item.status == "expired") {
+        Ok("expired")
+    } else {
+        Ok("completed")
+    }
+}
+
+fn is_deferred_response(response: &BatchItemResponse) -> bool {
+    let code = response.error_code.as_deref();
+    (response.status_code == 503 && code == Some(ErrorCode::BatchDeferred.as_str()))
+        || (response.status_code == 429 && code == Some(ErrorCode::KeyRateLimited.as_str()))
+}
+
+fn run_batch_item(
+    storage: &Storage,
+    batch: &GatewayBatch,
+    mut item: GatewayBatchItem,
+    executor: &mut Option<BatchExecutor>,
+) -> Result<RunnerStep, String> {
+    let executor = match executor {
+        Some(executor) => executor,
+        None => executor.insert(BatchExecutor::start()?),
+    };
+    let result = executor.send(&batch.key_id, &item);
+    // 中文注释：没有空闲额度或 Key 限流时只是延后执行，不计入重试次数。
+    if result.as_ref().is_ok_and(is_deferred_response) {
+        return Ok(RunnerStep::Deferred);
+    }
+    item.attempts += 1;
+    item.updated_at = now_ts();
+    let retryable = match &result {
+        Ok(response) => response.status_code == 429 || response.status_code >= 500,
+        Err(_) => true,
+    };
+    if retryable && item.attempts < BATCH_ITEM_MAX_ATTEMPTS {
+        storage
+            .update_gateway_batch_item(&item)
+            .map_err(|err| format!("update batch item failed: {err}"))?;
+        return Ok(RunnerStep::Deferred);
+    }
+    match result {
+        Ok(response) => {
+            item.status = if (200..300).contains(&response.status_code) {
+                "completed".to_string()
+            } else {
+                "failed".to_string()
+            };
+            item.response_status = Some(i64::from(response.status_code));
+            item.request_id = response.request_id;
+            item.response_body = Some(response.body);
+        }
+        Err(err) => {
+            item.status = "failed".to_string();
+            item.error_json = Some(
+                json!({
+                    "code": "batch_request_failed",
+                    "message": err,
+                })
+                .to_string(),
+            );
+        }
+    }
+    storage
+        .update_gateway_batch_item(&item)
+        .map_err(|err| format!("update batch item failed: {err}"))?;
+    Ok(RunnerStep::Progress)
+}
+
+/// 生成输出文件中的一行；成功的请求进 output 文件，其余进 error 文件。仍在排队的行返回 None。
+fn build_batch_output_line(item: &GatewayBatchItem) -> Option<(Value, bool)> {
+    let id = format!(
+        "batch_req_{}_{}",
+        item.batch_id.trim_start_matches("batch_"),
+        item.line_index
+    );
+    if item.status == "pending" {
+        return None;
+    }
+    if let Some(status_code) = item.response_status {
+        let body = item
+            .response_body
+            .as_deref()
+            .map(|raw| {
+                serde_json::from_str::<Value>(raw)
+                    .unwrap_or_else(|_| Value::String(raw.to_string()))
+            })
+            .unwrap_or(Value::Null);
+        let line = json!({
+            "id": id,
+            "custom_id": item.custom_id.as_str(),
+            "response": {
+                "status_code": status_code,
+                "request_id": item.request_id.as_deref(),
+                "body": body,
+            },
+            "error": null,
+        });
+        return Some((line, item.status != "completed"));
+    }
+    let error = item
+        .error_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .unwrap_or_else(|| json!({ "code": "batch_request_failed", "message": null }));
+    let line = json!({
+        "id": id,
+        "custom_id": item.custom_id.as_str(),
+        "response": null,
+        "error": error,
+    });
+    Some((line, true))
+}
+
+fn write_result_file(
+    storage: &Storage,
+    batch: &GatewayBatch,
+    kind: &str,
+    content: &[u8],
+) -> Result<Option<String>, String> {
+    if content.is_empty() {
+        return Ok(None);
+    }
+    let filename = format!("{}_{kind}.jsonl", batch.id);
+    store_file(
+        storage,
+        &batch.key_id,
+        &filename,
+        BATCH_OUTPUT_PURPOSE,
+        content,
+    )
+    .map(|file| Some(file.id))
+}
+
+fn finalize_batch(storage: &Storage, batch: &GatewayBatch, status: &str) -> Result<(), String> {
+    if batch.status != "finalizing"
+        && !storage
+            .transition_gateway_batch(&batch.id, &[batch.status.as_str()], "finalizing", now_ts())
+            .map_err(|err| format!("finalize batch {} failed: {err}", batch.id))?
+    {
+        return Ok(());
+    }
+    let items = storage
+        .list_gateway_batch_items(&batch.id)
+        .map_err(|err| format!("read batch {} items failed: {err}", batch.id))?;
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    for (line, is_error) in items.iter().filter_map(build_batch_output_line) {
+        let target = if is_error { &mut errors } else { &mut output };
+        target.extend_from_slice(line.to_string().as_bytes());
+        target.push(b'\n');
+    }
+    let output_file_id = write_result_file(storage, batch, "output", &output)?;
+    let error_file_id = write_result_file(storage, batch, "error", &errors)?;
+    storage
+        .complete_gateway_batch(
+            &batch.id,
+            status,
+            output_file_id.as_deref(),
+            error_file_id.as_deref(),
+            now_ts(),
+        )
+        .map_err(|err| format!("complete batch {} failed: {err}", batch.id))
+}
+
+#[cfg(test)]
+#[path = "tests/runner_tests.rs"]
+mod tests;
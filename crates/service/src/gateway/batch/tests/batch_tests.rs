@@ -0,0 +1,62 @@
+use super::*;
+
+#[test]
+fn batch_api_routes_match_files_and_batches_endpoints() {
+    assert_eq!(
+        resolve_batch_api_route("POST", "/v1/files"),
+        Some(BatchApiRoute::UploadFile)
+    );
+    assert_eq!(
+        resolve_batch_api_route("GET", "/v1/files?purpose=batch"),
+        Some(BatchApiRoute::ListFiles)
+    );
+    assert_eq!(
+        resolve_batch_api_route("GET", "/v1/files/file-abc"),
+        Some(BatchApiRoute::RetrieveFile("file-abc"))
+    );
+    assert_eq!(
+        resolve_batch_api_route("GET", "/v1/files/file-abc/content"),
+        Some(BatchApiRoute::FileContent("file-abc"))
+    );
+    assert_eq!(
+        resolve_batch_api_route("DELETE", "/v1/files/file-abc"),
+        Some(BatchApiRoute::DeleteFile("file-abc"))
+    );
+    assert_eq!(
+        resolve_batch_api_route("POST", "/v1/batches"),
+        Some(BatchApiRoute::CreateBatch)
+    );
+    assert_eq!(
+        resolve_batch_api_route("GET", "/v1/batches?limit=5"),
+        Some(BatchApiRoute::ListBatches)
+    );
+    assert_eq!(
+        resolve_batch_api_route("GET", "/v1/batches/batch_1"),
+        Some(BatchApiRoute::RetrieveBatch("batch_1"))
+    );
+    assert_eq!(
+        resolve_batch_api_route("POST", "/v1/batches/batch_1/cancel"),
+        Some(BatchApiRoute::CancelBatch("batch_1"))
+    );
+}
+
+#[test]
+fn batch_api_routes_ignore_other_paths_and_methods() {
+    assert_eq!(resolve_batch_api_route("POST", "/v1/responses"), None);
+    assert_eq!(resolve_batch_api_route("GET", "/v1/filesystem"), None);
+    assert_eq!(resolve_batch_api_route("PUT", "/v1/files/file-abc"), None);
+    assert_eq!(resolve_batch_api_route("POST", "/v1/batches/batch_1"), None);
+    assert_eq!(resolve_batch_api_route("GET", "/v1/files/"), None);
+}
+
+#[test]
+fn list_limit_defaults_and_clamps() {
+    assert_eq!(list_limit("/v1/batches"), 20);
+    assert_eq!(list_limit("/v1/batches?limit=5"), 5);
+    assert_eq!(list_limit("/v1/batches?limit=0"), 1);
+    assert_eq!(list_limit("/v1/batches?limit=1000"), 100);
+    assert_eq!(
+        query_param("/v1/files?purpose=batch_output", "purpose").as_deref(),
+        Some("batch_output")
+    );
+}
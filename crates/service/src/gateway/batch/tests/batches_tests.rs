@@ -0,0 +1,71 @@
+use super::*;
+
+fn line(custom_id: &str, url: &str, body: &str) -> String {
+    format!(r#"{{"custom_id":"{custom_id}","method":"POST","url":"{url}","body":{body}}}"#)
+}
+
+#[test]
+fn parse_batch_input_accepts_valid_jsonl() {
+    let content = [
+        line("a", "/v1/embeddings", r#"{"model":"m","input":"x"}"#),
+        String::new(),
+        line("b", "/v1/embeddings", r#"{"model":"m","input":"y"}"#),
+    ]
+    .join("\n");
+    let lines = parse_batch_input(content.as_bytes(), "/v1/embeddings").expect("valid input");
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].custom_id, "a");
+    assert_eq!(lines[1].url, "/v1/embeddings");
+    let body: Value = serde_json::from_str(&lines[1].body).expect("body json");
+    assert_eq!(body["input"], json!("y"));
+}
+
+#[test]
+fn parse_batch_input_reports_line_errors() {
+    let content = [
+        line("a", "/v1/embeddings", r#"{"model":"m","input":"x"}"#),
+        line("a", "/v1/embeddings", r#"{"model":"m","input":"y"}"#),
+        line("c", "/v1/responses", r#"{"model":"m"}"#),
+        r#"{"custom_id":"d","method":"GET","url":"/v1/embeddings","body":{}}"#.to_string(),
+        line("e", "/v1/embeddings", r#"{"model":"m","stream":true}"#),
+        line("f", "/v1/embeddings", "[]"),
+        "not json".to_string(),
+    ]
+    .join("\n");
+    let errors = parse_batch_input(content.as_bytes(), "/v1/embeddings").expect_err("invalid");
+    let summary = errors
+        .iter()
+        .map(|err| (err.code, err.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("duplicate_custom_id", Some(2)),
+            ("mismatched_url", Some(3)),
+            ("invalid_method", Some(4)),
+            ("streaming_unsupported", Some(5)),
+            ("invalid_body", Some(6)),
+            ("invalid_json_line", Some(7)),
+        ]
+    );
+    assert_eq!(errors[0].param, Some("custom_id"));
+}
+
+#[test]
+fn parse_batch_input_rejects_empty_and_non_utf8_files() {
+    let errors = parse_batch_input(b"\n\n", "/v1/embeddings").expect_err("empty");
+    assert_eq!(errors[0].code, "empty_file");
+    let errors = parse_batch_input(&[0xff, 0xfe], "/v1/embeddings").expect_err("binary");
+    assert_eq!(errors[0].code, "invalid_file_format");
+}
+
+#[test]
+fn parse_batch_metadata_requires_string_values() {
+    assert_eq!(parse_batch_metadata(None), Ok(None));
+    assert_eq!(
+        parse_batch_metadata(Some(&json!({"job": "nightly"}))),
+        Ok(Some(r#"{"job":"nightly"}"#.to_string()))
+    );
+    assert!(parse_batch_metadata(Some(&json!({"count": 1}))).is_err());
+    assert!(parse_batch_metadata(Some(&json!("nightly"))).is_err());
+}
@@ -0,0 +1,57 @@
+use super::*;
+
+#[test]
+fn multipart_boundary_requires_form_data() {
+    assert_eq!(
+        multipart_boundary("multipart/form-data; boundary=----abc").as_deref(),
+        Some("----abc")
+    );
+    assert_eq!(
+        multipart_boundary("multipart/form-data; charset=utf-8; boundary=\"xyz\"").as_deref(),
+        Some("xyz")
+    );
+    assert_eq!(multipart_boundary("application/json"), None);
+    assert_eq!(multipart_boundary("multipart/mixed; boundary=abc"), None);
+}
+
+#[test]
+fn parse_multipart_form_extracts_fields_and_file() {
+    let body = concat!(
+        "--XYZ\r\n",
+        "Content-Disposition: form-data; name=\"purpose\"\r\n",
+        "\r\n",
+        "batch\r\n",
+        "--XYZ\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"dir/input.jsonl\"\r\n",
+        "Content-Type: application/jsonl\r\n",
+        "\r\n",
+        "{\"custom_id\":\"a\"}\n{\"custom_id\":\"b\"}\n\r\n",
+        "--XYZ--\r\n",
+    );
+    let parts = parse_multipart_form(body.as_bytes(), "XYZ").expect("parse multipart");
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].name, "purpose");
+    assert_eq!(parts[0].data, b"batch");
+    assert_eq!(parts[1].name, "file");
+    assert_eq!(parts[1].filename.as_deref(), Some("dir/input.jsonl"));
+    assert_eq!(
+        parts[1].data,
+        b"{\"custom_id\":\"a\"}\n{\"custom_id\":\"b\"}\n".as_slice()
+    );
+    assert_eq!(upload_filename(parts[1].filename.as_deref()), "input.jsonl");
+    assert_eq!(upload_filename(None), DEFAULT_UPLOAD_FILENAME);
+}
+
+#[test]
+fn parse_multipart_form_rejects_truncated_body() {
+    let body = "--XYZ\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\nabc";
+    assert!(parse_multipart_form(body.as_bytes(), "XYZ").is_err());
+    assert!(parse_multipart_form(b"no boundary here", "XYZ").is_err());
+}
+
+#[test]
+fn file_content_path_rejects_path_traversal() {
+    assert!(file_content_path("../secrets").is_err());
+    assert!(file_content_path("file-a/b").is_err());
+    assert!(file_content_path("").is_err());
+}
@@ -0,0 +1,77 @@
+use super::*;
+
+fn item(status: &str) -> GatewayBatchItem {
+    GatewayBatchItem {
+        batch_id: "batch_abc".to_string(),
+        line_index: 3,
+        custom_id: "req-3".to_string(),
+        method: "POST".to_string(),
+        url: "/v1/embeddings".to_string(),
+        body: "{}".to_string(),
+        status: status.to_string(),
+        attempts: 1,
+        response_status: None,
+        request_id: None,
+        response_body: None,
+        error_json: None,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn output_line_wraps_successful_response() {
+    let mut completed = item("completed");
+    completed.response_status = Some(200);
+    completed.request_id = Some("trc_1".to_string());
+    completed.response_body = Some(r#"{"object":"list","data":[]}"#.to_string());
+    let (line, is_error) = build_batch_output_line(&completed).expect("output line");
+    assert!(!is_error);
+    assert_eq!(line["id"], json!("batch_req_abc_3"));
+    assert_eq!(line["custom_id"], json!("req-3"));
+    assert_eq!(line["response"]["status_code"], json!(200));
+    assert_eq!(line["response"]["request_id"], json!("trc_1"));
+    assert_eq!(line["response"]["body"]["object"], json!("list"));
+    assert!(line["error"].is_null());
+}
+
+#[test]
+fn output_line_routes_failures_to_error_file() {
+    let mut failed = item("failed");
+    failed.response_status = Some(400);
+    failed.response_body = Some("bad request".to_string());
+    let (line, is_error) = build_batch_output_line(&failed).expect("error line");
+    assert!(is_error);
+    assert_eq!(line["response"]["body"], json!("bad request"));
+
+    let mut cancelled = item("cancelled");
+    cancelled.error_json = Some(r#"{"code":"batch_cancelled","message":"m"}"#.to_string());
+    let (line, is_error) = build_batch_output_line(&cancelled).expect("cancelled line");
+    assert!(is_error);
+    assert!(line["response"].is_null());
+    assert_eq!(line["error"]["code"], json!("batch_cancelled"));
+
+    assert!(build_batch_output_line(&item("pending")).is_none());
+}
+
+#[test]
+fn deferred_responses_are_recognized_by_error_code() {
+    let response = |status_code: u16, code: Option<ErrorCode>| BatchItemResponse {
+        status_code,
+        error_code: code.map(|code| code.as_str().to_string()),
+        request_id: None,
+        body: String::new(),
+    };
+    assert!(is_deferred_response(&response(
+        503,
+        Some(ErrorCode::BatchDeferred)
+    )));
+    assert!(is_deferred_response(&response(
+        429,
+        Some(ErrorCode::KeyRateLimited)
+    )));
+    assert!(!is_deferred_response(&response(
+        503,
+        Some(ErrorCode::NoAvailableAccount)
+    )));
+    assert!(!is_deferred_response(&response(429, None)));
+}
@@ -2,11 +2,12 @@ use codexmanager_core::auth::{DEFAULT_CLIENT_ID, DEFAULT_ISSUER};
 use reqwest::blocking::Client;
 use reqwest::Proxy;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::sync::{OnceLock, RwLock};
+use std::sync::{Mutex, OnceLock, RwLock};
 use std::time::Duration;
 
 static UPSTREAM_CLIENT: OnceLock<RwLock<Client>> = OnceLock::new();
 static UPSTREAM_CLIENT_POOL: OnceLock<RwLock<UpstreamClientPool>> = OnceLock::new();
+static UPSTREAM_CLIENT_SETTINGS: OnceLock<Mutex<Option<UpstreamClientSettings>>> = OnceLock::new();
 static RUNTIME_CONFIG_LOADED: OnceLock<()> = OnceLock::new();
 static REQUEST_GATE_WAIT_TIMEOUT_MS: AtomicU64 =
     AtomicU64::new(DEFAULT_REQUEST_GATE_WAIT_TIMEOUT_MS);
@@ -60,6 +61,24 @@ struct UpstreamClientPool {
     clients: Vec<Client>,
 }
 
+/// 构建上游客户端时用到的配置；不变时复用已有客户端，避免重复加载 TLS 根证书。
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UpstreamClientSettings {
+    proxy_url: Option<String>,
+    proxy_list: Vec<String>,
+    connect_timeout_secs: u64,
+}
+
+impl UpstreamClientSettings {
+    fn current() -> Self {
+        Self {
+            proxy_url: current_upstream_proxy_url(),
+            proxy_list: parse_proxy_list_env(),
+            connect_timeout_secs: UPSTREAM_CONNECT_TIMEOUT_SECS.load(Ordering::Relaxed),
+        }
+    }
+}
+
 impl UpstreamClientPool {
     fn client_for_account(&self, account_id: &str) -> Option<&Client> {
         let idx = stable_proxy_index(account_id, self.clients.len())?;
@@ -100,6 +119,16 @@ pub(crate) fn fresh_upstream_client_for_account(account_id: &str) -> Client {
     build_upstream_client()
 }
 
+/// 账号实际走的出口代理：代理池优先，其次全局代理；直连时返回 None。
+pub(crate) fn upstream_proxy_for_account(account_id: &str) -> Option<String> {
+    ensure_runtime_config_loaded();
+    let pooled =
+        crate::lock_utils::read_recover(upstream_client_pool_lock(), "upstream_client_pool")
+            .proxy_for_account(account_id)
+            .map(str::to_string);
+    pooled.or_else(current_upstream_proxy_url)
+}
+
 fn upstream_connect_timeout_cached() -> Duration {
     Duration::from_secs(UPSTREAM_CONNECT_TIMEOUT_SECS.load(Ordering::Relaxed))
 }
@@ -348,16 +377,31 @@ fn upstream_client_pool_lock() -> &'static RwLock<UpstreamClientPool> {
 }
 
 fn refresh_upstream_clients_from_runtime_config() {
-    let client = build_upstream_client();
-    let mut client_lock =
-        crate::lock_utils::write_recover(upstream_client_lock(), "upstream_client");
-    *client_lock = client;
-    drop(client_lock);
-
-    let pool = build_upstream_client_pool();
-    let mut pool_lock =
-        crate::lock_utils::write_recover(upstream_client_pool_lock(), "upstream_client_pool");
-    *pool_lock = pool;
+    let settings = UpstreamClientSettings::current();
+    let mut applied = crate::lock_utils::lock_recover(
+        UPSTREAM_CLIENT_SETTINGS.get_or_init(|| Mutex::new(None)),
+        "upstream_client_settings",
+    );
+    // 中文注释：启动时 env 与持久化设置会各触发一次 reload；客户端配置没变就不重建，
+    // 否则每次都要重新加载 TLS 根证书，拖慢首个请求。
+    if applied.as_ref() == Some(&settings) {
+        return;
+    }
+
+    let mut client = Some(build_upstream_client());
+    let client_lock =
+        UPSTREAM_CLIENT.get_or_init(|| RwLock::new(client.take().unwrap_or_default()));
+    if let Some(client) = client {
+        *crate::lock_utils::write_recover(client_lock, "upstream_client") = client;
+    }
+
+    let mut pool = Some(build_upstream_client_pool());
+    let pool_lock =
+        UPSTREAM_CLIENT_POOL.get_or_init(|| RwLock::new(pool.take().unwrap_or_default()));
+    if let Some(pool) = pool {
+        *crate::lock_utils::write_recover(pool_lock, "upstream_client_pool") = pool;
+    }
+    *applied = Some(settings);
 }
 
 fn build_upstream_client_pool() -> UpstreamClientPool {
@@ -31,6 +31,32 @@ pub(super) fn terminal_text_response(
     with_trace_id_header(response, trace_id)
 }
 
+/// 带结构化错误体与附加响应头的终止响应（例如按协议构造的 429 限流错误）。
+pub(super) fn terminal_json_response(
+    status_code: u16,
+    message: &str,
+    body: Vec<u8>,
+    extra_headers: &[(String, String)],
+    trace_id: Option<&str>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut response = Response::from_data(body).with_status_code(status_code);
+    if let Ok(header) = Header::from_bytes(b"Content-Type".as_slice(), b"application/json") {
+        response.add_header(header);
+    }
+    if let Ok(header) = Header::from_bytes(
+        crate::error_codes::ERROR_CODE_HEADER_NAME.as_bytes(),
+        crate::error_codes::code_for_message(message).as_bytes(),
+    ) {
+        response.add_header(header);
+    }
+    for (name, value) in extra_headers {
+        if let Ok(header) = Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+            response.add_header(header);
+        }
+    }
+    with_trace_id_header(response, trace_id)
+}
+
 #[cfg(test)]
 #[path = "tests/error_response_tests.rs"]
 mod tests;
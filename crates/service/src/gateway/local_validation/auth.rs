@@ -28,6 +28,27 @@ pub(super) fn load_active_api_key(
         return Err(super::LocalValidationError::new(403, "invalid api key"));
     };
 
+    ensure_api_key_active(api_key, request_url, debug)
+}
+
+pub(super) fn load_active_api_key_by_id(
+    storage: &Storage,
+    key_id: &str,
+) -> Result<ApiKey, super::LocalValidationError> {
+    let api_key = storage.find_api_key_by_id(key_id).map_err(|err| {
+        super::LocalValidationError::new(500, format!("storage read failed: {err}"))
+    })?;
+    let Some(api_key) = api_key else {
+        return Err(super::LocalValidationError::new(403, "invalid api key"));
+    };
+    ensure_api_key_active(api_key, "-", false)
+}
+
+fn ensure_api_key_active(
+    api_key: ApiKey,
+    request_url: &str,
+    debug: bool,
+) -> Result<ApiKey, super::LocalValidationError> {
     if api_key.status != "active" {
         if debug {
             log::warn!(
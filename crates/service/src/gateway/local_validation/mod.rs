@@ -1,5 +1,8 @@
 use bytes::Bytes;
+use codexmanager_core::rpc::types::ApiKeyBudgetStatus;
+use codexmanager_core::storage::now_ts;
 use reqwest::Method;
+use serde_json::json;
 use tiny_http::Request;
 
 mod auth;
@@ -23,14 +26,23 @@ pub(super) struct LocalValidationResult {
     pub(super) tool_name_restore_map: super::ToolNameRestoreMap,
     pub(super) request_method: String,
     pub(super) key_id: String,
+    pub(super) account_groups: Vec<String>,
+    pub(super) hedge_delay_ms: Option<u64>,
+    pub(super) rate_limit_permit: super::KeyRateLimitPermit,
     pub(super) model_for_log: Option<String>,
+    pub(super) requested_model_for_log: Option<String>,
     pub(super) reasoning_for_log: Option<String>,
     pub(super) method: Method,
+    pub(super) embeddings: Option<super::EmbeddingsRequest>,
+    pub(super) batch_item: bool,
 }
 
 pub(super) struct LocalValidationError {
     pub(super) status_code: u16,
     pub(super) message: String,
+    pub(super) key_id: Option<String>,
+    pub(super) body: Option<Vec<u8>>,
+    pub(super) headers: Vec<(String, String)>,
 }
 
 impl LocalValidationError {
@@ -38,8 +50,63 @@ impl LocalValidationError {
         Self {
             status_code,
             message: message.into(),
+            key_id: None,
+            body: None,
+            headers: Vec::new(),
         }
     }
+
+    fn rate_limited(
+        key_id: &str,
+        protocol_type: &str,
+        rejection: &super::KeyRateLimitRejection,
+    ) -> Self {
+        Self {
+            status_code: 429,
+            message: rejection.message(),
+            key_id: Some(key_id.to_string()),
+            body: Some(rejection.response_body(protocol_type)),
+            headers: rejection.response_headers(),
+        }
+    }
+
+    fn budget_exceeded(key_id: &str, protocol_type: &str, status: &ApiKeyBudgetStatus) -> Self {
+        let message = crate::apikey_budget::budget_exceeded_message(status);
+        let retry_after_secs = status.period_end.saturating_sub(now_ts()).max(1);
+        Self {
+            status_code: 429,
+            body: Some(budget_exceeded_body(protocol_type, &message)),
+            message,
+            key_id: Some(key_id.to_string()),
+            headers: vec![("Retry-After".to_string(), retry_after_secs.to_string())],
+        }
+    }
+}
+
+/// 预算耗尽按各协议的“额度不足”语义返回，让 SDK 不做无意义的自动重试。
+fn budget_exceeded_body(protocol_type: &str, message: &str) -> Vec<u8> {
+    if protocol_type == crate::apikey_profile::PROTOCOL_GEMINI_NATIVE {
+        return super::build_gemini_error_body(429, message);
+    }
+    let payload = if protocol_type == crate::apikey_profile::PROTOCOL_ANTHROPIC_NATIVE {
+        json!({
+            "type": "error",
+            "error": {
+                "type": "rate_limit_error",
+                "message": message,
+            }
+        })
+    } else {
+        json!({
+            "error": {
+                "message": message,
+                "type": "insufficient_quota",
+                "param": null,
+                "code": "insufficient_quota",
+            }
+        })
+    };
+    serde_json::to_vec(&payload).unwrap_or_else(|_| message.as_bytes().to_vec())
 }
 
 pub(super) fn prepare_local_request(
@@ -49,10 +116,32 @@ pub(super) fn prepare_local_request(
 ) -> Result<LocalValidationResult, LocalValidationError> {
     let body = io::read_request_body(request)?;
     let incoming_headers = super::IncomingHeaderSnapshot::from_request(request);
-    let platform_key = io::extract_platform_key_or_error(request, &incoming_headers, debug)?;
-
-    let storage = auth::open_storage_or_error()?;
-    let api_key = auth::load_active_api_key(&storage, &platform_key, request.url(), debug)?;
+    // 中文注释：后台批处理执行器回环调用时不持有明文 Key，凭进程内令牌按 key id 鉴权。
+    let batch_key_id = super::batch_item_key_id(request);
+    let storage;
+    let api_key = match batch_key_id.as_deref() {
+        Some(key_id) => {
+            storage = auth::open_storage_or_error()?;
+            auth::load_active_api_key_by_id(&storage, key_id)?
+        }
+        None => {
+            let platform_key =
+                io::extract_platform_key_or_error(request, &incoming_headers, debug)?;
+            storage = auth::open_storage_or_error()?;
+            auth::load_active_api_key(&storage, &platform_key, request.url(), debug)?
+        }
+    };
+    // 中文注释：预算与限流都放在请求改写之前，被拒绝的请求不再消耗协议转换与上游资源。
+    crate::apikey_budget::check_key_budget_before_dispatch(&storage, &api_key.id).map_err(
+        |status| {
+            LocalValidationError::budget_exceeded(&api_key.id, &api_key.protocol_type, &status)
+        },
+    )?;
+    let rate_limit_permit =
+        super::acquire_key_rate_limit(&api_key.id, super::KeyRateLimits::from_api_key(&api_key))
+            .map_err(|rejection| {
+                LocalValidationError::rate_limited(&api_key.id, &api_key.protocol_type, &rejection)
+            })?;
 
     request::build_local_validation_result(
         request,
@@ -61,5 +150,7 @@ pub(super) fn prepare_local_request(
         storage,
         body,
         api_key,
+        rate_limit_permit,
+        batch_key_id.is_some(),
     )
 }
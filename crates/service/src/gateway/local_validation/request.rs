@@ -1,4 +1,4 @@
-use crate::apikey_profile::PROTOCOL_ANTHROPIC_NATIVE;
+use crate::apikey_profile::{PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_AZURE_OPENAI};
 use bytes::Bytes;
 use codexmanager_core::storage::ApiKey;
 use reqwest::Method;
@@ -22,12 +22,60 @@ fn resolve_effective_request_overrides(api_key: &ApiKey) -> (Option<String>, Opt
     (normalized_model, normalized_reasoning)
 }
 
+fn requested_model_from_request(path: &str, body: &[u8]) -> Option<String> {
+    super::super::parse_request_metadata(body)
+        .model
+        .or_else(|| super::super::parse_gemini_generate_content_path(path).map(|(model, _)| model))
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty())
+}
+
+/// 把别名改写落到请求上：OpenAI / Anthropic 改写请求体的 model，Gemini 原生协议改写路径中的模型段。
+fn apply_model_alias_to_request(
+    path: String,
+    body: Vec<u8>,
+    rewrite: &crate::model_alias_rules::ModelAliasRewrite,
+) -> (String, Vec<u8>) {
+    let requested = rewrite.requested_model.as_str();
+    let target = rewrite.target_model.as_str();
+    let path = match super::super::parse_gemini_generate_content_path(&path) {
+        Some((model, _)) if model == requested => path.replacen(
+            &format!("/models/{model}:"),
+            &format!("/models/{target}:"),
+            1,
+        ),
+        _ => path,
+    };
+    let Ok(mut payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return (path, body);
+    };
+    let Some(model) = payload.get_mut("model") else {
+        return (path, body);
+    };
+    if model.as_str().map(str::trim) != Some(requested) {
+        return (path, body);
+    }
+    *model = serde_json::Value::String(target.to_string());
+    match serde_json::to_vec(&payload) {
+        Ok(rewritten) => (path, rewritten),
+        Err(_) => (path, body),
+    }
+}
+
 fn allow_openai_responses_path_rewrite(protocol_type: &str, normalized_path: &str) -> bool {
+    if protocol_type == crate::apikey_profile::PROTOCOL_GEMINI_NATIVE {
+        return super::super::parse_gemini_generate_content_path(normalized_path).is_some();
+    }
     protocol_type == crate::apikey_profile::PROTOCOL_OPENAI_COMPAT
         && (normalized_path.starts_with("/v1/chat/completions")
-            || normalized_path.starts_with("/v1/completions"))
+            || normalized_path.starts_with("/v1/completions")
+            || matches!(
+                super::super::parse_ollama_compat_path(normalized_path),
+                Some(super::super::OllamaEndpoint::Chat | super::super::OllamaEndpoint::Generate)
+            ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn build_local_validation_result(
     request: &Request,
     trace_id: String,
@@ -35,16 +83,53 @@ pub(super) fn build_local_validation_result(
     storage: crate::storage_helpers::StorageHandle,
     mut body: Vec<u8>,
     api_key: ApiKey,
+    rate_limit_permit: super::super::KeyRateLimitPermit,
+    batch_item: bool,
 ) -> Result<LocalValidationResult, LocalValidationError> {
     // 按当前策略取消每次请求都更新 api_keys.last_used_at，减少并发写入冲突。
-    let normalized_path = super::super::normalize_models_path(request.url());
+    let mut normalized_path = super::super::normalize_models_path(request.url());
+    let requested_model = requested_model_from_request(&normalized_path, &body);
+    // 中文注释：别名改写要先于协议适配，保证后续适配、路由与计费都基于真实模型。
+    let alias_rewrite = requested_model.as_deref().and_then(|model| {
+        crate::model_alias_rules::resolve_request_model_alias(&storage, &api_key.id, model)
+    });
+    if let Some(rewrite) = alias_rewrite.as_ref() {
+        (normalized_path, body) = apply_model_alias_to_request(normalized_path, body, rewrite);
+    }
     let original_body = body.clone();
-    let adapted = super::super::adapt_request_for_protocol(
+    let anthropic_passthrough = crate::apikey_profile::is_anthropic_passthrough(
         api_key.protocol_type.as_str(),
-        &normalized_path,
-        body,
-    )
-    .map_err(|err| LocalValidationError::new(400, err))?;
+        api_key.upstream_base_url.as_deref(),
+    );
+    // 中文注释：embeddings 只能由 OpenAI API 或服务商承接，这里先校验请求体并按批次拆好，
+    // proxy 据此走独立的 embeddings 路由；Azure 与 Anthropic 直连仍按各自协议原样转发。
+    let embeddings = if !anthropic_passthrough
+        && api_key.protocol_type != PROTOCOL_AZURE_OPENAI
+        && request.method() == &tiny_http::Method::Post
+        && super::super::is_embeddings_path(&normalized_path)
+    {
+        let parsed = super::super::split_embeddings_request(&body)
+            .map_err(|err| LocalValidationError::new(400, err))?;
+        Some(parsed)
+    } else {
+        None
+    };
+    let adapted = if anthropic_passthrough {
+        // 中文注释：直连 Anthropic 上游时请求体与路径都原样保留，响应也不做转换。
+        super::super::AdaptedGatewayRequest {
+            path: normalized_path.clone(),
+            body,
+            response_adapter: super::super::ResponseAdapter::Passthrough,
+            tool_name_restore_map: Default::default(),
+        }
+    } else {
+        super::super::adapt_request_for_protocol(
+            api_key.protocol_type.as_str(),
+            &normalized_path,
+            body,
+        )
+        .map_err(|err| LocalValidationError::new(400, err))?
+    };
     let mut path = adapted.path;
     let mut response_adapter = adapted.response_adapter;
     let mut tool_name_restore_map = adapted.tool_name_restore_map;
@@ -70,14 +155,23 @@ pub(super) fn build_local_validation_result(
     // 中文注释：下游调用方的 stream 语义应在请求改写前确定；
     // 否则上游兼容改写（例如 /responses 强制 stream=true）会污染下游响应模式判断。
     let client_request_meta = super::super::parse_request_metadata(&body);
-    let (effective_model, effective_reasoning) = resolve_effective_request_overrides(&api_key);
-    body = super::super::apply_request_overrides(
-        &path,
-        body,
-        effective_model.as_deref(),
-        effective_reasoning.as_deref(),
-        api_key.upstream_base_url.as_deref(),
-    );
+    let (effective_model, mut effective_reasoning) = resolve_effective_request_overrides(&api_key);
+    // 中文注释：Key 上显式配置的推理等级优先，别名规则附带的等级只作为补充。
+    if effective_reasoning.is_none() {
+        effective_reasoning = alias_rewrite
+            .as_ref()
+            .and_then(|rewrite| rewrite.reasoning_effort.clone());
+    }
+    // 中文注释：Key 上配置的模型与推理等级面向对话模型，不能套到 embeddings 请求上。
+    if !anthropic_passthrough && embeddings.is_none() {
+        body = super::super::apply_request_overrides(
+            &path,
+            body,
+            effective_model.as_deref(),
+            effective_reasoning.as_deref(),
+            api_key.upstream_base_url.as_deref(),
+        );
+    }
 
     let request_method = request.method().as_str().to_string();
     let method = Method::from_bytes(request_method.as_bytes())
@@ -109,9 +203,20 @@ pub(super) fn build_local_validation_result(
         tool_name_restore_map,
         request_method,
         key_id: api_key.id,
+        account_groups: crate::apikey_profile::parse_account_groups(
+            api_key.account_group.as_deref(),
+        ),
+        hedge_delay_ms: api_key
+            .hedge_delay_ms
+            .filter(|value| *value > 0)
+            .map(|value| value as u64),
+        rate_limit_permit,
         model_for_log,
+        requested_model_for_log: requested_model,
         reasoning_for_log,
         method,
+        embeddings,
+        batch_item,
     })
 }
 
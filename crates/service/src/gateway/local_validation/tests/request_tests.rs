@@ -15,6 +15,11 @@ fn sample_api_key(
         auth_scheme: "authorization_bearer".to_string(),
         upstream_base_url: None,
         static_headers_json: None,
+        account_group: None,
+        rpm_limit: None,
+        tpm_limit: None,
+        max_concurrency: None,
+        hedge_delay_ms: None,
         key_hash: "hash".to_string(),
         status: "active".to_string(),
         created_at: 0,
@@ -49,3 +54,46 @@ fn openai_key_keeps_empty_overrides() {
     assert_eq!(model, None);
     assert_eq!(reasoning, None);
 }
+
+fn alias_rewrite(requested: &str, target: &str) -> crate::model_alias_rules::ModelAliasRewrite {
+    crate::model_alias_rules::ModelAliasRewrite {
+        requested_model: requested.to_string(),
+        target_model: target.to_string(),
+        reasoning_effort: None,
+    }
+}
+
+#[test]
+fn model_alias_rewrites_body_model_field() {
+    let body = br#"{"model":"team-fast","input":"hi"}"#.to_vec();
+    assert_eq!(
+        requested_model_from_request("/v1/responses", &body).as_deref(),
+        Some("team-fast")
+    );
+    let (path, body) = apply_model_alias_to_request(
+        "/v1/responses".to_string(),
+        body,
+        &alias_rewrite("team-fast", "gpt-5.1-codex-mini"),
+    );
+    let payload: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+    assert_eq!(path, "/v1/responses");
+    assert_eq!(payload["model"], "gpt-5.1-codex-mini");
+    assert_eq!(payload["input"], "hi");
+}
+
+#[test]
+fn model_alias_rewrites_gemini_path_model() {
+    let path = "/v1beta/models/gemini-fast:streamGenerateContent?alt=sse";
+    let body = br#"{"contents":[]}"#.to_vec();
+    assert_eq!(
+        requested_model_from_request(path, &body).as_deref(),
+        Some("gemini-fast")
+    );
+    let (path, body) = apply_model_alias_to_request(
+        path.to_string(),
+        body.clone(),
+        &alias_rewrite("gemini-fast", "gpt-5.1"),
+    );
+    assert_eq!(path, "/v1beta/models/gpt-5.1:streamGenerateContent?alt=sse");
+    assert_eq!(body, br#"{"contents":[]}"#.to_vec());
+}
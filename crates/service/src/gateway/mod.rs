@@ -1,7 +1,16 @@
 use crate::storage_helpers::open_storage;
 
+#[path = "routing/admission_queue.rs"]
+mod admission_queue;
+mod batch;
+#[path = "request/client_cancel.rs"]
+mod client_cancel;
 #[path = "routing/cooldown.rs"]
 mod cooldown;
+#[path = "routing/cooldown_policy.rs"]
+mod cooldown_policy;
+#[path = "request/embeddings.rs"]
+mod embeddings;
 mod error_response;
 #[path = "routing/failover.rs"]
 mod failover;
@@ -9,6 +18,8 @@ mod failover;
 mod http_bridge;
 #[path = "request/incoming_headers.rs"]
 mod incoming_headers;
+#[path = "routing/key_rate_limit.rs"]
+mod key_rate_limit;
 #[path = "request/local_count_tokens.rs"]
 mod local_count_tokens;
 #[path = "request/local_models.rs"]
@@ -16,6 +27,8 @@ mod local_models;
 mod local_validation;
 #[path = "observability/metrics.rs"]
 mod metrics;
+#[path = "routing/model_entitlements.rs"]
+mod model_entitlements;
 mod model_picker;
 #[path = "auth/openai_fallback.rs"]
 mod openai_fallback;
@@ -30,6 +43,8 @@ mod request_helpers;
 mod request_log;
 #[path = "request/request_rewrite.rs"]
 mod request_rewrite;
+#[path = "routing/route_affinity.rs"]
+mod route_affinity;
 #[path = "routing/route_hint.rs"]
 mod route_hint;
 #[path = "routing/route_quality.rs"]
@@ -38,16 +53,31 @@ mod route_quality;
 mod runtime_config;
 #[path = "routing/selection.rs"]
 mod selection;
+#[path = "routing/state_flush.rs"]
+mod state_flush;
+#[path = "request/token_counter.rs"]
+mod token_counter;
 #[path = "auth/token_exchange.rs"]
 mod token_exchange;
 #[path = "observability/trace_log.rs"]
 mod trace_log;
 mod upstream;
 
+use admission_queue::{
+    admission_queue_max_wait, admission_retry_after_secs, notify_admission_capacity_changed,
+    wait_for_admission, AdmissionOutcome,
+};
+pub(crate) use batch::ensure_batch_runner;
+use batch::{
+    batch_item_key_id, maybe_respond_batch_api, retain_batch_candidates, BATCH_DEFERRED_MESSAGE,
+};
+use client_cancel::{client_cancel_token_for_request, ClientCancelToken};
+pub(crate) use client_cancel::{register_client_request, CLIENT_REQUEST_ID_HEADER_NAME};
 use metrics::{
     account_inflight_count, acquire_account_inflight, begin_gateway_request,
-    record_gateway_cooldown_mark, record_gateway_failover_attempt, record_gateway_request_outcome,
-    AccountInFlightGuard,
+    record_admission_queue_dequeue, record_admission_queue_enqueue,
+    record_admission_queue_rejected, record_gateway_cooldown_mark, record_gateway_failover_attempt,
+    record_gateway_request_outcome, AccountInFlightGuard,
 };
 pub(crate) use metrics::{
     begin_rpc_request, duration_to_millis, gateway_metrics_prometheus, record_usage_refresh_outcome,
@@ -55,8 +85,11 @@ pub(crate) use metrics::{
 use protocol_adapter::{
     adapt_request_for_protocol, adapt_upstream_response,
     adapt_upstream_response_with_tool_name_restore_map, build_anthropic_error_body,
-    convert_openai_chat_stream_chunk_with_tool_name_restore_map,
-    convert_openai_completions_stream_chunk, ResponseAdapter, ToolNameRestoreMap,
+    build_gemini_error_body, build_ollama_error_body, convert_gemini_stream_chunk,
+    convert_ollama_stream_chunk, convert_openai_chat_stream_chunk_with_tool_name_restore_map,
+    convert_openai_completions_stream_chunk, parse_gemini_generate_content_path,
+    parse_ollama_compat_path, strip_ollama_model_tag, AdaptedGatewayRequest, OllamaEndpoint,
+    ResponseAdapter, ToolNameRestoreMap,
 };
 pub(super) use request_helpers::{
     is_html_content_type, is_upstream_challenge_response, normalize_models_path,
@@ -93,50 +126,98 @@ pub(crate) fn record_http_queue_enqueue_failure() {
 #[cfg(test)]
 use cooldown::cooldown_reason_for_status;
 use cooldown::{
-    clear_account_cooldown, is_account_in_cooldown, mark_account_cooldown,
-    mark_account_cooldown_for_status, CooldownReason,
+    account_cooldown_reason, clear_account_cooldown, is_account_in_cooldown, mark_account_cooldown,
+    mark_account_cooldown_for_status, mark_account_cooldown_with_hint, CooldownReason,
+};
+pub(crate) use cooldown::{clear_account_cooldowns, list_account_cooldowns};
+use cooldown_policy::rate_limit_cooldown_hint_secs;
+pub(crate) use cooldown_policy::{
+    current_cooldown_policy, set_cooldown_policy, CooldownPolicyPatch, RATE_LIMIT_SOURCE_OPTIONS,
+};
+use embeddings::{
+    embeddings_usage, is_embeddings_path, merge_embeddings_responses, split_embeddings_request,
+    EmbeddingsRequest,
 };
 #[cfg(test)]
 pub(super) use failover::should_failover_after_refresh;
 use failover::should_failover_from_cached_snapshot;
 use http_bridge::respond_with_upstream;
 pub(super) use incoming_headers::IncomingHeaderSnapshot;
+use key_rate_limit::{
+    acquire_key_rate_limit, charge_key_tokens, KeyRateLimitPermit, KeyRateLimitRejection,
+    KeyRateLimits,
+};
 use local_count_tokens::maybe_respond_local_count_tokens;
 use local_models::maybe_respond_local_models;
+pub(crate) use model_entitlements::{
+    clear_account_model_exclusions, list_account_model_exclusions,
+};
+use model_entitlements::{
+    is_account_model_excluded, mark_account_model_unavailable, may_carry_model_unavailable_error,
+    model_unavailable_kind, model_unavailable_reason, record_model_served,
+};
 pub(crate) use model_picker::fetch_models_for_picker;
 use openai_fallback::try_openai_fallback;
 pub(crate) use request_entry::handle_gateway_request;
 use request_gate::{request_gate_lock, RequestGateAcquireError};
 use request_log::write_request_log;
 use route_hint::apply_route_strategy;
-use route_quality::record_route_quality;
+use route_quality::{record_route_latency, record_route_quality, RouteLatencyKind};
 pub(crate) use runtime_config::front_proxy_max_body_bytes;
 use runtime_config::{
     account_max_inflight_limit, fresh_upstream_client, fresh_upstream_client_for_account,
     request_gate_wait_timeout, trace_body_preview_max_bytes, upstream_client,
-    upstream_client_for_account, upstream_cookie, upstream_stream_timeout, upstream_total_timeout,
-    DEFAULT_GATEWAY_DEBUG, DEFAULT_MODELS_CLIENT_VERSION,
+    upstream_client_for_account, upstream_cookie, upstream_proxy_for_account,
+    upstream_stream_timeout, upstream_total_timeout, DEFAULT_GATEWAY_DEBUG,
+    DEFAULT_MODELS_CLIENT_VERSION,
 };
-use selection::collect_gateway_candidates;
+use selection::{collect_gateway_candidates, filter_candidates_by_account_groups};
+use state_flush::{ensure_routing_state_flusher, routing_state_flush_guard};
 #[cfg(test)]
 use token_exchange::account_token_exchange_lock;
 use token_exchange::resolve_openai_bearer_token;
 use upstream::candidates::prepare_gateway_candidates;
 use upstream::proxy::proxy_validated_request;
+use upstream::stream_failover::StreamFailover;
 
 pub(crate) fn reload_runtime_config_from_env() {
     runtime_config::reload_from_env();
     selection::reload_from_env();
     request_gate::clear_runtime_state();
+    admission_queue::reload_from_env();
+    key_rate_limit::clear_runtime_state();
+    cooldown_policy::reload_from_env();
     cooldown::clear_runtime_state();
+    model_entitlements::clear_runtime_state();
     route_quality::clear_runtime_state();
+    route_affinity::reload_from_env();
     route_hint::reload_from_env();
     upstream::config::reload_from_env();
+    upstream::stream_failover::reload_from_env();
+    embeddings::reload_from_env();
+    batch::reload_from_env();
     trace_log::reload_from_env();
     http_bridge::reload_from_env();
     protocol_adapter::reload_env_dependent_state();
 }
 
+/// 启动时在请求到来前从库里恢复冷却、线路质量、模型排除与会话粘性，并启动后台落库线程。
+pub(crate) fn load_persisted_routing_state() {
+    cooldown::load_persisted_cooldowns();
+    route_quality::load_persisted_route_quality();
+    model_entitlements::load_persisted_model_exclusions();
+    route_affinity::load_persisted_route_affinities();
+    ensure_routing_state_flusher();
+}
+
+pub(crate) fn flush_routing_state() {
+    state_flush::flush_routing_state();
+}
+
+pub(crate) fn warm_up_token_counter() {
+    token_counter::warm_up_tokenizer();
+}
+
 pub(crate) fn current_route_strategy() -> &'static str {
     route_hint::current_route_strategy()
 }
@@ -147,6 +228,10 @@ pub(crate) fn set_route_strategy(strategy: &str) -> Result<&'static str, String>
     Ok(applied)
 }
 
+pub(crate) fn invalidate_candidate_cache() {
+    selection::clear_candidate_cache();
+}
+
 pub(crate) fn cpa_no_cookie_header_mode_enabled() -> bool {
     runtime_config::cpa_no_cookie_header_mode_enabled()
 }
@@ -186,7 +271,9 @@ pub(crate) fn set_manual_preferred_account(account_id: &str) -> Result<(), Strin
     }
     let storage = open_storage().ok_or_else(|| "storage not initialized".to_string())?;
     let candidates = collect_gateway_candidates(&storage)?;
-    let found = candidates.iter().any(|(account, _)| account.id == id);
+    let found = candidates
+        .iter()
+        .any(|(account, _)| account.id == id && !account.is_provider_account());
     if !found {
         return Err("account is not available for routing".to_string());
     }
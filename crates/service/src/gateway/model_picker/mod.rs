@@ -9,7 +9,11 @@ use request::send_models_request;
 
 pub(crate) fn fetch_models_for_picker() -> Result<Vec<ModelOption>, String> {
     let storage = super::open_storage().ok_or_else(|| "storage unavailable".to_string())?;
-    let mut candidates = super::collect_gateway_candidates(&storage)?;
+    // 中文注释：模型列表只向 Codex 上游拉取，服务商账号的 Key 不能发给 Codex。
+    let mut candidates = super::collect_gateway_candidates(&storage)?
+        .into_iter()
+        .filter(|(account, _)| !account.is_provider_account())
+        .collect::<Vec<_>>();
     if candidates.is_empty() {
         return Err("no available account".to_string());
     }
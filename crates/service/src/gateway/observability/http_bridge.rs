@@ -1,11 +1,15 @@
+use codexmanager_core::storage::now_ts;
 use serde_json::{json, Map, Value};
 use std::collections::BTreeMap;
-use std::io::{BufRead, BufReader, Cursor, Read};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex, OnceLock};
-use tiny_http::{Header, Request, Response, StatusCode};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{HTTPVersion, Header, Method, Request, Response, StatusCode};
 
-use super::AccountInFlightGuard;
+use super::{AccountInFlightGuard, ClientCancelToken, StreamFailover};
 
 // Env:
 // - CODEXMANAGER_HTTP_BRIDGE_OUTPUT_TEXT_LIMIT_BYTES (default: 131072; 0 disables limit)
@@ -16,6 +20,340 @@ const OUTPUT_TEXT_TRUNCATED_MARKER: &str = "[output_text truncated]";
 static OUTPUT_TEXT_LIMIT_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_OUTPUT_TEXT_LIMIT_BYTES);
 static OUTPUT_TEXT_LIMIT_LOADED: OnceLock<()> = OnceLock::new();
 
+// Env:
+// - CODEXMANAGER_SSE_HEARTBEAT_INTERVAL_SECS (default: 0 = disabled)
+// Emits keepalives while the upstream stream is idle (e.g. long reasoning pauses before the first token),
+// so clients and proxies with idle timeouts keep the connection open. Opt-in: enabling it moves streaming
+// responses onto a background upstream pump and a frame-by-frame chunked writer.
+const SSE_HEARTBEAT_INTERVAL_SECS_ENV: &str = "CODEXMANAGER_SSE_HEARTBEAT_INTERVAL_SECS";
+const DEFAULT_SSE_HEARTBEAT_INTERVAL_SECS: u64 = 0;
+static SSE_HEARTBEAT_INTERVAL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_SSE_HEARTBEAT_INTERVAL_SECS);
+const OPENAI_SSE_HEARTBEAT_FRAME: &[u8] = b": keepalive\n\n";
+
+const CLIENT_CANCELLED_MESSAGE: &str = "client_cancelled";
+// 中文注释：读上游时检查客户端取消令牌的间隔；上游长时间无输出时也能及时发现客户端已离开。
+const CLIENT_CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const UPSTREAM_PUMP_CHUNK_BYTES: usize = 16 * 1024;
+const UPSTREAM_PUMP_QUEUE_CHUNKS: usize = 8;
+
+#[derive(Debug)]
+struct ClientCancelledError;
+
+impl std::fmt::Display for ClientCancelledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(CLIENT_CANCELLED_MESSAGE)
+    }
+}
+
+impl std::error::Error for ClientCancelledError {}
+
+fn client_cancelled_error() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::ConnectionAborted, ClientCancelledError)
+}
+
+fn is_client_cancelled_error(err: &std::io::Error) -> bool {
+    err.get_ref()
+        .is_some_and(|inner| inner.is::<ClientCancelledError>())
+}
+
+#[derive(Debug)]
+struct UpstreamIdleError;
+
+impl std::fmt::Display for UpstreamIdleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("upstream idle")
+    }
+}
+
+impl std::error::Error for UpstreamIdleError {}
+
+fn upstream_idle_error() -> std::io::Error {
+    std::io::Error::other(UpstreamIdleError)
+}
+
+fn is_upstream_idle_error(err: &std::io::Error) -> bool {
+    err.get_ref()
+        .is_some_and(|inner| inner.is::<UpstreamIdleError>())
+}
+
+fn sse_heartbeat_interval() -> Option<Duration> {
+    let _ = OUTPUT_TEXT_LIMIT_LOADED.get_or_init(|| {
+        reload_from_env();
+    });
+    match SSE_HEARTBEAT_INTERVAL_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// 读取一行上游 SSE。上游空闲超过心跳间隔时返回 `None`，由调用方下发协议对应的心跳；
+/// 只有行缓冲为空且调用方处于可插入心跳的位置时才返回，否则继续等待，半行数据不会丢失。
+fn read_sse_line(
+    upstream: &mut BufReader<UpstreamBody>,
+    line: &mut String,
+    heartbeat_allowed: bool,
+) -> std::io::Result<Option<usize>> {
+    // 中文注释：按字节读到换行再整体转 UTF-8；`read_line` 在空闲超时打断时会丢掉已读出的半个多字节字符。
+    let mut bytes = Vec::new();
+    loop {
+        match upstream.read_until(b'\n', &mut bytes) {
+            Ok(_) => break,
+            Err(err) if is_upstream_idle_error(&err) => {
+                if heartbeat_allowed && bytes.is_empty() && line.is_empty() {
+                    return Ok(None);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    let text = String::from_utf8(bytes).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })?;
+    line.push_str(&text);
+    Ok(Some(line.len()))
+}
+
+/// 流式响应读取的上游响应体。带客户端取消令牌或开启心跳时改由后台线程读取上游，读端按固定间隔
+/// 检查令牌与空闲时长：客户端离开后立即停止转发，后台线程在当前这次读取返回后丢弃上游响应，连接随之关闭；
+/// 上游空闲超过心跳间隔时返回 [`UpstreamIdleError`]，由各 SSE reader 插入心跳。
+enum UpstreamBody {
+    Direct(reqwest::blocking::Response),
+    Watched(WatchedUpstream),
+}
+
+impl UpstreamBody {
+    fn new(
+        upstream: reqwest::blocking::Response,
+        client_cancel: Option<ClientCancelToken>,
+    ) -> Self {
+        let heartbeat_interval = sse_heartbeat_interval();
+        if client_cancel.is_none() && heartbeat_interval.is_none() {
+            return UpstreamBody::Direct(upstream);
+        }
+        UpstreamBody::Watched(WatchedUpstream::spawn(
+            upstream,
+            client_cancel,
+            heartbeat_interval,
+        ))
+    }
+
+    fn client_cancel(&self) -> Option<ClientCancelToken> {
+        match self {
+            UpstreamBody::Direct(_) => None,
+            UpstreamBody::Watched(watched) => watched.token.clone(),
+        }
+    }
+}
+
+impl From<reqwest::blocking::Response> for UpstreamBody {
+    fn from(upstream: reqwest::blocking::Response) -> Self {
+        UpstreamBody::Direct(upstream)
+    }
+}
+
+impl Read for UpstreamBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            UpstreamBody::Direct(upstream) => upstream.read(buf),
+            UpstreamBody::Watched(watched) => watched.read(buf),
+        }
+    }
+}
+
+struct WatchedUpstream {
+    chunks: mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    pending: Cursor<Vec<u8>>,
+    token: Option<ClientCancelToken>,
+    heartbeat_interval: Option<Duration>,
+    idle_since: Instant,
+    finished: bool,
+}
+
+impl WatchedUpstream {
+    fn spawn<R: Read + Send + 'static>(
+        mut upstream: R,
+        token: Option<ClientCancelToken>,
+        heartbeat_interval: Option<Duration>,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel(UPSTREAM_PUMP_QUEUE_CHUNKS);
+        let _ = thread::Builder::new()
+            .name("gateway-upstream-pump".to_string())
+            .spawn(move || loop {
+                let mut chunk = vec![0u8; UPSTREAM_PUMP_CHUNK_BYTES];
+                match upstream.read(&mut chunk) {
+                    Ok(read) => {
+                        chunk.truncate(read);
+                        // 中文注释：读端已放弃（客户端取消）时发送失败，上游响应在此丢弃。
+                        if tx.send(Ok(chunk)).is_err() || read == 0 {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err));
+                        break;
+                    }
+                }
+            });
+        Self {
+            chunks: rx,
+            pending: Cursor::new(Vec::new()),
+            token,
+            heartbeat_interval,
+            idle_since: Instant::now(),
+            finished: false,
+        }
+    }
+}
+
+impl Read for WatchedUpstream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.pending.read(buf)?;
+            if read > 0 || self.finished {
+                return Ok(read);
+            }
+            if self
+                .token
+                .as_ref()
+                .is_some_and(ClientCancelToken::is_cancelled)
+            {
+                return Err(client_cancelled_error());
+            }
+            if self
+                .heartbeat_interval
+                .is_some_and(|interval| self.idle_since.elapsed() >= interval)
+            {
+                self.idle_since = Instant::now();
+                return Err(upstream_idle_error());
+            }
+            let wait = self
+                .heartbeat_interval
+                .map_or(CLIENT_CANCEL_POLL_INTERVAL, |interval| {
+                    interval
+                        .saturating_sub(self.idle_since.elapsed())
+                        .min(CLIENT_CANCEL_POLL_INTERVAL)
+                });
+            match self.chunks.recv_timeout(wait) {
+                Ok(Ok(chunk)) if chunk.is_empty() => self.finished = true,
+                Ok(Ok(chunk)) => {
+                    self.idle_since = Instant::now();
+                    self.pending = Cursor::new(chunk);
+                }
+                Ok(Err(err)) => {
+                    self.finished = true;
+                    return Err(err);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => self.finished = true,
+            }
+        }
+    }
+}
+
+/// 记录响应体是否读到结尾。tiny_http 会吞掉客户端断开导致的写错误（respond 仍返回 Ok），
+/// 响应体没读完就结束下发，说明客户端已经离开。
+struct DeliveryTrackedReader<R> {
+    inner: R,
+    body_ended: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for DeliveryTrackedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.inner.read(buf) {
+            Ok(0) if !buf.is_empty() => {
+                self.body_ended.store(true, Ordering::Relaxed);
+                Ok(0)
+            }
+            Ok(read) => Ok(read),
+            Err(err) => {
+                // 中文注释：上游读错误同样算“读到结尾”，只有客户端取消不算。
+                if !is_client_cancelled_error(&err) {
+                    self.body_ended.store(true, Ordering::Relaxed);
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// 下发流式响应体，返回写回错误与客户端是否在响应体送完前离开。
+fn respond_stream_body<R: Read>(
+    request: Request,
+    status: StatusCode,
+    headers: Vec<Header>,
+    body: R,
+) -> (Option<String>, bool) {
+    let body_ended = Arc::new(AtomicBool::new(false));
+    let body = DeliveryTrackedReader {
+        inner: body,
+        body_ended: Arc::clone(&body_ended),
+    };
+    // 中文注释：只有开启心跳时才改用逐帧 flush 的分块写出，默认保持 tiny_http 原有的下发方式。
+    let delivery_error = if sse_heartbeat_interval().is_some()
+        && *request.http_version() >= HTTPVersion(1, 1)
+        && *request.method() != Method::Head
+    {
+        write_chunked_stream(request, status, headers, body)
+            .err()
+            .filter(|err| !is_client_closing_error(err))
+            .map(|err| err.to_string())
+    } else {
+        let response = Response::new(status, headers, body, None, None);
+        request.respond(response).err().map(|err| err.to_string())
+    };
+    (delivery_error, !body_ended.load(Ordering::Relaxed))
+}
+
+/// tiny_http 的分块编码会攒满 8KB 才下发且中途不 flush，SSE 事件与心跳会被憋住；
+/// 这里自己写响应头并逐块 flush，每个 reader 产出的帧都能立即送到客户端。
+fn write_chunked_stream<R: Read>(
+    request: Request,
+    status: StatusCode,
+    headers: Vec<Header>,
+    mut body: R,
+) -> std::io::Result<()> {
+    let mut writer = request.into_writer();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.0,
+        status.default_reason_phrase()
+    );
+    for header in headers.iter().filter(|header| {
+        !header.field.equiv("Content-Length") && !header.field.equiv("Transfer-Encoding")
+    }) {
+        head.push_str(&format!("{header}\r\n"));
+    }
+    head.push_str("Transfer-Encoding: chunked\r\n\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.flush()?;
+    let mut buf = vec![0u8; UPSTREAM_PUMP_CHUNK_BYTES];
+    loop {
+        let read = body.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        write!(writer, "{read:x}\r\n")?;
+        writer.write_all(&buf[..read])?;
+        writer.write_all(b"\r\n")?;
+        writer.flush()?;
+    }
+    writer.write_all(b"0\r\n\r\n")?;
+    writer.flush()
+}
+
+fn is_client_closing_error(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionRefused
+            | std::io::ErrorKind::ConnectionReset
+    )
+}
+
 fn push_trace_id_header(headers: &mut Vec<Header>, trace_id: &str) {
     let Some(trace_id) = Some(trace_id)
         .map(str::trim)
@@ -53,11 +391,15 @@ pub(super) struct UpstreamResponseBridgeResult {
     pub delivery_error: Option<String>,
     // Optional upstream error hint parsed from non-stream error bodies.
     pub upstream_error_hint: Option<String>,
+    // Quota reset time carried by non-stream 429 bodies (`resets_at` / `resets_in_seconds`).
+    pub usage_limit_resets_at: Option<i64>,
+    // The downstream client went away before the stream body was fully delivered.
+    pub client_cancelled: bool,
 }
 
 impl UpstreamResponseBridgeResult {
     pub(super) fn is_ok(&self, is_stream: bool) -> bool {
-        if self.delivery_error.is_some() {
+        if self.delivery_error.is_some() || self.client_cancelled {
             return false;
         }
         if is_stream {
@@ -72,6 +414,9 @@ impl UpstreamResponseBridgeResult {
     }
 
     pub(super) fn error_message(&self, is_stream: bool) -> Option<String> {
+        if self.client_cancelled {
+            return Some(CLIENT_CANCELLED_MESSAGE.to_string());
+        }
         if let Some(err) = self.stream_terminal_error.as_ref() {
             return Some(err.clone());
         }
@@ -123,6 +468,19 @@ fn parse_usage_from_object(usage: Option<&Map<String, Value>>) -> UpstreamRespon
     let input_tokens = usage
         .and_then(|map| map.get("input_tokens").and_then(Value::as_i64))
         .or_else(|| usage.and_then(|map| map.get("prompt_tokens").and_then(Value::as_i64)));
+    // Anthropic: input_tokens excludes cache reads/writes; fold them back in so input/cached
+    // follow the OpenAI convention (cached is a subset of input).
+    let anthropic_cache_read =
+        usage.and_then(|map| map.get("cache_read_input_tokens").and_then(Value::as_i64));
+    let anthropic_cache_creation = usage.and_then(|map| {
+        map.get("cache_creation_input_tokens")
+            .and_then(Value::as_i64)
+    });
+    let input_tokens = input_tokens.map(|tokens| {
+        tokens
+            .saturating_add(anthropic_cache_read.unwrap_or(0).max(0))
+            .saturating_add(anthropic_cache_creation.unwrap_or(0).max(0))
+    });
     let output_tokens = usage
         .and_then(|map| map.get("output_tokens").and_then(Value::as_i64))
         .or_else(|| usage.and_then(|map| map.get("completion_tokens").and_then(Value::as_i64)));
@@ -138,7 +496,8 @@ fn parse_usage_from_object(usage: Option<&Map<String, Value>>) -> UpstreamRespon
                 .and_then(Value::as_object)
                 .and_then(|details| details.get("cached_tokens"))
                 .and_then(Value::as_i64)
-        });
+        })
+        .or(anthropic_cache_read);
     let reasoning_output_tokens = usage
         .and_then(|map| map.get("output_tokens_details"))
         .and_then(Value::as_object)
@@ -264,6 +623,11 @@ pub(super) fn reload_from_env() {
         .parse::<usize>()
         .unwrap_or(DEFAULT_OUTPUT_TEXT_LIMIT_BYTES);
     OUTPUT_TEXT_LIMIT_BYTES.store(limit, Ordering::Relaxed);
+    let heartbeat_secs = std::env::var(SSE_HEARTBEAT_INTERVAL_SECS_ENV)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SSE_HEARTBEAT_INTERVAL_SECS);
+    SSE_HEARTBEAT_INTERVAL_SECS.store(heartbeat_secs, Ordering::Relaxed);
 }
 
 fn truncate_str_to_bytes(text: &str, max_bytes: usize) -> &str {
@@ -353,6 +717,12 @@ fn extract_output_text_from_json(value: &Value) -> Option<String> {
     if let Some(part) = value.get("part") {
         collect_response_output_text(part, &mut output);
     }
+    // Anthropic Messages: non-stream body is `{ "type": "message", "content": [...] }`.
+    if value.get("type").and_then(Value::as_str) == Some("message") {
+        if let Some(content) = value.get("content") {
+            collect_response_output_text(content, &mut output);
+        }
+    }
     if output.trim().is_empty() {
         None
     } else {
@@ -367,6 +737,12 @@ fn parse_usage_from_json(value: &Value) -> UpstreamResponseUsage {
         .and_then(|response| response.get("usage"))
         .and_then(Value::as_object);
     merge_usage(&mut usage, parse_usage_from_object(response_usage));
+    // Anthropic `message_start` carries the initial usage under message.usage.
+    let message_usage = value
+        .get("message")
+        .and_then(|message| message.get("usage"))
+        .and_then(Value::as_object);
+    merge_usage(&mut usage, parse_usage_from_object(message_usage));
     usage.output_text = extract_output_text_from_json(value);
     usage
 }
@@ -435,6 +811,7 @@ fn classify_terminal_event_name(name: &str) -> Option<SseTerminal> {
         return None;
     }
     if normalized == "done"
+        || normalized == "message_stop"
         || is_response_completed_event_name(normalized.as_str())
         || normalized.ends_with(".completed")
     {
@@ -592,6 +969,10 @@ fn extract_error_hint_from_body(status_code: u16, body: &[u8]) -> Option<String>
         })
 }
 
+fn extract_usage_limit_resets_at(status_code: u16, body: &[u8]) -> Option<i64> {
+    crate::usage_passive::parse_usage_limit_resets_at(status_code, body, now_ts())
+}
+
 fn inspect_sse_frame(lines: &[String]) -> SseFrameInspection {
     let mut inspection = SseFrameInspection::default();
     let mut data_lines = Vec::new();
@@ -671,12 +1052,29 @@ fn inspect_sse_frame(lines: &[String]) -> SseFrameInspection {
                 let target = usage.output_text.get_or_insert_with(String::new);
                 append_output_text(target, delta);
             }
+        } else if let Some(text) = anthropic_text_delta(&value) {
+            let usage = inspection
+                .usage
+                .get_or_insert_with(UpstreamResponseUsage::default);
+            let target = usage.output_text.get_or_insert_with(String::new);
+            append_output_text(target, text);
         }
     }
 
     inspection
 }
 
+/// Anthropic `content_block_delta` 的文本增量（`text_delta`）。
+fn anthropic_text_delta(value: &Value) -> Option<&str> {
+    if value.get("type").and_then(Value::as_str) != Some("content_block_delta") {
+        return None;
+    }
+    value
+        .pointer("/delta/text")
+        .and_then(Value::as_str)
+        .filter(|text| !text.is_empty())
+}
+
 #[derive(Debug, Clone, Default)]
 struct ChatCompletionChoiceSynthesis {
     role: Option<String>,
@@ -1233,6 +1631,7 @@ fn looks_like_sse_payload(body: &[u8]) -> bool {
     saw_sse_prefix
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn respond_with_upstream(
     request: Request,
     upstream: reqwest::blocking::Response,
@@ -1241,7 +1640,14 @@ pub(super) fn respond_with_upstream(
     tool_name_restore_map: Option<&super::ToolNameRestoreMap>,
     is_stream: bool,
     trace_id: Option<&str>,
+    stream_failover: Option<StreamFailover>,
 ) -> Result<UpstreamResponseBridgeResult, String> {
+    // 中文注释：经前置代理转发的流式请求才有取消令牌，客户端离开后读上游的循环会及时退出。
+    let client_cancel = if is_stream {
+        super::client_cancel_token_for_request(&request)
+    } else {
+        None
+    };
     match response_adapter {
         super::ResponseAdapter::Passthrough => {
             let upstream_content_type = upstream
@@ -1289,6 +1695,7 @@ pub(super) fn respond_with_upstream(
                         merge_usage(&mut usage, parse_usage_from_json(&value));
                     }
                     let upstream_error_hint = extract_error_hint_from_body(status.0, &body);
+                    let usage_limit_resets_at = extract_usage_limit_resets_at(status.0, &body);
                     if synthesized_response {
                         headers.retain(|header| {
                             !header
@@ -1314,6 +1721,8 @@ pub(super) fn respond_with_upstream(
                         stream_terminal_error: None,
                         delivery_error,
                         upstream_error_hint,
+                        usage_limit_resets_at,
+                        client_cancelled: false,
                     });
                 }
 
@@ -1347,18 +1756,25 @@ pub(super) fn respond_with_upstream(
                     stream_terminal_error: None,
                     delivery_error,
                     upstream_error_hint,
+                    usage_limit_resets_at: extract_usage_limit_resets_at(
+                        status.0,
+                        upstream_body.as_ref(),
+                    ),
+                    client_cancelled: false,
                 });
             }
             if is_sse || is_stream {
                 let usage_collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
-                let response = Response::new(
+                let (delivery_error, client_cancelled) = respond_stream_body(
+                    request,
                     status,
                     headers,
-                    PassthroughSseUsageReader::new(upstream, Arc::clone(&usage_collector)),
-                    None,
-                    None,
+                    PassthroughSseUsageReader::new(
+                        UpstreamBody::new(upstream, client_cancel),
+                        Arc::clone(&usage_collector),
+                    )
+                    .with_stream_failover(stream_failover),
                 );
-                let delivery_error = request.respond(response).err().map(|err| err.to_string());
                 let collector = usage_collector
                     .lock()
                     .map(|guard| guard.clone())
@@ -1369,6 +1785,8 @@ pub(super) fn respond_with_upstream(
                     stream_terminal_error: collector.terminal_error,
                     delivery_error,
                     upstream_error_hint: None,
+                    usage_limit_resets_at: None,
+                    client_cancelled,
                 });
             }
             let len = upstream.content_length().map(|v| v as usize);
@@ -1380,6 +1798,8 @@ pub(super) fn respond_with_upstream(
                 stream_terminal_error: None,
                 delivery_error,
                 upstream_error_hint: None,
+                usage_limit_resets_at: None,
+                client_cancelled: false,
             })
         }
         super::ResponseAdapter::OpenAIChatCompletionsJson
@@ -1437,29 +1857,27 @@ pub(super) fn respond_with_upstream(
                     headers.push(content_type_header);
                 }
                 let usage_collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
-                let delivery_error =
+                let upstream = UpstreamBody::new(upstream, client_cancel);
+                let (delivery_error, client_cancelled) =
                     if response_adapter == super::ResponseAdapter::OpenAIChatCompletionsSse {
-                        let response = Response::new(
+                        respond_stream_body(
+                            request,
                             status,
                             headers,
                             OpenAIChatCompletionsSseReader::new(
                                 upstream,
                                 Arc::clone(&usage_collector),
                                 tool_name_restore_map.cloned(),
-                            ),
-                            None,
-                            None,
-                        );
-                        request.respond(response).err().map(|err| err.to_string())
+                            )
+                            .with_stream_failover(stream_failover),
+                        )
                     } else {
-                        let response = Response::new(
+                        respond_stream_body(
+                            request,
                             status,
                             headers,
                             OpenAICompletionsSseReader::new(upstream, Arc::clone(&usage_collector)),
-                            None,
-                            None,
-                        );
-                        request.respond(response).err().map(|err| err.to_string())
+                        )
                     };
                 let collector = usage_collector
                     .lock()
@@ -1486,6 +1904,8 @@ pub(super) fn respond_with_upstream(
                     stream_terminal_error: collector.terminal_error,
                     delivery_error,
                     upstream_error_hint: None,
+                    usage_limit_resets_at: None,
+                    client_cancelled,
                 });
             }
 
@@ -1562,9 +1982,14 @@ pub(super) fn respond_with_upstream(
                 stream_terminal_error: None,
                 delivery_error,
                 upstream_error_hint,
+                usage_limit_resets_at: extract_usage_limit_resets_at(
+                    status.0,
+                    upstream_body.as_ref(),
+                ),
+                client_cancelled: false,
             })
         }
-        super::ResponseAdapter::AnthropicJson | super::ResponseAdapter::AnthropicSse => {
+        super::ResponseAdapter::GeminiJson | super::ResponseAdapter::GeminiSse => {
             let status = StatusCode(upstream.status().as_u16());
             let mut headers = Vec::new();
             for (name, value) in upstream.headers().iter() {
@@ -1588,107 +2013,372 @@ pub(super) fn respond_with_upstream(
                 .get(reqwest::header::CONTENT_TYPE)
                 .and_then(|v| v.to_str().ok())
                 .map(|v| v.to_string());
+            let is_sse = upstream_content_type
+                .as_deref()
+                .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+                .unwrap_or(false);
+            // 中文注释：Gemini 入口的上游请求始终是 stream=true，
+            // 下游是否流式只取决于 generateContent / streamGenerateContent。
+            let client_wants_sse = response_adapter == super::ResponseAdapter::GeminiSse;
 
-            if response_adapter == super::ResponseAdapter::AnthropicSse
-                && (is_stream
-                    || upstream_content_type
-                        .as_deref()
-                        .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
-                        .unwrap_or(false))
-            {
+            if client_wants_sse && is_sse && status.0 < 400 {
                 if let Ok(content_type_header) =
                     Header::from_bytes(b"Content-Type".as_slice(), b"text/event-stream".as_slice())
                 {
                     headers.push(content_type_header);
                 }
-                let usage_collector = Arc::new(Mutex::new(UpstreamResponseUsage::default()));
-                let response = Response::new(
+                let usage_collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
+                let (delivery_error, client_cancelled) = respond_stream_body(
+                    request,
                     status,
                     headers,
-                    AnthropicSseReader::new(upstream, Arc::clone(&usage_collector)),
-                    None,
-                    None,
+                    GeminiSseReader::new(
+                        UpstreamBody::new(upstream, client_cancel),
+                        Arc::clone(&usage_collector),
+                    ),
                 );
-                let delivery_error = request.respond(response).err().map(|err| err.to_string());
-                let usage = usage_collector
+                let collector = usage_collector
                     .lock()
                     .map(|guard| guard.clone())
                     .unwrap_or_default();
                 return Ok(UpstreamResponseBridgeResult {
-                    usage,
-                    stream_terminal_seen: true,
-                    stream_terminal_error: None,
+                    usage: collector.usage,
+                    stream_terminal_seen: collector.saw_terminal,
+                    stream_terminal_error: collector.terminal_error,
                     delivery_error,
                     upstream_error_hint: None,
+                    usage_limit_resets_at: None,
+                    client_cancelled,
                 });
             }
 
             let upstream_body = upstream
                 .bytes()
                 .map_err(|err| format!("read upstream body failed: {err}"))?;
-            let usage = serde_json::from_slice::<Value>(upstream_body.as_ref())
-                .ok()
-                .map(|value| parse_usage_from_json(&value))
-                .unwrap_or_default();
-
-            let (body, content_type) = match super::adapt_upstream_response(
-                response_adapter,
-                upstream_content_type.as_deref(),
-                upstream_body.as_ref(),
-            ) {
-                Ok(result) => result,
-                Err(err) => (
-                    super::build_anthropic_error_body(&format!(
-                        "response conversion failed: {err}"
-                    )),
-                    "application/json",
-                ),
+            let mut usage = if is_sse {
+                let (_, parsed) = collect_non_stream_json_from_sse_bytes(upstream_body.as_ref());
+                parsed
+            } else {
+                UpstreamResponseUsage::default()
+            };
+            if let Ok(value) = serde_json::from_slice::<Value>(upstream_body.as_ref()) {
+                merge_usage(&mut usage, parse_usage_from_json(&value));
+            }
+            let upstream_error_hint =
+                extract_error_hint_from_body(status.0, upstream_body.as_ref());
+            let mut body = if status.0 >= 400 {
+                super::build_gemini_error_body(
+                    status.0,
+                    upstream_error_hint
+                        .as_deref()
+                        .unwrap_or("upstream request failed"),
+                )
+            } else {
+                match super::adapt_upstream_response(
+                    response_adapter,
+                    upstream_content_type.as_deref(),
+                    upstream_body.as_ref(),
+                ) {
+                    Ok((body, _)) => body,
+                    Err(err) => super::build_gemini_error_body(
+                        502,
+                        &format!("response conversion failed: {err}"),
+                    ),
+                }
             };
+            let mut content_type = "application/json";
+            if client_wants_sse && status.0 < 400 {
+                // 中文注释：上游未返回 SSE 时，把聚合后的 Gemini JSON 包成单帧 SSE 回写。
+                let mut framed = b"data: ".to_vec();
+                framed.extend_from_slice(&body);
+                framed.extend_from_slice(b"\n\n");
+                body = framed;
+                content_type = "text/event-stream";
+            }
             if let Ok(content_type_header) =
                 Header::from_bytes(b"Content-Type".as_slice(), content_type.as_bytes())
             {
                 headers.push(content_type_header);
             }
-
             let len = Some(body.len());
             let response = Response::new(status, headers, std::io::Cursor::new(body), len, None);
             let delivery_error = request.respond(response).err().map(|err| err.to_string());
-            let upstream_error_hint =
-                extract_error_hint_from_body(status.0, upstream_body.as_ref());
             Ok(UpstreamResponseBridgeResult {
                 usage,
                 stream_terminal_seen: true,
                 stream_terminal_error: None,
                 delivery_error,
                 upstream_error_hint,
+                usage_limit_resets_at: extract_usage_limit_resets_at(
+                    status.0,
+                    upstream_body.as_ref(),
+                ),
+                client_cancelled: false,
             })
         }
-    }
-}
-
-#[derive(Debug, Clone, Default)]
-struct PassthroughSseCollector {
-    usage: UpstreamResponseUsage,
-    saw_terminal: bool,
-    terminal_error: Option<String>,
-}
-
-#[derive(Debug, Clone, Default)]
-struct OpenAIStreamMeta {
-    response_id: Option<String>,
-    model: Option<String>,
-    created: Option<i64>,
-}
-
-fn update_openai_stream_meta(meta: &mut OpenAIStreamMeta, value: &Value) {
-    let response = value.get("response");
+        super::ResponseAdapter::OllamaChatJson
+        | super::ResponseAdapter::OllamaChatNdjson
+        | super::ResponseAdapter::OllamaGenerateJson
+        | super::ResponseAdapter::OllamaGenerateNdjson => {
+            let status = StatusCode(upstream.status().as_u16());
+            let mut headers = Vec::new();
+            for (name, value) in upstream.headers().iter() {
+                let name_str = name.as_str();
+                if name_str.eq_ignore_ascii_case("transfer-encoding")
+                    || name_str.eq_ignore_ascii_case("content-length")
+                    || name_str.eq_ignore_ascii_case("connection")
+                    || name_str.eq_ignore_ascii_case("content-type")
+                {
+                    continue;
+                }
+                if let Ok(header) = Header::from_bytes(name_str.as_bytes(), value.as_bytes()) {
+                    headers.push(header);
+                }
+            }
+            if let Some(trace_id) = trace_id {
+                push_trace_id_header(&mut headers, trace_id);
+            }
+            let upstream_content_type = upstream
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+            let is_sse = upstream_content_type
+                .as_deref()
+                .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+                .unwrap_or(false);
+            // 中文注释：Ollama 入口的上游请求始终是 stream=true，下游是否逐行输出取决于客户端 stream。
+            let client_wants_ndjson = matches!(
+                response_adapter,
+                super::ResponseAdapter::OllamaChatNdjson
+                    | super::ResponseAdapter::OllamaGenerateNdjson
+            );
 
-    if meta.response_id.is_none() {
-        meta.response_id = value
-            .get("response_id")
-            .and_then(Value::as_str)
-            .map(str::trim)
-            .filter(|id| !id.is_empty())
+            if client_wants_ndjson && is_sse && status.0 < 400 {
+                if let Ok(content_type_header) = Header::from_bytes(
+                    b"Content-Type".as_slice(),
+                    b"application/x-ndjson".as_slice(),
+                ) {
+                    headers.push(content_type_header);
+                }
+                let usage_collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
+                let (delivery_error, client_cancelled) = respond_stream_body(
+                    request,
+                    status,
+                    headers,
+                    OllamaNdjsonReader::new(
+                        UpstreamBody::new(upstream, client_cancel),
+                        Arc::clone(&usage_collector),
+                        response_adapter,
+                    ),
+                );
+                let collector = usage_collector
+                    .lock()
+                    .map(|guard| guard.clone())
+                    .unwrap_or_default();
+                return Ok(UpstreamResponseBridgeResult {
+                    usage: collector.usage,
+                    stream_terminal_seen: collector.saw_terminal,
+                    stream_terminal_error: collector.terminal_error,
+                    delivery_error,
+                    upstream_error_hint: None,
+                    usage_limit_resets_at: None,
+                    client_cancelled,
+                });
+            }
+
+            let upstream_body = upstream
+                .bytes()
+                .map_err(|err| format!("read upstream body failed: {err}"))?;
+            let mut usage = if is_sse {
+                let (_, parsed) = collect_non_stream_json_from_sse_bytes(upstream_body.as_ref());
+                parsed
+            } else {
+                UpstreamResponseUsage::default()
+            };
+            if let Ok(value) = serde_json::from_slice::<Value>(upstream_body.as_ref()) {
+                merge_usage(&mut usage, parse_usage_from_json(&value));
+            }
+            let upstream_error_hint =
+                extract_error_hint_from_body(status.0, upstream_body.as_ref());
+            let mut body = if status.0 >= 400 {
+                super::build_ollama_error_body(
+                    upstream_error_hint
+                        .as_deref()
+                        .unwrap_or("upstream request failed"),
+                )
+            } else {
+                match super::adapt_upstream_response(
+                    response_adapter,
+                    upstream_content_type.as_deref(),
+                    upstream_body.as_ref(),
+                ) {
+                    Ok((body, _)) => body,
+                    Err(err) => super::build_ollama_error_body(&format!(
+                        "response conversion failed: {err}"
+                    )),
+                }
+            };
+            let mut content_type = "application/json";
+            if client_wants_ndjson && status.0 < 400 {
+                // 中文注释：上游未返回 SSE 时，把聚合后的最终对象作为单行 NDJSON 回写。
+                body.push(b'\n');
+                content_type = "application/x-ndjson";
+            }
+            if let Ok(content_type_header) =
+                Header::from_bytes(b"Content-Type".as_slice(), content_type.as_bytes())
+            {
+                headers.push(content_type_header);
+            }
+            let len = Some(body.len());
+            let response = Response::new(status, headers, std::io::Cursor::new(body), len, None);
+            let delivery_error = request.respond(response).err().map(|err| err.to_string());
+            Ok(UpstreamResponseBridgeResult {
+                usage,
+                stream_terminal_seen: true,
+                stream_terminal_error: None,
+                delivery_error,
+                upstream_error_hint,
+                usage_limit_resets_at: extract_usage_limit_resets_at(
+                    status.0,
+                    upstream_body.as_ref(),
+                ),
+                client_cancelled: false,
+            })
+        }
+        super::ResponseAdapter::AnthropicJson | super::ResponseAdapter::AnthropicSse => {
+            let status = StatusCode(upstream.status().as_u16());
+            let mut headers = Vec::new();
+            for (name, value) in upstream.headers().iter() {
+                let name_str = name.as_str();
+                if name_str.eq_ignore_ascii_case("transfer-encoding")
+                    || name_str.eq_ignore_ascii_case("content-length")
+                    || name_str.eq_ignore_ascii_case("connection")
+                    || name_str.eq_ignore_ascii_case("content-type")
+                {
+                    continue;
+                }
+                if let Ok(header) = Header::from_bytes(name_str.as_bytes(), value.as_bytes()) {
+                    headers.push(header);
+                }
+            }
+            if let Some(trace_id) = trace_id {
+                push_trace_id_header(&mut headers, trace_id);
+            }
+            let upstream_content_type = upstream
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+
+            if response_adapter == super::ResponseAdapter::AnthropicSse
+                && (is_stream
+                    || upstream_content_type
+                        .as_deref()
+                        .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+                        .unwrap_or(false))
+            {
+                if let Ok(content_type_header) =
+                    Header::from_bytes(b"Content-Type".as_slice(), b"text/event-stream".as_slice())
+                {
+                    headers.push(content_type_header);
+                }
+                let usage_collector = Arc::new(Mutex::new(UpstreamResponseUsage::default()));
+                let (delivery_error, client_cancelled) = respond_stream_body(
+                    request,
+                    status,
+                    headers,
+                    AnthropicSseReader::new(
+                        UpstreamBody::new(upstream, client_cancel),
+                        Arc::clone(&usage_collector),
+                    ),
+                );
+                let usage = usage_collector
+                    .lock()
+                    .map(|guard| guard.clone())
+                    .unwrap_or_default();
+                return Ok(UpstreamResponseBridgeResult {
+                    usage,
+                    stream_terminal_seen: true,
+                    stream_terminal_error: None,
+                    delivery_error,
+                    upstream_error_hint: None,
+                    usage_limit_resets_at: None,
+                    client_cancelled,
+                });
+            }
+
+            let upstream_body = upstream
+                .bytes()
+                .map_err(|err| format!("read upstream body failed: {err}"))?;
+            let usage = serde_json::from_slice::<Value>(upstream_body.as_ref())
+                .ok()
+                .map(|value| parse_usage_from_json(&value))
+                .unwrap_or_default();
+
+            let (body, content_type) = match super::adapt_upstream_response(
+                response_adapter,
+                upstream_content_type.as_deref(),
+                upstream_body.as_ref(),
+            ) {
+                Ok(result) => result,
+                Err(err) => (
+                    super::build_anthropic_error_body(&format!(
+                        "response conversion failed: {err}"
+                    )),
+                    "application/json",
+                ),
+            };
+            if let Ok(content_type_header) =
+                Header::from_bytes(b"Content-Type".as_slice(), content_type.as_bytes())
+            {
+                headers.push(content_type_header);
+            }
+
+            let len = Some(body.len());
+            let response = Response::new(status, headers, std::io::Cursor::new(body), len, None);
+            let delivery_error = request.respond(response).err().map(|err| err.to_string());
+            let upstream_error_hint =
+                extract_error_hint_from_body(status.0, upstream_body.as_ref());
+            Ok(UpstreamResponseBridgeResult {
+                usage,
+                stream_terminal_seen: true,
+                stream_terminal_error: None,
+                delivery_error,
+                upstream_error_hint,
+                usage_limit_resets_at: extract_usage_limit_resets_at(
+                    status.0,
+                    upstream_body.as_ref(),
+                ),
+                client_cancelled: false,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PassthroughSseCollector {
+    usage: UpstreamResponseUsage,
+    saw_terminal: bool,
+    terminal_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct OpenAIStreamMeta {
+    response_id: Option<String>,
+    model: Option<String>,
+    created: Option<i64>,
+}
+
+fn update_openai_stream_meta(meta: &mut OpenAIStreamMeta, value: &Value) {
+    let response = value.get("response");
+
+    if meta.response_id.is_none() {
+        meta.response_id = value
+            .get("response_id")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
             .map(str::to_string)
             .or_else(|| {
                 value
@@ -2107,28 +2797,194 @@ fn mark_collector_terminal_success(usage_collector: &Arc<Mutex<PassthroughSseCol
     }
 }
 
+const STREAM_DISCONNECTED_MESSAGE: &str = "stream disconnected before completion";
+// 中文注释：续流后新上游会重新下发这些开场事件；客户端已经收到过的同名事件直接吞掉。
+const RESPONSES_STREAM_PREAMBLE_EVENTS: [&str; 2] = ["response.created", "response.in_progress"];
+
+/// 中途续流状态：只有客户端还没收到任何实际输出时，才能换号重开上游流并无缝接到同一连接上；
+/// 已有输出时改为补发协议内的失败终止事件，避免客户端只看到被截断的流。
+struct StreamSplice {
+    failover: StreamFailover,
+    output_started: bool,
+    resumed: bool,
+    sent_preamble_events: Vec<String>,
+    response_id: Option<String>,
+    _inflight_guard: Option<AccountInFlightGuard>,
+}
+
+impl StreamSplice {
+    fn new(failover: StreamFailover) -> Self {
+        Self {
+            failover,
+            output_started: false,
+            resumed: false,
+            sent_preamble_events: Vec::new(),
+            response_id: None,
+            _inflight_guard: None,
+        }
+    }
+
+    fn try_resume(
+        &mut self,
+        reason: &str,
+        client_cancel: Option<ClientCancelToken>,
+    ) -> Option<BufReader<UpstreamBody>> {
+        if self.output_started {
+            self.failover.give_up(reason);
+            return None;
+        }
+        let resume = self.failover.resume(reason)?;
+        // 中文注释：替换旧的续流计数；最初账号的计数由 respond_with_upstream 持有到流结束。
+        self._inflight_guard = Some(resume.inflight_guard);
+        self.resumed = true;
+        Some(BufReader::new(UpstreamBody::new(
+            resume.response,
+            client_cancel,
+        )))
+    }
+
+    /// 记录 `/v1/responses` 帧是否属于实际输出；返回 false 表示该帧是续流后重复的开场事件。
+    fn observe_responses_frame(&mut self, lines: &[String]) -> bool {
+        let Some(value) = parse_sse_frame_json(lines) else {
+            return true;
+        };
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !RESPONSES_STREAM_PREAMBLE_EVENTS.contains(&event_type) {
+            self.output_started = true;
+            return true;
+        }
+        if self.resumed
+            && self
+                .sent_preamble_events
+                .iter()
+                .any(|sent| sent == event_type)
+        {
+            return false;
+        }
+        if self.response_id.is_none() {
+            self.response_id = value
+                .pointer("/response/id")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+        }
+        self.sent_preamble_events.push(event_type.to_string());
+        true
+    }
+
+    /// 续流后新上游会分配新的响应 id；把帧里的 `response.id` / `response_id` 改写成客户端最先收到的 id，
+    /// 保证同一连接内 id 一致，客户端按 id 关联的后续请求（如 previous_response_id）不会断链。
+    fn rewrite_response_id(&self, frame: Vec<String>) -> Vec<String> {
+        let (true, Some(first_id)) = (self.resumed, self.response_id.as_deref()) else {
+            return frame;
+        };
+        let has_data_line = frame
+            .iter()
+            .any(|line| line.trim_end_matches(['\r', '\n']).starts_with("data:"));
+        if !has_data_line {
+            return frame;
+        }
+        let Some(mut value) = extract_sse_frame_payload(&frame)
+            .and_then(|data| serde_json::from_str::<Value>(&data).ok())
+        else {
+            return frame;
+        };
+        let mut changed = false;
+        for pointer in ["/response/id", "/response_id"] {
+            if let Some(id) = value.pointer_mut(pointer) {
+                if id.as_str().is_some_and(|id| id != first_id) {
+                    *id = Value::String(first_id.to_string());
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return frame;
+        }
+        let mut rewritten: Vec<String> = frame
+            .into_iter()
+            .filter(|line| !line.trim_end_matches(['\r', '\n']).starts_with("data:"))
+            .collect();
+        rewritten.push(format!("data: {value}\n"));
+        rewritten
+    }
+
+    fn responses_failed_frame(&self) -> Vec<u8> {
+        let payload = json!({
+            "type": "response.failed",
+            "response": {
+                "id": self.response_id.clone().unwrap_or_default(),
+                "object": "response",
+                "status": "failed",
+                "error": {
+                    "code": "stream_disconnected",
+                    "message": STREAM_DISCONNECTED_MESSAGE
+                }
+            }
+        });
+        format!("event: response.failed\ndata: {payload}\n\n").into_bytes()
+    }
+
+    fn chat_error_frame() -> Vec<u8> {
+        let payload = json!({
+            "error": {
+                "message": STREAM_DISCONNECTED_MESSAGE,
+                "type": "server_error",
+                "code": "stream_disconnected"
+            }
+        });
+        format!("data: {payload}\n\n").into_bytes()
+    }
+}
+
+fn mark_collector_stream_disconnected(usage_collector: &Arc<Mutex<PassthroughSseCollector>>) {
+    if let Ok(mut collector) = usage_collector.lock() {
+        if !collector.saw_terminal {
+            collector
+                .terminal_error
+                .get_or_insert_with(|| STREAM_DISCONNECTED_MESSAGE.to_string());
+        }
+    }
+}
+
+fn collector_saw_terminal(usage_collector: &Arc<Mutex<PassthroughSseCollector>>) -> bool {
+    usage_collector
+        .lock()
+        .map(|collector| collector.saw_terminal)
+        .unwrap_or(false)
+}
+
 struct PassthroughSseUsageReader {
-    upstream: BufReader<reqwest::blocking::Response>,
+    upstream: BufReader<UpstreamBody>,
     pending_frame_lines: Vec<String>,
     out_cursor: Cursor<Vec<u8>>,
     usage_collector: Arc<Mutex<PassthroughSseCollector>>,
+    splice: Option<StreamSplice>,
     finished: bool,
 }
 
 impl PassthroughSseUsageReader {
     fn new(
-        upstream: reqwest::blocking::Response,
+        upstream: impl Into<UpstreamBody>,
         usage_collector: Arc<Mutex<PassthroughSseCollector>>,
     ) -> Self {
         Self {
-            upstream: BufReader::new(upstream),
+            upstream: BufReader::new(upstream.into()),
             pending_frame_lines: Vec::new(),
             out_cursor: Cursor::new(Vec::new()),
             usage_collector,
+            splice: None,
             finished: false,
         }
     }
 
+    fn with_stream_failover(mut self, failover: Option<StreamFailover>) -> Self {
+        self.splice = failover.map(StreamSplice::new);
+        self
+    }
+
     fn update_usage_from_frame(&self, lines: &[String]) {
         let inspection = inspect_sse_frame(lines);
         if inspection.usage.is_none() && inspection.terminal.is_none() {
@@ -2147,9 +3003,79 @@ impl PassthroughSseUsageReader {
         }
     }
 
+    /// 开启中途续流时按整帧转发，保证断流时客户端不会收到半帧，续流的新帧可以直接拼接。
+    fn next_spliced_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let disconnect_reason = match read_sse_line(&mut self.upstream, &mut line, true) {
+                Ok(None) => return Ok(OPENAI_SSE_HEARTBEAT_FRAME.to_vec()),
+                Ok(Some(0)) => "eof",
+                Ok(Some(_)) if line == "\n" || line == "\r\n" => {
+                    if self.pending_frame_lines.is_empty() {
+                        return Ok(line.into_bytes());
+                    }
+                    let mut frame = std::mem::take(&mut self.pending_frame_lines);
+                    self.update_usage_from_frame(&frame);
+                    if let Some(splice) = self.splice.as_mut() {
+                        if !splice.observe_responses_frame(&frame) {
+                            continue;
+                        }
+                        frame = splice.rewrite_response_id(frame);
+                    }
+                    let mut out = frame.concat();
+                    out.push_str(&line);
+                    return Ok(out.into_bytes());
+                }
+                Ok(Some(_)) => {
+                    self.pending_frame_lines.push(line.clone());
+                    continue;
+                }
+                Err(err) if is_client_cancelled_error(&err) => return Err(err),
+                Err(_) => "read_error",
+            };
+            if disconnect_reason == "eof" && !self.pending_frame_lines.is_empty() {
+                // 中文注释：末尾缺少空行的帧只有带终止事件时才下发，其余视为断流残帧丢弃。
+                let mut frame = std::mem::take(&mut self.pending_frame_lines);
+                self.update_usage_from_frame(&frame);
+                if collector_saw_terminal(&self.usage_collector) {
+                    if let Some(splice) = self.splice.as_ref() {
+                        frame = splice.rewrite_response_id(frame);
+                    }
+                    self.finished = true;
+                    return Ok(frame.concat().into_bytes());
+                }
+            }
+            self.pending_frame_lines.clear();
+            if collector_saw_terminal(&self.usage_collector) {
+                self.finished = true;
+                return Ok(Vec::new());
+            }
+            let Some(splice) = self.splice.as_mut() else {
+                self.finished = true;
+                return Ok(Vec::new());
+            };
+            let client_cancel = self.upstream.get_ref().client_cancel();
+            if let Some(upstream) = splice.try_resume(disconnect_reason, client_cancel) {
+                self.upstream = upstream;
+                continue;
+            }
+            mark_collector_stream_disconnected(&self.usage_collector);
+            self.finished = true;
+            return Ok(splice.responses_failed_frame());
+        }
+    }
+
     fn next_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        if self.splice.is_some() {
+            return self.next_spliced_chunk();
+        }
         let mut line = String::new();
-        let read = self.upstream.read_line(&mut line)?;
+        // 中文注释：逐行透传时只能在帧与帧之间插入心跳，否则会把半个事件提前分发出去。
+        let heartbeat_allowed = self.pending_frame_lines.is_empty();
+        let Some(read) = read_sse_line(&mut self.upstream, &mut line, heartbeat_allowed)? else {
+            return Ok(OPENAI_SSE_HEARTBEAT_FRAME.to_vec());
+        };
         if read == 0 {
             if !self.pending_frame_lines.is_empty() {
                 let frame = std::mem::take(&mut self.pending_frame_lines);
@@ -2193,7 +3119,7 @@ impl Read for PassthroughSseUsageReader {
 }
 
 struct OpenAICompletionsSseReader {
-    upstream: BufReader<reqwest::blocking::Response>,
+    upstream: BufReader<UpstreamBody>,
     pending_frame_lines: Vec<String>,
     out_cursor: Cursor<Vec<u8>>,
     usage_collector: Arc<Mutex<PassthroughSseCollector>>,
@@ -2204,11 +3130,11 @@ struct OpenAICompletionsSseReader {
 
 impl OpenAICompletionsSseReader {
     fn new(
-        upstream: reqwest::blocking::Response,
+        upstream: impl Into<UpstreamBody>,
         usage_collector: Arc<Mutex<PassthroughSseCollector>>,
     ) -> Self {
         Self {
-            upstream: BufReader::new(upstream),
+            upstream: BufReader::new(upstream.into()),
             pending_frame_lines: Vec::new(),
             out_cursor: Cursor::new(Vec::new()),
             usage_collector,
@@ -2317,7 +3243,9 @@ impl OpenAICompletionsSseReader {
         let mut line = String::new();
         loop {
             line.clear();
-            let read = self.upstream.read_line(&mut line)?;
+            let Some(read) = read_sse_line(&mut self.upstream, &mut line, true)? else {
+                return Ok(OPENAI_SSE_HEARTBEAT_FRAME.to_vec());
+            };
             if read == 0 {
                 if !self.pending_frame_lines.is_empty() {
                     let frame = std::mem::take(&mut self.pending_frame_lines);
@@ -2374,8 +3302,291 @@ impl Read for OpenAICompletionsSseReader {
     }
 }
 
+struct GeminiSseReader {
+    upstream: BufReader<UpstreamBody>,
+    pending_frame_lines: Vec<String>,
+    out_cursor: Cursor<Vec<u8>>,
+    usage_collector: Arc<Mutex<PassthroughSseCollector>>,
+    finished: bool,
+}
+
+impl GeminiSseReader {
+    fn new(
+        upstream: impl Into<UpstreamBody>,
+        usage_collector: Arc<Mutex<PassthroughSseCollector>>,
+    ) -> Self {
+        Self {
+            upstream: BufReader::new(upstream.into()),
+            pending_frame_lines: Vec::new(),
+            out_cursor: Cursor::new(Vec::new()),
+            usage_collector,
+            finished: false,
+        }
+    }
+
+    fn update_usage_from_frame(&self, lines: &[String]) -> Option<String> {
+        let inspection = inspect_sse_frame(lines);
+        if inspection.usage.is_none() && inspection.terminal.is_none() {
+            return None;
+        }
+        let mut terminal_error = None;
+        if let Ok(mut collector) = self.usage_collector.lock() {
+            if let Some(parsed) = inspection.usage {
+                merge_usage(&mut collector.usage, parsed);
+            }
+            if let Some(terminal) = inspection.terminal {
+                collector.saw_terminal = true;
+                if let SseTerminal::Err(message) = terminal {
+                    collector.terminal_error = Some(message.clone());
+                    terminal_error = Some(message);
+                }
+            }
+        }
+        terminal_error
+    }
+
+    fn map_frame_to_gemini_sse(&mut self, lines: &[String]) -> Vec<u8> {
+        let terminal_error = self.update_usage_from_frame(lines);
+        let Some(value) = parse_sse_frame_json(lines) else {
+            return Vec::new();
+        };
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if let Some(message) = terminal_error {
+            // 中文注释：Gemini 流式协议没有独立的 error 事件，按官方行为直接下发 error 对象后结束。
+            let detail = extract_error_message_from_json(&value).unwrap_or(message);
+            let payload = super::build_gemini_error_body(500, detail.as_str());
+            self.finished = true;
+            let mut out = b"data: ".to_vec();
+            out.extend_from_slice(&payload);
+            out.extend_from_slice(b"\n\n");
+            return out;
+        }
+        let mut out = String::new();
+        if let Some(chunk) = super::convert_gemini_stream_chunk(&value) {
+            append_sse_data_frame(&mut out, &chunk);
+        }
+        if is_response_completed_event_name(event_type) {
+            self.finished = true;
+        }
+        out.into_bytes()
+    }
+
+    fn next_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let Some(read) = read_sse_line(&mut self.upstream, &mut line, true)? else {
+                return Ok(OPENAI_SSE_HEARTBEAT_FRAME.to_vec());
+            };
+            if read == 0 {
+                if !self.pending_frame_lines.is_empty() {
+                    let frame = std::mem::take(&mut self.pending_frame_lines);
+                    let mapped = self.map_frame_to_gemini_sse(&frame);
+                    if !mapped.is_empty() {
+                        return Ok(mapped);
+                    }
+                }
+                if let Ok(mut collector) = self.usage_collector.lock() {
+                    if !collector.saw_terminal {
+                        collector.terminal_error.get_or_insert_with(|| {
+                            "stream disconnected before completion".to_string()
+                        });
+                    }
+                }
+                self.finished = true;
+                return Ok(Vec::new());
+            }
+            if line == "\n" || line == "\r\n" {
+                if self.pending_frame_lines.is_empty() {
+                    continue;
+                }
+                let frame = std::mem::take(&mut self.pending_frame_lines);
+                let mapped = self.map_frame_to_gemini_sse(&frame);
+                if !mapped.is_empty() {
+                    return Ok(mapped);
+                }
+                if self.finished {
+                    return Ok(Vec::new());
+                }
+                continue;
+            }
+            self.pending_frame_lines.push(line.clone());
+        }
+    }
+}
+
+impl Read for GeminiSseReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.out_cursor.read(buf)?;
+            if read > 0 {
+                return Ok(read);
+            }
+            if self.finished {
+                return Ok(0);
+            }
+            self.out_cursor = Cursor::new(self.next_chunk()?);
+        }
+    }
+}
+
+/// 把上游 Responses SSE 逐帧转换为 Ollama NDJSON；NDJSON 没有注释行，上游空闲时不插入心跳。
+struct OllamaNdjsonReader {
+    upstream: BufReader<UpstreamBody>,
+    pending_frame_lines: Vec<String>,
+    out_cursor: Cursor<Vec<u8>>,
+    usage_collector: Arc<Mutex<PassthroughSseCollector>>,
+    response_adapter: super::ResponseAdapter,
+    stream_model: String,
+    finished: bool,
+}
+
+impl OllamaNdjsonReader {
+    fn new(
+        upstream: impl Into<UpstreamBody>,
+        usage_collector: Arc<Mutex<PassthroughSseCollector>>,
+        response_adapter: super::ResponseAdapter,
+    ) -> Self {
+        Self {
+            upstream: BufReader::new(upstream.into()),
+            pending_frame_lines: Vec::new(),
+            out_cursor: Cursor::new(Vec::new()),
+            usage_collector,
+            response_adapter,
+            stream_model: String::new(),
+            finished: false,
+        }
+    }
+
+    fn update_usage_from_frame(&self, lines: &[String]) -> Option<String> {
+        let inspection = inspect_sse_frame(lines);
+        if inspection.usage.is_none() && inspection.terminal.is_none() {
+            return None;
+        }
+        let mut terminal_error = None;
+        if let Ok(mut collector) = self.usage_collector.lock() {
+            if let Some(parsed) = inspection.usage {
+                merge_usage(&mut collector.usage, parsed);
+            }
+            if let Some(terminal) = inspection.terminal {
+                collector.saw_terminal = true;
+                if let SseTerminal::Err(message) = terminal {
+                    collector.terminal_error = Some(message.clone());
+                    terminal_error = Some(message);
+                }
+            }
+        }
+        terminal_error
+    }
+
+    fn map_frame_to_ollama_ndjson(&mut self, lines: &[String]) -> Vec<u8> {
+        let terminal_error = self.update_usage_from_frame(lines);
+        let Some(value) = parse_sse_frame_json(lines) else {
+            return Vec::new();
+        };
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if let Some(model) = value
+            .get("response")
+            .and_then(|response| response.get("model"))
+            .and_then(Value::as_str)
+            .filter(|model| !model.is_empty())
+        {
+            self.stream_model = model.to_string();
+        }
+        if let Some(message) = terminal_error {
+            // 中文注释：Ollama 流式出错时以一行 {"error": ...} 结束。
+            let detail = extract_error_message_from_json(&value).unwrap_or(message);
+            let mut out = super::build_ollama_error_body(detail.as_str());
+            out.push(b'\n');
+            self.finished = true;
+            return out;
+        }
+        let mut out = Vec::new();
+        if let Some(mut chunk) = super::convert_ollama_stream_chunk(self.response_adapter, &value) {
+            // 中文注释：文本增量事件不带模型名，沿用 response.created 等事件里记下的模型。
+            if let Some(model) = chunk.get_mut("model") {
+                if model.as_str().is_some_and(str::is_empty) {
+                    *model = Value::String(self.stream_model.clone());
+                }
+            }
+            if let Ok(line) = serde_json::to_vec(&chunk) {
+                out.extend_from_slice(&line);
+                out.push(b'\n');
+            }
+        }
+        if is_response_completed_event_name(event_type) {
+            self.finished = true;
+        }
+        out
+    }
+
+    fn next_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let Some(read) = read_sse_line(&mut self.upstream, &mut line, false)? else {
+                continue;
+            };
+            if read == 0 {
+                if !self.pending_frame_lines.is_empty() {
+                    let frame = std::mem::take(&mut self.pending_frame_lines);
+                    let mapped = self.map_frame_to_ollama_ndjson(&frame);
+                    if !mapped.is_empty() {
+                        return Ok(mapped);
+                    }
+                }
+                if let Ok(mut collector) = self.usage_collector.lock() {
+                    if !collector.saw_terminal {
+                        collector.terminal_error.get_or_insert_with(|| {
+                            "stream disconnected before completion".to_string()
+                        });
+                    }
+                }
+                self.finished = true;
+                return Ok(Vec::new());
+            }
+            if line == "\n" || line == "\r\n" {
+                if self.pending_frame_lines.is_empty() {
+                    continue;
+                }
+                let frame = std::mem::take(&mut self.pending_frame_lines);
+                let mapped = self.map_frame_to_ollama_ndjson(&frame);
+                if !mapped.is_empty() {
+                    return Ok(mapped);
+                }
+                if self.finished {
+                    return Ok(Vec::new());
+                }
+                continue;
+            }
+            self.pending_frame_lines.push(line.clone());
+        }
+    }
+}
+
+impl Read for OllamaNdjsonReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.out_cursor.read(buf)?;
+            if read > 0 {
+                return Ok(read);
+            }
+            if self.finished {
+                return Ok(0);
+            }
+            self.out_cursor = Cursor::new(self.next_chunk()?);
+        }
+    }
+}
+
 struct OpenAIChatCompletionsSseReader {
-    upstream: BufReader<reqwest::blocking::Response>,
+    upstream: BufReader<UpstreamBody>,
     pending_frame_lines: Vec<String>,
     out_cursor: Cursor<Vec<u8>>,
     usage_collector: Arc<Mutex<PassthroughSseCollector>>,
@@ -2383,17 +3594,18 @@ struct OpenAIChatCompletionsSseReader {
     stream_meta: OpenAIStreamMeta,
     emitted_text_delta: bool,
     emitted_assistant_role: bool,
+    splice: Option<StreamSplice>,
     finished: bool,
 }
 
 impl OpenAIChatCompletionsSseReader {
     fn new(
-        upstream: reqwest::blocking::Response,
+        upstream: impl Into<UpstreamBody>,
         usage_collector: Arc<Mutex<PassthroughSseCollector>>,
         tool_name_restore_map: Option<super::ToolNameRestoreMap>,
     ) -> Self {
         Self {
-            upstream: BufReader::new(upstream),
+            upstream: BufReader::new(upstream.into()),
             pending_frame_lines: Vec::new(),
             out_cursor: Cursor::new(Vec::new()),
             usage_collector,
@@ -2401,10 +3613,23 @@ impl OpenAIChatCompletionsSseReader {
             stream_meta: OpenAIStreamMeta::default(),
             emitted_text_delta: false,
             emitted_assistant_role: false,
+            splice: None,
             finished: false,
         }
     }
 
+    fn with_stream_failover(mut self, failover: Option<StreamFailover>) -> Self {
+        self.splice = failover.map(StreamSplice::new);
+        self
+    }
+
+    fn mark_output(&mut self, out: Vec<u8>) -> Vec<u8> {
+        if let Some(splice) = self.splice.as_mut() {
+            splice.output_started |= !out.is_empty();
+        }
+        out
+    }
+
     fn update_usage_from_frame(&self, lines: &[String]) {
         let inspection = inspect_sse_frame(lines);
         if inspection.usage.is_none() && inspection.terminal.is_none() {
@@ -2513,28 +3738,42 @@ impl OpenAIChatCompletionsSseReader {
         let mut line = String::new();
         loop {
             line.clear();
-            let read = self.upstream.read_line(&mut line)?;
-            if read == 0 {
-                if !self.pending_frame_lines.is_empty() {
+            let read = match read_sse_line(&mut self.upstream, &mut line, true) {
+                Ok(None) => return Ok(OPENAI_SSE_HEARTBEAT_FRAME.to_vec()),
+                Ok(Some(read)) => Some(read),
+                // 中文注释：开启中途续流时，读错误与提前 EOF 一样进入续流判断。
+                Err(err) if self.splice.is_some() && !is_client_cancelled_error(&err) => None,
+                Err(err) => return Err(err),
+            };
+            if read.is_none_or(|read| read == 0) {
+                if read.is_some() && !self.pending_frame_lines.is_empty() {
                     let frame = std::mem::take(&mut self.pending_frame_lines);
                     self.update_usage_from_frame(&frame);
                     let mapped = self.map_frame_to_chat_completions_sse(&frame);
                     if !mapped.is_empty() {
-                        return Ok(mapped);
+                        return Ok(self.mark_output(mapped));
                     }
                 }
+                self.pending_frame_lines.clear();
                 if let Some(fallback) = self.try_build_chat_fallback_stream(true) {
-                    return Ok(fallback);
+                    return Ok(self.mark_output(fallback));
                 }
-                if let Ok(mut collector) = self.usage_collector.lock() {
-                    if !collector.saw_terminal {
-                        // 中文注释：对齐最新 Codex SSE 语义：
-                        // 只有 response.completed / response.done / [DONE] 才算正常结束。
-                        collector.terminal_error.get_or_insert_with(|| {
-                            "stream disconnected before completion".to_string()
-                        });
+                if !collector_saw_terminal(&self.usage_collector) {
+                    if let Some(splice) = self.splice.as_mut() {
+                        let reason = if read.is_some() { "eof" } else { "read_error" };
+                        let client_cancel = self.upstream.get_ref().client_cancel();
+                        if let Some(upstream) = splice.try_resume(reason, client_cancel) {
+                            self.upstream = upstream;
+                            continue;
+                        }
+                        mark_collector_stream_disconnected(&self.usage_collector);
+                        self.finished = true;
+                        return Ok(StreamSplice::chat_error_frame());
                     }
                 }
+                // 中文注释：对齐最新 Codex SSE 语义：
+                // 只有 response.completed / response.done / [DONE] 才算正常结束。
+                mark_collector_stream_disconnected(&self.usage_collector);
                 self.finished = true;
                 return Ok(Vec::new());
             }
@@ -2546,7 +3785,7 @@ impl OpenAIChatCompletionsSseReader {
                 self.update_usage_from_frame(&frame);
                 let mapped = self.map_frame_to_chat_completions_sse(&frame);
                 if !mapped.is_empty() {
-                    return Ok(mapped);
+                    return Ok(self.mark_output(mapped));
                 }
                 continue;
             }
@@ -2571,7 +3810,7 @@ impl Read for OpenAIChatCompletionsSseReader {
 }
 
 struct AnthropicSseReader {
-    upstream: BufReader<reqwest::blocking::Response>,
+    upstream: BufReader<UpstreamBody>,
     pending_frame_lines: Vec<String>,
     out_cursor: Cursor<Vec<u8>>,
     state: AnthropicSseState,
@@ -2597,11 +3836,11 @@ struct AnthropicSseState {
 
 impl AnthropicSseReader {
     fn new(
-        upstream: reqwest::blocking::Response,
+        upstream: impl Into<UpstreamBody>,
         usage_collector: Arc<Mutex<UpstreamResponseUsage>>,
     ) -> Self {
         Self {
-            upstream: BufReader::new(upstream),
+            upstream: BufReader::new(upstream.into()),
             pending_frame_lines: Vec::new(),
             out_cursor: Cursor::new(Vec::new()),
             state: AnthropicSseState::default(),
@@ -2613,7 +3852,9 @@ impl AnthropicSseReader {
         let mut line = String::new();
         loop {
             line.clear();
-            let read = self.upstream.read_line(&mut line)?;
+            let Some(read) = read_sse_line(&mut self.upstream, &mut line, true)? else {
+                return Ok(anthropic_ping_event());
+            };
             if read == 0 {
                 return Ok(self.finish_stream());
             }
@@ -2969,6 +4210,13 @@ impl Read for AnthropicSseReader {
     }
 }
 
+/// Anthropic 协议自带 `ping` 事件，客户端会直接忽略，适合作为空闲心跳。
+fn anthropic_ping_event() -> Vec<u8> {
+    let mut out = String::new();
+    append_sse_event(&mut out, "ping", &json!({ "type": "ping" }));
+    out.into_bytes()
+}
+
 fn append_sse_event(buffer: &mut String, event_name: &str, payload: &Value) {
     let data = serde_json::to_string(payload).unwrap_or_else(|_| "{}".to_string());
     buffer.push_str("event: ");
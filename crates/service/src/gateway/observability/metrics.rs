@@ -27,6 +27,11 @@ static HTTP_QUEUE_ENQUEUE_FAILURES: AtomicUsize = AtomicUsize::new(0);
 static GATEWAY_UPSTREAM_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
 static GATEWAY_UPSTREAM_ATTEMPT_ERRORS: AtomicUsize = AtomicUsize::new(0);
 static GATEWAY_UPSTREAM_ATTEMPT_DURATION_MS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static ADMISSION_QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+static ADMISSION_QUEUE_ADMITTED: AtomicUsize = AtomicUsize::new(0);
+static ADMISSION_QUEUE_TIMEOUTS: AtomicUsize = AtomicUsize::new(0);
+static ADMISSION_QUEUE_REJECTED: AtomicUsize = AtomicUsize::new(0);
+static ADMISSION_QUEUE_WAIT_MS_TOTAL: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct GatewayRequestLabelKey {
@@ -59,6 +64,11 @@ pub(crate) struct GatewayMetricsSnapshot {
     pub gateway_upstream_attempt_duration_ms_total: u64,
     pub gateway_upstream_attempts: usize,
     pub gateway_upstream_attempt_errors: usize,
+    pub admission_queue_depth: usize,
+    pub admission_queue_admitted: usize,
+    pub admission_queue_timeouts: usize,
+    pub admission_queue_rejected: usize,
+    pub admission_queue_wait_ms_total: u64,
 }
 
 pub(crate) struct GatewayRequestGuard;
@@ -161,6 +171,24 @@ pub(crate) fn record_gateway_upstream_attempt(duration_ms: u64, failed: bool) {
     }
 }
 
+pub(crate) fn record_admission_queue_enqueue() {
+    ADMISSION_QUEUE_DEPTH.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_admission_queue_dequeue(waited_ms: u64, admitted: bool) {
+    atomic_dec_saturating(&ADMISSION_QUEUE_DEPTH);
+    ADMISSION_QUEUE_WAIT_MS_TOTAL.fetch_add(waited_ms, Ordering::Relaxed);
+    if admitted {
+        ADMISSION_QUEUE_ADMITTED.fetch_add(1, Ordering::Relaxed);
+    } else {
+        ADMISSION_QUEUE_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_admission_queue_rejected() {
+    ADMISSION_QUEUE_REJECTED.fetch_add(1, Ordering::Relaxed);
+}
+
 pub(crate) fn record_gateway_request_outcome(
     path: &str,
     status_code: u16,
@@ -212,6 +240,11 @@ pub(crate) fn gateway_metrics_snapshot() -> GatewayMetricsSnapshot {
             .load(Ordering::Relaxed),
         gateway_upstream_attempts: GATEWAY_UPSTREAM_ATTEMPTS.load(Ordering::Relaxed),
         gateway_upstream_attempt_errors: GATEWAY_UPSTREAM_ATTEMPT_ERRORS.load(Ordering::Relaxed),
+        admission_queue_depth: ADMISSION_QUEUE_DEPTH.load(Ordering::Relaxed),
+        admission_queue_admitted: ADMISSION_QUEUE_ADMITTED.load(Ordering::Relaxed),
+        admission_queue_timeouts: ADMISSION_QUEUE_TIMEOUTS.load(Ordering::Relaxed),
+        admission_queue_rejected: ADMISSION_QUEUE_REJECTED.load(Ordering::Relaxed),
+        admission_queue_wait_ms_total: ADMISSION_QUEUE_WAIT_MS_TOTAL.load(Ordering::Relaxed),
     }
 }
 
@@ -243,6 +276,12 @@ codexmanager_http_queue_enqueue_failures_total {}\n\
 codexmanager_gateway_upstream_attempt_duration_milliseconds_total {}\n\
 codexmanager_gateway_upstream_attempt_duration_milliseconds_count {}\n\
 codexmanager_gateway_upstream_attempt_errors_total {}\n\
+codexmanager_gateway_admission_queue_depth {}\n\
+codexmanager_gateway_admission_queue_admitted_total {}\n\
+codexmanager_gateway_admission_queue_timeouts_total {}\n\
+codexmanager_gateway_admission_queue_rejected_total {}\n\
+codexmanager_gateway_admission_queue_wait_milliseconds_total {}\n\
+codexmanager_gateway_admission_queue_wait_milliseconds_count {}\n\
 {}",
         m.total_requests,
         m.active_requests,
@@ -268,6 +307,12 @@ codexmanager_gateway_upstream_attempt_errors_total {}\n\
         m.gateway_upstream_attempt_duration_ms_total,
         m.gateway_upstream_attempts,
         m.gateway_upstream_attempt_errors,
+        m.admission_queue_depth,
+        m.admission_queue_admitted,
+        m.admission_queue_timeouts,
+        m.admission_queue_rejected,
+        m.admission_queue_wait_ms_total,
+        m.admission_queue_admitted + m.admission_queue_timeouts,
         labeled,
     )
 }
@@ -290,6 +335,9 @@ fn gateway_labeled_metrics_prometheus() -> String {
 
 fn classify_gateway_route(path: &str) -> &'static str {
     let path = path.split('?').next().unwrap_or(path);
+    if path.starts_with("/v1/responses/input_tokens") {
+        return "responses_input_tokens";
+    }
     if path.starts_with("/v1/responses") {
         return "responses";
     }
@@ -363,6 +411,8 @@ impl Drop for AccountInFlightGuard {
                 map.remove(&self.account_id);
             }
         }
+        drop(map);
+        super::notify_admission_capacity_changed();
     }
 }
 
@@ -15,79 +15,43 @@ pub(super) struct RequestLogTraceContext<'a> {
     pub original_path: Option<&'a str>,
     pub adapted_path: Option<&'a str>,
     pub response_adapter: Option<super::ResponseAdapter>,
+    /// 客户端原始请求的模型；与实际转发模型不同（别名改写或 Key 固定模型）时才会落库。
+    pub requested_model: Option<&'a str>,
 }
 
-const MODEL_PRICE_PER_1K_TOKENS: &[(&str, f64, f64, f64)] = &[
-    // OpenAI 官方价格（单位：USD / 1K tokens）。按模型前缀匹配，越具体越靠前。
-    // gpt-5.3-codex 暂未公开价格，临时按 gpt-5.2-codex 计费。
-    ("gpt-5.3-codex", 0.00175, 0.000175, 0.014),
-    ("gpt-5.2-codex", 0.00175, 0.000175, 0.014),
-    ("gpt-5.2", 0.00175, 0.000175, 0.014),
-    ("gpt-5.1-codex-mini", 0.00025, 0.000025, 0.002),
-    ("gpt-5.1-codex-max", 0.00125, 0.000125, 0.01),
-    ("gpt-5.1-codex", 0.00125, 0.000125, 0.01),
-    ("gpt-5.1", 0.00125, 0.000125, 0.01),
-    ("gpt-5-codex", 0.00125, 0.000125, 0.01),
-    ("gpt-5", 0.00125, 0.000125, 0.01),
-    // 兼容旧模型：缓存输入按输入同价处理，保持历史口径稳定。
-    ("gpt-4.1", 0.002, 0.002, 0.008),
-    ("gpt-4o", 0.0025, 0.0025, 0.01),
-    ("gpt-4", 0.03, 0.03, 0.06),
-    ("claude-3-7", 0.003, 0.003, 0.015),
-    ("claude-3-5", 0.003, 0.003, 0.015),
-    ("claude-3", 0.003, 0.003, 0.015),
-];
-
-fn resolve_model_price_per_1k(
-    normalized: &str,
-    input_tokens_total: i64,
-) -> Option<(f64, f64, f64)> {
-    // OpenAI 官方定价：gpt-5.4 / gpt-5.4-pro 在输入超过 272K 时切换到更高档位。
-    // gpt-5.4-pro 官方未提供 cached input 单价，这里按普通输入价计算，避免低估费用。
-    if normalized.starts_with("gpt-5.4-pro") {
-        if input_tokens_total > 272_000 {
-            return Some((0.06, 0.06, 0.27));
+/// 上游未返回 usage 时，用本地 tokenizer 按请求体与输出文本估算，避免日志与费用统计出现空洞。
+pub(super) fn fill_missing_usage_from_estimate(
+    usage: RequestLogUsage,
+    request_body: &[u8],
+    output_text: Option<&str>,
+) -> RequestLogUsage {
+    let mut usage = usage;
+    let mut estimated = false;
+    if usage.input_tokens.unwrap_or(0) <= 0 && usage.total_tokens.unwrap_or(0) <= 0 {
+        if let Some(input_tokens) =
+            super::token_counter::count_request_body_input_tokens(request_body)
+        {
+            usage.input_tokens = Some(input_tokens.min(i64::MAX as u64) as i64);
+            estimated = true;
         }
-        return Some((0.03, 0.03, 0.18));
     }
-    if normalized.starts_with("gpt-5.4") {
-        if input_tokens_total > 272_000 {
-            return Some((0.005, 0.0005, 0.0225));
+    if usage.output_tokens.unwrap_or(0) <= 0 {
+        if let Some(text) = output_text.map(str::trim).filter(|text| !text.is_empty()) {
+            let output_tokens = super::token_counter::count_text_tokens(text);
+            usage.output_tokens = Some(output_tokens.min(i64::MAX as u64) as i64);
+            estimated = true;
         }
-        return Some((0.0025, 0.00025, 0.015));
     }
-    MODEL_PRICE_PER_1K_TOKENS
-        .iter()
-        .find(|(prefix, _, _, _)| normalized.starts_with(prefix))
-        .map(|(_, input, cached_input, output)| (*input, *cached_input, *output))
-}
-
-fn estimate_cost_usd(
-    model: Option<&str>,
-    input_tokens: Option<i64>,
-    cached_input_tokens: Option<i64>,
-    output_tokens: Option<i64>,
-) -> f64 {
-    let normalized = model
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(|value| value.to_ascii_lowercase());
-    let Some(normalized) = normalized else {
-        return 0.0;
-    };
-    let input_tokens_total = input_tokens.unwrap_or(0).max(0);
-    let Some((in_per_1k, cached_in_per_1k, out_per_1k)) =
-        resolve_model_price_per_1k(&normalized, input_tokens_total)
-    else {
-        return 0.0;
-    };
-    let in_tokens_total = input_tokens_total as f64;
-    let cached_in_tokens = (cached_input_tokens.unwrap_or(0).max(0) as f64).min(in_tokens_total);
-    let billable_in_tokens = (in_tokens_total - cached_in_tokens).max(0.0);
-    let out_tokens = output_tokens.unwrap_or(0).max(0) as f64;
-    (billable_in_tokens / 1000.0) * in_per_1k
-        + (cached_in_tokens / 1000.0) * cached_in_per_1k
-        + (out_tokens / 1000.0) * out_per_1k
+    // 中文注释：上游给出的 total_tokens 可能包含估算不到的部分（如推理 token），只在缺失时补算。
+    if estimated && usage.total_tokens.is_none() {
+        usage.total_tokens = Some(
+            usage
+                .input_tokens
+                .unwrap_or(0)
+                .saturating_add(usage.output_tokens.unwrap_or(0)),
+        );
+    }
+    usage
 }
 
 fn normalize_token(value: Option<i64>) -> Option<i64> {
@@ -109,6 +73,12 @@ fn response_adapter_label(value: super::ResponseAdapter) -> &'static str {
         super::ResponseAdapter::OpenAIChatCompletionsSse => "OpenAIChatCompletionsSse",
         super::ResponseAdapter::OpenAICompletionsJson => "OpenAICompletionsJson",
         super::ResponseAdapter::OpenAICompletionsSse => "OpenAICompletionsSse",
+        super::ResponseAdapter::GeminiJson => "GeminiJson",
+        super::ResponseAdapter::GeminiSse => "GeminiSse",
+        super::ResponseAdapter::OllamaChatJson => "OllamaChatJson",
+        super::ResponseAdapter::OllamaChatNdjson => "OllamaChatNdjson",
+        super::ResponseAdapter::OllamaGenerateJson => "OllamaGenerateJson",
+        super::ResponseAdapter::OllamaGenerateNdjson => "OllamaGenerateNdjson",
     }
 }
 
@@ -133,9 +103,24 @@ pub(super) fn write_request_log(
     let output_tokens = normalize_token(usage.output_tokens);
     let total_tokens = normalize_token(usage.total_tokens);
     let reasoning_output_tokens = normalize_token(usage.reasoning_output_tokens);
+    if let Some(key_id) = key_id {
+        // 中文注释：TPM 按实际消耗计费；total 缺失时用 input + output 兜底。
+        let charged_tokens = total_tokens
+            .filter(|tokens| *tokens > 0)
+            .unwrap_or_else(|| input_tokens.unwrap_or(0) + output_tokens.unwrap_or(0));
+        super::charge_key_tokens(key_id, charged_tokens.max(0) as u64);
+    }
     let created_at = now_ts();
-    let estimated_cost_usd =
-        estimate_cost_usd(model, input_tokens, cached_input_tokens, output_tokens);
+    // 中文注释：价目表缓存在内存中，调价 RPC 会让缓存失效，新请求立即按新价计费；未定价模型费用留空而不是记 0。
+    let pricing = crate::model_pricing::load_model_pricing(storage);
+    let estimated_cost_usd = crate::model_pricing::estimate_cost_usd(
+        &pricing,
+        model,
+        input_tokens,
+        cached_input_tokens,
+        output_tokens,
+        reasoning_output_tokens,
+    );
     let success = status_code
         .map(|status| (200..300).contains(&status))
         .unwrap_or(false);
@@ -172,6 +157,11 @@ pub(super) fn write_request_log(
             adapted_path: Some(adapted_path.to_string()),
             method: method.to_string(),
             model: model.map(|v| v.to_string()),
+            requested_model: trace_context
+                .requested_model
+                .map(str::trim)
+                .filter(|v| !v.is_empty() && Some(*v) != model)
+                .map(str::to_string),
             reasoning_effort: reasoning_effort.map(|v| v.to_string()),
             response_adapter: trace_context
                 .response_adapter
@@ -198,7 +188,7 @@ pub(super) fn write_request_log(
             output_tokens,
             total_tokens,
             reasoning_output_tokens,
-            estimated_cost_usd: Some(estimated_cost_usd),
+            estimated_cost_usd,
             created_at,
         },
     ) {
@@ -218,6 +208,16 @@ pub(super) fn write_request_log(
         }
     };
 
+    if let (Some(key_id), None) = (key_id, token_stat_error.as_ref()) {
+        crate::apikey_budget::record_key_spend(
+            key_id,
+            request_log_id,
+            created_at,
+            total_tokens.unwrap_or_else(|| input_tokens.unwrap_or(0) + output_tokens.unwrap_or(0)),
+            estimated_cost_usd,
+        );
+    }
+
     if let Some(err) = token_stat_error {
         let err_text = err.to_string();
         super::metrics::record_db_error(err_text.as_str());
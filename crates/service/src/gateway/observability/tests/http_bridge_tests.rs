@@ -4,14 +4,16 @@ use super::{
     parse_sse_frame_json, parse_usage_from_json, parse_usage_from_sse_frame,
     should_skip_chat_live_text_event, should_skip_completion_live_text_event,
     synthesize_chat_completion_sse_from_json, synthesize_completions_sse_from_json,
-    OpenAIChatCompletionsSseReader, OpenAICompletionsSseReader, OpenAIStreamMeta,
-    PassthroughSseCollector,
+    AnthropicSseReader, OpenAIChatCompletionsSseReader, OpenAICompletionsSseReader,
+    OpenAIStreamMeta, PassthroughSseCollector, PassthroughSseUsageReader, UpstreamBody,
+    UpstreamResponseUsage, WatchedUpstream,
 };
 use serde_json::json;
 use std::io::{Read, Write};
 use std::net::TcpListener;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
 fn open_mock_http_response(content_type: &str, body: &str) -> reqwest::blocking::Response {
     let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock upstream");
@@ -134,6 +136,52 @@ fn parse_usage_from_sse_frame_reads_top_level_and_response_usage() {
     assert_eq!(usage.reasoning_output_tokens, Some(4));
 }
 
+#[test]
+fn parse_usage_from_sse_frame_reads_anthropic_message_start_cache_tokens() {
+    let frame_lines = vec![
+        "event: message_start\n".to_string(),
+        r#"data: {"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-sonnet-4-5","content":[],"usage":{"input_tokens":40,"cache_read_input_tokens":50,"cache_creation_input_tokens":10,"output_tokens":1}}}"#
+            .to_string(),
+        "\n".to_string(),
+    ];
+    let usage = parse_usage_from_sse_frame(&frame_lines).expect("extract usage from sse frame");
+    assert_eq!(usage.input_tokens, Some(100));
+    assert_eq!(usage.cached_input_tokens, Some(50));
+    assert_eq!(usage.output_tokens, Some(1));
+}
+
+#[test]
+fn inspect_sse_frame_reads_anthropic_text_delta_and_message_delta_usage() {
+    let delta = inspect_sse_frame(&[
+        "event: content_block_delta\n".to_string(),
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"hello"}}"#
+            .to_string(),
+        "\n".to_string(),
+    ]);
+    assert_eq!(
+        delta.usage.and_then(|usage| usage.output_text).as_deref(),
+        Some("hello")
+    );
+
+    let message_delta = inspect_sse_frame(&[
+        "event: message_delta\n".to_string(),
+        r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":27}}"#
+            .to_string(),
+        "\n".to_string(),
+    ]);
+    assert_eq!(
+        message_delta.usage.and_then(|usage| usage.output_tokens),
+        Some(27)
+    );
+
+    let stop = inspect_sse_frame(&[
+        "event: message_stop\n".to_string(),
+        r#"data: {"type":"message_stop"}"#.to_string(),
+        "\n".to_string(),
+    ]);
+    assert!(matches!(stop.terminal, Some(super::SseTerminal::Ok)));
+}
+
 #[test]
 fn parse_usage_from_sse_frame_caps_output_text() {
     let limit = super::output_text_limit_bytes();
@@ -618,3 +666,129 @@ fn openai_completions_sse_reader_requires_terminal_event_before_success() {
         Some("stream disconnected before completion")
     );
 }
+
+/// 按顺序吐出数据块，每块之前先等待指定时长，模拟推理阶段长时间无输出的上游。
+struct PausingUpstream {
+    chunks: Vec<(Duration, &'static [u8])>,
+}
+
+impl Read for PausingUpstream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.chunks.is_empty() {
+            return Ok(0);
+        }
+        let (pause, chunk) = self.chunks.remove(0);
+        thread::sleep(pause);
+        buf[..chunk.len()].copy_from_slice(chunk);
+        Ok(chunk.len())
+    }
+}
+
+fn pausing_upstream_with_heartbeat(chunks: Vec<(Duration, &'static str)>) -> UpstreamBody {
+    pausing_byte_upstream_with_heartbeat(
+        chunks
+            .into_iter()
+            .map(|(pause, chunk)| (pause, chunk.as_bytes()))
+            .collect(),
+    )
+}
+
+fn pausing_byte_upstream_with_heartbeat(chunks: Vec<(Duration, &'static [u8])>) -> UpstreamBody {
+    UpstreamBody::Watched(WatchedUpstream::spawn(
+        PausingUpstream { chunks },
+        None,
+        Some(Duration::from_millis(50)),
+    ))
+}
+
+#[test]
+fn passthrough_sse_reader_keeps_multibyte_char_split_by_idle_pause() {
+    // 中文注释："你" 的 UTF-8 编码被上游停顿拆在两块里，空闲超时不能吞掉前半个字符。
+    let upstream = pausing_byte_upstream_with_heartbeat(vec![
+        (
+            Duration::ZERO,
+            b"data: {\"type\":\"response.output_text.delta\",\"delta\":\"\xe4\xbd",
+        ),
+        (Duration::from_millis(150), b"\xa0\"}\n\n"),
+        (
+            Duration::ZERO,
+            b"data: {\"type\":\"response.completed\",\"response\":{\"usage\":{\"input_tokens\":1,\"output_tokens\":1,\"total_tokens\":2}}}\n\n",
+        ),
+    ]);
+    let collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
+    let mut reader = PassthroughSseUsageReader::new(upstream, Arc::clone(&collector));
+    let mut body = String::new();
+    reader.read_to_string(&mut body).expect("read stream");
+
+    assert!(body.contains("\"delta\":\"你\"}"), "body: {body:?}");
+    let collector = collector.lock().expect("collector");
+    assert!(collector.saw_terminal);
+    assert_eq!(collector.usage.output_tokens, Some(1));
+}
+
+#[test]
+fn passthrough_sse_reader_injects_heartbeat_between_frames_when_upstream_idles() {
+    let upstream = pausing_upstream_with_heartbeat(vec![
+        (
+            Duration::ZERO,
+            "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_1\"}}\n",
+        ),
+        (Duration::from_millis(150), "\n"),
+        (
+            Duration::from_millis(150),
+            "data: {\"type\":\"response.completed\",\"response\":{\"usage\":{\"input_tokens\":3,\"output_tokens\":5,\"total_tokens\":8}}}\n\n",
+        ),
+    ]);
+    let collector = Arc::new(Mutex::new(PassthroughSseCollector::default()));
+    let mut reader = PassthroughSseUsageReader::new(upstream, Arc::clone(&collector));
+    let mut body = String::new();
+    reader.read_to_string(&mut body).expect("read stream");
+
+    // 中文注释：第一帧读到一半时上游停顿，心跳只能出现在帧结束之后。
+    assert!(
+        body.starts_with(
+            "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_1\"}}\n\n: keepalive\n\n"
+        ),
+        "body: {body:?}"
+    );
+    assert!(
+        body.ends_with("\"total_tokens\":8}}}\n\n"),
+        "body: {body:?}"
+    );
+    let collector = collector.lock().expect("collector");
+    assert!(collector.saw_terminal);
+    assert!(collector.terminal_error.is_none());
+    assert_eq!(collector.usage.input_tokens, Some(3));
+    assert_eq!(collector.usage.output_tokens, Some(5));
+}
+
+#[test]
+fn anthropic_sse_reader_emits_ping_events_while_upstream_idles() {
+    let upstream = pausing_upstream_with_heartbeat(vec![
+        (
+            Duration::ZERO,
+            "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_1\",\"model\":\"gpt-5.3-codex\"}}\n\n",
+        ),
+        (
+            Duration::from_millis(150),
+            "data: {\"type\":\"response.output_text.delta\",\"delta\":\"hi\"}\n\n",
+        ),
+        (
+            Duration::ZERO,
+            "data: {\"type\":\"response.completed\",\"response\":{\"usage\":{\"input_tokens\":3,\"output_tokens\":1,\"total_tokens\":4}}}\n\n",
+        ),
+    ]);
+    let usage = Arc::new(Mutex::new(UpstreamResponseUsage::default()));
+    let mut reader = AnthropicSseReader::new(upstream, Arc::clone(&usage));
+    let mut body = String::new();
+    reader.read_to_string(&mut body).expect("read stream");
+
+    assert!(
+        body.contains("event: ping\ndata: {\"type\":\"ping\"}\n\n"),
+        "body: {body}"
+    );
+    assert!(body.contains("event: message_stop"), "body: {body}");
+    let usage = usage.lock().expect("usage");
+    assert_eq!(usage.output_tokens, Some(1));
+    assert_eq!(usage.output_text.as_deref(), Some("hi"));
+}
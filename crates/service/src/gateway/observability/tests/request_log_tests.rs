@@ -1,108 +1,42 @@
-use super::estimate_cost_usd;
-
-fn assert_close(actual: f64, expected: f64) {
-    assert!(
-        (actual - expected).abs() < 1e-12,
-        "actual={actual}, expected={expected}"
-    );
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt5_family_prices() {
-    // 基准样本：输入 1000，缓存 200，输出 500
-    // gpt-5 系列：输入 1.25/M，缓存 0.125/M，输出 10/M
-    // => 非缓存输入 800*0.00125/1000 + 缓存 200*0.000125/1000 + 输出 500*0.01/1000
-    // => 0.006025
-    let expected = 0.006025_f64;
-    let models = [
-        "gpt-5",
-        "gpt-5-codex",
-        "gpt-5.1",
-        "gpt-5.1-codex",
-        "gpt-5.1-codex-max",
-    ];
-    for model in models {
-        let actual = estimate_cost_usd(Some(model), Some(1000), Some(200), Some(500));
-        assert_close(actual, expected);
-    }
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt54_prices() {
-    // gpt-5.4：输入 2.5/M，缓存 0.25/M，输出 15/M
-    // 样本：输入 1000，缓存 200，输出 500
-    // => 非缓存输入 800*0.0025/1000 + 缓存 200*0.00025/1000 + 输出 500*0.015/1000
-    // => 0.00955
-    let actual = estimate_cost_usd(Some("gpt-5.4"), Some(1000), Some(200), Some(500));
-    assert_close(actual, 0.00955);
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt54_large_context_prices() {
-    // gpt-5.4：输入超过 272K 时，输入 5/M，缓存 0.5/M，输出 22.5/M
-    // 样本：输入 300000，缓存 50000，输出 100000
-    // => 非缓存输入 250000*0.005/1000 + 缓存 50000*0.0005/1000 + 输出 100000*0.0225/1000
-    // => 3.525
-    let actual = estimate_cost_usd(Some("gpt-5.4"), Some(300_000), Some(50_000), Some(100_000));
-    assert_close(actual, 3.525);
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt54_pro_prices() {
-    // gpt-5.4-pro：输入 30/M，输出 180/M；无缓存折扣时按输入同价处理。
-    let actual = estimate_cost_usd(Some("gpt-5.4-pro"), Some(1000), Some(200), Some(500));
-    assert_close(actual, 0.12);
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt54_pro_large_context_prices() {
-    // gpt-5.4-pro：输入超过 272K 时，输入 60/M，输出 270/M。
-    let actual = estimate_cost_usd(
-        Some("gpt-5.4-pro"),
-        Some(300_000),
-        Some(50_000),
-        Some(100_000),
-    );
-    assert_close(actual, 45.0);
-}
-
-#[test]
-fn estimate_cost_matches_openai_gpt5_mini_and_52_prices() {
-    // mini：输入 0.25/M，缓存 0.025/M，输出 2/M
-    // 样本同上 => 0.001205
-    let mini_cost = estimate_cost_usd(Some("gpt-5.1-codex-mini"), Some(1000), Some(200), Some(500));
-    assert_close(mini_cost, 0.001205);
-
-    // 5.2：输入 1.75/M，缓存 0.175/M，输出 14/M
-    // 样本同上 => 0.008435
-    let v52_models = ["gpt-5.2", "gpt-5.2-codex"];
-    for model in v52_models {
-        let actual = estimate_cost_usd(Some(model), Some(1000), Some(200), Some(500));
-        assert_close(actual, 0.008435);
-    }
-}
-
-#[test]
-fn estimate_cost_uses_cached_input_rate_for_gpt_5_1_codex() {
-    // 非缓存输入 800k * 1.25 + 缓存输入 200k * 0.125 + 输出 500k * 10
-    // 期望：1 + 0.025 + 5 = 6.025 USD
-    let actual = estimate_cost_usd(
-        Some("gpt-5.1-codex"),
-        Some(1_000_000),
-        Some(200_000),
-        Some(500_000),
-    );
-    assert_close(actual, 6.025);
-}
-
-#[test]
-fn estimate_cost_falls_back_gpt_5_3_codex_to_gpt_5_2_codex_price() {
-    // gpt-5.3-codex 暂按 gpt-5.2-codex：输入 1.75 + 输出 14.00
-    let actual = estimate_cost_usd(
-        Some("gpt-5.3-codex"),
-        Some(1_000_000),
-        Some(0),
-        Some(1_000_000),
-    );
-    assert_close(actual, 15.75);
+use super::{fill_missing_usage_from_estimate, RequestLogUsage};
+
+#[test]
+fn fill_missing_usage_estimates_input_and_output_tokens() {
+    let body = br#"{"model":"gpt-5.3-codex","instructions":"be brief","input":"hello there"}"#;
+    let usage =
+        fill_missing_usage_from_estimate(RequestLogUsage::default(), body, Some("general kenobi"));
+    let input_tokens = usage.input_tokens.expect("input estimated");
+    let output_tokens = usage.output_tokens.expect("output estimated");
+    assert!(input_tokens > 0);
+    assert!(output_tokens > 0);
+    assert_eq!(usage.total_tokens, Some(input_tokens + output_tokens));
+}
+
+#[test]
+fn fill_missing_usage_keeps_upstream_reported_values() {
+    let reported = RequestLogUsage {
+        input_tokens: Some(42),
+        cached_input_tokens: Some(0),
+        output_tokens: Some(7),
+        total_tokens: Some(49),
+        reasoning_output_tokens: Some(0),
+    };
+    let usage = fill_missing_usage_from_estimate(reported, br#"{"input":"hello"}"#, Some("x"));
+    assert_eq!(usage.input_tokens, Some(42));
+    assert_eq!(usage.output_tokens, Some(7));
+    assert_eq!(usage.total_tokens, Some(49));
+}
+
+#[test]
+fn fill_missing_usage_keeps_upstream_total_when_output_is_estimated() {
+    let reported = RequestLogUsage {
+        input_tokens: Some(42),
+        total_tokens: Some(120),
+        ..RequestLogUsage::default()
+    };
+    let usage =
+        fill_missing_usage_from_estimate(reported, br#"{"input":"hello"}"#, Some("general kenobi"));
+    assert!(usage.output_tokens.expect("output estimated") > 0);
+    assert_eq!(usage.input_tokens, Some(42));
+    assert_eq!(usage.total_tokens, Some(120));
 }
@@ -295,6 +295,18 @@ pub(crate) fn log_request_gate_acquired(
     append_trace_line(line, false);
 }
 
+pub(crate) fn log_admission_queue(trace_id: &str, key_id: &str, outcome: &str, wait_ms: u64) {
+    let ts = now_ts();
+    let line = format!(
+        "ts={ts} event=ADMISSION_QUEUE trace_id={} key_id={} outcome={} wait_ms={}",
+        sanitize_text(trace_id),
+        sanitize_text(key_id),
+        sanitize_text(outcome),
+        wait_ms,
+    );
+    append_trace_line(line, false);
+}
+
 pub(crate) fn log_request_gate_skip(trace_id: &str, reason: &str) {
     let ts = now_ts();
     let line = format!(
@@ -350,6 +362,37 @@ pub(crate) fn log_candidate_pool(
     append_trace_line(line, false);
 }
 
+pub(crate) fn log_route_affinity(
+    trace_id: &str,
+    affinity_key: &str,
+    decision: &str,
+    account_id: &str,
+    reason: Option<&str>,
+) {
+    let ts = now_ts();
+    let line = format!(
+        "ts={ts} event=ROUTE_AFFINITY trace_id={} affinity_key={} decision={} account_id={} reason={}",
+        sanitize_text(trace_id),
+        sanitize_text(affinity_key),
+        sanitize_text(decision),
+        sanitize_text(account_id),
+        sanitize_text(reason.unwrap_or("-")),
+    );
+    append_trace_line(line, false);
+}
+
+pub(crate) fn log_model_fallback(trace_id: &str, from_model: &str, to_model: &str, reason: &str) {
+    let ts = now_ts();
+    let line = format!(
+        "ts={ts} event=MODEL_FALLBACK trace_id={} from_model={} to_model={} reason={}",
+        sanitize_text(trace_id),
+        sanitize_text(from_model),
+        sanitize_text(to_model),
+        sanitize_text(reason),
+    );
+    append_trace_line(line, false);
+}
+
 pub(crate) fn log_candidate_skip(
     trace_id: &str,
     idx: usize,
@@ -369,6 +412,37 @@ pub(crate) fn log_candidate_skip(
     append_trace_line(line, false);
 }
 
+pub(crate) fn log_hedge_attempt(trace_id: &str, role: &str, account_id: &str, outcome: &str) {
+    let ts = now_ts();
+    let line = format!(
+        "ts={ts} event=HEDGE_ATTEMPT trace_id={} role={} account_id={} outcome={}",
+        sanitize_text(trace_id),
+        sanitize_text(role),
+        sanitize_text(account_id),
+        sanitize_text(outcome),
+    );
+    append_trace_line(line, false);
+}
+
+pub(crate) fn log_stream_failover(
+    trace_id: &str,
+    from_account_id: &str,
+    to_account_id: Option<&str>,
+    outcome: &str,
+    reason: &str,
+) {
+    let ts = now_ts();
+    let line = format!(
+        "ts={ts} event=STREAM_FAILOVER trace_id={} from_account_id={} to_account_id={} outcome={} reason={}",
+        sanitize_text(trace_id),
+        sanitize_text(from_account_id),
+        sanitize_text(to_account_id.unwrap_or("-")),
+        sanitize_text(outcome),
+        sanitize_text(reason),
+    );
+    append_trace_line(line, false);
+}
+
 pub(crate) fn log_attempt_result(
     trace_id: &str,
     account_id: &str,
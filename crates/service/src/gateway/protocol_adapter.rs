@@ -1,4 +1,6 @@
-use crate::apikey_profile::{PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_OPENAI_COMPAT};
+use crate::apikey_profile::{
+    PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_GEMINI_NATIVE, PROTOCOL_OPENAI_COMPAT,
+};
 use serde_json::Value;
 
 mod prompt_cache;
@@ -16,6 +18,12 @@ pub(super) enum ResponseAdapter {
     OpenAIChatCompletionsSse,
     OpenAICompletionsJson,
     OpenAICompletionsSse,
+    GeminiJson,
+    GeminiSse,
+    OllamaChatJson,
+    OllamaChatNdjson,
+    OllamaGenerateJson,
+    OllamaGenerateNdjson,
 }
 
 #[derive(Debug)]
@@ -26,6 +34,50 @@ pub(super) struct AdaptedGatewayRequest {
     pub(super) tool_name_restore_map: ToolNameRestoreMap,
 }
 
+/// 解析 `/v1beta/models/{model}:generateContent` 形式的 Gemini 路径，返回 (model, is_stream)。
+pub(super) fn parse_gemini_generate_content_path(path: &str) -> Option<(String, bool)> {
+    let path = path.split('?').next().unwrap_or(path);
+    let rest = ["/v1beta/models/", "/v1/models/", "/v1alpha/models/"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))?;
+    let (model, action) = rest.rsplit_once(':')?;
+    let model = model.trim();
+    if model.is_empty() || model.contains('/') {
+        return None;
+    }
+    match action {
+        "generateContent" => Some((model.to_string(), false)),
+        "streamGenerateContent" => Some((model.to_string(), true)),
+        _ => None,
+    }
+}
+
+/// Ollama 兼容入口（ollama_compat）的端点；chat/generate 走上游 Responses，tags/show 由网关本地应答。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OllamaEndpoint {
+    Chat,
+    Generate,
+    Tags,
+    Show,
+}
+
+pub(super) fn parse_ollama_compat_path(path: &str) -> Option<OllamaEndpoint> {
+    let path = path.split('?').next().unwrap_or(path);
+    match path.trim_end_matches('/') {
+        "/api/chat" => Some(OllamaEndpoint::Chat),
+        "/api/generate" => Some(OllamaEndpoint::Generate),
+        "/api/tags" => Some(OllamaEndpoint::Tags),
+        "/api/show" => Some(OllamaEndpoint::Show),
+        _ => None,
+    }
+}
+
+/// Ollama 客户端习惯在模型名后带 `:latest` 标签，上游与模型缓存都不认识，统一去掉。
+pub(super) fn strip_ollama_model_tag(model: &str) -> &str {
+    let model = model.trim();
+    model.strip_suffix(":latest").unwrap_or(model)
+}
+
 pub(super) fn adapt_request_for_protocol(
     protocol_type: &str,
     path: &str,
@@ -76,6 +128,55 @@ pub(super) fn adapt_request_for_protocol(
         });
     }
 
+    if protocol_type == PROTOCOL_OPENAI_COMPAT {
+        let ollama_request = match parse_ollama_compat_path(path) {
+            Some(OllamaEndpoint::Chat) => Some((
+                request_mapping::convert_ollama_chat_request(&body)?,
+                ResponseAdapter::OllamaChatNdjson,
+                ResponseAdapter::OllamaChatJson,
+            )),
+            Some(OllamaEndpoint::Generate) => Some((
+                request_mapping::convert_ollama_generate_request(&body)?,
+                ResponseAdapter::OllamaGenerateNdjson,
+                ResponseAdapter::OllamaGenerateJson,
+            )),
+            _ => None,
+        };
+        if let Some(((adapted_body, request_stream), stream_adapter, json_adapter)) = ollama_request
+        {
+            // 中文注释：与 Gemini 入口一致，上游统一 stream=true，下游是否逐行输出 NDJSON 由客户端 stream 决定。
+            return Ok(AdaptedGatewayRequest {
+                path: "/v1/responses".to_string(),
+                body: adapted_body,
+                response_adapter: if request_stream {
+                    stream_adapter
+                } else {
+                    json_adapter
+                },
+                tool_name_restore_map: ToolNameRestoreMap::new(),
+            });
+        }
+    }
+
+    if protocol_type == PROTOCOL_GEMINI_NATIVE {
+        if let Some((model, request_stream)) = parse_gemini_generate_content_path(path) {
+            let adapted_body =
+                request_mapping::convert_gemini_generate_content_request(&body, &model)?;
+            // 中文注释：streamGenerateContent 统一按 SSE（alt=sse）回写，
+            // Gemini CLI / google-genai SDK 默认即使用该模式。
+            return Ok(AdaptedGatewayRequest {
+                path: "/v1/responses".to_string(),
+                body: adapted_body,
+                response_adapter: if request_stream {
+                    ResponseAdapter::GeminiSse
+                } else {
+                    ResponseAdapter::GeminiJson
+                },
+                tool_name_restore_map: ToolNameRestoreMap::new(),
+            });
+        }
+    }
+
     if protocol_type != PROTOCOL_ANTHROPIC_NATIVE {
         return Ok(AdaptedGatewayRequest {
             path: path.to_string(),
@@ -137,6 +238,25 @@ pub(super) fn build_anthropic_error_body(message: &str) -> Vec<u8> {
     response_conversion::build_anthropic_error_body(message)
 }
 
+pub(super) fn build_gemini_error_body(status_code: u16, message: &str) -> Vec<u8> {
+    response_conversion::build_gemini_error_body(status_code, message)
+}
+
+pub(super) fn convert_gemini_stream_chunk(value: &Value) -> Option<Value> {
+    response_conversion::convert_gemini_stream_chunk(value)
+}
+
+pub(super) fn build_ollama_error_body(message: &str) -> Vec<u8> {
+    response_conversion::build_ollama_error_body(message)
+}
+
+pub(super) fn convert_ollama_stream_chunk(
+    adapter: ResponseAdapter,
+    value: &Value,
+) -> Option<Value> {
+    response_conversion::convert_ollama_stream_chunk(adapter, value)
+}
+
 pub(super) fn convert_openai_completions_stream_chunk(value: &Value) -> Option<Value> {
     response_conversion::convert_openai_completions_stream_chunk(value)
 }
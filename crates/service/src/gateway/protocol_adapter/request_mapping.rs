@@ -3,6 +3,12 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use super::prompt_cache;
 
+mod gemini;
+mod ollama;
+
+pub(super) use gemini::convert_gemini_generate_content_request;
+pub(super) use ollama::{convert_ollama_chat_request, convert_ollama_generate_request};
+
 const DEFAULT_ANTHROPIC_MODEL: &str = "gpt-5.3-codex";
 const DEFAULT_ANTHROPIC_REASONING: &str = "high";
 const DEFAULT_ANTHROPIC_INSTRUCTIONS: &str =
@@ -0,0 +1,482 @@
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+
+const DEFAULT_GEMINI_MODEL: &str = "gpt-5.3-codex";
+const DEFAULT_GEMINI_REASONING: &str = "medium";
+const DEFAULT_GEMINI_INSTRUCTIONS: &str =
+    "You are Codex, a coding assistant that responds clearly and safely.";
+
+/// Gemini 原生 API 同时接受 camelCase 与 snake_case 字段名。
+fn gemini_field<'a>(obj: &'a Map<String, Value>, camel: &str, snake: &str) -> Option<&'a Value> {
+    obj.get(camel).or_else(|| obj.get(snake))
+}
+
+fn resolve_gemini_upstream_model(requested_model: &str) -> String {
+    let model = requested_model.trim().trim_start_matches("models/").trim();
+    let lower = model.to_ascii_lowercase();
+    if !model.is_empty() && (lower.contains("codex") || lower.starts_with("gpt-")) {
+        return model.to_string();
+    }
+    DEFAULT_GEMINI_MODEL.to_string()
+}
+
+fn resolve_gemini_reasoning_effort(generation_config: Option<&Map<String, Value>>) -> String {
+    let thinking_config = generation_config
+        .and_then(|config| gemini_field(config, "thinkingConfig", "thinking_config"))
+        .and_then(Value::as_object);
+    let Some(thinking_config) = thinking_config else {
+        return DEFAULT_GEMINI_REASONING.to_string();
+    };
+    if let Some(level) = gemini_field(thinking_config, "thinkingLevel", "thinking_level")
+        .and_then(Value::as_str)
+        .and_then(crate::reasoning_effort::normalize_reasoning_effort)
+    {
+        return level.to_string();
+    }
+    // 中文注释：thinkingBudget 为 token 预算；-1 表示动态预算，按默认档位处理。
+    let effort = match gemini_field(thinking_config, "thinkingBudget", "thinking_budget")
+        .and_then(Value::as_i64)
+    {
+        Some(budget) if budget < 0 => DEFAULT_GEMINI_REASONING,
+        Some(budget) if budget <= 1024 => "low",
+        Some(budget) if budget <= 8192 => "medium",
+        Some(_) => "high",
+        None => DEFAULT_GEMINI_REASONING,
+    };
+    effort.to_string()
+}
+
+fn normalize_gemini_schema(value: &Value) -> Value {
+    match value {
+        Value::Object(obj) => {
+            let mut out = Map::new();
+            for (key, item) in obj {
+                if key == "type" {
+                    if let Some(kind) = item.as_str() {
+                        out.insert(key.clone(), Value::String(kind.to_ascii_lowercase()));
+                        continue;
+                    }
+                }
+                out.insert(key.clone(), normalize_gemini_schema(item));
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(normalize_gemini_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+fn extract_gemini_parts_text(value: &Value) -> String {
+    if let Some(text) = value.as_str() {
+        return text.to_string();
+    }
+    let parts = value
+        .get("parts")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_else(|| value.as_array().cloned().unwrap_or_default());
+    parts
+        .iter()
+        .filter_map(|part| part.get("text").and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn map_gemini_inline_data(obj: &Map<String, Value>) -> Option<Value> {
+    let mime_type = gemini_field(obj, "mimeType", "mime_type")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("image/png");
+    let data = obj
+        .get("data")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    if mime_type.starts_with("image/") {
+        return Some(json!({
+            "type": "input_image",
+            "image_url": format!("data:{mime_type};base64,{data}"),
+        }));
+    }
+    Some(json!({
+        "type": "input_file",
+        "filename": "inline_data",
+        "file_data": format!("data:{mime_type};base64,{data}"),
+    }))
+}
+
+fn map_gemini_file_data(obj: &Map<String, Value>) -> Option<Value> {
+    let uri = gemini_field(obj, "fileUri", "file_uri")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    let mime_type = gemini_field(obj, "mimeType", "mime_type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if mime_type.starts_with("image/") || mime_type.is_empty() {
+        return Some(json!({
+            "type": "input_image",
+            "image_url": uri,
+        }));
+    }
+    Some(json!({
+        "type": "input_file",
+        "file_url": uri,
+    }))
+}
+
+#[derive(Default)]
+struct GeminiCallIdTracker {
+    next_seq: usize,
+    // 中文注释：Gemini 旧版协议的 functionCall/functionResponse 不带 id，只能按函数名先进先出配对。
+    pending_by_name: BTreeMap<String, VecDeque<String>>,
+}
+
+impl GeminiCallIdTracker {
+    fn register_call(&mut self, name: &str, explicit_id: Option<&str>) -> String {
+        let call_id = match explicit_id {
+            Some(id) => id.to_string(),
+            None => {
+                self.next_seq += 1;
+                format!("call_gemini_{}", self.next_seq)
+            }
+        };
+        self.pending_by_name
+            .entry(name.to_string())
+            .or_default()
+            .push_back(call_id.clone());
+        call_id
+    }
+
+    fn resolve_response(&mut self, name: &str, explicit_id: Option<&str>) -> String {
+        if let Some(id) = explicit_id {
+            if let Some(queue) = self.pending_by_name.get_mut(name) {
+                queue.retain(|pending| pending != id);
+            }
+            return id.to_string();
+        }
+        if let Some(call_id) = self
+            .pending_by_name
+            .get_mut(name)
+            .and_then(VecDeque::pop_front)
+        {
+            return call_id;
+        }
+        self.next_seq += 1;
+        format!("call_gemini_{}", self.next_seq)
+    }
+}
+
+fn flush_gemini_message(input_items: &mut Vec<Value>, role: &str, pending_parts: &mut Vec<Value>) {
+    if pending_parts.is_empty() {
+        return;
+    }
+    input_items.push(json!({
+        "type": "message",
+        "role": role,
+        "content": std::mem::take(pending_parts),
+    }));
+}
+
+fn append_gemini_content(
+    input_items: &mut Vec<Value>,
+    content: &Map<String, Value>,
+    call_ids: &mut GeminiCallIdTracker,
+) -> Result<(), String> {
+    let role = match content
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or("user")
+    {
+        "model" | "assistant" => "assistant",
+        "user" | "function" | "tool" => "user",
+        other => return Err(format!("unsupported gemini content role: {other}")),
+    };
+    let text_type = if role == "assistant" {
+        "output_text"
+    } else {
+        "input_text"
+    };
+    let Some(parts) = content.get("parts").and_then(Value::as_array) else {
+        return Ok(());
+    };
+
+    let mut pending_parts = Vec::new();
+    for part in parts {
+        let Some(part_obj) = part.as_object() else {
+            continue;
+        };
+        // 中文注释：thought 部分是模型思考摘要，回灌给上游没有意义，直接丢弃。
+        if part_obj.get("thought").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        if let Some(text) = part_obj.get("text").and_then(Value::as_str) {
+            if !text.is_empty() {
+                pending_parts.push(json!({ "type": text_type, "text": text }));
+            }
+            continue;
+        }
+        if let Some(function_call) =
+            gemini_field(part_obj, "functionCall", "function_call").and_then(Value::as_object)
+        {
+            let name = function_call
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| "gemini functionCall name is required".to_string())?;
+            flush_gemini_message(input_items, role, &mut pending_parts);
+            let call_id = call_ids.register_call(
+                name,
+                function_call
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .filter(|value| !value.trim().is_empty()),
+            );
+            let arguments = function_call
+                .get("args")
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            input_items.push(json!({
+                "type": "function_call",
+                "call_id": call_id,
+                "name": name,
+                "arguments": serde_json::to_string(&arguments).unwrap_or_else(|_| "{}".to_string()),
+            }));
+            continue;
+        }
+        if let Some(function_response) =
+            gemini_field(part_obj, "functionResponse", "function_response")
+                .and_then(Value::as_object)
+        {
+            let name = function_response
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .unwrap_or_default();
+            flush_gemini_message(input_items, role, &mut pending_parts);
+            let call_id = call_ids.resolve_response(
+                name,
+                function_response
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .filter(|value| !value.trim().is_empty()),
+            );
+            let output = match function_response.get("response") {
+                Some(Value::String(text)) => text.clone(),
+                Some(value) => serde_json::to_string(value).unwrap_or_default(),
+                None => String::new(),
+            };
+            input_items.push(json!({
+                "type": "function_call_output",
+                "call_id": call_id,
+                "output": output,
+            }));
+            continue;
+        }
+        if role != "user" {
+            continue;
+        }
+        if let Some(item) = gemini_field(part_obj, "inlineData", "inline_data")
+            .and_then(Value::as_object)
+            .and_then(map_gemini_inline_data)
+        {
+            pending_parts.push(item);
+            continue;
+        }
+        if let Some(item) = gemini_field(part_obj, "fileData", "file_data")
+            .and_then(Value::as_object)
+            .and_then(map_gemini_file_data)
+        {
+            pending_parts.push(item);
+        }
+    }
+    flush_gemini_message(input_items, role, &mut pending_parts);
+    Ok(())
+}
+
+fn map_gemini_tools(tools: &[Value]) -> Vec<Value> {
+    let mut out = Vec::new();
+    for tool in tools {
+        let Some(tool_obj) = tool.as_object() else {
+            continue;
+        };
+        let Some(declarations) =
+            gemini_field(tool_obj, "functionDeclarations", "function_declarations")
+                .and_then(Value::as_array)
+        else {
+            continue;
+        };
+        for declaration in declarations {
+            let Some(declaration_obj) = declaration.as_object() else {
+                continue;
+            };
+            let Some(name) = declaration_obj
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+            else {
+                continue;
+            };
+            let mut mapped = Map::new();
+            mapped.insert("type".to_string(), Value::String("function".to_string()));
+            mapped.insert("name".to_string(), Value::String(name.to_string()));
+            if let Some(description) = declaration_obj.get("description") {
+                mapped.insert("description".to_string(), description.clone());
+            }
+            let parameters = gemini_field(
+                declaration_obj,
+                "parametersJsonSchema",
+                "parameters_json_schema",
+            )
+            .cloned()
+            .or_else(|| {
+                declaration_obj
+                    .get("parameters")
+                    .map(normalize_gemini_schema)
+            })
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+            mapped.insert("parameters".to_string(), parameters);
+            out.push(Value::Object(mapped));
+        }
+    }
+    out
+}
+
+fn map_gemini_tool_config(tool_config: &Map<String, Value>) -> Option<Value> {
+    let calling_config = gemini_field(
+        tool_config,
+        "functionCallingConfig",
+        "function_calling_config",
+    )?
+    .as_object()?;
+    let mode = calling_config
+        .get("mode")
+        .and_then(Value::as_str)
+        .unwrap_or("AUTO")
+        .to_ascii_uppercase();
+    match mode.as_str() {
+        "NONE" => Some(Value::String("none".to_string())),
+        "ANY" | "VALIDATED" => {
+            let allowed = gemini_field(
+                calling_config,
+                "allowedFunctionNames",
+                "allowed_function_names",
+            )
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+            .unwrap_or_default();
+            if allowed.len() == 1 {
+                return Some(json!({ "type": "function", "name": allowed[0] }));
+            }
+            Some(Value::String("required".to_string()))
+        }
+        _ => Some(Value::String("auto".to_string())),
+    }
+}
+
+fn map_gemini_response_format(generation_config: &Map<String, Value>) -> Option<Value> {
+    let schema = gemini_field(
+        generation_config,
+        "responseJsonSchema",
+        "response_json_schema",
+    )
+    .cloned()
+    .or_else(|| {
+        gemini_field(generation_config, "responseSchema", "response_schema")
+            .map(normalize_gemini_schema)
+    });
+    if let Some(schema) = schema {
+        return Some(json!({
+            "type": "json_schema",
+            "name": "response",
+            "schema": schema,
+            "strict": false,
+        }));
+    }
+    let is_json = gemini_field(generation_config, "responseMimeType", "response_mime_type")
+        .and_then(Value::as_str)
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if is_json {
+        return Some(json!({ "type": "json_object" }));
+    }
+    None
+}
+
+pub(in super::super) fn convert_gemini_generate_content_request(
+    body: &[u8],
+    requested_model: &str,
+) -> Result<Vec<u8>, String> {
+    let payload: Value =
+        serde_json::from_slice(body).map_err(|_| "invalid gemini request json".to_string())?;
+    let Some(obj) = payload.as_object() else {
+        return Err("gemini request body must be an object".to_string());
+    };
+
+    let contents = obj
+        .get("contents")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "gemini contents field is required".to_string())?;
+    let mut input_items = Vec::new();
+    let mut call_ids = GeminiCallIdTracker::default();
+    for content in contents {
+        let Some(content_obj) = content.as_object() else {
+            return Err("invalid gemini content item".to_string());
+        };
+        append_gemini_content(&mut input_items, content_obj, &mut call_ids)?;
+    }
+
+    let mut out = Map::new();
+    out.insert(
+        "model".to_string(),
+        Value::String(resolve_gemini_upstream_model(requested_model)),
+    );
+    let instructions = gemini_field(obj, "systemInstruction", "system_instruction")
+        .map(extract_gemini_parts_text)
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| DEFAULT_GEMINI_INSTRUCTIONS.to_string());
+    out.insert("instructions".to_string(), Value::String(instructions));
+    out.insert("input".to_string(), Value::Array(input_items));
+
+    let generation_config =
+        gemini_field(obj, "generationConfig", "generation_config").and_then(Value::as_object);
+    out.insert(
+        "reasoning".to_string(),
+        json!({
+            "effort": resolve_gemini_reasoning_effort(generation_config),
+        }),
+    );
+    let text_format = generation_config
+        .and_then(map_gemini_response_format)
+        .unwrap_or_else(|| json!({ "type": "text" }));
+    out.insert("text".to_string(), json!({ "format": text_format }));
+
+    if let Some(tools) = obj.get("tools").and_then(Value::as_array) {
+        let mapped_tools = map_gemini_tools(tools);
+        if !mapped_tools.is_empty() {
+            out.insert("tools".to_string(), Value::Array(mapped_tools));
+            let tool_choice = gemini_field(obj, "toolConfig", "tool_config")
+                .and_then(Value::as_object)
+                .and_then(map_gemini_tool_config)
+                .unwrap_or_else(|| Value::String("auto".to_string()));
+            out.insert("tool_choice".to_string(), tool_choice);
+        }
+    }
+
+    // 说明：与 Claude 入口一致，上游统一 stream=true，由网关侧按需聚合为 Gemini JSON。
+    out.insert("stream".to_string(), Value::Bool(true));
+    out.insert("parallel_tool_calls".to_string(), Value::Bool(true));
+    out.insert("store".to_string(), Value::Bool(false));
+    out.insert(
+        "include".to_string(),
+        Value::Array(vec![Value::String(
+            "reasoning.encrypted_content".to_string(),
+        )]),
+    );
+
+    serde_json::to_vec(&Value::Object(out))
+        .map_err(|err| format!("convert gemini request failed: {err}"))
+}
@@ -0,0 +1,360 @@
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
+
+const DEFAULT_OLLAMA_MODEL: &str = "gpt-5.3-codex";
+const DEFAULT_OLLAMA_REASONING: &str = "medium";
+const DEFAULT_OLLAMA_INSTRUCTIONS: &str =
+    "You are Codex, a coding assistant that responds clearly and safely.";
+
+fn resolve_ollama_upstream_model(obj: &Map<String, Value>) -> String {
+    let model = obj
+        .get("model")
+        .and_then(Value::as_str)
+        .map(super::super::strip_ollama_model_tag)
+        .unwrap_or_default();
+    if model.is_empty() {
+        return DEFAULT_OLLAMA_MODEL.to_string();
+    }
+    model.to_string()
+}
+
+/// Ollama 的 `think` 既可以是布尔值，也可以是 gpt-oss 风格的 low / medium / high。
+fn resolve_ollama_reasoning_effort(obj: &Map<String, Value>) -> String {
+    match obj.get("think") {
+        Some(Value::Bool(true)) => "high".to_string(),
+        Some(Value::Bool(false)) => "low".to_string(),
+        Some(Value::String(level)) => crate::reasoning_effort::normalize_reasoning_effort(level)
+            .unwrap_or(DEFAULT_OLLAMA_REASONING)
+            .to_string(),
+        _ => DEFAULT_OLLAMA_REASONING.to_string(),
+    }
+}
+
+/// Ollama 的 images 是不带 MIME 的裸 base64，这里按文件头魔数猜测图片类型。
+fn guess_ollama_image_mime(data: &str) -> &'static str {
+    if data.starts_with("/9j/") {
+        "image/jpeg"
+    } else if data.starts_with("R0lGOD") {
+        "image/gif"
+    } else if data.starts_with("UklGR") {
+        "image/webp"
+    } else {
+        "image/png"
+    }
+}
+
+fn map_ollama_images(value: Option<&Value>) -> Vec<Value> {
+    let Some(images) = value.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    images
+        .iter()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .filter(|data| !data.is_empty())
+        .map(|data| {
+            let image_url = if data.starts_with("data:") {
+                data.to_string()
+            } else {
+                format!("data:{};base64,{data}", guess_ollama_image_mime(data))
+            };
+            json!({ "type": "input_image", "image_url": image_url })
+        })
+        .collect()
+}
+
+fn build_ollama_user_message(text: &str, images: Option<&Value>) -> Option<Value> {
+    let mut content = Vec::new();
+    if !text.is_empty() {
+        content.push(json!({ "type": "input_text", "text": text }));
+    }
+    content.extend(map_ollama_images(images));
+    if content.is_empty() {
+        return None;
+    }
+    Some(json!({
+        "type": "message",
+        "role": "user",
+        "content": content,
+    }))
+}
+
+#[derive(Default)]
+struct OllamaCallIdTracker {
+    next_seq: usize,
+    // 中文注释：Ollama 的 tool_calls / tool 消息通常不带 id，只能按工具名（缺省时按顺序）先进先出配对。
+    pending: VecDeque<(String, String)>,
+}
+
+impl OllamaCallIdTracker {
+    fn next_id(&mut self) -> String {
+        self.next_seq += 1;
+        format!("call_ollama_{}", self.next_seq)
+    }
+
+    fn register_call(&mut self, name: &str, explicit_id: Option<&str>) -> String {
+        let call_id = match explicit_id {
+            Some(id) => id.to_string(),
+            None => self.next_id(),
+        };
+        self.pending.push_back((name.to_string(), call_id.clone()));
+        call_id
+    }
+
+    fn resolve_response(&mut self, name: Option<&str>, explicit_id: Option<&str>) -> String {
+        if let Some(id) = explicit_id {
+            self.pending.retain(|(_, pending)| pending != id);
+            return id.to_string();
+        }
+        let position = match name {
+            Some(name) => self
+                .pending
+                .iter()
+                .position(|(pending_name, _)| pending_name == name),
+            None => (!self.pending.is_empty()).then_some(0),
+        };
+        match position.and_then(|index| self.pending.remove(index)) {
+            Some((_, call_id)) => call_id,
+            None => self.next_id(),
+        }
+    }
+}
+
+fn non_empty_str<'a>(obj: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    obj.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn append_ollama_message(
+    input_items: &mut Vec<Value>,
+    instructions: &mut Vec<String>,
+    message: &Map<String, Value>,
+    call_ids: &mut OllamaCallIdTracker,
+) -> Result<(), String> {
+    let role = message
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or("user");
+    let content = message
+        .get("content")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    match role {
+        "system" => {
+            if !content.trim().is_empty() {
+                instructions.push(content.to_string());
+            }
+        }
+        "user" => {
+            if let Some(item) = build_ollama_user_message(content, message.get("images")) {
+                input_items.push(item);
+            }
+        }
+        "assistant" => {
+            if !content.is_empty() {
+                input_items.push(json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": content }],
+                }));
+            }
+            let tool_calls = message
+                .get("tool_calls")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for tool_call in tool_calls {
+                let Some(function) = tool_call.get("function").and_then(Value::as_object) else {
+                    continue;
+                };
+                let name = non_empty_str(function, "name")
+                    .ok_or_else(|| "ollama tool_call function name is required".to_string())?;
+                let call_id = call_ids.register_call(
+                    name,
+                    tool_call
+                        .as_object()
+                        .and_then(|obj| non_empty_str(obj, "id")),
+                );
+                let arguments = match function.get("arguments") {
+                    Some(Value::String(raw)) => raw.clone(),
+                    Some(value) => {
+                        serde_json::to_string(value).unwrap_or_else(|_| "{}".to_string())
+                    }
+                    None => "{}".to_string(),
+                };
+                input_items.push(json!({
+                    "type": "function_call",
+                    "call_id": call_id,
+                    "name": name,
+                    "arguments": arguments,
+                }));
+            }
+        }
+        "tool" => {
+            let call_id = call_ids.resolve_response(
+                non_empty_str(message, "tool_name").or_else(|| non_empty_str(message, "name")),
+                non_empty_str(message, "tool_call_id"),
+            );
+            input_items.push(json!({
+                "type": "function_call_output",
+                "call_id": call_id,
+                "output": content,
+            }));
+        }
+        other => return Err(format!("unsupported ollama message role: {other}")),
+    }
+    Ok(())
+}
+
+fn map_ollama_tools(tools: &[Value]) -> Vec<Value> {
+    tools
+        .iter()
+        .filter_map(|tool| {
+            let function = tool.get("function").and_then(Value::as_object)?;
+            let name = non_empty_str(function, "name")?;
+            let mut mapped = Map::new();
+            mapped.insert("type".to_string(), Value::String("function".to_string()));
+            mapped.insert("name".to_string(), Value::String(name.to_string()));
+            if let Some(description) = function.get("description") {
+                mapped.insert("description".to_string(), description.clone());
+            }
+            mapped.insert(
+                "parameters".to_string(),
+                function
+                    .get("parameters")
+                    .cloned()
+                    .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+            );
+            Some(Value::Object(mapped))
+        })
+        .collect()
+}
+
+/// `format` 为 `"json"` 时要求 JSON 输出，为对象时视为 JSON Schema。
+fn map_ollama_format(format: &Value) -> Option<Value> {
+    match format {
+        Value::String(kind) if kind.trim().eq_ignore_ascii_case("json") => {
+            Some(json!({ "type": "json_object" }))
+        }
+        Value::Object(_) => Some(json!({
+            "type": "json_schema",
+            "name": "response",
+            "schema": format,
+            "strict": false,
+        })),
+        _ => None,
+    }
+}
+
+fn build_ollama_responses_body(
+    obj: &Map<String, Value>,
+    instructions: Vec<String>,
+    input_items: Vec<Value>,
+) -> Result<Vec<u8>, String> {
+    let mut out = Map::new();
+    out.insert(
+        "model".to_string(),
+        Value::String(resolve_ollama_upstream_model(obj)),
+    );
+    let instructions = if instructions.is_empty() {
+        DEFAULT_OLLAMA_INSTRUCTIONS.to_string()
+    } else {
+        instructions.join("\n\n")
+    };
+    out.insert("instructions".to_string(), Value::String(instructions));
+    out.insert("input".to_string(), Value::Array(input_items));
+    out.insert(
+        "reasoning".to_string(),
+        json!({
+            "effort": resolve_ollama_reasoning_effort(obj),
+        }),
+    );
+    let text_format = obj
+        .get("format")
+        .and_then(map_ollama_format)
+        .unwrap_or_else(|| json!({ "type": "text" }));
+    out.insert("text".to_string(), json!({ "format": text_format }));
+
+    if let Some(tools) = obj.get("tools").and_then(Value::as_array) {
+        let mapped_tools = map_ollama_tools(tools);
+        if !mapped_tools.is_empty() {
+            out.insert("tools".to_string(), Value::Array(mapped_tools));
+            out.insert("tool_choice".to_string(), Value::String("auto".to_string()));
+        }
+    }
+
+    // 说明：options 里的采样参数（temperature / num_predict 等）与 Claude 入口一样不透传，优先稳定性；
+    // 上游统一 stream=true，由网关侧按需聚合为 Ollama JSON。
+    out.insert("stream".to_string(), Value::Bool(true));
+    out.insert("parallel_tool_calls".to_string(), Value::Bool(true));
+    out.insert("store".to_string(), Value::Bool(false));
+    out.insert(
+        "include".to_string(),
+        Value::Array(vec![Value::String(
+            "reasoning.encrypted_content".to_string(),
+        )]),
+    );
+
+    serde_json::to_vec(&Value::Object(out))
+        .map_err(|err| format!("convert ollama request failed: {err}"))
+}
+
+fn parse_ollama_request(body: &[u8]) -> Result<Map<String, Value>, String> {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(obj)) => Ok(obj),
+        Ok(_) => Err("ollama request body must be an object".to_string()),
+        Err(_) => Err("invalid ollama request json".to_string()),
+    }
+}
+
+/// Ollama 默认流式输出，只有显式 `stream: false` 才返回单个 JSON。
+fn ollama_request_stream(obj: &Map<String, Value>) -> bool {
+    obj.get("stream").and_then(Value::as_bool).unwrap_or(true)
+}
+
+/// 把 Ollama `/api/chat` 请求转换为 Responses 请求体，返回 (body, 客户端是否要求流式)。
+pub(in super::super) fn convert_ollama_chat_request(
+    body: &[u8],
+) -> Result<(Vec<u8>, bool), String> {
+    let obj = parse_ollama_request(body)?;
+    let messages = obj
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "ollama messages field is required".to_string())?;
+    let mut instructions = Vec::new();
+    let mut input_items = Vec::new();
+    let mut call_ids = OllamaCallIdTracker::default();
+    for message in messages {
+        let Some(message_obj) = message.as_object() else {
+            return Err("invalid ollama message item".to_string());
+        };
+        append_ollama_message(
+            &mut input_items,
+            &mut instructions,
+            message_obj,
+            &mut call_ids,
+        )?;
+    }
+    let adapted = build_ollama_responses_body(&obj, instructions, input_items)?;
+    Ok((adapted, ollama_request_stream(&obj)))
+}
+
+/// 把 Ollama `/api/generate` 请求（system + prompt + images）转换为 Responses 请求体。
+pub(in super::super) fn convert_ollama_generate_request(
+    body: &[u8],
+) -> Result<(Vec<u8>, bool), String> {
+    let obj = parse_ollama_request(body)?;
+    let prompt = obj
+        .get("prompt")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let input_item = build_ollama_user_message(prompt, obj.get("images"))
+        .ok_or_else(|| "ollama prompt field is required".to_string())?;
+    let instructions = non_empty_str(&obj, "system")
+        .map(|system| vec![system.to_string()])
+        .unwrap_or_default();
+    let adapted = build_ollama_responses_body(&obj, instructions, vec![input_item])?;
+    Ok((adapted, ollama_request_stream(&obj)))
+}
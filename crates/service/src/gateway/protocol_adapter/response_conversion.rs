@@ -3,7 +3,9 @@ use serde_json::{json, Map, Value};
 use crate::gateway::request_helpers::is_html_content_type;
 
 use super::ResponseAdapter;
+use gemini::{convert_openai_json_to_gemini, convert_openai_sse_to_gemini_json};
 use json_conversion::convert_openai_json_to_anthropic;
+use ollama::{convert_openai_json_to_ollama, convert_openai_sse_to_ollama_json};
 use openai_chat::{
     convert_openai_json_to_chat_completions, convert_openai_sse_to_chat_completions_json,
     extract_chat_content_text, extract_stream_event_text, map_openai_response_to_chat_completion,
@@ -12,7 +14,9 @@ use sse_conversion::{
     convert_anthropic_json_to_sse, convert_anthropic_sse_to_json, convert_openai_sse_to_anthropic,
 };
 
+mod gemini;
 mod json_conversion;
+mod ollama;
 mod openai_chat;
 mod sse_conversion;
 mod tool_mapping;
@@ -553,6 +557,33 @@ pub(super) fn adapt_upstream_response(
             }
             convert_openai_json_to_completions(body)
         }
+        ResponseAdapter::GeminiJson | ResponseAdapter::GeminiSse => {
+            if upstream_content_type.is_some_and(is_html_content_type) {
+                return Err("upstream returned html challenge".to_string());
+            }
+            let is_sse = upstream_content_type
+                .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+                .unwrap_or(false);
+            if is_sse || looks_like_sse_payload(body) {
+                return convert_openai_sse_to_gemini_json(body);
+            }
+            convert_openai_json_to_gemini(body)
+        }
+        ResponseAdapter::OllamaChatJson
+        | ResponseAdapter::OllamaChatNdjson
+        | ResponseAdapter::OllamaGenerateJson
+        | ResponseAdapter::OllamaGenerateNdjson => {
+            if upstream_content_type.is_some_and(is_html_content_type) {
+                return Err("upstream returned html challenge".to_string());
+            }
+            let is_sse = upstream_content_type
+                .map(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+                .unwrap_or(false);
+            if is_sse || looks_like_sse_payload(body) {
+                return convert_openai_sse_to_ollama_json(adapter, body);
+            }
+            convert_openai_json_to_ollama(adapter, body)
+        }
     }
 }
 
@@ -570,6 +601,25 @@ pub(super) fn build_anthropic_error_body(message: &str) -> Vec<u8> {
     })
 }
 
+pub(super) fn build_gemini_error_body(status_code: u16, message: &str) -> Vec<u8> {
+    gemini::build_gemini_error_body(status_code, message)
+}
+
+pub(super) fn convert_gemini_stream_chunk(value: &Value) -> Option<Value> {
+    gemini::convert_gemini_stream_chunk(value)
+}
+
+pub(super) fn build_ollama_error_body(message: &str) -> Vec<u8> {
+    ollama::build_ollama_error_body(message)
+}
+
+pub(super) fn convert_ollama_stream_chunk(
+    adapter: ResponseAdapter,
+    value: &Value,
+) -> Option<Value> {
+    ollama::convert_ollama_stream_chunk(adapter, value)
+}
+
 fn looks_like_sse_payload(body: &[u8]) -> bool {
     let Ok(text) = std::str::from_utf8(body) else {
         return false;
@@ -0,0 +1,417 @@
+use serde_json::{json, Map, Value};
+
+use super::json_conversion::parse_tool_arguments_as_object;
+use super::openai_chat::{stream_event_model, stream_event_response_id};
+use super::{is_response_completed_event_type, parse_openai_sse_event_value};
+
+fn gemini_status_for_code(status_code: u16) -> &'static str {
+    match status_code {
+        400 => "INVALID_ARGUMENT",
+        401 => "UNAUTHENTICATED",
+        403 => "PERMISSION_DENIED",
+        404 => "NOT_FOUND",
+        409 => "ABORTED",
+        429 => "RESOURCE_EXHAUSTED",
+        499 => "CANCELLED",
+        501 => "UNIMPLEMENTED",
+        503 => "UNAVAILABLE",
+        504 => "DEADLINE_EXCEEDED",
+        _ => "INTERNAL",
+    }
+}
+
+pub(super) fn build_gemini_error_body(status_code: u16, message: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "error": {
+            "code": status_code,
+            "message": message,
+            "status": gemini_status_for_code(status_code),
+        }
+    }))
+    .unwrap_or_else(|_| {
+        b"{\"error\":{\"code\":500,\"message\":\"unknown error\",\"status\":\"INTERNAL\"}}".to_vec()
+    })
+}
+
+fn map_usage_to_gemini(usage: &Value) -> Option<Value> {
+    let usage = usage.as_object()?;
+    let prompt_tokens = usage
+        .get("input_tokens")
+        .or_else(|| usage.get("prompt_tokens"))
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    let output_tokens = usage
+        .get("output_tokens")
+        .or_else(|| usage.get("completion_tokens"))
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    let reasoning_tokens = usage
+        .get("output_tokens_details")
+        .and_then(|details| details.get("reasoning_tokens"))
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    let cached_tokens = usage
+        .get("input_tokens_details")
+        .and_then(|details| details.get("cached_tokens"))
+        .and_then(Value::as_i64)
+        .unwrap_or(0);
+    let total_tokens = usage
+        .get("total_tokens")
+        .and_then(Value::as_i64)
+        .unwrap_or(prompt_tokens + output_tokens);
+
+    // 中文注释：Gemini 的 candidatesTokenCount 不含思考 token，需要从 output_tokens 中扣除。
+    let mut out = Map::new();
+    out.insert("promptTokenCount".to_string(), json!(prompt_tokens));
+    out.insert(
+        "candidatesTokenCount".to_string(),
+        json!((output_tokens - reasoning_tokens).max(0)),
+    );
+    out.insert("totalTokenCount".to_string(), json!(total_tokens));
+    if cached_tokens > 0 {
+        out.insert("cachedContentTokenCount".to_string(), json!(cached_tokens));
+    }
+    if reasoning_tokens > 0 {
+        out.insert("thoughtsTokenCount".to_string(), json!(reasoning_tokens));
+    }
+    Some(Value::Object(out))
+}
+
+fn resolve_gemini_finish_reason(response: &Value) -> &'static str {
+    let is_incomplete = response
+        .get("status")
+        .and_then(Value::as_str)
+        .is_some_and(|status| status == "incomplete");
+    if !is_incomplete {
+        return "STOP";
+    }
+    match response
+        .get("incomplete_details")
+        .and_then(|details| details.get("reason"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+    {
+        "max_output_tokens" => "MAX_TOKENS",
+        "content_filter" => "SAFETY",
+        _ => "OTHER",
+    }
+}
+
+fn map_function_call_item_to_gemini_part(item_obj: &Map<String, Value>) -> Option<Value> {
+    let name = item_obj
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    let args = match item_obj.get("arguments") {
+        Some(Value::String(raw)) => parse_tool_arguments_as_object(raw),
+        Some(Value::Object(obj)) => Value::Object(obj.clone()),
+        _ => json!({}),
+    };
+    let mut function_call = Map::new();
+    if let Some(call_id) = item_obj
+        .get("call_id")
+        .or_else(|| item_obj.get("id"))
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+    {
+        function_call.insert("id".to_string(), Value::String(call_id.to_string()));
+    }
+    function_call.insert("name".to_string(), Value::String(name.to_string()));
+    function_call.insert("args".to_string(), args);
+    Some(json!({ "functionCall": Value::Object(function_call) }))
+}
+
+fn append_output_item_parts(item: &Value, parts: &mut Vec<Value>) {
+    let Some(item_obj) = item.as_object() else {
+        return;
+    };
+    match item_obj
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+    {
+        "message" => {
+            let Some(content) = item_obj.get("content").and_then(Value::as_array) else {
+                return;
+            };
+            for block in content {
+                let block_type = block
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                if block_type != "output_text" && block_type != "text" {
+                    continue;
+                }
+                if let Some(text) = block.get("text").and_then(Value::as_str) {
+                    if !text.is_empty() {
+                        parts.push(json!({ "text": text }));
+                    }
+                }
+            }
+        }
+        "function_call" => {
+            if let Some(part) = map_function_call_item_to_gemini_part(item_obj) {
+                parts.push(part);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn build_gemini_response(response: &Value, parts: Vec<Value>) -> Value {
+    let parts = if parts.is_empty() {
+        vec![json!({ "text": "" })]
+    } else {
+        parts
+    };
+    let mut out = Map::new();
+    out.insert(
+        "candidates".to_string(),
+        json!([{
+            "content": {
+                "role": "model",
+                "parts": parts,
+            },
+            "finishReason": resolve_gemini_finish_reason(response),
+            "index": 0,
+        }]),
+    );
+    if let Some(usage) = response.get("usage").and_then(map_usage_to_gemini) {
+        out.insert("usageMetadata".to_string(), usage);
+    }
+    if let Some(model) = response.get("model").and_then(Value::as_str) {
+        out.insert("modelVersion".to_string(), Value::String(model.to_string()));
+    }
+    if let Some(id) = response.get("id").and_then(Value::as_str) {
+        out.insert("responseId".to_string(), Value::String(id.to_string()));
+    }
+    Value::Object(out)
+}
+
+fn map_openai_response_to_gemini(response: &Value) -> Value {
+    let mut parts = Vec::new();
+    if let Some(output) = response.get("output").and_then(Value::as_array) {
+        for item in output {
+            append_output_item_parts(item, &mut parts);
+        }
+    }
+    if parts.is_empty() {
+        if let Some(text) = response
+            .get("output_text")
+            .and_then(Value::as_str)
+            .filter(|text| !text.is_empty())
+        {
+            parts.push(json!({ "text": text }));
+        }
+    }
+    build_gemini_response(response, parts)
+}
+
+fn extract_openai_error_message(value: &Value) -> Option<String> {
+    let error = value.get("error").filter(|error| !error.is_null())?;
+    if let Some(message) = error.as_str() {
+        return Some(message.to_string());
+    }
+    error
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| Some(error.to_string()))
+}
+
+pub(super) fn convert_openai_json_to_gemini(
+    body: &[u8],
+) -> Result<(Vec<u8>, &'static str), String> {
+    let value: Value =
+        serde_json::from_slice(body).map_err(|_| "invalid upstream json payload".to_string())?;
+    if let Some(message) = extract_openai_error_message(&value) {
+        return Ok((build_gemini_error_body(500, &message), "application/json"));
+    }
+    let response = value.get("response").unwrap_or(&value);
+    let mapped = map_openai_response_to_gemini(response);
+    let bytes = serde_json::to_vec(&mapped)
+        .map_err(|err| format!("serialize gemini json failed: {err}"))?;
+    Ok((bytes, "application/json"))
+}
+
+pub(super) fn convert_openai_sse_to_gemini_json(
+    body: &[u8],
+) -> Result<(Vec<u8>, &'static str), String> {
+    let text = std::str::from_utf8(body).map_err(|_| "invalid upstream sse bytes".to_string())?;
+    let mut completed_response: Option<Value> = None;
+    let mut failure_message: Option<String> = None;
+    let mut done_items = Vec::<Value>::new();
+    let mut text_out = String::new();
+    let mut data_lines = Vec::<String>::new();
+    let mut event_name: Option<String> = None;
+
+    let mut flush_frame = |lines: &mut Vec<String>, event_name: &mut Option<String>| {
+        if lines.is_empty() {
+            *event_name = None;
+            return;
+        }
+        let data = lines.join("\n");
+        lines.clear();
+        let parsed = parse_openai_sse_event_value(&data, event_name.as_deref());
+        *event_name = None;
+        let Some(value) = parsed else {
+            return;
+        };
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        match event_type {
+            "response.output_text.delta" => {
+                if let Some(delta) = value.get("delta").and_then(Value::as_str) {
+                    text_out.push_str(delta);
+                }
+            }
+            "response.output_item.done" => {
+                if let Some(item) = value.get("item") {
+                    done_items.push(item.clone());
+                }
+            }
+            "response.failed" | "error" => {
+                failure_message = value
+                    .get("response")
+                    .and_then(extract_openai_error_message)
+                    .or_else(|| extract_openai_error_message(&value))
+                    .or_else(|| {
+                        value
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .map(str::to_string)
+                    })
+                    .or_else(|| Some("upstream response failed".to_string()));
+            }
+            kind if is_response_completed_event_type(kind) => {
+                if let Some(response) = value.get("response") {
+                    completed_response = Some(response.clone());
+                }
+            }
+            _ => {}
+        }
+    };
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("event:") {
+            event_name = Some(rest.trim_start().to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start().to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            flush_frame(&mut data_lines, &mut event_name);
+        }
+    }
+    flush_frame(&mut data_lines, &mut event_name);
+
+    if completed_response.is_none() {
+        if let Some(message) = failure_message {
+            return Ok((build_gemini_error_body(500, &message), "application/json"));
+        }
+    }
+
+    let response = completed_response.unwrap_or_else(|| json!({}));
+    let mut mapped = map_openai_response_to_gemini(&response);
+    let has_output = response
+        .get("output")
+        .and_then(Value::as_array)
+        .is_some_and(|output| !output.is_empty());
+    if !has_output {
+        // 中文注释：部分上游 completed 事件的 output 为空，此时改用流式过程中累计的条目与文本。
+        let mut parts = Vec::new();
+        for item in &done_items {
+            append_output_item_parts(item, &mut parts);
+        }
+        let has_text_part = parts.iter().any(|part| part.get("text").is_some());
+        if !has_text_part && !text_out.is_empty() {
+            parts.insert(0, json!({ "text": text_out }));
+        }
+        mapped = build_gemini_response(&response, parts);
+    }
+    let bytes = serde_json::to_vec(&mapped)
+        .map_err(|err| format!("serialize gemini json failed: {err}"))?;
+    Ok((bytes, "application/json"))
+}
+
+fn build_gemini_stream_chunk(value: &Value, parts: Vec<Value>) -> Value {
+    let mut out = Map::new();
+    out.insert(
+        "candidates".to_string(),
+        json!([{
+            "content": {
+                "role": "model",
+                "parts": parts,
+            },
+            "index": 0,
+        }]),
+    );
+    let model = stream_event_model(value);
+    if !model.is_empty() {
+        out.insert("modelVersion".to_string(), Value::String(model));
+    }
+    let response_id = stream_event_response_id(value);
+    if !response_id.is_empty() {
+        out.insert("responseId".to_string(), Value::String(response_id));
+    }
+    Value::Object(out)
+}
+
+pub(super) fn convert_gemini_stream_chunk(value: &Value) -> Option<Value> {
+    let event_type = value.get("type").and_then(Value::as_str)?;
+    match event_type {
+        "response.output_text.delta" => {
+            let delta = value
+                .get("delta")
+                .and_then(Value::as_str)
+                .filter(|delta| !delta.is_empty())?;
+            Some(build_gemini_stream_chunk(
+                value,
+                vec![json!({ "text": delta })],
+            ))
+        }
+        "response.output_item.done" => {
+            let item_obj = value.get("item").and_then(Value::as_object)?;
+            let is_function_call = item_obj
+                .get("type")
+                .and_then(Value::as_str)
+                .is_some_and(|kind| kind == "function_call");
+            if !is_function_call {
+                return None;
+            }
+            let part = map_function_call_item_to_gemini_part(item_obj)?;
+            Some(build_gemini_stream_chunk(value, vec![part]))
+        }
+        kind if is_response_completed_event_type(kind) => {
+            let response = value.get("response").unwrap_or(&Value::Null);
+            let mut chunk = build_gemini_stream_chunk(value, vec![json!({ "text": "" })]);
+            if let Some(candidate) = chunk
+                .get_mut("candidates")
+                .and_then(Value::as_array_mut)
+                .and_then(|candidates| candidates.get_mut(0))
+                .and_then(Value::as_object_mut)
+            {
+                candidate.insert(
+                    "finishReason".to_string(),
+                    Value::String(resolve_gemini_finish_reason(response).to_string()),
+                );
+            }
+            if let Some(usage) = response
+                .get("usage")
+                .or_else(|| value.get("usage"))
+                .and_then(map_usage_to_gemini)
+            {
+                if let Some(chunk_obj) = chunk.as_object_mut() {
+                    chunk_obj.insert("usageMetadata".to_string(), usage);
+                }
+            }
+            Some(chunk)
+        }
+        _ => None,
+    }
+}
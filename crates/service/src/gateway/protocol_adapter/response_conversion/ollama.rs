@@ -0,0 +1,375 @@
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+use super::super::ResponseAdapter;
+use super::json_conversion::parse_tool_arguments_as_object;
+use super::openai_chat::stream_event_model;
+use super::{is_response_completed_event_type, parse_openai_sse_event_value};
+
+pub(super) fn build_ollama_error_body(message: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({ "error": message }))
+        .unwrap_or_else(|_| b"{\"error\":\"unknown error\"}".to_vec())
+}
+
+fn is_ollama_chat(adapter: ResponseAdapter) -> bool {
+    matches!(
+        adapter,
+        ResponseAdapter::OllamaChatJson | ResponseAdapter::OllamaChatNdjson
+    )
+}
+
+fn ollama_created_at() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn resolve_ollama_done_reason(response: &Value) -> &'static str {
+    let is_max_tokens = response
+        .get("status")
+        .and_then(Value::as_str)
+        .is_some_and(|status| status == "incomplete")
+        && response
+            .get("incomplete_details")
+            .and_then(|details| details.get("reason"))
+            .and_then(Value::as_str)
+            .is_some_and(|reason| reason == "max_output_tokens");
+    if is_max_tokens {
+        "length"
+    } else {
+        "stop"
+    }
+}
+
+fn map_function_call_item_to_ollama_tool_call(item_obj: &Map<String, Value>) -> Option<Value> {
+    let name = item_obj
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    // 中文注释：Ollama 的 tool_calls.arguments 是对象而不是 JSON 字符串。
+    let arguments = match item_obj.get("arguments") {
+        Some(Value::String(raw)) => parse_tool_arguments_as_object(raw),
+        Some(Value::Object(obj)) => Value::Object(obj.clone()),
+        _ => json!({}),
+    };
+    let mut tool_call = Map::new();
+    if let Some(call_id) = item_obj
+        .get("call_id")
+        .or_else(|| item_obj.get("id"))
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+    {
+        tool_call.insert("id".to_string(), Value::String(call_id.to_string()));
+    }
+    tool_call.insert(
+        "function".to_string(),
+        json!({ "name": name, "arguments": arguments }),
+    );
+    Some(Value::Object(tool_call))
+}
+
+fn append_output_item(item: &Value, text: &mut String, tool_calls: &mut Vec<Value>) {
+    let Some(item_obj) = item.as_object() else {
+        return;
+    };
+    match item_obj
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+    {
+        "message" => {
+            let Some(content) = item_obj.get("content").and_then(Value::as_array) else {
+                return;
+            };
+            for block in content {
+                let block_type = block
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                if block_type != "output_text" && block_type != "text" {
+                    continue;
+                }
+                if let Some(block_text) = block.get("text").and_then(Value::as_str) {
+                    text.push_str(block_text);
+                }
+            }
+        }
+        "function_call" => {
+            if let Some(tool_call) = map_function_call_item_to_ollama_tool_call(item_obj) {
+                tool_calls.push(tool_call);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 组装一条 Ollama 响应：chat 端点输出 `message`，generate 端点输出 `response` 文本。
+fn build_ollama_message(
+    adapter: ResponseAdapter,
+    model: &str,
+    text: &str,
+    tool_calls: Vec<Value>,
+    done: bool,
+) -> Map<String, Value> {
+    let mut out = Map::new();
+    out.insert("model".to_string(), Value::String(model.to_string()));
+    out.insert("created_at".to_string(), Value::String(ollama_created_at()));
+    if is_ollama_chat(adapter) {
+        let mut message = Map::new();
+        message.insert("role".to_string(), Value::String("assistant".to_string()));
+        message.insert("content".to_string(), Value::String(text.to_string()));
+        if !tool_calls.is_empty() {
+            message.insert("tool_calls".to_string(), Value::Array(tool_calls));
+        }
+        out.insert("message".to_string(), Value::Object(message));
+    } else {
+        out.insert("response".to_string(), Value::String(text.to_string()));
+    }
+    out.insert("done".to_string(), Value::Bool(done));
+    out
+}
+
+fn insert_ollama_done_fields(
+    out: &mut Map<String, Value>,
+    response: &Value,
+    usage: Option<&Value>,
+) {
+    out.insert(
+        "done_reason".to_string(),
+        Value::String(resolve_ollama_done_reason(response).to_string()),
+    );
+    let Some(usage) = usage.and_then(Value::as_object) else {
+        return;
+    };
+    if let Some(input_tokens) = usage
+        .get("input_tokens")
+        .or_else(|| usage.get("prompt_tokens"))
+        .and_then(Value::as_i64)
+    {
+        out.insert("prompt_eval_count".to_string(), json!(input_tokens));
+    }
+    if let Some(output_tokens) = usage
+        .get("output_tokens")
+        .or_else(|| usage.get("completion_tokens"))
+        .and_then(Value::as_i64)
+    {
+        out.insert("eval_count".to_string(), json!(output_tokens));
+    }
+}
+
+fn build_ollama_final_response(
+    adapter: ResponseAdapter,
+    response: &Value,
+    text: &str,
+    tool_calls: Vec<Value>,
+) -> Value {
+    let model = response
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let mut out = build_ollama_message(adapter, model, text, tool_calls, true);
+    insert_ollama_done_fields(&mut out, response, response.get("usage"));
+    Value::Object(out)
+}
+
+fn map_openai_response_to_ollama(adapter: ResponseAdapter, response: &Value) -> Value {
+    let mut text = String::new();
+    let mut tool_calls = Vec::new();
+    if let Some(output) = response.get("output").and_then(Value::as_array) {
+        for item in output {
+            append_output_item(item, &mut text, &mut tool_calls);
+        }
+    }
+    if text.is_empty() {
+        if let Some(output_text) = response.get("output_text").and_then(Value::as_str) {
+            text.push_str(output_text);
+        }
+    }
+    build_ollama_final_response(adapter, response, &text, tool_calls)
+}
+
+fn extract_openai_error_message(value: &Value) -> Option<String> {
+    let error = value.get("error").filter(|error| !error.is_null())?;
+    if let Some(message) = error.as_str() {
+        return Some(message.to_string());
+    }
+    error
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| Some(error.to_string()))
+}
+
+pub(super) fn convert_openai_json_to_ollama(
+    adapter: ResponseAdapter,
+    body: &[u8],
+) -> Result<(Vec<u8>, &'static str), String> {
+    let value: Value =
+        serde_json::from_slice(body).map_err(|_| "invalid upstream json payload".to_string())?;
+    if let Some(message) = extract_openai_error_message(&value) {
+        return Ok((build_ollama_error_body(&message), "application/json"));
+    }
+    let response = value.get("response").unwrap_or(&value);
+    let mapped = map_openai_response_to_ollama(adapter, response);
+    let bytes = serde_json::to_vec(&mapped)
+        .map_err(|err| format!("serialize ollama json failed: {err}"))?;
+    Ok((bytes, "application/json"))
+}
+
+pub(super) fn convert_openai_sse_to_ollama_json(
+    adapter: ResponseAdapter,
+    body: &[u8],
+) -> Result<(Vec<u8>, &'static str), String> {
+    let text = std::str::from_utf8(body).map_err(|_| "invalid upstream sse bytes".to_string())?;
+    let mut completed_response: Option<Value> = None;
+    let mut failure_message: Option<String> = None;
+    let mut done_items = Vec::<Value>::new();
+    let mut text_out = String::new();
+    let mut data_lines = Vec::<String>::new();
+    let mut event_name: Option<String> = None;
+
+    let mut flush_frame = |lines: &mut Vec<String>, event_name: &mut Option<String>| {
+        if lines.is_empty() {
+            *event_name = None;
+            return;
+        }
+        let data = lines.join("\n");
+        lines.clear();
+        let parsed = parse_openai_sse_event_value(&data, event_name.as_deref());
+        *event_name = None;
+        let Some(value) = parsed else {
+            return;
+        };
+        let event_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        match event_type {
+            "response.output_text.delta" => {
+                if let Some(delta) = value.get("delta").and_then(Value::as_str) {
+                    text_out.push_str(delta);
+                }
+            }
+            "response.output_item.done" => {
+                if let Some(item) = value.get("item") {
+                    done_items.push(item.clone());
+                }
+            }
+            "response.failed" | "error" => {
+                failure_message = value
+                    .get("response")
+                    .and_then(extract_openai_error_message)
+                    .or_else(|| extract_openai_error_message(&value))
+                    .or_else(|| {
+                        value
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .map(str::to_string)
+                    })
+                    .or_else(|| Some("upstream response failed".to_string()));
+            }
+            kind if is_response_completed_event_type(kind) => {
+                if let Some(response) = value.get("response") {
+                    completed_response = Some(response.clone());
+                }
+            }
+            _ => {}
+        }
+    };
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("event:") {
+            event_name = Some(rest.trim_start().to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start().to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            flush_frame(&mut data_lines, &mut event_name);
+        }
+    }
+    flush_frame(&mut data_lines, &mut event_name);
+
+    if completed_response.is_none() {
+        if let Some(message) = failure_message {
+            return Ok((build_ollama_error_body(&message), "application/json"));
+        }
+    }
+
+    let response = completed_response.unwrap_or_else(|| json!({}));
+    let has_output = response
+        .get("output")
+        .and_then(Value::as_array)
+        .is_some_and(|output| !output.is_empty());
+    let mapped = if has_output {
+        map_openai_response_to_ollama(adapter, &response)
+    } else {
+        // 中文注释：部分上游 completed 事件的 output 为空，此时改用流式过程中累计的条目与文本。
+        let mut item_text = String::new();
+        let mut tool_calls = Vec::new();
+        for item in &done_items {
+            append_output_item(item, &mut item_text, &mut tool_calls);
+        }
+        if item_text.is_empty() {
+            item_text = text_out;
+        }
+        build_ollama_final_response(adapter, &response, &item_text, tool_calls)
+    };
+    let bytes = serde_json::to_vec(&mapped)
+        .map_err(|err| format!("serialize ollama json failed: {err}"))?;
+    Ok((bytes, "application/json"))
+}
+
+/// 把一条上游 Responses 流事件映射为一行 Ollama NDJSON；不产生输出的事件返回 None。
+pub(super) fn convert_ollama_stream_chunk(
+    adapter: ResponseAdapter,
+    value: &Value,
+) -> Option<Value> {
+    let event_type = value.get("type").and_then(Value::as_str)?;
+    let model = stream_event_model(value);
+    match event_type {
+        "response.output_text.delta" => {
+            let delta = value
+                .get("delta")
+                .and_then(Value::as_str)
+                .filter(|delta| !delta.is_empty())?;
+            Some(Value::Object(build_ollama_message(
+                adapter,
+                &model,
+                delta,
+                Vec::new(),
+                false,
+            )))
+        }
+        "response.output_item.done" if is_ollama_chat(adapter) => {
+            let item_obj = value.get("item").and_then(Value::as_object)?;
+            let is_function_call = item_obj
+                .get("type")
+                .and_then(Value::as_str)
+                .is_some_and(|kind| kind == "function_call");
+            if !is_function_call {
+                return None;
+            }
+            let tool_call = map_function_call_item_to_ollama_tool_call(item_obj)?;
+            Some(Value::Object(build_ollama_message(
+                adapter,
+                &model,
+                "",
+                vec![tool_call],
+                false,
+            )))
+        }
+        kind if is_response_completed_event_type(kind) => {
+            let response = value.get("response").unwrap_or(&Value::Null);
+            let mut out = build_ollama_message(adapter, &model, "", Vec::new(), true);
+            insert_ollama_done_fields(
+                &mut out,
+                response,
+                response.get("usage").or_else(|| value.get("usage")),
+            );
+            Some(Value::Object(out))
+        }
+        _ => None,
+    }
+}
@@ -1,10 +1,14 @@
 use super::{
     adapt_request_for_protocol, adapt_upstream_response,
-    adapt_upstream_response_with_tool_name_restore_map, convert_openai_chat_stream_chunk,
+    adapt_upstream_response_with_tool_name_restore_map, convert_gemini_stream_chunk,
+    convert_ollama_stream_chunk, convert_openai_chat_stream_chunk,
     convert_openai_chat_stream_chunk_with_tool_name_restore_map,
-    convert_openai_completions_stream_chunk, ResponseAdapter,
+    convert_openai_completions_stream_chunk, parse_gemini_generate_content_path,
+    parse_ollama_compat_path, OllamaEndpoint, ResponseAdapter,
+};
+use crate::apikey_profile::{
+    PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_GEMINI_NATIVE, PROTOCOL_OPENAI_COMPAT,
 };
-use crate::apikey_profile::{PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_OPENAI_COMPAT};
 
 #[test]
 fn openai_chat_completions_are_adapted_to_responses() {
@@ -1336,3 +1340,313 @@ fn anthropic_chat_completions_still_passthrough() {
     assert_eq!(adapted.body, body);
     assert_eq!(adapted.response_adapter, ResponseAdapter::Passthrough);
 }
+
+#[test]
+fn gemini_generate_content_path_is_parsed() {
+    assert_eq!(
+        parse_gemini_generate_content_path("/v1beta/models/gemini-2.5-pro:generateContent"),
+        Some(("gemini-2.5-pro".to_string(), false))
+    );
+    assert_eq!(
+        parse_gemini_generate_content_path(
+            "/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse"
+        ),
+        Some(("gemini-2.5-flash".to_string(), true))
+    );
+    assert_eq!(
+        parse_gemini_generate_content_path("/v1beta/models/gemini-2.5-pro:countTokens"),
+        None
+    );
+    assert_eq!(parse_gemini_generate_content_path("/v1/responses"), None);
+}
+
+#[test]
+fn gemini_generate_content_is_adapted_to_responses() {
+    let body = serde_json::json!({
+        "systemInstruction": { "parts": [{ "text": "be brief" }] },
+        "contents": [
+            { "role": "user", "parts": [{ "text": "weather in Paris?" }] },
+            {
+                "role": "model",
+                "parts": [{ "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }]
+            },
+            {
+                "role": "user",
+                "parts": [{ "functionResponse": { "name": "get_weather", "response": { "temp": 21 } } }]
+            }
+        ],
+        "tools": [{
+            "functionDeclarations": [{
+                "name": "get_weather",
+                "description": "weather lookup",
+                "parameters": {
+                    "type": "OBJECT",
+                    "properties": { "city": { "type": "STRING" } }
+                }
+            }]
+        }],
+        "toolConfig": { "functionCallingConfig": { "mode": "ANY" } },
+        "generationConfig": { "thinkingConfig": { "thinkingBudget": 512 } }
+    });
+    let body = serde_json::to_vec(&body).expect("serialize request");
+    let adapted = adapt_request_for_protocol(
+        PROTOCOL_GEMINI_NATIVE,
+        "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+        body,
+    )
+    .expect("adapt request");
+    assert_eq!(adapted.path, "/v1/responses");
+    assert_eq!(adapted.response_adapter, ResponseAdapter::GeminiSse);
+
+    let value: serde_json::Value = serde_json::from_slice(&adapted.body).expect("adapted json");
+    assert_eq!(value["model"], "gpt-5.3-codex");
+    assert_eq!(value["instructions"], "be brief");
+    assert_eq!(value["stream"], true);
+    assert_eq!(value["reasoning"]["effort"], "low");
+    assert_eq!(value["tool_choice"], "required");
+    assert_eq!(value["tools"][0]["name"], "get_weather");
+    assert_eq!(
+        value["tools"][0]["parameters"]["properties"]["city"]["type"],
+        "string"
+    );
+    assert_eq!(value["input"][0]["content"][0]["type"], "input_text");
+    assert_eq!(value["input"][1]["type"], "function_call");
+    assert_eq!(value["input"][1]["arguments"], "{\"city\":\"Paris\"}");
+    assert_eq!(value["input"][2]["type"], "function_call_output");
+    assert_eq!(value["input"][2]["call_id"], value["input"][1]["call_id"]);
+    assert_eq!(value["input"][2]["output"], "{\"temp\":21}");
+}
+
+#[test]
+fn gemini_paths_outside_generate_content_passthrough() {
+    let body = br#"{"contents":[]}"#.to_vec();
+    let adapted = adapt_request_for_protocol(
+        PROTOCOL_GEMINI_NATIVE,
+        "/v1beta/models/gemini-2.5-pro:embedContent",
+        body.clone(),
+    )
+    .expect("adapt request");
+    assert_eq!(adapted.path, "/v1beta/models/gemini-2.5-pro:embedContent");
+    assert_eq!(adapted.body, body);
+    assert_eq!(adapted.response_adapter, ResponseAdapter::Passthrough);
+}
+
+#[test]
+fn gemini_json_adapter_aggregates_responses_sse() {
+    let upstream = concat!(
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"Hel\"}\n\n",
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"lo\"}\n\n",
+        "data: {\"type\":\"response.output_item.done\",\"item\":{\"type\":\"function_call\",\"call_id\":\"call_1\",\"name\":\"lookup\",\"arguments\":\"{\\\"q\\\":\\\"x\\\"}\"}}\n\n",
+        "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_1\",\"model\":\"gpt-5.3-codex\",\"output\":[],\"usage\":{\"input_tokens\":10,\"output_tokens\":7,\"total_tokens\":17,\"output_tokens_details\":{\"reasoning_tokens\":2}}}}\n\n"
+    );
+    let (body, content_type) = adapt_upstream_response(
+        ResponseAdapter::GeminiJson,
+        Some("text/event-stream"),
+        upstream.as_bytes(),
+    )
+    .expect("convert response");
+    assert_eq!(content_type, "application/json");
+    let value: serde_json::Value = serde_json::from_slice(&body).expect("gemini json");
+    let parts = &value["candidates"][0]["content"]["parts"];
+    assert_eq!(parts[0]["text"], "Hello");
+    assert_eq!(parts[1]["functionCall"]["name"], "lookup");
+    assert_eq!(parts[1]["functionCall"]["args"]["q"], "x");
+    assert_eq!(value["candidates"][0]["finishReason"], "STOP");
+    assert_eq!(value["usageMetadata"]["promptTokenCount"], 10);
+    assert_eq!(value["usageMetadata"]["candidatesTokenCount"], 5);
+    assert_eq!(value["usageMetadata"]["thoughtsTokenCount"], 2);
+    assert_eq!(value["responseId"], "resp_1");
+}
+
+#[test]
+fn gemini_stream_chunk_maps_text_delta_and_completion() {
+    let delta = serde_json::json!({
+        "type": "response.output_text.delta",
+        "delta": "hi",
+        "response_id": "resp_1"
+    });
+    let chunk = convert_gemini_stream_chunk(&delta).expect("text chunk");
+    assert_eq!(chunk["candidates"][0]["content"]["parts"][0]["text"], "hi");
+    assert!(chunk["candidates"][0].get("finishReason").is_none());
+
+    let completed = serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "id": "resp_1",
+            "status": "incomplete",
+            "incomplete_details": { "reason": "max_output_tokens" },
+            "usage": { "input_tokens": 3, "output_tokens": 4, "total_tokens": 7 }
+        }
+    });
+    let chunk = convert_gemini_stream_chunk(&completed).expect("final chunk");
+    assert_eq!(chunk["candidates"][0]["finishReason"], "MAX_TOKENS");
+    assert_eq!(chunk["usageMetadata"]["totalTokenCount"], 7);
+
+    let created = serde_json::json!({ "type": "response.created" });
+    assert!(convert_gemini_stream_chunk(&created).is_none());
+}
+
+#[test]
+fn ollama_compat_path_is_parsed() {
+    assert_eq!(
+        parse_ollama_compat_path("/api/chat"),
+        Some(OllamaEndpoint::Chat)
+    );
+    assert_eq!(
+        parse_ollama_compat_path("/api/generate/"),
+        Some(OllamaEndpoint::Generate)
+    );
+    assert_eq!(
+        parse_ollama_compat_path("/api/tags"),
+        Some(OllamaEndpoint::Tags)
+    );
+    assert_eq!(
+        parse_ollama_compat_path("/api/show"),
+        Some(OllamaEndpoint::Show)
+    );
+    assert_eq!(parse_ollama_compat_path("/api/pull"), None);
+    assert_eq!(parse_ollama_compat_path("/v1/chat/completions"), None);
+}
+
+#[test]
+fn ollama_chat_is_adapted_to_responses() {
+    let body = serde_json::json!({
+        "model": "gpt-5.3-codex:latest",
+        "messages": [
+            { "role": "system", "content": "be brief" },
+            { "role": "user", "content": "what is in this image?", "images": ["iVBORw0KGgoAAA"] },
+            {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "lookup", "arguments": { "q": "cat" } } }]
+            },
+            { "role": "tool", "content": "a cat", "tool_name": "lookup" }
+        ],
+        "tools": [{
+            "type": "function",
+            "function": {
+                "name": "lookup",
+                "description": "search",
+                "parameters": { "type": "object", "properties": { "q": { "type": "string" } } }
+            }
+        }],
+        "format": "json",
+        "think": true
+    });
+    let body = serde_json::to_vec(&body).expect("serialize request");
+    let adapted = adapt_request_for_protocol(PROTOCOL_OPENAI_COMPAT, "/api/chat", body)
+        .expect("adapt request");
+    assert_eq!(adapted.path, "/v1/responses");
+    assert_eq!(adapted.response_adapter, ResponseAdapter::OllamaChatNdjson);
+
+    let value: serde_json::Value = serde_json::from_slice(&adapted.body).expect("adapted json");
+    assert_eq!(value["model"], "gpt-5.3-codex");
+    assert_eq!(value["instructions"], "be brief");
+    assert_eq!(value["stream"], true);
+    assert_eq!(value["reasoning"]["effort"], "high");
+    assert_eq!(value["text"]["format"]["type"], "json_object");
+    assert_eq!(value["tools"][0]["name"], "lookup");
+    assert_eq!(value["input"][0]["content"][0]["type"], "input_text");
+    assert_eq!(value["input"][0]["content"][1]["type"], "input_image");
+    assert_eq!(
+        value["input"][0]["content"][1]["image_url"],
+        "data:image/png;base64,iVBORw0KGgoAAA"
+    );
+    assert_eq!(value["input"][1]["type"], "function_call");
+    assert_eq!(value["input"][1]["arguments"], "{\"q\":\"cat\"}");
+    assert_eq!(value["input"][2]["type"], "function_call_output");
+    assert_eq!(value["input"][2]["call_id"], value["input"][1]["call_id"]);
+    assert_eq!(value["input"][2]["output"], "a cat");
+}
+
+#[test]
+fn ollama_generate_non_stream_is_adapted_to_responses() {
+    let body = br#"{"model":"gpt-5.3-codex","prompt":"hello","system":"be nice","stream":false}"#;
+    let adapted =
+        adapt_request_for_protocol(PROTOCOL_OPENAI_COMPAT, "/api/generate", body.to_vec())
+            .expect("adapt request");
+    assert_eq!(adapted.path, "/v1/responses");
+    assert_eq!(
+        adapted.response_adapter,
+        ResponseAdapter::OllamaGenerateJson
+    );
+    let value: serde_json::Value = serde_json::from_slice(&adapted.body).expect("adapted json");
+    assert_eq!(value["instructions"], "be nice");
+    assert_eq!(value["input"][0]["content"][0]["text"], "hello");
+    assert_eq!(value["stream"], true);
+
+    let err = adapt_request_for_protocol(
+        PROTOCOL_OPENAI_COMPAT,
+        "/api/generate",
+        br#"{"model":"gpt-5.3-codex"}"#.to_vec(),
+    )
+    .expect_err("missing prompt");
+    assert!(err.contains("prompt"));
+}
+
+#[test]
+fn ollama_chat_json_adapter_aggregates_responses_sse() {
+    let upstream = concat!(
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"Hel\"}\n\n",
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"lo\"}\n\n",
+        "data: {\"type\":\"response.output_item.done\",\"item\":{\"type\":\"function_call\",\"call_id\":\"call_1\",\"name\":\"lookup\",\"arguments\":\"{\\\"q\\\":\\\"x\\\"}\"}}\n\n",
+        "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_1\",\"model\":\"gpt-5.3-codex\",\"output\":[],\"usage\":{\"input_tokens\":10,\"output_tokens\":7,\"total_tokens\":17}}}\n\n"
+    );
+    let (body, content_type) = adapt_upstream_response(
+        ResponseAdapter::OllamaChatJson,
+        Some("text/event-stream"),
+        upstream.as_bytes(),
+    )
+    .expect("convert response");
+    assert_eq!(content_type, "application/json");
+    let value: serde_json::Value = serde_json::from_slice(&body).expect("ollama json");
+    assert_eq!(value["model"], "gpt-5.3-codex");
+    assert_eq!(value["message"]["role"], "assistant");
+    assert_eq!(value["message"]["content"], "Hello");
+    assert_eq!(
+        value["message"]["tool_calls"][0]["function"]["name"],
+        "lookup"
+    );
+    assert_eq!(
+        value["message"]["tool_calls"][0]["function"]["arguments"]["q"],
+        "x"
+    );
+    assert_eq!(value["done"], true);
+    assert_eq!(value["done_reason"], "stop");
+    assert_eq!(value["prompt_eval_count"], 10);
+    assert_eq!(value["eval_count"], 7);
+    assert!(value["created_at"].as_str().is_some());
+}
+
+#[test]
+fn ollama_stream_chunk_maps_text_delta_and_completion() {
+    let delta = serde_json::json!({
+        "type": "response.output_text.delta",
+        "delta": "hi"
+    });
+    let chunk =
+        convert_ollama_stream_chunk(ResponseAdapter::OllamaGenerateNdjson, &delta).expect("chunk");
+    assert_eq!(chunk["response"], "hi");
+    assert_eq!(chunk["done"], false);
+    assert!(chunk.get("message").is_none());
+
+    let completed = serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "model": "gpt-5.3-codex",
+            "status": "incomplete",
+            "incomplete_details": { "reason": "max_output_tokens" },
+            "usage": { "input_tokens": 3, "output_tokens": 4, "total_tokens": 7 }
+        }
+    });
+    let chunk = convert_ollama_stream_chunk(ResponseAdapter::OllamaChatNdjson, &completed)
+        .expect("final chunk");
+    assert_eq!(chunk["model"], "gpt-5.3-codex");
+    assert_eq!(chunk["message"]["content"], "");
+    assert_eq!(chunk["done"], true);
+    assert_eq!(chunk["done_reason"], "length");
+    assert_eq!(chunk["eval_count"], 4);
+
+    let created = serde_json::json!({ "type": "response.created" });
+    assert!(convert_ollama_stream_chunk(ResponseAdapter::OllamaChatNdjson, &created).is_none());
+}
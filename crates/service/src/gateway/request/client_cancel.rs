@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tiny_http::Request;
+
+/// 前置代理转发到后端时附带的请求编号，后端据此找到对应的客户端取消令牌。
+pub(crate) const CLIENT_REQUEST_ID_HEADER_NAME: &str = "X-CodexManager-Client-Request-Id";
+
+static NEXT_CLIENT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+static CLIENT_CANCEL_TOKENS: OnceLock<Mutex<HashMap<u64, ClientCancelToken>>> = OnceLock::new();
+
+/// 客户端取消令牌：前置代理发现下游连接提前关闭时置位，网关在读上游流时轮询它。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientCancelToken(Arc<AtomicBool>);
+
+impl ClientCancelToken {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 前置代理持有的登记项；响应体完整送达前被释放（客户端断开、handler 被取消）即视为客户端取消。
+pub(crate) struct ClientRequestRegistration {
+    id: u64,
+    token: ClientCancelToken,
+    completed: bool,
+}
+
+impl ClientRequestRegistration {
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn mark_completed(&mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for ClientRequestRegistration {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.token.cancel();
+        }
+        crate::lock_utils::lock_recover(tokens(), "client_cancel_tokens").remove(&self.id);
+    }
+}
+
+fn tokens() -> &'static Mutex<HashMap<u64, ClientCancelToken>> {
+    CLIENT_CANCEL_TOKENS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub(crate) fn register_client_request() -> ClientRequestRegistration {
+    let id = NEXT_CLIENT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let token = ClientCancelToken::default();
+    crate::lock_utils::lock_recover(tokens(), "client_cancel_tokens").insert(id, token.clone());
+    ClientRequestRegistration {
+        id,
+        token,
+        completed: false,
+    }
+}
+
+/// 按请求头里的编号查找取消令牌；直连后端（未经前置代理）的请求没有令牌。
+pub(crate) fn client_cancel_token_for_request(request: &Request) -> Option<ClientCancelToken> {
+    let id = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(CLIENT_REQUEST_ID_HEADER_NAME))
+        .and_then(|header| header.value.as_str().trim().parse::<u64>().ok())?;
+    crate::lock_utils::lock_recover(tokens(), "client_cancel_tokens")
+        .get(&id)
+        .cloned()
+}
+
+#[cfg(test)]
+#[path = "tests/client_cancel_tests.rs"]
+mod tests;
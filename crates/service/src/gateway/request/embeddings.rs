@@ -0,0 +1,178 @@
+use bytes::Bytes;
+use serde_json::{json, Map, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Env:
+// - CODEXMANAGER_EMBEDDINGS_BATCH_SIZE (default: 2048; 0 disables batching)
+// Splits `/v1/embeddings` input arrays into upstream requests of at most this many inputs.
+const EMBEDDINGS_BATCH_SIZE_ENV: &str = "CODEXMANAGER_EMBEDDINGS_BATCH_SIZE";
+const DEFAULT_EMBEDDINGS_BATCH_SIZE: usize = 2048;
+static EMBEDDINGS_BATCH_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_EMBEDDINGS_BATCH_SIZE);
+
+pub(super) const EMBEDDINGS_PATH: &str = "/v1/embeddings";
+
+/// 已校验并按批次拆好的 embeddings 请求；每个批次都是一份可直接发往上游的完整请求体。
+#[derive(Debug, Clone)]
+pub(super) struct EmbeddingsRequest {
+    pub(super) batches: Vec<Bytes>,
+}
+
+pub(in super::super) fn reload_from_env() {
+    EMBEDDINGS_BATCH_SIZE.store(
+        std::env::var(EMBEDDINGS_BATCH_SIZE_ENV)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_EMBEDDINGS_BATCH_SIZE),
+        Ordering::Relaxed,
+    );
+}
+
+fn embeddings_batch_size() -> usize {
+    EMBEDDINGS_BATCH_SIZE.load(Ordering::Relaxed)
+}
+
+pub(super) fn is_embeddings_path(path: &str) -> bool {
+    path == EMBEDDINGS_PATH
+        || path
+            .strip_prefix(EMBEDDINGS_PATH)
+            .is_some_and(|rest| rest.starts_with('?'))
+}
+
+fn is_token_array(items: &[Value]) -> bool {
+    items.iter().all(|item| item.is_u64())
+}
+
+/// 把 `input` 拆成可独立发送的条目：单个字符串和单个 token 数组都只算一条输入。
+fn split_embeddings_inputs(input: &Value) -> Result<Vec<Value>, String> {
+    match input {
+        Value::String(text) if !text.is_empty() => Ok(vec![input.clone()]),
+        Value::Array(items) if items.is_empty() => {
+            Err("embeddings input must not be empty".to_string())
+        }
+        Value::Array(items) if is_token_array(items) => Ok(vec![input.clone()]),
+        Value::Array(items) => {
+            let valid = items.iter().all(|item| match item {
+                Value::String(text) => !text.is_empty(),
+                Value::Array(tokens) => !tokens.is_empty() && is_token_array(tokens),
+                _ => false,
+            });
+            if !valid {
+                return Err(
+                    "embeddings input must be a string, an array of strings or token arrays"
+                        .to_string(),
+                );
+            }
+            Ok(items.clone())
+        }
+        Value::String(_) => Err("embeddings input must not be empty".to_string()),
+        _ => Err(
+            "embeddings input must be a string, an array of strings or token arrays".to_string(),
+        ),
+    }
+}
+
+fn build_batch_body(obj: &Map<String, Value>, input: Value) -> Result<Bytes, String> {
+    let mut batch = obj.clone();
+    batch.insert("input".to_string(), input);
+    serde_json::to_vec(&Value::Object(batch))
+        .map(Bytes::from)
+        .map_err(|err| format!("serialize embeddings batch failed: {err}"))
+}
+
+fn split_embeddings_request_with_batch_size(
+    body: &[u8],
+    batch_size: usize,
+) -> Result<EmbeddingsRequest, String> {
+    let obj = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(obj)) => obj,
+        Ok(_) => return Err("embeddings request body must be an object".to_string()),
+        Err(_) => return Err("invalid embeddings request json".to_string()),
+    };
+    let has_model = obj
+        .get("model")
+        .and_then(Value::as_str)
+        .is_some_and(|model| !model.trim().is_empty());
+    if !has_model {
+        return Err("embeddings model field is required".to_string());
+    }
+    let input = obj
+        .get("input")
+        .ok_or_else(|| "embeddings input field is required".to_string())?;
+    let inputs = split_embeddings_inputs(input)?;
+    // 中文注释：不超过批次上限时原样发送，保留客户端的 input 形态（单字符串 / 单 token 数组）。
+    if batch_size == 0 || inputs.len() <= batch_size {
+        return Ok(EmbeddingsRequest {
+            batches: vec![Bytes::copy_from_slice(body)],
+        });
+    }
+    let batches = inputs
+        .chunks(batch_size)
+        .map(|chunk| build_batch_body(&obj, Value::Array(chunk.to_vec())))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(EmbeddingsRequest { batches })
+}
+
+/// 校验 embeddings 请求体（必须带 model 与非空 input），并按当前批次上限拆分 input 数组。
+pub(super) fn split_embeddings_request(body: &[u8]) -> Result<EmbeddingsRequest, String> {
+    split_embeddings_request_with_batch_size(body, embeddings_batch_size())
+}
+
+fn usage_field(usage: Option<&Value>, key: &str) -> Option<i64> {
+    usage
+        .and_then(|usage| usage.get(key))
+        .and_then(Value::as_i64)
+}
+
+/// 从 embeddings 响应里取 (prompt_tokens, total_tokens)。
+pub(super) fn embeddings_usage(response: &Value) -> (Option<i64>, Option<i64>) {
+    let usage = response.get("usage");
+    let prompt_tokens = usage_field(usage, "prompt_tokens");
+    let total_tokens = usage_field(usage, "total_tokens").or(prompt_tokens);
+    (prompt_tokens, total_tokens)
+}
+
+/// 合并各批次的响应：data 按批次顺序拼接并重排 index，usage 逐项求和。
+pub(super) fn merge_embeddings_responses(responses: Vec<Value>) -> Value {
+    let mut data = Vec::new();
+    let mut model = Value::Null;
+    let mut prompt_tokens = 0_i64;
+    let mut total_tokens = 0_i64;
+    for response in responses {
+        if model.is_null() {
+            if let Some(value) = response.get("model").filter(|value| value.is_string()) {
+                model = value.clone();
+            }
+        }
+        let (batch_prompt, batch_total) = embeddings_usage(&response);
+        prompt_tokens += batch_prompt.unwrap_or(0);
+        total_tokens += batch_total.unwrap_or(0);
+        let offset = data.len() as u64;
+        let Some(items) = response.get("data").and_then(Value::as_array) else {
+            continue;
+        };
+        for (position, item) in items.iter().enumerate() {
+            let mut item = item.clone();
+            let index = item
+                .get("index")
+                .and_then(Value::as_u64)
+                .unwrap_or(position as u64);
+            if let Some(obj) = item.as_object_mut() {
+                obj.insert("index".to_string(), json!(offset + index));
+            }
+            data.push(item);
+        }
+    }
+    json!({
+        "object": "list",
+        "data": data,
+        "model": model,
+        "usage": {
+            "prompt_tokens": prompt_tokens,
+            "total_tokens": total_tokens,
+        },
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/embeddings_tests.rs"]
+mod tests;
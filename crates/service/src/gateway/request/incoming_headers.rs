@@ -7,6 +7,7 @@ pub(crate) struct IncomingHeaderSnapshot {
     authorization_bearer_strict: Option<String>,
     authorization_bearer_case_insensitive: Option<String>,
     x_api_key: Option<String>,
+    x_goog_api_key: Option<String>,
     session_id: Option<String>,
     turn_state: Option<String>,
     conversation_id: Option<String>,
@@ -38,6 +39,16 @@ impl IncomingHeaderSnapshot {
                 }
                 continue;
             }
+            if header.field.equiv("x-goog-api-key") {
+                // 中文注释：Gemini SDK 默认用 x-goog-api-key 传递密钥，这里视同平台 Key。
+                if snapshot.x_goog_api_key.is_none() {
+                    let value = header.value.as_str().trim();
+                    if !value.is_empty() {
+                        snapshot.x_goog_api_key = Some(value.to_string());
+                    }
+                }
+                continue;
+            }
             if snapshot.session_id.is_none() && header.field.equiv("session_id") {
                 let value = header.value.as_str().trim();
                 if !value.is_empty() {
@@ -65,12 +76,14 @@ impl IncomingHeaderSnapshot {
     pub(crate) fn platform_key(&self) -> Option<&str> {
         self.x_api_key
             .as_deref()
+            .or(self.x_goog_api_key.as_deref())
             .or(self.authorization_bearer_strict.as_deref())
     }
 
     pub(crate) fn sticky_key_material(&self) -> Option<&str> {
         self.x_api_key
             .as_deref()
+            .or(self.x_goog_api_key.as_deref())
             .or(self.authorization_bearer_case_insensitive.as_deref())
     }
 
@@ -93,6 +106,18 @@ impl IncomingHeaderSnapshot {
     pub(crate) fn conversation_id(&self) -> Option<&str> {
         self.conversation_id.as_deref()
     }
+
+    #[cfg(test)]
+    pub(crate) fn with_session_ids(
+        session_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Self {
+        Self {
+            session_id: session_id.map(str::to_string),
+            conversation_id: conversation_id.map(str::to_string),
+            ..Self::default()
+        }
+    }
 }
 
 fn strict_bearer_token(value: &str) -> Option<String> {
@@ -3,48 +3,49 @@ use tiny_http::Response;
 
 use crate::apikey_profile::PROTOCOL_ANTHROPIC_NATIVE;
 
-fn accumulate_text_len(value: &Value) -> usize {
-    match value {
-        Value::String(text) => text.chars().count(),
-        Value::Array(items) => items.iter().map(accumulate_text_len).sum(),
-        Value::Object(map) => {
-            if let Some(text) = map.get("text").and_then(Value::as_str) {
-                return text.chars().count();
-            }
-            if let Some(content) = map.get("content") {
-                return accumulate_text_len(content);
-            }
-            if let Some(input) = map.get("input") {
-                return accumulate_text_len(input);
-            }
-            map.values().map(accumulate_text_len).sum()
-        }
-        _ => 0,
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalCountTokensKind {
+    AnthropicMessages,
+    OpenAIResponses,
 }
 
-fn estimate_input_tokens_from_anthropic_messages(body: &[u8]) -> Result<u64, String> {
-    let payload: Value =
-        serde_json::from_slice(body).map_err(|_| "invalid claude request json".to_string())?;
-    let Some(object) = payload.as_object() else {
-        return Err("claude request body must be an object".to_string());
-    };
-
-    let mut char_count = 0usize;
-    if let Some(system) = object.get("system") {
-        char_count += accumulate_text_len(system);
+fn resolve_local_count_tokens_kind(
+    protocol_type: &str,
+    request_method: &str,
+    path: &str,
+) -> Option<LocalCountTokensKind> {
+    if !request_method.eq_ignore_ascii_case("POST") {
+        return None;
     }
-    if let Some(messages) = object.get("messages").and_then(Value::as_array) {
-        for message in messages {
-            if let Some(content) = message.get("content") {
-                char_count += accumulate_text_len(content);
-            }
-        }
+    if protocol_type == PROTOCOL_ANTHROPIC_NATIVE
+        && (path == "/v1/messages/count_tokens" || path.starts_with("/v1/messages/count_tokens?"))
+    {
+        return Some(LocalCountTokensKind::AnthropicMessages);
     }
+    // 中文注释：Codex 上游没有 input_tokens 端点，这里对所有协议统一本地计算。
+    if path == "/v1/responses/input_tokens" || path.starts_with("/v1/responses/input_tokens?") {
+        return Some(LocalCountTokensKind::OpenAIResponses);
+    }
+    None
+}
 
-    // 中文注释：count_tokens 仅用于本地预算估计，采用稳定的轻量估算（约 4 chars/token）。
-    let estimated = ((char_count as u64) / 4).max(1);
-    Ok(estimated)
+fn count_input_tokens(kind: LocalCountTokensKind, body: &[u8]) -> Result<(u64, Value), String> {
+    match kind {
+        LocalCountTokensKind::AnthropicMessages => {
+            let input_tokens = super::token_counter::count_anthropic_messages_input_tokens(body)?;
+            Ok((input_tokens, json!({ "input_tokens": input_tokens })))
+        }
+        LocalCountTokensKind::OpenAIResponses => {
+            let input_tokens = super::token_counter::count_responses_input_tokens(body)?;
+            Ok((
+                input_tokens,
+                json!({
+                    "object": "response.input_tokens",
+                    "input_tokens": input_tokens,
+                }),
+            ))
+        }
+    }
 }
 
 pub(super) fn maybe_respond_local_count_tokens(
@@ -61,16 +62,13 @@ pub(super) fn maybe_respond_local_count_tokens(
     reasoning_for_log: Option<&str>,
     storage: &codexmanager_core::storage::Storage,
 ) -> Result<Option<tiny_http::Request>, String> {
-    let is_anthropic_count_tokens = protocol_type == PROTOCOL_ANTHROPIC_NATIVE
-        && request_method.eq_ignore_ascii_case("POST")
-        && (path == "/v1/messages/count_tokens" || path.starts_with("/v1/messages/count_tokens?"));
-    if !is_anthropic_count_tokens {
+    let Some(kind) = resolve_local_count_tokens_kind(protocol_type, request_method, path) else {
         return Ok(Some(request));
-    }
+    };
 
-    match estimate_input_tokens_from_anthropic_messages(body) {
-        Ok(input_tokens) => {
-            let output = json!({ "input_tokens": input_tokens }).to_string();
+    match count_input_tokens(kind, body) {
+        Ok((input_tokens, payload)) => {
+            let output = payload.to_string();
             super::trace_log::log_attempt_result(trace_id, "-", None, 200, None);
             super::trace_log::log_request_final(trace_id, 200, None, None, None, 0);
             super::record_gateway_request_outcome(path, 200, Some(protocol_type));
@@ -81,6 +79,7 @@ pub(super) fn maybe_respond_local_count_tokens(
                     original_path: Some(original_path),
                     adapted_path: Some(path),
                     response_adapter: Some(response_adapter),
+                    requested_model: None,
                 },
                 Some(key_id),
                 None,
@@ -125,6 +124,7 @@ pub(super) fn maybe_respond_local_count_tokens(
                     original_path: Some(original_path),
                     adapted_path: Some(path),
                     response_adapter: Some(response_adapter),
+                    requested_model: None,
                 },
                 Some(key_id),
                 None,
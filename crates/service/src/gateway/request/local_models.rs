@@ -6,6 +6,31 @@ use tiny_http::Response;
 const MODEL_CACHE_SCOPE_DEFAULT: &str = "default";
 const MODELS_OWNED_BY: &str = "codexmanager";
 
+/// 本地应答的模型列表端点：OpenAI `/v1/models` 与 Ollama 兼容的 `/api/tags`、`/api/show`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalModelsEndpoint {
+    OpenAIModels,
+    OllamaTags,
+    OllamaShow,
+}
+
+fn resolve_local_models_endpoint(request_method: &str, path: &str) -> Option<LocalModelsEndpoint> {
+    if request_method.eq_ignore_ascii_case("GET")
+        && (path == "/v1/models" || path.starts_with("/v1/models?"))
+    {
+        return Some(LocalModelsEndpoint::OpenAIModels);
+    }
+    match super::parse_ollama_compat_path(path)? {
+        super::OllamaEndpoint::Tags if request_method.eq_ignore_ascii_case("GET") => {
+            Some(LocalModelsEndpoint::OllamaTags)
+        }
+        super::OllamaEndpoint::Show if request_method.eq_ignore_ascii_case("POST") => {
+            Some(LocalModelsEndpoint::OllamaShow)
+        }
+        _ => None,
+    }
+}
+
 fn build_openai_models_list(items: &[ModelOption]) -> String {
     let created = now_ts();
     let data = items
@@ -26,6 +51,90 @@ fn build_openai_models_list(items: &[ModelOption]) -> String {
     .to_string()
 }
 
+fn ollama_model_details() -> serde_json::Value {
+    json!({
+        "format": "",
+        "family": MODELS_OWNED_BY,
+        "families": [MODELS_OWNED_BY],
+        "parameter_size": "",
+        "quantization_level": "",
+    })
+}
+
+fn ollama_modified_at() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn build_ollama_tags_list(items: &[ModelOption]) -> String {
+    let modified_at = ollama_modified_at();
+    // 中文注释：不带 `:latest` 标签返回模型名，客户端回传给 /api/chat 时即为真实 slug。
+    let models = items
+        .iter()
+        .map(|item| {
+            json!({
+                "name": item.slug.as_str(),
+                "model": item.slug.as_str(),
+                "modified_at": modified_at.as_str(),
+                "size": 0,
+                "digest": "",
+                "details": ollama_model_details(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "models": models }).to_string()
+}
+
+fn requested_ollama_show_model(body: &[u8]) -> Option<String> {
+    let payload = serde_json::from_slice::<serde_json::Value>(body).ok()?;
+    payload
+        .get("model")
+        .or_else(|| payload.get("name"))
+        .and_then(serde_json::Value::as_str)
+        .map(super::strip_ollama_model_tag)
+        .filter(|model| !model.is_empty())
+        .map(str::to_string)
+}
+
+/// `/api/show` 只返回网关能给出的信息，modelfile / template 等本地模型字段留空。
+fn build_ollama_show_response(items: &[ModelOption], body: &[u8]) -> Result<String, String> {
+    let Some(model) = requested_ollama_show_model(body) else {
+        return Err("model is required".to_string());
+    };
+    let Some(item) = items
+        .iter()
+        .find(|item| item.slug.eq_ignore_ascii_case(model.as_str()))
+    else {
+        return Err(format!("model '{model}' not found"));
+    };
+    Ok(json!({
+        "modelfile": "",
+        "parameters": "",
+        "template": "",
+        "details": ollama_model_details(),
+        "model_info": {},
+        "capabilities": ["completion", "tools"],
+        "modified_at": ollama_modified_at(),
+        "display_name": item.display_name.as_str(),
+    })
+    .to_string())
+}
+
+fn append_model_alias_options(items: &mut Vec<ModelOption>, aliases: Vec<String>) {
+    // 中文注释：别名只追加到本次响应，不写回模型缓存，避免不同 Key 的别名互相串用。
+    for alias in aliases {
+        if items
+            .iter()
+            .any(|item| item.slug.eq_ignore_ascii_case(alias.as_str()))
+        {
+            continue;
+        }
+        items.push(ModelOption {
+            display_name: alias.clone(),
+            slug: alias,
+        });
+    }
+}
+
 fn fallback_model_options(model_for_log: Option<&str>) -> Vec<ModelOption> {
     let Some(slug) = model_for_log
         .map(str::trim)
@@ -39,6 +148,7 @@ fn fallback_model_options(model_for_log: Option<&str>) -> Vec<ModelOption> {
     }]
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn maybe_respond_local_models(
     request: tiny_http::Request,
     trace_id: &str,
@@ -48,15 +158,14 @@ pub(super) fn maybe_respond_local_models(
     path: &str,
     response_adapter: super::ResponseAdapter,
     request_method: &str,
+    body: &[u8],
     model_for_log: Option<&str>,
     reasoning_for_log: Option<&str>,
     storage: &codexmanager_core::storage::Storage,
 ) -> Result<Option<tiny_http::Request>, String> {
-    let is_models_list = request_method.eq_ignore_ascii_case("GET")
-        && (path == "/v1/models" || path.starts_with("/v1/models?"));
-    if !is_models_list {
+    let Some(endpoint) = resolve_local_models_endpoint(request_method, path) else {
         return Ok(Some(request));
-    }
+    };
 
     let mut fallback_reason: Option<String> = None;
     let cached_items = match storage.get_model_options_cache(MODEL_CACHE_SCOPE_DEFAULT) {
@@ -83,6 +192,7 @@ pub(super) fn maybe_respond_local_models(
                     original_path: Some(original_path),
                     adapted_path: Some(path),
                     response_adapter: Some(response_adapter),
+                    requested_model: None,
                 },
                 Some(key_id),
                 None,
@@ -102,7 +212,7 @@ pub(super) fn maybe_respond_local_models(
         }
     };
 
-    let items = if !cached_items.is_empty() {
+    let mut items = if !cached_items.is_empty() {
         cached_items
     } else {
         match super::fetch_models_for_picker() {
@@ -135,10 +245,27 @@ pub(super) fn maybe_respond_local_models(
         }
     };
 
-    let output = build_openai_models_list(&items);
-    super::trace_log::log_attempt_result(trace_id, "-", None, 200, None);
-    super::trace_log::log_request_final(trace_id, 200, None, None, None, 0);
-    super::record_gateway_request_outcome(path, 200, Some(protocol_type));
+    append_model_alias_options(
+        &mut items,
+        crate::model_alias_rules::list_model_alias_names(storage, key_id),
+    );
+    let (status_code, output, error) = match endpoint {
+        LocalModelsEndpoint::OpenAIModels => (200, build_openai_models_list(&items), None),
+        LocalModelsEndpoint::OllamaTags => (200, build_ollama_tags_list(&items), None),
+        LocalModelsEndpoint::OllamaShow => match build_ollama_show_response(&items, body) {
+            Ok(output) => (200, output, None),
+            Err(message) => (
+                404,
+                String::from_utf8_lossy(&super::build_ollama_error_body(message.as_str()))
+                    .into_owned(),
+                Some(message),
+            ),
+        },
+    };
+    let log_error = error.as_deref().or(fallback_reason.as_deref());
+    super::trace_log::log_attempt_result(trace_id, "-", None, status_code, error.as_deref());
+    super::trace_log::log_request_final(trace_id, status_code, None, None, error.as_deref(), 0);
+    super::record_gateway_request_outcome(path, status_code, Some(protocol_type));
     super::write_request_log(
         storage,
         super::request_log::RequestLogTraceContext {
@@ -146,6 +273,7 @@ pub(super) fn maybe_respond_local_models(
             original_path: Some(original_path),
             adapted_path: Some(path),
             response_adapter: Some(response_adapter),
+            requested_model: None,
         },
         Some(key_id),
         None,
@@ -154,13 +282,13 @@ pub(super) fn maybe_respond_local_models(
         model_for_log,
         reasoning_for_log,
         None,
-        Some(200),
+        Some(status_code),
         super::request_log::RequestLogUsage::default(),
-        fallback_reason.as_deref(),
+        log_error,
     );
     let response = super::error_response::with_trace_id_header(
         Response::from_string(output)
-            .with_status_code(200)
+            .with_status_code(status_code)
             .with_header(
                 tiny_http::Header::from_bytes(
                     b"content-type".as_slice(),
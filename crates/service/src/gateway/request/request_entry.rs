@@ -55,8 +55,9 @@ pub(crate) fn handle_gateway_request(mut request: Request) -> Result<(), String>
                             original_path: Some(request_path_for_log.as_str()),
                             adapted_path: Some(request_path_for_log.as_str()),
                             response_adapter: None,
+                            requested_model: None,
                         },
-                        None,
+                        err.key_id.as_deref(),
                         None,
                         &request_path_for_log,
                         &request_method_for_log,
@@ -68,16 +69,49 @@ pub(crate) fn handle_gateway_request(mut request: Request) -> Result<(), String>
                         Some(err.message.as_str()),
                     );
                 }
-                let response = super::error_response::terminal_text_response(
-                    err.status_code,
-                    err.message,
-                    Some(trace_id.as_str()),
-                );
+                let response = match err.body {
+                    Some(body) => super::error_response::terminal_json_response(
+                        err.status_code,
+                        err.message.as_str(),
+                        body,
+                        &err.headers,
+                        Some(trace_id.as_str()),
+                    ),
+                    None => super::error_response::terminal_text_response(
+                        err.status_code,
+                        err.message,
+                        Some(trace_id.as_str()),
+                    ),
+                };
                 let _ = request.respond(response);
                 return Ok(());
             }
         };
 
+    // 中文注释：直连 Anthropic 上游的 Key 由上游自己处理模型列表与 count_tokens，不走本地应答。
+    if crate::apikey_profile::is_anthropic_passthrough(
+        validated.protocol_type.as_str(),
+        validated.upstream_base_url.as_deref(),
+    ) {
+        return super::proxy_validated_request(request, validated, debug);
+    }
+
+    let request = match super::maybe_respond_batch_api(
+        request,
+        validated.trace_id.as_str(),
+        validated.key_id.as_str(),
+        validated.protocol_type.as_str(),
+        validated.original_path.as_str(),
+        validated.path.as_str(),
+        validated.response_adapter,
+        validated.request_method.as_str(),
+        validated.body.as_ref(),
+        &validated.storage,
+    )? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+
     let request = match super::maybe_respond_local_models(
         request,
         validated.trace_id.as_str(),
@@ -87,6 +121,7 @@ pub(crate) fn handle_gateway_request(mut request: Request) -> Result<(), String>
         validated.path.as_str(),
         validated.response_adapter,
         validated.request_method.as_str(),
+        validated.body.as_ref(),
         validated.model_for_log.as_deref(),
         validated.reasoning_for_log.as_deref(),
         &validated.storage,
@@ -347,6 +347,26 @@ pub(super) fn apply_request_overrides(
     filtered_body
 }
 
+/// 第三方服务商只认 Chat Completions：把已适配成 Responses 的请求体转回 chat 形态，
+/// `stream` 跟随客户端，流式时补 include_usage 以便记账，并只保留官方 chat 字段。
+pub(super) fn convert_responses_body_for_chat_provider(
+    body: &[u8],
+    is_stream: bool,
+) -> Option<Vec<u8>> {
+    const CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
+    let mut payload = serde_json::from_slice::<Value>(body).ok()?;
+    let obj = payload.as_object_mut()?;
+    chat_completions::normalize_responses_payload(CHAT_COMPLETIONS_PATH, obj);
+    obj.insert("stream".to_string(), Value::Bool(is_stream));
+    if !is_stream {
+        obj.remove("stream_options");
+    }
+    chat_completions::ensure_reasoning_effort(CHAT_COMPLETIONS_PATH, obj);
+    chat_completions::ensure_stream_usage_override(CHAT_COMPLETIONS_PATH, obj);
+    chat_completions::retain_official_fields(CHAT_COMPLETIONS_PATH, obj);
+    serde_json::to_vec(&payload).ok()
+}
+
 #[cfg(test)]
 #[path = "tests/request_rewrite_tests.rs"]
 mod tests;
@@ -0,0 +1,38 @@
+use super::*;
+
+fn registered_token(id: u64) -> Option<ClientCancelToken> {
+    crate::lock_utils::lock_recover(tokens(), "client_cancel_tokens")
+        .get(&id)
+        .cloned()
+}
+
+#[test]
+fn dropping_unfinished_registration_cancels_token() {
+    let registration = register_client_request();
+    let id = registration.id();
+    let token = registered_token(id).expect("token registered");
+    assert!(!token.is_cancelled());
+
+    drop(registration);
+    assert!(token.is_cancelled());
+    assert!(registered_token(id).is_none());
+}
+
+#[test]
+fn completed_registration_does_not_cancel_token() {
+    let mut registration = register_client_request();
+    let id = registration.id();
+    let token = registered_token(id).expect("token registered");
+
+    registration.mark_completed();
+    drop(registration);
+    assert!(!token.is_cancelled());
+    assert!(registered_token(id).is_none());
+}
+
+#[test]
+fn registrations_get_distinct_ids() {
+    let first = register_client_request();
+    let second = register_client_request();
+    assert_ne!(first.id(), second.id());
+}
@@ -0,0 +1,112 @@
+use super::*;
+use serde_json::Value;
+
+fn batch_inputs(request: &EmbeddingsRequest) -> Vec<Value> {
+    request
+        .batches
+        .iter()
+        .map(|body| {
+            let value: Value = serde_json::from_slice(body).expect("batch json");
+            value.get("input").cloned().expect("batch input")
+        })
+        .collect()
+}
+
+#[test]
+fn embeddings_path_matches_with_and_without_query() {
+    assert!(is_embeddings_path("/v1/embeddings"));
+    assert!(is_embeddings_path("/v1/embeddings?api-version=1"));
+    assert!(!is_embeddings_path("/v1/embeddings2"));
+    assert!(!is_embeddings_path("/v1/responses"));
+}
+
+#[test]
+fn split_rejects_missing_model_and_empty_input() {
+    let err = split_embeddings_request_with_batch_size(br#"{"input":"hi"}"#, 2)
+        .expect_err("missing model");
+    assert!(err.contains("model"));
+
+    for body in [
+        r#"{"model":"text-embedding-3-small"}"#,
+        r#"{"model":"text-embedding-3-small","input":""}"#,
+        r#"{"model":"text-embedding-3-small","input":[]}"#,
+        r#"{"model":"text-embedding-3-small","input":["a",1.5]}"#,
+        r#"{"model":"text-embedding-3-small","input":{"text":"a"}}"#,
+    ] {
+        assert!(
+            split_embeddings_request_with_batch_size(body.as_bytes(), 2).is_err(),
+            "body should be rejected: {body}"
+        );
+    }
+}
+
+#[test]
+fn split_keeps_single_inputs_and_small_arrays_unchanged() {
+    for body in [
+        r#"{"model":"text-embedding-3-small","input":"hello"}"#,
+        r#"{"model":"text-embedding-3-small","input":[1,2,3,4,5]}"#,
+        r#"{"model":"text-embedding-3-small","input":["a","b"]}"#,
+    ] {
+        let request =
+            split_embeddings_request_with_batch_size(body.as_bytes(), 2).expect("valid request");
+        assert_eq!(request.batches.len(), 1);
+        assert_eq!(request.batches[0].as_ref(), body.as_bytes());
+    }
+}
+
+#[test]
+fn split_chunks_input_arrays_by_batch_size() {
+    let body =
+        br#"{"model":"text-embedding-3-small","input":["a","b","c","d","e"],"dimensions":256}"#;
+    let request = split_embeddings_request_with_batch_size(body, 2).expect("valid request");
+    assert_eq!(
+        batch_inputs(&request),
+        vec![json!(["a", "b"]), json!(["c", "d"]), json!(["e"])]
+    );
+    let first: Value = serde_json::from_slice(&request.batches[0]).expect("batch json");
+    assert_eq!(first["dimensions"], json!(256));
+    assert_eq!(first["model"], json!("text-embedding-3-small"));
+
+    let tokens = br#"{"model":"text-embedding-3-small","input":[[1,2],[3],[4,5]]}"#;
+    let request = split_embeddings_request_with_batch_size(tokens, 2).expect("valid request");
+    assert_eq!(
+        batch_inputs(&request),
+        vec![json!([[1, 2], [3]]), json!([[4, 5]])]
+    );
+
+    let unbatched = split_embeddings_request_with_batch_size(body, 0).expect("valid request");
+    assert_eq!(unbatched.batches.len(), 1);
+}
+
+#[test]
+fn merge_reindexes_data_and_sums_usage() {
+    let merged = merge_embeddings_responses(vec![
+        json!({
+            "object": "list",
+            "model": "text-embedding-3-small",
+            "data": [
+                {"object": "embedding", "index": 0, "embedding": [0.1]},
+                {"object": "embedding", "index": 1, "embedding": [0.2]}
+            ],
+            "usage": {"prompt_tokens": 4, "total_tokens": 4}
+        }),
+        json!({
+            "object": "list",
+            "model": "text-embedding-3-small",
+            "data": [{"object": "embedding", "index": 0, "embedding": [0.3]}],
+            "usage": {"prompt_tokens": 3, "total_tokens": 3}
+        }),
+    ]);
+    let indexes = merged["data"]
+        .as_array()
+        .expect("data array")
+        .iter()
+        .map(|item| item["index"].as_u64().expect("index"))
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, vec![0, 1, 2]);
+    assert_eq!(merged["data"][2]["embedding"], json!([0.3]));
+    assert_eq!(merged["model"], json!("text-embedding-3-small"));
+    assert_eq!(merged["usage"]["prompt_tokens"], json!(7));
+    assert_eq!(merged["usage"]["total_tokens"], json!(7));
+    assert_eq!(embeddings_usage(&merged), (Some(7), Some(7)));
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 #[test]
-fn estimate_input_tokens_uses_messages_and_system_text() {
+fn count_input_tokens_uses_messages_and_system_text() {
     let body = br#"{
         "model":"gpt-5.3-codex",
         "system":"abcdabcd",
@@ -10,20 +10,54 @@ fn estimate_input_tokens_uses_messages_and_system_text() {
             {"role":"assistant","content":[{"type":"text","text":"abcdabcd"}]}
         ]
     }"#;
-    let count = estimate_input_tokens_from_anthropic_messages(body).expect("estimate failed");
-    assert_eq!(count, 5);
+    let (count, payload) =
+        count_input_tokens(LocalCountTokensKind::AnthropicMessages, body).expect("count failed");
+    let text_only = super::super::token_counter::count_text_tokens("abcdabcd") * 2
+        + super::super::token_counter::count_text_tokens("abcd");
+    assert!(count > text_only);
+    assert_eq!(payload["input_tokens"], count);
 }
 
 #[test]
-fn estimate_input_tokens_rejects_invalid_json() {
-    let err = estimate_input_tokens_from_anthropic_messages(br#"{"messages":["#)
+fn count_input_tokens_rejects_invalid_json() {
+    let err = count_input_tokens(LocalCountTokensKind::AnthropicMessages, br#"{"messages":["#)
         .expect_err("should reject invalid json");
     assert_eq!(err, "invalid claude request json");
 }
 
 #[test]
-fn estimate_input_tokens_rejects_non_object_payload() {
-    let err = estimate_input_tokens_from_anthropic_messages(br#"["bad"]"#)
+fn count_input_tokens_rejects_non_object_payload() {
+    let err = count_input_tokens(LocalCountTokensKind::AnthropicMessages, br#"["bad"]"#)
         .expect_err("should reject non-object payload");
     assert_eq!(err, "claude request body must be an object");
 }
+
+#[test]
+fn responses_input_tokens_payload_matches_openai_shape() {
+    let body = br#"{"model":"gpt-5.3-codex","instructions":"be brief","input":"hello"}"#;
+    let (count, payload) =
+        count_input_tokens(LocalCountTokensKind::OpenAIResponses, body).expect("count failed");
+    assert!(count > 0);
+    assert_eq!(payload["object"], "response.input_tokens");
+    assert_eq!(payload["input_tokens"], count);
+}
+
+#[test]
+fn local_count_tokens_kind_matches_supported_paths() {
+    assert_eq!(
+        resolve_local_count_tokens_kind("anthropic_native", "POST", "/v1/messages/count_tokens"),
+        Some(LocalCountTokensKind::AnthropicMessages)
+    );
+    assert_eq!(
+        resolve_local_count_tokens_kind("openai_compat", "POST", "/v1/messages/count_tokens"),
+        None
+    );
+    assert_eq!(
+        resolve_local_count_tokens_kind("openai_compat", "POST", "/v1/responses/input_tokens"),
+        Some(LocalCountTokensKind::OpenAIResponses)
+    );
+    assert_eq!(
+        resolve_local_count_tokens_kind("openai_compat", "GET", "/v1/responses/input_tokens"),
+        None
+    );
+}
@@ -31,3 +31,61 @@ fn build_openai_models_list_outputs_expected_shape() {
         );
     }
 }
+
+#[test]
+fn append_model_alias_options_skips_existing_slugs() {
+    let mut items = vec![ModelOption {
+        slug: "gpt-5.1".to_string(),
+        display_name: "GPT-5.1".to_string(),
+    }];
+    append_model_alias_options(
+        &mut items,
+        vec!["GPT-5.1".to_string(), "team-fast".to_string()],
+    );
+    let slugs = items
+        .iter()
+        .map(|item| item.slug.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(slugs, vec!["gpt-5.1", "team-fast"]);
+}
+
+#[test]
+fn local_models_endpoint_covers_ollama_tags_and_show() {
+    assert_eq!(
+        resolve_local_models_endpoint("GET", "/v1/models"),
+        Some(LocalModelsEndpoint::OpenAIModels)
+    );
+    assert_eq!(
+        resolve_local_models_endpoint("GET", "/api/tags"),
+        Some(LocalModelsEndpoint::OllamaTags)
+    );
+    assert_eq!(
+        resolve_local_models_endpoint("POST", "/api/show"),
+        Some(LocalModelsEndpoint::OllamaShow)
+    );
+    assert_eq!(resolve_local_models_endpoint("POST", "/api/tags"), None);
+    assert_eq!(resolve_local_models_endpoint("POST", "/api/chat"), None);
+}
+
+#[test]
+fn build_ollama_tags_and_show_use_model_slugs() {
+    let items = vec![ModelOption {
+        slug: "gpt-5.3-codex".to_string(),
+        display_name: "GPT-5.3 Codex".to_string(),
+    }];
+    let value: Value = serde_json::from_str(&build_ollama_tags_list(&items)).expect("valid json");
+    let models = value["models"].as_array().expect("models array");
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0]["name"], "gpt-5.3-codex");
+    assert_eq!(models[0]["model"], "gpt-5.3-codex");
+    assert!(models[0]["details"].is_object());
+
+    let show = build_ollama_show_response(&items, br#"{"model":"gpt-5.3-codex:latest"}"#)
+        .expect("show known model");
+    let value: Value = serde_json::from_str(&show).expect("valid json");
+    assert_eq!(value["display_name"], "GPT-5.3 Codex");
+    assert!(value["capabilities"].is_array());
+
+    let err = build_ollama_show_response(&items, br#"{"name":"llama3"}"#).expect_err("unknown");
+    assert_eq!(err, "model 'llama3' not found");
+}
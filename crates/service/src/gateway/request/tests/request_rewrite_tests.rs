@@ -1,4 +1,4 @@
-use super::apply_request_overrides;
+use super::{apply_request_overrides, convert_responses_body_for_chat_provider};
 use serde_json::json;
 
 #[test]
@@ -391,3 +391,45 @@ fn non_matching_endpoint_keeps_non_json_body() {
     let out = apply_request_overrides("/v1/non-standard", body.clone(), None, None, None);
     assert_eq!(out, body);
 }
+
+#[test]
+fn chat_provider_body_is_converted_from_responses_shape() {
+    let body = json!({
+        "model": "deepseek-chat",
+        "instructions": "be brief",
+        "input": [
+            { "role": "user", "content": [{ "type": "input_text", "text": "hi" }] }
+        ],
+        "reasoning": { "effort": "high" },
+        "stream": true,
+        "store": false,
+        "include": ["reasoning.encrypted_content"],
+        "prompt_cache_key": "thread-1"
+    });
+    let out = convert_responses_body_for_chat_provider(
+        &serde_json::to_vec(&body).expect("serialize request body"),
+        false,
+    )
+    .expect("convert body");
+    let value: serde_json::Value = serde_json::from_slice(&out).expect("parse output body");
+    let messages = value["messages"].as_array().expect("messages");
+    assert_eq!(messages[0]["role"], "system");
+    assert_eq!(messages[0]["content"], "be brief");
+    assert_eq!(messages[1]["role"], "user");
+    assert_eq!(value["stream"], false);
+    assert_eq!(value["reasoning_effort"], "high");
+    assert!(value.get("input").is_none());
+    assert!(value.get("include").is_none());
+    assert!(value.get("prompt_cache_key").is_none());
+    assert!(value.get("stream_options").is_none());
+
+    let streamed = convert_responses_body_for_chat_provider(
+        &serde_json::to_vec(&body).expect("serialize request body"),
+        true,
+    )
+    .expect("convert body");
+    let value: serde_json::Value = serde_json::from_slice(&streamed).expect("parse output body");
+    assert_eq!(value["stream"], true);
+    assert_eq!(value["stream_options"]["include_usage"], true);
+    assert!(convert_responses_body_for_chat_provider(b"not json", true).is_none());
+}
@@ -0,0 +1,63 @@
+use super::*;
+
+#[test]
+fn count_text_tokens_uses_bpe_vocabulary() {
+    assert_eq!(count_text_tokens(""), 0);
+    assert_eq!(count_text_tokens("hello"), 1);
+    // 中文注释：CJK 文本按 4 chars/token 估算会严重偏低。
+    let cjk = "今天天气很好，我们一起去公园散步吧。";
+    assert!(count_text_tokens(cjk) > (cjk.chars().count() as u64) / 4);
+}
+
+#[test]
+fn anthropic_count_includes_tool_definitions_and_images() {
+    let base = br#"{"messages":[{"role":"user","content":"hi"}]}"#;
+    let with_tools = br#"{
+        "messages":[{"role":"user","content":"hi"}],
+        "tools":[{"name":"read_file","description":"Read a file from disk","input_schema":{"type":"object","properties":{"path":{"type":"string"}},"required":["path"]}}]
+    }"#;
+    let with_image = br#"{
+        "messages":[{"role":"user","content":[
+            {"type":"text","text":"hi"},
+            {"type":"image","source":{"type":"base64","media_type":"image/png","data":"aGVsbG8gd29ybGQ="}}
+        ]}]
+    }"#;
+    let base_count = count_anthropic_messages_input_tokens(base).expect("base");
+    let tools_count = count_anthropic_messages_input_tokens(with_tools).expect("tools");
+    let image_count = count_anthropic_messages_input_tokens(with_image).expect("image");
+    assert!(tools_count > base_count + TOOL_OVERHEAD_TOKENS);
+    assert_eq!(image_count, base_count + IMAGE_DEFAULT_TOKENS);
+}
+
+#[test]
+fn responses_count_covers_function_calls_and_outputs() {
+    let body = br#"{
+        "instructions":"sys",
+        "input":[
+            {"type":"message","role":"user","content":[{"type":"input_text","text":"weather?"}]},
+            {"type":"function_call","call_id":"c1","name":"get_weather","arguments":"{\"city\":\"Paris\"}"},
+            {"type":"function_call_output","call_id":"c1","output":"sunny"}
+        ]
+    }"#;
+    let count = count_responses_input_tokens(body).expect("count");
+    let text_tokens = count_text_tokens("sys")
+        + count_text_tokens("weather?")
+        + count_text_tokens("get_weather")
+        + count_text_tokens("{\"city\":\"Paris\"}")
+        + count_text_tokens("sunny");
+    assert_eq!(
+        count,
+        REPLY_PRIMING_TOKENS + MESSAGE_OVERHEAD_TOKENS * 4 + text_tokens
+    );
+}
+
+#[test]
+fn request_body_count_dispatches_by_shape() {
+    assert!(count_request_body_input_tokens(br#"{"input":"hello"}"#).is_some());
+    assert!(
+        count_request_body_input_tokens(br#"{"messages":[{"role":"user","content":"hi"}]}"#)
+            .is_some()
+    );
+    assert_eq!(count_request_body_input_tokens(br#"{"prompt":"hi"}"#), None);
+    assert_eq!(count_request_body_input_tokens(b"not json"), None);
+}
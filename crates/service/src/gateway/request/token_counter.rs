@@ -0,0 +1,282 @@
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+
+// 中文注释：计数口径对齐上游 Codex 模型（o200k 词表，随二进制内置），
+// 每条消息、每个工具定义都有少量固定的协议开销 token。
+const MESSAGE_OVERHEAD_TOKENS: u64 = 3;
+const REPLY_PRIMING_TOKENS: u64 = 3;
+const TOOL_OVERHEAD_TOKENS: u64 = 8;
+const TOOLS_PREAMBLE_TOKENS: u64 = 12;
+const IMAGE_LOW_DETAIL_TOKENS: u64 = 85;
+const IMAGE_DEFAULT_TOKENS: u64 = 765;
+const FILE_ATTACHMENT_TOKENS: u64 = 1_500;
+
+fn tokenizer() -> &'static CoreBPE {
+    tiktoken_rs::o200k_base_singleton()
+}
+
+/// 词表构建耗时较长：服务启动时在后台线程先构建好，避免首个需要估算用量的请求承担这部分开销。
+pub(super) fn warm_up_tokenizer() {
+    let _ = std::thread::Builder::new()
+        .name("token-counter-warmup".to_string())
+        .spawn(|| {
+            let _ = tokenizer();
+        });
+}
+
+pub(super) fn count_text_tokens(text: &str) -> u64 {
+    if text.is_empty() {
+        return 0;
+    }
+    tokenizer().encode_ordinary(text).len() as u64
+}
+
+fn count_json_tokens(value: &Value) -> u64 {
+    match value {
+        Value::Null => 0,
+        Value::String(text) => count_text_tokens(text),
+        other => count_text_tokens(&other.to_string()),
+    }
+}
+
+fn is_inline_binary_string(text: &str) -> bool {
+    text.starts_with("data:") && text.contains(";base64,")
+}
+
+/// 兜底遍历未知结构：只统计文本，跳过 base64 等二进制载荷。
+fn count_generic_value_tokens(value: &Value) -> u64 {
+    match value {
+        Value::String(text) if is_inline_binary_string(text) => 0,
+        Value::String(text) => count_text_tokens(text),
+        Value::Array(items) => items.iter().map(count_generic_value_tokens).sum(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| key.as_str() != "data" && key.as_str() != "type")
+            .map(|(_, item)| count_generic_value_tokens(item))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn image_tokens_for_detail(detail: Option<&str>) -> u64 {
+    match detail {
+        Some(detail) if detail.eq_ignore_ascii_case("low") => IMAGE_LOW_DETAIL_TOKENS,
+        _ => IMAGE_DEFAULT_TOKENS,
+    }
+}
+
+fn count_content_part_tokens(part: &Value) -> u64 {
+    let Some(obj) = part.as_object() else {
+        return count_generic_value_tokens(part);
+    };
+    let part_type = obj.get("type").and_then(Value::as_str).unwrap_or_default();
+    match part_type {
+        "text" | "input_text" | "output_text" => obj
+            .get("text")
+            .and_then(Value::as_str)
+            .map(count_text_tokens)
+            .unwrap_or(0),
+        "thinking" => obj
+            .get("thinking")
+            .and_then(Value::as_str)
+            .map(count_text_tokens)
+            .unwrap_or(0),
+        "redacted_thinking" => 0,
+        "image" | "input_image" => {
+            image_tokens_for_detail(obj.get("detail").and_then(Value::as_str))
+        }
+        "image_url" => image_tokens_for_detail(
+            obj.get("image_url")
+                .and_then(|image| image.get("detail"))
+                .and_then(Value::as_str),
+        ),
+        "document" | "input_file" | "file" => {
+            let inline_text = obj
+                .get("source")
+                .filter(|source| source.get("type").and_then(Value::as_str) == Some("text"))
+                .and_then(|source| source.get("data"))
+                .and_then(Value::as_str);
+            match inline_text {
+                Some(text) => count_text_tokens(text),
+                None => FILE_ATTACHMENT_TOKENS,
+            }
+        }
+        "tool_use" => {
+            obj.get("name")
+                .and_then(Value::as_str)
+                .map(count_text_tokens)
+                .unwrap_or(0)
+                + obj.get("input").map(count_json_tokens).unwrap_or(0)
+        }
+        "tool_result" => obj.get("content").map(count_content_tokens).unwrap_or(0),
+        _ => count_generic_value_tokens(part),
+    }
+}
+
+fn count_content_tokens(content: &Value) -> u64 {
+    match content {
+        Value::String(text) => count_text_tokens(text),
+        Value::Array(parts) => parts.iter().map(count_content_part_tokens).sum(),
+        Value::Null => 0,
+        other => count_content_part_tokens(other),
+    }
+}
+
+fn count_tool_definition_tokens(tool: &Value) -> u64 {
+    let Some(obj) = tool.as_object() else {
+        return 0;
+    };
+    // 中文注释：兼容 Anthropic（input_schema）、Chat（function.parameters）与 Responses（parameters）三种形态。
+    let function = obj
+        .get("function")
+        .and_then(Value::as_object)
+        .unwrap_or(obj);
+    let name = function
+        .get("name")
+        .and_then(Value::as_str)
+        .map(count_text_tokens)
+        .unwrap_or(0);
+    let description = function
+        .get("description")
+        .and_then(Value::as_str)
+        .map(count_text_tokens)
+        .unwrap_or(0);
+    let schema = function
+        .get("parameters")
+        .or_else(|| function.get("input_schema"))
+        .map(count_json_tokens)
+        .unwrap_or(0);
+    if name == 0 && description == 0 && schema == 0 {
+        return count_json_tokens(tool);
+    }
+    TOOL_OVERHEAD_TOKENS + name + description + schema
+}
+
+fn count_tools_tokens(tools: Option<&Value>) -> u64 {
+    let Some(tools) = tools.and_then(Value::as_array) else {
+        return 0;
+    };
+    if tools.is_empty() {
+        return 0;
+    }
+    TOOLS_PREAMBLE_TOKENS + tools.iter().map(count_tool_definition_tokens).sum::<u64>()
+}
+
+fn count_chat_message_tokens(message: &Value) -> u64 {
+    let mut tokens = MESSAGE_OVERHEAD_TOKENS;
+    if let Some(content) = message.get("content") {
+        tokens += count_content_tokens(content);
+    }
+    if let Some(tool_calls) = message.get("tool_calls").and_then(Value::as_array) {
+        for call in tool_calls {
+            let function = call.get("function").unwrap_or(call);
+            tokens += function
+                .get("name")
+                .and_then(Value::as_str)
+                .map(count_text_tokens)
+                .unwrap_or(0);
+            tokens += function
+                .get("arguments")
+                .map(count_json_tokens)
+                .unwrap_or(0);
+        }
+    }
+    tokens
+}
+
+fn count_responses_input_item_tokens(item: &Value) -> u64 {
+    let Some(obj) = item.as_object() else {
+        return count_generic_value_tokens(item);
+    };
+    let item_type = obj.get("type").and_then(Value::as_str).unwrap_or("message");
+    match item_type {
+        "message" => {
+            MESSAGE_OVERHEAD_TOKENS + obj.get("content").map(count_content_tokens).unwrap_or(0)
+        }
+        "function_call" | "custom_tool_call" => {
+            MESSAGE_OVERHEAD_TOKENS
+                + obj
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(count_text_tokens)
+                    .unwrap_or(0)
+                + obj
+                    .get("arguments")
+                    .or_else(|| obj.get("input"))
+                    .map(count_json_tokens)
+                    .unwrap_or(0)
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            MESSAGE_OVERHEAD_TOKENS + obj.get("output").map(count_content_tokens).unwrap_or(0)
+        }
+        "reasoning" => obj
+            .get("summary")
+            .map(count_generic_value_tokens)
+            .unwrap_or(0),
+        _ => count_content_part_tokens(item),
+    }
+}
+
+/// 统计 Anthropic `/v1/messages` 请求的输入 token（system + messages + tools + 图片）。
+pub(super) fn count_anthropic_messages_input_tokens(body: &[u8]) -> Result<u64, String> {
+    let payload: Value =
+        serde_json::from_slice(body).map_err(|_| "invalid claude request json".to_string())?;
+    let Some(object) = payload.as_object() else {
+        return Err("claude request body must be an object".to_string());
+    };
+
+    let mut tokens = REPLY_PRIMING_TOKENS;
+    if let Some(system) = object.get("system") {
+        tokens += MESSAGE_OVERHEAD_TOKENS + count_content_tokens(system);
+    }
+    if let Some(messages) = object.get("messages").and_then(Value::as_array) {
+        tokens += messages.iter().map(count_chat_message_tokens).sum::<u64>();
+    }
+    tokens += count_tools_tokens(object.get("tools"));
+    Ok(tokens)
+}
+
+/// 统计 OpenAI Responses 请求的输入 token（instructions + input + tools）。
+pub(super) fn count_responses_input_tokens(body: &[u8]) -> Result<u64, String> {
+    let payload: Value =
+        serde_json::from_slice(body).map_err(|_| "invalid responses request json".to_string())?;
+    let Some(object) = payload.as_object() else {
+        return Err("responses request body must be an object".to_string());
+    };
+
+    let mut tokens = REPLY_PRIMING_TOKENS;
+    if let Some(instructions) = object.get("instructions").and_then(Value::as_str) {
+        if !instructions.is_empty() {
+            tokens += MESSAGE_OVERHEAD_TOKENS + count_text_tokens(instructions);
+        }
+    }
+    match object.get("input") {
+        Some(Value::String(text)) => tokens += MESSAGE_OVERHEAD_TOKENS + count_text_tokens(text),
+        Some(Value::Array(items)) => {
+            tokens += items
+                .iter()
+                .map(count_responses_input_item_tokens)
+                .sum::<u64>();
+        }
+        _ => {}
+    }
+    tokens += count_tools_tokens(object.get("tools"));
+    Ok(tokens)
+}
+
+/// 按请求体形态估算输入 token；用于上游未返回 usage 时的日志兜底。
+pub(super) fn count_request_body_input_tokens(body: &[u8]) -> Option<u64> {
+    let payload: Value = serde_json::from_slice(body).ok()?;
+    let object = payload.as_object()?;
+    if object.contains_key("input") || object.contains_key("instructions") {
+        return count_responses_input_tokens(body).ok();
+    }
+    if object.contains_key("messages") {
+        return count_anthropic_messages_input_tokens(body).ok();
+    }
+    None
+}
+
+#[cfg(test)]
+#[path = "tests/token_counter_tests.rs"]
+mod tests;
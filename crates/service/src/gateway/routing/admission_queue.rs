@@ -0,0 +1,238 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const ADMISSION_QUEUE_ENABLED_ENV: &str = "CODEXMANAGER_ADMISSION_QUEUE_ENABLED";
+const ADMISSION_QUEUE_MAX_SIZE_ENV: &str = "CODEXMANAGER_ADMISSION_QUEUE_MAX_SIZE";
+const ADMISSION_QUEUE_PER_KEY_MAX_ENV: &str = "CODEXMANAGER_ADMISSION_QUEUE_PER_KEY_MAX";
+const ADMISSION_QUEUE_MAX_WAIT_MS_ENV: &str = "CODEXMANAGER_ADMISSION_QUEUE_MAX_WAIT_MS";
+const DEFAULT_ADMISSION_QUEUE_ENABLED: bool = false;
+const DEFAULT_ADMISSION_QUEUE_MAX_SIZE: usize = 64;
+const DEFAULT_ADMISSION_QUEUE_PER_KEY_MAX: usize = 16;
+const DEFAULT_ADMISSION_QUEUE_MAX_WAIT_MS: u64 = 30_000;
+// 中文注释：冷却到期没有事件通知，排队请求按该间隔轮询一次；并发槽释放会立即唤醒。
+const ADMISSION_QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static ADMISSION_QUEUE_ENABLED: AtomicBool = AtomicBool::new(DEFAULT_ADMISSION_QUEUE_ENABLED);
+static ADMISSION_QUEUE_MAX_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_ADMISSION_QUEUE_MAX_SIZE);
+static ADMISSION_QUEUE_PER_KEY_MAX: AtomicUsize =
+    AtomicUsize::new(DEFAULT_ADMISSION_QUEUE_PER_KEY_MAX);
+static ADMISSION_QUEUE_MAX_WAIT_MS: AtomicU64 = AtomicU64::new(DEFAULT_ADMISSION_QUEUE_MAX_WAIT_MS);
+static ADMISSION_QUEUE: OnceLock<AdmissionQueue> = OnceLock::new();
+
+/// 排队结果：未排队（功能关闭或池子有空位）、排队后放行、等待超时，或因队列已满/没有剩余时间被拒绝。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AdmissionOutcome {
+    NotQueued,
+    Admitted { waited_ms: u64 },
+    TimedOut { waited_ms: u64 },
+    Rejected { reason: &'static str },
+}
+
+#[derive(Default)]
+struct AdmissionQueueState {
+    // 中文注释：每个 Key 一条 FIFO，key_order 轮转决定队头，保证多个 Key 之间公平出队。
+    tickets_by_key: HashMap<String, VecDeque<u64>>,
+    key_order: VecDeque<String>,
+    total: usize,
+    next_ticket: u64,
+}
+
+struct AdmissionQueue {
+    state: Mutex<AdmissionQueueState>,
+    changed: Condvar,
+}
+
+impl AdmissionQueueState {
+    fn head_ticket(&self) -> Option<u64> {
+        let key = self.key_order.front()?;
+        self.tickets_by_key.get(key)?.front().copied()
+    }
+
+    fn push(&mut self, key_id: &str) -> u64 {
+        self.next_ticket = self.next_ticket.wrapping_add(1);
+        let ticket = self.next_ticket;
+        let tickets = self.tickets_by_key.entry(key_id.to_string()).or_default();
+        if tickets.is_empty() {
+            self.key_order.push_back(key_id.to_string());
+        }
+        tickets.push_back(ticket);
+        self.total += 1;
+        ticket
+    }
+
+    fn remove(&mut self, key_id: &str, ticket: u64, admitted: bool) {
+        let Some(tickets) = self.tickets_by_key.get_mut(key_id) else {
+            return;
+        };
+        let before = tickets.len();
+        tickets.retain(|item| *item != ticket);
+        if tickets.len() == before {
+            return;
+        }
+        self.total = self.total.saturating_sub(1);
+        let key_drained = tickets.is_empty();
+        if key_drained {
+            self.tickets_by_key.remove(key_id);
+        }
+        if let Some(pos) = self.key_order.iter().position(|item| item == key_id) {
+            // 中文注释：出队的 Key 挪到队尾，让其它 Key 的请求先拿到下一次空位。
+            if key_drained || admitted {
+                self.key_order.remove(pos);
+            }
+            if !key_drained && admitted {
+                self.key_order.push_back(key_id.to_string());
+            }
+        }
+    }
+}
+
+fn admission_queue() -> &'static AdmissionQueue {
+    ADMISSION_QUEUE.get_or_init(|| AdmissionQueue {
+        state: Mutex::new(AdmissionQueueState::default()),
+        changed: Condvar::new(),
+    })
+}
+
+pub(crate) fn admission_queue_enabled() -> bool {
+    ADMISSION_QUEUE_ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn admission_queue_max_wait() -> Duration {
+    Duration::from_millis(ADMISSION_QUEUE_MAX_WAIT_MS.load(Ordering::Relaxed))
+}
+
+/// 排队被拒或等待超时时返回给客户端的 Retry-After 秒数：队列最长在一个等待周期内轮转完。
+pub(crate) fn admission_retry_after_secs() -> u64 {
+    admission_queue_max_wait().as_secs_f64().ceil().max(1.0) as u64
+}
+
+/// 并发槽释放或冷却被清除时调用，唤醒排队请求重新检查账号池。
+pub(crate) fn notify_admission_capacity_changed() {
+    if let Some(queue) = ADMISSION_QUEUE.get() {
+        queue.changed.notify_all();
+    }
+}
+
+/// 账号池饱和时按 Key 公平排队，直到池子有空位或等待超时；`max_wait` 应已按请求总截止时间裁剪。
+pub(crate) fn wait_for_admission<F>(
+    key_id: &str,
+    max_wait: Option<Duration>,
+    pool_saturated: F,
+) -> AdmissionOutcome
+where
+    F: Fn() -> bool,
+{
+    if !admission_queue_enabled() || !pool_saturated() {
+        return AdmissionOutcome::NotQueued;
+    }
+    let Some(max_wait) = max_wait.filter(|wait| !wait.is_zero()) else {
+        return AdmissionOutcome::Rejected { reason: "deadline" };
+    };
+    let queue = admission_queue();
+    let mut state = crate::lock_utils::lock_recover(&queue.state, "admission_queue");
+    if state.total >= ADMISSION_QUEUE_MAX_SIZE.load(Ordering::Relaxed) {
+        super::record_admission_queue_rejected();
+        return AdmissionOutcome::Rejected {
+            reason: "queue_full",
+        };
+    }
+    let queued_for_key = state.tickets_by_key.get(key_id).map_or(0, VecDeque::len);
+    if queued_for_key >= ADMISSION_QUEUE_PER_KEY_MAX.load(Ordering::Relaxed) {
+        super::record_admission_queue_rejected();
+        return AdmissionOutcome::Rejected {
+            reason: "key_queue_full",
+        };
+    }
+
+    let ticket = state.push(key_id);
+    super::record_admission_queue_enqueue();
+    let started_at = Instant::now();
+    let wait_deadline = started_at + max_wait;
+    loop {
+        if state.head_ticket() == Some(ticket) && !pool_saturated() {
+            state.remove(key_id, ticket, true);
+            drop(state);
+            queue.changed.notify_all();
+            let waited_ms = super::duration_to_millis(started_at.elapsed());
+            super::record_admission_queue_dequeue(waited_ms, true);
+            return AdmissionOutcome::Admitted { waited_ms };
+        }
+        let now = Instant::now();
+        if now >= wait_deadline {
+            state.remove(key_id, ticket, false);
+            drop(state);
+            queue.changed.notify_all();
+            let waited_ms = super::duration_to_millis(started_at.elapsed());
+            super::record_admission_queue_dequeue(waited_ms, false);
+            return AdmissionOutcome::TimedOut { waited_ms };
+        }
+        let slice = ADMISSION_QUEUE_POLL_INTERVAL.min(wait_deadline - now);
+        state = match queue.changed.wait_timeout(state, slice) {
+            Ok((guard, _)) => guard,
+            Err(poisoned) => {
+                log::warn!("event=lock_poisoned lock=admission_queue action=recover");
+                poisoned.into_inner().0
+            }
+        };
+    }
+}
+
+pub(super) fn reload_from_env() {
+    ADMISSION_QUEUE_ENABLED.store(
+        env_bool_or(ADMISSION_QUEUE_ENABLED_ENV, DEFAULT_ADMISSION_QUEUE_ENABLED),
+        Ordering::Relaxed,
+    );
+    ADMISSION_QUEUE_MAX_SIZE.store(
+        env_usize_or(
+            ADMISSION_QUEUE_MAX_SIZE_ENV,
+            DEFAULT_ADMISSION_QUEUE_MAX_SIZE,
+        ),
+        Ordering::Relaxed,
+    );
+    ADMISSION_QUEUE_PER_KEY_MAX.store(
+        env_usize_or(
+            ADMISSION_QUEUE_PER_KEY_MAX_ENV,
+            DEFAULT_ADMISSION_QUEUE_PER_KEY_MAX,
+        ),
+        Ordering::Relaxed,
+    );
+    ADMISSION_QUEUE_MAX_WAIT_MS.store(
+        env_u64_or(
+            ADMISSION_QUEUE_MAX_WAIT_MS_ENV,
+            DEFAULT_ADMISSION_QUEUE_MAX_WAIT_MS,
+        ),
+        Ordering::Relaxed,
+    );
+    notify_admission_capacity_changed();
+}
+
+fn env_bool_or(name: &str, default: bool) -> bool {
+    let Ok(raw) = std::env::var(name) else {
+        return default;
+    };
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => true,
+        "0" | "false" | "no" | "off" => false,
+        _ => default,
+    }
+}
+
+fn env_usize_or(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .unwrap_or(default)
+}
+
+fn env_u64_or(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+#[path = "tests/admission_queue_tests.rs"]
+mod tests;
@@ -1,27 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Mutex, OnceLock};
 
-use codexmanager_core::storage::now_ts;
+use codexmanager_core::rpc::types::AccountCooldownItem;
+use codexmanager_core::storage::{now_ts, AccountCooldownRecord};
 
-const DEFAULT_ACCOUNT_COOLDOWN_SECS: i64 = 20;
-const DEFAULT_ACCOUNT_COOLDOWN_NETWORK_SECS: i64 = DEFAULT_ACCOUNT_COOLDOWN_SECS;
-const DEFAULT_ACCOUNT_COOLDOWN_429_SECS: i64 = 45;
-const DEFAULT_ACCOUNT_COOLDOWN_5XX_SECS: i64 = 30;
-const DEFAULT_ACCOUNT_COOLDOWN_4XX_SECS: i64 = DEFAULT_ACCOUNT_COOLDOWN_SECS;
-const DEFAULT_ACCOUNT_COOLDOWN_CHALLENGE_SECS: i64 = 6;
-const ACCOUNT_RATE_LIMIT_COOLDOWN_LADDER_SECS: [i64; 4] =
-    [DEFAULT_ACCOUNT_COOLDOWN_429_SECS, 300, 1800, 7200];
-// 中文注释：offense 只用于“短时间内持续 429”场景；超过该时间视为新一轮，避免长期记仇导致误伤。
-const ACCOUNT_RATE_LIMIT_OFFENSE_FORGET_AFTER_SECS: i64 = 30 * 60;
+use super::cooldown_policy::{current_cooldown_policy, CooldownPolicy};
 
 const ACCOUNT_COOLDOWN_CLEANUP_INTERVAL_SECS: i64 = 30;
 
 #[derive(Default)]
 struct AccountCooldownState {
     entries: HashMap<String, i64>,
+    reasons: HashMap<String, CooldownReason>,
     offense_counts: HashMap<String, u32>,
     offense_last_at: HashMap<String, i64>,
     last_cleanup_at: i64,
+    // 中文注释：冷却或违规计数有变化、等待后台线程落库的账号。
+    dirty: HashSet<String>,
 }
 
 static ACCOUNT_COOLDOWN_UNTIL: OnceLock<Mutex<AccountCooldownState>> = OnceLock::new();
@@ -36,35 +31,65 @@ pub(super) enum CooldownReason {
     Challenge,
 }
 
-fn cooldown_secs_for_reason(reason: CooldownReason) -> i64 {
+impl CooldownReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            CooldownReason::Default => "default",
+            CooldownReason::Network => "network",
+            CooldownReason::RateLimited => "rate_limited",
+            CooldownReason::Upstream5xx => "upstream_5xx",
+            CooldownReason::Upstream4xx => "upstream_4xx",
+            CooldownReason::Challenge => "challenge",
+        }
+    }
+
+    fn from_persisted(raw: &str) -> Self {
+        match raw {
+            "network" => CooldownReason::Network,
+            "rate_limited" => CooldownReason::RateLimited,
+            "upstream_5xx" => CooldownReason::Upstream5xx,
+            "upstream_4xx" => CooldownReason::Upstream4xx,
+            "challenge" => CooldownReason::Challenge,
+            _ => CooldownReason::Default,
+        }
+    }
+}
+
+fn cooldown_secs_for_reason(policy: &CooldownPolicy, reason: CooldownReason) -> i64 {
     match reason {
-        CooldownReason::Default => DEFAULT_ACCOUNT_COOLDOWN_SECS,
-        CooldownReason::Network => DEFAULT_ACCOUNT_COOLDOWN_NETWORK_SECS,
-        CooldownReason::RateLimited => DEFAULT_ACCOUNT_COOLDOWN_429_SECS,
-        CooldownReason::Upstream5xx => DEFAULT_ACCOUNT_COOLDOWN_5XX_SECS,
-        CooldownReason::Upstream4xx => DEFAULT_ACCOUNT_COOLDOWN_4XX_SECS,
-        CooldownReason::Challenge => DEFAULT_ACCOUNT_COOLDOWN_CHALLENGE_SECS,
+        CooldownReason::Default => policy.default_secs,
+        CooldownReason::Network => policy.network_secs,
+        CooldownReason::RateLimited => ladder_secs_for_offense(policy, 1),
+        CooldownReason::Upstream5xx => policy.upstream_5xx_secs,
+        CooldownReason::Upstream4xx => policy.upstream_4xx_secs,
+        CooldownReason::Challenge => policy.challenge_secs,
     }
 }
 
+fn ladder_secs_for_offense(policy: &CooldownPolicy, offense_count: u32) -> i64 {
+    let ladder = &policy.rate_limit_ladder_secs;
+    let idx = (offense_count.saturating_sub(1) as usize).min(ladder.len().saturating_sub(1));
+    ladder.get(idx).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
 fn rate_limit_cooldown_secs_for_offense(offense_count: u32) -> i64 {
-    let idx = offense_count
-        .saturating_sub(1)
-        .min((ACCOUNT_RATE_LIMIT_COOLDOWN_LADDER_SECS.len() - 1) as u32) as usize;
-    ACCOUNT_RATE_LIMIT_COOLDOWN_LADDER_SECS[idx]
+    ladder_secs_for_offense(&current_cooldown_policy(), offense_count)
 }
 
 fn cooldown_secs_for_mark(
+    policy: &CooldownPolicy,
     offense_counts: &mut HashMap<String, u32>,
     offense_last_at: &mut HashMap<String, i64>,
     account_id: &str,
     reason: CooldownReason,
+    hint_secs: Option<i64>,
     now: i64,
 ) -> i64 {
     match reason {
         CooldownReason::RateLimited => {
             if let Some(last) = offense_last_at.get(account_id).copied() {
-                if now.saturating_sub(last) > ACCOUNT_RATE_LIMIT_OFFENSE_FORGET_AFTER_SECS {
+                if now.saturating_sub(last) > policy.rate_limit_forget_after_secs {
                     offense_counts.remove(account_id);
                 }
             }
@@ -73,9 +98,10 @@ fn cooldown_secs_for_mark(
                 .and_modify(|count| *count = count.saturating_add(1))
                 .or_insert(1);
             offense_last_at.insert(account_id.to_string(), now);
-            rate_limit_cooldown_secs_for_offense(*offense_count)
+            // 中文注释：策略要求按 Retry-After / 额度重置时间冷却时优先用提示值，缺失时回落到阶梯。
+            hint_secs.unwrap_or_else(|| ladder_secs_for_offense(policy, *offense_count))
         }
-        _ => cooldown_secs_for_reason(reason),
+        _ => cooldown_secs_for_reason(policy, reason),
     }
 }
 
@@ -108,65 +134,300 @@ pub(super) fn cooldown_reason_for_status(status: u16) -> CooldownReason {
     }
 }
 
-pub(super) fn is_account_in_cooldown(account_id: &str) -> bool {
+fn with_state<T>(mutator: impl FnOnce(&mut AccountCooldownState) -> T) -> T {
     let lock = ACCOUNT_COOLDOWN_UNTIL.get_or_init(|| Mutex::new(AccountCooldownState::default()));
     let mut state = crate::lock_utils::lock_recover(lock, "account_cooldown_until");
+    mutator(&mut state)
+}
+
+/// 启动时从库里恢复冷却与 429 阶梯；读库在全局锁外完成，合并时保留内存里更晚的到期时间。
+pub(super) fn load_persisted_cooldowns() {
+    // 中文注释：按库里的绝对到期时间恢复，剩余时长自然等于 until - now。
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    let now = now_ts();
+    let offense_since = now - current_cooldown_policy().rate_limit_forget_after_secs;
+    let _ = storage.prune_account_cooldowns(now, offense_since);
+    let records = match storage.list_account_cooldowns(now, offense_since) {
+        Ok(records) => records,
+        Err(err) => {
+            log::warn!("load account cooldowns failed: {err}");
+            return;
+        }
+    };
+    with_state(|state| merge_persisted_cooldowns(state, records, now, offense_since));
+}
+
+fn merge_persisted_cooldowns(
+    state: &mut AccountCooldownState,
+    records: Vec<AccountCooldownRecord>,
+    now: i64,
+    offense_since: i64,
+) {
+    for record in records {
+        if record.cooldown_until > now
+            && state
+                .entries
+                .get(&record.account_id)
+                .is_none_or(|until| *until < record.cooldown_until)
+        {
+            state
+                .entries
+                .insert(record.account_id.clone(), record.cooldown_until);
+            state.reasons.insert(
+                record.account_id.clone(),
+                CooldownReason::from_persisted(&record.reason),
+            );
+        }
+        if record.offense_count > 0
+            && record.offense_last_at >= offense_since
+            && !state.offense_counts.contains_key(&record.account_id)
+        {
+            state
+                .offense_counts
+                .insert(record.account_id.clone(), record.offense_count);
+            state
+                .offense_last_at
+                .insert(record.account_id, record.offense_last_at);
+        }
+    }
+}
+
+fn cooldown_record_for(
+    state: &AccountCooldownState,
+    account_id: &str,
+    now: i64,
+) -> Option<AccountCooldownRecord> {
+    let cooldown_until = state
+        .entries
+        .get(account_id)
+        .copied()
+        .filter(|until| *until > now)
+        .unwrap_or(0);
+    let offense_count = state.offense_counts.get(account_id).copied().unwrap_or(0);
+    if cooldown_until == 0 && offense_count == 0 {
+        return None;
+    }
+    Some(AccountCooldownRecord {
+        account_id: account_id.to_string(),
+        cooldown_until,
+        reason: state
+            .reasons
+            .get(account_id)
+            .copied()
+            .unwrap_or(CooldownReason::Default)
+            .as_str()
+            .to_string(),
+        offense_count,
+        offense_last_at: state.offense_last_at.get(account_id).copied().unwrap_or(0),
+        updated_at: now,
+    })
+}
+
+/// 由后台落库线程调用：在锁内取出脏账号的最新快照，锁外批量写库。
+pub(super) fn flush_pending_cooldowns() {
+    let now = now_ts();
+    let pending = with_state(|state| {
+        std::mem::take(&mut state.dirty)
+            .into_iter()
+            .map(|account_id| {
+                let record = cooldown_record_for(state, &account_id, now);
+                (account_id, record)
+            })
+            .collect::<Vec<_>>()
+    });
+    if pending.is_empty() {
+        return;
+    }
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    for (account_id, record) in pending {
+        let result = match record {
+            Some(record) => storage.upsert_account_cooldown(&record),
+            None => storage.delete_account_cooldown(&account_id),
+        };
+        if let Err(err) = result {
+            log::warn!("persist account cooldown failed: account_id={account_id} err={err}");
+        }
+    }
+}
+
+pub(super) fn is_account_in_cooldown(account_id: &str) -> bool {
+    account_cooldown_reason(account_id).is_some()
+}
+
+/// 账号仍在冷却时返回冷却原因；候选循环据此区分额度类冷却与其它故障冷却。
+pub(super) fn account_cooldown_reason(account_id: &str) -> Option<CooldownReason> {
     let now = now_ts();
-    match state.entries.get(account_id).copied() {
-        Some(until) if until > now => true,
+    with_state(|state| match state.entries.get(account_id).copied() {
+        Some(until) if until > now => Some(
+            state
+                .reasons
+                .get(account_id)
+                .copied()
+                .unwrap_or(CooldownReason::Default),
+        ),
         Some(_) => {
             state.entries.remove(account_id);
-            false
+            state.reasons.remove(account_id);
+            None
         }
-        None => false,
-    }
+        None => None,
+    })
 }
 
 pub(super) fn mark_account_cooldown(account_id: &str, reason: CooldownReason) {
-    let lock = ACCOUNT_COOLDOWN_UNTIL.get_or_init(|| Mutex::new(AccountCooldownState::default()));
-    let mut guard = crate::lock_utils::lock_recover(lock, "account_cooldown_until");
-    let state = &mut *guard;
+    mark_account_cooldown_with_hint(account_id, reason, None);
+}
+
+/// 标记冷却；`hint_secs` 仅对 429 生效，来自 Retry-After 或账号额度重置时间。
+pub(super) fn mark_account_cooldown_with_hint(
+    account_id: &str,
+    reason: CooldownReason,
+    hint_secs: Option<i64>,
+) {
     super::record_gateway_cooldown_mark();
     let now = now_ts();
-    maybe_cleanup_expired_cooldowns(state, now);
-    let cooldown_until = now
-        + cooldown_secs_for_mark(
+    let policy = current_cooldown_policy();
+    with_state(|state| {
+        maybe_cleanup_expired_cooldowns(state, &policy, now);
+        let cooldown_until = now
+            + cooldown_secs_for_mark(
+                &policy,
+                &mut state.offense_counts,
+                &mut state.offense_last_at,
+                account_id,
+                reason,
+                hint_secs,
+                now,
+            );
+        // 中文注释：同账号短时间内可能触发不同失败类型；保留更晚的 until 可避免被较短冷却覆盖。
+        match state.entries.get_mut(account_id) {
+            Some(until) => {
+                if cooldown_until > *until {
+                    *until = cooldown_until;
+                    state.reasons.insert(account_id.to_string(), reason);
+                }
+            }
+            None => {
+                state.entries.insert(account_id.to_string(), cooldown_until);
+                state.reasons.insert(account_id.to_string(), reason);
+            }
+        }
+        state.dirty.insert(account_id.to_string());
+    });
+    super::ensure_routing_state_flusher();
+}
+
+pub(super) fn mark_account_cooldown_for_status(account_id: &str, status: u16) {
+    mark_account_cooldown(account_id, cooldown_reason_for_status(status));
+}
+
+pub(super) fn clear_account_cooldown(account_id: &str) {
+    let changed = with_state(|state| {
+        // 中文注释：成功请求很频繁，只有冷却或违规计数真的变化时才落库。
+        let had_cooldown = state.entries.remove(account_id).is_some();
+        state.reasons.remove(account_id);
+        let had_offense = state.offense_counts.contains_key(account_id);
+        decay_offense_count_for_success(
             &mut state.offense_counts,
             &mut state.offense_last_at,
             account_id,
-            reason,
-            now,
         );
-    // 中文注释：同账号短时间内可能触发不同失败类型；保留更晚的 until 可避免被较短冷却覆盖。
-    match state.entries.get_mut(account_id) {
-        Some(until) => {
-            if cooldown_until > *until {
-                *until = cooldown_until;
-            }
-        }
-        None => {
-            state.entries.insert(account_id.to_string(), cooldown_until);
+        let changed = had_cooldown || had_offense;
+        if changed {
+            state.dirty.insert(account_id.to_string());
         }
+        changed
+    });
+    if changed {
+        super::ensure_routing_state_flusher();
     }
 }
 
-pub(super) fn mark_account_cooldown_for_status(account_id: &str, status: u16) {
-    mark_account_cooldown(account_id, cooldown_reason_for_status(status));
+/// RPC 使用：列出仍在冷却或仍有 429 违规计数的账号，按剩余冷却时长倒序。
+pub(crate) fn list_account_cooldowns() -> Vec<AccountCooldownItem> {
+    let now = now_ts();
+    let mut items = with_state(|state| {
+        let mut account_ids = state
+            .entries
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(account_id, _)| account_id.clone())
+            .collect::<Vec<_>>();
+        for account_id in state.offense_counts.keys() {
+            if !account_ids.contains(account_id) {
+                account_ids.push(account_id.clone());
+            }
+        }
+        account_ids
+            .into_iter()
+            .filter_map(|account_id| cooldown_record_for(state, &account_id, now))
+            .map(|record| AccountCooldownItem {
+                remaining_secs: record.cooldown_until.saturating_sub(now).max(0),
+                account_id: record.account_id,
+                cooldown_until: record.cooldown_until,
+                reason: record.reason,
+                offense_count: record.offense_count,
+                offense_last_at: record.offense_last_at,
+            })
+            .collect::<Vec<_>>()
+    });
+    items.sort_by(|a, b| {
+        b.remaining_secs
+            .cmp(&a.remaining_secs)
+            .then_with(|| a.account_id.cmp(&b.account_id))
+    });
+    items
 }
 
-pub(super) fn clear_account_cooldown(account_id: &str) {
-    let lock = ACCOUNT_COOLDOWN_UNTIL.get_or_init(|| Mutex::new(AccountCooldownState::default()));
-    let mut guard = crate::lock_utils::lock_recover(lock, "account_cooldown_until");
-    let state = &mut *guard;
-    state.entries.remove(account_id);
-    decay_offense_count_for_success(
-        &mut state.offense_counts,
-        &mut state.offense_last_at,
-        account_id,
-    );
+/// 手动清除冷却与 429 阶梯；账号为空时清空全部，返回清除的账号数。
+pub(crate) fn clear_account_cooldowns(account_id: Option<&str>) -> usize {
+    let account_id = account_id.map(str::trim).filter(|value| !value.is_empty());
+    let flush_guard = super::routing_state_flush_guard();
+    let cleared = with_state(|state| match account_id {
+        Some(account_id) => {
+            state.dirty.remove(account_id);
+            let had_cooldown = state.entries.remove(account_id).is_some();
+            let had_offense = state.offense_counts.remove(account_id).is_some();
+            state.reasons.remove(account_id);
+            state.offense_last_at.remove(account_id);
+            usize::from(had_cooldown || had_offense)
+        }
+        None => {
+            let mut account_ids = state.entries.keys().cloned().collect::<Vec<_>>();
+            account_ids.extend(state.offense_counts.keys().cloned());
+            account_ids.sort_unstable();
+            account_ids.dedup();
+            state.entries.clear();
+            state.reasons.clear();
+            state.offense_counts.clear();
+            state.offense_last_at.clear();
+            state.dirty.clear();
+            account_ids.len()
+        }
+    });
+    if let Some(storage) = super::open_storage() {
+        let result = match account_id {
+            Some(account_id) => storage.delete_account_cooldown(account_id),
+            None => storage.clear_account_cooldowns().map(|_| ()),
+        };
+        if let Err(err) = result {
+            log::warn!("clear persisted account cooldowns failed: {err}");
+        }
+    }
+    drop(flush_guard);
+    super::notify_admission_capacity_changed();
+    cleared
 }
 
-fn maybe_cleanup_expired_cooldowns(state: &mut AccountCooldownState, now: i64) {
+fn maybe_cleanup_expired_cooldowns(
+    state: &mut AccountCooldownState,
+    policy: &CooldownPolicy,
+    now: i64,
+) {
     if state.last_cleanup_at != 0
         && now.saturating_sub(state.last_cleanup_at) < ACCOUNT_COOLDOWN_CLEANUP_INTERVAL_SECS
     {
@@ -174,9 +435,13 @@ fn maybe_cleanup_expired_cooldowns(state: &mut AccountCooldownState, now: i64) {
     }
     state.last_cleanup_at = now;
     state.entries.retain(|_, until| *until > now);
+    let entries = &state.entries;
+    state
+        .reasons
+        .retain(|account_id, _| entries.contains_key(account_id));
     let mut stale_offenses = Vec::new();
     for (account_id, last) in state.offense_last_at.iter() {
-        if now.saturating_sub(*last) > ACCOUNT_RATE_LIMIT_OFFENSE_FORGET_AFTER_SECS {
+        if now.saturating_sub(*last) > policy.rate_limit_forget_after_secs {
             stale_offenses.push(account_id.clone());
         }
     }
@@ -187,12 +452,10 @@ fn maybe_cleanup_expired_cooldowns(state: &mut AccountCooldownState, now: i64) {
 }
 
 pub(super) fn clear_runtime_state() {
+    // 中文注释：只清内存，库里的冷却保留；服务启动时再通过 load_persisted_cooldowns 从当前库恢复。
     let lock = ACCOUNT_COOLDOWN_UNTIL.get_or_init(|| Mutex::new(AccountCooldownState::default()));
     let mut state = crate::lock_utils::lock_recover(lock, "account_cooldown_until");
-    state.entries.clear();
-    state.offense_counts.clear();
-    state.offense_last_at.clear();
-    state.last_cleanup_at = 0;
+    *state = AccountCooldownState::default();
 }
 
 #[cfg(test)]
@@ -0,0 +1,286 @@
+use codexmanager_core::storage::{now_ts, Storage, UsageSnapshotRecord};
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+const COOLDOWN_DEFAULT_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_DEFAULT_SECS";
+const COOLDOWN_NETWORK_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_NETWORK_SECS";
+const COOLDOWN_4XX_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_4XX_SECS";
+const COOLDOWN_5XX_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_5XX_SECS";
+const COOLDOWN_CHALLENGE_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_CHALLENGE_SECS";
+const COOLDOWN_429_LADDER_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_429_LADDER_SECS";
+const COOLDOWN_429_FORGET_AFTER_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_429_FORGET_AFTER_SECS";
+const COOLDOWN_429_SOURCE_ENV: &str = "CODEXMANAGER_COOLDOWN_429_SOURCE";
+const COOLDOWN_429_MAX_SECS_ENV: &str = "CODEXMANAGER_COOLDOWN_429_MAX_SECS";
+
+pub(crate) const RATE_LIMIT_SOURCE_LADDER: &str = "ladder";
+pub(crate) const RATE_LIMIT_SOURCE_RETRY_AFTER: &str = "retry_after";
+pub(crate) const RATE_LIMIT_SOURCE_USAGE_RESET: &str = "usage_reset";
+pub(crate) const RATE_LIMIT_SOURCE_OPTIONS: [&str; 3] = [
+    RATE_LIMIT_SOURCE_LADDER,
+    RATE_LIMIT_SOURCE_RETRY_AFTER,
+    RATE_LIMIT_SOURCE_USAGE_RESET,
+];
+
+const DEFAULT_COOLDOWN_SECS: i64 = 20;
+const DEFAULT_COOLDOWN_5XX_SECS: i64 = 30;
+const DEFAULT_COOLDOWN_CHALLENGE_SECS: i64 = 6;
+const DEFAULT_RATE_LIMIT_LADDER_SECS: [i64; 4] = [45, 300, 1800, 7200];
+// 中文注释：offense 只用于“短时间内持续 429”场景；超过该时间视为新一轮，避免长期记仇导致误伤。
+const DEFAULT_RATE_LIMIT_FORGET_AFTER_SECS: i64 = 30 * 60;
+const DEFAULT_RATE_LIMIT_MAX_SECS: i64 = 6 * 60 * 60;
+// 中文注释：任何冷却都不超过一天，防止误填的超大值把账号长期踢出轮换。
+const MAX_COOLDOWN_SECS: i64 = 24 * 60 * 60;
+const MAX_RATE_LIMIT_LADDER_STEPS: usize = 8;
+
+static COOLDOWN_POLICY: OnceLock<RwLock<CooldownPolicy>> = OnceLock::new();
+static COOLDOWN_POLICY_LOADED: OnceLock<()> = OnceLock::new();
+
+/// 账号冷却策略：各失败类型的冷却秒数、429 退避阶梯与遗忘窗口，以及 429 冷却时长的来源。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CooldownPolicy {
+    pub(crate) default_secs: i64,
+    pub(crate) network_secs: i64,
+    pub(crate) upstream_4xx_secs: i64,
+    pub(crate) upstream_5xx_secs: i64,
+    pub(crate) challenge_secs: i64,
+    pub(crate) rate_limit_ladder_secs: Vec<i64>,
+    pub(crate) rate_limit_forget_after_secs: i64,
+    pub(crate) rate_limit_source: &'static str,
+    pub(crate) rate_limit_max_secs: i64,
+}
+
+impl Default for CooldownPolicy {
+    fn default() -> Self {
+        Self {
+            default_secs: DEFAULT_COOLDOWN_SECS,
+            network_secs: DEFAULT_COOLDOWN_SECS,
+            upstream_4xx_secs: DEFAULT_COOLDOWN_SECS,
+            upstream_5xx_secs: DEFAULT_COOLDOWN_5XX_SECS,
+            challenge_secs: DEFAULT_COOLDOWN_CHALLENGE_SECS,
+            rate_limit_ladder_secs: DEFAULT_RATE_LIMIT_LADDER_SECS.to_vec(),
+            rate_limit_forget_after_secs: DEFAULT_RATE_LIMIT_FORGET_AFTER_SECS,
+            rate_limit_source: RATE_LIMIT_SOURCE_LADDER,
+            rate_limit_max_secs: DEFAULT_RATE_LIMIT_MAX_SECS,
+        }
+    }
+}
+
+/// 冷却策略的局部修改；字段名与 `CooldownPolicy` 序列化结果一致，持久化的 JSON 可直接回放。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CooldownPolicyPatch {
+    pub(crate) default_secs: Option<i64>,
+    pub(crate) network_secs: Option<i64>,
+    pub(crate) upstream_4xx_secs: Option<i64>,
+    pub(crate) upstream_5xx_secs: Option<i64>,
+    pub(crate) challenge_secs: Option<i64>,
+    pub(crate) rate_limit_ladder_secs: Option<Vec<i64>>,
+    pub(crate) rate_limit_forget_after_secs: Option<i64>,
+    pub(crate) rate_limit_source: Option<String>,
+    pub(crate) rate_limit_max_secs: Option<i64>,
+}
+
+fn normalize_secs(field: &str, secs: i64) -> Result<i64, String> {
+    if !(0..=MAX_COOLDOWN_SECS).contains(&secs) {
+        return Err(format!("{field} must be between 0 and {MAX_COOLDOWN_SECS}"));
+    }
+    Ok(secs)
+}
+
+pub(crate) fn normalize_rate_limit_source(raw: &str) -> Result<&'static str, String> {
+    match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+        "" | "ladder" => Ok(RATE_LIMIT_SOURCE_LADDER),
+        "retry_after" => Ok(RATE_LIMIT_SOURCE_RETRY_AFTER),
+        "usage_reset" | "resets_at" => Ok(RATE_LIMIT_SOURCE_USAGE_RESET),
+        other => Err(format!("invalid rateLimitSource: {other}")),
+    }
+}
+
+fn normalize_ladder(steps: Vec<i64>) -> Result<Vec<i64>, String> {
+    if steps.is_empty() {
+        return Err("rateLimitLadderSecs must not be empty".to_string());
+    }
+    if steps.len() > MAX_RATE_LIMIT_LADDER_STEPS {
+        return Err(format!(
+            "rateLimitLadderSecs supports at most {MAX_RATE_LIMIT_LADDER_STEPS} steps"
+        ));
+    }
+    steps
+        .into_iter()
+        .map(|secs| normalize_secs("rateLimitLadderSecs", secs))
+        .collect()
+}
+
+/// 在现有策略上应用修改并校验；任何字段非法时整体拒绝，不做部分生效。
+pub(crate) fn apply_cooldown_policy_patch(
+    mut policy: CooldownPolicy,
+    patch: CooldownPolicyPatch,
+) -> Result<CooldownPolicy, String> {
+    if let Some(secs) = patch.default_secs {
+        policy.default_secs = normalize_secs("defaultSecs", secs)?;
+    }
+    if let Some(secs) = patch.network_secs {
+        policy.network_secs = normalize_secs("networkSecs", secs)?;
+    }
+    if let Some(secs) = patch.upstream_4xx_secs {
+        policy.upstream_4xx_secs = normalize_secs("upstream4xxSecs", secs)?;
+    }
+    if let Some(secs) = patch.upstream_5xx_secs {
+        policy.upstream_5xx_secs = normalize_secs("upstream5xxSecs", secs)?;
+    }
+    if let Some(secs) = patch.challenge_secs {
+        policy.challenge_secs = normalize_secs("challengeSecs", secs)?;
+    }
+    if let Some(steps) = patch.rate_limit_ladder_secs {
+        policy.rate_limit_ladder_secs = normalize_ladder(steps)?;
+    }
+    if let Some(secs) = patch.rate_limit_forget_after_secs {
+        policy.rate_limit_forget_after_secs = normalize_secs("rateLimitForgetAfterSecs", secs)?;
+    }
+    if let Some(source) = patch.rate_limit_source.as_deref() {
+        policy.rate_limit_source = normalize_rate_limit_source(source)?;
+    }
+    if let Some(secs) = patch.rate_limit_max_secs {
+        policy.rate_limit_max_secs = normalize_secs("rateLimitMaxSecs", secs)?.max(1);
+    }
+    Ok(policy)
+}
+
+fn policy_cell() -> &'static RwLock<CooldownPolicy> {
+    COOLDOWN_POLICY.get_or_init(|| RwLock::new(CooldownPolicy::default()))
+}
+
+pub(crate) fn current_cooldown_policy() -> CooldownPolicy {
+    let _ = COOLDOWN_POLICY_LOADED.get_or_init(reload_from_env);
+    crate::lock_utils::read_recover(policy_cell(), "cooldown_policy").clone()
+}
+
+/// 修改冷却策略并同步写回环境变量，保证后续按环境变量重载运行时配置时不会丢失。
+pub(crate) fn set_cooldown_policy(patch: CooldownPolicyPatch) -> Result<CooldownPolicy, String> {
+    let applied = apply_cooldown_policy_patch(current_cooldown_policy(), patch)?;
+    std::env::set_var(COOLDOWN_DEFAULT_SECS_ENV, applied.default_secs.to_string());
+    std::env::set_var(COOLDOWN_NETWORK_SECS_ENV, applied.network_secs.to_string());
+    std::env::set_var(COOLDOWN_4XX_SECS_ENV, applied.upstream_4xx_secs.to_string());
+    std::env::set_var(COOLDOWN_5XX_SECS_ENV, applied.upstream_5xx_secs.to_string());
+    std::env::set_var(
+        COOLDOWN_CHALLENGE_SECS_ENV,
+        applied.challenge_secs.to_string(),
+    );
+    std::env::set_var(
+        COOLDOWN_429_LADDER_SECS_ENV,
+        applied
+            .rate_limit_ladder_secs
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    std::env::set_var(
+        COOLDOWN_429_FORGET_AFTER_SECS_ENV,
+        applied.rate_limit_forget_after_secs.to_string(),
+    );
+    std::env::set_var(COOLDOWN_429_SOURCE_ENV, applied.rate_limit_source);
+    std::env::set_var(
+        COOLDOWN_429_MAX_SECS_ENV,
+        applied.rate_limit_max_secs.to_string(),
+    );
+    *crate::lock_utils::write_recover(policy_cell(), "cooldown_policy") = applied.clone();
+    Ok(applied)
+}
+
+fn env_i64(name: &str) -> Option<i64> {
+    std::env::var(name)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<i64>().ok())
+}
+
+fn cooldown_policy_patch_from_env() -> CooldownPolicyPatch {
+    CooldownPolicyPatch {
+        default_secs: env_i64(COOLDOWN_DEFAULT_SECS_ENV),
+        network_secs: env_i64(COOLDOWN_NETWORK_SECS_ENV),
+        upstream_4xx_secs: env_i64(COOLDOWN_4XX_SECS_ENV),
+        upstream_5xx_secs: env_i64(COOLDOWN_5XX_SECS_ENV),
+        challenge_secs: env_i64(COOLDOWN_CHALLENGE_SECS_ENV),
+        rate_limit_ladder_secs: std::env::var(COOLDOWN_429_LADDER_SECS_ENV)
+            .ok()
+            .and_then(|raw| {
+                raw.split(',')
+                    .filter(|item| !item.trim().is_empty())
+                    .map(|item| item.trim().parse::<i64>().ok())
+                    .collect::<Option<Vec<_>>>()
+            }),
+        rate_limit_forget_after_secs: env_i64(COOLDOWN_429_FORGET_AFTER_SECS_ENV),
+        rate_limit_source: std::env::var(COOLDOWN_429_SOURCE_ENV).ok(),
+        rate_limit_max_secs: env_i64(COOLDOWN_429_MAX_SECS_ENV),
+    }
+}
+
+pub(super) fn reload_from_env() {
+    let policy = match apply_cooldown_policy_patch(
+        CooldownPolicy::default(),
+        cooldown_policy_patch_from_env(),
+    ) {
+        Ok(policy) => policy,
+        Err(err) => {
+            log::warn!("invalid cooldown policy env, fallback to defaults: {err}");
+            CooldownPolicy::default()
+        }
+    };
+    *crate::lock_utils::write_recover(policy_cell(), "cooldown_policy") = policy;
+}
+
+/// 解析 Retry-After：支持秒数与 HTTP 日期两种格式，返回距离现在的秒数。
+pub(crate) fn parse_retry_after_secs(raw: &str, now: i64) -> Option<i64> {
+    let raw = raw.trim();
+    if let Ok(secs) = raw.parse::<i64>() {
+        return (secs > 0).then_some(secs);
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(raw).ok()?.timestamp();
+    let secs = at - now;
+    (secs > 0).then_some(secs)
+}
+
+fn usage_reset_secs(snapshot: &UsageSnapshotRecord, now: i64) -> Option<i64> {
+    // 中文注释：只看已经用满的窗口；多个窗口同时用满时要等最晚的那个重置。
+    let windows = [
+        (snapshot.used_percent, snapshot.resets_at),
+        (
+            snapshot.secondary_used_percent,
+            snapshot.secondary_resets_at,
+        ),
+    ];
+    windows
+        .into_iter()
+        .filter(|(used, _)| used.is_some_and(|used| used >= 100.0))
+        .filter_map(|(_, resets_at)| resets_at)
+        .map(|resets_at| resets_at - now)
+        .filter(|secs| *secs > 0)
+        .max()
+}
+
+/// 按策略计算 429 冷却提示秒数；返回 None 时沿用退避阶梯。
+pub(crate) fn rate_limit_cooldown_hint_secs(
+    policy: &CooldownPolicy,
+    storage: &Storage,
+    account_id: &str,
+    retry_after: Option<&str>,
+) -> Option<i64> {
+    let now = now_ts();
+    let secs = match policy.rate_limit_source {
+        RATE_LIMIT_SOURCE_RETRY_AFTER => {
+            retry_after.and_then(|raw| parse_retry_after_secs(raw, now))
+        }
+        RATE_LIMIT_SOURCE_USAGE_RESET => storage
+            .latest_usage_snapshot_for_account(account_id)
+            .ok()
+            .flatten()
+            .and_then(|snapshot| usage_reset_secs(&snapshot, now)),
+        _ => None,
+    }?;
+    Some(secs.clamp(1, policy.rate_limit_max_secs.max(1)))
+}
+
+#[cfg(test)]
+#[path = "tests/cooldown_policy_tests.rs"]
+mod tests;
@@ -0,0 +1,357 @@
+use codexmanager_core::storage::ApiKey;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::apikey_profile::{PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_GEMINI_NATIVE};
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+// 中文注释：状态表超过该规模时顺带清理空闲 Key，避免长期运行后残留大量无效条目。
+const KEY_RATE_LIMIT_PRUNE_THRESHOLD: usize = 4096;
+
+static KEY_RATE_LIMIT_STATE: OnceLock<Mutex<HashMap<String, KeyRateLimitState>>> = OnceLock::new();
+
+/// 平台 Key 的限流阈值；均为 None 时不做任何限制。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct KeyRateLimits {
+    pub(crate) requests_per_minute: Option<u64>,
+    pub(crate) tokens_per_minute: Option<u64>,
+    pub(crate) max_concurrency: Option<u64>,
+}
+
+impl KeyRateLimits {
+    pub(crate) fn from_api_key(api_key: &ApiKey) -> Self {
+        let positive = |value: Option<i64>| value.filter(|v| *v > 0).map(|v| v as u64);
+        Self {
+            requests_per_minute: positive(api_key.rpm_limit),
+            tokens_per_minute: positive(api_key.tpm_limit),
+            max_concurrency: positive(api_key.max_concurrency),
+        }
+    }
+
+    pub(crate) fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none()
+            && self.tokens_per_minute.is_none()
+            && self.max_concurrency.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyRateLimitKind {
+    Requests,
+    Tokens,
+    Concurrency,
+}
+
+impl KeyRateLimitKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Requests => "requests per minute",
+            Self::Tokens => "tokens per minute",
+            Self::Concurrency => "concurrent requests",
+        }
+    }
+
+    fn openai_type(self) -> &'static str {
+        match self {
+            Self::Requests | Self::Concurrency => "requests",
+            Self::Tokens => "tokens",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KeyRateLimitRejection {
+    pub(crate) kind: KeyRateLimitKind,
+    pub(crate) limit: u64,
+    pub(crate) retry_after_secs: u64,
+    pub(crate) requests: Option<RateLimitWindowStatus>,
+    pub(crate) tokens: Option<RateLimitWindowStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RateLimitWindowStatus {
+    pub(crate) limit: u64,
+    pub(crate) remaining: u64,
+    pub(crate) reset_after_secs: u64,
+}
+
+#[derive(Default)]
+struct KeyRateLimitState {
+    request_times: VecDeque<Instant>,
+    token_events: VecDeque<(Instant, u64)>,
+    tokens_in_window: u64,
+    inflight: u64,
+    track_tokens: bool,
+}
+
+impl KeyRateLimitState {
+    fn evict_expired(&mut self, now: Instant) {
+        while self
+            .request_times
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= RATE_LIMIT_WINDOW)
+        {
+            self.request_times.pop_front();
+        }
+        while let Some((at, tokens)) = self.token_events.front().copied() {
+            if now.saturating_duration_since(at) < RATE_LIMIT_WINDOW {
+                break;
+            }
+            self.tokens_in_window = self.tokens_in_window.saturating_sub(tokens);
+            self.token_events.pop_front();
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.inflight == 0 && self.request_times.is_empty() && self.token_events.is_empty()
+    }
+
+    fn requests_status(&self, limit: u64, now: Instant) -> RateLimitWindowStatus {
+        let used = self.request_times.len() as u64;
+        RateLimitWindowStatus {
+            limit,
+            remaining: limit.saturating_sub(used),
+            reset_after_secs: self
+                .request_times
+                .front()
+                .map(|at| secs_until_expired(*at, now))
+                .unwrap_or(0),
+        }
+    }
+
+    fn tokens_status(&self, limit: u64, now: Instant) -> RateLimitWindowStatus {
+        // 中文注释：TPM 按滑动窗口计算，需要等到足够多的历史消耗滑出窗口后才能恢复到限额以下。
+        let mut reset_after_secs = 0;
+        if self.tokens_in_window >= limit {
+            let mut remaining_tokens = self.tokens_in_window;
+            for (at, tokens) in &self.token_events {
+                remaining_tokens = remaining_tokens.saturating_sub(*tokens);
+                reset_after_secs = secs_until_expired(*at, now);
+                if remaining_tokens < limit {
+                    break;
+                }
+            }
+        }
+        RateLimitWindowStatus {
+            limit,
+            remaining: limit.saturating_sub(self.tokens_in_window),
+            reset_after_secs,
+        }
+    }
+}
+
+fn secs_until_expired(at: Instant, now: Instant) -> u64 {
+    let elapsed = now.saturating_duration_since(at);
+    let left = RATE_LIMIT_WINDOW.saturating_sub(elapsed);
+    // 中文注释：向上取整，避免客户端按 Retry-After 重试时仍落在窗口内。
+    left.as_millis().div_ceil(1000).max(1) as u64
+}
+
+/// 并发许可；请求结束（含流式响应写完）时随 Drop 归还。
+#[must_use]
+pub(crate) struct KeyRateLimitPermit {
+    key_id: Option<String>,
+}
+
+impl KeyRateLimitPermit {
+    pub(crate) fn unlimited() -> Self {
+        Self { key_id: None }
+    }
+}
+
+impl Drop for KeyRateLimitPermit {
+    fn drop(&mut self) {
+        let Some(key_id) = self.key_id.take() else {
+            return;
+        };
+        with_state(|table| {
+            if let Some(state) = table.get_mut(&key_id) {
+                state.inflight = state.inflight.saturating_sub(1);
+            }
+        });
+    }
+}
+
+/// 按平台 Key 的 RPM / TPM / 并发上限做准入；通过时占用一个并发名额并计入本分钟请求数。
+pub(crate) fn acquire_key_rate_limit(
+    key_id: &str,
+    limits: KeyRateLimits,
+) -> Result<KeyRateLimitPermit, KeyRateLimitRejection> {
+    acquire_key_rate_limit_at(key_id, limits, Instant::now())
+}
+
+fn acquire_key_rate_limit_at(
+    key_id: &str,
+    limits: KeyRateLimits,
+    now: Instant,
+) -> Result<KeyRateLimitPermit, KeyRateLimitRejection> {
+    if limits.is_unlimited() {
+        return Ok(KeyRateLimitPermit::unlimited());
+    }
+    with_state(|table| {
+        if table.len() > KEY_RATE_LIMIT_PRUNE_THRESHOLD {
+            prune_idle_entries(table, now);
+        }
+        let state = table.entry(key_id.to_string()).or_default();
+        state.track_tokens = limits.tokens_per_minute.is_some();
+        state.evict_expired(now);
+
+        let requests = limits
+            .requests_per_minute
+            .map(|limit| state.requests_status(limit, now));
+        let tokens = limits
+            .tokens_per_minute
+            .map(|limit| state.tokens_status(limit, now));
+        let reject = |kind, limit, retry_after_secs| KeyRateLimitRejection {
+            kind,
+            limit,
+            retry_after_secs,
+            requests,
+            tokens,
+        };
+
+        if let Some(limit) = limits.max_concurrency {
+            if state.inflight >= limit {
+                return Err(reject(KeyRateLimitKind::Concurrency, limit, 1));
+            }
+        }
+        if let Some(status) = requests {
+            if status.remaining == 0 {
+                return Err(reject(
+                    KeyRateLimitKind::Requests,
+                    status.limit,
+                    status.reset_after_secs,
+                ));
+            }
+        }
+        if let Some(status) = tokens {
+            if status.remaining == 0 {
+                return Err(reject(
+                    KeyRateLimitKind::Tokens,
+                    status.limit,
+                    status.reset_after_secs,
+                ));
+            }
+        }
+
+        state.request_times.push_back(now);
+        state.inflight += 1;
+        Ok(KeyRateLimitPermit {
+            key_id: Some(key_id.to_string()),
+        })
+    })
+}
+
+/// 请求结束后按上游返回（或本地估算）的 usage 扣减 TPM 额度；未配置 TPM 的 Key 直接忽略。
+pub(crate) fn charge_key_tokens(key_id: &str, tokens: u64) {
+    charge_key_tokens_at(key_id, tokens, Instant::now());
+}
+
+fn charge_key_tokens_at(key_id: &str, tokens: u64, now: Instant) {
+    if tokens == 0 {
+        return;
+    }
+    let Some(lock) = KEY_RATE_LIMIT_STATE.get() else {
+        return;
+    };
+    let mut table = crate::lock_utils::lock_recover(lock, "key_rate_limit_state");
+    let Some(state) = table.get_mut(key_id) else {
+        return;
+    };
+    if !state.track_tokens {
+        return;
+    }
+    state.evict_expired(now);
+    state.token_events.push_back((now, tokens));
+    state.tokens_in_window = state.tokens_in_window.saturating_add(tokens);
+}
+
+fn prune_idle_entries(table: &mut HashMap<String, KeyRateLimitState>, now: Instant) {
+    table.retain(|_, state| {
+        state.evict_expired(now);
+        !state.is_idle()
+    });
+}
+
+fn with_state<T>(mutator: impl FnOnce(&mut HashMap<String, KeyRateLimitState>) -> T) -> T {
+    let lock = KEY_RATE_LIMIT_STATE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut table = crate::lock_utils::lock_recover(lock, "key_rate_limit_state");
+    mutator(&mut table)
+}
+
+pub(super) fn clear_runtime_state() {
+    if let Some(lock) = KEY_RATE_LIMIT_STATE.get() {
+        let mut table = crate::lock_utils::lock_recover(lock, "key_rate_limit_state");
+        table.clear();
+    }
+}
+
+impl KeyRateLimitRejection {
+    pub(crate) fn message(&self) -> String {
+        format!(
+            "api key rate limit exceeded: {} (limit {}), retry after {}s",
+            self.kind.label(),
+            self.limit,
+            self.retry_after_secs
+        )
+    }
+
+    /// 429 响应头：Retry-After 与 OpenAI 风格的 x-ratelimit-* 系列。
+    pub(crate) fn response_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![("Retry-After".to_string(), self.retry_after_secs.to_string())];
+        let mut push_window = |name: &str, status: &RateLimitWindowStatus| {
+            headers.push((
+                format!("x-ratelimit-limit-{name}"),
+                status.limit.to_string(),
+            ));
+            headers.push((
+                format!("x-ratelimit-remaining-{name}"),
+                status.remaining.to_string(),
+            ));
+            headers.push((
+                format!("x-ratelimit-reset-{name}"),
+                format!("{}s", status.reset_after_secs),
+            ));
+        };
+        if let Some(status) = self.requests.as_ref() {
+            push_window("requests", status);
+        }
+        if let Some(status) = self.tokens.as_ref() {
+            push_window("tokens", status);
+        }
+        headers
+    }
+
+    /// 按平台 Key 协议生成 429 错误体，让 OpenAI / Anthropic / Gemini SDK 都能识别为限流。
+    pub(crate) fn response_body(&self, protocol_type: &str) -> Vec<u8> {
+        let message = self.message();
+        if protocol_type == PROTOCOL_GEMINI_NATIVE {
+            return super::build_gemini_error_body(429, &message);
+        }
+        let payload = if protocol_type == PROTOCOL_ANTHROPIC_NATIVE {
+            json!({
+                "type": "error",
+                "error": {
+                    "type": "rate_limit_error",
+                    "message": message,
+                }
+            })
+        } else {
+            json!({
+                "error": {
+                    "message": message,
+                    "type": self.kind.openai_type(),
+                    "param": null,
+                    "code": "rate_limit_exceeded",
+                }
+            })
+        };
+        serde_json::to_vec(&payload).unwrap_or_else(|_| message.into_bytes())
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/key_rate_limit_tests.rs"]
+mod tests;
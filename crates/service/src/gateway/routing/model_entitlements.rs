@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use codexmanager_core::rpc::types::AccountModelExclusionItem;
+use codexmanager_core::storage::{now_ts, AccountModelExclusionRecord};
+
+// 中文注释：套餐权限变化很慢，学到的“账号不支持该模型”保留数小时；过期后自动重新探测一次。
+const ACCOUNT_MODEL_EXCLUSION_TTL_SECS: i64 = 6 * 60 * 60;
+const ACCOUNT_MODEL_EXCLUSION_CLEANUP_INTERVAL_SECS: i64 = 60;
+const MODEL_UNAVAILABLE_SCAN_MAX_BYTES: usize = 4096;
+// 中文注释：明确指向套餐/账号权限的错误，说明模型本身存在，只是该账号用不了。
+const MODEL_ENTITLEMENT_MARKERS: [&str; 7] = [
+    "model_not_supported",
+    "unsupported_model",
+    "is not supported when using codex with a chatgpt account",
+    "not supported on your plan",
+    "not available on your plan",
+    "not available for your plan",
+    "you do not have access to model",
+];
+// 中文注释：“模型不存在”同样可能是模型名写错，只有该模型曾在某个账号上成功过才当作权限缺失。
+const MODEL_NOT_FOUND_MARKERS: [&str; 2] = [
+    "model_not_found",
+    "does not exist or you do not have access",
+];
+
+/// 模型不可用错误的类别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ModelUnavailableKind {
+    Entitlement,
+    NotFound,
+}
+
+#[derive(Debug, Clone)]
+struct ModelExclusionEntry {
+    status_code: u16,
+    reason: String,
+    learned_at: i64,
+    expires_at: i64,
+}
+
+#[derive(Default)]
+struct ModelExclusionState {
+    // 中文注释：键为 (account_id, 小写模型名)，模型名大小写不敏感。
+    entries: HashMap<(String, String), ModelExclusionEntry>,
+    last_cleanup_at: i64,
+    // 中文注释：新学到、等待后台线程落库的排除记录。
+    dirty: HashSet<(String, String)>,
+    // 中文注释：曾在任一账号上成功返回过的模型（小写），用来区分“模型名写错”和“账号无权使用”。
+    served_models: HashSet<String>,
+}
+
+static ACCOUNT_MODEL_EXCLUSIONS: OnceLock<Mutex<ModelExclusionState>> = OnceLock::new();
+
+fn exclusion_key(account_id: &str, model: &str) -> (String, String) {
+    (account_id.to_string(), model.trim().to_ascii_lowercase())
+}
+
+fn with_state<T>(mutator: impl FnOnce(&mut ModelExclusionState, i64) -> T) -> T {
+    let lock = ACCOUNT_MODEL_EXCLUSIONS.get_or_init(|| Mutex::new(ModelExclusionState::default()));
+    let mut state = crate::lock_utils::lock_recover(lock, "account_model_exclusions");
+    let now = now_ts();
+    if state.last_cleanup_at == 0
+        || now.saturating_sub(state.last_cleanup_at)
+            >= ACCOUNT_MODEL_EXCLUSION_CLEANUP_INTERVAL_SECS
+    {
+        state.last_cleanup_at = now;
+        state.entries.retain(|_, entry| entry.expires_at > now);
+    }
+    mutator(&mut state, now)
+}
+
+/// 启动时从库里恢复仍未过期的排除记录；读库在全局锁外完成，内存里已有的记录优先。
+pub(super) fn load_persisted_model_exclusions() {
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    let now = now_ts();
+    let _ = storage.prune_account_model_exclusions(now);
+    let records = match storage.list_account_model_exclusions(now) {
+        Ok(records) => records,
+        Err(err) => {
+            log::warn!("load account model exclusions failed: {err}");
+            return;
+        }
+    };
+    with_state(|state, _| {
+        for record in records {
+            state
+                .entries
+                .entry(exclusion_key(&record.account_id, &record.model))
+                .or_insert(ModelExclusionEntry {
+                    status_code: record.status_code,
+                    reason: record.reason,
+                    learned_at: record.learned_at,
+                    expires_at: record.expires_at,
+                });
+        }
+    });
+}
+
+/// 由后台落库线程调用：在锁内取出新学到的排除记录，锁外批量写库。
+pub(super) fn flush_pending_model_exclusions() {
+    let pending = with_state(|state, _| {
+        std::mem::take(&mut state.dirty)
+            .into_iter()
+            .filter_map(|key| {
+                let entry = state.entries.get(&key)?;
+                Some(AccountModelExclusionRecord {
+                    account_id: key.0,
+                    model: key.1,
+                    status_code: entry.status_code,
+                    reason: entry.reason.clone(),
+                    learned_at: entry.learned_at,
+                    expires_at: entry.expires_at,
+                })
+            })
+            .collect::<Vec<_>>()
+    });
+    if pending.is_empty() {
+        return;
+    }
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    for record in pending {
+        if let Err(err) = storage.upsert_account_model_exclusion(&record) {
+            log::warn!(
+                "persist account model exclusion failed: account_id={} model={} err={err}",
+                record.account_id,
+                record.model
+            );
+        }
+    }
+}
+
+/// 只有 400/403/404 的 JSON 或纯文本响应才可能带模型权限关键字；其它响应不读取响应体，原样交给后续流程。
+pub(super) fn may_carry_model_unavailable_error(
+    status_code: u16,
+    content_type: Option<&str>,
+) -> bool {
+    if !matches!(status_code, 400 | 403 | 404) {
+        return false;
+    }
+    let Some(content_type) = content_type else {
+        return false;
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || mime.ends_with("+json") || mime == "text/plain"
+}
+
+/// 判断上游错误是否表示“该账号无法使用请求模型”，只识别 400/403/404 中带模型权限或模型不存在关键字的响应体。
+pub(super) fn model_unavailable_kind(
+    status_code: u16,
+    body: &[u8],
+) -> Option<ModelUnavailableKind> {
+    if !matches!(status_code, 400 | 403 | 404) || body.is_empty() {
+        return None;
+    }
+    let scan = &body[..body.len().min(MODEL_UNAVAILABLE_SCAN_MAX_BYTES)];
+    let text = String::from_utf8_lossy(scan).to_ascii_lowercase();
+    if MODEL_ENTITLEMENT_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+    {
+        return Some(ModelUnavailableKind::Entitlement);
+    }
+    MODEL_NOT_FOUND_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+        .then_some(ModelUnavailableKind::NotFound)
+}
+
+/// 从错误响应体中提取简短原因，优先取 JSON 里的 message/detail。
+pub(super) fn model_unavailable_reason(body: &[u8]) -> String {
+    let value = serde_json::from_slice::<serde_json::Value>(body).ok();
+    let message = value.as_ref().and_then(|value| {
+        value
+            .pointer("/error/message")
+            .or_else(|| value.get("detail"))
+            .or_else(|| value.get("message"))
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    });
+    let text = message.unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+    text.trim().chars().take(200).collect()
+}
+
+/// 请求成功送达后调用：记下该模型确实存在，之后它的“模型不存在”错误才会被当作账号权限缺失。
+pub(super) fn record_model_served(model: &str) {
+    let model = model.trim().to_ascii_lowercase();
+    if model.is_empty() {
+        return;
+    }
+    with_state(|state, _| {
+        state.served_models.insert(model);
+    });
+}
+
+/// 记录账号对某个模型不可用；只影响该 (账号, 模型) 组合，账号的其它模型照常参与路由。
+/// 从未成功过的模型报“不存在”，或排除后整个账号池都用不了该模型时不学习，返回 false。
+pub(super) fn mark_account_model_unavailable(
+    account_id: &str,
+    model: &str,
+    kind: ModelUnavailableKind,
+    status_code: u16,
+    reason: &str,
+    pool_account_ids: &[&str],
+) -> bool {
+    if account_id.is_empty() || model.trim().is_empty() {
+        return false;
+    }
+    let learned = with_state(|state, now| {
+        let key = exclusion_key(account_id, model);
+        if kind == ModelUnavailableKind::NotFound && !state.served_models.contains(&key.1) {
+            return false;
+        }
+        // 中文注释：排除覆盖整个账号池时，问题多半在请求本身而不是某个账号，学下来只会让该模型整池不可用数小时。
+        let covers_pool = pool_account_ids.iter().all(|pool_account_id| {
+            *pool_account_id == account_id
+                || state
+                    .entries
+                    .get(&exclusion_key(pool_account_id, model))
+                    .is_some_and(|entry| entry.expires_at > now)
+        });
+        if covers_pool {
+            return false;
+        }
+        state.entries.insert(
+            key.clone(),
+            ModelExclusionEntry {
+                status_code,
+                reason: reason.to_string(),
+                learned_at: now,
+                expires_at: now + ACCOUNT_MODEL_EXCLUSION_TTL_SECS,
+            },
+        );
+        state.dirty.insert(key);
+        true
+    });
+    if learned {
+        super::ensure_routing_state_flusher();
+    }
+    learned
+}
+
+pub(super) fn is_account_model_excluded(account_id: &str, model: &str) -> bool {
+    if model.trim().is_empty() {
+        return false;
+    }
+    with_state(|state, now| {
+        let key = exclusion_key(account_id, model);
+        match state.entries.get(&key).map(|entry| entry.expires_at) {
+            Some(expires_at) if expires_at > now => true,
+            Some(_) => {
+                state.entries.remove(&key);
+                false
+            }
+            None => false,
+        }
+    })
+}
+
+/// RPC 使用：列出当前仍生效的 (账号, 模型) 排除矩阵，按账号、模型排序。
+pub(crate) fn list_account_model_exclusions() -> Vec<AccountModelExclusionItem> {
+    let mut items = with_state(|state, now| {
+        state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.expires_at > now)
+            .map(|((account_id, model), entry)| AccountModelExclusionItem {
+                account_id: account_id.clone(),
+                model: model.clone(),
+                status_code: entry.status_code,
+                reason: entry.reason.clone(),
+                learned_at: entry.learned_at,
+                expires_at: entry.expires_at,
+            })
+            .collect::<Vec<_>>()
+    });
+    items.sort_by(|a, b| {
+        a.account_id
+            .cmp(&b.account_id)
+            .then_with(|| a.model.cmp(&b.model))
+    });
+    items
+}
+
+/// 手动清除排除记录；账号与模型都为空时清空全部，返回清除条数。
+pub(crate) fn clear_account_model_exclusions(
+    account_id: Option<&str>,
+    model: Option<&str>,
+) -> usize {
+    let account_id = account_id.map(str::trim).filter(|value| !value.is_empty());
+    let model = model
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty());
+    let matches = |(entry_account, entry_model): &(String, String)| {
+        account_id.is_none_or(|value| value == entry_account)
+            && model.as_deref().is_none_or(|value| value == entry_model)
+    };
+    let flush_guard = super::routing_state_flush_guard();
+    let removed = with_state(|state, now| {
+        state.dirty.retain(|key| !matches(key));
+        let mut removed = Vec::new();
+        state.entries.retain(|key, entry| {
+            if !matches(key) {
+                return true;
+            }
+            if entry.expires_at > now {
+                removed.push(key.clone());
+            }
+            false
+        });
+        removed
+    });
+    // 中文注释：库里已过期的行不会再被加载，启动时统一清理，这里只删除仍生效的记录。
+    if let Some(storage) = super::open_storage() {
+        for (entry_account, entry_model) in &removed {
+            if let Err(err) = storage.delete_account_model_exclusion(entry_account, entry_model) {
+                log::warn!("clear persisted account model exclusion failed: {err}");
+            }
+        }
+    }
+    drop(flush_guard);
+    removed.len()
+}
+
+pub(super) fn clear_runtime_state() {
+    let lock = ACCOUNT_MODEL_EXCLUSIONS.get_or_init(|| Mutex::new(ModelExclusionState::default()));
+    let mut state = crate::lock_utils::lock_recover(lock, "account_model_exclusions");
+    // 中文注释：只清内存，库里的排除记录保留；服务启动时再通过 load_persisted_model_exclusions 从当前库恢复。
+    *state = ModelExclusionState::default();
+}
+
+#[cfg(test)]
+#[path = "tests/model_entitlements_tests.rs"]
+mod tests;
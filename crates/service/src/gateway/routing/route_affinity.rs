@@ -0,0 +1,330 @@
+use codexmanager_core::storage::now_ts;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use super::IncomingHeaderSnapshot;
+
+const ROUTE_AFFINITY_ENABLED_ENV: &str = "CODEXMANAGER_ROUTE_AFFINITY_ENABLED";
+const ROUTE_AFFINITY_TTL_SECS_ENV: &str = "CODEXMANAGER_ROUTE_AFFINITY_TTL_SECS";
+const ROUTE_AFFINITY_CAPACITY_ENV: &str = "CODEXMANAGER_ROUTE_AFFINITY_CAPACITY";
+const DEFAULT_ROUTE_AFFINITY_ENABLED: bool = true;
+const DEFAULT_ROUTE_AFFINITY_TTL_SECS: u64 = 60 * 60;
+const DEFAULT_ROUTE_AFFINITY_CAPACITY: usize = 20_000;
+// 中文注释：同一会话连续命中同一账号时不必每次落库，按间隔刷新 updated_at 即可。
+const ROUTE_AFFINITY_PERSIST_INTERVAL_SECS: i64 = 60;
+const ROUTE_AFFINITY_PRUNE_EVERY: u64 = 256;
+const PROMPT_CACHE_KEY_FIELD: &[u8] = b"\"prompt_cache_key\"";
+const PROMPT_CACHE_KEY_MAX_LEN: usize = 256;
+
+static ROUTE_AFFINITY_ENABLED: AtomicBool = AtomicBool::new(DEFAULT_ROUTE_AFFINITY_ENABLED);
+static ROUTE_AFFINITY_TTL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_ROUTE_AFFINITY_TTL_SECS);
+static ROUTE_AFFINITY_CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_ROUTE_AFFINITY_CAPACITY);
+static ROUTE_AFFINITY_STATE: OnceLock<Mutex<RouteAffinityState>> = OnceLock::new();
+static ROUTE_AFFINITY_CONFIG_LOADED: OnceLock<()> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct RouteAffinityEntry {
+    account_id: String,
+    last_seen: i64,
+    persisted_at: i64,
+}
+
+#[derive(Default)]
+struct RouteAffinityState {
+    entries: HashMap<String, RouteAffinityEntry>,
+    binds_since_prune: u64,
+    // 中文注释：等待后台线程落库的粘性键，以及是否到了顺带清理库表的时机。
+    dirty: HashSet<String>,
+    prune_due: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RouteAffinityDecision {
+    NotApplicable,
+    Honored {
+        account_id: String,
+    },
+    Broken {
+        account_id: String,
+        reason: &'static str,
+    },
+}
+
+/// 生成会话粘性键：优先 prompt_cache_key，其次 conversation_id / session_id 请求头，
+/// 并按平台 Key 隔离，避免不同调用方共享同一会话锚点。
+pub(crate) fn resolve_route_affinity_key(
+    key_id: &str,
+    incoming_headers: &IncomingHeaderSnapshot,
+    body: &[u8],
+) -> Option<String> {
+    ensure_route_affinity_config_loaded();
+    if !ROUTE_AFFINITY_ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let (source, value) = affinity_prompt_cache_key(body)
+        .map(|value| ("prompt_cache_key", value))
+        .or_else(|| {
+            incoming_headers
+                .conversation_id()
+                .map(|value| ("conversation_id", value.to_string()))
+        })
+        .or_else(|| {
+            incoming_headers
+                .session_id()
+                .map(|value| ("session_id", value.to_string()))
+        })?;
+    let mut hasher = Sha256::new();
+    hasher.update(key_id.trim().as_bytes());
+    hasher.update(b"|");
+    hasher.update(source.as_bytes());
+    hasher.update(b"|");
+    hasher.update(value.as_bytes());
+    let digest = hasher.finalize();
+    Some(
+        digest[..16]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect(),
+    )
+}
+
+fn affinity_prompt_cache_key(body: &[u8]) -> Option<String> {
+    // 中文注释：transport 只解析 64KB 以内的请求体；长会话请求体通常更大，这里再做一次轻量扫描兜底。
+    super::upstream::transport::extract_prompt_cache_key(body)
+        .or_else(|| scan_prompt_cache_key(body))
+}
+
+fn scan_prompt_cache_key(body: &[u8]) -> Option<String> {
+    let start = body
+        .windows(PROMPT_CACHE_KEY_FIELD.len())
+        .position(|window| window == PROMPT_CACHE_KEY_FIELD)?;
+    let mut rest = body[start + PROMPT_CACHE_KEY_FIELD.len()..].trim_ascii_start();
+    rest = rest.strip_prefix(b":")?.trim_ascii_start();
+    rest = rest.strip_prefix(b"\"")?;
+    let end = rest
+        .iter()
+        .take(PROMPT_CACHE_KEY_MAX_LEN + 1)
+        .position(|byte| *byte == b'"' || *byte == b'\\')?;
+    if rest[end] != b'"' {
+        return None;
+    }
+    std::str::from_utf8(&rest[..end])
+        .ok()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// 查询粘性键上次成功使用的账号（未过期时）。
+pub(crate) fn route_affinity_account(affinity_key: &str) -> Option<String> {
+    ensure_route_affinity_config_loaded();
+    let now = now_ts();
+    let ttl = route_affinity_ttl_secs();
+    with_state(|state| {
+        let entry = state.entries.get(affinity_key)?;
+        if is_expired(entry.last_seen, now, ttl) {
+            state.entries.remove(affinity_key);
+            return None;
+        }
+        Some(entry.account_id.clone())
+    })
+}
+
+/// 请求成功后记录会话与账号的绑定；返回是否发生了新绑定或改绑。
+pub(crate) fn bind_route_affinity(affinity_key: &str, account_id: &str) -> bool {
+    ensure_route_affinity_config_loaded();
+    let now = now_ts();
+    let capacity = route_affinity_capacity();
+    let (changed, should_flush) = with_state(|state| {
+        let previous = state.entries.get(affinity_key).cloned();
+        let changed = previous
+            .as_ref()
+            .is_none_or(|entry| entry.account_id != account_id);
+        let should_persist = changed
+            || previous.as_ref().is_some_and(|entry| {
+                now.saturating_sub(entry.persisted_at) >= ROUTE_AFFINITY_PERSIST_INTERVAL_SECS
+            });
+        let persisted_at = if should_persist {
+            now
+        } else {
+            previous.map(|entry| entry.persisted_at).unwrap_or(now)
+        };
+        state.entries.insert(
+            affinity_key.to_string(),
+            RouteAffinityEntry {
+                account_id: account_id.to_string(),
+                last_seen: now,
+                persisted_at,
+            },
+        );
+        enforce_capacity(&mut state.entries, capacity);
+        if should_persist {
+            state.dirty.insert(affinity_key.to_string());
+        }
+        state.binds_since_prune = state.binds_since_prune.wrapping_add(1);
+        if state.binds_since_prune % ROUTE_AFFINITY_PRUNE_EVERY == 0 {
+            state.prune_due = true;
+        }
+        (changed, should_persist || state.prune_due)
+    });
+    if should_flush {
+        super::ensure_routing_state_flusher();
+    }
+    changed
+}
+
+fn with_state<T>(mutator: impl FnOnce(&mut RouteAffinityState) -> T) -> T {
+    let lock = ROUTE_AFFINITY_STATE.get_or_init(|| Mutex::new(RouteAffinityState::default()));
+    let mut state = crate::lock_utils::lock_recover(lock, "route_affinity_state");
+    mutator(&mut state)
+}
+
+/// 启动时从 SQLite 恢复未过期的绑定，让进行中的会话继续命中原账号的 prompt cache；
+/// 读库在全局锁外完成，内存里已有的绑定优先。
+pub(super) fn load_persisted_route_affinities() {
+    ensure_route_affinity_config_loaded();
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    let items = match storage.list_route_affinities(
+        affinity_updated_since(now_ts(), route_affinity_ttl_secs()),
+        affinity_capacity_limit(route_affinity_capacity()),
+    ) {
+        Ok(items) => items,
+        Err(err) => {
+            log::warn!("load route affinity failed: {err}");
+            return;
+        }
+    };
+    with_state(|state| {
+        for item in items {
+            state
+                .entries
+                .entry(item.affinity_key)
+                .or_insert(RouteAffinityEntry {
+                    account_id: item.account_id,
+                    last_seen: item.updated_at,
+                    persisted_at: item.updated_at,
+                });
+        }
+    });
+}
+
+/// 由后台落库线程调用：在锁内取出待落库的绑定，锁外批量写库并按需清理过期与超量记录。
+pub(super) fn flush_pending_route_affinities() {
+    let (pending, prune_due) = with_state(|state| {
+        let pending = std::mem::take(&mut state.dirty)
+            .into_iter()
+            .filter_map(|affinity_key| {
+                let entry = state.entries.get(&affinity_key)?;
+                Some((affinity_key, entry.account_id.clone(), entry.last_seen))
+            })
+            .collect::<Vec<_>>();
+        (pending, std::mem::take(&mut state.prune_due))
+    });
+    if pending.is_empty() && !prune_due {
+        return;
+    }
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    for (affinity_key, account_id, updated_at) in pending {
+        if let Err(err) = storage.upsert_route_affinity(&affinity_key, &account_id, updated_at) {
+            log::warn!("persist route affinity failed: {err}");
+        }
+    }
+    if prune_due {
+        let _ = storage.prune_route_affinities(
+            affinity_updated_since(now_ts(), route_affinity_ttl_secs()),
+            affinity_capacity_limit(route_affinity_capacity()),
+        );
+    }
+}
+
+// 中文注释：TTL 为 0 表示绑定永不过期，容量为 0 表示不限条数，对应的库表过滤与清理都要跳过。
+fn affinity_updated_since(now: i64, ttl_secs: i64) -> Option<i64> {
+    (ttl_secs > 0).then(|| now - ttl_secs)
+}
+
+fn affinity_capacity_limit(capacity: usize) -> Option<usize> {
+    (capacity > 0).then_some(capacity)
+}
+
+fn enforce_capacity(entries: &mut HashMap<String, RouteAffinityEntry>, capacity: usize) {
+    if capacity == 0 || entries.len() <= capacity {
+        return;
+    }
+    // 中文注释：超出容量时一次性淘汰最久未用的约 10%，避免每次插入都全表扫描。
+    let evict = entries.len() - capacity + (capacity / 10).max(1);
+    let mut by_age = entries
+        .iter()
+        .map(|(key, entry)| (entry.last_seen, key.clone()))
+        .collect::<Vec<_>>();
+    by_age.sort_unstable();
+    for (_, key) in by_age.into_iter().take(evict) {
+        entries.remove(key.as_str());
+    }
+}
+
+fn is_expired(last_seen: i64, now: i64, ttl_secs: i64) -> bool {
+    ttl_secs > 0 && now.saturating_sub(last_seen) > ttl_secs
+}
+
+fn route_affinity_ttl_secs() -> i64 {
+    ROUTE_AFFINITY_TTL_SECS.load(Ordering::Relaxed) as i64
+}
+
+fn route_affinity_capacity() -> usize {
+    ROUTE_AFFINITY_CAPACITY.load(Ordering::Relaxed)
+}
+
+pub(super) fn reload_from_env() {
+    ROUTE_AFFINITY_ENABLED.store(
+        env_bool_or(ROUTE_AFFINITY_ENABLED_ENV, DEFAULT_ROUTE_AFFINITY_ENABLED),
+        Ordering::Relaxed,
+    );
+    ROUTE_AFFINITY_TTL_SECS.store(
+        std::env::var(ROUTE_AFFINITY_TTL_SECS_ENV)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ROUTE_AFFINITY_TTL_SECS),
+        Ordering::Relaxed,
+    );
+    ROUTE_AFFINITY_CAPACITY.store(
+        std::env::var(ROUTE_AFFINITY_CAPACITY_ENV)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_ROUTE_AFFINITY_CAPACITY),
+        Ordering::Relaxed,
+    );
+    clear_runtime_state();
+}
+
+pub(super) fn clear_runtime_state() {
+    // 中文注释：只清内存，库里的绑定保留；服务启动时再通过 load_persisted_route_affinities 从当前库恢复。
+    if let Some(lock) = ROUTE_AFFINITY_STATE.get() {
+        let mut state = crate::lock_utils::lock_recover(lock, "route_affinity_state");
+        *state = RouteAffinityState::default();
+    }
+}
+
+fn ensure_route_affinity_config_loaded() {
+    let _ = ROUTE_AFFINITY_CONFIG_LOADED.get_or_init(reload_from_env);
+}
+
+fn env_bool_or(name: &str, default: bool) -> bool {
+    let Ok(raw) = std::env::var(name) else {
+        return default;
+    };
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => true,
+        "0" | "false" | "no" | "off" => false,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/route_affinity_tests.rs"]
+mod tests;
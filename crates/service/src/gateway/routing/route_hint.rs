@@ -1,5 +1,6 @@
+use super::route_affinity::{route_affinity_account, RouteAffinityDecision};
 use super::route_quality::route_health_score;
-use codexmanager_core::storage::{Account, Token};
+use codexmanager_core::storage::{now_ts, Account, Token, UsageSnapshotRecord};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::sync::{Mutex, OnceLock};
@@ -8,8 +9,10 @@ use std::time::{Duration, Instant};
 const ROUTE_STRATEGY_ENV: &str = "CODEXMANAGER_ROUTE_STRATEGY";
 const ROUTE_MODE_ORDERED: u8 = 0;
 const ROUTE_MODE_BALANCED_ROUND_ROBIN: u8 = 1;
+const ROUTE_MODE_QUOTA_WEIGHTED: u8 = 2;
 const ROUTE_STRATEGY_ORDERED: &str = "ordered";
 const ROUTE_STRATEGY_BALANCED: &str = "balanced";
+const ROUTE_STRATEGY_QUOTA_WEIGHTED: &str = "quota_weighted";
 const ROUTE_HEALTH_P2C_ENABLED_ENV: &str = "CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED";
 const ROUTE_HEALTH_P2C_ORDERED_WINDOW_ENV: &str = "CODEXMANAGER_ROUTE_HEALTH_P2C_ORDERED_WINDOW";
 const ROUTE_HEALTH_P2C_BALANCED_WINDOW_ENV: &str = "CODEXMANAGER_ROUTE_HEALTH_P2C_BALANCED_WINDOW";
@@ -59,14 +62,22 @@ pub(crate) fn apply_route_strategy(
     candidates: &mut [(Account, Token)],
     key_id: &str,
     model: Option<&str>,
-) {
+    affinity_key: Option<&str>,
+) -> RouteAffinityDecision {
     ensure_route_config_loaded();
     if candidates.len() <= 1 {
-        return;
+        return RouteAffinityDecision::NotApplicable;
     }
 
     if rotate_to_manual_preferred_account(candidates) {
-        return;
+        return RouteAffinityDecision::NotApplicable;
+    }
+
+    let affinity = affinity_key
+        .map(|affinity_key| rotate_to_affinity_account(candidates, affinity_key))
+        .unwrap_or(RouteAffinityDecision::NotApplicable);
+    if matches!(affinity, RouteAffinityDecision::Honored { .. }) {
+        return affinity;
     }
 
     let mode = route_mode();
@@ -75,9 +86,42 @@ pub(crate) fn apply_route_strategy(
         if start > 0 {
             candidates.rotate_left(start);
         }
+    } else if mode == ROUTE_MODE_QUOTA_WEIGHTED {
+        let snapshots = super::selection::latest_usage_snapshots();
+        order_by_quota_headroom(candidates, &snapshots, now_ts());
     }
 
     apply_health_p2c(candidates, key_id, model, mode);
+    affinity
+}
+
+fn rotate_to_affinity_account(
+    candidates: &mut [(Account, Token)],
+    affinity_key: &str,
+) -> RouteAffinityDecision {
+    let Some(account_id) = route_affinity_account(affinity_key) else {
+        return RouteAffinityDecision::NotApplicable;
+    };
+    let Some(index) = candidates
+        .iter()
+        .position(|(account, _)| account.id == account_id)
+    else {
+        // 中文注释：原账号已不在候选池（额度耗尽/被禁用），只能打破粘性，成功后再改绑新账号。
+        return RouteAffinityDecision::Broken {
+            account_id,
+            reason: "account_unavailable",
+        };
+    };
+    if super::is_account_in_cooldown(&account_id) {
+        return RouteAffinityDecision::Broken {
+            account_id,
+            reason: "account_cooldown",
+        };
+    }
+    if index > 0 {
+        candidates.rotate_left(index);
+    }
+    RouteAffinityDecision::Honored { account_id }
 }
 
 fn rotate_to_manual_preferred_account(candidates: &mut [(Account, Token)]) -> bool {
@@ -105,10 +149,10 @@ fn route_mode() -> u8 {
 }
 
 fn route_mode_label(mode: u8) -> &'static str {
-    if mode == ROUTE_MODE_BALANCED_ROUND_ROBIN {
-        ROUTE_STRATEGY_BALANCED
-    } else {
-        ROUTE_STRATEGY_ORDERED
+    match mode {
+        ROUTE_MODE_BALANCED_ROUND_ROBIN => ROUTE_STRATEGY_BALANCED,
+        ROUTE_MODE_QUOTA_WEIGHTED => ROUTE_STRATEGY_QUOTA_WEIGHTED,
+        _ => ROUTE_STRATEGY_ORDERED,
     }
 }
 
@@ -118,6 +162,9 @@ fn parse_route_mode(raw: &str) -> Option<u8> {
         ROUTE_STRATEGY_BALANCED | "round_robin" | "round-robin" | "rr" => {
             Some(ROUTE_MODE_BALANCED_ROUND_ROBIN)
         }
+        ROUTE_STRATEGY_QUOTA_WEIGHTED | "quota-weighted" | "quota" | "headroom" => {
+            Some(ROUTE_MODE_QUOTA_WEIGHTED)
+        }
         _ => None,
     }
 }
@@ -130,7 +177,7 @@ pub(crate) fn current_route_strategy() -> &'static str {
 pub(crate) fn set_route_strategy(strategy: &str) -> Result<&'static str, String> {
     let Some(mode) = parse_route_mode(strategy) else {
         return Err(
-            "invalid strategy; use ordered or balanced (aliases: round_robin/round-robin/rr)"
+            "invalid strategy; use ordered, balanced or quota_weighted (aliases: round_robin/round-robin/rr, quota)"
                 .to_string(),
         );
     };
@@ -242,6 +289,61 @@ fn apply_health_p2c(
     }
 }
 
+/// 剩余额度（百分比）与约束窗口的重置时间；窗口已过重置时间视为额度已恢复。
+pub(super) fn quota_headroom(
+    snapshot: &UsageSnapshotRecord,
+    now: i64,
+) -> Option<(f64, Option<i64>)> {
+    let windows = [
+        (snapshot.used_percent, snapshot.resets_at),
+        (
+            snapshot.secondary_used_percent,
+            snapshot.secondary_resets_at,
+        ),
+    ];
+    let mut binding: Option<(f64, Option<i64>)> = None;
+    for (used_percent, resets_at) in windows {
+        let Some(used_percent) = used_percent else {
+            continue;
+        };
+        let reset_passed = resets_at.is_some_and(|value| value <= now);
+        let headroom = if reset_passed {
+            100.0
+        } else {
+            (100.0 - used_percent).clamp(0.0, 100.0)
+        };
+        let resets_at = resets_at.filter(|_| !reset_passed);
+        let tighter = binding.is_none_or(|(current, _)| headroom < current);
+        if tighter {
+            binding = Some((headroom, resets_at));
+        }
+    }
+    binding
+}
+
+fn order_by_quota_headroom(
+    candidates: &mut [(Account, Token)],
+    snapshots: &HashMap<String, UsageSnapshotRecord>,
+    now: i64,
+) {
+    // 中文注释：按整数百分点比较剩余额度，避免小数抖动让“额度相同时优先快重置”的规则失效；
+    // 稳定排序保证完全相同时仍按账号原始顺序。
+    let rank = |account_id: &str| {
+        let headroom = snapshots
+            .get(account_id)
+            .and_then(|snapshot| quota_headroom(snapshot, now));
+        match headroom {
+            Some((headroom, resets_at)) => (
+                0u8,
+                std::cmp::Reverse(headroom.floor() as i64),
+                resets_at.unwrap_or(i64::MAX),
+            ),
+            None => (1u8, std::cmp::Reverse(0), i64::MAX),
+        }
+    };
+    candidates.sort_by_cached_key(|(account, _)| rank(account.id.as_str()));
+}
+
 fn p2c_challenger_index(
     key_id: &str,
     model: Option<&str>,
@@ -296,6 +398,7 @@ fn route_health_p2c_enabled() -> bool {
 }
 
 fn route_health_window(mode: u8) -> usize {
+    // 中文注释：quota_weighted 与 ordered 共用较小窗口，尽量保持按额度排好的头部顺序。
     if mode == ROUTE_MODE_BALANCED_ROUND_ROBIN {
         ROUTE_HEALTH_P2C_BALANCED_WINDOW.load(Ordering::Relaxed)
     } else {
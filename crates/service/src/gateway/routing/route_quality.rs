@@ -1,10 +1,17 @@
-use codexmanager_core::storage::now_ts;
-use std::collections::HashMap;
+use codexmanager_core::storage::{now_ts, AccountRouteQualityRecord};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Mutex, OnceLock};
 
 const DEFAULT_ROUTE_HEALTH_SCORE: i32 = 100;
 const MIN_ROUTE_HEALTH_SCORE: i32 = 0;
 const MAX_ROUTE_HEALTH_SCORE: i32 = 200;
+// 中文注释：延迟 EWMA 新样本权重 20%，连续几次变慢就能体现，单次抖动不会立刻改变排序。
+const ROUTE_LATENCY_EWMA_ALPHA: f64 = 0.2;
+// 中文注释：首字节延迟每 250ms 扣 1 分，最多扣 40 分；总耗时主要取决于输出长度，只记录不参与扣分，
+// 否则长流式响应的账号会被误判为“慢”。
+const ROUTE_TTFB_PENALTY_STEP_MS: f64 = 250.0;
+const ROUTE_TTFB_PENALTY_MAX: i32 = 40;
+const ROUTE_DIRECT_PROXY_KEY: &str = "direct";
 
 #[derive(Debug, Clone, Default)]
 struct RouteQualityRecord {
@@ -14,37 +21,156 @@ struct RouteQualityRecord {
     upstream_5xx: u32,
     upstream_4xx: u32,
     health_score: i32,
+    ttfb_ewma_ms: Option<f64>,
+    total_ewma_ms: Option<f64>,
+    updated_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RouteLatencyKind {
+    FirstByte,
+    Total,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ProxyLatencyRecord {
+    ttfb_ewma_ms: Option<f64>,
     updated_at: i64,
 }
 
 static ROUTE_QUALITY: OnceLock<Mutex<RouteQualityState>> = OnceLock::new();
 const ROUTE_QUALITY_TTL_SECS: i64 = 24 * 60 * 60;
 const ROUTE_QUALITY_CLEANUP_INTERVAL_SECS: i64 = 60;
+// 中文注释：成功请求只需按间隔刷新库里的统计；失败会在下一轮后台落库时写出，保证重启后不会忘记刚出问题的账号。
+const ROUTE_QUALITY_PERSIST_INTERVAL_SECS: i64 = 60;
 
 #[derive(Default)]
 struct RouteQualityState {
     entries: HashMap<String, RouteQualityRecord>,
+    persisted_at: HashMap<String, i64>,
+    // 中文注释：等待后台线程落库的账号，落库时取当时的最新统计。
+    dirty: HashSet<String>,
+    // 中文注释：按出口代理聚合的延迟，账号还没有自己的样本时用它作为先验。
+    proxy_latency: HashMap<String, ProxyLatencyRecord>,
     last_cleanup_at: i64,
 }
 
-fn with_map_mut<F>(mutator: F)
-where
-    F: FnOnce(&mut HashMap<String, RouteQualityRecord>, i64),
-{
+fn lock_state() -> std::sync::MutexGuard<'static, RouteQualityState> {
     let lock = ROUTE_QUALITY.get_or_init(|| Mutex::new(RouteQualityState::default()));
-    let mut state = crate::lock_utils::lock_recover(lock, "route_quality_state");
-    let now = now_ts();
-    maybe_cleanup_route_quality(&mut state, now);
-    mutator(&mut state.entries, now);
+    crate::lock_utils::lock_recover(lock, "route_quality_state")
 }
 
-pub(crate) fn record_route_quality(account_id: &str, status_code: u16) {
-    with_map_mut(|map, now| {
-        let record = map.entry(account_id.to_string()).or_default();
-        if record.updated_at == 0 {
-            record.health_score = DEFAULT_ROUTE_HEALTH_SCORE;
+/// 启动时从库里恢复线路质量；读库在全局锁外完成，内存里已有的统计优先。
+pub(super) fn load_persisted_route_quality() {
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    let since = now_ts() - ROUTE_QUALITY_TTL_SECS;
+    let _ = storage.prune_account_route_quality(since);
+    match storage.list_account_route_quality(since) {
+        Ok(records) => {
+            let mut state = lock_state();
+            for record in records {
+                if state.entries.contains_key(&record.account_id) {
+                    continue;
+                }
+                state
+                    .persisted_at
+                    .insert(record.account_id.clone(), record.updated_at);
+                state.entries.insert(
+                    record.account_id,
+                    RouteQualityRecord {
+                        success_2xx: record.success_2xx,
+                        challenge_403: record.challenge_403,
+                        throttle_429: record.throttle_429,
+                        upstream_5xx: record.upstream_5xx,
+                        upstream_4xx: record.upstream_4xx,
+                        health_score: record.health_score,
+                        ttfb_ewma_ms: record.ttfb_ewma_ms.map(|value| value as f64),
+                        total_ewma_ms: record.total_ewma_ms.map(|value| value as f64),
+                        updated_at: record.updated_at,
+                    },
+                );
+            }
         }
-        record.updated_at = now;
+        Err(err) => log::warn!("load route quality failed: {err}"),
+    }
+}
+
+/// 由后台落库线程调用：在锁内取出脏账号的最新快照，锁外批量写库。
+pub(super) fn flush_pending_route_quality() {
+    let pending = {
+        let mut state = lock_state();
+        let dirty = std::mem::take(&mut state.dirty);
+        dirty
+            .into_iter()
+            .filter_map(|account_id| {
+                let record = state.entries.get(&account_id)?;
+                Some(route_quality_record_for_storage(&account_id, record))
+            })
+            .collect::<Vec<_>>()
+    };
+    if pending.is_empty() {
+        return;
+    }
+    let Some(storage) = super::open_storage() else {
+        return;
+    };
+    for record in pending {
+        if let Err(err) = storage.upsert_account_route_quality(&record) {
+            log::warn!(
+                "persist route quality failed: account_id={} err={err}",
+                record.account_id
+            );
+        }
+    }
+}
+
+fn route_quality_record_for_storage(
+    account_id: &str,
+    record: &RouteQualityRecord,
+) -> AccountRouteQualityRecord {
+    AccountRouteQualityRecord {
+        account_id: account_id.to_string(),
+        success_2xx: record.success_2xx,
+        challenge_403: record.challenge_403,
+        throttle_429: record.throttle_429,
+        upstream_5xx: record.upstream_5xx,
+        upstream_4xx: record.upstream_4xx,
+        health_score: record.health_score,
+        ttfb_ewma_ms: record.ttfb_ewma_ms.map(|value| value.round() as i64),
+        total_ewma_ms: record.total_ewma_ms.map(|value| value.round() as i64),
+        updated_at: record.updated_at,
+    }
+}
+
+fn route_quality_entry<'a>(
+    state: &'a mut RouteQualityState,
+    account_id: &str,
+    now: i64,
+) -> &'a mut RouteQualityRecord {
+    let record = state.entries.entry(account_id.to_string()).or_default();
+    if record.updated_at == 0 {
+        record.health_score = DEFAULT_ROUTE_HEALTH_SCORE;
+    }
+    record.updated_at = now;
+    record
+}
+
+fn update_ewma(current: Option<f64>, sample_ms: u64) -> Option<f64> {
+    let sample = sample_ms as f64;
+    Some(match current {
+        Some(value) => value + ROUTE_LATENCY_EWMA_ALPHA * (sample - value),
+        None => sample,
+    })
+}
+
+pub(crate) fn record_route_quality(account_id: &str, status_code: u16) {
+    let mut state = lock_state();
+    let now = now_ts();
+    maybe_cleanup_route_quality(&mut state, now);
+    {
+        let record = route_quality_entry(&mut state, account_id, now);
         let delta = route_health_delta(status_code);
         record.health_score =
             (record.health_score + delta).clamp(MIN_ROUTE_HEALTH_SCORE, MAX_ROUTE_HEALTH_SCORE);
@@ -66,29 +192,94 @@ pub(crate) fn record_route_quality(account_id: &str, status_code: u16) {
             }
             _ => {}
         }
-    });
+    }
+    let persisted_at = state.persisted_at.get(account_id).copied().unwrap_or(0);
+    let should_persist = !(200..=299).contains(&status_code)
+        || now.saturating_sub(persisted_at) >= ROUTE_QUALITY_PERSIST_INTERVAL_SECS;
+    if !should_persist {
+        return;
+    }
+    state.persisted_at.insert(account_id.to_string(), now);
+    state.dirty.insert(account_id.to_string());
+    drop(state);
+    super::ensure_routing_state_flusher();
+}
+
+/// 记录一次成功尝试的延迟样本，同时更新账号与其出口代理（`None` 表示直连）的 EWMA。
+/// 延迟只随下一次状态落库一起持久化，不单独标记落库。
+pub(crate) fn record_route_latency(
+    account_id: &str,
+    proxy: Option<&str>,
+    kind: RouteLatencyKind,
+    duration_ms: u64,
+) {
+    let mut state = lock_state();
+    let now = now_ts();
+    maybe_cleanup_route_quality(&mut state, now);
+    let record = route_quality_entry(&mut state, account_id, now);
+    match kind {
+        RouteLatencyKind::FirstByte => {
+            record.ttfb_ewma_ms = update_ewma(record.ttfb_ewma_ms, duration_ms)
+        }
+        RouteLatencyKind::Total => {
+            record.total_ewma_ms = update_ewma(record.total_ewma_ms, duration_ms)
+        }
+    }
+    // 中文注释：代理先验只用于评分，而评分只看首字节延迟，总耗时样本无需按代理聚合。
+    if kind == RouteLatencyKind::FirstByte {
+        let proxy_record = state
+            .proxy_latency
+            .entry(proxy.unwrap_or(ROUTE_DIRECT_PROXY_KEY).to_string())
+            .or_default();
+        proxy_record.updated_at = now;
+        proxy_record.ttfb_ewma_ms = update_ewma(proxy_record.ttfb_ewma_ms, duration_ms);
+    }
 }
 
+fn latency_penalty(ttfb_ewma_ms: Option<f64>) -> i32 {
+    ttfb_ewma_ms.map_or(0, |value| {
+        ((value / ROUTE_TTFB_PENALTY_STEP_MS) as i32).clamp(0, ROUTE_TTFB_PENALTY_MAX)
+    })
+}
+
+fn proxy_latency_penalty(state: &RouteQualityState, proxy: Option<&str>, now: i64) -> i32 {
+    state
+        .proxy_latency
+        .get(proxy.unwrap_or(ROUTE_DIRECT_PROXY_KEY))
+        .filter(|record| record.updated_at + ROUTE_QUALITY_TTL_SECS > now)
+        .map_or(0, |record| latency_penalty(record.ttfb_ewma_ms))
+}
+
+/// 综合评分：状态码健康分减去首字节延迟惩罚；账号没有首字节样本时借用其出口代理的延迟。
 pub(crate) fn route_health_score(account_id: &str) -> i32 {
-    let lock = ROUTE_QUALITY.get_or_init(|| Mutex::new(RouteQualityState::default()));
-    let mut state = crate::lock_utils::lock_recover(lock, "route_quality_state");
+    let proxy = super::upstream_proxy_for_account(account_id);
+    route_health_score_with_proxy(account_id, proxy.as_deref())
+}
+
+fn route_health_score_with_proxy(account_id: &str, proxy: Option<&str>) -> i32 {
+    let mut state = lock_state();
     let now = now_ts();
-    let Some(record) = state.entries.get(account_id).cloned() else {
-        return DEFAULT_ROUTE_HEALTH_SCORE;
-    };
-    if route_quality_record_expired(&record, now) {
+    let record = state
+        .entries
+        .get(account_id)
+        .cloned()
+        .filter(|record| !route_quality_record_expired(record, now));
+    let Some(record) = record else {
         state.entries.remove(account_id);
-        return DEFAULT_ROUTE_HEALTH_SCORE;
-    }
-    record
-        .health_score
-        .clamp(MIN_ROUTE_HEALTH_SCORE, MAX_ROUTE_HEALTH_SCORE)
+        return (DEFAULT_ROUTE_HEALTH_SCORE - proxy_latency_penalty(&state, proxy, now))
+            .clamp(MIN_ROUTE_HEALTH_SCORE, MAX_ROUTE_HEALTH_SCORE);
+    };
+    let penalty = if record.ttfb_ewma_ms.is_some() {
+        latency_penalty(record.ttfb_ewma_ms)
+    } else {
+        proxy_latency_penalty(&state, proxy, now)
+    };
+    (record.health_score - penalty).clamp(MIN_ROUTE_HEALTH_SCORE, MAX_ROUTE_HEALTH_SCORE)
 }
 
 #[allow(dead_code)]
 pub(crate) fn route_quality_penalty(account_id: &str) -> i64 {
-    let lock = ROUTE_QUALITY.get_or_init(|| Mutex::new(RouteQualityState::default()));
-    let mut state = crate::lock_utils::lock_recover(lock, "route_quality_state");
+    let mut state = lock_state();
     let now = now_ts();
     let Some(record) = state.entries.get(account_id).cloned() else {
         return 0;
@@ -102,10 +293,10 @@ pub(crate) fn route_quality_penalty(account_id: &str) -> i64 {
 }
 
 pub(super) fn clear_runtime_state() {
+    // 中文注释：只清内存统计；服务启动时再通过 load_persisted_route_quality 从当前库恢复。
     let lock = ROUTE_QUALITY.get_or_init(|| Mutex::new(RouteQualityState::default()));
     let mut state = crate::lock_utils::lock_recover(lock, "route_quality_state");
-    state.entries.clear();
-    state.last_cleanup_at = 0;
+    *state = RouteQualityState::default();
 }
 
 #[cfg(test)]
@@ -132,6 +323,13 @@ fn maybe_cleanup_route_quality(state: &mut RouteQualityState, now: i64) {
     state
         .entries
         .retain(|_, value| !route_quality_record_expired(value, now));
+    let entries = &state.entries;
+    state
+        .persisted_at
+        .retain(|account_id, _| entries.contains_key(account_id));
+    state
+        .proxy_latency
+        .retain(|_, value| value.updated_at + ROUTE_QUALITY_TTL_SECS > now);
 }
 
 fn route_quality_record_expired(record: &RouteQualityRecord, now: i64) -> bool {
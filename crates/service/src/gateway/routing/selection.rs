@@ -1,11 +1,13 @@
-use codexmanager_core::storage::{now_ts, Account, Storage, Token};
+use codexmanager_core::storage::{now_ts, Account, Storage, Token, UsageSnapshotRecord};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Mutex, OnceLock, RwLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 
 use crate::usage_account_meta::{derive_account_meta, patch_account_meta_in_place};
 
 static CANDIDATE_SNAPSHOT_CACHE: OnceLock<Mutex<Option<CandidateSnapshotCache>>> = OnceLock::new();
+static USAGE_SNAPSHOT_CACHE: OnceLock<Mutex<Option<UsageSnapshotCache>>> = OnceLock::new();
 static SELECTION_CONFIG_LOADED: OnceLock<()> = OnceLock::new();
 static CANDIDATE_CACHE_TTL_MS: AtomicU64 = AtomicU64::new(DEFAULT_CANDIDATE_CACHE_TTL_MS);
 static CURRENT_DB_PATH: OnceLock<RwLock<String>> = OnceLock::new();
@@ -19,6 +21,47 @@ struct CandidateSnapshotCache {
     candidates: Vec<(Account, Token)>,
 }
 
+/// 各账号最新用量快照；与候选快照共用 TTL，额度加权路由与批处理筛选不必每个请求都读库。
+#[derive(Clone)]
+struct UsageSnapshotCache {
+    db_path: String,
+    expires_at: Instant,
+    snapshots: Arc<HashMap<String, UsageSnapshotRecord>>,
+}
+
+pub(super) fn latest_usage_snapshots() -> Arc<HashMap<String, UsageSnapshotRecord>> {
+    let ttl = candidate_cache_ttl();
+    let db_path = cache_identity();
+    let mutex = USAGE_SNAPSHOT_CACHE.get_or_init(|| Mutex::new(None));
+    if let Some(db_path) = db_path.as_deref().filter(|_| !ttl.is_zero()) {
+        let guard = crate::lock_utils::lock_recover(mutex, "usage_snapshot_cache");
+        if let Some(cached) = guard
+            .as_ref()
+            .filter(|cached| cached.db_path == db_path && cached.expires_at > Instant::now())
+        {
+            return cached.snapshots.clone();
+        }
+    }
+
+    let snapshots = Arc::new(
+        crate::storage_helpers::open_storage()
+            .and_then(|storage| storage.latest_usage_snapshots_by_account().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|snapshot| (snapshot.account_id.clone(), snapshot))
+            .collect::<HashMap<_, _>>(),
+    );
+    if let Some(db_path) = db_path.filter(|_| !ttl.is_zero()) {
+        *crate::lock_utils::lock_recover(mutex, "usage_snapshot_cache") =
+            Some(UsageSnapshotCache {
+                db_path,
+                expires_at: Instant::now() + ttl,
+                snapshots: snapshots.clone(),
+            });
+    }
+    snapshots
+}
+
 pub(crate) fn collect_gateway_candidates(
     storage: &Storage,
 ) -> Result<Vec<(Account, Token)>, String> {
@@ -31,6 +74,30 @@ pub(crate) fn collect_gateway_candidates(
     Ok(candidates)
 }
 
+/// 按平台 Key 绑定的账号分组过滤候选；未绑定分组时保留全部候选。
+pub(crate) fn filter_candidates_by_account_groups(
+    candidates: Vec<(Account, Token)>,
+    account_groups: &[String],
+) -> Vec<(Account, Token)> {
+    if account_groups.is_empty() {
+        return candidates;
+    }
+    candidates
+        .into_iter()
+        .filter(|(account, _)| {
+            account
+                .group_name
+                .as_deref()
+                .map(str::trim)
+                .is_some_and(|group| {
+                    account_groups
+                        .iter()
+                        .any(|item| item.eq_ignore_ascii_case(group))
+                })
+        })
+        .collect()
+}
+
 fn collect_gateway_candidates_uncached(storage: &Storage) -> Result<Vec<(Account, Token)>, String> {
     // 选择可用账号作为网关上游候选
     let candidates = storage
@@ -38,6 +105,11 @@ fn collect_gateway_candidates_uncached(storage: &Storage) -> Result<Vec<(Account
         .map_err(|e| e.to_string())?;
     let mut out = Vec::with_capacity(candidates.len());
     for (account, token) in candidates {
+        if account.is_provider_account() {
+            // 中文注释：服务商账号没有 id_token，也不在 accounts 表里，不做元信息回填。
+            out.push((account, token));
+            continue;
+        }
         let mut candidate_account = account.clone();
         let (chatgpt_account_id, workspace_id) = derive_account_meta(&token);
         if patch_account_meta_in_place(&mut candidate_account, chatgpt_account_id, workspace_id) {
@@ -178,7 +250,7 @@ fn current_db_path_cell() -> &'static RwLock<String> {
     CURRENT_DB_PATH.get_or_init(|| RwLock::new("<unset>".to_string()))
 }
 
-fn clear_candidate_cache() {
+pub(super) fn clear_candidate_cache() {
     if let Some(mutex) = CANDIDATE_SNAPSHOT_CACHE.get() {
         let mut guard = match mutex.lock() {
             Ok(guard) => guard,
@@ -189,6 +261,9 @@ fn clear_candidate_cache() {
         };
         *guard = None;
     }
+    if let Some(mutex) = USAGE_SNAPSHOT_CACHE.get() {
+        *crate::lock_utils::lock_recover(mutex, "usage_snapshot_cache") = None;
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,42 @@
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+// 中文注释：冷却、线路质量、模型排除与会话粘性在请求线程里只改内存并标记脏记录，由后台线程按固定间隔合并写库；
+// 进程异常退出最多丢失一个间隔内的变更，下次请求失败时会重新学到。
+const ROUTING_STATE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+static ROUTING_STATE_FLUSHER_STARTED: OnceLock<()> = OnceLock::new();
+static ROUTING_STATE_FLUSH_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// 启动后台落库线程（只启动一次）。
+pub(super) fn ensure_routing_state_flusher() {
+    ROUTING_STATE_FLUSHER_STARTED.get_or_init(|| {
+        let _ = thread::Builder::new()
+            .name("routing-state-flush".to_string())
+            .spawn(routing_state_flush_loop);
+    });
+}
+
+/// 串行化后台落库与 RPC 清除，避免清除后又被刚取出的旧快照写回库里。
+/// 加锁顺序固定为先本锁、后各模块状态锁。
+pub(super) fn routing_state_flush_guard() -> MutexGuard<'static, ()> {
+    let lock = ROUTING_STATE_FLUSH_LOCK.get_or_init(|| Mutex::new(()));
+    crate::lock_utils::lock_recover(lock, "routing_state_flush")
+}
+
+/// 立即写出所有待落库的路由状态。
+pub(super) fn flush_routing_state() {
+    let _guard = routing_state_flush_guard();
+    super::cooldown::flush_pending_cooldowns();
+    super::route_quality::flush_pending_route_quality();
+    super::model_entitlements::flush_pending_model_exclusions();
+    super::route_affinity::flush_pending_route_affinities();
+}
+
+fn routing_state_flush_loop() {
+    loop {
+        thread::sleep(ROUTING_STATE_FLUSH_INTERVAL);
+        flush_routing_state();
+    }
+}
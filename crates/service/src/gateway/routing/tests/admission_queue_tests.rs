@@ -0,0 +1,102 @@
+use super::*;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn admission_test_guard() -> std::sync::MutexGuard<'static, ()> {
+    static ADMISSION_TEST_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
+    ADMISSION_TEST_MUTEX
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+struct EnabledQueue;
+
+impl EnabledQueue {
+    fn new(max_size: usize, per_key_max: usize) -> Self {
+        ADMISSION_QUEUE_ENABLED.store(true, Ordering::Relaxed);
+        ADMISSION_QUEUE_MAX_SIZE.store(max_size, Ordering::Relaxed);
+        ADMISSION_QUEUE_PER_KEY_MAX.store(per_key_max, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for EnabledQueue {
+    fn drop(&mut self) {
+        ADMISSION_QUEUE_ENABLED.store(DEFAULT_ADMISSION_QUEUE_ENABLED, Ordering::Relaxed);
+        ADMISSION_QUEUE_MAX_SIZE.store(DEFAULT_ADMISSION_QUEUE_MAX_SIZE, Ordering::Relaxed);
+        ADMISSION_QUEUE_PER_KEY_MAX.store(DEFAULT_ADMISSION_QUEUE_PER_KEY_MAX, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn disabled_queue_never_parks_requests() {
+    let _guard = admission_test_guard();
+    assert_eq!(
+        wait_for_admission("gk_disabled", Some(Duration::from_secs(5)), || true),
+        AdmissionOutcome::NotQueued
+    );
+}
+
+#[test]
+fn saturated_pool_times_out_at_max_wait() {
+    let _guard = admission_test_guard();
+    let _queue = EnabledQueue::new(8, 8);
+    let outcome = wait_for_admission("gk_timeout", Some(Duration::from_millis(60)), || true);
+    assert!(matches!(outcome, AdmissionOutcome::TimedOut { waited_ms } if waited_ms >= 60));
+}
+
+#[test]
+fn parked_request_is_admitted_once_capacity_frees() {
+    let _guard = admission_test_guard();
+    let _queue = EnabledQueue::new(8, 8);
+    let saturated = Arc::new(AtomicBool::new(true));
+    let releaser = {
+        let saturated = Arc::clone(&saturated);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            saturated.store(false, Ordering::Relaxed);
+            notify_admission_capacity_changed();
+        })
+    };
+    let outcome = wait_for_admission("gk_admitted", Some(Duration::from_secs(5)), || {
+        saturated.load(Ordering::Relaxed)
+    });
+    releaser.join().expect("join releaser");
+    assert!(matches!(outcome, AdmissionOutcome::Admitted { .. }));
+}
+
+#[test]
+fn full_queue_rejects_without_waiting() {
+    let _guard = admission_test_guard();
+    let _queue = EnabledQueue::new(0, 8);
+    assert_eq!(
+        wait_for_admission("gk_full", Some(Duration::from_secs(5)), || true),
+        AdmissionOutcome::Rejected {
+            reason: "queue_full"
+        }
+    );
+    assert_eq!(
+        wait_for_admission("gk_full", None, || true),
+        AdmissionOutcome::Rejected { reason: "deadline" }
+    );
+}
+
+#[test]
+fn queue_head_rotates_across_keys() {
+    let mut state = AdmissionQueueState::default();
+    let a1 = state.push("gk_a");
+    let a2 = state.push("gk_a");
+    let b1 = state.push("gk_b");
+
+    assert_eq!(state.head_ticket(), Some(a1));
+    state.remove("gk_a", a1, true);
+    // 中文注释：gk_a 出队一次后让位给 gk_b，避免单个 Key 的批量请求长期占住队头。
+    assert_eq!(state.head_ticket(), Some(b1));
+    state.remove("gk_b", b1, true);
+    assert_eq!(state.head_ticket(), Some(a2));
+    state.remove("gk_a", a2, false);
+    assert_eq!(state.head_ticket(), None);
+    assert_eq!(state.total, 0);
+    assert!(state.key_order.is_empty());
+}
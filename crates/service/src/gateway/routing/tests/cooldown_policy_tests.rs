@@ -0,0 +1,140 @@
+use super::*;
+
+#[test]
+fn patch_overrides_only_given_fields() {
+    let policy = apply_cooldown_policy_patch(
+        CooldownPolicy::default(),
+        CooldownPolicyPatch {
+            upstream_5xx_secs: Some(90),
+            rate_limit_ladder_secs: Some(vec![10, 60]),
+            rate_limit_source: Some("Retry-After".to_string()),
+            ..CooldownPolicyPatch::default()
+        },
+    )
+    .expect("apply patch");
+
+    assert_eq!(policy.upstream_5xx_secs, 90);
+    assert_eq!(policy.rate_limit_ladder_secs, vec![10, 60]);
+    assert_eq!(policy.rate_limit_source, RATE_LIMIT_SOURCE_RETRY_AFTER);
+    assert_eq!(policy.default_secs, DEFAULT_COOLDOWN_SECS);
+    assert_eq!(policy.challenge_secs, DEFAULT_COOLDOWN_CHALLENGE_SECS);
+}
+
+#[test]
+fn invalid_patch_is_rejected_as_a_whole() {
+    let cases = [
+        CooldownPolicyPatch {
+            default_secs: Some(-1),
+            ..CooldownPolicyPatch::default()
+        },
+        CooldownPolicyPatch {
+            rate_limit_ladder_secs: Some(Vec::new()),
+            ..CooldownPolicyPatch::default()
+        },
+        CooldownPolicyPatch {
+            challenge_secs: Some(MAX_COOLDOWN_SECS + 1),
+            ..CooldownPolicyPatch::default()
+        },
+        CooldownPolicyPatch {
+            network_secs: Some(5),
+            rate_limit_source: Some("random".to_string()),
+            ..CooldownPolicyPatch::default()
+        },
+    ];
+    for patch in cases {
+        assert!(apply_cooldown_policy_patch(CooldownPolicy::default(), patch).is_err());
+    }
+}
+
+#[test]
+fn persisted_policy_json_roundtrips_as_patch() {
+    let policy = apply_cooldown_policy_patch(
+        CooldownPolicy::default(),
+        CooldownPolicyPatch {
+            upstream_4xx_secs: Some(12),
+            rate_limit_source: Some("usage_reset".to_string()),
+            ..CooldownPolicyPatch::default()
+        },
+    )
+    .expect("apply patch");
+    let raw = serde_json::to_string(&policy).expect("serialize policy");
+    let patch = serde_json::from_str::<CooldownPolicyPatch>(&raw).expect("parse patch");
+    assert_eq!(
+        apply_cooldown_policy_patch(CooldownPolicy::default(), patch).expect("replay"),
+        policy
+    );
+}
+
+#[test]
+fn retry_after_accepts_seconds_and_http_date() {
+    let now = 1_445_412_480; // 2015-10-21T07:28:00Z
+    assert_eq!(parse_retry_after_secs("120", now), Some(120));
+    assert_eq!(parse_retry_after_secs("0", now), None);
+    assert_eq!(
+        parse_retry_after_secs("Wed, 21 Oct 2015 07:30:00 GMT", now),
+        Some(120)
+    );
+    assert_eq!(
+        parse_retry_after_secs("Wed, 21 Oct 2015 07:00:00 GMT", now),
+        None
+    );
+    assert_eq!(parse_retry_after_secs("soon", now), None);
+}
+
+#[test]
+fn usage_reset_waits_for_latest_exhausted_window() {
+    let now = 1_000;
+    let snapshot = UsageSnapshotRecord {
+        account_id: "acc".to_string(),
+        used_percent: Some(100.0),
+        window_minutes: Some(300),
+        resets_at: Some(now + 600),
+        secondary_used_percent: Some(40.0),
+        secondary_window_minutes: Some(10_080),
+        secondary_resets_at: Some(now + 86_400),
+        credits_json: None,
+        captured_at: now,
+    };
+    assert_eq!(usage_reset_secs(&snapshot, now), Some(600));
+
+    let both_exhausted = UsageSnapshotRecord {
+        secondary_used_percent: Some(100.0),
+        ..snapshot.clone()
+    };
+    assert_eq!(usage_reset_secs(&both_exhausted, now), Some(86_400));
+
+    let none_exhausted = UsageSnapshotRecord {
+        used_percent: Some(80.0),
+        ..snapshot
+    };
+    assert_eq!(usage_reset_secs(&none_exhausted, now), None);
+}
+
+#[test]
+fn rate_limit_hint_follows_policy_source_and_cap() {
+    let storage = Storage::open_in_memory().expect("open");
+    storage.init().expect("init");
+    let ladder = CooldownPolicy::default();
+    assert_eq!(
+        rate_limit_cooldown_hint_secs(&ladder, &storage, "acc", Some("30")),
+        None
+    );
+
+    let retry_after = CooldownPolicy {
+        rate_limit_source: RATE_LIMIT_SOURCE_RETRY_AFTER,
+        rate_limit_max_secs: 100,
+        ..CooldownPolicy::default()
+    };
+    assert_eq!(
+        rate_limit_cooldown_hint_secs(&retry_after, &storage, "acc", Some("30")),
+        Some(30)
+    );
+    assert_eq!(
+        rate_limit_cooldown_hint_secs(&retry_after, &storage, "acc", Some("3600")),
+        Some(100)
+    );
+    assert_eq!(
+        rate_limit_cooldown_hint_secs(&retry_after, &storage, "acc", None),
+        None
+    );
+}
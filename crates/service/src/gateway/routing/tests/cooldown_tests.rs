@@ -98,7 +98,7 @@ fn rate_limited_offense_resets_after_quiet_period() {
         state.offense_counts.insert("acc".to_string(), 3);
         state.offense_last_at.insert(
             "acc".to_string(),
-            now - ACCOUNT_RATE_LIMIT_OFFENSE_FORGET_AFTER_SECS - 1,
+            now - current_cooldown_policy().rate_limit_forget_after_secs - 1,
         );
     }
 
@@ -107,3 +107,81 @@ fn rate_limited_offense_resets_after_quiet_period() {
     let state = lock.lock().expect("cooldown state lock");
     assert_eq!(state.offense_counts.get("acc"), Some(&1));
 }
+
+#[test]
+fn rate_limit_hint_replaces_ladder_step_but_still_counts_offense() {
+    let policy = CooldownPolicy {
+        rate_limit_ladder_secs: vec![10, 20],
+        ..CooldownPolicy::default()
+    };
+    let mut offense_counts = HashMap::new();
+    let mut offense_last_at = HashMap::new();
+    let mut mark = |hint_secs| {
+        cooldown_secs_for_mark(
+            &policy,
+            &mut offense_counts,
+            &mut offense_last_at,
+            "acc",
+            CooldownReason::RateLimited,
+            hint_secs,
+            100,
+        )
+    };
+    assert_eq!(mark(None), 10);
+    assert_eq!(mark(Some(600)), 600);
+    assert_eq!(mark(None), 20);
+    assert_eq!(offense_counts.get("acc"), Some(&3));
+
+    assert_eq!(
+        cooldown_secs_for_reason(&policy, CooldownReason::Upstream5xx),
+        policy.upstream_5xx_secs
+    );
+}
+
+#[test]
+fn persisted_cooldowns_merge_without_shortening_in_memory_state() {
+    let _guard = cooldown_test_guard();
+    clear_account_cooldown_for_tests();
+    let now = now_ts();
+    let persisted =
+        |account_id: &str, cooldown_until: i64, offense_count: u32| AccountCooldownRecord {
+            account_id: account_id.to_string(),
+            cooldown_until,
+            reason: "upstream_5xx".to_string(),
+            offense_count,
+            offense_last_at: now,
+            updated_at: now,
+        };
+    with_state(|state| {
+        state.entries.insert("acc-live".to_string(), now + 600);
+        state
+            .reasons
+            .insert("acc-live".to_string(), CooldownReason::RateLimited);
+        state.offense_counts.insert("acc-live".to_string(), 2);
+        merge_persisted_cooldowns(
+            state,
+            vec![
+                persisted("acc-live", now + 60, 5),
+                persisted("acc-restored", now + 300, 0),
+                persisted("acc-expired", now - 1, 0),
+            ],
+            now,
+            now - 3600,
+        );
+    });
+
+    with_state(|state| {
+        assert_eq!(state.entries.get("acc-live"), Some(&(now + 600)));
+        assert_eq!(
+            state.reasons.get("acc-live"),
+            Some(&CooldownReason::RateLimited)
+        );
+        assert_eq!(state.offense_counts.get("acc-live"), Some(&2));
+        assert_eq!(state.entries.get("acc-restored"), Some(&(now + 300)));
+        assert_eq!(
+            state.reasons.get("acc-restored"),
+            Some(&CooldownReason::Upstream5xx)
+        );
+        assert!(!state.entries.contains_key("acc-expired"));
+    });
+}
@@ -0,0 +1,114 @@
+use super::{
+    acquire_key_rate_limit_at, charge_key_tokens_at, clear_runtime_state, KeyRateLimitKind,
+    KeyRateLimits,
+};
+use std::time::{Duration, Instant};
+
+fn limits(rpm: Option<u64>, tpm: Option<u64>, concurrency: Option<u64>) -> KeyRateLimits {
+    KeyRateLimits {
+        requests_per_minute: rpm,
+        tokens_per_minute: tpm,
+        max_concurrency: concurrency,
+    }
+}
+
+#[test]
+fn unlimited_key_is_always_admitted() {
+    let now = Instant::now();
+    for _ in 0..100 {
+        let _permit = acquire_key_rate_limit_at("krl-unlimited", KeyRateLimits::default(), now)
+            .expect("admit");
+    }
+}
+
+#[test]
+fn requests_per_minute_rejects_until_window_slides() {
+    clear_runtime_state();
+    let key = "krl-rpm";
+    let start = Instant::now();
+    let limits = limits(Some(2), None, None);
+
+    drop(acquire_key_rate_limit_at(key, limits, start).expect("first"));
+    drop(acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(10)).expect("second"));
+    let rejection = acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(20))
+        .err()
+        .expect("third rejected");
+    assert_eq!(rejection.kind, KeyRateLimitKind::Requests);
+    assert_eq!(rejection.retry_after_secs, 40);
+    let requests = rejection.requests.expect("requests window");
+    assert_eq!(requests.remaining, 0);
+
+    let headers = rejection.response_headers();
+    assert!(headers.contains(&("Retry-After".to_string(), "40".to_string())));
+    assert!(headers.contains(&("x-ratelimit-limit-requests".to_string(), "2".to_string())));
+    assert!(headers.contains(&("x-ratelimit-reset-requests".to_string(), "40s".to_string())));
+
+    assert!(acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(61)).is_ok());
+}
+
+#[test]
+fn tokens_per_minute_charges_reported_usage() {
+    clear_runtime_state();
+    let key = "krl-tpm";
+    let start = Instant::now();
+    let limits = limits(None, Some(1_000), None);
+
+    drop(acquire_key_rate_limit_at(key, limits, start).expect("first"));
+    charge_key_tokens_at(key, 600, start);
+    drop(acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(5)).expect("second"));
+    charge_key_tokens_at(key, 500, start + Duration::from_secs(5));
+
+    let rejection = acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(30))
+        .err()
+        .expect("tokens exhausted");
+    assert_eq!(rejection.kind, KeyRateLimitKind::Tokens);
+    // 中文注释：第一笔 600 滑出窗口后即可恢复到限额以下。
+    assert_eq!(rejection.retry_after_secs, 30);
+
+    assert!(acquire_key_rate_limit_at(key, limits, start + Duration::from_secs(60)).is_ok());
+}
+
+#[test]
+fn concurrency_limit_releases_on_permit_drop() {
+    clear_runtime_state();
+    let key = "krl-concurrency";
+    let now = Instant::now();
+    let limits = limits(None, None, Some(1));
+
+    let permit = acquire_key_rate_limit_at(key, limits, now).expect("first");
+    let rejection = acquire_key_rate_limit_at(key, limits, now)
+        .err()
+        .expect("second rejected");
+    assert_eq!(rejection.kind, KeyRateLimitKind::Concurrency);
+    assert_eq!(rejection.retry_after_secs, 1);
+
+    drop(permit);
+    assert!(acquire_key_rate_limit_at(key, limits, now).is_ok());
+}
+
+#[test]
+fn rejection_body_matches_key_protocol() {
+    clear_runtime_state();
+    let key = "krl-body";
+    let now = Instant::now();
+    let limits = limits(None, None, Some(1));
+    let _permit = acquire_key_rate_limit_at(key, limits, now).expect("first");
+    let rejection = acquire_key_rate_limit_at(key, limits, now)
+        .err()
+        .expect("rejected");
+
+    let openai: serde_json::Value =
+        serde_json::from_slice(&rejection.response_body("openai_compat")).expect("openai json");
+    assert_eq!(openai["error"]["code"], "rate_limit_exceeded");
+    assert_eq!(openai["error"]["type"], "requests");
+
+    let anthropic: serde_json::Value =
+        serde_json::from_slice(&rejection.response_body("anthropic_native"))
+            .expect("anthropic json");
+    assert_eq!(anthropic["type"], "error");
+    assert_eq!(anthropic["error"]["type"], "rate_limit_error");
+
+    let gemini: serde_json::Value =
+        serde_json::from_slice(&rejection.response_body("gemini_native")).expect("gemini json");
+    assert_eq!(gemini["error"]["code"], 429);
+}
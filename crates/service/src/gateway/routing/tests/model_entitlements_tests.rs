@@ -0,0 +1,166 @@
+use super::*;
+
+const POOL: [&str; 3] = ["acc-pool-a", "acc-pool-b", "acc-pool-c"];
+
+#[test]
+fn model_unavailable_error_detects_plan_and_not_found_bodies() {
+    let not_supported = br#"{"detail":"The 'gpt-5.4-pro' model is not supported when using Codex with a ChatGPT account."}"#;
+    assert_eq!(
+        model_unavailable_kind(400, not_supported),
+        Some(ModelUnavailableKind::Entitlement)
+    );
+    let not_found = br#"{"error":{"code":"model_not_found","message":"The model `o3-pro` does not exist or you do not have access to it."}}"#;
+    assert_eq!(
+        model_unavailable_kind(404, not_found),
+        Some(ModelUnavailableKind::NotFound)
+    );
+
+    assert_eq!(model_unavailable_kind(429, not_found), None);
+    assert_eq!(model_unavailable_kind(404, b"Not Found"), None);
+    assert_eq!(
+        model_unavailable_kind(400, br#"{"error":{"message":"invalid_encrypted_content"}}"#),
+        None
+    );
+}
+
+#[test]
+fn only_json_or_text_entitlement_statuses_are_inspected() {
+    assert!(may_carry_model_unavailable_error(
+        400,
+        Some("application/json; charset=utf-8")
+    ));
+    assert!(may_carry_model_unavailable_error(404, Some("text/plain")));
+    assert!(may_carry_model_unavailable_error(
+        403,
+        Some("application/problem+json")
+    ));
+
+    assert!(!may_carry_model_unavailable_error(
+        403,
+        Some("text/html; charset=utf-8")
+    ));
+    assert!(!may_carry_model_unavailable_error(400, None));
+    assert!(!may_carry_model_unavailable_error(
+        429,
+        Some("application/json")
+    ));
+}
+
+#[test]
+fn exclusion_only_applies_to_the_learned_model() {
+    assert!(mark_account_model_unavailable(
+        "acc-entitlement-scope",
+        "GPT-5.4-Pro",
+        ModelUnavailableKind::Entitlement,
+        400,
+        "plan",
+        &["acc-entitlement-scope", "acc-entitlement-other"],
+    ));
+
+    assert!(is_account_model_excluded(
+        "acc-entitlement-scope",
+        "gpt-5.4-pro"
+    ));
+    assert!(!is_account_model_excluded(
+        "acc-entitlement-scope",
+        "gpt-5.4"
+    ));
+    assert!(!is_account_model_excluded(
+        "acc-entitlement-other",
+        "gpt-5.4-pro"
+    ));
+    assert!(list_account_model_exclusions()
+        .iter()
+        .any(|item| { item.account_id == "acc-entitlement-scope" && item.model == "gpt-5.4-pro" }));
+
+    assert_eq!(
+        clear_account_model_exclusions(Some("acc-entitlement-scope"), Some("GPT-5.4-PRO")),
+        1
+    );
+    assert!(!is_account_model_excluded(
+        "acc-entitlement-scope",
+        "gpt-5.4-pro"
+    ));
+}
+
+#[test]
+fn expired_exclusion_is_dropped_on_lookup() {
+    assert!(mark_account_model_unavailable(
+        "acc-entitlement-expired",
+        "o3-pro",
+        ModelUnavailableKind::Entitlement,
+        403,
+        "plan",
+        &["acc-entitlement-expired", "acc-entitlement-other"],
+    ));
+    {
+        let lock = ACCOUNT_MODEL_EXCLUSIONS.get().expect("exclusion state");
+        let mut state = lock.lock().expect("exclusion state lock");
+        let entry = state
+            .entries
+            .get_mut(&exclusion_key("acc-entitlement-expired", "o3-pro"))
+            .expect("exclusion entry");
+        entry.expires_at = now_ts() - 1;
+    }
+
+    assert!(!is_account_model_excluded(
+        "acc-entitlement-expired",
+        "o3-pro"
+    ));
+    assert!(!list_account_model_exclusions()
+        .iter()
+        .any(|item| item.account_id == "acc-entitlement-expired"));
+}
+
+#[test]
+fn not_found_is_learned_only_for_models_served_before() {
+    assert!(!mark_account_model_unavailable(
+        "acc-pool-a",
+        "gpt-typo-unserved",
+        ModelUnavailableKind::NotFound,
+        404,
+        "not found",
+        &POOL,
+    ));
+    assert!(!is_account_model_excluded(
+        "acc-pool-a",
+        "gpt-typo-unserved"
+    ));
+
+    record_model_served("GPT-Served-Once");
+    assert!(mark_account_model_unavailable(
+        "acc-pool-a",
+        "gpt-served-once",
+        ModelUnavailableKind::NotFound,
+        404,
+        "not found",
+        &POOL,
+    ));
+    assert!(is_account_model_excluded("acc-pool-a", "gpt-served-once"));
+    clear_account_model_exclusions(None, Some("gpt-served-once"));
+}
+
+#[test]
+fn exclusion_covering_whole_pool_is_not_learned() {
+    let model = "gpt-pool-coverage";
+    for account_id in &POOL[..2] {
+        assert!(mark_account_model_unavailable(
+            account_id,
+            model,
+            ModelUnavailableKind::Entitlement,
+            400,
+            "plan",
+            &POOL,
+        ));
+    }
+    assert!(!mark_account_model_unavailable(
+        POOL[2],
+        model,
+        ModelUnavailableKind::Entitlement,
+        400,
+        "plan",
+        &POOL,
+    ));
+    assert!(!is_account_model_excluded(POOL[2], model));
+    clear_account_model_exclusions(None, Some(model));
+}
@@ -0,0 +1,94 @@
+use super::{
+    affinity_capacity_limit, affinity_updated_since, bind_route_affinity, clear_runtime_state,
+    enforce_capacity, resolve_route_affinity_key, route_affinity_account, scan_prompt_cache_key,
+    RouteAffinityEntry,
+};
+use crate::gateway::IncomingHeaderSnapshot;
+use std::collections::HashMap;
+
+#[test]
+fn affinity_key_prefers_prompt_cache_key_and_is_scoped_by_platform_key() {
+    let headers = IncomingHeaderSnapshot::with_session_ids(Some("sess-1"), Some("conv-1"));
+    let body = br#"{"model":"gpt-5.3-codex","prompt_cache_key":"cache-1"}"#;
+
+    let from_body = resolve_route_affinity_key("gk_1", &headers, body).expect("body key");
+    let from_conversation =
+        resolve_route_affinity_key("gk_1", &headers, b"{}").expect("conversation key");
+    let other_platform_key =
+        resolve_route_affinity_key("gk_2", &headers, body).expect("other platform key");
+
+    assert_ne!(from_body, from_conversation);
+    assert_ne!(from_body, other_platform_key);
+    assert_eq!(
+        resolve_route_affinity_key("gk_1", &headers, body).as_deref(),
+        Some(from_body.as_str())
+    );
+}
+
+#[test]
+fn affinity_key_falls_back_to_session_header_and_requires_some_anchor() {
+    let session_only = IncomingHeaderSnapshot::with_session_ids(Some("sess-1"), None);
+    assert!(resolve_route_affinity_key("gk_1", &session_only, b"{}").is_some());
+
+    let anonymous = IncomingHeaderSnapshot::default();
+    assert!(resolve_route_affinity_key("gk_1", &anonymous, b"{}").is_none());
+}
+
+#[test]
+fn scan_prompt_cache_key_handles_large_bodies() {
+    let padding = "x".repeat(80 * 1024);
+    let body = format!(r#"{{"input":"{padding}","prompt_cache_key" : "cache-large"}}"#);
+    assert_eq!(
+        scan_prompt_cache_key(body.as_bytes()).as_deref(),
+        Some("cache-large")
+    );
+    // 中文注释：字符串内被转义的同名字段不应被误识别。
+    let escaped = br#"{"input":"say \"prompt_cache_key\": \"nope\""}"#;
+    assert_eq!(scan_prompt_cache_key(escaped), None);
+}
+
+#[test]
+fn bind_route_affinity_reports_new_bindings_and_rebinds() {
+    clear_runtime_state();
+    let key = "affinity-test-bind";
+
+    assert_eq!(route_affinity_account(key), None);
+    assert!(bind_route_affinity(key, "acc-a"));
+    assert!(!bind_route_affinity(key, "acc-a"));
+    assert_eq!(route_affinity_account(key).as_deref(), Some("acc-a"));
+    assert!(bind_route_affinity(key, "acc-b"));
+    assert_eq!(route_affinity_account(key).as_deref(), Some("acc-b"));
+}
+
+#[test]
+fn enforce_capacity_evicts_least_recently_used_entries() {
+    let mut entries = (0..12)
+        .map(|idx| {
+            (
+                format!("key-{idx}"),
+                RouteAffinityEntry {
+                    account_id: "acc-a".to_string(),
+                    last_seen: idx,
+                    persisted_at: idx,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    enforce_capacity(&mut entries, 10);
+
+    assert_eq!(entries.len(), 9);
+    assert!(!entries.contains_key("key-0"));
+    assert!(!entries.contains_key("key-2"));
+    assert!(entries.contains_key("key-3"));
+    assert!(entries.contains_key("key-11"));
+}
+
+#[test]
+fn zero_ttl_and_capacity_skip_persisted_filters() {
+    // 中文注释：TTL 0 表示永不过期、容量 0 表示不限，落库清理与启动加载都不能据此删掉或漏掉记录。
+    assert_eq!(affinity_updated_since(1_000, 0), None);
+    assert_eq!(affinity_capacity_limit(0), None);
+    assert_eq!(affinity_updated_since(1_000, 600), Some(400));
+    assert_eq!(affinity_capacity_limit(50), Some(50));
+}
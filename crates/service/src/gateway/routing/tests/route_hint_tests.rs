@@ -87,7 +87,7 @@ fn defaults_to_ordered_strategy() {
     clear_route_state_for_tests();
 
     let mut candidates = candidate_list();
-    apply_route_strategy(&mut candidates, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut candidates, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(
         account_ids(&candidates),
         vec![
@@ -114,7 +114,7 @@ fn balanced_round_robin_rotates_start_by_key_and_model() {
     clear_route_state_for_tests();
 
     let mut first = candidate_list();
-    apply_route_strategy(&mut first, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut first, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(
         account_ids(&first),
         vec![
@@ -125,7 +125,7 @@ fn balanced_round_robin_rotates_start_by_key_and_model() {
     );
 
     let mut second = candidate_list();
-    apply_route_strategy(&mut second, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut second, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(
         account_ids(&second),
         vec![
@@ -136,7 +136,7 @@ fn balanced_round_robin_rotates_start_by_key_and_model() {
     );
 
     let mut third = candidate_list();
-    apply_route_strategy(&mut third, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut third, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(
         account_ids(&third),
         vec![
@@ -163,19 +163,19 @@ fn balanced_round_robin_isolated_by_key_and_model() {
     clear_route_state_for_tests();
 
     let mut gpt_first = candidate_list();
-    apply_route_strategy(&mut gpt_first, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut gpt_first, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(account_ids(&gpt_first)[0], "acc-a");
 
     let mut gpt_second = candidate_list();
-    apply_route_strategy(&mut gpt_second, "gk_1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut gpt_second, "gk_1", Some("gpt-5.3-codex"), None);
     assert_eq!(account_ids(&gpt_second)[0], "acc-b");
 
     let mut o3_first = candidate_list();
-    apply_route_strategy(&mut o3_first, "gk_1", Some("o3"));
+    apply_route_strategy(&mut o3_first, "gk_1", Some("o3"), None);
     assert_eq!(account_ids(&o3_first)[0], "acc-a");
 
     let mut other_key_first = candidate_list();
-    apply_route_strategy(&mut other_key_first, "gk_2", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut other_key_first, "gk_2", Some("gpt-5.3-codex"), None);
     assert_eq!(account_ids(&other_key_first)[0], "acc-a");
 
     if let Some(value) = previous {
@@ -336,7 +336,7 @@ fn health_p2c_promotes_healthier_candidate_in_ordered_mode() {
     }
 
     let mut candidates = candidate_list();
-    apply_route_strategy(&mut candidates, "gk-health-1", Some("gpt-5.3-codex"));
+    apply_route_strategy(&mut candidates, "gk-health-1", Some("gpt-5.3-codex"), None);
     assert_eq!(account_ids(&candidates)[0], "acc-b");
 
     std::env::remove_var(ROUTE_HEALTH_P2C_ENABLED_ENV);
@@ -344,3 +344,166 @@ fn health_p2c_promotes_healthier_candidate_in_ordered_mode() {
     std::env::remove_var(ROUTE_STRATEGY_ENV);
     reload_from_env();
 }
+
+fn usage_snapshot(
+    account_id: &str,
+    primary: (f64, i64),
+    secondary: Option<(f64, i64)>,
+) -> UsageSnapshotRecord {
+    UsageSnapshotRecord {
+        account_id: account_id.to_string(),
+        used_percent: Some(primary.0),
+        window_minutes: Some(300),
+        resets_at: Some(primary.1),
+        secondary_used_percent: secondary.map(|(used, _)| used),
+        secondary_window_minutes: secondary.map(|_| 10_080),
+        secondary_resets_at: secondary.map(|(_, resets_at)| resets_at),
+        credits_json: None,
+        captured_at: 0,
+    }
+}
+
+#[test]
+fn quota_weighted_ranks_by_tightest_window_headroom() {
+    let mut candidates = candidate_list();
+    let snapshots = HashMap::from([
+        // 中文注释：acc-a 主窗口宽裕但周窗口只剩 10%，整体余量按最紧的窗口算。
+        (
+            "acc-a".to_string(),
+            usage_snapshot("acc-a", (10.0, 5_000), Some((90.0, 9_000))),
+        ),
+        (
+            "acc-b".to_string(),
+            usage_snapshot("acc-b", (30.0, 5_000), None),
+        ),
+        (
+            "acc-c".to_string(),
+            usage_snapshot("acc-c", (60.0, 5_000), None),
+        ),
+    ]);
+
+    order_by_quota_headroom(&mut candidates, &snapshots, 1_000);
+
+    assert_eq!(
+        account_ids(&candidates),
+        vec![
+            "acc-b".to_string(),
+            "acc-c".to_string(),
+            "acc-a".to_string()
+        ]
+    );
+}
+
+#[test]
+fn quota_weighted_prefers_sooner_reset_and_treats_elapsed_windows_as_fresh() {
+    let mut candidates = candidate_list();
+    let snapshots = HashMap::from([
+        (
+            "acc-a".to_string(),
+            usage_snapshot("acc-a", (50.2, 8_000), None),
+        ),
+        (
+            "acc-b".to_string(),
+            usage_snapshot("acc-b", (50.7, 3_000), None),
+        ),
+        // 中文注释：窗口已过重置时间，旧的 100% 不再可信，视为额度已恢复。
+        (
+            "acc-c".to_string(),
+            usage_snapshot("acc-c", (100.0, 500), None),
+        ),
+    ]);
+
+    order_by_quota_headroom(&mut candidates, &snapshots, 1_000);
+
+    assert_eq!(
+        account_ids(&candidates),
+        vec![
+            "acc-c".to_string(),
+            "acc-b".to_string(),
+            "acc-a".to_string()
+        ]
+    );
+}
+
+#[test]
+fn quota_weighted_keeps_accounts_without_snapshot_last() {
+    let mut candidates = candidate_list();
+    let snapshots = HashMap::from([(
+        "acc-c".to_string(),
+        usage_snapshot("acc-c", (95.0, 5_000), None),
+    )]);
+
+    order_by_quota_headroom(&mut candidates, &snapshots, 1_000);
+
+    assert_eq!(
+        account_ids(&candidates),
+        vec![
+            "acc-c".to_string(),
+            "acc-a".to_string(),
+            "acc-b".to_string()
+        ]
+    );
+}
+
+#[test]
+fn set_route_strategy_accepts_quota_weighted() {
+    let _guard = route_strategy_test_guard();
+    clear_route_state_for_tests();
+    assert_eq!(
+        set_route_strategy("quota").expect("set quota alias"),
+        "quota_weighted"
+    );
+    assert_eq!(current_route_strategy(), "quota_weighted");
+    assert_eq!(
+        set_route_strategy("ordered").expect("restore ordered"),
+        "ordered"
+    );
+}
+
+#[test]
+fn route_affinity_moves_previous_account_to_front_while_healthy() {
+    let _guard = route_strategy_test_guard();
+    clear_route_state_for_tests();
+    super::super::route_affinity::clear_runtime_state();
+    super::super::route_affinity::bind_route_affinity("affinity-hint-1", "acc-c");
+
+    let mut candidates = candidate_list();
+    let decision = apply_route_strategy(
+        &mut candidates,
+        "gk_1",
+        Some("gpt-5.3-codex"),
+        Some("affinity-hint-1"),
+    );
+
+    assert_eq!(
+        decision,
+        RouteAffinityDecision::Honored {
+            account_id: "acc-c".to_string()
+        }
+    );
+    assert_eq!(account_ids(&candidates)[0], "acc-c");
+}
+
+#[test]
+fn route_affinity_breaks_when_previous_account_left_the_pool() {
+    let _guard = route_strategy_test_guard();
+    clear_route_state_for_tests();
+    super::super::route_affinity::clear_runtime_state();
+    super::super::route_affinity::bind_route_affinity("affinity-hint-2", "acc-gone");
+
+    let mut candidates = candidate_list();
+    let decision = apply_route_strategy(
+        &mut candidates,
+        "gk_1",
+        Some("gpt-5.3-codex"),
+        Some("affinity-hint-2"),
+    );
+
+    assert_eq!(
+        decision,
+        RouteAffinityDecision::Broken {
+            account_id: "acc-gone".to_string(),
+            reason: "account_unavailable",
+        }
+    );
+}
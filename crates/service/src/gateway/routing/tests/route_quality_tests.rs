@@ -27,6 +27,8 @@ fn route_quality_penalty_evicts_expired_record() {
             upstream_5xx: 0,
             upstream_4xx: 0,
             health_score: DEFAULT_ROUTE_HEALTH_SCORE,
+            ttfb_ewma_ms: None,
+            total_ewma_ms: None,
             updated_at: now - ROUTE_QUALITY_TTL_SECS - 1,
         },
     );
@@ -53,6 +55,8 @@ fn record_path_cleanup_prunes_expired_records() {
             upstream_5xx: 0,
             upstream_4xx: 0,
             health_score: DEFAULT_ROUTE_HEALTH_SCORE,
+            ttfb_ewma_ms: None,
+            total_ewma_ms: None,
             updated_at: now - ROUTE_QUALITY_TTL_SECS - 1,
         },
     );
@@ -64,3 +68,72 @@ fn record_path_cleanup_prunes_expired_records() {
     assert!(!state.entries.contains_key("acc_stale"));
     assert!(state.entries.contains_key("acc_fresh"));
 }
+
+#[test]
+fn route_health_score_prefers_lower_latency_accounts() {
+    let _guard = route_quality_test_guard();
+    clear_route_quality_for_tests();
+    for account_id in ["acc_fast", "acc_slow"] {
+        record_route_quality(account_id, 200);
+    }
+    record_route_latency("acc_fast", None, RouteLatencyKind::FirstByte, 300);
+    record_route_latency("acc_fast", None, RouteLatencyKind::Total, 2_000);
+    record_route_latency("acc_slow", None, RouteLatencyKind::FirstByte, 4_000);
+    record_route_latency("acc_slow", None, RouteLatencyKind::Total, 30_000);
+
+    let fast = route_health_score_with_proxy("acc_fast", None);
+    let slow = route_health_score_with_proxy("acc_slow", None);
+    assert!(fast > slow, "fast={fast} slow={slow}");
+    assert_eq!(slow, DEFAULT_ROUTE_HEALTH_SCORE + 4 - 16);
+}
+
+#[test]
+fn route_health_score_ignores_total_latency_of_long_responses() {
+    let _guard = route_quality_test_guard();
+    clear_route_quality_for_tests();
+    for account_id in ["acc_short", "acc_long"] {
+        record_route_quality(account_id, 200);
+        record_route_latency(account_id, None, RouteLatencyKind::FirstByte, 400);
+    }
+    record_route_latency("acc_short", None, RouteLatencyKind::Total, 1_000);
+    record_route_latency("acc_long", None, RouteLatencyKind::Total, 120_000);
+
+    assert_eq!(
+        route_health_score_with_proxy("acc_short", None),
+        route_health_score_with_proxy("acc_long", None)
+    );
+}
+
+#[test]
+fn route_latency_ewma_smooths_single_spike() {
+    let _guard = route_quality_test_guard();
+    clear_route_quality_for_tests();
+    for _ in 0..5 {
+        record_route_latency("acc_spike", None, RouteLatencyKind::FirstByte, 500);
+    }
+    record_route_latency("acc_spike", None, RouteLatencyKind::FirstByte, 10_500);
+
+    let lock = ROUTE_QUALITY.get_or_init(|| Mutex::new(RouteQualityState::default()));
+    let state = lock.lock().expect("route quality state lock");
+    let ttfb = state.entries["acc_spike"]
+        .ttfb_ewma_ms
+        .expect("ttfb sample");
+    assert!((ttfb - 2_500.0).abs() < 1.0, "ttfb={ttfb}");
+}
+
+#[test]
+fn route_health_score_uses_proxy_latency_for_accounts_without_samples() {
+    let _guard = route_quality_test_guard();
+    clear_route_quality_for_tests();
+    let slow_proxy = Some("http://slow-proxy.local:8080");
+    record_route_latency("acc_seen", slow_proxy, RouteLatencyKind::FirstByte, 5_000);
+
+    assert_eq!(
+        route_health_score_with_proxy("acc_new", slow_proxy),
+        DEFAULT_ROUTE_HEALTH_SCORE - 20
+    );
+    assert_eq!(
+        route_health_score_with_proxy("acc_new", None),
+        DEFAULT_ROUTE_HEALTH_SCORE
+    );
+}
@@ -1,4 +1,7 @@
-use super::{clear_candidate_cache_for_tests, collect_gateway_candidates, CANDIDATE_CACHE_TTL_ENV};
+use super::{
+    clear_candidate_cache_for_tests, collect_gateway_candidates,
+    filter_candidates_by_account_groups, latest_usage_snapshots, CANDIDATE_CACHE_TTL_ENV,
+};
 use codexmanager_core::storage::{now_ts, Account, Storage, Token, UsageSnapshotRecord};
 use std::sync::Mutex;
 
@@ -77,6 +80,69 @@ fn candidate_snapshot_cache_reuses_recent_snapshot() {
     super::reload_from_env();
 }
 
+fn usage_snapshot(account_id: &str, used_percent: f64) -> UsageSnapshotRecord {
+    UsageSnapshotRecord {
+        account_id: account_id.to_string(),
+        used_percent: Some(used_percent),
+        window_minutes: Some(300),
+        resets_at: None,
+        secondary_used_percent: None,
+        secondary_window_minutes: None,
+        secondary_resets_at: None,
+        credits_json: None,
+        captured_at: now_ts(),
+    }
+}
+
+#[test]
+fn usage_snapshot_cache_reuses_recent_map_until_cleared() {
+    let _guard = CANDIDATE_CACHE_TEST_LOCK.lock().expect("lock");
+    let previous_ttl = std::env::var(CANDIDATE_CACHE_TTL_ENV).ok();
+    let previous_db_path = std::env::var("CODEXMANAGER_DB_PATH").ok();
+    let dir = std::env::temp_dir().join(format!(
+        "codexmanager-usage-snapshot-cache-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let db_path = dir.join("codexmanager.db");
+    let _ = std::fs::remove_file(&db_path);
+    std::env::set_var(CANDIDATE_CACHE_TTL_ENV, "60000");
+    std::env::set_var("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    super::reload_from_env();
+
+    let storage = Storage::open(&db_path).expect("open");
+    storage.init().expect("init");
+    storage
+        .insert_usage_snapshot(&usage_snapshot("acc-usage-cache", 10.0))
+        .expect("insert snapshot");
+    let first = latest_usage_snapshots();
+    assert_eq!(first["acc-usage-cache"].used_percent, Some(10.0));
+
+    storage
+        .insert_usage_snapshot(&usage_snapshot("acc-usage-cache-2", 70.0))
+        .expect("insert second snapshot");
+    let cached = latest_usage_snapshots();
+    assert!(!cached.contains_key("acc-usage-cache-2"));
+
+    clear_candidate_cache_for_tests();
+    let refreshed = latest_usage_snapshots();
+    assert_eq!(refreshed["acc-usage-cache-2"].used_percent, Some(70.0));
+
+    clear_candidate_cache_for_tests();
+    let _ = std::fs::remove_dir_all(&dir);
+    if let Some(value) = previous_ttl {
+        std::env::set_var(CANDIDATE_CACHE_TTL_ENV, value);
+    } else {
+        std::env::remove_var(CANDIDATE_CACHE_TTL_ENV);
+    }
+    if let Some(value) = previous_db_path {
+        std::env::set_var("CODEXMANAGER_DB_PATH", value);
+    } else {
+        std::env::remove_var("CODEXMANAGER_DB_PATH");
+    }
+    super::reload_from_env();
+}
+
 #[test]
 fn candidates_follow_account_sort_order() {
     let _guard = CANDIDATE_CACHE_TEST_LOCK.lock().expect("lock");
@@ -156,3 +222,51 @@ fn candidates_follow_account_sort_order() {
     }
     super::reload_from_env();
 }
+
+fn grouped_candidate(id: &str, group_name: Option<&str>) -> (Account, Token) {
+    (
+        Account {
+            id: id.to_string(),
+            label: id.to_string(),
+            issuer: "issuer".to_string(),
+            chatgpt_account_id: None,
+            workspace_id: None,
+            group_name: group_name.map(str::to_string),
+            sort: 0,
+            status: "active".to_string(),
+            created_at: 0,
+            updated_at: 0,
+        },
+        Token {
+            account_id: id.to_string(),
+            id_token: "id".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            api_key_access_token: None,
+            last_refresh: 0,
+        },
+    )
+}
+
+#[test]
+fn account_group_filter_keeps_only_bound_groups() {
+    let candidates = vec![
+        grouped_candidate("acc-a", Some("TEAM-A")),
+        grouped_candidate("acc-b", Some("team-b")),
+        grouped_candidate("acc-none", None),
+    ];
+
+    let all = filter_candidates_by_account_groups(candidates.clone(), &[]);
+    assert_eq!(all.len(), 3);
+
+    let groups = vec!["team-a".to_string(), "team-c".to_string()];
+    let filtered = filter_candidates_by_account_groups(candidates.clone(), &groups);
+    let ids = filtered
+        .iter()
+        .map(|(account, _)| account.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["acc-a"]);
+
+    let missing = vec!["team-x".to_string()];
+    assert!(filter_candidates_by_account_groups(candidates, &missing).is_empty());
+}
@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use codexmanager_core::storage::Account;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+/// 同一请求发往多个候选账号（对冲、流式续流）时共享的部分。
+pub(super) struct SharedUpstreamRequest {
+    pub(super) method: reqwest::Method,
+    pub(super) url: String,
+    pub(super) request_deadline: Option<Instant>,
+    pub(super) remote: Option<SocketAddr>,
+    pub(super) incoming_headers: super::super::IncomingHeaderSnapshot,
+    pub(super) is_stream: bool,
+    pub(super) upstream_cookie: Option<String>,
+}
+
+/// 单个候选的发送参数。
+pub(super) struct UpstreamAttempt {
+    pub(super) account: Account,
+    pub(super) auth_token: String,
+    pub(super) body: Bytes,
+    pub(super) strip_session_affinity: bool,
+}
+
+/// 脱离主候选循环的额外发送不走“最后一个候选兜底”的软约束：冷却中、模型不可用或并发已满都直接放弃。
+pub(super) fn attempt_candidate_available(
+    account_id: &str,
+    account_max_inflight: usize,
+    model: Option<&str>,
+) -> bool {
+    if super::super::is_account_in_cooldown(account_id) {
+        return false;
+    }
+    if model.is_some_and(|model| super::super::is_account_model_excluded(account_id, model)) {
+        return false;
+    }
+    account_max_inflight == 0
+        || super::super::account_inflight_count(account_id) < account_max_inflight
+}
+
+/// 按共享请求与候选参数发送一次；并发计数由调用方持有。
+pub(super) fn send_upstream_attempt(
+    shared: &SharedUpstreamRequest,
+    attempt: &UpstreamAttempt,
+) -> Result<reqwest::blocking::Response, reqwest::Error> {
+    let client = super::super::upstream_client_for_account(&attempt.account.id);
+    super::transport::send_upstream_request_from(
+        &client,
+        &shared.method,
+        &shared.url,
+        shared.request_deadline,
+        shared.remote,
+        &shared.incoming_headers,
+        &attempt.body,
+        shared.is_stream,
+        shared.upstream_cookie.as_deref(),
+        &attempt.auth_token,
+        &attempt.account,
+        attempt.strip_session_affinity,
+    )
+}
@@ -35,6 +35,8 @@ pub(super) fn process_candidate_upstream_flow<F>(
     allow_openai_fallback: bool,
     disable_challenge_stateless_retry: bool,
     has_more_candidates: bool,
+    model: Option<&str>,
+    prefetched: Option<super::hedge::PrefetchedUpstream>,
     mut log_gateway_result: F,
 ) -> CandidateUpstreamDecision
 where
@@ -105,6 +107,7 @@ where
         debug,
         allow_openai_fallback,
         has_more_candidates,
+        prefetched,
         &mut log_gateway_result,
     ) {
         PrimaryFlowDecision::Continue {
@@ -151,6 +154,7 @@ where
         allow_openai_fallback,
         disable_challenge_stateless_retry,
         has_more_candidates,
+        model,
         upstream,
         &mut log_gateway_result,
     ) {
@@ -3,14 +3,35 @@ use codexmanager_core::storage::{Account, Storage, Token};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum CandidateSkipReason {
     Cooldown,
+    // 中文注释：因 429/额度耗尽进入的冷却，只有这类跳过才算“整池被额度耗尽”。
+    RateLimitedCooldown,
     Inflight,
+    ModelUnavailable,
 }
 
 pub(crate) fn prepare_gateway_candidates(
     storage: &Storage,
+    account_groups: &[String],
 ) -> Result<Vec<(Account, Token)>, String> {
     // 中文注释：保持账号原始顺序（按账户排序字段）作为候选顺序，失败时再依次切下一个。
-    super::super::collect_gateway_candidates(storage)
+    let candidates = super::super::collect_gateway_candidates(storage)?;
+    Ok(super::super::filter_candidates_by_account_groups(
+        candidates,
+        account_groups,
+    ))
+}
+
+/// 所有候选都处于冷却或已达单账号并发上限时视为账号池饱和，可进入排队等待。
+pub(crate) fn candidate_pool_saturated(
+    candidates: &[(Account, Token)],
+    account_max_inflight: usize,
+) -> bool {
+    !candidates.is_empty()
+        && candidates.iter().all(|(account, _)| {
+            super::super::is_account_in_cooldown(&account.id)
+                || (account_max_inflight > 0
+                    && super::super::account_inflight_count(&account.id) >= account_max_inflight)
+        })
 }
 
 pub(crate) fn candidate_skip_reason_for_proxy(
@@ -18,6 +39,8 @@ pub(crate) fn candidate_skip_reason_for_proxy(
     idx: usize,
     candidate_count: usize,
     account_max_inflight: usize,
+    fallback_model_pass: bool,
+    model: Option<&str>,
 ) -> Option<CandidateSkipReason> {
     // 中文注释：当用户手动“切到当前”后，首候选应持续优先命中；
     // 仅在真实请求失败时由上游流程自动清除手动锁定，再回退常规轮转。
@@ -30,9 +53,27 @@ pub(crate) fn candidate_skip_reason_for_proxy(
     }
 
     let has_more_candidates = idx + 1 < candidate_count;
-    if super::super::is_account_in_cooldown(account_id) && has_more_candidates {
+    // 中文注释：降级模型轮次只放行限流冷却，额度按模型计算，换模型后账号仍可能可用；
+    // 5xx、网络、challenge 等冷却与模型无关，降级轮次照常跳过。
+    if has_more_candidates {
+        if let Some(reason) = super::super::account_cooldown_reason(account_id) {
+            if reason != super::super::CooldownReason::RateLimited {
+                super::super::record_gateway_failover_attempt();
+                return Some(CandidateSkipReason::Cooldown);
+            }
+            if !fallback_model_pass {
+                super::super::record_gateway_failover_attempt();
+                return Some(CandidateSkipReason::RateLimitedCooldown);
+            }
+        }
+    }
+
+    // 中文注释：已学到该账号无权使用当前模型时直接跳过，账号的其它模型不受影响。
+    if model.is_some_and(|model| super::super::is_account_model_excluded(account_id, model))
+        && has_more_candidates
+    {
         super::super::record_gateway_failover_attempt();
-        return Some(CandidateSkipReason::Cooldown);
+        return Some(CandidateSkipReason::ModelUnavailable);
     }
 
     if account_max_inflight > 0
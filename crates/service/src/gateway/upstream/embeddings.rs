@@ -0,0 +1,490 @@
+use bytes::Bytes;
+use codexmanager_core::storage::{Account, ProviderAccount, Storage, Token};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Instant;
+use tiny_http::{Header, Request, Response};
+
+use super::super::request_log::{RequestLogTraceContext, RequestLogUsage};
+use super::precheck::{prepare_candidates_for_proxy, CandidatePrecheckResult};
+
+const NO_EMBEDDINGS_UPSTREAM_MESSAGE: &str =
+    "no embeddings-capable upstream: configure an OpenAI API fallback base or a provider account";
+
+/// embeddings 候选：ChatGPT OAuth 账号只能经 OpenAI API base、用换取的 API Bearer 调用，
+/// 服务商账号直接用其静态 Key 调用自己的 `/v1/embeddings`。
+enum EmbeddingsTarget {
+    OpenAI {
+        account: Account,
+        token: Token,
+        base: String,
+    },
+    Provider(ProviderAccount),
+}
+
+impl EmbeddingsTarget {
+    fn account_id(&self) -> &str {
+        match self {
+            EmbeddingsTarget::OpenAI { account, .. } => account.id.as_str(),
+            EmbeddingsTarget::Provider(provider) => provider.id.as_str(),
+        }
+    }
+
+    fn is_provider(&self) -> bool {
+        matches!(self, EmbeddingsTarget::Provider(_))
+    }
+
+    fn url(&self) -> String {
+        let base = match self {
+            EmbeddingsTarget::OpenAI { base, .. } => base.as_str(),
+            EmbeddingsTarget::Provider(provider) => provider.base_url.as_str(),
+        };
+        super::super::compute_upstream_url(base.trim(), super::super::embeddings::EMBEDDINGS_PATH).0
+    }
+
+    fn bearer_token(&mut self, storage: &Storage) -> Result<String, String> {
+        match self {
+            EmbeddingsTarget::OpenAI { account, token, .. } => {
+                super::super::resolve_openai_bearer_token(storage, account, token)
+            }
+            EmbeddingsTarget::Provider(provider) => Ok(provider.api_key.trim().to_string()),
+        }
+    }
+}
+
+/// 上游 base 本身就是 OpenAI API 时直接用它，否则只能借助 fallback base（ChatGPT backend 默认兜底到 api.openai.com）。
+fn resolve_openai_embeddings_base() -> Option<String> {
+    let primary = super::super::resolve_upstream_base_url();
+    if super::super::is_openai_api_base(&primary) {
+        return Some(primary);
+    }
+    super::super::resolve_upstream_fallback_base_url(&primary)
+}
+
+fn load_embeddings_providers(
+    storage: &Storage,
+    model: Option<&str>,
+) -> HashMap<String, ProviderAccount> {
+    match storage.list_active_provider_accounts() {
+        Ok(items) => items
+            .into_iter()
+            .filter(|provider| {
+                crate::provider_accounts::provider_serves_model(&provider.models_json, model)
+            })
+            .map(|provider| (provider.id.clone(), provider))
+            .collect(),
+        Err(err) => {
+            log::warn!("event=gateway_provider_accounts_load_failed err={err}");
+            HashMap::new()
+        }
+    }
+}
+
+/// 只保留能承接 embeddings 的候选：OAuth 账号在前、服务商在后，各自保持原候选顺序。
+fn resolve_embeddings_targets(
+    storage: &Storage,
+    candidates: Vec<(Account, Token)>,
+    openai_base: Option<&str>,
+    model: Option<&str>,
+) -> Vec<EmbeddingsTarget> {
+    let mut providers = if candidates
+        .iter()
+        .any(|(account, _)| account.is_provider_account())
+    {
+        load_embeddings_providers(storage, model)
+    } else {
+        HashMap::new()
+    };
+    let mut targets = candidates
+        .into_iter()
+        .filter_map(|(account, token)| {
+            if account.is_provider_account() {
+                return providers
+                    .remove(&account.id)
+                    .map(EmbeddingsTarget::Provider);
+            }
+            openai_base.map(|base| EmbeddingsTarget::OpenAI {
+                account,
+                token,
+                base: base.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    targets.sort_by_key(EmbeddingsTarget::is_provider);
+    targets
+}
+
+enum BatchAttempt {
+    Success(Value),
+    Failover,
+    Respond { status_code: u16, body: Vec<u8> },
+    Terminal { status_code: u16, message: String },
+}
+
+/// 发送一个批次；限流、鉴权失败与 5xx 冷却后换下一个候选，其余请求错误原样回给客户端。
+fn send_embeddings_batch(
+    storage: &Storage,
+    target: &mut EmbeddingsTarget,
+    url: &str,
+    batch: &Bytes,
+    request_deadline: Option<Instant>,
+) -> BatchAttempt {
+    let account_id = target.account_id().to_string();
+    let bearer = match target.bearer_token(storage) {
+        Ok(value) => value,
+        Err(err) => {
+            log::warn!(
+                "event=gateway_embeddings_bearer_unavailable account_id={} err={}",
+                account_id,
+                err
+            );
+            return BatchAttempt::Failover;
+        }
+    };
+    let client = super::super::upstream_client_for_account(account_id.as_str());
+    let mut builder = client
+        .post(url)
+        .bearer_auth(bearer)
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .body(batch.clone());
+    if let Some(timeout) = super::deadline::send_timeout(request_deadline, false) {
+        builder = builder.timeout(timeout);
+    }
+    let _inflight_guard = super::super::acquire_account_inflight(account_id.as_str());
+    let attempt_started_at = Instant::now();
+    let result = builder.send();
+    let duration_ms = super::super::duration_to_millis(attempt_started_at.elapsed());
+    super::super::metrics::record_gateway_upstream_attempt(duration_ms, result.is_err());
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(err) => {
+            log::warn!(
+                "event=gateway_embeddings_upstream_error account_id={} err={}",
+                account_id,
+                err
+            );
+            super::super::mark_account_cooldown(
+                account_id.as_str(),
+                super::super::CooldownReason::Network,
+            );
+            return BatchAttempt::Failover;
+        }
+    };
+
+    let status_code = resp.status().as_u16();
+    if resp.status().is_success() {
+        let body = resp.bytes().unwrap_or_default();
+        return match serde_json::from_slice::<Value>(&body) {
+            Ok(value) => {
+                super::super::clear_account_cooldown(account_id.as_str());
+                BatchAttempt::Success(value)
+            }
+            Err(_) => BatchAttempt::Terminal {
+                status_code: 502,
+                message: "invalid upstream embeddings json".to_string(),
+            },
+        };
+    }
+    match status_code {
+        429 => {
+            let hint_secs = super::super::rate_limit_cooldown_hint_secs(
+                &super::super::current_cooldown_policy(),
+                storage,
+                account_id.as_str(),
+                resp.headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()),
+            );
+            super::super::mark_account_cooldown_with_hint(
+                account_id.as_str(),
+                super::super::CooldownReason::RateLimited,
+                hint_secs,
+            );
+            BatchAttempt::Failover
+        }
+        401 | 403 | 500..=599 => {
+            super::super::mark_account_cooldown_for_status(account_id.as_str(), status_code);
+            BatchAttempt::Failover
+        }
+        _ => BatchAttempt::Respond {
+            status_code,
+            body: resp.bytes().map(|body| body.to_vec()).unwrap_or_default(),
+        },
+    }
+}
+
+struct EmbeddingsRequestLog<'a> {
+    storage: &'a Storage,
+    trace_id: &'a str,
+    key_id: &'a str,
+    original_path: &'a str,
+    path: &'a str,
+    request_method: &'a str,
+    protocol_type: &'a str,
+    response_adapter: super::super::ResponseAdapter,
+    model_for_log: Option<&'a str>,
+    requested_model_for_log: Option<&'a str>,
+    started_at: Instant,
+}
+
+impl EmbeddingsRequestLog<'_> {
+    fn finish(
+        &self,
+        account_id: Option<&str>,
+        status_code: u16,
+        upstream_url: Option<&str>,
+        usage: RequestLogUsage,
+        error: Option<&str>,
+    ) {
+        super::super::record_gateway_request_outcome(
+            self.path,
+            status_code,
+            Some(self.protocol_type),
+        );
+        super::super::trace_log::log_request_final(
+            self.trace_id,
+            status_code,
+            account_id,
+            upstream_url,
+            error,
+            self.started_at.elapsed().as_millis(),
+        );
+        super::super::write_request_log(
+            self.storage,
+            RequestLogTraceContext {
+                trace_id: Some(self.trace_id),
+                original_path: Some(self.original_path),
+                adapted_path: Some(self.path),
+                response_adapter: Some(self.response_adapter),
+                requested_model: self.requested_model_for_log,
+            },
+            Some(self.key_id),
+            account_id,
+            self.path,
+            self.request_method,
+            self.model_for_log,
+            None,
+            upstream_url,
+            Some(status_code),
+            usage,
+            error,
+        );
+    }
+
+    fn fail(
+        &self,
+        request: Request,
+        account_id: Option<&str>,
+        status_code: u16,
+        upstream_url: Option<&str>,
+        usage: RequestLogUsage,
+        message: &str,
+    ) {
+        self.finish(account_id, status_code, upstream_url, usage, Some(message));
+        let response = super::super::error_response::terminal_text_response(
+            status_code,
+            message,
+            Some(self.trace_id),
+        );
+        let _ = request.respond(response);
+    }
+}
+
+/// 累加已完成批次的用量；后续批次失败时这些 token 已在上游消耗，仍要计入日志与额度统计。
+fn accumulate_batch_usage(usage: &mut RequestLogUsage, response: &serde_json::Value) {
+    let (input_tokens, total_tokens) = super::super::embeddings_usage(response);
+    if let Some(input_tokens) = input_tokens {
+        usage.input_tokens = Some(usage.input_tokens.unwrap_or(0) + input_tokens);
+    }
+    if let Some(total_tokens) = total_tokens {
+        usage.total_tokens = Some(usage.total_tokens.unwrap_or(0) + total_tokens);
+    }
+}
+
+fn respond_json(request: Request, status_code: u16, body: Vec<u8>, trace_id: &str) {
+    let mut response = Response::from_data(body).with_status_code(status_code);
+    if let Ok(header) = Header::from_bytes(b"Content-Type".as_slice(), b"application/json") {
+        response.add_header(header);
+    }
+    let response = super::super::error_response::with_trace_id_header(response, Some(trace_id));
+    let _ = request.respond(response);
+}
+
+/// `/v1/embeddings` 的独立路由：只发往能承接 embeddings 的候选，按批次逐个发送，
+/// 某个候选失败后由剩余候选接手后续批次，最后把各批次结果合并成一个响应。
+#[allow(clippy::too_many_arguments)]
+pub(super) fn proxy_embeddings_request(
+    request: Request,
+    storage: &Storage,
+    trace_id: &str,
+    key_id: &str,
+    account_groups: &[String],
+    original_path: &str,
+    path: &str,
+    request_method: &str,
+    protocol_type: &str,
+    embeddings: &super::super::EmbeddingsRequest,
+    response_adapter: super::super::ResponseAdapter,
+    model_for_log: Option<&str>,
+    requested_model_for_log: Option<&str>,
+    batch_item: bool,
+    request_deadline: Option<Instant>,
+    started_at: Instant,
+) -> Result<(), String> {
+    let log = EmbeddingsRequestLog {
+        storage,
+        trace_id,
+        key_id,
+        original_path,
+        path,
+        request_method,
+        protocol_type,
+        response_adapter,
+        model_for_log,
+        requested_model_for_log,
+        started_at,
+    };
+    let (request, candidates) = match prepare_candidates_for_proxy(
+        request,
+        storage,
+        trace_id,
+        key_id,
+        account_groups,
+        original_path,
+        path,
+        response_adapter,
+        request_method,
+        model_for_log,
+        requested_model_for_log,
+        None,
+        batch_item,
+    ) {
+        CandidatePrecheckResult::Ready {
+            request,
+            candidates,
+        } => (request, candidates),
+        CandidatePrecheckResult::Responded => return Ok(()),
+    };
+    // 中文注释：ChatGPT backend 不提供 embeddings，没有可用候选时立即拒绝，不再逐个账号试错。
+    let mut targets = resolve_embeddings_targets(
+        storage,
+        candidates,
+        resolve_openai_embeddings_base().as_deref(),
+        model_for_log,
+    );
+    if targets.is_empty() {
+        log.fail(
+            request,
+            None,
+            503,
+            None,
+            RequestLogUsage::default(),
+            NO_EMBEDDINGS_UPSTREAM_MESSAGE,
+        );
+        return Ok(());
+    }
+
+    let account_max_inflight = super::super::account_max_inflight_limit();
+    let target_count = targets.len();
+    let mut cursor = 0_usize;
+    let mut responses = Vec::with_capacity(embeddings.batches.len());
+    let mut served_by: Option<(String, String)> = None;
+    let mut completed_usage = RequestLogUsage::default();
+    for batch in &embeddings.batches {
+        let value = loop {
+            if super::deadline::is_expired(request_deadline) {
+                log.fail(
+                    request,
+                    None,
+                    504,
+                    None,
+                    completed_usage,
+                    "upstream total timeout exceeded",
+                );
+                return Ok(());
+            }
+            let Some(target) = targets.get_mut(cursor) else {
+                log.fail(
+                    request,
+                    None,
+                    503,
+                    None,
+                    completed_usage,
+                    "all embeddings-capable upstreams failed",
+                );
+                return Ok(());
+            };
+            let account_id = target.account_id().to_string();
+            if super::candidates::candidate_skip_reason_for_proxy(
+                account_id.as_str(),
+                cursor,
+                target_count,
+                account_max_inflight,
+                false,
+                None,
+            )
+            .is_some()
+            {
+                cursor += 1;
+                continue;
+            }
+            let url = target.url();
+            match send_embeddings_batch(storage, target, url.as_str(), batch, request_deadline) {
+                BatchAttempt::Success(value) => {
+                    served_by = Some((account_id, url));
+                    break value;
+                }
+                BatchAttempt::Failover => {
+                    super::super::record_gateway_failover_attempt();
+                    cursor += 1;
+                }
+                BatchAttempt::Respond { status_code, body } => {
+                    log.finish(
+                        Some(account_id.as_str()),
+                        status_code,
+                        Some(url.as_str()),
+                        completed_usage,
+                        Some("embeddings upstream non-success"),
+                    );
+                    respond_json(request, status_code, body, trace_id);
+                    return Ok(());
+                }
+                BatchAttempt::Terminal {
+                    status_code,
+                    message,
+                } => {
+                    log.fail(
+                        request,
+                        Some(account_id.as_str()),
+                        status_code,
+                        Some(url.as_str()),
+                        completed_usage,
+                        message.as_str(),
+                    );
+                    return Ok(());
+                }
+            }
+        };
+        accumulate_batch_usage(&mut completed_usage, &value);
+        responses.push(value);
+    }
+
+    let merged = if responses.len() == 1 {
+        responses.pop().unwrap_or_default()
+    } else {
+        super::super::merge_embeddings_responses(responses)
+    };
+    let (account_id, url) = served_by.unzip();
+    log.finish(
+        account_id.as_deref(),
+        200,
+        url.as_deref(),
+        completed_usage,
+        None,
+    );
+    let body = serde_json::to_vec(&merged)
+        .map_err(|err| format!("serialize embeddings response failed: {err}"))?;
+    respond_json(request, 200, body, trace_id);
+    Ok(())
+}
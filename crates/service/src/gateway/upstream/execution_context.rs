@@ -10,9 +10,11 @@ pub(super) struct GatewayUpstreamExecutionContext<'a> {
     response_adapter: super::super::ResponseAdapter,
     protocol_type: &'a str,
     model_for_log: Option<&'a str>,
+    requested_model_for_log: Option<&'a str>,
     reasoning_for_log: Option<&'a str>,
     candidate_count: usize,
     account_max_inflight: usize,
+    fallback_model_active: bool,
 }
 
 impl<'a> GatewayUpstreamExecutionContext<'a> {
@@ -27,6 +29,7 @@ impl<'a> GatewayUpstreamExecutionContext<'a> {
         response_adapter: super::super::ResponseAdapter,
         protocol_type: &'a str,
         model_for_log: Option<&'a str>,
+        requested_model_for_log: Option<&'a str>,
         reasoning_for_log: Option<&'a str>,
         candidate_count: usize,
         account_max_inflight: usize,
@@ -41,12 +44,27 @@ impl<'a> GatewayUpstreamExecutionContext<'a> {
             response_adapter,
             protocol_type,
             model_for_log,
+            requested_model_for_log,
             reasoning_for_log,
             candidate_count,
             account_max_inflight,
+            fallback_model_active: false,
         }
     }
 
+    pub(super) fn model_for_log(&self) -> Option<&'a str> {
+        self.model_for_log
+    }
+
+    /// 切换到降级模型：日志记录实际服务的模型，原请求模型保留在 requested_model 中。
+    pub(super) fn switch_to_fallback_model(&mut self, model: &'a str) {
+        if self.requested_model_for_log.is_none() {
+            self.requested_model_for_log = self.model_for_log;
+        }
+        self.model_for_log = Some(model);
+        self.fallback_model_active = true;
+    }
+
     pub(super) fn has_more_candidates(&self, idx: usize) -> bool {
         idx + 1 < self.candidate_count
     }
@@ -61,6 +79,8 @@ impl<'a> GatewayUpstreamExecutionContext<'a> {
             idx,
             self.candidate_count,
             self.account_max_inflight,
+            self.fallback_model_active,
+            self.model_for_log,
         )
     }
 
@@ -87,7 +107,9 @@ impl<'a> GatewayUpstreamExecutionContext<'a> {
     ) {
         let reason_text = match reason {
             super::candidates::CandidateSkipReason::Cooldown => "cooldown",
+            super::candidates::CandidateSkipReason::RateLimitedCooldown => "rate_limited_cooldown",
             super::candidates::CandidateSkipReason::Inflight => "inflight",
+            super::candidates::CandidateSkipReason::ModelUnavailable => "model_unavailable",
         };
         super::super::trace_log::log_candidate_skip(
             self.trace_id,
@@ -130,6 +152,7 @@ impl<'a> GatewayUpstreamExecutionContext<'a> {
                 original_path: Some(self.original_path),
                 adapted_path: Some(self.path),
                 response_adapter: Some(self.response_adapter),
+                requested_model: self.requested_model_for_log,
             },
             Some(self.key_id),
             final_account_id,
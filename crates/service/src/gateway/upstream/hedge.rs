@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::super::AccountInFlightGuard;
+
+/// 对冲竞速中提前拿到的首个发送结果，交给常规候选流程继续处理（重试、failover、回包）。
+pub(super) type PrefetchedUpstream = Result<reqwest::blocking::Response, reqwest::Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum HedgeSlot {
+    Primary,
+    Hedge,
+}
+
+impl HedgeSlot {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            HedgeSlot::Primary => "primary",
+            HedgeSlot::Hedge => "hedge",
+        }
+    }
+}
+
+pub(super) enum HedgeRaceOutcome<T, E> {
+    /// 主候选的结果：未触发对冲、主候选先成功，或两边都没成功时以主候选为准。
+    Primary(Result<T, E>),
+    /// 对冲候选先拿到成功响应，主候选已被放弃。
+    HedgeWon(T),
+    /// 工作线程异常退出，没有任何结果；调用方按常规流程重新发送。
+    Abandoned,
+}
+
+/// 竞速双方共享的并发计数寄存处：竞速一结束就归还输家仍占着的计数，不必等它的响应头。
+/// 阻塞发送无法中途撤回，输家的上游请求仍会跑到响应头为止（照常消耗上游额度），随后连接被关闭。
+#[derive(Default)]
+pub(super) struct HedgeSettlement {
+    state: Mutex<HedgeSettlementState>,
+}
+
+#[derive(Default)]
+struct HedgeSettlementState {
+    settled: bool,
+    parked: HashMap<String, AccountInFlightGuard>,
+}
+
+impl HedgeSettlement {
+    fn park(&self, account_id: &str) {
+        let mut state = crate::lock_utils::lock_recover(&self.state, "hedge_settlement");
+        if !state.settled {
+            state.parked.insert(
+                account_id.to_string(),
+                super::super::acquire_account_inflight(account_id),
+            );
+        }
+    }
+
+    fn release(&self, account_id: &str) {
+        let guard = crate::lock_utils::lock_recover(&self.state, "hedge_settlement")
+            .parked
+            .remove(account_id);
+        drop(guard);
+    }
+
+    /// 竞速结束后调用：归还所有仍在等待响应头的发送占用的并发计数。
+    pub(super) fn settle(&self) {
+        let parked = {
+            let mut state = crate::lock_utils::lock_recover(&self.state, "hedge_settlement");
+            state.settled = true;
+            std::mem::take(&mut state.parked)
+        };
+        drop(parked);
+    }
+}
+
+/// 把共享请求与候选参数组装成可在独立线程执行的发送闭包；发送期间的并发计数寄存在 `settlement` 里。
+pub(super) fn upstream_send(
+    shared: Arc<super::attempt::SharedUpstreamRequest>,
+    attempt: super::attempt::UpstreamAttempt,
+    settlement: Arc<HedgeSettlement>,
+) -> impl FnOnce() -> PrefetchedUpstream + Send + 'static {
+    move || {
+        settlement.park(&attempt.account.id);
+        let result = super::attempt::send_upstream_attempt(&shared, &attempt);
+        settlement.release(&attempt.account.id);
+        result
+    }
+}
+
+type HedgeResultSender<T, E> = mpsc::Sender<(HedgeSlot, Result<T, E>)>;
+
+fn spawn_attempt<T, E, F, D>(
+    slot: HedgeSlot,
+    attempt: F,
+    tx: HedgeResultSender<T, E>,
+    closed: Arc<Mutex<bool>>,
+    on_discarded: Arc<D>,
+) -> bool
+where
+    T: Send + 'static,
+    E: Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+    D: Fn(HedgeSlot, Result<T, E>) + Send + Sync + 'static,
+{
+    thread::Builder::new()
+        .name(format!("gateway-hedge-{}", slot.as_str()))
+        .spawn(move || {
+            let result = attempt();
+            // 中文注释：竞速已结束时结果不再交给调用方，改由丢弃回调补记惩罚；持锁发送保证不会漏掉。
+            let closed_guard = crate::lock_utils::lock_recover(&closed, "hedge_race_closed");
+            if !*closed_guard {
+                let _ = tx.send((slot, result));
+                return;
+            }
+            drop(closed_guard);
+            on_discarded(slot, result);
+        })
+        .is_ok()
+}
+
+/// 先发主候选；`delay` 内没有拿到响应头且 `can_start_hedge` 允许时，再把同一请求发给对冲候选，
+/// 谁先拿到成功响应就用谁。非成功响应不会抢跑，会继续等另一边；两边都没成功时返回主候选结果。
+/// 没有交还给调用方的结果（输掉的失败响应、竞速结束后才返回的一方）都交给 `on_discarded`。
+pub(super) fn race_hedged<T, E, P, H, S, L, D>(
+    delay: Duration,
+    primary: P,
+    hedge: H,
+    can_start_hedge: impl FnOnce() -> bool,
+    is_success: S,
+    mut log_event: L,
+    on_discarded: D,
+) -> HedgeRaceOutcome<T, E>
+where
+    T: Send + 'static,
+    E: Send + 'static,
+    P: FnOnce() -> Result<T, E> + Send + 'static,
+    H: FnOnce() -> Result<T, E> + Send + 'static,
+    S: Fn(&T) -> bool,
+    L: FnMut(HedgeSlot, &'static str),
+    D: Fn(HedgeSlot, Result<T, E>) + Send + Sync + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let closed = Arc::new(Mutex::new(false));
+    let on_discarded = Arc::new(on_discarded);
+    let outcome = 'race: {
+        if !spawn_attempt(
+            HedgeSlot::Primary,
+            primary,
+            tx.clone(),
+            closed.clone(),
+            on_discarded.clone(),
+        ) {
+            break 'race HedgeRaceOutcome::Abandoned;
+        }
+        match rx.recv_timeout(delay) {
+            Ok((_, result)) => break 'race HedgeRaceOutcome::Primary(result),
+            Err(RecvTimeoutError::Disconnected) => break 'race HedgeRaceOutcome::Abandoned,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if !can_start_hedge() {
+            log_event(HedgeSlot::Hedge, "unavailable");
+            drop(tx);
+            break 'race rx
+                .recv()
+                .map(|(_, result)| HedgeRaceOutcome::Primary(result))
+                .unwrap_or(HedgeRaceOutcome::Abandoned);
+        }
+        let mut hedge_pending = spawn_attempt(
+            HedgeSlot::Hedge,
+            hedge,
+            tx,
+            closed.clone(),
+            on_discarded.clone(),
+        );
+        if hedge_pending {
+            log_event(HedgeSlot::Hedge, "started");
+        }
+
+        let mut primary_result: Option<Result<T, E>> = None;
+        while let Ok((slot, result)) = rx.recv() {
+            match slot {
+                HedgeSlot::Primary => {
+                    if matches!(&result, Ok(value) if is_success(value)) {
+                        log_event(HedgeSlot::Primary, "won");
+                        if hedge_pending {
+                            log_event(HedgeSlot::Hedge, "abandoned");
+                        }
+                        break 'race HedgeRaceOutcome::Primary(result);
+                    }
+                    primary_result = Some(result);
+                }
+                HedgeSlot::Hedge => {
+                    hedge_pending = false;
+                    match result {
+                        Ok(value) if is_success(&value) => {
+                            log_event(HedgeSlot::Hedge, "won");
+                            match primary_result.take() {
+                                Some(discarded) => {
+                                    log_event(HedgeSlot::Primary, "discarded");
+                                    on_discarded(HedgeSlot::Primary, discarded);
+                                }
+                                None => log_event(HedgeSlot::Primary, "abandoned"),
+                            }
+                            break 'race HedgeRaceOutcome::HedgeWon(value);
+                        }
+                        result => {
+                            log_event(HedgeSlot::Hedge, "discarded");
+                            on_discarded(HedgeSlot::Hedge, result);
+                        }
+                    }
+                }
+            }
+        }
+        primary_result
+            .map(HedgeRaceOutcome::Primary)
+            .unwrap_or(HedgeRaceOutcome::Abandoned)
+    };
+    *crate::lock_utils::lock_recover(&closed, "hedge_race_closed") = true;
+    for (slot, result) in rx.try_iter() {
+        on_discarded(slot, result);
+    }
+    outcome
+}
+
+/// 没有交还给调用方的发送结果（对冲胜出前后主候选的失败、输掉的对冲失败）：按常规候选流程补记冷却与线路质量，
+/// 否则限流或故障的账号会在后续请求里继续被优先选中。成功响应只记线路质量，随后被丢弃、连接关闭。
+pub(super) fn penalize_discarded_attempt(account_id: &str, result: &PrefetchedUpstream) {
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(_) => {
+            super::super::mark_account_cooldown(account_id, super::super::CooldownReason::Network);
+            super::super::record_route_quality(account_id, 502);
+            return;
+        }
+    };
+    let status_code = resp.status().as_u16();
+    match status_code {
+        429 => {
+            let retry_after = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok());
+            let hint_secs = super::super::open_storage().and_then(|storage| {
+                super::super::rate_limit_cooldown_hint_secs(
+                    &super::super::current_cooldown_policy(),
+                    &storage,
+                    account_id,
+                    retry_after,
+                )
+            });
+            super::super::mark_account_cooldown_with_hint(
+                account_id,
+                super::super::CooldownReason::RateLimited,
+                hint_secs,
+            );
+        }
+        500..=599 => super::super::mark_account_cooldown_for_status(account_id, status_code),
+        _ => {}
+    }
+    super::super::record_route_quality(account_id, status_code);
+}
+
+#[cfg(test)]
+#[path = "tests/hedge_tests.rs"]
+mod tests;
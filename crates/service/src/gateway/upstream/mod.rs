@@ -1,11 +1,14 @@
+pub(super) mod attempt;
 pub(super) mod backoff;
 pub(super) mod candidate_flow;
 pub(super) mod candidates;
 pub(super) mod config;
 pub(super) mod deadline;
+pub(super) mod embeddings;
 pub(super) mod execution_context;
 pub(super) mod fallback_branch;
 pub(super) mod header_profile;
+pub(super) mod hedge;
 pub(super) mod openai_base;
 pub(super) mod outcome;
 pub(super) mod postprocess;
@@ -13,7 +16,9 @@ pub(super) mod precheck;
 pub(super) mod primary_attempt;
 pub(super) mod primary_flow;
 pub(super) mod protocol;
+pub(super) mod provider;
 pub(super) mod proxy;
 pub(super) mod retry;
 pub(super) mod stateless_retry;
+pub(super) mod stream_failover;
 pub(super) mod transport;
@@ -7,11 +7,13 @@ pub(super) enum UpstreamOutcomeDecision {
     RespondUpstream,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn decide_upstream_outcome<F>(
     storage: &Storage,
     account_id: &str,
     status: reqwest::StatusCode,
     upstream_content_type: Option<&HeaderValue>,
+    upstream_retry_after: Option<&HeaderValue>,
     url: &str,
     has_more_candidates: bool,
     mut log_gateway_result: F,
@@ -19,7 +21,19 @@ pub(super) fn decide_upstream_outcome<F>(
 where
     F: FnMut(Option<&str>, u16, Option<&str>),
 {
-    if matches!(status.as_u16(), 429 | 500..=599) {
+    if status.as_u16() == 429 {
+        let hint_secs = super::super::rate_limit_cooldown_hint_secs(
+            &super::super::current_cooldown_policy(),
+            storage,
+            account_id,
+            upstream_retry_after.and_then(|value| value.to_str().ok()),
+        );
+        super::super::mark_account_cooldown_with_hint(
+            account_id,
+            super::super::CooldownReason::RateLimited,
+            hint_secs,
+        );
+    } else if matches!(status.as_u16(), 500..=599) {
         // 中文注释：即使当前响应会回给客户端，也要先标记冷却，
         // 否则并发流量会继续命中同一故障账号造成雪崩。
         super::super::mark_account_cooldown_for_status(account_id, status.as_u16());
@@ -1,5 +1,6 @@
 use bytes::Bytes;
 use codexmanager_core::storage::{Account, Storage, Token};
+use reqwest::ResponseBuilderExt;
 use std::time::Instant;
 use tiny_http::Request;
 
@@ -14,6 +15,24 @@ pub(super) enum PostRetryFlowDecision {
     RespondUpstream(reqwest::blocking::Response),
 }
 
+// 中文注释：响应体被读出后重新组装，保留状态码、协议版本、响应头与上游 URL，后续日志与透传看到的与原响应一致。
+fn rebuild_upstream_response(
+    status: reqwest::StatusCode,
+    version: reqwest::Version,
+    url: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+    body: Bytes,
+) -> reqwest::blocking::Response {
+    let mut response = axum::http::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url)
+        .body(body)
+        .unwrap_or_default();
+    *response.headers_mut() = headers;
+    reqwest::blocking::Response::from(response)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(super) fn process_upstream_post_retry_flow<F>(
     client: &reqwest::blocking::Client,
@@ -38,6 +57,7 @@ pub(super) fn process_upstream_post_retry_flow<F>(
     allow_openai_fallback: bool,
     disable_challenge_stateless_retry: bool,
     has_more_candidates: bool,
+    model: Option<&str>,
     mut upstream: reqwest::blocking::Response,
     mut log_gateway_result: F,
 ) -> PostRetryFlowDecision
@@ -56,6 +76,47 @@ where
         );
     }
 
+    // 中文注释：模型权限类错误换账号即可恢复，先于各类重试识别，只排除该 (账号, 模型) 而不冷却整个账号。
+    let content_type = upstream
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if let Some(model) = model
+        .filter(|_| super::super::may_carry_model_unavailable_error(status.as_u16(), content_type))
+    {
+        let version = upstream.version();
+        let upstream_url = upstream.url().clone();
+        let headers = upstream.headers().clone();
+        let error_body = upstream.bytes().unwrap_or_default();
+        if let Some(kind) =
+            super::super::model_unavailable_kind(status.as_u16(), error_body.as_ref())
+        {
+            let reason = super::super::model_unavailable_reason(error_body.as_ref());
+            let pool = super::super::collect_gateway_candidates(storage).unwrap_or_default();
+            let pool_account_ids = pool
+                .iter()
+                .map(|(candidate, _)| candidate.id.as_str())
+                .collect::<Vec<_>>();
+            super::super::mark_account_model_unavailable(
+                &account.id,
+                model,
+                kind,
+                status.as_u16(),
+                reason.as_str(),
+                &pool_account_ids,
+            );
+            if has_more_candidates {
+                log_gateway_result(
+                    Some(url),
+                    status.as_u16(),
+                    Some("upstream model unavailable failover"),
+                );
+                return PostRetryFlowDecision::Failover;
+            }
+        }
+        upstream = rebuild_upstream_response(status, version, upstream_url, headers, error_body);
+    }
+
     if !compact_no_cookie_mode {
         if let Some(alt_url) = url_alt {
             match retry_with_alternate_path(
@@ -171,16 +232,38 @@ where
         }
     }
 
+    // 中文注释：上游每个响应都带用量头，先被动刷新快照，让后续请求尽早避开已耗尽账号。
+    crate::usage_passive::observe_rate_limit_headers(storage, &account.id, upstream.headers());
+
     match decide_upstream_outcome(
         storage,
         &account.id,
         status,
         upstream.headers().get(reqwest::header::CONTENT_TYPE),
+        upstream.headers().get(reqwest::header::RETRY_AFTER),
         url,
         has_more_candidates,
         &mut log_gateway_result,
     ) {
-        UpstreamOutcomeDecision::Failover => PostRetryFlowDecision::Failover,
+        UpstreamOutcomeDecision::Failover => {
+            if status.as_u16() == 429 {
+                // 中文注释：切换候选后响应体会被丢弃，这里先读出 429 里的额度重置时间。
+                if let Ok(body) = upstream.bytes() {
+                    if let Some(resets_at) = crate::usage_passive::parse_usage_limit_resets_at(
+                        status.as_u16(),
+                        body.as_ref(),
+                        codexmanager_core::storage::now_ts(),
+                    ) {
+                        crate::usage_passive::observe_usage_limit_reset(
+                            storage,
+                            &account.id,
+                            resets_at,
+                        );
+                    }
+                }
+            }
+            PostRetryFlowDecision::Failover
+        }
         UpstreamOutcomeDecision::Terminal {
             status_code,
             message,
@@ -9,20 +9,34 @@ pub(super) enum CandidatePrecheckResult {
     Responded,
 }
 
+fn no_candidate_message(account_groups: &[String]) -> String {
+    if account_groups.is_empty() {
+        "no available account".to_string()
+    } else {
+        format!(
+            "no available account in group(s) {} for this api key",
+            account_groups.join(",")
+        )
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(super) fn prepare_candidates_for_proxy(
     request: Request,
     storage: &Storage,
     trace_id: &str,
     key_id: &str,
+    account_groups: &[String],
     original_path: &str,
     path: &str,
     response_adapter: super::super::ResponseAdapter,
     request_method: &str,
     model_for_log: Option<&str>,
+    requested_model_for_log: Option<&str>,
     reasoning_for_log: Option<&str>,
+    batch_item: bool,
 ) -> CandidatePrecheckResult {
-    let candidates = match super::super::prepare_gateway_candidates(storage) {
+    let candidates = match super::super::prepare_gateway_candidates(storage, account_groups) {
         Ok(v) => v,
         Err(err) => {
             let err_text = format!("candidate resolve failed: {err}");
@@ -33,6 +47,7 @@ pub(super) fn prepare_candidates_for_proxy(
                     original_path: Some(original_path),
                     adapted_path: Some(path),
                     response_adapter: Some(response_adapter),
+                    requested_model: requested_model_for_log,
                 },
                 Some(key_id),
                 None,
@@ -64,6 +79,7 @@ pub(super) fn prepare_candidates_for_proxy(
     };
 
     if candidates.is_empty() {
+        let err_text = no_candidate_message(account_groups);
         super::super::write_request_log(
             storage,
             super::super::request_log::RequestLogTraceContext {
@@ -71,6 +87,7 @@ pub(super) fn prepare_candidates_for_proxy(
                 original_path: Some(original_path),
                 adapted_path: Some(path),
                 response_adapter: Some(response_adapter),
+                requested_model: requested_model_for_log,
             },
             Some(key_id),
             None,
@@ -81,11 +98,36 @@ pub(super) fn prepare_candidates_for_proxy(
             None,
             Some(503),
             super::super::request_log::RequestLogUsage::default(),
-            Some("no available account"),
+            Some(err_text.as_str()),
+        );
+        let response = super::super::error_response::terminal_text_response(
+            503,
+            err_text.clone(),
+            Some(trace_id),
+        );
+        let _ = request.respond(response);
+        super::super::trace_log::log_request_final(
+            trace_id,
+            503,
+            None,
+            None,
+            Some(err_text.as_str()),
+            0,
         );
+        return CandidatePrecheckResult::Responded;
+    }
+
+    // 中文注释：批处理请求是低优先级任务，只挑空闲且额度充足的账号；暂时没有时让执行器稍后重试，
+    // 这类延后不算真实请求，因此不写请求日志。
+    let candidates = if batch_item {
+        super::super::retain_batch_candidates(candidates)
+    } else {
+        candidates
+    };
+    if candidates.is_empty() {
         let response = super::super::error_response::terminal_text_response(
             503,
-            "no available account",
+            super::super::BATCH_DEFERRED_MESSAGE,
             Some(trace_id),
         );
         let _ = request.respond(response);
@@ -94,7 +136,7 @@ pub(super) fn prepare_candidates_for_proxy(
             503,
             None,
             None,
-            Some("no available account"),
+            Some(super::super::BATCH_DEFERRED_MESSAGE),
             0,
         );
         return CandidatePrecheckResult::Responded;
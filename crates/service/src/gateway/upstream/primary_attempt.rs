@@ -23,6 +23,7 @@ pub(super) fn run_primary_upstream_attempt<F>(
     account: &Account,
     strip_session_affinity: bool,
     has_more_candidates: bool,
+    prefetched: Option<super::hedge::PrefetchedUpstream>,
     mut log_gateway_result: F,
 ) -> PrimaryAttemptResult
 where
@@ -35,20 +36,25 @@ where
             message: "upstream total timeout exceeded".to_string(),
         };
     }
-    match super::transport::send_upstream_request(
-        client,
-        method,
-        url,
-        request_deadline,
-        request,
-        incoming_headers,
-        body,
-        is_stream,
-        upstream_cookie,
-        auth_token,
-        account,
-        strip_session_affinity,
-    ) {
+    // 中文注释：对冲竞速已经拿到的结果直接复用，不再重复发送。
+    let result = match prefetched {
+        Some(result) => result,
+        None => super::transport::send_upstream_request(
+            client,
+            method,
+            url,
+            request_deadline,
+            request,
+            incoming_headers,
+            body,
+            is_stream,
+            upstream_cookie,
+            auth_token,
+            account,
+            strip_session_affinity,
+        ),
+    };
+    match result {
         Ok(resp) => PrimaryAttemptResult::Upstream(resp),
         Err(err) => {
             let err_msg = err.to_string();
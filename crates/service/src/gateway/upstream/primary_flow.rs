@@ -50,6 +50,7 @@ pub(super) fn run_primary_upstream_flow<F>(
     debug: bool,
     allow_openai_fallback: bool,
     has_more_candidates: bool,
+    prefetched: Option<super::hedge::PrefetchedUpstream>,
     mut log_gateway_result: F,
 ) -> PrimaryFlowDecision
 where
@@ -101,6 +102,7 @@ where
         account,
         strip_session_affinity,
         has_more_candidates,
+        prefetched,
         &mut log_gateway_result,
     ) {
         PrimaryAttemptResult::Upstream(resp) => resp,
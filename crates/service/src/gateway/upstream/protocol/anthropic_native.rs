@@ -0,0 +1,326 @@
+use bytes::Bytes;
+use codexmanager_core::storage::Storage;
+use reqwest::header::{HeaderName, HeaderValue};
+use std::time::Instant;
+use tiny_http::Request;
+
+use crate::apikey_profile::PROTOCOL_ANTHROPIC_NATIVE;
+
+use super::super::super::request_log::{RequestLogTraceContext, RequestLogUsage};
+
+const DEFAULT_ANTHROPIC_VERSION: &str = "2023-06-01";
+// 中文注释：只透传 Anthropic 自己的版本/特性头；客户端带来的平台 Key 等其余头一律不外发。
+const FORWARDED_CLIENT_HEADERS: [&str; 2] = ["anthropic-version", "anthropic-beta"];
+
+fn has_anthropic_auth_header(headers: &[(HeaderName, HeaderValue)]) -> bool {
+    headers.iter().any(|(name, _)| {
+        name.as_str().eq_ignore_ascii_case("x-api-key")
+            || name.as_str().eq_ignore_ascii_case("authorization")
+    })
+}
+
+fn has_header(headers: &[(HeaderName, HeaderValue)], target: &str) -> bool {
+    headers
+        .iter()
+        .any(|(name, _)| name.as_str().eq_ignore_ascii_case(target))
+}
+
+fn client_forwarded_headers(request: &Request) -> Vec<(HeaderName, HeaderValue)> {
+    FORWARDED_CLIENT_HEADERS
+        .iter()
+        .filter_map(|target| {
+            let header = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv(target))?;
+            let value = HeaderValue::from_str(header.value.as_str().trim()).ok()?;
+            Some((HeaderName::from_static(target), value))
+        })
+        .collect()
+}
+
+/// 组装发往 Anthropic 的请求头：Key 配置的静态头优先，其次客户端的版本头，最后补默认版本。
+fn build_upstream_headers(
+    static_headers: Vec<(HeaderName, HeaderValue)>,
+    client_headers: Vec<(HeaderName, HeaderValue)>,
+) -> Vec<(HeaderName, HeaderValue)> {
+    let mut headers = static_headers;
+    for (name, value) in client_headers {
+        if !has_header(&headers, name.as_str()) {
+            headers.push((name, value));
+        }
+    }
+    if !has_header(&headers, "anthropic-version") {
+        headers.push((
+            HeaderName::from_static("anthropic-version"),
+            HeaderValue::from_static(DEFAULT_ANTHROPIC_VERSION),
+        ));
+    }
+    headers
+}
+
+fn is_count_tokens_path(path: &str) -> bool {
+    path == "/v1/messages/count_tokens" || path.starts_with("/v1/messages/count_tokens?")
+}
+
+/// Anthropic 的 usage 不带 total，这里按输入 + 输出补齐；count_tokens 不产生用量，不记账。
+fn finalize_usage(path: &str, usage: RequestLogUsage) -> RequestLogUsage {
+    if is_count_tokens_path(path) {
+        return RequestLogUsage::default();
+    }
+    let total_tokens = usage
+        .total_tokens
+        .or(match (usage.input_tokens, usage.output_tokens) {
+            (None, None) => None,
+            (input, output) => Some(input.unwrap_or(0) + output.unwrap_or(0)),
+        });
+    RequestLogUsage {
+        total_tokens,
+        ..usage
+    }
+}
+
+struct AnthropicRequestLog<'a> {
+    storage: &'a Storage,
+    trace_id: &'a str,
+    key_id: &'a str,
+    original_path: &'a str,
+    path: &'a str,
+    request_method: &'a str,
+    response_adapter: super::super::super::ResponseAdapter,
+    model_for_log: Option<&'a str>,
+    requested_model_for_log: Option<&'a str>,
+    reasoning_for_log: Option<&'a str>,
+    started_at: Instant,
+}
+
+impl AnthropicRequestLog<'_> {
+    fn finish(
+        &self,
+        status_code: u16,
+        upstream_url: Option<&str>,
+        usage: RequestLogUsage,
+        error: Option<&str>,
+    ) {
+        super::super::super::record_gateway_request_outcome(
+            self.path,
+            status_code,
+            Some(PROTOCOL_ANTHROPIC_NATIVE),
+        );
+        super::super::super::trace_log::log_request_final(
+            self.trace_id,
+            status_code,
+            Some(self.key_id),
+            upstream_url,
+            error,
+            self.started_at.elapsed().as_millis(),
+        );
+        super::super::super::write_request_log(
+            self.storage,
+            RequestLogTraceContext {
+                trace_id: Some(self.trace_id),
+                original_path: Some(self.original_path),
+                adapted_path: Some(self.path),
+                response_adapter: Some(self.response_adapter),
+                requested_model: self.requested_model_for_log,
+            },
+            Some(self.key_id),
+            None,
+            self.path,
+            self.request_method,
+            self.model_for_log,
+            self.reasoning_for_log,
+            upstream_url,
+            Some(status_code),
+            usage,
+            error,
+        );
+    }
+
+    fn fail(&self, request: Request, status_code: u16, upstream_url: Option<&str>, message: &str) {
+        self.finish(
+            status_code,
+            upstream_url,
+            RequestLogUsage::default(),
+            Some(message),
+        );
+        let response = super::super::super::error_response::terminal_json_response(
+            status_code,
+            message,
+            super::super::super::build_anthropic_error_body(message),
+            &[],
+            Some(self.trace_id),
+        );
+        let _ = request.respond(response);
+    }
+}
+
+fn send_with_client(
+    client: &reqwest::blocking::Client,
+    method: &reqwest::Method,
+    url: &str,
+    headers: &[(HeaderName, HeaderValue)],
+    body: &Bytes,
+    is_stream: bool,
+    request_deadline: Option<Instant>,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let mut builder = client.request(method.clone(), url);
+    if let Some(timeout) = super::super::deadline::send_timeout(request_deadline, is_stream) {
+        builder = builder.timeout(timeout);
+    }
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    builder = builder.header(
+        "Accept",
+        if is_stream {
+            "text/event-stream"
+        } else {
+            "application/json"
+        },
+    );
+    if !body.is_empty() {
+        builder = builder.header("Content-Type", "application/json");
+        builder = builder.body(body.clone());
+    }
+    builder.send()
+}
+
+/// anthropic_native Key 配置了上游地址时的直连路径：请求体、路径与 SSE 原样转发，
+/// 只替换鉴权头，用量从 Anthropic 的 message_start / message_delta 事件里解析。
+#[allow(clippy::too_many_arguments)]
+pub(in super::super) fn proxy_anthropic_request(
+    request: Request,
+    storage: &Storage,
+    trace_id: &str,
+    key_id: &str,
+    original_path: &str,
+    path: &str,
+    request_method: &str,
+    method: &reqwest::Method,
+    body: &Bytes,
+    is_stream: bool,
+    response_adapter: super::super::super::ResponseAdapter,
+    model_for_log: Option<&str>,
+    requested_model_for_log: Option<&str>,
+    reasoning_for_log: Option<&str>,
+    upstream_base_url: &str,
+    static_headers_json: Option<&str>,
+    request_deadline: Option<Instant>,
+    started_at: Instant,
+) -> Result<(), String> {
+    let log = AnthropicRequestLog {
+        storage,
+        trace_id,
+        key_id,
+        original_path,
+        path,
+        request_method,
+        response_adapter,
+        model_for_log,
+        requested_model_for_log,
+        reasoning_for_log,
+        started_at,
+    };
+
+    let static_headers = match super::azure_openai::parse_static_headers_json(static_headers_json) {
+        Ok(value) => value,
+        Err(err) => {
+            log.fail(request, 400, None, err.as_str());
+            return Ok(());
+        }
+    };
+    // 中文注释：平台 Key 本身不是 Anthropic Key，缺少上游鉴权头时直接拒绝，不回退到平台 Key 明文。
+    if !has_anthropic_auth_header(&static_headers) {
+        log.fail(
+            request,
+            403,
+            None,
+            "anthropic api key missing: please set API Key in Anthropic fields",
+        );
+        return Ok(());
+    }
+    let headers = build_upstream_headers(static_headers, client_forwarded_headers(&request));
+
+    let (url, _) = super::super::super::compute_upstream_url(upstream_base_url.trim(), path);
+    let attempt_started_at = Instant::now();
+    let sent = send_with_client(
+        &super::super::super::upstream_client(),
+        method,
+        &url,
+        &headers,
+        body,
+        is_stream,
+        request_deadline,
+    )
+    .or_else(|first_err| {
+        // 中文注释：与 Azure 直连一致，系统代理切换后旧 client 可能失效，用 fresh client 再试一次。
+        send_with_client(
+            &super::super::super::fresh_upstream_client(),
+            method,
+            &url,
+            &headers,
+            body,
+            is_stream,
+            request_deadline,
+        )
+        .map_err(|second_err| {
+            format!("anthropic upstream error: {first_err}; retry_after_fresh_client: {second_err}")
+        })
+    });
+    let duration_ms = super::super::super::duration_to_millis(attempt_started_at.elapsed());
+    super::super::super::metrics::record_gateway_upstream_attempt(duration_ms, sent.is_err());
+    let upstream = match sent {
+        Ok(resp) => resp,
+        Err(message) => {
+            log.fail(request, 502, Some(url.as_str()), message.as_str());
+            return Ok(());
+        }
+    };
+
+    let status_code = upstream.status().as_u16();
+    let inflight_guard = super::super::super::acquire_account_inflight(key_id);
+    let bridge = super::super::super::respond_with_upstream(
+        request,
+        upstream,
+        inflight_guard,
+        response_adapter,
+        None,
+        is_stream,
+        Some(trace_id),
+        None,
+    )?;
+    let bridge_ok = bridge.is_ok(is_stream);
+    let bridge_error = bridge.error_message(is_stream);
+    let mut final_status_code = status_code;
+    let mut final_error_text =
+        (status_code >= 400).then(|| "anthropic upstream non-success".to_string());
+    if bridge.client_cancelled {
+        final_status_code = 499;
+        final_error_text = Some("client_cancelled".to_string());
+    } else if status_code < 400 && !bridge_ok {
+        final_status_code = 502;
+        final_error_text = bridge_error.or(final_error_text);
+    }
+    let usage = bridge.usage;
+    log.finish(
+        final_status_code,
+        Some(url.as_str()),
+        finalize_usage(
+            path,
+            RequestLogUsage {
+                input_tokens: usage.input_tokens,
+                cached_input_tokens: usage.cached_input_tokens,
+                output_tokens: usage.output_tokens,
+                total_tokens: usage.total_tokens,
+                reasoning_output_tokens: usage.reasoning_output_tokens,
+            },
+        ),
+        final_error_text.as_deref(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/anthropic_native_tests.rs"]
+mod tests;
@@ -6,7 +6,9 @@ use tiny_http::Request;
 
 use crate::apikey_profile::PROTOCOL_AZURE_OPENAI;
 
-fn parse_static_headers_json(raw: Option<&str>) -> Result<Vec<(HeaderName, HeaderValue)>, String> {
+pub(super) fn parse_static_headers_json(
+    raw: Option<&str>,
+) -> Result<Vec<(HeaderName, HeaderValue)>, String> {
     let Some(raw) = raw.map(str::trim).filter(|value| !value.is_empty()) else {
         return Ok(Vec::new());
     };
@@ -63,6 +65,7 @@ pub(in super::super) fn proxy_azure_request(
     response_adapter: super::super::super::ResponseAdapter,
     tool_name_restore_map: &super::super::super::ToolNameRestoreMap,
     model_for_log: Option<&str>,
+    requested_model_for_log: Option<&str>,
     reasoning_for_log: Option<&str>,
     upstream_base_url: Option<&str>,
     static_headers_json: Option<&str>,
@@ -90,6 +93,7 @@ pub(in super::super) fn proxy_azure_request(
                 original_path: Some(original_path),
                 adapted_path: Some(path),
                 response_adapter: Some(response_adapter),
+                requested_model: requested_model_for_log,
             },
             Some(key_id),
             None,
@@ -129,6 +133,7 @@ pub(in super::super) fn proxy_azure_request(
                     original_path: Some(original_path),
                     adapted_path: Some(path),
                     response_adapter: Some(response_adapter),
+                    requested_model: requested_model_for_log,
                 },
                 Some(key_id),
                 None,
@@ -172,6 +177,7 @@ pub(in super::super) fn proxy_azure_request(
                         original_path: Some(original_path),
                         adapted_path: Some(path),
                         response_adapter: Some(response_adapter),
+                        requested_model: requested_model_for_log,
                     },
                     Some(key_id),
                     None,
@@ -209,6 +215,7 @@ pub(in super::super) fn proxy_azure_request(
                         original_path: Some(original_path),
                         adapted_path: Some(path),
                         response_adapter: Some(response_adapter),
+                        requested_model: requested_model_for_log,
                     },
                     Some(key_id),
                     None,
@@ -328,6 +335,7 @@ pub(in super::super) fn proxy_azure_request(
                             original_path: Some(original_path),
                             adapted_path: Some(path),
                             response_adapter: Some(response_adapter),
+                            requested_model: requested_model_for_log,
                         },
                         Some(key_id),
                         None,
@@ -362,6 +370,7 @@ pub(in super::super) fn proxy_azure_request(
         Some(tool_name_restore_map),
         is_stream,
         Some(trace_id),
+        None,
     )?;
     let bridge_ok = bridge.is_ok(is_stream);
     let bridge_error = bridge.error_message(is_stream);
@@ -395,6 +404,7 @@ pub(in super::super) fn proxy_azure_request(
             original_path: Some(original_path),
             adapted_path: Some(path),
             response_adapter: Some(response_adapter),
+            requested_model: requested_model_for_log,
         },
         Some(key_id),
         None,
@@ -1 +1,2 @@
+pub(super) mod anthropic_native;
 pub(super) mod azure_openai;
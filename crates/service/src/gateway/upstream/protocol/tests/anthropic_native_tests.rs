@@ -0,0 +1,105 @@
+use super::*;
+
+fn header_value<'a>(headers: &'a [(HeaderName, HeaderValue)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.as_str().eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.to_str().ok())
+}
+
+#[test]
+fn static_auth_headers_are_detected() {
+    let x_api_key =
+        super::super::azure_openai::parse_static_headers_json(Some(r#"{"x-api-key":"sk-ant-1"}"#))
+            .expect("parse headers");
+    assert!(has_anthropic_auth_header(&x_api_key));
+
+    let bearer = super::super::azure_openai::parse_static_headers_json(Some(
+        r#"{"Authorization":"Bearer sk-ant-1"}"#,
+    ))
+    .expect("parse headers");
+    assert!(has_anthropic_auth_header(&bearer));
+
+    let api_key = super::super::azure_openai::parse_static_headers_json(Some(r#"{"api-key":"k"}"#))
+        .expect("parse headers");
+    assert!(!has_anthropic_auth_header(&api_key));
+}
+
+#[test]
+fn upstream_headers_default_anthropic_version() {
+    let headers = build_upstream_headers(
+        vec![(
+            HeaderName::from_static("x-api-key"),
+            HeaderValue::from_static("sk-ant-1"),
+        )],
+        Vec::new(),
+    );
+    assert_eq!(header_value(&headers, "x-api-key"), Some("sk-ant-1"));
+    assert_eq!(
+        header_value(&headers, "anthropic-version"),
+        Some(DEFAULT_ANTHROPIC_VERSION)
+    );
+}
+
+#[test]
+fn upstream_headers_keep_client_version_and_beta_but_prefer_static_headers() {
+    let headers = build_upstream_headers(
+        vec![
+            (
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_static("sk-ant-1"),
+            ),
+            (
+                HeaderName::from_static("anthropic-beta"),
+                HeaderValue::from_static("static-beta"),
+            ),
+        ],
+        vec![
+            (
+                HeaderName::from_static("anthropic-version"),
+                HeaderValue::from_static("2024-01-01"),
+            ),
+            (
+                HeaderName::from_static("anthropic-beta"),
+                HeaderValue::from_static("client-beta"),
+            ),
+        ],
+    );
+    assert_eq!(
+        header_value(&headers, "anthropic-version"),
+        Some("2024-01-01")
+    );
+    assert_eq!(
+        header_value(&headers, "anthropic-beta"),
+        Some("static-beta")
+    );
+    assert_eq!(
+        headers
+            .iter()
+            .filter(|(name, _)| name.as_str() == "anthropic-version")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn finalize_usage_fills_total_and_skips_count_tokens() {
+    let usage = RequestLogUsage {
+        input_tokens: Some(120),
+        cached_input_tokens: Some(20),
+        output_tokens: Some(30),
+        total_tokens: None,
+        reasoning_output_tokens: None,
+    };
+    assert_eq!(
+        finalize_usage("/v1/messages", usage).total_tokens,
+        Some(150)
+    );
+    assert_eq!(
+        finalize_usage("/v1/messages", RequestLogUsage::default()).total_tokens,
+        None
+    );
+    let counted = finalize_usage("/v1/messages/count_tokens", usage);
+    assert_eq!(counted.input_tokens, None);
+    assert_eq!(counted.total_tokens, None);
+}
@@ -0,0 +1,181 @@
+use bytes::Bytes;
+use codexmanager_core::storage::{Account, ProviderAccount, Storage, Token};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use super::candidate_flow::CandidateUpstreamDecision;
+
+const PROVIDER_CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
+
+/// 服务商只提供 Chat Completions，响应要能原样交给 chat 适配器，因此只承接 chat 入口的请求。
+fn provider_adapter_supported(response_adapter: super::super::ResponseAdapter) -> bool {
+    matches!(
+        response_adapter,
+        super::super::ResponseAdapter::OpenAIChatCompletionsJson
+            | super::super::ResponseAdapter::OpenAIChatCompletionsSse
+    )
+}
+
+/// 剔除当前请求用不上的服务商候选（入口不是 chat、模型不在服务商列表、配置已删除），
+/// 返回剩余服务商的配置，供候选循环按 id 取 base URL。
+pub(super) fn retain_eligible_providers(
+    storage: &Storage,
+    candidates: &mut Vec<(Account, Token)>,
+    response_adapter: super::super::ResponseAdapter,
+    model: Option<&str>,
+) -> HashMap<String, ProviderAccount> {
+    if !candidates
+        .iter()
+        .any(|(account, _)| account.is_provider_account())
+    {
+        return HashMap::new();
+    }
+    let providers = if provider_adapter_supported(response_adapter) {
+        match storage.list_active_provider_accounts() {
+            Ok(items) => items
+                .into_iter()
+                .filter(|provider| {
+                    crate::provider_accounts::provider_serves_model(&provider.models_json, model)
+                })
+                .map(|provider| (provider.id.clone(), provider))
+                .collect(),
+            Err(err) => {
+                log::warn!("event=gateway_provider_accounts_load_failed err={err}");
+                HashMap::new()
+            }
+        }
+    } else {
+        HashMap::new()
+    };
+    candidates.retain(|(account, _)| {
+        !account.is_provider_account() || providers.contains_key(&account.id)
+    });
+    providers
+}
+
+/// 路由策略可能打乱顺序，这里把服务商稳定地挪回队尾，保证只有 OAuth 账号都失败后才会用到。
+pub(super) fn move_providers_last(candidates: &mut [(Account, Token)]) {
+    candidates.sort_by_key(|(account, _)| account.is_provider_account());
+}
+
+/// 把已适配成 Responses 的请求体转回 Chat Completions，Bearer 静态 Key 发往服务商。
+#[allow(clippy::too_many_arguments)]
+pub(super) fn process_provider_upstream_flow<F>(
+    storage: &Storage,
+    provider: &ProviderAccount,
+    body: &Bytes,
+    is_stream: bool,
+    request_deadline: Option<Instant>,
+    has_more_candidates: bool,
+    mut log_gateway_result: F,
+) -> CandidateUpstreamDecision
+where
+    F: FnMut(Option<&str>, u16, Option<&str>),
+{
+    if super::deadline::is_expired(request_deadline) {
+        return CandidateUpstreamDecision::Terminal {
+            status_code: 504,
+            message: "upstream total timeout exceeded".to_string(),
+        };
+    }
+    let account_id = provider.id.as_str();
+    let (url, _) = super::super::compute_upstream_url(
+        provider.base_url.trim(),
+        PROVIDER_CHAT_COMPLETIONS_PATH,
+    );
+    let chat_body =
+        super::super::request_rewrite::convert_responses_body_for_chat_provider(body, is_stream)
+            .map(Bytes::from)
+            .unwrap_or_else(|| body.clone());
+
+    let client = super::super::upstream_client_for_account(account_id);
+    let mut builder = client
+        .post(url.as_str())
+        .bearer_auth(provider.api_key.trim())
+        .header("Content-Type", "application/json")
+        .header(
+            "Accept",
+            if is_stream {
+                "text/event-stream"
+            } else {
+                "application/json"
+            },
+        )
+        .body(chat_body);
+    if let Some(timeout) = super::deadline::send_timeout(request_deadline, is_stream) {
+        builder = builder.timeout(timeout);
+    }
+    let attempt_started_at = Instant::now();
+    let result = builder.send();
+    let duration_ms = super::super::duration_to_millis(attempt_started_at.elapsed());
+    super::super::metrics::record_gateway_upstream_attempt(duration_ms, result.is_err());
+
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(err) => {
+            let message = format!("provider upstream error: {err}");
+            super::super::mark_account_cooldown(account_id, super::super::CooldownReason::Network);
+            log_gateway_result(Some(url.as_str()), 502, Some(message.as_str()));
+            if has_more_candidates {
+                return CandidateUpstreamDecision::Failover;
+            }
+            return CandidateUpstreamDecision::Terminal {
+                status_code: 502,
+                message,
+            };
+        }
+    };
+
+    let status = resp.status().as_u16();
+    if resp.status().is_success() {
+        let proxy = super::super::upstream_proxy_for_account(account_id);
+        super::super::record_route_latency(
+            account_id,
+            proxy.as_deref(),
+            super::super::RouteLatencyKind::FirstByte,
+            duration_ms,
+        );
+        super::super::clear_account_cooldown(account_id);
+        log_gateway_result(Some(url.as_str()), status, None);
+        return CandidateUpstreamDecision::RespondUpstream(resp);
+    }
+
+    // 中文注释：服务商没有用量快照可参考，限流、鉴权失败与 5xx 都直接冷却并换下一个服务商；
+    // 400 之类的请求错误换服务商也大概率相同，直接回给客户端。
+    let should_failover = match status {
+        429 => {
+            let hint_secs = super::super::rate_limit_cooldown_hint_secs(
+                &super::super::current_cooldown_policy(),
+                storage,
+                account_id,
+                resp.headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()),
+            );
+            super::super::mark_account_cooldown_with_hint(
+                account_id,
+                super::super::CooldownReason::RateLimited,
+                hint_secs,
+            );
+            true
+        }
+        401 | 403 | 404 | 500..=599 => {
+            super::super::mark_account_cooldown_for_status(account_id, status);
+            true
+        }
+        _ => false,
+    };
+    log_gateway_result(
+        Some(url.as_str()),
+        status,
+        Some("provider upstream non-success"),
+    );
+    if should_failover && has_more_candidates {
+        return CandidateUpstreamDecision::Failover;
+    }
+    CandidateUpstreamDecision::RespondUpstream(resp)
+}
+
+#[cfg(test)]
+#[path = "tests/provider_tests.rs"]
+mod tests;
@@ -1,10 +1,13 @@
-use crate::apikey_profile::{PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_AZURE_OPENAI};
+use crate::apikey_profile::{
+    is_anthropic_passthrough, PROTOCOL_ANTHROPIC_NATIVE, PROTOCOL_AZURE_OPENAI,
+};
 use serde_json::Value;
 use std::time::{Duration, Instant};
 use tiny_http::Request;
 
 use super::super::local_validation::LocalValidationResult;
 use super::super::request_log::RequestLogUsage;
+use super::super::route_affinity::RouteAffinityDecision;
 use super::candidate_flow::{process_candidate_upstream_flow, CandidateUpstreamDecision};
 use super::execution_context::GatewayUpstreamExecutionContext;
 use super::precheck::{prepare_candidates_for_proxy, CandidatePrecheckResult};
@@ -48,6 +51,42 @@ fn strip_encrypted_content_from_body(body: &[u8]) -> Option<Vec<u8>> {
     serde_json::to_vec(&value).ok()
 }
 
+fn candidate_account_scope(account: &codexmanager_core::storage::Account) -> Option<String> {
+    account
+        .chatgpt_account_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .or_else(|| {
+            account
+                .workspace_id
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+        })
+}
+
+fn rewrite_body_model(body: &[u8], model: &str) -> Option<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let obj = value.as_object_mut()?;
+    obj.insert("model".to_string(), Value::String(model.to_string()));
+    serde_json::to_vec(&value).ok()
+}
+
+fn insert_served_model_header(resp: &mut reqwest::blocking::Response, model: &str) {
+    let Ok(value) = reqwest::header::HeaderValue::from_str(model) else {
+        return;
+    };
+    let Ok(name) = reqwest::header::HeaderName::from_bytes(
+        crate::error_codes::SERVED_MODEL_HEADER_NAME.as_bytes(),
+    ) else {
+        return;
+    };
+    resp.headers_mut().insert(name, value);
+}
+
 fn respond_terminal(
     request: Request,
     status_code: u16,
@@ -89,6 +128,36 @@ fn respond_total_timeout(
     respond_terminal(request, 504, message, Some(trace_id))
 }
 
+/// 排队已满或等待超时：账号池仍然饱和，直接返回 503 让客户端稍后重试，而不是把请求打到必败的账号上。
+fn respond_admission_rejected(
+    request: Request,
+    context: &GatewayUpstreamExecutionContext<'_>,
+    trace_id: &str,
+    started_at: Instant,
+    message: &str,
+) -> Result<(), String> {
+    context.log_final_result(
+        None,
+        None,
+        503,
+        RequestLogUsage::default(),
+        Some(message),
+        started_at.elapsed().as_millis(),
+    );
+    let mut response =
+        super::super::error_response::terminal_text_response(503, message, Some(trace_id));
+    if let Ok(header) = tiny_http::Header::from_bytes(
+        b"Retry-After".as_slice(),
+        super::super::admission_retry_after_secs()
+            .to_string()
+            .as_bytes(),
+    ) {
+        response.add_header(header);
+    }
+    let _ = request.respond(response);
+    Ok(())
+}
+
 pub(in super::super) fn proxy_validated_request(
     request: Request,
     validated: LocalValidationResult,
@@ -111,9 +180,15 @@ pub(in super::super) fn proxy_validated_request(
         tool_name_restore_map,
         request_method,
         key_id,
+        account_groups,
+        hedge_delay_ms,
+        rate_limit_permit: _rate_limit_permit,
         model_for_log,
+        requested_model_for_log,
         reasoning_for_log,
         method,
+        embeddings,
+        batch_item,
     } = validated;
     let started_at = Instant::now();
     let client_is_stream = is_stream;
@@ -149,6 +224,7 @@ pub(in super::super) fn proxy_validated_request(
             response_adapter,
             &tool_name_restore_map,
             model_for_log.as_deref(),
+            requested_model_for_log.as_deref(),
             reasoning_for_log.as_deref(),
             upstream_base_url.as_deref(),
             static_headers_json.as_deref(),
@@ -157,17 +233,67 @@ pub(in super::super) fn proxy_validated_request(
         );
     }
 
+    if let Some(base) = upstream_base_url
+        .as_deref()
+        .filter(|base| is_anthropic_passthrough(protocol_type.as_str(), Some(base)))
+    {
+        return super::protocol::anthropic_native::proxy_anthropic_request(
+            request,
+            &storage,
+            trace_id.as_str(),
+            key_id.as_str(),
+            original_path.as_str(),
+            path.as_str(),
+            request_method.as_str(),
+            &method,
+            &body,
+            client_is_stream,
+            response_adapter,
+            model_for_log.as_deref(),
+            requested_model_for_log.as_deref(),
+            reasoning_for_log.as_deref(),
+            base,
+            static_headers_json.as_deref(),
+            request_deadline,
+            started_at,
+        );
+    }
+
+    if let Some(embeddings) = embeddings.as_ref() {
+        return super::embeddings::proxy_embeddings_request(
+            request,
+            &storage,
+            trace_id.as_str(),
+            key_id.as_str(),
+            &account_groups,
+            original_path.as_str(),
+            path.as_str(),
+            request_method.as_str(),
+            protocol_type.as_str(),
+            embeddings,
+            response_adapter,
+            model_for_log.as_deref(),
+            requested_model_for_log.as_deref(),
+            batch_item,
+            request_deadline,
+            started_at,
+        );
+    }
+
     let (request, mut candidates) = match prepare_candidates_for_proxy(
         request,
         &storage,
         trace_id.as_str(),
         &key_id,
+        &account_groups,
         &original_path,
         &path,
         response_adapter,
         &request_method,
         model_for_log.as_deref(),
+        requested_model_for_log.as_deref(),
         reasoning_for_log.as_deref(),
+        batch_item,
     ) {
         CandidatePrecheckResult::Ready {
             request,
@@ -176,6 +302,13 @@ pub(in super::super) fn proxy_validated_request(
         CandidatePrecheckResult::Responded => return Ok(()),
     };
     let mut request = Some(request);
+    // 中文注释：服务商账号只作为 OAuth 账号之后的溢出层，先剔除当前请求用不上的服务商。
+    let provider_accounts = super::provider::retain_eligible_providers(
+        &storage,
+        &mut candidates,
+        response_adapter,
+        model_for_log.as_deref(),
+    );
 
     let upstream_base = super::super::resolve_upstream_base_url();
     let base = upstream_base.as_str();
@@ -188,7 +321,41 @@ pub(in super::super) fn proxy_validated_request(
     let account_max_inflight = super::super::account_max_inflight_limit();
     let anthropic_has_prompt_cache_key =
         protocol_type == PROTOCOL_ANTHROPIC_NATIVE && has_prompt_cache_key;
-    super::super::apply_route_strategy(&mut candidates, &key_id, model_for_log.as_deref());
+    let affinity_key = super::super::route_affinity::resolve_route_affinity_key(
+        key_id.as_str(),
+        &incoming_headers,
+        body.as_ref(),
+    );
+    let affinity_decision = super::super::apply_route_strategy(
+        &mut candidates,
+        &key_id,
+        model_for_log.as_deref(),
+        affinity_key.as_deref(),
+    );
+    super::provider::move_providers_last(&mut candidates);
+    if let Some(affinity_key) = affinity_key.as_deref() {
+        match &affinity_decision {
+            RouteAffinityDecision::Honored { account_id } => {
+                super::super::trace_log::log_route_affinity(
+                    trace_id.as_str(),
+                    affinity_key,
+                    "honored",
+                    account_id,
+                    None,
+                );
+            }
+            RouteAffinityDecision::Broken { account_id, reason } => {
+                super::super::trace_log::log_route_affinity(
+                    trace_id.as_str(),
+                    affinity_key,
+                    "broken",
+                    account_id,
+                    Some(reason),
+                );
+            }
+            RouteAffinityDecision::NotApplicable => {}
+        }
+    }
     let candidate_order = candidates
         .iter()
         .map(|(account, _)| format!("{}#sort={}", account.id, account.sort))
@@ -200,7 +367,7 @@ pub(in super::super) fn proxy_validated_request(
         candidate_order.as_slice(),
     );
 
-    let context = GatewayUpstreamExecutionContext::new(
+    let mut context = GatewayUpstreamExecutionContext::new(
         &trace_id,
         &storage,
         &key_id,
@@ -210,10 +377,75 @@ pub(in super::super) fn proxy_validated_request(
         response_adapter,
         protocol_type.as_str(),
         model_for_log.as_deref(),
+        requested_model_for_log.as_deref(),
         reasoning_for_log.as_deref(),
         candidate_count,
         account_max_inflight,
     );
+
+    // 中文注释：开启排队后，账号池整体饱和时先等冷却到期或并发槽释放，而不是立刻把请求打到必败的账号上。
+    let admission = super::super::wait_for_admission(
+        key_id.as_str(),
+        super::deadline::cap_wait(super::super::admission_queue_max_wait(), request_deadline),
+        || super::candidates::candidate_pool_saturated(&candidates, account_max_inflight),
+    );
+    match admission {
+        super::super::AdmissionOutcome::NotQueued => {}
+        super::super::AdmissionOutcome::Admitted { waited_ms } => {
+            super::super::trace_log::log_admission_queue(
+                trace_id.as_str(),
+                key_id.as_str(),
+                "admitted",
+                waited_ms,
+            );
+        }
+        super::super::AdmissionOutcome::TimedOut { waited_ms } => {
+            super::super::trace_log::log_admission_queue(
+                trace_id.as_str(),
+                key_id.as_str(),
+                "timeout",
+                waited_ms,
+            );
+            let request = request
+                .take()
+                .expect("request should be available before admission response");
+            return respond_admission_rejected(
+                request,
+                &context,
+                trace_id.as_str(),
+                started_at,
+                "admission queue wait timeout",
+            );
+        }
+        super::super::AdmissionOutcome::Rejected { reason } => {
+            super::super::trace_log::log_admission_queue(
+                trace_id.as_str(),
+                key_id.as_str(),
+                reason,
+                0,
+            );
+            let request = request
+                .take()
+                .expect("request should be available before admission response");
+            // 中文注释：总截止时间已耗尽按超时处理；其余（队列满、排队等待被配置为 0）按饱和返回 503。
+            if super::deadline::is_expired(request_deadline) {
+                return respond_total_timeout(request, &context, trace_id.as_str(), started_at);
+            }
+            let message = match reason {
+                "queue_full" => "admission queue full",
+                "key_queue_full" => "admission queue full for api key",
+                _ => "admission queue wait timeout",
+            };
+            return respond_admission_rejected(
+                request,
+                &context,
+                trace_id.as_str(),
+                started_at,
+                message,
+            );
+        }
+    }
+
     let allow_openai_fallback = true;
     let disable_challenge_stateless_retry = !(protocol_type == PROTOCOL_ANTHROPIC_NATIVE
         && body.len() <= 2 * 1024)
@@ -287,365 +519,723 @@ pub(in super::super) fn proxy_validated_request(
     let has_sticky_fallback_conversation =
         super::header_profile::derive_sticky_conversation_id_from_headers(&incoming_headers)
             .is_some();
-    let has_body_encrypted_content = body_has_encrypted_content_hint(body.as_ref());
-    let mut stripped_body: Option<bytes::Bytes> = None;
-
-    // For `anthropic_native` with `prompt_cache_key`, keep Session/Conversation affinity within the
-    // same Chatgpt-Account-Id "scope" (chatgpt_account_id preferred, otherwise workspace_id).
-    // Switching scope on failover can increase upstream challenge probability.
-    let mut first_candidate_account_scope: Option<String> = None;
-    for (idx, (account, mut token)) in candidates.into_iter().enumerate() {
-        if super::deadline::is_expired(request_deadline) {
-            let request = request
-                .take()
-                .expect("request should be available before timeout response");
-            return respond_total_timeout(request, &context, trace_id.as_str(), started_at);
-        }
-        // 中文注释：Claude 兼容入口命中 prompt_cache_key 时，优先保持会话粘性；
-        // failover 时若强制重置 Session/Conversation，更容易触发 upstream challenge。
-        let strip_session_affinity = if anthropic_has_prompt_cache_key {
-            let candidate_scope = account
-                .chatgpt_account_id
-                .as_deref()
-                .map(str::trim)
-                .filter(|value| !value.is_empty())
-                .map(|value| value.to_string())
-                .or_else(|| {
-                    account
-                        .workspace_id
-                        .as_deref()
-                        .map(str::trim)
-                        .filter(|value| !value.is_empty())
-                        .map(|value| value.to_string())
-                });
-            if idx == 0 {
-                first_candidate_account_scope = candidate_scope.clone();
-                false
-            } else {
-                candidate_scope != first_candidate_account_scope
-            }
+    let fallback_models = model_for_log
+        .as_deref()
+        .map(|model| {
+            crate::model_fallback_chains::resolve_model_fallback_chain(&storage, &key_id, model)
+        })
+        .unwrap_or_default();
+    let original_body = body;
+    let client_cancel = request
+        .as_ref()
+        .and_then(super::super::client_cancel_token_for_request);
+    // 中文注释：第 0 轮使用请求模型；只有整池候选都因 429/额度耗尽失败时，才按降级链换模型重跑候选。
+    for model_pass in 0..=fallback_models.len() {
+        let has_more_models = model_pass < fallback_models.len();
+        let served_fallback_model = model_pass
+            .checked_sub(1)
+            .and_then(|idx| fallback_models.get(idx))
+            .map(String::as_str);
+        let body = match served_fallback_model {
+            Some(model) => rewrite_body_model(original_body.as_ref(), model)
+                .map(bytes::Bytes::from)
+                .unwrap_or_else(|| original_body.clone()),
+            None => original_body.clone(),
+        };
+        let pass_candidates = if has_more_models {
+            candidates.clone()
         } else {
-            idx > 0
+            std::mem::take(&mut candidates)
         };
+        let mut pool_exhausted_by_quota = true;
+        let has_body_encrypted_content = body_has_encrypted_content_hint(body.as_ref());
+        let mut stripped_body: Option<bytes::Bytes> = None;
 
-        let body_for_attempt = if strip_session_affinity && has_body_encrypted_content {
-            if stripped_body.is_none() {
-                stripped_body = strip_encrypted_content_from_body(body.as_ref())
-                    .map(bytes::Bytes::from)
-                    .or_else(|| Some(body.clone()));
-            }
-            stripped_body
-                .as_ref()
-                .expect("stripped body should be initialized")
+        // For `anthropic_native` with `prompt_cache_key`, keep Session/Conversation affinity within the
+        // same Chatgpt-Account-Id "scope" (chatgpt_account_id preferred, otherwise workspace_id).
+        // Switching scope on failover can increase upstream challenge probability.
+        let mut first_candidate_account_scope: Option<String> = None;
+        // 中文注释：对冲只在原模型轮次对前两个候选生效；对冲请求胜出时把响应留给第 2 个候选复用。
+        let hedge_partner = hedge_delay_ms
+            .filter(|_| model_pass == 0 && !super::super::is_openai_api_base(base))
+            .filter(|_| {
+                pass_candidates
+                    .first()
+                    .is_some_and(|(account, _)| !account.is_provider_account())
+            })
+            .and_then(|_| pass_candidates.get(1).cloned())
+            .filter(|(partner, _)| !partner.is_provider_account());
+        let mut hedge_winner: Option<reqwest::blocking::Response> = None;
+        // 中文注释：中途续流只覆盖 `/v1/responses` 透传流与 chat 适配流，保留候选副本供断流后换号。
+        let stream_failover_active = client_is_stream
+            && super::stream_failover::stream_failover_enabled()
+            && !super::super::is_openai_api_base(base)
+            && match response_adapter {
+                super::super::ResponseAdapter::Passthrough => path.starts_with("/v1/responses"),
+                super::super::ResponseAdapter::OpenAIChatCompletionsSse => true,
+                _ => false,
+            };
+        // 中文注释：服务商只认 Chat Completions，不能接管 Codex 上游的断流续写。
+        let stream_failover_pool = if stream_failover_active {
+            pass_candidates
+                .iter()
+                .filter(|(account, _)| !account.is_provider_account())
+                .cloned()
+                .collect()
         } else {
-            &body
+            Vec::new()
         };
-        context.log_candidate_start(&account.id, idx, strip_session_affinity);
-        if let Some(skip_reason) = context.should_skip_candidate(&account.id, idx) {
-            context.log_candidate_skip(&account.id, idx, skip_reason);
-            let _ = super::super::clear_manual_preferred_account_if(&account.id);
-            continue;
-        }
-
-        let request_ref = request
-            .as_ref()
-            .ok_or_else(|| "request already consumed".to_string())?;
-        let incoming_session_id = incoming_headers.session_id();
-        let incoming_turn_state = incoming_headers.turn_state();
-        let incoming_conversation_id = incoming_headers.conversation_id();
-        super::super::trace_log::log_attempt_profile(
-            trace_id.as_str(),
-            &account.id,
-            idx,
-            candidate_count,
-            strip_session_affinity,
-            incoming_session_id.is_some() || has_sticky_fallback_session,
-            incoming_turn_state.is_some(),
-            incoming_conversation_id.is_some() || has_sticky_fallback_conversation,
-            None,
-            request_shape.as_deref(),
-            body_for_attempt.len(),
-            model_for_log.as_deref(),
-        );
-        // 中文注释：把 inflight 计数覆盖到整个响应生命周期，确保下一批请求能看到真实负载。
-        let mut inflight_guard = Some(super::super::acquire_account_inflight(&account.id));
-        let mut last_attempt_url: Option<String> = None;
-        let mut last_attempt_error: Option<String> = None;
-
-        let decision = process_candidate_upstream_flow(
-            &storage,
-            &method,
-            request_ref,
-            &incoming_headers,
-            body_for_attempt,
-            upstream_is_stream,
-            base,
-            &path,
-            url.as_str(),
-            url_alt.as_deref(),
-            request_deadline,
-            upstream_fallback_base.as_deref(),
-            &account,
-            &mut token,
-            upstream_cookie.as_deref(),
-            strip_session_affinity,
-            debug,
-            allow_openai_fallback,
-            disable_challenge_stateless_retry,
-            context.has_more_candidates(idx),
-            |upstream_url, status_code, error| {
-                last_attempt_url = upstream_url.map(str::to_string);
-                last_attempt_error = error.map(str::to_string);
-                super::super::record_route_quality(&account.id, status_code);
-                context.log_attempt_result(&account.id, upstream_url, status_code, error);
-            },
-        );
-        match decision {
-            CandidateUpstreamDecision::Failover => {
-                let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                super::super::record_gateway_failover_attempt();
-                continue;
+        for (idx, (account, mut token)) in pass_candidates.into_iter().enumerate() {
+            let hedge_prefetched = if idx == 1 { hedge_winner.take() } else { None };
+            if super::deadline::is_expired(request_deadline) {
+                let request = request
+                    .take()
+                    .expect("request should be available before timeout response");
+                return respond_total_timeout(request, &context, trace_id.as_str(), started_at);
             }
-            CandidateUpstreamDecision::Terminal {
-                status_code,
-                message,
-            } => {
-                let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                let elapsed_ms = started_at.elapsed().as_millis();
+            if client_cancel
+                .as_ref()
+                .is_some_and(super::super::ClientCancelToken::is_cancelled)
+            {
+                // 中文注释：客户端已经离开，剩余候选不再尝试，直接记一条取消日志。
                 context.log_final_result(
-                    Some(&account.id),
-                    last_attempt_url.as_deref(),
-                    status_code,
+                    None,
+                    None,
+                    499,
                     RequestLogUsage::default(),
-                    Some(message.as_str()),
-                    elapsed_ms,
+                    Some("client_cancelled"),
+                    started_at.elapsed().as_millis(),
                 );
-                let request = request
-                    .take()
-                    .expect("request should be available before terminal response");
-                return respond_terminal(request, status_code, message, Some(trace_id.as_str()));
+                return Ok(());
             }
-            CandidateUpstreamDecision::RespondUpstream(mut resp) => {
-                let mut status_code = resp.status().as_u16();
-                // If the client is continuing a previous session but the selected upstream account belongs
-                // to another org/workspace, the server can reject the org-scoped encrypted blobs with:
-                // `invalid_encrypted_content`. Attempt a one-shot stateless retry (strip affinity + drop
-                // encrypted_content fields) to salvage the request.
-                if status_code == 400
-                    && !strip_session_affinity
-                    && (incoming_turn_state.is_some() || has_body_encrypted_content)
-                {
-                    let retry_body = if has_body_encrypted_content {
-                        if stripped_body.is_none() {
-                            stripped_body = strip_encrypted_content_from_body(body.as_ref())
-                                .map(bytes::Bytes::from)
-                                .or_else(|| Some(body.clone()));
-                        }
-                        stripped_body
-                            .as_ref()
-                            .expect("stripped body should be initialized")
+            // 中文注释：Claude 兼容入口命中 prompt_cache_key 时，优先保持会话粘性；
+            // failover 时若强制重置 Session/Conversation，更容易触发 upstream challenge。
+            let strip_session_affinity = if anthropic_has_prompt_cache_key {
+                let candidate_scope = candidate_account_scope(&account);
+                if idx == 0 {
+                    first_candidate_account_scope = candidate_scope.clone();
+                    false
+                } else {
+                    candidate_scope != first_candidate_account_scope
+                }
+            } else {
+                idx > 0
+            };
+
+            let body_for_attempt = if strip_session_affinity && has_body_encrypted_content {
+                if stripped_body.is_none() {
+                    stripped_body = strip_encrypted_content_from_body(body.as_ref())
+                        .map(bytes::Bytes::from)
+                        .or_else(|| Some(body.clone()));
+                }
+                stripped_body
+                    .as_ref()
+                    .expect("stripped body should be initialized")
+            } else {
+                &body
+            };
+            context.log_candidate_start(&account.id, idx, strip_session_affinity);
+            let skip_reason = if hedge_prefetched.is_some() {
+                None
+            } else {
+                context.should_skip_candidate(&account.id, idx)
+            };
+            if let Some(skip_reason) = skip_reason {
+                context.log_candidate_skip(&account.id, idx, skip_reason);
+                let _ = super::super::clear_manual_preferred_account_if(&account.id);
+                // 中文注释：只有限流冷却算作额度耗尽；故障冷却、并发占满或模型不可用都说明换模型解决不了问题。
+                if skip_reason != super::candidates::CandidateSkipReason::RateLimitedCooldown {
+                    pool_exhausted_by_quota = false;
+                }
+                continue;
+            }
+            let provider = provider_accounts.get(&account.id);
+            if provider.is_some_and(|provider| {
+                !crate::provider_accounts::provider_serves_model(
+                    &provider.models_json,
+                    context.model_for_log(),
+                )
+            }) {
+                // 中文注释：降级轮次换了模型，服务商未声明支持该模型时跳过。
+                continue;
+            }
+
+            let request_ref = request
+                .as_ref()
+                .ok_or_else(|| "request already consumed".to_string())?;
+            let incoming_session_id = incoming_headers.session_id();
+            let incoming_turn_state = incoming_headers.turn_state();
+            let incoming_conversation_id = incoming_headers.conversation_id();
+            super::super::trace_log::log_attempt_profile(
+                trace_id.as_str(),
+                &account.id,
+                idx,
+                candidate_count,
+                strip_session_affinity,
+                incoming_session_id.is_some() || has_sticky_fallback_session,
+                incoming_turn_state.is_some(),
+                incoming_conversation_id.is_some() || has_sticky_fallback_conversation,
+                None,
+                request_shape.as_deref(),
+                body_for_attempt.len(),
+                context.model_for_log(),
+            );
+            let mut prefetched = hedge_prefetched.map(Ok);
+            if let (0, Some((partner_account, partner_token))) = (idx, hedge_partner.as_ref()) {
+                let primary_token = token.access_token.trim();
+                let partner_token = partner_token.access_token.trim();
+                if !primary_token.is_empty() && !partner_token.is_empty() {
+                    let partner_strip = if anthropic_has_prompt_cache_key {
+                        candidate_account_scope(partner_account) != first_candidate_account_scope
                     } else {
-                        &body
+                        true
                     };
-
-                    let retry_decision = process_candidate_upstream_flow(
-                        &storage,
-                        &method,
-                        request_ref,
-                        &incoming_headers,
-                        retry_body,
-                        upstream_is_stream,
-                        base,
-                        &path,
-                        url.as_str(),
-                        url_alt.as_deref(),
+                    let partner_body = if partner_strip && has_body_encrypted_content {
+                        strip_encrypted_content_from_body(body.as_ref())
+                            .map(bytes::Bytes::from)
+                            .unwrap_or_else(|| body.clone())
+                    } else {
+                        body.clone()
+                    };
+                    let shared = std::sync::Arc::new(super::attempt::SharedUpstreamRequest {
+                        method: method.clone(),
+                        url: url.clone(),
                         request_deadline,
-                        upstream_fallback_base.as_deref(),
-                        &account,
-                        &mut token,
-                        upstream_cookie.as_deref(),
-                        true,
-                        debug,
-                        allow_openai_fallback,
-                        disable_challenge_stateless_retry,
-                        context.has_more_candidates(idx),
-                        |upstream_url, status_code, error| {
-                            last_attempt_url = upstream_url.map(str::to_string);
-                            last_attempt_error = error.map(str::to_string);
-                            super::super::record_route_quality(&account.id, status_code);
-                            context.log_attempt_result(
-                                &account.id,
-                                upstream_url,
-                                status_code,
-                                error,
+                        remote: request_ref.remote_addr().copied(),
+                        incoming_headers: incoming_headers.clone(),
+                        is_stream: upstream_is_stream,
+                        upstream_cookie: upstream_cookie.clone(),
+                    });
+                    let settlement = std::sync::Arc::new(super::hedge::HedgeSettlement::default());
+                    let primary_send = super::hedge::upstream_send(
+                        shared.clone(),
+                        super::attempt::UpstreamAttempt {
+                            account: account.clone(),
+                            auth_token: primary_token.to_string(),
+                            body: body_for_attempt.clone(),
+                            strip_session_affinity,
+                        },
+                        settlement.clone(),
+                    );
+                    let partner_send = super::hedge::upstream_send(
+                        shared,
+                        super::attempt::UpstreamAttempt {
+                            account: partner_account.clone(),
+                            auth_token: partner_token.to_string(),
+                            body: partner_body,
+                            strip_session_affinity: partner_strip,
+                        },
+                        settlement.clone(),
+                    );
+                    let primary_account_id = account.id.clone();
+                    let partner_account_id = partner_account.id.clone();
+                    let delay = super::deadline::cap_wait(
+                        Duration::from_millis(hedge_delay_ms.unwrap_or_default()),
+                        request_deadline,
+                    )
+                    .unwrap_or_default();
+                    let outcome = super::hedge::race_hedged(
+                        delay,
+                        primary_send,
+                        partner_send,
+                        || {
+                            super::attempt::attempt_candidate_available(
+                                &partner_account.id,
+                                account_max_inflight,
+                                context.model_for_log(),
+                            )
+                        },
+                        |resp: &reqwest::blocking::Response| resp.status().is_success(),
+                        |slot, outcome| {
+                            let account_id = match slot {
+                                super::hedge::HedgeSlot::Primary => account.id.as_str(),
+                                super::hedge::HedgeSlot::Hedge => partner_account.id.as_str(),
+                            };
+                            super::super::trace_log::log_hedge_attempt(
+                                trace_id.as_str(),
+                                slot.as_str(),
+                                account_id,
+                                outcome,
                             );
                         },
+                        // 中文注释：输掉或竞速结束后才返回的一方照常记冷却与线路质量。
+                        move |slot, result| {
+                            let account_id = match slot {
+                                super::hedge::HedgeSlot::Primary => primary_account_id.as_str(),
+                                super::hedge::HedgeSlot::Hedge => partner_account_id.as_str(),
+                            };
+                            super::hedge::penalize_discarded_attempt(account_id, &result);
+                        },
                     );
-
-                    match retry_decision {
-                        CandidateUpstreamDecision::RespondUpstream(retry_resp) => {
-                            resp = retry_resp;
-                            status_code = resp.status().as_u16();
+                    settlement.settle();
+                    match outcome {
+                        super::hedge::HedgeRaceOutcome::Primary(result) => {
+                            prefetched = Some(result);
                         }
-                        CandidateUpstreamDecision::Failover => {
-                            let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                            super::super::record_gateway_failover_attempt();
+                        super::hedge::HedgeRaceOutcome::HedgeWon(winner) => {
+                            // 中文注释：主候选输掉竞速后不再重试，直接由对冲候选接管。
+                            hedge_winner = Some(winner);
                             continue;
                         }
-                        CandidateUpstreamDecision::Terminal {
-                            status_code,
-                            message,
-                        } => {
-                            let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                            let elapsed_ms = started_at.elapsed().as_millis();
-                            context.log_final_result(
-                                Some(&account.id),
-                                last_attempt_url.as_deref(),
-                                status_code,
-                                RequestLogUsage::default(),
-                                Some(message.as_str()),
-                                elapsed_ms,
-                            );
-                            let request = request
-                                .take()
-                                .expect("request should be available before terminal response");
-                            return respond_terminal(
-                                request,
-                                status_code,
-                                message,
-                                Some(trace_id.as_str()),
-                            );
-                        }
+                        super::hedge::HedgeRaceOutcome::Abandoned => {}
                     }
                 }
+            }
+            // 中文注释：把 inflight 计数覆盖到整个响应生命周期，确保下一批请求能看到真实负载。
+            let mut inflight_guard = Some(super::super::acquire_account_inflight(&account.id));
+            let mut last_attempt_url: Option<String> = None;
+            let mut last_attempt_error: Option<String> = None;
+            let mut last_attempt_status: u16 = 0;
+            let attempt_started_at = Instant::now();
 
-                if status_code >= 400 {
-                    let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                }
-                let mut final_error: Option<String> = if status_code >= 400 {
-                    last_attempt_error.clone()
-                } else {
-                    None
+            let log_attempt =
+                |upstream_url: Option<&str>, status_code: u16, error: Option<&str>| {
+                    last_attempt_url = upstream_url.map(str::to_string);
+                    last_attempt_error = error.map(str::to_string);
+                    last_attempt_status = status_code;
+                    super::super::record_route_quality(&account.id, status_code);
+                    context.log_attempt_result(&account.id, upstream_url, status_code, error);
                 };
-                let elapsed_ms = started_at.elapsed().as_millis();
-                let request = request
-                    .take()
-                    .expect("request should be available before terminal response");
-                let guard = inflight_guard
-                    .take()
-                    .expect("inflight guard should be available before terminal response");
-                let bridge = super::super::respond_with_upstream(
-                    request,
-                    resp,
-                    guard,
-                    response_adapter,
-                    Some(&tool_name_restore_map),
-                    client_is_stream,
-                    Some(trace_id.as_str()),
-                )?;
-                let bridge_output_text_len = bridge
-                    .usage
-                    .output_text
-                    .as_deref()
-                    .map(str::trim)
-                    .map(str::len)
-                    .unwrap_or(0);
-                super::super::trace_log::log_bridge_result(
-                    trace_id.as_str(),
-                    format!("{response_adapter:?}").as_str(),
-                    path.as_str(),
+            let decision = match provider {
+                Some(provider) => super::provider::process_provider_upstream_flow(
+                    &storage,
+                    provider,
+                    body_for_attempt,
                     client_is_stream,
-                    bridge.stream_terminal_seen,
-                    bridge.stream_terminal_error.as_deref(),
-                    bridge.delivery_error.as_deref(),
-                    bridge_output_text_len,
-                    bridge.usage.output_tokens,
-                );
-                let bridge_ok = bridge.is_ok(client_is_stream);
-                let bridge_error_message = if bridge_ok {
-                    None
-                } else {
-                    Some(
-                        bridge
-                            .error_message(client_is_stream)
-                            .unwrap_or_else(|| "upstream response incomplete".to_string()),
-                    )
-                };
-                if !bridge_ok {
-                    let bridge_error = bridge_error_message
-                        .as_deref()
-                        .unwrap_or("upstream response incomplete");
-                    match final_error.as_deref() {
-                        Some(existing) if existing != bridge_error => {
-                            final_error = Some(format!("{existing}; {bridge_error}"));
-                        }
-                        None => {
-                            final_error = Some(bridge_error.to_string());
-                        }
-                        _ => {}
+                    request_deadline,
+                    context.has_more_candidates(idx),
+                    log_attempt,
+                ),
+                None => process_candidate_upstream_flow(
+                    &storage,
+                    &method,
+                    request_ref,
+                    &incoming_headers,
+                    body_for_attempt,
+                    upstream_is_stream,
+                    base,
+                    &path,
+                    url.as_str(),
+                    url_alt.as_deref(),
+                    request_deadline,
+                    upstream_fallback_base.as_deref(),
+                    &account,
+                    &mut token,
+                    upstream_cookie.as_deref(),
+                    strip_session_affinity,
+                    debug,
+                    allow_openai_fallback,
+                    disable_challenge_stateless_retry,
+                    context.has_more_candidates(idx),
+                    context.model_for_log(),
+                    prefetched,
+                    log_attempt,
+                ),
+            };
+            match decision {
+                CandidateUpstreamDecision::Failover => {
+                    let _ = super::super::clear_manual_preferred_account_if(&account.id);
+                    super::super::record_gateway_failover_attempt();
+                    if last_attempt_status != 429 {
+                        pool_exhausted_by_quota = false;
                     }
+                    continue;
+                }
+                CandidateUpstreamDecision::Terminal {
+                    status_code,
+                    message,
+                } => {
+                    let _ = super::super::clear_manual_preferred_account_if(&account.id);
+                    let elapsed_ms = started_at.elapsed().as_millis();
+                    context.log_final_result(
+                        Some(&account.id),
+                        last_attempt_url.as_deref(),
+                        status_code,
+                        RequestLogUsage::default(),
+                        Some(message.as_str()),
+                        elapsed_ms,
+                    );
+                    let request = request
+                        .take()
+                        .expect("request should be available before terminal response");
+                    return respond_terminal(
+                        request,
+                        status_code,
+                        message,
+                        Some(trace_id.as_str()),
+                    );
                 }
-                if let Some(upstream_hint) = bridge.upstream_error_hint.as_deref() {
-                    match final_error.as_deref() {
-                        Some(existing) if existing.contains(upstream_hint) => {}
-                        Some(existing) => {
-                            final_error =
-                                Some(format!("{existing}; upstream_error={upstream_hint}"));
+                CandidateUpstreamDecision::RespondUpstream(mut resp) => {
+                    let mut status_code = resp.status().as_u16();
+                    if status_code == 429
+                        && has_more_models
+                        && pool_exhausted_by_quota
+                        && !context.has_more_candidates(idx)
+                    {
+                        // 中文注释：最后一个候选也被限流时丢弃该 429，交给降级链换模型重试。
+                        let _ = super::super::clear_manual_preferred_account_if(&account.id);
+                        break;
+                    }
+                    // If the client is continuing a previous session but the selected upstream account belongs
+                    // to another org/workspace, the server can reject the org-scoped encrypted blobs with:
+                    // `invalid_encrypted_content`. Attempt a one-shot stateless retry (strip affinity + drop
+                    // encrypted_content fields) to salvage the request.
+                    if status_code == 400
+                        && provider.is_none()
+                        && !strip_session_affinity
+                        && (incoming_turn_state.is_some() || has_body_encrypted_content)
+                    {
+                        let retry_body = if has_body_encrypted_content {
+                            if stripped_body.is_none() {
+                                stripped_body = strip_encrypted_content_from_body(body.as_ref())
+                                    .map(bytes::Bytes::from)
+                                    .or_else(|| Some(body.clone()));
+                            }
+                            stripped_body
+                                .as_ref()
+                                .expect("stripped body should be initialized")
+                        } else {
+                            &body
+                        };
+
+                        let retry_decision = process_candidate_upstream_flow(
+                            &storage,
+                            &method,
+                            request_ref,
+                            &incoming_headers,
+                            retry_body,
+                            upstream_is_stream,
+                            base,
+                            &path,
+                            url.as_str(),
+                            url_alt.as_deref(),
+                            request_deadline,
+                            upstream_fallback_base.as_deref(),
+                            &account,
+                            &mut token,
+                            upstream_cookie.as_deref(),
+                            true,
+                            debug,
+                            allow_openai_fallback,
+                            disable_challenge_stateless_retry,
+                            context.has_more_candidates(idx),
+                            context.model_for_log(),
+                            None,
+                            |upstream_url, status_code, error| {
+                                last_attempt_url = upstream_url.map(str::to_string);
+                                last_attempt_error = error.map(str::to_string);
+                                last_attempt_status = status_code;
+                                super::super::record_route_quality(&account.id, status_code);
+                                context.log_attempt_result(
+                                    &account.id,
+                                    upstream_url,
+                                    status_code,
+                                    error,
+                                );
+                            },
+                        );
+
+                        match retry_decision {
+                            CandidateUpstreamDecision::RespondUpstream(retry_resp) => {
+                                resp = retry_resp;
+                                status_code = resp.status().as_u16();
+                            }
+                            CandidateUpstreamDecision::Failover => {
+                                let _ =
+                                    super::super::clear_manual_preferred_account_if(&account.id);
+                                super::super::record_gateway_failover_attempt();
+                                if last_attempt_status != 429 {
+                                    pool_exhausted_by_quota = false;
+                                }
+                                continue;
+                            }
+                            CandidateUpstreamDecision::Terminal {
+                                status_code,
+                                message,
+                            } => {
+                                let _ =
+                                    super::super::clear_manual_preferred_account_if(&account.id);
+                                let elapsed_ms = started_at.elapsed().as_millis();
+                                context.log_final_result(
+                                    Some(&account.id),
+                                    last_attempt_url.as_deref(),
+                                    status_code,
+                                    RequestLogUsage::default(),
+                                    Some(message.as_str()),
+                                    elapsed_ms,
+                                );
+                                let request = request
+                                    .take()
+                                    .expect("request should be available before terminal response");
+                                return respond_terminal(
+                                    request,
+                                    status_code,
+                                    message,
+                                    Some(trace_id.as_str()),
+                                );
+                            }
+                        }
+                    }
+
+                    if status_code >= 400 {
+                        let _ = super::super::clear_manual_preferred_account_if(&account.id);
+                    }
+                    let mut final_error: Option<String> = if status_code >= 400 {
+                        last_attempt_error.clone()
+                    } else {
+                        None
+                    };
+                    if let Some(model) = served_fallback_model {
+                        insert_served_model_header(&mut resp, model);
+                    }
+                    let stream_failover =
+                        (stream_failover_active && provider.is_none()).then(|| {
+                            let shared =
+                                std::sync::Arc::new(super::attempt::SharedUpstreamRequest {
+                                    method: method.clone(),
+                                    url: url.clone(),
+                                    request_deadline,
+                                    remote: request_ref.remote_addr().copied(),
+                                    incoming_headers: incoming_headers.clone(),
+                                    is_stream: upstream_is_stream,
+                                    upstream_cookie: upstream_cookie.clone(),
+                                });
+                            let attempts = stream_failover_pool
+                                .iter()
+                                .skip(idx + 1)
+                                .filter(|(_, candidate_token)| {
+                                    !candidate_token.access_token.trim().is_empty()
+                                })
+                                .map(|(candidate, candidate_token)| {
+                                    let candidate_strip = !anthropic_has_prompt_cache_key
+                                        || candidate_account_scope(candidate)
+                                            != first_candidate_account_scope;
+                                    let candidate_body =
+                                        if candidate_strip && has_body_encrypted_content {
+                                            strip_encrypted_content_from_body(body.as_ref())
+                                                .map(bytes::Bytes::from)
+                                                .unwrap_or_else(|| body.clone())
+                                        } else {
+                                            body.clone()
+                                        };
+                                    super::attempt::UpstreamAttempt {
+                                        account: candidate.clone(),
+                                        auth_token: candidate_token.access_token.trim().to_string(),
+                                        body: candidate_body,
+                                        strip_session_affinity: candidate_strip,
+                                    }
+                                })
+                                .collect();
+                            super::stream_failover::StreamFailover::new(
+                                trace_id.as_str(),
+                                &account.id,
+                                shared,
+                                attempts,
+                                account_max_inflight,
+                                context.model_for_log(),
+                            )
+                        });
+                    let stream_failover_report = stream_failover
+                        .as_ref()
+                        .map(super::stream_failover::StreamFailover::report_handle);
+                    let elapsed_ms = started_at.elapsed().as_millis();
+                    let request = request
+                        .take()
+                        .expect("request should be available before terminal response");
+                    let guard = inflight_guard
+                        .take()
+                        .expect("inflight guard should be available before terminal response");
+                    let bridge = super::super::respond_with_upstream(
+                        request,
+                        resp,
+                        guard,
+                        response_adapter,
+                        Some(&tool_name_restore_map),
+                        client_is_stream,
+                        Some(trace_id.as_str()),
+                        stream_failover,
+                    )?;
+                    let failover_report = stream_failover_report
+                        .map(|report| {
+                            crate::lock_utils::lock_recover(&report, "stream_failover_report")
+                                .clone()
+                        })
+                        .unwrap_or_default();
+                    // 中文注释：续流成功时由最后接管的账号承担日志、用量与亲和绑定。
+                    let served_account_id = failover_report
+                        .served_account_id
+                        .clone()
+                        .unwrap_or_else(|| account.id.clone());
+                    let bridge_output_text_len = bridge
+                        .usage
+                        .output_text
+                        .as_deref()
+                        .map(str::trim)
+                        .map(str::len)
+                        .unwrap_or(0);
+                    super::super::trace_log::log_bridge_result(
+                        trace_id.as_str(),
+                        format!("{response_adapter:?}").as_str(),
+                        path.as_str(),
+                        client_is_stream,
+                        bridge.stream_terminal_seen,
+                        bridge.stream_terminal_error.as_deref(),
+                        bridge.delivery_error.as_deref(),
+                        bridge_output_text_len,
+                        bridge.usage.output_tokens,
+                    );
+                    let bridge_ok = bridge.is_ok(client_is_stream);
+                    let bridge_error_message = if bridge_ok {
+                        None
+                    } else {
+                        Some(
+                            bridge
+                                .error_message(client_is_stream)
+                                .unwrap_or_else(|| "upstream response incomplete".to_string()),
+                        )
+                    };
+                    if !bridge_ok {
+                        let bridge_error = bridge_error_message
+                            .as_deref()
+                            .unwrap_or("upstream response incomplete");
+                        match final_error.as_deref() {
+                            Some(existing) if existing != bridge_error => {
+                                final_error = Some(format!("{existing}; {bridge_error}"));
+                            }
+                            None => {
+                                final_error = Some(bridge_error.to_string());
+                            }
+                            _ => {}
                         }
-                        None => {
-                            final_error = Some(format!("upstream_error={upstream_hint}"));
+                    }
+                    if let Some(resets_at) = bridge.usage_limit_resets_at {
+                        crate::usage_passive::observe_usage_limit_reset(
+                            &storage,
+                            &served_account_id,
+                            resets_at,
+                        );
+                    }
+                    if let Some(upstream_hint) = bridge.upstream_error_hint.as_deref() {
+                        match final_error.as_deref() {
+                            Some(existing) if existing.contains(upstream_hint) => {}
+                            Some(existing) => {
+                                final_error =
+                                    Some(format!("{existing}; upstream_error={upstream_hint}"));
+                            }
+                            None => {
+                                final_error = Some(format!("upstream_error={upstream_hint}"));
+                            }
                         }
                     }
-                }
 
-                // 中文注释：流式响应可能以 200 开始，但在未收到终止事件时提前断流（上游 5xx/网络抖动）。
-                // 这种情况对客户端等同失败，日志里也应标记为 5xx（或 499 客户端断开）。
-                let upstream_stream_failed = client_is_stream
-                    && (!bridge.stream_terminal_seen || bridge.stream_terminal_error.is_some());
-                let client_delivery_failed = bridge
-                    .delivery_error
-                    .as_deref()
-                    .is_some_and(is_client_disconnect_error);
-                let status_for_log = if status_code >= 400 {
-                    status_code
-                } else if upstream_stream_failed {
-                    502
-                } else if bridge_ok {
-                    status_code
-                } else if client_delivery_failed {
-                    499
-                } else {
-                    502
-                };
+                    // 中文注释：流式响应可能以 200 开始，但在未收到终止事件时提前断流（上游 5xx/网络抖动）。
+                    // 这种情况对客户端等同失败，日志里也应标记为 5xx（或 499 客户端断开）。
+                    // 客户端中途离开导致的断流不算上游故障，不冷却账号，日志记为 499 client_cancelled。
+                    let upstream_stream_failed = client_is_stream
+                        && !bridge.client_cancelled
+                        && (!bridge.stream_terminal_seen || bridge.stream_terminal_error.is_some());
+                    let client_delivery_failed = bridge.client_cancelled
+                        || bridge
+                            .delivery_error
+                            .as_deref()
+                            .is_some_and(is_client_disconnect_error);
+                    let status_for_log = if status_code >= 400 {
+                        status_code
+                    } else if upstream_stream_failed {
+                        502
+                    } else if bridge_ok {
+                        status_code
+                    } else if client_delivery_failed {
+                        499
+                    } else {
+                        502
+                    };
 
-                if upstream_stream_failed {
-                    // 下次请求尽量避开该账号，避免连续断流造成体验很差。
-                    let _ = super::super::clear_manual_preferred_account_if(&account.id);
-                    super::super::mark_account_cooldown(
-                        &account.id,
-                        super::super::CooldownReason::Network,
-                    );
-                    super::super::record_route_quality(&account.id, 502);
-                }
+                    let mut stream_failed_accounts = failover_report.abandoned_account_ids;
+                    if upstream_stream_failed
+                        && !stream_failed_accounts.contains(&served_account_id)
+                    {
+                        stream_failed_accounts.push(served_account_id.clone());
+                    }
+                    for failed_account_id in &stream_failed_accounts {
+                        // 下次请求尽量避开该账号，避免连续断流造成体验很差。
+                        let _ = super::super::clear_manual_preferred_account_if(failed_account_id);
+                        super::super::mark_account_cooldown(
+                            failed_account_id,
+                            super::super::CooldownReason::Network,
+                        );
+                        super::super::record_route_quality(failed_account_id, 502);
+                    }
 
-                let usage = bridge.usage;
-                context.log_final_result(
-                    Some(&account.id),
-                    last_attempt_url.as_deref(),
-                    status_for_log,
-                    RequestLogUsage {
+                    let usage = bridge.usage;
+                    let mut log_usage = RequestLogUsage {
                         input_tokens: usage.input_tokens,
                         cached_input_tokens: usage.cached_input_tokens,
                         output_tokens: usage.output_tokens,
                         total_tokens: usage.total_tokens,
                         reasoning_output_tokens: usage.reasoning_output_tokens,
-                    },
-                    final_error.as_deref(),
-                    elapsed_ms,
-                );
-                return Ok(());
+                    };
+                    if status_for_log < 400 {
+                        if let Some(model) = context.model_for_log() {
+                            super::super::record_model_served(model);
+                        }
+                        // 中文注释：总耗时包含完整响应体传输，只统计完整送达的成功请求。
+                        let proxy = super::super::upstream_proxy_for_account(&served_account_id);
+                        super::super::record_route_latency(
+                            &served_account_id,
+                            proxy.as_deref(),
+                            super::super::RouteLatencyKind::Total,
+                            super::super::duration_to_millis(attempt_started_at.elapsed()),
+                        );
+                        // 中文注释：服务商只是溢出层，不绑定会话亲和，下次仍优先回到 OAuth 账号。
+                        if let Some(affinity_key) =
+                            affinity_key.as_deref().filter(|_| provider.is_none())
+                        {
+                            if super::super::route_affinity::bind_route_affinity(
+                                affinity_key,
+                                &served_account_id,
+                            ) {
+                                super::super::trace_log::log_route_affinity(
+                                    trace_id.as_str(),
+                                    affinity_key,
+                                    "bound",
+                                    &served_account_id,
+                                    None,
+                                );
+                            }
+                        }
+                    }
+                    if status_for_log < 400 || bridge.client_cancelled {
+                        // 中文注释：客户端取消时按已送达的部分输出估算用量。
+                        log_usage = super::super::request_log::fill_missing_usage_from_estimate(
+                            log_usage,
+                            body.as_ref(),
+                            usage.output_text.as_deref(),
+                        );
+                    }
+                    context.log_final_result(
+                        Some(&served_account_id),
+                        last_attempt_url.as_deref(),
+                        status_for_log,
+                        log_usage,
+                        final_error.as_deref(),
+                        elapsed_ms,
+                    );
+                    return Ok(());
+                }
             }
         }
+        if !pool_exhausted_by_quota || !has_more_models {
+            break;
+        }
+        let next_model = fallback_models[model_pass].as_str();
+        super::super::trace_log::log_model_fallback(
+            trace_id.as_str(),
+            context.model_for_log().unwrap_or("-"),
+            next_model,
+            "pool_exhausted",
+        );
+        context.switch_to_fallback_model(next_model);
     }
 
     context.log_final_result(
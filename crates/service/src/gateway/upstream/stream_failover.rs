@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::super::AccountInFlightGuard;
+use super::attempt::{SharedUpstreamRequest, UpstreamAttempt};
+
+const STREAM_FAILOVER_ENABLED_ENV: &str = "CODEXMANAGER_STREAM_FAILOVER_ENABLED";
+const STREAM_FAILOVER_MAX_ATTEMPTS_ENV: &str = "CODEXMANAGER_STREAM_FAILOVER_MAX_ATTEMPTS";
+const DEFAULT_STREAM_FAILOVER_ENABLED: bool = false;
+const DEFAULT_STREAM_FAILOVER_MAX_ATTEMPTS: usize = 1;
+
+static STREAM_FAILOVER_ENABLED: AtomicBool = AtomicBool::new(DEFAULT_STREAM_FAILOVER_ENABLED);
+static STREAM_FAILOVER_MAX_ATTEMPTS: AtomicUsize =
+    AtomicUsize::new(DEFAULT_STREAM_FAILOVER_MAX_ATTEMPTS);
+
+pub(crate) fn stream_failover_enabled() -> bool {
+    STREAM_FAILOVER_ENABLED.load(Ordering::Relaxed)
+}
+
+/// 续流拿到的新上游响应；该账号的并发计数随响应一起交给桥接层，直到流结束才释放。
+pub(crate) struct StreamResume {
+    pub(crate) response: reqwest::blocking::Response,
+    pub(crate) inflight_guard: AccountInFlightGuard,
+}
+
+/// 桥接层回报给 proxy 的续流结果：中途断流的账号，以及最后一次续流接管输出的账号。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamFailoverReport {
+    pub(crate) abandoned_account_ids: Vec<String>,
+    pub(crate) served_account_id: Option<String>,
+}
+
+/// 流式响应的中途续流器：上游在终止事件前断开、且客户端还没收到任何输出时，
+/// 按原候选顺序把同一请求重新发给剩余账号，由桥接层把新流接到同一个下游连接上。
+pub(crate) struct StreamFailover {
+    trace_id: String,
+    current_account_id: String,
+    shared: Arc<SharedUpstreamRequest>,
+    candidates: VecDeque<UpstreamAttempt>,
+    remaining_attempts: usize,
+    account_max_inflight: usize,
+    model: Option<String>,
+    report: Arc<Mutex<StreamFailoverReport>>,
+}
+
+impl StreamFailover {
+    pub(super) fn new(
+        trace_id: &str,
+        current_account_id: &str,
+        shared: Arc<SharedUpstreamRequest>,
+        candidates: Vec<UpstreamAttempt>,
+        account_max_inflight: usize,
+        model: Option<&str>,
+    ) -> Self {
+        Self {
+            trace_id: trace_id.to_string(),
+            current_account_id: current_account_id.to_string(),
+            shared,
+            candidates: candidates.into(),
+            remaining_attempts: STREAM_FAILOVER_MAX_ATTEMPTS.load(Ordering::Relaxed),
+            account_max_inflight,
+            model: model.map(str::to_string),
+            report: Arc::new(Mutex::new(StreamFailoverReport::default())),
+        }
+    }
+
+    pub(super) fn report_handle(&self) -> Arc<Mutex<StreamFailoverReport>> {
+        Arc::clone(&self.report)
+    }
+
+    fn abandon_current(&mut self) {
+        let mut report = crate::lock_utils::lock_recover(&self.report, "stream_failover_report");
+        report
+            .abandoned_account_ids
+            .push(self.current_account_id.clone());
+    }
+
+    /// 客户端尚未收到任何输出时调用：依次尝试剩余候选，返回第一个成功建立的 SSE 流。
+    pub(crate) fn resume(&mut self, reason: &str) -> Option<StreamResume> {
+        self.abandon_current();
+        let from_account_id = self.current_account_id.clone();
+        while self.remaining_attempts > 0 {
+            if super::deadline::is_expired(self.shared.request_deadline) {
+                break;
+            }
+            let Some(attempt) = self.candidates.pop_front() else {
+                break;
+            };
+            if !super::attempt::attempt_candidate_available(
+                &attempt.account.id,
+                self.account_max_inflight,
+                self.model.as_deref(),
+            ) {
+                continue;
+            }
+            self.remaining_attempts -= 1;
+            super::super::record_gateway_failover_attempt();
+            let inflight_guard = super::super::acquire_account_inflight(&attempt.account.id);
+            let sent = super::attempt::send_upstream_attempt(&self.shared, &attempt);
+            let status_code = match &sent {
+                Ok(resp) if resp.status().is_success() && is_event_stream(resp) => {
+                    super::super::record_route_quality(&attempt.account.id, 200);
+                    super::super::trace_log::log_stream_failover(
+                        self.trace_id.as_str(),
+                        from_account_id.as_str(),
+                        Some(attempt.account.id.as_str()),
+                        "resumed",
+                        reason,
+                    );
+                    self.current_account_id = attempt.account.id.clone();
+                    crate::lock_utils::lock_recover(&self.report, "stream_failover_report")
+                        .served_account_id = Some(attempt.account.id.clone());
+                    return sent.ok().map(|response| StreamResume {
+                        response,
+                        inflight_guard,
+                    });
+                }
+                Ok(resp) => resp.status().as_u16(),
+                Err(_) => 502,
+            };
+            super::super::record_route_quality(&attempt.account.id, status_code);
+            super::super::trace_log::log_stream_failover(
+                self.trace_id.as_str(),
+                from_account_id.as_str(),
+                Some(attempt.account.id.as_str()),
+                "send_failed",
+                reason,
+            );
+        }
+        super::super::trace_log::log_stream_failover(
+            self.trace_id.as_str(),
+            from_account_id.as_str(),
+            None,
+            "unavailable",
+            reason,
+        );
+        None
+    }
+
+    /// 客户端已收到部分输出、无法无缝续流时调用：只记录日志，由桥接层补发失败终止事件。
+    pub(crate) fn give_up(&mut self, reason: &str) {
+        self.abandon_current();
+        super::super::trace_log::log_stream_failover(
+            self.trace_id.as_str(),
+            self.current_account_id.as_str(),
+            None,
+            "output_started",
+            reason,
+        );
+    }
+}
+
+fn is_event_stream(resp: &reqwest::blocking::Response) -> bool {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.to_ascii_lowercase().starts_with("text/event-stream"))
+}
+
+pub(in super::super) fn reload_from_env() {
+    STREAM_FAILOVER_ENABLED.store(
+        env_bool_or(STREAM_FAILOVER_ENABLED_ENV, DEFAULT_STREAM_FAILOVER_ENABLED),
+        Ordering::Relaxed,
+    );
+    STREAM_FAILOVER_MAX_ATTEMPTS.store(
+        std::env::var(STREAM_FAILOVER_MAX_ATTEMPTS_ENV)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_STREAM_FAILOVER_MAX_ATTEMPTS),
+        Ordering::Relaxed,
+    );
+}
+
+fn env_bool_or(name: &str, default: bool) -> bool {
+    let Ok(raw) = std::env::var(name) else {
+        return default;
+    };
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => true,
+        "0" | "false" | "no" | "off" => false,
+        _ => default,
+    }
+}
@@ -0,0 +1,162 @@
+use super::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+type Discarded = Arc<Mutex<Vec<(HedgeSlot, Result<u16, &'static str>)>>>;
+
+fn delayed(
+    ms: u64,
+    result: Result<u16, &'static str>,
+) -> impl FnOnce() -> Result<u16, &'static str> {
+    move || {
+        thread::sleep(Duration::from_millis(ms));
+        result
+    }
+}
+
+fn run_race(
+    delay_ms: u64,
+    primary: impl FnOnce() -> Result<u16, &'static str> + Send + 'static,
+    hedge: impl FnOnce() -> Result<u16, &'static str> + Send + 'static,
+    can_start_hedge: bool,
+) -> (
+    HedgeRaceOutcome<u16, &'static str>,
+    Vec<(HedgeSlot, &'static str)>,
+    Discarded,
+) {
+    let mut events = Vec::new();
+    let discarded: Discarded = Arc::default();
+    let sink = discarded.clone();
+    let outcome = race_hedged(
+        Duration::from_millis(delay_ms),
+        primary,
+        hedge,
+        || can_start_hedge,
+        |status: &u16| (200..300).contains(status),
+        |slot, event| events.push((slot, event)),
+        move |slot, result| sink.lock().expect("discarded lock").push((slot, result)),
+    );
+    (outcome, events, discarded)
+}
+
+fn discarded_after(
+    discarded: &Discarded,
+    wait_ms: u64,
+) -> Vec<(HedgeSlot, Result<u16, &'static str>)> {
+    thread::sleep(Duration::from_millis(wait_ms));
+    discarded.lock().expect("discarded lock").clone()
+}
+
+#[test]
+fn fast_primary_never_starts_hedge() {
+    let hedge_started = Arc::new(AtomicBool::new(false));
+    let flag = hedge_started.clone();
+    let (outcome, events, discarded) = run_race(
+        200,
+        delayed(0, Ok(200)),
+        move || {
+            flag.store(true, Ordering::SeqCst);
+            Ok(200)
+        },
+        true,
+    );
+    assert!(matches!(outcome, HedgeRaceOutcome::Primary(Ok(200))));
+    assert!(events.is_empty());
+    assert!(!hedge_started.load(Ordering::SeqCst));
+    assert!(discarded_after(&discarded, 0).is_empty());
+}
+
+#[test]
+fn slow_primary_loses_to_hedge() {
+    let (outcome, events, _) = run_race(20, delayed(500, Ok(200)), delayed(0, Ok(201)), true);
+    assert!(matches!(outcome, HedgeRaceOutcome::HedgeWon(201)));
+    assert_eq!(
+        events,
+        vec![
+            (HedgeSlot::Hedge, "started"),
+            (HedgeSlot::Hedge, "won"),
+            (HedgeSlot::Primary, "abandoned"),
+        ]
+    );
+}
+
+#[test]
+fn hedge_win_hands_failed_primary_to_discard_handler() {
+    let (outcome, events, discarded) =
+        run_race(10, delayed(30, Ok(429)), delayed(100, Ok(200)), true);
+    assert!(matches!(outcome, HedgeRaceOutcome::HedgeWon(200)));
+    assert_eq!(
+        events,
+        vec![
+            (HedgeSlot::Hedge, "started"),
+            (HedgeSlot::Hedge, "won"),
+            (HedgeSlot::Primary, "discarded"),
+        ]
+    );
+    assert_eq!(
+        discarded_after(&discarded, 0),
+        vec![(HedgeSlot::Primary, Ok(429))]
+    );
+}
+
+#[test]
+fn primary_failing_after_hedge_won_reaches_discard_handler() {
+    let (outcome, _, discarded) = run_race(10, delayed(150, Ok(503)), delayed(0, Ok(200)), true);
+    assert!(matches!(outcome, HedgeRaceOutcome::HedgeWon(200)));
+    assert_eq!(
+        discarded_after(&discarded, 400),
+        vec![(HedgeSlot::Primary, Ok(503))]
+    );
+}
+
+#[test]
+fn unavailable_hedge_waits_for_primary() {
+    let (outcome, events, _) = run_race(10, delayed(50, Ok(200)), delayed(0, Ok(201)), false);
+    assert!(matches!(outcome, HedgeRaceOutcome::Primary(Ok(200))));
+    assert_eq!(events, vec![(HedgeSlot::Hedge, "unavailable")]);
+}
+
+#[test]
+fn unsuccessful_hedge_does_not_win_the_race() {
+    let (outcome, events, discarded) =
+        run_race(10, delayed(100, Ok(200)), delayed(0, Ok(429)), true);
+    assert!(matches!(outcome, HedgeRaceOutcome::Primary(Ok(200))));
+    assert_eq!(
+        events,
+        vec![
+            (HedgeSlot::Hedge, "started"),
+            (HedgeSlot::Hedge, "discarded"),
+            (HedgeSlot::Primary, "won"),
+        ]
+    );
+    assert_eq!(
+        discarded_after(&discarded, 0),
+        vec![(HedgeSlot::Hedge, Ok(429))]
+    );
+}
+
+#[test]
+fn both_failures_fall_back_to_primary_result() {
+    let (outcome, _, discarded) =
+        run_race(10, delayed(50, Err("primary")), delayed(0, Ok(503)), true);
+    assert!(matches!(outcome, HedgeRaceOutcome::Primary(Err("primary"))));
+    assert_eq!(
+        discarded_after(&discarded, 0),
+        vec![(HedgeSlot::Hedge, Ok(503))]
+    );
+}
+
+#[test]
+fn settle_returns_inflight_slot_of_pending_sender() {
+    let account_id = "acc_hedge_settlement";
+    let settlement = HedgeSettlement::default();
+    settlement.park(account_id);
+    assert_eq!(super::super::super::account_inflight_count(account_id), 1);
+
+    settlement.settle();
+    assert_eq!(super::super::super::account_inflight_count(account_id), 0);
+
+    // 中文注释：竞速结束后才开始的发送不再占用并发计数，之后的归还也不会重复扣减。
+    settlement.park(account_id);
+    settlement.release(account_id);
+    assert_eq!(super::super::super::account_inflight_count(account_id), 0);
+}
@@ -10,6 +10,7 @@ fn status_404_with_more_candidates_triggers_failover() {
         "acc-404",
         reqwest::StatusCode::NOT_FOUND,
         None,
+        None,
         "https://chatgpt.com/backend-api/codex/chat/completions",
         true,
         |_, _, _| {},
@@ -26,6 +27,7 @@ fn status_404_on_last_candidate_keeps_upstream_response() {
         "acc-404",
         reqwest::StatusCode::NOT_FOUND,
         None,
+        None,
         "https://chatgpt.com/backend-api/codex/chat/completions",
         false,
         |_, _, _| {},
@@ -42,6 +44,7 @@ fn status_429_with_more_candidates_triggers_failover() {
         "acc-429",
         reqwest::StatusCode::TOO_MANY_REQUESTS,
         None,
+        None,
         "https://api.openai.com/v1/responses",
         true,
         |_, _, _| {},
@@ -58,6 +61,7 @@ fn status_429_on_last_candidate_keeps_upstream_response() {
         "acc-429",
         reqwest::StatusCode::TOO_MANY_REQUESTS,
         None,
+        None,
         "https://api.openai.com/v1/responses",
         false,
         |_, _, _| {},
@@ -75,6 +79,7 @@ fn challenge_with_more_candidates_triggers_failover() {
         "acc-challenge",
         reqwest::StatusCode::FORBIDDEN,
         Some(&content_type),
+        None,
         "https://chatgpt.com/backend-api/codex/responses",
         true,
         |_, _, _| {},
@@ -92,6 +97,7 @@ fn challenge_on_last_candidate_returns_terminal_502() {
         "acc-challenge",
         reqwest::StatusCode::FORBIDDEN,
         Some(&content_type),
+        None,
         "https://chatgpt.com/backend-api/codex/responses",
         false,
         |_, _, _| {},
@@ -0,0 +1,111 @@
+use super::*;
+use codexmanager_core::storage::PROVIDER_ACCOUNT_ISSUER;
+
+fn candidate(id: &str, issuer: &str) -> (Account, Token) {
+    (
+        Account {
+            id: id.to_string(),
+            label: id.to_string(),
+            issuer: issuer.to_string(),
+            chatgpt_account_id: None,
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: 0,
+            updated_at: 0,
+        },
+        Token {
+            account_id: id.to_string(),
+            id_token: String::new(),
+            access_token: format!("token-{id}"),
+            refresh_token: String::new(),
+            api_key_access_token: None,
+            last_refresh: 0,
+        },
+    )
+}
+
+fn candidate_ids(candidates: &[(Account, Token)]) -> Vec<&str> {
+    candidates
+        .iter()
+        .map(|(account, _)| account.id.as_str())
+        .collect()
+}
+
+#[test]
+fn move_providers_last_keeps_relative_order() {
+    let mut candidates = vec![
+        candidate("prov_a", PROVIDER_ACCOUNT_ISSUER),
+        candidate("acc-1", "https://auth.openai.com"),
+        candidate("prov_b", PROVIDER_ACCOUNT_ISSUER),
+        candidate("acc-2", "https://auth.openai.com"),
+    ];
+    move_providers_last(&mut candidates);
+    assert_eq!(
+        candidate_ids(&candidates),
+        vec!["acc-1", "acc-2", "prov_a", "prov_b"]
+    );
+}
+
+#[test]
+fn retain_eligible_providers_filters_by_adapter_and_model() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    for (id, models_json) in [("prov_any", "[]"), ("prov_ds", r#"["deepseek-chat"]"#)] {
+        storage
+            .upsert_provider_account(&ProviderAccount {
+                id: id.to_string(),
+                label: id.to_string(),
+                base_url: "http://127.0.0.1:8080".to_string(),
+                api_key: "sk-test".to_string(),
+                models_json: models_json.to_string(),
+                group_name: None,
+                sort: 0,
+                status: "active".to_string(),
+                created_at: 0,
+                updated_at: 0,
+            })
+            .expect("upsert provider");
+    }
+    let pool = || {
+        vec![
+            candidate("acc-1", "https://auth.openai.com"),
+            candidate("prov_any", PROVIDER_ACCOUNT_ISSUER),
+            candidate("prov_ds", PROVIDER_ACCOUNT_ISSUER),
+            candidate("prov_deleted", PROVIDER_ACCOUNT_ISSUER),
+        ]
+    };
+
+    let mut candidates = pool();
+    let providers = retain_eligible_providers(
+        &storage,
+        &mut candidates,
+        super::super::super::ResponseAdapter::OpenAIChatCompletionsSse,
+        Some("gpt-5.4"),
+    );
+    assert_eq!(candidate_ids(&candidates), vec!["acc-1", "prov_any"]);
+    assert!(providers.contains_key("prov_any"));
+
+    let mut candidates = pool();
+    retain_eligible_providers(
+        &storage,
+        &mut candidates,
+        super::super::super::ResponseAdapter::OpenAIChatCompletionsJson,
+        Some("DeepSeek-Chat"),
+    );
+    assert_eq!(
+        candidate_ids(&candidates),
+        vec!["acc-1", "prov_any", "prov_ds"]
+    );
+
+    let mut candidates = pool();
+    let providers = retain_eligible_providers(
+        &storage,
+        &mut candidates,
+        super::super::super::ResponseAdapter::Passthrough,
+        Some("gpt-5.4"),
+    );
+    assert_eq!(candidate_ids(&candidates), vec!["acc-1"]);
+    assert!(providers.is_empty());
+}
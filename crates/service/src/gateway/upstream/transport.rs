@@ -1,5 +1,6 @@
 use bytes::Bytes;
 use codexmanager_core::storage::Account;
+use std::net::SocketAddr;
 use std::time::Instant;
 use tiny_http::Request;
 
@@ -21,7 +22,7 @@ fn force_connection_close(headers: &mut Vec<(String, String)>) {
     }
 }
 
-fn extract_prompt_cache_key(body: &[u8]) -> Option<String> {
+pub(in super::super) fn extract_prompt_cache_key(body: &[u8]) -> Option<String> {
     if body.is_empty() || body.len() > 64 * 1024 {
         return None;
     }
@@ -53,6 +54,38 @@ pub(super) fn send_upstream_request(
     auth_token: &str,
     account: &Account,
     strip_session_affinity: bool,
+) -> Result<reqwest::blocking::Response, reqwest::Error> {
+    send_upstream_request_from(
+        client,
+        method,
+        target_url,
+        request_deadline,
+        request.remote_addr().copied(),
+        incoming_headers,
+        body,
+        is_stream,
+        upstream_cookie,
+        auth_token,
+        account,
+        strip_session_affinity,
+    )
+}
+
+/// 与 `send_upstream_request` 相同，但只需要客户端地址，可在不持有 tiny_http 请求的线程里调用（对冲请求）。
+#[allow(clippy::too_many_arguments)]
+pub(super) fn send_upstream_request_from(
+    client: &reqwest::blocking::Client,
+    method: &reqwest::Method,
+    target_url: &str,
+    request_deadline: Option<Instant>,
+    remote: Option<SocketAddr>,
+    incoming_headers: &super::super::IncomingHeaderSnapshot,
+    body: &Bytes,
+    is_stream: bool,
+    upstream_cookie: Option<&str>,
+    auth_token: &str,
+    account: &Account,
+    strip_session_affinity: bool,
 ) -> Result<reqwest::blocking::Response, reqwest::Error> {
     let attempt_started_at = Instant::now();
     let compact_headers_mode = should_compact_upstream_headers();
@@ -69,11 +102,10 @@ pub(super) fn send_upstream_request(
         incoming_session_id = None;
         incoming_conversation_id = None;
     }
-    let remote = request.remote_addr();
     let mut derived_session_id = if !strip_session_affinity && incoming_session_id.is_none() {
         super::header_profile::derive_sticky_session_id_from_headers_with_remote(
             incoming_headers,
-            remote.copied(),
+            remote,
         )
     } else {
         None
@@ -82,7 +114,7 @@ pub(super) fn send_upstream_request(
         if !strip_session_affinity && incoming_conversation_id.is_none() {
             super::header_profile::derive_sticky_conversation_id_from_headers_with_remote(
                 incoming_headers,
-                remote.copied(),
+                remote,
             )
         } else {
             None
@@ -152,5 +184,15 @@ pub(super) fn send_upstream_request(
     };
     let duration_ms = super::super::duration_to_millis(attempt_started_at.elapsed());
     super::super::metrics::record_gateway_upstream_attempt(duration_ms, result.is_err());
+    if result.as_ref().is_ok_and(|resp| resp.status().is_success()) {
+        // 中文注释：send 返回即拿到响应头，这段耗时就是首字节延迟；错误响应另由状态码计分。
+        let proxy = super::super::upstream_proxy_for_account(account.id.as_str());
+        super::super::record_route_latency(
+            account.id.as_str(),
+            proxy.as_deref(),
+            super::super::RouteLatencyKind::FirstByte,
+            duration_ms,
+        );
+    }
     result
 }
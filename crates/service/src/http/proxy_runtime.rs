@@ -1,10 +1,12 @@
 use axum::body::{to_bytes, Body};
 use axum::extract::State;
-use axum::http::{header, Request as HttpRequest, Response, StatusCode};
+use axum::http::{header, HeaderName, HeaderValue, Request as HttpRequest, Response, StatusCode};
 use axum::routing::any;
 use axum::Router;
+use futures_util::StreamExt;
 use reqwest::Client;
 use std::io;
+use std::task::Poll;
 
 use crate::http::proxy_bridge::run_proxy_server;
 use crate::http::proxy_request::{build_target_url, filter_request_headers};
@@ -55,7 +57,7 @@ async fn proxy_handler(
         }
     }
 
-    let outbound_headers = filter_request_headers(&parts.headers);
+    let mut outbound_headers = filter_request_headers(&parts.headers);
     let body_bytes = match to_bytes(body, max_body_bytes).await {
         Ok(bytes) => bytes,
         Err(_) => {
@@ -69,6 +71,16 @@ async fn proxy_handler(
         }
     };
 
+    // 中文注释：登记项随响应体一起释放；客户端在响应送完前断开时，后端据此中止上游读取。
+    // 同名头直接覆盖，后端只认前置代理登记过的编号。
+    let mut registration = crate::gateway::register_client_request();
+    if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(crate::gateway::CLIENT_REQUEST_ID_HEADER_NAME.as_bytes()),
+        HeaderValue::from_str(registration.id().to_string().as_str()),
+    ) {
+        outbound_headers.insert(name, value);
+    }
+
     let mut builder = state.client.request(parts.method, target_url.as_str());
     builder = builder.headers(outbound_headers);
     builder = builder.body(body_bytes);
@@ -91,7 +103,13 @@ async fn proxy_handler(
         upstream.headers(),
     );
 
-    match response_builder.body(Body::from_stream(upstream.bytes_stream())) {
+    let body_stream = upstream
+        .bytes_stream()
+        .chain(futures_util::stream::poll_fn(move |_| {
+            registration.mark_completed();
+            Poll::Ready(None)
+        }));
+    match response_builder.body(Body::from_stream(body_stream)) {
         Ok(response) => response,
         Err(err) => {
             let message = format!("build response failed: {err}");
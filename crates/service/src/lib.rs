@@ -28,6 +28,8 @@ mod account_list;
 mod account_status;
 #[path = "account/account_update.rs"]
 mod account_update;
+#[path = "apikey/apikey_budget.rs"]
+mod apikey_budget;
 #[path = "apikey/apikey_create.rs"]
 mod apikey_create;
 #[path = "apikey/apikey_delete.rs"]
@@ -57,7 +59,15 @@ mod error_codes;
 mod gateway;
 mod http;
 mod lock_utils;
+#[path = "model_alias/model_alias_rules.rs"]
+mod model_alias_rules;
+#[path = "model_alias/model_fallback_chains.rs"]
+mod model_fallback_chains;
+#[path = "pricing/model_pricing.rs"]
+mod model_pricing;
 pub mod process_env;
+#[path = "provider/provider_accounts.rs"]
+mod provider_accounts;
 mod reasoning_effort;
 #[path = "requestlog/requestlog_clear.rs"]
 mod requestlog_clear;
@@ -76,6 +86,8 @@ mod usage_http;
 mod usage_keepalive;
 #[path = "usage/usage_list.rs"]
 mod usage_list;
+#[path = "usage/usage_passive.rs"]
+mod usage_passive;
 #[path = "usage/usage_read.rs"]
 mod usage_read;
 #[path = "usage/usage_refresh.rs"]
@@ -211,6 +223,7 @@ pub const APP_SETTING_GATEWAY_CPA_NO_COOKIE_HEADER_MODE_KEY: &str =
     "gateway.cpa_no_cookie_header_mode";
 pub const APP_SETTING_GATEWAY_UPSTREAM_PROXY_URL_KEY: &str = "gateway.upstream_proxy_url";
 pub const APP_SETTING_GATEWAY_BACKGROUND_TASKS_KEY: &str = "gateway.background_tasks";
+pub const APP_SETTING_GATEWAY_COOLDOWN_POLICY_KEY: &str = "gateway.cooldown_policy";
 pub const APP_SETTING_ENV_OVERRIDES_KEY: &str = "app.env_overrides";
 pub const APP_SETTING_WEB_ACCESS_PASSWORD_HASH_KEY: &str = "web.auth.password_hash";
 pub const WEB_ACCESS_SESSION_COOKIE_NAME: &str = "codexmanager_web_auth";
@@ -269,6 +282,7 @@ struct AppSettingsPatch {
     cpa_no_cookie_header_mode_enabled: Option<bool>,
     upstream_proxy_url: Option<String>,
     background_tasks: Option<BackgroundTasksInput>,
+    cooldown_policy: Option<gateway::CooldownPolicyPatch>,
     env_overrides: Option<HashMap<String, String>>,
     web_access_password: Option<String>,
 }
@@ -439,6 +453,16 @@ pub fn set_gateway_background_tasks(
     serde_json::to_value(applied).map_err(|err| err.to_string())
 }
 
+pub(crate) fn set_gateway_cooldown_policy(
+    patch: gateway::CooldownPolicyPatch,
+) -> Result<serde_json::Value, String> {
+    let applied = gateway::set_cooldown_policy(patch)?;
+    let raw = serde_json::to_string(&applied)
+        .map_err(|err| format!("serialize cooldown policy failed: {err}"))?;
+    save_persisted_app_setting(APP_SETTING_GATEWAY_COOLDOWN_POLICY_KEY, Some(&raw))?;
+    serde_json::to_value(applied).map_err(|err| err.to_string())
+}
+
 fn current_background_tasks_snapshot_value() -> Result<serde_json::Value, String> {
     serde_json::to_value(usage_refresh::background_tasks_settings()).map_err(|err| err.to_string())
 }
@@ -480,6 +504,18 @@ pub fn sync_runtime_settings_from_storage() {
             }
         }
     }
+    if let Some(raw) = settings.get(APP_SETTING_GATEWAY_COOLDOWN_POLICY_KEY) {
+        match serde_json::from_str::<gateway::CooldownPolicyPatch>(raw) {
+            Ok(patch) => {
+                if let Err(err) = gateway::set_cooldown_policy(patch) {
+                    log::warn!("sync persisted cooldown policy failed: {err}");
+                }
+            }
+            Err(err) => {
+                log::warn!("parse persisted cooldown policy failed: {err}");
+            }
+        }
+    }
 }
 
 pub fn app_settings_get() -> Result<Value, String> {
@@ -506,6 +542,9 @@ pub fn app_settings_get_with_overrides(
     let upstream_proxy_url = gateway::current_upstream_proxy_url();
     let background_tasks_raw = serde_json::to_string(&background_tasks)
         .map_err(|err| format!("serialize background tasks failed: {err}"))?;
+    let cooldown_policy = gateway::current_cooldown_policy();
+    let cooldown_policy_raw = serde_json::to_string(&cooldown_policy)
+        .map_err(|err| format!("serialize cooldown policy failed: {err}"))?;
     let env_overrides = current_env_overrides();
 
     let _ = save_persisted_bool_setting(APP_SETTING_UPDATE_AUTO_CHECK_KEY, update_auto_check);
@@ -537,6 +576,10 @@ pub fn app_settings_get_with_overrides(
         APP_SETTING_GATEWAY_BACKGROUND_TASKS_KEY,
         Some(&background_tasks_raw),
     );
+    let _ = save_persisted_app_setting(
+        APP_SETTING_GATEWAY_COOLDOWN_POLICY_KEY,
+        Some(&cooldown_policy_raw),
+    );
     let _ = save_env_overrides_value(&env_overrides);
 
     Ok(serde_json::json!({
@@ -553,10 +596,12 @@ pub fn app_settings_get_with_overrides(
             SERVICE_BIND_MODE_ALL_INTERFACES
         ],
         "routeStrategy": route_strategy,
-        "routeStrategyOptions": ["ordered", "balanced"],
+        "routeStrategyOptions": ["ordered", "balanced", "quota_weighted"],
         "cpaNoCookieHeaderModeEnabled": cpa_no_cookie_header_mode_enabled,
         "upstreamProxyUrl": upstream_proxy_url.unwrap_or_default(),
         "backgroundTasks": background_tasks,
+        "cooldownPolicy": cooldown_policy,
+        "cooldownRateLimitSourceOptions": gateway::RATE_LIMIT_SOURCE_OPTIONS,
         "envOverrides": env_overrides,
         "envOverrideCatalog": env_override_catalog_value(),
         "envOverrideReservedKeys": env_override_reserved_keys(),
@@ -606,6 +651,9 @@ pub fn app_settings_set(params: Option<&Value>) -> Result<Value, String> {
     if let Some(background_tasks) = patch.background_tasks {
         let _ = set_gateway_background_tasks(background_tasks)?;
     }
+    if let Some(cooldown_policy) = patch.cooldown_policy {
+        let _ = set_gateway_cooldown_policy(cooldown_policy)?;
+    }
     if let Some(env_overrides) = patch.env_overrides {
         let _ = set_env_overrides(env_overrides)?;
     }
@@ -635,6 +683,7 @@ pub fn start_one_shot_server() -> std::io::Result<ServerHandle> {
         log::warn!("storage startup init skipped: {}", err);
     }
     sync_runtime_settings_from_storage();
+    gateway::load_persisted_routing_state();
     let server = tiny_http::Server::http("127.0.0.1:0")
         .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
     let addr = server
@@ -658,10 +707,16 @@ pub fn start_server(addr: &str) -> std::io::Result<()> {
         log::warn!("storage startup init skipped: {}", err);
     }
     sync_runtime_settings_from_storage();
+    gateway::load_persisted_routing_state();
     usage_refresh::ensure_usage_polling();
     usage_refresh::ensure_gateway_keepalive();
     usage_refresh::ensure_token_refresh_polling();
-    http::server::start_http(addr)
+    gateway::ensure_batch_runner();
+    gateway::warm_up_token_counter();
+    let result = http::server::start_http(addr);
+    // 中文注释：正常退出前写出后台线程还没来得及落库的路由状态。
+    gateway::flush_routing_state();
+    result
 }
 
 pub fn initialize_storage_if_needed() -> Result<(), String> {
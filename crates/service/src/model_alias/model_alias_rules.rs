@@ -0,0 +1,283 @@
+use codexmanager_core::rpc::types::ModelAliasRuleItem;
+use codexmanager_core::storage::{now_ts, ModelAliasRule, Storage};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::storage_helpers::open_storage;
+
+pub(crate) const MATCH_KIND_GLOB: &str = "glob";
+pub(crate) const MATCH_KIND_REGEX: &str = "regex";
+const MODEL_ALIAS_PATTERN_MAX_LEN: usize = 256;
+// 中文注释：正则规则按原文缓存编译结果；规则数量有限，超过上限直接整表清空即可。
+const MODEL_ALIAS_REGEX_CACHE_LIMIT: usize = 256;
+
+static MODEL_ALIAS_REGEX_CACHE: OnceLock<Mutex<HashMap<String, Option<Regex>>>> = OnceLock::new();
+
+/// 别名命中后的改写结果：客户端请求的模型、实际转发的模型，以及规则附带的推理等级。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModelAliasRewrite {
+    pub(crate) requested_model: String,
+    pub(crate) target_model: String,
+    pub(crate) reasoning_effort: Option<String>,
+}
+
+pub(crate) fn normalize_match_kind(raw: Option<&str>) -> Result<&'static str, String> {
+    match raw
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "" | "glob" | "wildcard" => Ok(MATCH_KIND_GLOB),
+        "regex" | "regexp" => Ok(MATCH_KIND_REGEX),
+        other => Err(format!("invalid match kind: {other}")),
+    }
+}
+
+/// 大小写不敏感的通配符匹配，`*` 匹配任意长度，`?` 匹配单个字符。
+pub(crate) fn glob_matches(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase().chars().collect::<Vec<_>>();
+    let value = value.to_ascii_lowercase().chars().collect::<Vec<_>>();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = backtrack {
+            p = star_p + 1;
+            v = star_v + 1;
+            backtrack = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+fn compile_alias_regex(pattern: &str) -> Result<Regex, String> {
+    // 中文注释：正则默认整串匹配且忽略大小写，避免 `gpt-4` 误命中 `gpt-4o-mini` 之类的模型。
+    Regex::new(&format!("(?i)^(?:{pattern})$"))
+        .map_err(|err| format!("invalid regex pattern: {err}"))
+}
+
+fn cached_alias_regex(pattern: &str) -> Option<Regex> {
+    let lock = MODEL_ALIAS_REGEX_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = crate::lock_utils::lock_recover(lock, "model_alias_regex_cache");
+    if let Some(compiled) = cache.get(pattern) {
+        return compiled.clone();
+    }
+    if cache.len() >= MODEL_ALIAS_REGEX_CACHE_LIMIT {
+        cache.clear();
+    }
+    let compiled = match compile_alias_regex(pattern) {
+        Ok(regex) => Some(regex),
+        Err(err) => {
+            log::warn!("skip model alias rule: pattern={pattern} err={err}");
+            None
+        }
+    };
+    cache.insert(pattern.to_string(), compiled.clone());
+    compiled
+}
+
+fn match_alias_rule(rule: &ModelAliasRule, model: &str) -> Option<String> {
+    if rule.match_kind == MATCH_KIND_REGEX {
+        let regex = cached_alias_regex(&rule.pattern)?;
+        let captures = regex.captures(model)?;
+        // 中文注释：正则规则的目标支持 `$1` / `${name}` 引用捕获组。
+        let mut target = String::new();
+        captures.expand(&rule.target_model, &mut target);
+        return Some(target);
+    }
+    glob_matches(&rule.pattern, model).then(|| rule.target_model.clone())
+}
+
+/// 按顺序匹配别名规则，第一条命中的规则生效；调用方负责把 Key 专属规则排在全局规则之前。
+pub(crate) fn resolve_model_alias(
+    rules: &[ModelAliasRule],
+    model: &str,
+) -> Option<ModelAliasRewrite> {
+    let requested = model.trim();
+    if requested.is_empty() {
+        return None;
+    }
+    let rule = rules.iter().filter(|rule| rule.enabled).find_map(|rule| {
+        match_alias_rule(rule, requested)
+            .map(|target| target.trim().to_string())
+            .filter(|target| !target.is_empty())
+            .map(|target| (rule, target))
+    });
+    let (rule, target_model) = rule?;
+    let reasoning_effort =
+        crate::reasoning_effort::normalize_reasoning_effort_owned(rule.reasoning_effort.clone());
+    if target_model == requested && reasoning_effort.is_none() {
+        return None;
+    }
+    Some(ModelAliasRewrite {
+        requested_model: requested.to_string(),
+        target_model,
+        reasoning_effort,
+    })
+}
+
+/// 网关入口使用：读取对该 Key 生效的规则并解析别名；读取失败时放行原模型。
+pub(crate) fn resolve_request_model_alias(
+    storage: &Storage,
+    key_id: &str,
+    model: &str,
+) -> Option<ModelAliasRewrite> {
+    let rules = match storage.list_active_model_alias_rules_for_key(key_id) {
+        Ok(rules) => rules,
+        Err(err) => {
+            log::warn!("load model alias rules failed: key_id={key_id} err={err}");
+            return None;
+        }
+    };
+    resolve_model_alias(&rules, model)
+}
+
+/// /v1/models 需要展示的别名：只列出不含通配符的 glob 规则，正则与通配规则无法枚举。
+pub(crate) fn list_model_alias_names(storage: &Storage, key_id: &str) -> Vec<String> {
+    let rules = match storage.list_active_model_alias_rules_for_key(key_id) {
+        Ok(rules) => rules,
+        Err(err) => {
+            log::warn!("load model alias rules failed: key_id={key_id} err={err}");
+            return Vec::new();
+        }
+    };
+    let mut names: Vec<String> = Vec::new();
+    for rule in rules {
+        let name = rule.pattern.trim();
+        if rule.match_kind != MATCH_KIND_GLOB
+            || name.is_empty()
+            || name.contains(['*', '?'])
+            || names.iter().any(|item| item.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        names.push(name.to_string());
+    }
+    names
+}
+
+fn to_item(rule: ModelAliasRule) -> ModelAliasRuleItem {
+    ModelAliasRuleItem {
+        id: Some(rule.id),
+        key_id: rule.key_id,
+        sort: Some(rule.sort),
+        match_kind: Some(rule.match_kind),
+        pattern: rule.pattern,
+        target_model: rule.target_model,
+        reasoning_effort: rule.reasoning_effort,
+        enabled: Some(rule.enabled),
+        created_at: Some(rule.created_at),
+        updated_at: Some(rule.updated_at),
+    }
+}
+
+fn normalize_alias_rule_item(item: ModelAliasRuleItem, now: i64) -> Result<ModelAliasRule, String> {
+    let match_kind = normalize_match_kind(item.match_kind.as_deref())?;
+    let pattern = item.pattern.trim().to_string();
+    if pattern.is_empty() {
+        return Err("missing pattern".to_string());
+    }
+    if pattern.len() > MODEL_ALIAS_PATTERN_MAX_LEN {
+        return Err("pattern too long".to_string());
+    }
+    if match_kind == MATCH_KIND_REGEX {
+        compile_alias_regex(&pattern)?;
+    }
+    let target_model = item.target_model.trim().to_string();
+    if target_model.is_empty() {
+        return Err("missing targetModel".to_string());
+    }
+    let reasoning_effort = match item
+        .reasoning_effort
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(raw) => Some(
+            crate::reasoning_effort::normalize_reasoning_effort(raw)
+                .ok_or_else(|| format!("invalid reasoningEffort: {raw}"))?
+                .to_string(),
+        ),
+        None => None,
+    };
+    Ok(ModelAliasRule {
+        id: item.id.unwrap_or(0),
+        key_id: item
+            .key_id
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        sort: item.sort.unwrap_or(0),
+        match_kind: match_kind.to_string(),
+        pattern,
+        target_model,
+        reasoning_effort,
+        enabled: item.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+pub(crate) fn read_model_alias_rules(
+    key_id: Option<&str>,
+) -> Result<Vec<ModelAliasRuleItem>, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let key_id = key_id.map(str::trim).filter(|value| !value.is_empty());
+    Ok(storage
+        .list_model_alias_rules(key_id)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(to_item)
+        .collect())
+}
+
+/// 新增或更新别名规则（带 id 即更新），返回规则 id。
+pub(crate) fn save_model_alias_rule(item: ModelAliasRuleItem) -> Result<i64, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let rule = normalize_alias_rule_item(item, now_ts())?;
+    if let Some(key_id) = rule.key_id.as_deref() {
+        if storage
+            .find_api_key_by_id(key_id)
+            .map_err(|err| err.to_string())?
+            .is_none()
+        {
+            return Err("api key not found".to_string());
+        }
+    }
+    if rule.id <= 0 {
+        return storage
+            .insert_model_alias_rule(&rule)
+            .map_err(|err| err.to_string());
+    }
+    if !storage
+        .update_model_alias_rule(&rule)
+        .map_err(|err| err.to_string())?
+    {
+        return Err("model alias rule not found".to_string());
+    }
+    Ok(rule.id)
+}
+
+pub(crate) fn delete_model_alias_rule(id: Option<i64>) -> Result<(), String> {
+    let id = id.filter(|value| *value > 0).ok_or("missing id")?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    if !storage
+        .delete_model_alias_rule(id)
+        .map_err(|err| err.to_string())?
+    {
+        return Err("model alias rule not found".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/model_alias_rules_tests.rs"]
+mod tests;
@@ -0,0 +1,161 @@
+use codexmanager_core::rpc::types::ModelFallbackChainItem;
+use codexmanager_core::storage::{now_ts, ModelFallbackChain, Storage};
+
+use crate::storage_helpers::open_storage;
+
+// 中文注释：降级链过长只会拖慢失败请求，限制最多追加的降级模型数量。
+const MODEL_FALLBACK_CHAIN_MAX_LEN: usize = 5;
+
+/// 解析降级链中的模型列表：去掉空值、与原模型重复及链内重复的项，并截断到上限。
+pub(crate) fn parse_fallback_models(model: &str, fallback_models_json: &str) -> Vec<String> {
+    let items = serde_json::from_str::<Vec<String>>(fallback_models_json).unwrap_or_default();
+    normalize_fallback_models(model, items)
+}
+
+fn normalize_fallback_models(model: &str, items: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim();
+        if item.is_empty()
+            || item.eq_ignore_ascii_case(model.trim())
+            || out
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(item))
+        {
+            continue;
+        }
+        out.push(item.to_string());
+        if out.len() >= MODEL_FALLBACK_CHAIN_MAX_LEN {
+            break;
+        }
+    }
+    out
+}
+
+/// 网关使用：返回请求模型对应的降级模型（不含原模型）；未配置或读取失败时返回空列表。
+pub(crate) fn resolve_model_fallback_chain(
+    storage: &Storage,
+    key_id: &str,
+    model: &str,
+) -> Vec<String> {
+    let model = model.trim();
+    if model.is_empty() {
+        return Vec::new();
+    }
+    match storage.find_active_model_fallback_chain(key_id, model) {
+        Ok(Some(chain)) => parse_fallback_models(model, &chain.fallback_models_json),
+        Ok(None) => Vec::new(),
+        Err(err) => {
+            log::warn!("load model fallback chain failed: key_id={key_id} model={model} err={err}");
+            Vec::new()
+        }
+    }
+}
+
+fn to_item(chain: ModelFallbackChain) -> ModelFallbackChainItem {
+    ModelFallbackChainItem {
+        fallback_models: parse_fallback_models(&chain.model, &chain.fallback_models_json),
+        id: Some(chain.id),
+        key_id: chain.key_id,
+        model: chain.model,
+        enabled: Some(chain.enabled),
+        created_at: Some(chain.created_at),
+        updated_at: Some(chain.updated_at),
+    }
+}
+
+fn normalize_fallback_chain_item(
+    item: ModelFallbackChainItem,
+    now: i64,
+) -> Result<ModelFallbackChain, String> {
+    let model = item.model.trim().to_string();
+    if model.is_empty() {
+        return Err("missing model".to_string());
+    }
+    let fallback_models = normalize_fallback_models(&model, item.fallback_models);
+    if fallback_models.is_empty() {
+        return Err("missing fallbackModels".to_string());
+    }
+    Ok(ModelFallbackChain {
+        id: item.id.unwrap_or(0),
+        key_id: item
+            .key_id
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        fallback_models_json: serde_json::to_string(&fallback_models)
+            .map_err(|err| err.to_string())?,
+        model,
+        enabled: item.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+pub(crate) fn read_model_fallback_chains(
+    key_id: Option<&str>,
+) -> Result<Vec<ModelFallbackChainItem>, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let key_id = key_id.map(str::trim).filter(|value| !value.is_empty());
+    Ok(storage
+        .list_model_fallback_chains(key_id)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(to_item)
+        .collect())
+}
+
+/// 新增或更新降级链（带 id 即更新）；同一作用域下每个模型只保留一条，返回降级链 id。
+pub(crate) fn save_model_fallback_chain(item: ModelFallbackChainItem) -> Result<i64, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let mut chain = normalize_fallback_chain_item(item, now_ts())?;
+    if let Some(key_id) = chain.key_id.as_deref() {
+        if storage
+            .find_api_key_by_id(key_id)
+            .map_err(|err| err.to_string())?
+            .is_none()
+        {
+            return Err("api key not found".to_string());
+        }
+    }
+    let existing = storage
+        .find_model_fallback_chain_by_scope(chain.key_id.as_deref(), &chain.model)
+        .map_err(|err| err.to_string())?;
+    match existing {
+        Some(existing) if chain.id <= 0 => chain.id = existing.id,
+        Some(existing) if existing.id != chain.id => {
+            return Err(format!(
+                "model fallback chain already exists: {}",
+                chain.model
+            ));
+        }
+        _ => {}
+    }
+    if chain.id <= 0 {
+        return storage
+            .insert_model_fallback_chain(&chain)
+            .map_err(|err| err.to_string());
+    }
+    if !storage
+        .update_model_fallback_chain(&chain)
+        .map_err(|err| err.to_string())?
+    {
+        return Err("model fallback chain not found".to_string());
+    }
+    Ok(chain.id)
+}
+
+pub(crate) fn delete_model_fallback_chain(id: Option<i64>) -> Result<(), String> {
+    let id = id.filter(|value| *value > 0).ok_or("missing id")?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    if !storage
+        .delete_model_fallback_chain(id)
+        .map_err(|err| err.to_string())?
+    {
+        return Err("model fallback chain not found".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/model_fallback_chains_tests.rs"]
+mod tests;
@@ -0,0 +1,136 @@
+use super::{
+    glob_matches, list_model_alias_names, normalize_alias_rule_item, resolve_model_alias,
+    ModelAliasRewrite, MATCH_KIND_GLOB, MATCH_KIND_REGEX,
+};
+use codexmanager_core::rpc::types::ModelAliasRuleItem;
+use codexmanager_core::storage::{ModelAliasRule, Storage};
+
+fn rule(match_kind: &str, pattern: &str, target_model: &str) -> ModelAliasRule {
+    ModelAliasRule {
+        id: 0,
+        key_id: None,
+        sort: 0,
+        match_kind: match_kind.to_string(),
+        pattern: pattern.to_string(),
+        target_model: target_model.to_string(),
+        reasoning_effort: None,
+        enabled: true,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+fn item(match_kind: &str, pattern: &str, target_model: &str) -> ModelAliasRuleItem {
+    ModelAliasRuleItem {
+        id: None,
+        key_id: None,
+        sort: None,
+        match_kind: Some(match_kind.to_string()),
+        pattern: pattern.to_string(),
+        target_model: target_model.to_string(),
+        reasoning_effort: None,
+        enabled: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn glob_matches_wildcards_case_insensitively() {
+    assert!(glob_matches("gpt-4*", "GPT-4o-mini"));
+    assert!(glob_matches("claude-?-sonnet", "claude-3-sonnet"));
+    assert!(glob_matches("*", "anything"));
+    assert!(glob_matches("a*b*c", "aXXbYYc"));
+    assert!(!glob_matches("gpt-4", "gpt-4o"));
+    assert!(!glob_matches("claude-?-sonnet", "claude-35-sonnet"));
+}
+
+#[test]
+fn resolve_model_alias_uses_first_matching_rule() {
+    let mut with_reasoning = rule(MATCH_KIND_GLOB, "gpt-4*", "gpt-5.1");
+    with_reasoning.reasoning_effort = Some("HIGH".to_string());
+    let rules = vec![
+        rule(MATCH_KIND_GLOB, "gpt-4o-mini", "gpt-5.1-codex-mini"),
+        with_reasoning,
+    ];
+
+    assert_eq!(
+        resolve_model_alias(&rules, "gpt-4o-mini"),
+        Some(ModelAliasRewrite {
+            requested_model: "gpt-4o-mini".to_string(),
+            target_model: "gpt-5.1-codex-mini".to_string(),
+            reasoning_effort: None,
+        })
+    );
+    assert_eq!(
+        resolve_model_alias(&rules, "gpt-4.1"),
+        Some(ModelAliasRewrite {
+            requested_model: "gpt-4.1".to_string(),
+            target_model: "gpt-5.1".to_string(),
+            reasoning_effort: Some("high".to_string()),
+        })
+    );
+    assert_eq!(resolve_model_alias(&rules, "o3"), None);
+}
+
+#[test]
+fn resolve_model_alias_expands_regex_captures_and_skips_disabled_rules() {
+    let mut disabled = rule(MATCH_KIND_GLOB, "claude-*", "gpt-5");
+    disabled.enabled = false;
+    let rules = vec![
+        disabled,
+        rule(MATCH_KIND_REGEX, r"claude-(\w+)-latest", "gpt-5.1-$1"),
+        rule(MATCH_KIND_REGEX, "([", "broken"),
+    ];
+
+    let rewrite = resolve_model_alias(&rules, "claude-codex-latest").expect("regex alias");
+    assert_eq!(rewrite.target_model, "gpt-5.1-codex");
+    assert_eq!(resolve_model_alias(&rules, "claude-codex-latest-2"), None);
+    // 中文注释：目标与原模型一致且没有附带推理等级时视为无改写。
+    let identity = vec![rule(MATCH_KIND_GLOB, "gpt-5", "gpt-5")];
+    assert_eq!(resolve_model_alias(&identity, "gpt-5"), None);
+}
+
+#[test]
+fn normalize_alias_rule_item_validates_pattern_target_and_reasoning() {
+    let normalized =
+        normalize_alias_rule_item(item("", " gpt-4* ", " gpt-5 "), 42).expect("valid glob rule");
+    assert_eq!(normalized.match_kind, MATCH_KIND_GLOB);
+    assert_eq!(normalized.pattern, "gpt-4*");
+    assert_eq!(normalized.target_model, "gpt-5");
+    assert!(normalized.enabled);
+    assert_eq!(normalized.updated_at, 42);
+
+    assert!(normalize_alias_rule_item(item("regex", "([", "gpt-5"), 0).is_err());
+    assert!(normalize_alias_rule_item(item("prefix", "gpt-4", "gpt-5"), 0).is_err());
+    assert!(normalize_alias_rule_item(item("glob", "gpt-4", " "), 0).is_err());
+
+    let mut bad_reasoning = item("glob", "gpt-4", "gpt-5");
+    bad_reasoning.reasoning_effort = Some("turbo".to_string());
+    assert!(normalize_alias_rule_item(bad_reasoning, 0).is_err());
+}
+
+#[test]
+fn list_model_alias_names_only_returns_literal_rules_for_key() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    let mut key_rule = rule(MATCH_KIND_GLOB, "team-fast", "gpt-5.1-codex-mini");
+    key_rule.key_id = Some("gk_1".to_string());
+    let mut other_key_rule = rule(MATCH_KIND_GLOB, "other-fast", "gpt-5");
+    other_key_rule.key_id = Some("gk_2".to_string());
+    for item in [
+        key_rule,
+        other_key_rule,
+        rule(MATCH_KIND_GLOB, "smart", "gpt-5.1"),
+        rule(MATCH_KIND_GLOB, "Team-Fast", "gpt-5"),
+        rule(MATCH_KIND_GLOB, "gpt-4*", "gpt-5"),
+        rule(MATCH_KIND_REGEX, "claude-.*", "gpt-5"),
+    ] {
+        storage.insert_model_alias_rule(&item).expect("insert rule");
+    }
+
+    assert_eq!(
+        list_model_alias_names(&storage, "gk_1"),
+        vec!["team-fast".to_string(), "smart".to_string()]
+    );
+}
@@ -0,0 +1,78 @@
+use super::{normalize_fallback_chain_item, parse_fallback_models, resolve_model_fallback_chain};
+use codexmanager_core::rpc::types::ModelFallbackChainItem;
+use codexmanager_core::storage::{ModelFallbackChain, Storage};
+
+fn chain(key_id: Option<&str>, model: &str, fallback_models: &[&str]) -> ModelFallbackChain {
+    ModelFallbackChain {
+        id: 0,
+        key_id: key_id.map(str::to_string),
+        model: model.to_string(),
+        fallback_models_json: serde_json::to_string(fallback_models).expect("serialize chain"),
+        enabled: true,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn parse_fallback_models_drops_blank_duplicate_and_self_entries() {
+    let parsed = parse_fallback_models(
+        "gpt-5.4",
+        r#"["gpt-5.3-codex", " ", "GPT-5.4", "gpt-5.3-codex", "gpt-5.1", "a", "b", "c", "d"]"#,
+    );
+    assert_eq!(parsed, vec!["gpt-5.3-codex", "gpt-5.1", "a", "b", "c"]);
+    assert!(parse_fallback_models("gpt-5.4", "not json").is_empty());
+}
+
+#[test]
+fn resolve_model_fallback_chain_prefers_key_scope_over_global() {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    storage
+        .insert_model_fallback_chain(&chain(None, "gpt-5.4", &["gpt-5.1"]))
+        .expect("insert global chain");
+    storage
+        .insert_model_fallback_chain(&chain(
+            Some("gk_1"),
+            "gpt-5.4",
+            &["gpt-5.3-codex", "gpt-5.1"],
+        ))
+        .expect("insert key chain");
+    let mut disabled = chain(None, "gpt-5.2", &["gpt-5.1"]);
+    disabled.enabled = false;
+    storage
+        .insert_model_fallback_chain(&disabled)
+        .expect("insert disabled chain");
+
+    assert_eq!(
+        resolve_model_fallback_chain(&storage, "gk_1", "GPT-5.4"),
+        vec!["gpt-5.3-codex", "gpt-5.1"]
+    );
+    assert_eq!(
+        resolve_model_fallback_chain(&storage, "gk_2", "gpt-5.4"),
+        vec!["gpt-5.1"]
+    );
+    assert!(resolve_model_fallback_chain(&storage, "gk_1", "gpt-5.2").is_empty());
+}
+
+#[test]
+fn normalize_fallback_chain_item_requires_model_and_fallbacks() {
+    let item = |model: &str, fallback_models: &[&str]| ModelFallbackChainItem {
+        id: None,
+        key_id: Some(" ".to_string()),
+        model: model.to_string(),
+        fallback_models: fallback_models.iter().map(|v| v.to_string()).collect(),
+        enabled: None,
+        created_at: None,
+        updated_at: None,
+    };
+    let normalized =
+        normalize_fallback_chain_item(item(" gpt-5.4 ", &["gpt-5.3-codex"]), 7).expect("valid");
+    assert_eq!(normalized.model, "gpt-5.4");
+    assert_eq!(normalized.key_id, None);
+    assert_eq!(normalized.fallback_models_json, r#"["gpt-5.3-codex"]"#);
+    assert!(normalized.enabled);
+
+    assert!(normalize_fallback_chain_item(item("", &["gpt-5.1"]), 0).is_err());
+    assert!(normalize_fallback_chain_item(item("gpt-5.4", &["gpt-5.4", " "]), 0).is_err());
+}
@@ -0,0 +1,373 @@
+use codexmanager_core::rpc::types::ModelPricingItem;
+use codexmanager_core::storage::{now_ts, ModelPricing, RequestTokenStatCostRow, Storage};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::storage_helpers::open_storage;
+
+pub(crate) const MATCH_TYPE_EXACT: &str = "exact";
+pub(crate) const MATCH_TYPE_PREFIX: &str = "prefix";
+const RECOMPUTE_BATCH_SIZE: i64 = 500;
+// 中文注释：未定价模型只提示一次，避免每条请求都刷屏；超过上限后不再记录新模型。
+const UNPRICED_MODEL_LOG_LIMIT: usize = 256;
+
+static UNPRICED_MODELS_LOGGED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+static MODEL_PRICING_CACHE: OnceLock<Mutex<Option<ModelPricingCache>>> = OnceLock::new();
+// 中文注释：每次价目变更递增；读库期间发生变更时不回填缓存，避免把旧价目表写回去。
+static MODEL_PRICING_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 价目表内存副本：请求日志每条都要估算费用，不必每次都读库；按数据库路径区分。
+struct ModelPricingCache {
+    db_path: String,
+    generation: u64,
+    table: Arc<Vec<ModelPricing>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ModelPricingExportResult {
+    json: String,
+    total: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ModelPricingImportResult {
+    imported: usize,
+    replaced: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ModelPricingRecomputeResult {
+    scanned: usize,
+    updated: usize,
+    unpriced: usize,
+}
+
+/// 为模型挑选价目：精确匹配优先，其次最长前缀；同一规则内按输入 token 选最高的已达档位。
+pub(crate) fn resolve_model_pricing<'a>(
+    table: &'a [ModelPricing],
+    model: &str,
+    input_tokens_total: i64,
+) -> Option<&'a ModelPricing> {
+    let normalized = model.trim().to_ascii_lowercase();
+    if normalized.is_empty() {
+        return None;
+    }
+    let mut best: Option<&ModelPricing> = None;
+    for item in table {
+        let pattern = item.model_pattern.as_str();
+        let matched = match item.match_type.as_str() {
+            MATCH_TYPE_EXACT => normalized == pattern,
+            _ => normalized.starts_with(pattern),
+        };
+        let tier_reached =
+            item.input_tokens_above <= 0 || input_tokens_total > item.input_tokens_above;
+        if !matched || !tier_reached {
+            continue;
+        }
+        let rank = |entry: &ModelPricing| {
+            (
+                entry.match_type == MATCH_TYPE_EXACT,
+                entry.model_pattern.len(),
+                entry.input_tokens_above,
+            )
+        };
+        if best.is_none_or(|current| rank(item) > rank(current)) {
+            best = Some(item);
+        }
+    }
+    best
+}
+
+/// 按价目表估算一次请求的费用；模型未定价时返回 None，而不是记成 0。
+pub(crate) fn estimate_cost_usd(
+    table: &[ModelPricing],
+    model: Option<&str>,
+    input_tokens: Option<i64>,
+    cached_input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+    reasoning_output_tokens: Option<i64>,
+) -> Option<f64> {
+    let model = model.map(str::trim).filter(|value| !value.is_empty())?;
+    let input_tokens_total = input_tokens.unwrap_or(0).max(0);
+    let Some(pricing) = resolve_model_pricing(table, model, input_tokens_total) else {
+        log_unpriced_model_once(model);
+        return None;
+    };
+    let per_token = |price_per_1m: f64| price_per_1m / 1_000_000.0;
+    let in_tokens_total = input_tokens_total as f64;
+    let cached_in_tokens = (cached_input_tokens.unwrap_or(0).max(0) as f64).min(in_tokens_total);
+    let billable_in_tokens = (in_tokens_total - cached_in_tokens).max(0.0);
+    let out_tokens = output_tokens.unwrap_or(0).max(0) as f64;
+    // 中文注释：reasoning token 已包含在 output 中；单独定价时从 output 里拆出来按 reasoning 单价计费。
+    let (plain_out_tokens, reasoning_cost) = match pricing.reasoning_output_price_per_1m {
+        Some(price) => {
+            let reasoning_tokens =
+                (reasoning_output_tokens.unwrap_or(0).max(0) as f64).min(out_tokens);
+            (
+                out_tokens - reasoning_tokens,
+                reasoning_tokens * per_token(price),
+            )
+        }
+        None => (out_tokens, 0.0),
+    };
+    let cached_price = pricing
+        .cached_input_price_per_1m
+        .unwrap_or(pricing.input_price_per_1m);
+    Some(
+        billable_in_tokens * per_token(pricing.input_price_per_1m)
+            + cached_in_tokens * per_token(cached_price)
+            + plain_out_tokens * per_token(pricing.output_price_per_1m)
+            + reasoning_cost,
+    )
+}
+
+fn log_unpriced_model_once(model: &str) {
+    let lock = UNPRICED_MODELS_LOGGED.get_or_init(|| Mutex::new(HashSet::new()));
+    let mut logged = crate::lock_utils::lock_recover(lock, "unpriced_models_logged");
+    let key = model.to_ascii_lowercase();
+    if logged.len() >= UNPRICED_MODEL_LOG_LIMIT || logged.contains(&key) {
+        return;
+    }
+    log::warn!("model pricing missing, estimated cost left empty: model={model}");
+    logged.insert(key);
+}
+
+/// 读取当前价目表：优先使用内存缓存，价目增删改或导入后失效；读取失败时返回空表且不缓存，调用方按“未定价”处理。
+pub(crate) fn load_model_pricing(storage: &Storage) -> Arc<Vec<ModelPricing>> {
+    let db_path = std::env::var("CODEXMANAGER_DB_PATH").unwrap_or_default();
+    let generation = MODEL_PRICING_GENERATION.load(Ordering::Acquire);
+    let lock = MODEL_PRICING_CACHE.get_or_init(|| Mutex::new(None));
+    if let Some(cached) = crate::lock_utils::lock_recover(lock, "model_pricing_cache")
+        .as_ref()
+        .filter(|cached| cached.db_path == db_path && cached.generation == generation)
+    {
+        return cached.table.clone();
+    }
+
+    let table = match storage.list_model_pricing() {
+        Ok(items) => Arc::new(items),
+        Err(err) => {
+            log::warn!("load model pricing failed: {err}");
+            return Arc::new(Vec::new());
+        }
+    };
+    let mut cached = crate::lock_utils::lock_recover(lock, "model_pricing_cache");
+    if MODEL_PRICING_GENERATION.load(Ordering::Acquire) == generation {
+        *cached = Some(ModelPricingCache {
+            db_path,
+            generation,
+            table: table.clone(),
+        });
+    }
+    table
+}
+
+/// 价目表变更后调用，下一条请求重新读库。
+pub(crate) fn invalidate_model_pricing_cache() {
+    MODEL_PRICING_GENERATION.fetch_add(1, Ordering::AcqRel);
+    if let Some(lock) = MODEL_PRICING_CACHE.get() {
+        *crate::lock_utils::lock_recover(lock, "model_pricing_cache") = None;
+    }
+}
+
+fn normalize_pricing_item(item: ModelPricingItem, now: i64) -> Result<ModelPricing, String> {
+    let model_pattern = item.model_pattern.trim().to_ascii_lowercase();
+    if model_pattern.is_empty() {
+        return Err("model pattern is required".to_string());
+    }
+    let match_type = match item
+        .match_type
+        .as_deref()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .as_deref()
+    {
+        None | Some(MATCH_TYPE_PREFIX) => MATCH_TYPE_PREFIX,
+        Some(MATCH_TYPE_EXACT) => MATCH_TYPE_EXACT,
+        Some(other) => return Err(format!("invalid match type: {other}")),
+    };
+    let input_tokens_above = item.input_tokens_above.unwrap_or(0);
+    if input_tokens_above < 0 {
+        return Err(format!("invalid input tier for {model_pattern}"));
+    }
+    let check_price = |name: &str, value: f64| {
+        if value.is_finite() && value >= 0.0 {
+            Ok(value)
+        } else {
+            Err(format!("invalid {name} price for {model_pattern}"))
+        }
+    };
+    Ok(ModelPricing {
+        input_price_per_1m: check_price("input", item.input_price_per_1m)?,
+        cached_input_price_per_1m: item
+            .cached_input_price_per_1m
+            .map(|value| check_price("cached input", value))
+            .transpose()?,
+        output_price_per_1m: check_price("output", item.output_price_per_1m)?,
+        reasoning_output_price_per_1m: item
+            .reasoning_output_price_per_1m
+            .map(|value| check_price("reasoning output", value))
+            .transpose()?,
+        note: item
+            .note
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        updated_at: now,
+        model_pattern,
+        match_type: match_type.to_string(),
+        input_tokens_above,
+    })
+}
+
+fn to_pricing_item(item: ModelPricing) -> ModelPricingItem {
+    ModelPricingItem {
+        model_pattern: item.model_pattern,
+        match_type: Some(item.match_type),
+        input_tokens_above: Some(item.input_tokens_above),
+        input_price_per_1m: item.input_price_per_1m,
+        cached_input_price_per_1m: item.cached_input_price_per_1m,
+        output_price_per_1m: item.output_price_per_1m,
+        reasoning_output_price_per_1m: item.reasoning_output_price_per_1m,
+        note: item.note,
+        updated_at: Some(item.updated_at),
+    }
+}
+
+pub(crate) fn read_model_pricing() -> Result<Vec<ModelPricingItem>, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let items = storage
+        .list_model_pricing()
+        .map_err(|err| err.to_string())?;
+    Ok(items.into_iter().map(to_pricing_item).collect())
+}
+
+pub(crate) fn upsert_model_pricing(item: ModelPricingItem) -> Result<(), String> {
+    let pricing = normalize_pricing_item(item, now_ts())?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    storage
+        .upsert_model_pricing(&pricing)
+        .map_err(|err| err.to_string())?;
+    invalidate_model_pricing_cache();
+    Ok(())
+}
+
+pub(crate) fn delete_model_pricing(
+    model_pattern: &str,
+    match_type: Option<&str>,
+    input_tokens_above: Option<i64>,
+) -> Result<(), String> {
+    let model_pattern = model_pattern.trim().to_ascii_lowercase();
+    if model_pattern.is_empty() {
+        return Err("model pattern is required".to_string());
+    }
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let deleted = storage
+        .delete_model_pricing(
+            &model_pattern,
+            match_type.unwrap_or(MATCH_TYPE_PREFIX),
+            input_tokens_above.unwrap_or(0),
+        )
+        .map_err(|err| err.to_string())?;
+    if !deleted {
+        return Err("model pricing not found".to_string());
+    }
+    invalidate_model_pricing_cache();
+    Ok(())
+}
+
+pub(crate) fn export_model_pricing() -> Result<ModelPricingExportResult, String> {
+    let items = read_model_pricing()?;
+    let total = items.len();
+    let json = serde_json::to_string_pretty(&items)
+        .map_err(|err| format!("serialize model pricing failed: {err}"))?;
+    Ok(ModelPricingExportResult { json, total })
+}
+
+/// 导入价目 JSON：既接受数组，也接受导出时的 `{ "items": [...] }` 形态。
+pub(crate) fn import_model_pricing(
+    contents: &str,
+    replace: bool,
+) -> Result<ModelPricingImportResult, String> {
+    let value: Value = serde_json::from_str(contents.trim())
+        .map_err(|err| format!("invalid model pricing json: {err}"))?;
+    let items = match value {
+        Value::Object(mut object) => object.remove("items").unwrap_or(Value::Null),
+        other => other,
+    };
+    let items: Vec<ModelPricingItem> = serde_json::from_value(items)
+        .map_err(|err| format!("invalid model pricing json: {err}"))?;
+    let now = now_ts();
+    let pricing = items
+        .into_iter()
+        .map(|item| normalize_pricing_item(item, now))
+        .collect::<Result<Vec<_>, _>>()?;
+    if replace && pricing.is_empty() {
+        return Err("refuse to replace model pricing with an empty list".to_string());
+    }
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    storage
+        .import_model_pricing(&pricing, replace)
+        .map_err(|err| err.to_string())?;
+    invalidate_model_pricing_cache();
+    Ok(ModelPricingImportResult {
+        imported: pricing.len(),
+        replaced: replace,
+    })
+}
+
+fn recompute_row_cost(table: &[ModelPricing], row: &RequestTokenStatCostRow) -> Option<f64> {
+    estimate_cost_usd(
+        table,
+        row.model.as_deref(),
+        row.input_tokens,
+        row.cached_input_tokens,
+        row.output_tokens,
+        row.reasoning_output_tokens,
+    )
+}
+
+/// 价目调整后按当前价目表重算历史 `estimated_cost_usd`；`since_ts` 为空时重算全部记录。
+pub(crate) fn recompute_request_costs(
+    since_ts: Option<i64>,
+) -> Result<ModelPricingRecomputeResult, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let table = storage
+        .list_model_pricing()
+        .map_err(|err| err.to_string())?;
+    let mut result = ModelPricingRecomputeResult {
+        scanned: 0,
+        updated: 0,
+        unpriced: 0,
+    };
+    let mut after_id = 0;
+    loop {
+        let rows = storage
+            .list_request_token_stats_for_cost(after_id, since_ts, RECOMPUTE_BATCH_SIZE)
+            .map_err(|err| err.to_string())?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after_id = last.id;
+        let costs = rows
+            .iter()
+            .map(|row| (row.id, recompute_row_cost(&table, row)))
+            .collect::<Vec<_>>();
+        result.scanned += rows.len();
+        result.unpriced += costs.iter().filter(|(_, cost)| cost.is_none()).count();
+        result.updated += storage
+            .update_request_token_stat_costs(&costs)
+            .map_err(|err| err.to_string())?;
+    }
+    crate::apikey_budget::invalidate_key_spend_totals();
+    Ok(result)
+}
+
+#[cfg(test)]
+#[path = "tests/model_pricing_tests.rs"]
+mod tests;
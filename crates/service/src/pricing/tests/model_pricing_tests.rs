@@ -0,0 +1,213 @@
+use super::{estimate_cost_usd, normalize_pricing_item, resolve_model_pricing};
+use codexmanager_core::rpc::types::ModelPricingItem;
+use codexmanager_core::storage::{ModelPricing, Storage};
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-12,
+        "actual={actual}, expected={expected}"
+    );
+}
+
+fn seeded_pricing() -> Vec<ModelPricing> {
+    let storage = Storage::open_in_memory().expect("open in memory");
+    storage.init().expect("init schema");
+    storage.list_model_pricing().expect("list pricing")
+}
+
+fn cost(
+    model: Option<&str>,
+    input_tokens: Option<i64>,
+    cached_input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+) -> f64 {
+    estimate_cost_usd(
+        &seeded_pricing(),
+        model,
+        input_tokens,
+        cached_input_tokens,
+        output_tokens,
+        None,
+    )
+    .expect("priced model")
+}
+
+fn item(model_pattern: &str, match_type: &str) -> ModelPricingItem {
+    ModelPricingItem {
+        model_pattern: model_pattern.to_string(),
+        match_type: Some(match_type.to_string()),
+        input_tokens_above: None,
+        input_price_per_1m: 1.0,
+        cached_input_price_per_1m: None,
+        output_price_per_1m: 4.0,
+        reasoning_output_price_per_1m: None,
+        note: None,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt5_family_prices() {
+    // 基准样本：输入 1000，缓存 200，输出 500
+    // gpt-5 系列：输入 1.25/M，缓存 0.125/M，输出 10/M
+    // => 非缓存输入 800*0.00125/1000 + 缓存 200*0.000125/1000 + 输出 500*0.01/1000
+    // => 0.006025
+    let expected = 0.006025_f64;
+    let models = [
+        "gpt-5",
+        "gpt-5-codex",
+        "gpt-5.1",
+        "gpt-5.1-codex",
+        "gpt-5.1-codex-max",
+    ];
+    for model in models {
+        let actual = cost(Some(model), Some(1000), Some(200), Some(500));
+        assert_close(actual, expected);
+    }
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt54_prices() {
+    // gpt-5.4：输入 2.5/M，缓存 0.25/M，输出 15/M
+    // 样本：输入 1000，缓存 200，输出 500
+    // => 非缓存输入 800*0.0025/1000 + 缓存 200*0.00025/1000 + 输出 500*0.015/1000
+    // => 0.00955
+    let actual = cost(Some("gpt-5.4"), Some(1000), Some(200), Some(500));
+    assert_close(actual, 0.00955);
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt54_large_context_prices() {
+    // gpt-5.4：输入超过 272K 时，输入 5/M，缓存 0.5/M，输出 22.5/M
+    // 样本：输入 300000，缓存 50000，输出 100000
+    // => 非缓存输入 250000*0.005/1000 + 缓存 50000*0.0005/1000 + 输出 100000*0.0225/1000
+    // => 3.525
+    let actual = cost(Some("gpt-5.4"), Some(300_000), Some(50_000), Some(100_000));
+    assert_close(actual, 3.525);
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt54_pro_prices() {
+    // gpt-5.4-pro：输入 30/M，输出 180/M；无缓存折扣时按输入同价处理。
+    let actual = cost(Some("gpt-5.4-pro"), Some(1000), Some(200), Some(500));
+    assert_close(actual, 0.12);
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt54_pro_large_context_prices() {
+    // gpt-5.4-pro：输入超过 272K 时，输入 60/M，输出 270/M。
+    let actual = cost(
+        Some("gpt-5.4-pro"),
+        Some(300_000),
+        Some(50_000),
+        Some(100_000),
+    );
+    assert_close(actual, 45.0);
+}
+
+#[test]
+fn estimate_cost_matches_openai_gpt5_mini_and_52_prices() {
+    // mini：输入 0.25/M，缓存 0.025/M，输出 2/M
+    // 样本同上 => 0.001205
+    let mini_cost = cost(Some("gpt-5.1-codex-mini"), Some(1000), Some(200), Some(500));
+    assert_close(mini_cost, 0.001205);
+
+    // 5.2：输入 1.75/M，缓存 0.175/M，输出 14/M
+    // 样本同上 => 0.008435
+    let v52_models = ["gpt-5.2", "gpt-5.2-codex"];
+    for model in v52_models {
+        let actual = cost(Some(model), Some(1000), Some(200), Some(500));
+        assert_close(actual, 0.008435);
+    }
+}
+
+#[test]
+fn estimate_cost_uses_cached_input_rate_for_gpt_5_1_codex() {
+    // 非缓存输入 800k * 1.25 + 缓存输入 200k * 0.125 + 输出 500k * 10
+    // 期望：1 + 0.025 + 5 = 6.025 USD
+    let actual = cost(
+        Some("gpt-5.1-codex"),
+        Some(1_000_000),
+        Some(200_000),
+        Some(500_000),
+    );
+    assert_close(actual, 6.025);
+}
+
+#[test]
+fn estimate_cost_falls_back_gpt_5_3_codex_to_gpt_5_2_codex_price() {
+    // gpt-5.3-codex 暂按 gpt-5.2-codex：输入 1.75 + 输出 14.00
+    let actual = cost(
+        Some("gpt-5.3-codex"),
+        Some(1_000_000),
+        Some(0),
+        Some(1_000_000),
+    );
+    assert_close(actual, 15.75);
+}
+
+#[test]
+fn resolve_model_pricing_prefers_exact_then_longest_prefix() {
+    let table = vec![
+        normalize_pricing_item(item("gpt-5", "prefix"), 0).expect("prefix"),
+        normalize_pricing_item(item("gpt-5.1-codex", "prefix"), 0).expect("longer prefix"),
+        normalize_pricing_item(item("gpt-5.1-codex", "exact"), 0).expect("exact"),
+    ];
+    let exact = resolve_model_pricing(&table, "GPT-5.1-Codex", 10).expect("exact match");
+    assert_eq!(exact.match_type, "exact");
+    let prefix = resolve_model_pricing(&table, "gpt-5.1-codex-high", 10).expect("prefix match");
+    assert_eq!(prefix.model_pattern, "gpt-5.1-codex");
+    assert_eq!(prefix.match_type, "prefix");
+    assert!(resolve_model_pricing(&table, "o3", 10).is_none());
+}
+
+#[test]
+fn estimate_cost_leaves_unknown_models_unpriced() {
+    let table = seeded_pricing();
+    assert_eq!(
+        estimate_cost_usd(
+            &table,
+            Some("mystery-model"),
+            Some(1000),
+            None,
+            Some(10),
+            None
+        ),
+        None
+    );
+    assert_eq!(
+        estimate_cost_usd(&table, None, Some(1000), None, Some(10), None),
+        None
+    );
+}
+
+#[test]
+fn estimate_cost_bills_reasoning_tokens_at_reasoning_rate() {
+    // 输出 1000（其中 reasoning 400）：普通输出 600*4/M + reasoning 400*8/M，输入 1000*1/M。
+    let mut custom = item("my-model", "exact");
+    custom.reasoning_output_price_per_1m = Some(8.0);
+    let table = vec![normalize_pricing_item(custom, 0).expect("custom")];
+    let actual = estimate_cost_usd(
+        &table,
+        Some("my-model"),
+        Some(1000),
+        None,
+        Some(1000),
+        Some(400),
+    )
+    .expect("priced");
+    assert_close(actual, 0.001 + 0.0024 + 0.0032);
+}
+
+#[test]
+fn normalize_pricing_item_rejects_invalid_payloads() {
+    assert!(normalize_pricing_item(item("  ", "prefix"), 0).is_err());
+    assert!(normalize_pricing_item(item("gpt-x", "regex"), 0).is_err());
+    let mut negative = item("gpt-x", "prefix");
+    negative.output_price_per_1m = -1.0;
+    assert!(normalize_pricing_item(negative, 0).is_err());
+    let normalized = normalize_pricing_item(item(" GPT-X ", ""), 7).expect("normalized");
+    assert_eq!(normalized.model_pattern, "gpt-x");
+    assert_eq!(normalized.match_type, "prefix");
+    assert_eq!(normalized.updated_at, 7);
+}
@@ -0,0 +1,161 @@
+use codexmanager_core::rpc::types::ProviderAccountItem;
+use codexmanager_core::storage::{now_ts, ProviderAccount};
+
+use crate::apikey_profile::normalize_upstream_base_url;
+use crate::storage_helpers::{generate_provider_account_id, open_storage};
+
+const PROVIDER_STATUS_ACTIVE: &str = "active";
+const PROVIDER_STATUS_DISABLED: &str = "disabled";
+
+/// 解析服务商支持的模型列表：去掉空值与重复项（忽略大小写）。
+pub(crate) fn parse_provider_models(models_json: &str) -> Vec<String> {
+    let items = serde_json::from_str::<Vec<String>>(models_json).unwrap_or_default();
+    normalize_provider_models(items)
+}
+
+fn normalize_provider_models(items: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim();
+        if item.is_empty()
+            || out
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(item))
+        {
+            continue;
+        }
+        out.push(item.to_string());
+    }
+    out
+}
+
+/// 服务商是否承接该模型：未配置模型列表视为不限模型；请求未带模型时同样放行。
+pub(crate) fn provider_serves_model(models_json: &str, model: Option<&str>) -> bool {
+    let models = parse_provider_models(models_json);
+    let Some(model) = model.map(str::trim).filter(|value| !value.is_empty()) else {
+        return true;
+    };
+    models.is_empty() || models.iter().any(|item| item.eq_ignore_ascii_case(model))
+}
+
+fn normalize_provider_status(value: Option<String>) -> Result<String, String> {
+    let status = value
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| PROVIDER_STATUS_ACTIVE.to_string());
+    match status.as_str() {
+        PROVIDER_STATUS_ACTIVE | PROVIDER_STATUS_DISABLED => Ok(status),
+        _ => Err(format!("invalid provider status: {status}")),
+    }
+}
+
+fn to_item(provider: ProviderAccount) -> ProviderAccountItem {
+    ProviderAccountItem {
+        models: parse_provider_models(&provider.models_json),
+        has_api_key: Some(!provider.api_key.trim().is_empty()),
+        id: Some(provider.id),
+        label: provider.label,
+        base_url: provider.base_url,
+        // 中文注释：列表接口不回传服务商 Key 明文。
+        api_key: None,
+        group_name: provider.group_name,
+        sort: Some(provider.sort),
+        status: Some(provider.status),
+        created_at: Some(provider.created_at),
+        updated_at: Some(provider.updated_at),
+    }
+}
+
+/// 把前端提交的服务商配置规整成存储记录；更新时未传 Key 则沿用已保存的 Key。
+fn normalize_provider_item(
+    item: ProviderAccountItem,
+    existing: Option<&ProviderAccount>,
+    now: i64,
+) -> Result<ProviderAccount, String> {
+    let base_url = normalize_upstream_base_url(Some(item.base_url))
+        .map_err(|_| "invalid baseUrl".to_string())?
+        .ok_or_else(|| "missing baseUrl".to_string())?;
+    let api_key = item
+        .api_key
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| existing.map(|provider| provider.api_key.clone()))
+        .ok_or_else(|| "missing apiKey".to_string())?;
+    let id = existing
+        .map(|provider| provider.id.clone())
+        .unwrap_or_else(generate_provider_account_id);
+    let label = Some(item.label.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| base_url.clone());
+    Ok(ProviderAccount {
+        id,
+        label,
+        base_url,
+        api_key,
+        models_json: serde_json::to_string(&normalize_provider_models(item.models))
+            .map_err(|err| err.to_string())?,
+        group_name: item
+            .group_name
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        sort: item.sort.unwrap_or(0),
+        status: normalize_provider_status(item.status)?,
+        created_at: existing.map(|provider| provider.created_at).unwrap_or(now),
+        updated_at: now,
+    })
+}
+
+pub(crate) fn read_provider_accounts() -> Result<Vec<ProviderAccountItem>, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    Ok(storage
+        .list_provider_accounts()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(to_item)
+        .collect())
+}
+
+/// 新增或更新服务商账号（带 id 即更新），返回服务商账号 id。
+pub(crate) fn save_provider_account(item: ProviderAccountItem) -> Result<String, String> {
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    let existing = match item
+        .id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(id) => Some(
+            storage
+                .find_provider_account(id)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| "provider account not found".to_string())?,
+        ),
+        None => None,
+    };
+    let provider = normalize_provider_item(item, existing.as_ref(), now_ts())?;
+    storage
+        .upsert_provider_account(&provider)
+        .map_err(|err| err.to_string())?;
+    crate::gateway::invalidate_candidate_cache();
+    Ok(provider.id)
+}
+
+pub(crate) fn delete_provider_account(id: Option<&str>) -> Result<(), String> {
+    let id = id
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or("missing id")?;
+    let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
+    if !storage
+        .delete_provider_account(id)
+        .map_err(|err| err.to_string())?
+    {
+        return Err("provider account not found".to_string());
+    }
+    crate::gateway::invalidate_candidate_cache();
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/provider_accounts_tests.rs"]
+mod tests;
@@ -0,0 +1,100 @@
+use super::{normalize_provider_item, parse_provider_models, provider_serves_model};
+use codexmanager_core::rpc::types::ProviderAccountItem;
+use codexmanager_core::storage::ProviderAccount;
+
+fn item(base_url: &str, api_key: Option<&str>) -> ProviderAccountItem {
+    ProviderAccountItem {
+        id: None,
+        label: String::new(),
+        base_url: base_url.to_string(),
+        api_key: api_key.map(str::to_string),
+        has_api_key: None,
+        models: vec![
+            " deepseek-chat ".to_string(),
+            "DeepSeek-Chat".to_string(),
+            String::new(),
+            "deepseek-reasoner".to_string(),
+        ],
+        group_name: Some("  ".to_string()),
+        sort: None,
+        status: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn provider_models_are_deduplicated_and_empty_list_serves_any_model() {
+    assert_eq!(
+        parse_provider_models(r#"["a", " A ", "", "b"]"#),
+        vec!["a", "b"]
+    );
+    assert!(provider_serves_model("[]", Some("gpt-5.4")));
+    assert!(provider_serves_model("not json", Some("gpt-5.4")));
+    assert!(provider_serves_model(r#"["deepseek-chat"]"#, None));
+    assert!(provider_serves_model(
+        r#"["deepseek-chat"]"#,
+        Some("DeepSeek-Chat")
+    ));
+    assert!(!provider_serves_model(
+        r#"["deepseek-chat"]"#,
+        Some("gpt-5.4")
+    ));
+}
+
+#[test]
+fn normalize_provider_item_validates_and_fills_defaults() {
+    let provider = normalize_provider_item(
+        item("https://api.deepseek.com/v1/", Some(" sk-1 ")),
+        None,
+        100,
+    )
+    .expect("normalize provider");
+    assert!(provider.id.starts_with("prov_"));
+    assert_eq!(provider.base_url, "https://api.deepseek.com/v1");
+    assert_eq!(provider.label, "https://api.deepseek.com/v1");
+    assert_eq!(provider.api_key, "sk-1");
+    assert_eq!(
+        provider.models_json,
+        r#"["deepseek-chat","deepseek-reasoner"]"#
+    );
+    assert_eq!(provider.group_name, None);
+    assert_eq!(provider.status, "active");
+
+    assert_eq!(
+        normalize_provider_item(item("ftp://example.com", Some("k")), None, 0).unwrap_err(),
+        "invalid baseUrl"
+    );
+    assert_eq!(
+        normalize_provider_item(item("http://127.0.0.1:8080", None), None, 0).unwrap_err(),
+        "missing apiKey"
+    );
+    let mut bad_status = item("http://127.0.0.1:8080", Some("k"));
+    bad_status.status = Some("paused".to_string());
+    assert!(normalize_provider_item(bad_status, None, 0).is_err());
+}
+
+#[test]
+fn normalize_provider_item_keeps_existing_key_and_id_on_update() {
+    let existing = ProviderAccount {
+        id: "prov_existing".to_string(),
+        label: "DeepSeek".to_string(),
+        base_url: "https://api.deepseek.com".to_string(),
+        api_key: "sk-saved".to_string(),
+        models_json: "[]".to_string(),
+        group_name: None,
+        sort: 3,
+        status: "active".to_string(),
+        created_at: 10,
+        updated_at: 10,
+    };
+    let mut update = item("https://api.deepseek.com", None);
+    update.status = Some("Disabled".to_string());
+    let provider =
+        normalize_provider_item(update, Some(&existing), 20).expect("normalize provider update");
+    assert_eq!(provider.id, "prov_existing");
+    assert_eq!(provider.api_key, "sk-saved");
+    assert_eq!(provider.status, "disabled");
+    assert_eq!(provider.created_at, 10);
+    assert_eq!(provider.updated_at, 20);
+}
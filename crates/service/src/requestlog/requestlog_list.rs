@@ -21,6 +21,7 @@ pub(crate) fn read_request_logs(
             adapted_path: item.adapted_path,
             method: item.method,
             model: item.model,
+            requested_model: item.requested_model,
             reasoning_effort: item.reasoning_effort,
             response_adapter: item.response_adapter,
             upstream_url: item.upstream_url,
@@ -1,10 +1,36 @@
-use codexmanager_core::rpc::types::{ApiKeyListResult, JsonRpcRequest, JsonRpcResponse};
+use codexmanager_core::rpc::types::{
+    ApiKeyBudgetListResult, ApiKeyListResult, JsonRpcRequest, JsonRpcResponse,
+};
 
+use crate::apikey_profile::ApiKeyRateLimitParams;
 use crate::{
-    apikey_create, apikey_delete, apikey_disable, apikey_enable, apikey_list, apikey_models,
-    apikey_read_secret, apikey_update_model,
+    apikey_budget, apikey_create, apikey_delete, apikey_disable, apikey_enable, apikey_list,
+    apikey_models, apikey_read_secret, apikey_update_model,
 };
 
+fn account_group_param(req: &JsonRpcRequest) -> Option<String> {
+    // 中文注释：accountGroup 既可传逗号分隔字符串，也可传字符串数组。
+    match req.params.as_ref().and_then(|v| v.get("accountGroup"))? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        _ => None,
+    }
+}
+
+fn rate_limit_params(req: &JsonRpcRequest) -> ApiKeyRateLimitParams {
+    ApiKeyRateLimitParams {
+        rpm_limit: super::i64_param(req, "rpmLimit"),
+        tpm_limit: super::i64_param(req, "tpmLimit"),
+        max_concurrency: super::i64_param(req, "maxConcurrency"),
+    }
+}
+
 pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
     let result = match req.method.as_str() {
         "apikey/list" => super::value_or_error(
@@ -17,6 +43,7 @@ pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
             let protocol_type = super::string_param(req, "protocolType");
             let upstream_base_url = super::string_param(req, "upstreamBaseUrl");
             let static_headers_json = super::string_param(req, "staticHeadersJson");
+            let account_group = account_group_param(req);
             super::value_or_error(apikey_create::create_api_key(
                 name,
                 model_slug,
@@ -24,8 +51,35 @@ pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                 protocol_type,
                 upstream_base_url,
                 static_headers_json,
+                account_group,
+                rate_limit_params(req),
+                super::i64_param(req, "hedgeDelayMs"),
+            ))
+        }
+        "apikey/readBudget" => {
+            let key_id = super::str_param(req, "id").unwrap_or("");
+            super::value_or_error(
+                apikey_budget::read_api_key_budgets(key_id)
+                    .map(|items| ApiKeyBudgetListResult { items }),
+            )
+        }
+        "apikey/updateBudget" => {
+            let key_id = super::str_param(req, "id").unwrap_or("");
+            let period = super::str_param(req, "period").unwrap_or("");
+            super::ok_or_error(apikey_budget::update_api_key_budget(
+                key_id,
+                period,
+                super::i64_param(req, "tokenLimit"),
+                super::f64_param(req, "costLimitUsd"),
+                super::f64_param(req, "warnPercent"),
+                super::bool_param(req, "hardBlock"),
             ))
         }
+        "apikey/resetBudget" => {
+            let key_id = super::str_param(req, "id").unwrap_or("");
+            let period = super::str_param(req, "period").unwrap_or("");
+            super::ok_or_error(apikey_budget::reset_api_key_budget(key_id, period))
+        }
         "apikey/readSecret" => {
             let key_id = super::str_param(req, "id").unwrap_or("");
             super::value_or_error(apikey_read_secret::read_api_key_secret(key_id))
@@ -41,6 +95,7 @@ pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
             let protocol_type = super::string_param(req, "protocolType");
             let upstream_base_url = super::string_param(req, "upstreamBaseUrl");
             let static_headers_json = super::string_param(req, "staticHeadersJson");
+            let account_group = account_group_param(req);
             super::ok_or_error(apikey_update_model::update_api_key_model(
                 key_id,
                 model_slug,
@@ -48,6 +103,9 @@ pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                 protocol_type,
                 upstream_base_url,
                 static_headers_json,
+                account_group,
+                rate_limit_params(req),
+                super::i64_param(req, "hedgeDelayMs"),
             ))
         }
         "apikey/delete" => {
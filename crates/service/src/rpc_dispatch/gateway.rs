@@ -1,4 +1,6 @@
-use codexmanager_core::rpc::types::{JsonRpcRequest, JsonRpcResponse};
+use codexmanager_core::rpc::types::{
+    AccountCooldownListResult, AccountModelExclusionListResult, JsonRpcRequest, JsonRpcResponse,
+};
 use serde_json::Value;
 
 pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
@@ -46,6 +48,24 @@ pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                 },
             ))
         }
+        "gateway/modelEntitlements/list" => super::as_json(AccountModelExclusionListResult {
+            items: crate::gateway::list_account_model_exclusions(),
+        }),
+        "gateway/modelEntitlements/clear" => {
+            let cleared = crate::gateway::clear_account_model_exclusions(
+                super::str_param(req, "accountId"),
+                super::str_param(req, "model"),
+            );
+            super::as_json(serde_json::json!({ "cleared": cleared }))
+        }
+        "gateway/cooldowns/list" => super::as_json(AccountCooldownListResult {
+            items: crate::gateway::list_account_cooldowns(),
+        }),
+        "gateway/cooldowns/clear" => {
+            let cleared =
+                crate::gateway::clear_account_cooldowns(super::str_param(req, "accountId"));
+            super::as_json(serde_json::json!({ "cleared": cleared }))
+        }
         "gateway/backgroundTasks/get" => {
             super::as_json(crate::usage_refresh::background_tasks_settings())
         }
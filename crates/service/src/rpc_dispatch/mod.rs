@@ -9,6 +9,10 @@ mod account;
 mod apikey;
 mod app_settings;
 mod gateway;
+mod model_alias;
+mod model_fallback;
+mod pricing;
+mod provider;
 mod requestlog;
 mod service_config;
 mod usage;
@@ -39,6 +43,13 @@ pub(super) fn i64_param(req: &JsonRpcRequest, key: &str) -> Option<i64> {
         .and_then(|v| v.as_i64())
 }
 
+pub(super) fn f64_param(req: &JsonRpcRequest, key: &str) -> Option<f64> {
+    req.params
+        .as_ref()
+        .and_then(|v| v.get(key))
+        .and_then(|v| v.as_f64())
+}
+
 pub(super) fn bool_param(req: &JsonRpcRequest, key: &str) -> Option<bool> {
     req.params
         .as_ref()
@@ -103,6 +114,18 @@ pub(crate) fn handle_request(req: JsonRpcRequest) -> JsonRpcResponse {
     if let Some(resp) = requestlog::try_handle(&req) {
         return resp;
     }
+    if let Some(resp) = pricing::try_handle(&req) {
+        return resp;
+    }
+    if let Some(resp) = model_alias::try_handle(&req) {
+        return resp;
+    }
+    if let Some(resp) = model_fallback::try_handle(&req) {
+        return resp;
+    }
+    if let Some(resp) = provider::try_handle(&req) {
+        return resp;
+    }
 
     response(
         &req,
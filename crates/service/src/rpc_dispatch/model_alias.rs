@@ -0,0 +1,35 @@
+use codexmanager_core::rpc::types::{
+    JsonRpcRequest, JsonRpcResponse, ModelAliasRuleItem, ModelAliasRuleListResult,
+};
+use serde_json::json;
+
+use crate::model_alias_rules;
+
+fn alias_rule_param(req: &JsonRpcRequest) -> Result<ModelAliasRuleItem, String> {
+    let params = req
+        .params
+        .as_ref()
+        .ok_or_else(|| "missing model alias params".to_string())?;
+    let item = params.get("item").unwrap_or(params).clone();
+    serde_json::from_value(item).map_err(|err| format!("invalid model alias payload: {err}"))
+}
+
+pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let result = match req.method.as_str() {
+        "modelAlias/list" => super::value_or_error(
+            model_alias_rules::read_model_alias_rules(super::str_param(req, "keyId"))
+                .map(|items| ModelAliasRuleListResult { items }),
+        ),
+        "modelAlias/save" => super::value_or_error(
+            alias_rule_param(req)
+                .and_then(model_alias_rules::save_model_alias_rule)
+                .map(|id| json!({ "id": id })),
+        ),
+        "modelAlias/delete" => super::ok_or_error(model_alias_rules::delete_model_alias_rule(
+            super::i64_param(req, "id"),
+        )),
+        _ => return None,
+    };
+
+    Some(super::response(req, result))
+}
@@ -0,0 +1,35 @@
+use codexmanager_core::rpc::types::{
+    JsonRpcRequest, JsonRpcResponse, ModelFallbackChainItem, ModelFallbackChainListResult,
+};
+use serde_json::json;
+
+use crate::model_fallback_chains;
+
+fn fallback_chain_param(req: &JsonRpcRequest) -> Result<ModelFallbackChainItem, String> {
+    let params = req
+        .params
+        .as_ref()
+        .ok_or_else(|| "missing model fallback params".to_string())?;
+    let item = params.get("item").unwrap_or(params).clone();
+    serde_json::from_value(item).map_err(|err| format!("invalid model fallback payload: {err}"))
+}
+
+pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let result = match req.method.as_str() {
+        "modelFallback/list" => super::value_or_error(
+            model_fallback_chains::read_model_fallback_chains(super::str_param(req, "keyId"))
+                .map(|items| ModelFallbackChainListResult { items }),
+        ),
+        "modelFallback/save" => super::value_or_error(
+            fallback_chain_param(req)
+                .and_then(model_fallback_chains::save_model_fallback_chain)
+                .map(|id| json!({ "id": id })),
+        ),
+        "modelFallback/delete" => super::ok_or_error(
+            model_fallback_chains::delete_model_fallback_chain(super::i64_param(req, "id")),
+        ),
+        _ => return None,
+    };
+
+    Some(super::response(req, result))
+}
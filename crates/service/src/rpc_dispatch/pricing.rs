@@ -0,0 +1,48 @@
+use codexmanager_core::rpc::types::{
+    JsonRpcRequest, JsonRpcResponse, ModelPricingItem, ModelPricingListResult,
+};
+
+use crate::model_pricing;
+
+fn pricing_item_param(req: &JsonRpcRequest) -> Result<ModelPricingItem, String> {
+    let params = req
+        .params
+        .as_ref()
+        .ok_or_else(|| "missing model pricing params".to_string())?;
+    let item = params.get("item").unwrap_or(params).clone();
+    serde_json::from_value(item).map_err(|err| format!("invalid model pricing payload: {err}"))
+}
+
+pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let result = match req.method.as_str() {
+        "pricing/list" => super::value_or_error(
+            model_pricing::read_model_pricing().map(|items| ModelPricingListResult { items }),
+        ),
+        "pricing/upsert" => super::ok_or_error(
+            pricing_item_param(req).and_then(model_pricing::upsert_model_pricing),
+        ),
+        "pricing/delete" => {
+            let model_pattern = super::str_param(req, "modelPattern").unwrap_or("");
+            let match_type = super::str_param(req, "matchType");
+            let input_tokens_above = super::i64_param(req, "inputTokensAbove");
+            super::ok_or_error(model_pricing::delete_model_pricing(
+                model_pattern,
+                match_type,
+                input_tokens_above,
+            ))
+        }
+        "pricing/export" => super::value_or_error(model_pricing::export_model_pricing()),
+        "pricing/import" => {
+            let contents = super::str_param(req, "json").unwrap_or("");
+            let replace = super::bool_param(req, "replace").unwrap_or(false);
+            super::value_or_error(model_pricing::import_model_pricing(contents, replace))
+        }
+        "pricing/recompute" => {
+            let since = super::i64_param(req, "since");
+            super::value_or_error(model_pricing::recompute_request_costs(since))
+        }
+        _ => return None,
+    };
+
+    Some(super::response(req, result))
+}
@@ -0,0 +1,35 @@
+use codexmanager_core::rpc::types::{
+    JsonRpcRequest, JsonRpcResponse, ProviderAccountItem, ProviderAccountListResult,
+};
+use serde_json::json;
+
+use crate::provider_accounts;
+
+fn provider_account_param(req: &JsonRpcRequest) -> Result<ProviderAccountItem, String> {
+    let params = req
+        .params
+        .as_ref()
+        .ok_or_else(|| "missing provider params".to_string())?;
+    let item = params.get("item").unwrap_or(params).clone();
+    serde_json::from_value(item).map_err(|err| format!("invalid provider payload: {err}"))
+}
+
+pub(super) fn try_handle(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let result = match req.method.as_str() {
+        "provider/list" => super::value_or_error(
+            provider_accounts::read_provider_accounts()
+                .map(|items| ProviderAccountListResult { items }),
+        ),
+        "provider/save" => super::value_or_error(
+            provider_account_param(req)
+                .and_then(provider_accounts::save_provider_account)
+                .map(|id| json!({ "id": id })),
+        ),
+        "provider/delete" => super::ok_or_error(provider_accounts::delete_provider_account(
+            super::str_param(req, "id"),
+        )),
+        _ => return None,
+    };
+
+    Some(super::response(req, result))
+}
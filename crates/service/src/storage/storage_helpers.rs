@@ -136,6 +136,38 @@ pub(crate) fn generate_key_id() -> String {
     out
 }
 
+pub(crate) fn generate_provider_account_id() -> String {
+    // 服务商账号 ID 带固定前缀，避免与 OAuth 账号 ID 混淆
+    let mut buf = [0u8; 6];
+    rand::rngs::OsRng.fill_bytes(&mut buf);
+    let mut out = String::from("prov_");
+    for b in buf {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+pub(crate) fn generate_batch_file_id() -> String {
+    // Batch API 文件 ID 沿用 OpenAI 的 `file-` 前缀，便于 SDK 按原样处理
+    let mut buf = [0u8; 12];
+    rand::rngs::OsRng.fill_bytes(&mut buf);
+    let mut out = String::from("file-");
+    for b in buf {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+pub(crate) fn generate_batch_id() -> String {
+    let mut buf = [0u8; 12];
+    rand::rngs::OsRng.fill_bytes(&mut buf);
+    let mut out = String::from("batch_");
+    for b in buf {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
 #[cfg(test)]
 static STORAGE_OPEN_COUNTS: std::sync::OnceLock<std::sync::Mutex<HashMap<String, usize>>> =
     std::sync::OnceLock::new();
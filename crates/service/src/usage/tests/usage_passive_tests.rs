@@ -0,0 +1,152 @@
+use super::{
+    build_passive_usage_value, clear_observations_for_tests, observed_from_usage_limit,
+    observed_within, parse_rate_limit_headers, parse_usage_limit_resets_at,
+    should_write_observation, ObservedRateLimits, ObservedWindow,
+};
+use codexmanager_core::storage::UsageSnapshotRecord;
+use codexmanager_core::usage::parse_usage_snapshot;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+    let mut out = HeaderMap::new();
+    for (name, value) in pairs {
+        out.insert(
+            HeaderName::from_bytes(name.as_bytes()).expect("header name"),
+            HeaderValue::from_str(value).expect("header value"),
+        );
+    }
+    out
+}
+
+fn snapshot(account_id: &str) -> UsageSnapshotRecord {
+    UsageSnapshotRecord {
+        account_id: account_id.to_string(),
+        used_percent: Some(40.0),
+        window_minutes: Some(300),
+        resets_at: Some(2_000),
+        secondary_used_percent: Some(70.0),
+        secondary_window_minutes: Some(10_080),
+        secondary_resets_at: Some(9_000),
+        credits_json: Some(r#"{"has_credits":false}"#.to_string()),
+        captured_at: 100,
+    }
+}
+
+#[test]
+fn parse_rate_limit_headers_reads_both_windows_and_credits() {
+    let observed = parse_rate_limit_headers(
+        &headers(&[
+            ("x-codex-primary-used-percent", "12.5"),
+            ("x-codex-primary-window-minutes", "300"),
+            ("x-codex-primary-reset-at", "1700000000"),
+            ("x-codex-secondary-used-percent", "100"),
+            ("x-codex-secondary-window-minutes", "10080"),
+            ("x-codex-secondary-reset-after-seconds", "600"),
+            ("x-codex-credits-has-credits", "true"),
+            ("x-codex-credits-balance", "5.00"),
+        ]),
+        1_000,
+    )
+    .expect("observed");
+
+    assert_eq!(
+        observed.primary,
+        ObservedWindow {
+            used_percent: Some(12.5),
+            window_minutes: Some(300),
+            resets_at: Some(1_700_000_000),
+        }
+    );
+    assert_eq!(observed.secondary.used_percent, Some(100.0));
+    assert_eq!(observed.secondary.resets_at, Some(1_600));
+    let credits = observed.credits.expect("credits");
+    assert_eq!(credits["has_credits"], true);
+    assert_eq!(credits["balance"], "5.00");
+}
+
+#[test]
+fn parse_rate_limit_headers_ignores_responses_without_usage_headers() {
+    assert!(
+        parse_rate_limit_headers(&headers(&[("content-type", "application/json")]), 0).is_none()
+    );
+}
+
+#[test]
+fn parse_usage_limit_resets_at_reads_429_error_body() {
+    let body = br#"{"error":{"type":"usage_limit_reached","resets_at":1700000123}}"#;
+    assert_eq!(
+        parse_usage_limit_resets_at(429, body, 0),
+        Some(1_700_000_123)
+    );
+
+    let body = br#"{"error":{"type":"usage_limit_reached","resets_in_seconds":90}}"#;
+    assert_eq!(parse_usage_limit_resets_at(429, body, 1_000), Some(1_090));
+
+    assert_eq!(parse_usage_limit_resets_at(500, body, 1_000), None);
+    assert_eq!(parse_usage_limit_resets_at(429, b"slow down", 1_000), None);
+}
+
+#[test]
+fn passive_usage_value_round_trips_through_snapshot_parser() {
+    let observed = ObservedRateLimits {
+        primary: ObservedWindow {
+            used_percent: Some(55.0),
+            window_minutes: Some(300),
+            resets_at: Some(3_000),
+        },
+        ..ObservedRateLimits::default()
+    };
+    let previous = snapshot("acc-roundtrip");
+    let value = build_passive_usage_value(&observed, Some(&previous), 1_000);
+    let parsed = parse_usage_snapshot(&value);
+
+    assert_eq!(parsed.used_percent, Some(55.0));
+    assert_eq!(parsed.window_minutes, Some(300));
+    assert_eq!(parsed.resets_at, Some(3_000));
+    // 中文注释：头里缺失的次窗口沿用上一次快照。
+    assert_eq!(parsed.secondary_used_percent, Some(70.0));
+    assert_eq!(parsed.secondary_window_minutes, Some(10_080));
+    assert_eq!(
+        parsed.credits_json.as_deref(),
+        Some(r#"{"has_credits":false}"#)
+    );
+}
+
+#[test]
+fn passive_usage_value_drops_expired_previous_window() {
+    let previous = snapshot("acc-expired");
+    let value = build_passive_usage_value(&ObservedRateLimits::default(), Some(&previous), 5_000);
+    let parsed = parse_usage_snapshot(&value);
+
+    assert_eq!(parsed.used_percent, None);
+    assert_eq!(parsed.window_minutes, Some(300));
+    assert_eq!(parsed.secondary_used_percent, Some(70.0));
+}
+
+#[test]
+fn usage_limit_marks_binding_window_exhausted() {
+    let previous = snapshot("acc-limit");
+    let observed = observed_from_usage_limit(Some(&previous), 8_000);
+    assert_eq!(observed.secondary.used_percent, Some(100.0));
+    assert_eq!(observed.secondary.resets_at, Some(8_000));
+    assert!(observed.primary.used_percent.is_none());
+
+    let observed = observed_from_usage_limit(None, 8_000);
+    assert_eq!(observed.primary.used_percent, Some(100.0));
+}
+
+#[test]
+fn passive_observation_throttles_writes_until_exhaustion_changes() {
+    clear_observations_for_tests();
+    let account_id = "acc-throttle";
+
+    assert!(should_write_observation(account_id, false, 1_000));
+    assert!(!should_write_observation(account_id, false, 1_010));
+    assert!(should_write_observation(account_id, true, 1_020));
+    assert!(!should_write_observation(account_id, true, 1_030));
+    assert!(should_write_observation(account_id, true, 1_090));
+
+    assert!(observed_within(account_id, 1_100, 600));
+    assert!(!observed_within(account_id, 1_700, 600));
+    assert!(!observed_within("acc-never-seen", 1_100, 600));
+}
@@ -0,0 +1,324 @@
+use codexmanager_core::storage::{now_ts, Storage, UsageSnapshotRecord};
+use reqwest::header::HeaderMap;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::usage_snapshot_store::store_usage_snapshot;
+
+// 中文注释：Codex 上游每个响应都会带主/次窗口用量头；网关据此被动更新快照，
+// 避免两次轮询之间继续把流量路由到已耗尽的账号。
+const HEADER_PRIMARY_PREFIX: &str = "x-codex-primary";
+const HEADER_SECONDARY_PREFIX: &str = "x-codex-secondary";
+const HEADER_CREDITS_HAS_CREDITS: &str = "x-codex-credits-has-credits";
+const HEADER_CREDITS_UNLIMITED: &str = "x-codex-credits-unlimited";
+const HEADER_CREDITS_BALANCE: &str = "x-codex-credits-balance";
+const PASSIVE_SNAPSHOT_MIN_WRITE_INTERVAL_SECS: i64 = 60;
+
+static PASSIVE_USAGE_OBSERVATIONS: OnceLock<Mutex<HashMap<String, PassiveUsageObservation>>> =
+    OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct ObservedWindow {
+    pub used_percent: Option<f64>,
+    pub window_minutes: Option<i64>,
+    pub resets_at: Option<i64>,
+}
+
+impl ObservedWindow {
+    fn is_empty(&self) -> bool {
+        self.used_percent.is_none() && self.window_minutes.is_none() && self.resets_at.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ObservedRateLimits {
+    pub primary: ObservedWindow,
+    pub secondary: ObservedWindow,
+    pub credits: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PassiveUsageObservation {
+    observed_at: i64,
+    written_at: i64,
+    exhausted: bool,
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn header_f64(headers: &HeaderMap, name: &str) -> Option<f64> {
+    header_str(headers, name)
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|value| value.is_finite())
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    let raw = header_str(headers, name)?;
+    raw.parse::<i64>()
+        .ok()
+        .or_else(|| raw.parse::<f64>().ok().map(|value| value as i64))
+}
+
+fn header_bool(headers: &HeaderMap, name: &str) -> Option<bool> {
+    match header_str(headers, name)?.to_ascii_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_window_headers(headers: &HeaderMap, prefix: &str, now: i64) -> ObservedWindow {
+    let resets_at = header_i64(headers, &format!("{prefix}-reset-at")).or_else(|| {
+        header_i64(headers, &format!("{prefix}-reset-after-seconds")).map(|secs| now + secs.max(0))
+    });
+    ObservedWindow {
+        used_percent: header_f64(headers, &format!("{prefix}-used-percent")),
+        window_minutes: header_i64(headers, &format!("{prefix}-window-minutes")),
+        resets_at,
+    }
+}
+
+/// 解析 Codex 上游响应头中的主/次窗口用量；没有任何相关头时返回 None。
+pub(crate) fn parse_rate_limit_headers(
+    headers: &HeaderMap,
+    now: i64,
+) -> Option<ObservedRateLimits> {
+    let primary = parse_window_headers(headers, HEADER_PRIMARY_PREFIX, now);
+    let secondary = parse_window_headers(headers, HEADER_SECONDARY_PREFIX, now);
+    let has_credits = header_bool(headers, HEADER_CREDITS_HAS_CREDITS);
+    let unlimited = header_bool(headers, HEADER_CREDITS_UNLIMITED);
+    let balance = header_str(headers, HEADER_CREDITS_BALANCE);
+    let credits = if has_credits.is_some() || unlimited.is_some() || balance.is_some() {
+        Some(json!({
+            "has_credits": has_credits,
+            "unlimited": unlimited,
+            "balance": balance,
+        }))
+    } else {
+        None
+    };
+    if primary.used_percent.is_none() && secondary.used_percent.is_none() && credits.is_none() {
+        return None;
+    }
+    Some(ObservedRateLimits {
+        primary,
+        secondary,
+        credits,
+    })
+}
+
+/// 解析 429 错误体中的额度重置时间（`resets_at` 或 `resets_in_seconds`）。
+pub(crate) fn parse_usage_limit_resets_at(status_code: u16, body: &[u8], now: i64) -> Option<i64> {
+    if status_code != 429 || body.is_empty() {
+        return None;
+    }
+    let value = serde_json::from_slice::<Value>(body).ok()?;
+    let error = value.get("error").filter(|error| error.is_object());
+    let lookup = |key: &str| {
+        error
+            .and_then(|error| error.get(key))
+            .or_else(|| value.get(key))
+            .and_then(|item| item.as_i64().or_else(|| item.as_f64().map(|v| v as i64)))
+    };
+    lookup("resets_at").or_else(|| lookup("resets_in_seconds").map(|secs| now + secs.max(0)))
+}
+
+fn window_value(window: &ObservedWindow) -> Value {
+    let mut out = Map::new();
+    if let Some(used_percent) = window.used_percent {
+        out.insert("used_percent".to_string(), json!(used_percent));
+    }
+    if let Some(window_minutes) = window.window_minutes {
+        out.insert(
+            "limit_window_seconds".to_string(),
+            json!(window_minutes.saturating_mul(60)),
+        );
+    }
+    if let Some(resets_at) = window.resets_at {
+        out.insert("reset_at".to_string(), json!(resets_at));
+    }
+    Value::Object(out)
+}
+
+fn merge_window(observed: ObservedWindow, previous: ObservedWindow, now: i64) -> ObservedWindow {
+    // 中文注释：头里缺失的字段沿用上一次快照；但上一次的窗口已过重置时间时，旧百分比不再可信。
+    let previous_expired = previous.resets_at.is_some_and(|resets_at| resets_at <= now);
+    ObservedWindow {
+        used_percent: observed.used_percent.or(if previous_expired {
+            None
+        } else {
+            previous.used_percent
+        }),
+        window_minutes: observed.window_minutes.or(previous.window_minutes),
+        resets_at: observed.resets_at.or(if previous_expired {
+            None
+        } else {
+            previous.resets_at
+        }),
+    }
+}
+
+fn previous_windows(previous: Option<&UsageSnapshotRecord>) -> (ObservedWindow, ObservedWindow) {
+    let Some(previous) = previous else {
+        return (ObservedWindow::default(), ObservedWindow::default());
+    };
+    (
+        ObservedWindow {
+            used_percent: previous.used_percent,
+            window_minutes: previous.window_minutes,
+            resets_at: previous.resets_at,
+        },
+        ObservedWindow {
+            used_percent: previous.secondary_used_percent,
+            window_minutes: previous.secondary_window_minutes,
+            resets_at: previous.secondary_resets_at,
+        },
+    )
+}
+
+/// 把被动观测到的用量与上一次快照合并成 `/wham/usage` 同构的 JSON，交给快照存储统一解析。
+pub(crate) fn build_passive_usage_value(
+    observed: &ObservedRateLimits,
+    previous: Option<&UsageSnapshotRecord>,
+    now: i64,
+) -> Value {
+    let (previous_primary, previous_secondary) = previous_windows(previous);
+    let primary = merge_window(observed.primary, previous_primary, now);
+    let secondary = merge_window(observed.secondary, previous_secondary, now);
+    let mut rate_limit = Map::new();
+    if !primary.is_empty() {
+        rate_limit.insert("primary_window".to_string(), window_value(&primary));
+    }
+    if !secondary.is_empty() {
+        rate_limit.insert("secondary_window".to_string(), window_value(&secondary));
+    }
+    let credits = observed.credits.clone().or_else(|| {
+        previous
+            .and_then(|record| record.credits_json.as_deref())
+            .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+    });
+    let mut out = Map::new();
+    out.insert("rate_limit".to_string(), Value::Object(rate_limit));
+    if let Some(credits) = credits {
+        out.insert("credits".to_string(), credits);
+    }
+    Value::Object(out)
+}
+
+/// 429 只给出重置时间时，把占用更高的窗口标记为耗尽，直到该时间点。
+pub(crate) fn observed_from_usage_limit(
+    previous: Option<&UsageSnapshotRecord>,
+    resets_at: i64,
+) -> ObservedRateLimits {
+    let (previous_primary, previous_secondary) = previous_windows(previous);
+    let exhausted = ObservedWindow {
+        used_percent: Some(100.0),
+        window_minutes: None,
+        resets_at: Some(resets_at),
+    };
+    let secondary_is_binding = previous_secondary.window_minutes.is_some()
+        && previous_secondary.used_percent.unwrap_or(0.0)
+            > previous_primary.used_percent.unwrap_or(0.0);
+    if secondary_is_binding {
+        ObservedRateLimits {
+            secondary: exhausted,
+            ..ObservedRateLimits::default()
+        }
+    } else {
+        ObservedRateLimits {
+            primary: exhausted,
+            ..ObservedRateLimits::default()
+        }
+    }
+}
+
+fn is_exhausted(observed: &ObservedRateLimits) -> bool {
+    [
+        observed.primary.used_percent,
+        observed.secondary.used_percent,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|value| value >= 100.0)
+}
+
+fn observations() -> &'static Mutex<HashMap<String, PassiveUsageObservation>> {
+    PASSIVE_USAGE_OBSERVATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 记录一次观测并判断是否需要落库：耗尽状态变化时立即写，否则按最小间隔节流。
+fn should_write_observation(account_id: &str, exhausted: bool, now: i64) -> bool {
+    let mut map = crate::lock_utils::lock_recover(observations(), "passive_usage_observations");
+    let entry = map
+        .entry(account_id.to_string())
+        .or_insert(PassiveUsageObservation {
+            observed_at: now,
+            written_at: i64::MIN,
+            exhausted: !exhausted,
+        });
+    entry.observed_at = now;
+    let due = entry.exhausted != exhausted
+        || now.saturating_sub(entry.written_at) >= PASSIVE_SNAPSHOT_MIN_WRITE_INTERVAL_SECS;
+    if due {
+        entry.written_at = now;
+        entry.exhausted = exhausted;
+    }
+    due
+}
+
+/// 后台轮询据此跳过最近刚被网关流量观测过的账号。
+pub(crate) fn observed_within(account_id: &str, now: i64, window_secs: i64) -> bool {
+    let map = crate::lock_utils::lock_recover(observations(), "passive_usage_observations");
+    map.get(account_id)
+        .is_some_and(|entry| now.saturating_sub(entry.observed_at) < window_secs)
+}
+
+fn record_observation(storage: &Storage, account_id: &str, observed: &ObservedRateLimits) {
+    let now = now_ts();
+    if !should_write_observation(account_id, is_exhausted(observed), now) {
+        return;
+    }
+    let previous = storage
+        .latest_usage_snapshot_for_account(account_id)
+        .ok()
+        .flatten();
+    let value = build_passive_usage_value(observed, previous.as_ref(), now);
+    if let Err(err) = store_usage_snapshot(storage, account_id, value) {
+        log::warn!("passive usage snapshot store failed: account_id={account_id} err={err}");
+        return;
+    }
+    crate::gateway::invalidate_candidate_cache();
+}
+
+/// 从上游响应头被动更新账号用量快照。
+pub(crate) fn observe_rate_limit_headers(storage: &Storage, account_id: &str, headers: &HeaderMap) {
+    if let Some(observed) = parse_rate_limit_headers(headers, now_ts()) {
+        record_observation(storage, account_id, &observed);
+    }
+}
+
+/// 从 429 错误体中的重置时间被动标记账号额度耗尽。
+pub(crate) fn observe_usage_limit_reset(storage: &Storage, account_id: &str, resets_at: i64) {
+    let previous = storage
+        .latest_usage_snapshot_for_account(account_id)
+        .ok()
+        .flatten();
+    let observed = observed_from_usage_limit(previous.as_ref(), resets_at);
+    record_observation(storage, account_id, &observed);
+}
+
+#[cfg(test)]
+fn clear_observations_for_tests() {
+    crate::lock_utils::lock_recover(observations(), "passive_usage_observations").clear();
+}
+
+#[cfg(test)]
+#[path = "tests/usage_passive_tests.rs"]
+mod tests;
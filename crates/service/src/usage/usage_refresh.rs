@@ -17,6 +17,7 @@ use crate::usage_account_meta::{
 };
 use crate::usage_http::fetch_usage_snapshot;
 use crate::usage_keepalive::{is_keepalive_error_ignorable, run_gateway_keepalive_once};
+use crate::usage_passive::observed_within;
 use crate::usage_scheduler::{
     parse_interval_secs, DEFAULT_GATEWAY_KEEPALIVE_FAILURE_BACKOFF_MAX_SECS,
     DEFAULT_GATEWAY_KEEPALIVE_INTERVAL_SECS, DEFAULT_GATEWAY_KEEPALIVE_JITTER_SECS,
@@ -390,7 +391,7 @@ fn usage_polling_loop() {
                 interval_secs,
             )
         },
-        refresh_usage_for_all_accounts,
+        refresh_usage_for_polling,
         |_| true,
     );
 }
@@ -642,8 +643,22 @@ fn clear_pending_usage_refresh_tasks_for_tests() {
 
 pub(crate) fn refresh_usage_for_all_accounts() -> Result<(), String> {
     // 批量刷新所有账号用量
+    refresh_usage_for_accounts(None)
+}
+
+fn refresh_usage_for_polling() -> Result<(), String> {
+    // 中文注释：网关已从上游响应头被动拿到用量的账号，本轮轮询直接跳过，减少 /wham/usage 请求。
+    let observed_window_secs = USAGE_POLL_INTERVAL_SECS.load(Ordering::Relaxed) as i64;
+    refresh_usage_for_accounts(Some(observed_window_secs))
+}
+
+fn refresh_usage_for_accounts(skip_observed_within_secs: Option<i64>) -> Result<(), String> {
     let storage = open_storage().ok_or_else(|| "storage unavailable".to_string())?;
-    let tokens = storage.list_tokens().map_err(|e| e.to_string())?;
+    let mut tokens = storage.list_tokens().map_err(|e| e.to_string())?;
+    if let Some(window_secs) = skip_observed_within_secs {
+        let now = now_ts();
+        tokens.retain(|token| !observed_within(&token.account_id, now, window_secs));
+    }
     if tokens.is_empty() {
         return Ok(());
     }
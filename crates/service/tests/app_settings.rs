@@ -40,6 +40,17 @@ fn reset_runtime_defaults() {
             "httpWorkerMin": 8,
             "httpStreamWorkerFactor": 1,
             "httpStreamWorkerMin": 2
+        },
+        "cooldownPolicy": {
+            "defaultSecs": 20,
+            "networkSecs": 20,
+            "upstream4xxSecs": 20,
+            "upstream5xxSecs": 30,
+            "challengeSecs": 6,
+            "rateLimitLadderSecs": [45, 300, 1800, 7200],
+            "rateLimitForgetAfterSecs": 1800,
+            "rateLimitSource": "ladder",
+            "rateLimitMaxSecs": 21600
         }
     })));
 }
@@ -332,6 +343,45 @@ fn sync_runtime_settings_from_storage_applies_saved_runtime_values() {
     });
 }
 
+#[test]
+fn app_settings_set_applies_and_persists_cooldown_policy() {
+    with_temp_db(|db_path| {
+        let snapshot = codexmanager_service::app_settings_set(Some(&json!({
+            "cooldownPolicy": {
+                "upstream5xxSecs": 90,
+                "rateLimitLadderSecs": [30, 120],
+                "rateLimitSource": "retry_after"
+            }
+        })))
+        .expect("set cooldown policy");
+        let policy = snapshot.get("cooldownPolicy").expect("cooldownPolicy");
+        assert_eq!(policy["upstream5xxSecs"], 90);
+        assert_eq!(policy["challengeSecs"], 6);
+        assert_eq!(policy["rateLimitLadderSecs"], json!([30, 120]));
+        assert_eq!(policy["rateLimitSource"], "retry_after");
+        assert_eq!(
+            std::env::var("CODEXMANAGER_COOLDOWN_429_LADDER_SECS")
+                .ok()
+                .as_deref(),
+            Some("30,120")
+        );
+
+        let storage = Storage::open(db_path).expect("open storage");
+        let raw = storage
+            .get_app_setting(codexmanager_service::APP_SETTING_GATEWAY_COOLDOWN_POLICY_KEY)
+            .expect("read cooldown policy")
+            .expect("cooldown policy exists");
+        let persisted: serde_json::Value = serde_json::from_str(&raw).expect("parse policy");
+        assert_eq!(persisted["upstream5xxSecs"], 90);
+
+        let err = codexmanager_service::app_settings_set(Some(&json!({
+            "cooldownPolicy": { "rateLimitSource": "whenever" }
+        })))
+        .expect_err("invalid source rejected");
+        assert!(err.contains("rateLimitSource"), "unexpected error: {err}");
+    });
+}
+
 #[test]
 fn app_settings_get_loads_env_backed_dedicated_settings_when_storage_missing() {
     with_temp_db(|db_path| {
@@ -32,6 +32,8 @@ fn metrics_prometheus_contains_expected_series() {
     assert!(text.contains("codexmanager_gateway_account_inflight_total "));
     assert!(text.contains("codexmanager_gateway_failover_attempts_total "));
     assert!(text.contains("codexmanager_gateway_cooldown_marks_total "));
+    assert!(text.contains("codexmanager_gateway_admission_queue_depth "));
+    assert!(text.contains("codexmanager_gateway_admission_queue_wait_milliseconds_total "));
     assert!(text.contains("codexmanager_rpc_requests_total "));
     assert!(text.contains("codexmanager_rpc_requests_failed_total "));
     assert!(text.contains("codexmanager_rpc_request_duration_milliseconds_total "));
@@ -1,5 +1,9 @@
 use codexmanager_core::rpc::types::ModelOption;
-use codexmanager_core::storage::{now_ts, Account, ApiKey, Storage, Token};
+use codexmanager_core::storage::{
+    now_ts, Account, AccountCooldownRecord, ApiKey, ApiKeyBudget, ModelAliasRule,
+    ModelFallbackChain, ModelPricing, ProviderAccount, RequestLog, RequestTokenStat, Storage,
+    Token,
+};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
@@ -116,6 +120,16 @@ fn decode_chunked_body_if_needed(body: &str) -> String {
 }
 
 fn post_http_raw(addr: &str, path: &str, body: &str, headers: &[(&str, &str)]) -> (u16, String) {
+    let (status, _response_headers, body) = post_http_raw_with_headers(addr, path, body, headers);
+    (status, body)
+}
+
+fn post_http_raw_with_headers(
+    addr: &str,
+    path: &str,
+    body: &str,
+    headers: &[(&str, &str)],
+) -> (u16, String, String) {
     let mut last_raw = String::new();
     for _ in 0..20 {
         let mut stream = TcpStream::connect(addr).expect("connect server");
@@ -138,9 +152,10 @@ fn post_http_raw(addr: &str, path: &str, body: &str, headers: &[(&str, &str)]) -
             .and_then(|line| line.split_whitespace().nth(1))
             .and_then(|value| value.parse::<u16>().ok())
         {
+            let response_headers = buf.split("\r\n\r\n").next().unwrap_or("").to_string();
             let body_raw = buf.split("\r\n\r\n").nth(1).unwrap_or("").to_string();
             let body = decode_chunked_body_if_needed(&body_raw);
-            return (status, body);
+            return (status, response_headers, body);
         }
         last_raw = buf;
         thread::sleep(Duration::from_millis(50));
@@ -612,6 +627,11 @@ fn gateway_claude_protocol_end_to_end_uses_codex_headers() {
             auth_scheme: "x_api_key".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -781,6 +801,11 @@ fn gateway_openai_stream_logs_cached_and_reasoning_tokens() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -895,6 +920,11 @@ fn gateway_openai_stream_usage_with_plain_content_type() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1009,6 +1039,11 @@ fn gateway_openai_non_stream_sse_with_plain_content_type_is_collapsed_to_json()
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1047,7 +1082,7 @@ fn gateway_openai_non_stream_sse_with_plain_content_type_is_collapsed_to_json()
 }
 
 #[test]
-fn gateway_openai_non_stream_without_usage_keeps_tokens_null() {
+fn gateway_openai_non_stream_without_usage_estimates_tokens_locally() {
     let _lock = lock_env();
     let dir = new_test_dir("codexmanager-gateway-openai-no-usage");
     let db_path: PathBuf = dir.join("codexmanager.db");
@@ -1110,6 +1145,11 @@ fn gateway_openai_non_stream_without_usage_keeps_tokens_null() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1161,10 +1201,13 @@ fn gateway_openai_non_stream_without_usage_keeps_tokens_null() {
 
     let log = matched.expect("openai no usage request log");
     assert_eq!(log.status_code, Some(200), "log error: {:?}", log.error);
-    assert_eq!(log.input_tokens, None);
+    // 上游未返回 usage 时，日志用本地 tokenizer 估算输入/输出 token。
+    let input_tokens = log.input_tokens.expect("estimated input tokens");
+    let output_tokens = log.output_tokens.expect("estimated output tokens");
+    assert!(input_tokens > 0);
+    assert!(output_tokens > 0);
+    assert_eq!(log.total_tokens, Some(input_tokens + output_tokens));
     assert_eq!(log.cached_input_tokens, None);
-    assert_eq!(log.output_tokens, None);
-    assert_eq!(log.total_tokens, None);
     assert_eq!(log.reasoning_output_tokens, None);
 }
 
@@ -1196,6 +1239,11 @@ fn gateway_models_returns_cached_without_upstream() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1311,6 +1359,11 @@ fn gateway_openai_fallback_strips_turn_state_headers() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1430,6 +1483,11 @@ fn gateway_cpa_no_cookie_header_mode_suppresses_affinity_headers_on_responses()
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1552,6 +1610,11 @@ fn gateway_cpa_no_cookie_header_mode_binds_prompt_cache_key_to_session_only() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1677,6 +1740,11 @@ fn gateway_cpa_no_cookie_header_mode_skips_post_retries_on_404() {
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1791,6 +1859,11 @@ fn gateway_stateless_retry_strips_encrypted_content_on_invalid_encrypted_content
             auth_scheme: "authorization_bearer".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -1945,6 +2018,11 @@ fn gateway_claude_failover_cross_workspace_strips_session_affinity_headers() {
             auth_scheme: "x_api_key".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -2113,6 +2191,11 @@ fn gateway_claude_failover_same_workspace_preserves_session_affinity_headers() {
             auth_scheme: "x_api_key".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -2266,6 +2349,11 @@ fn gateway_request_log_keeps_only_final_result_for_multi_attempt_flow() {
             auth_scheme: "x_api_key".to_string(),
             upstream_base_url: None,
             static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
             key_hash: hash_platform_key_for_test(platform_key),
             status: "active".to_string(),
             created_at: now,
@@ -2327,3 +2415,2935 @@ fn gateway_request_log_keeps_only_final_result_for_multi_attempt_flow() {
     assert!(trace_text.contains("event=ATTEMPT_RESULT"));
     assert!(trace_text.contains("event=REQUEST_FINAL"));
 }
+
+#[test]
+fn gateway_rejects_key_when_bound_account_group_has_no_candidates() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-account-group");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    storage
+        .insert_account(&Account {
+            id: "acc_group_a".to_string(),
+            label: "group-a".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_group_a".to_string()),
+            workspace_id: None,
+            group_name: Some("TEAM-A".to_string()),
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_group_a".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_group_a".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: None,
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_account_group";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_account_group".to_string(),
+            name: Some("account-group".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: Some("TEAM-B".to_string()),
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.3-codex","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 503, "gateway response: {gateway_body}");
+    assert!(
+        gateway_body.contains("no available account in group(s) TEAM-B"),
+        "gateway response: {gateway_body}"
+    );
+}
+
+#[test]
+fn gateway_blocks_key_when_daily_token_budget_is_exhausted() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-key-budget");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    let platform_key = "pk_key_budget";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_key_budget".to_string(),
+            name: Some("key-budget".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    storage
+        .upsert_api_key_budget(&ApiKeyBudget {
+            key_id: "gk_key_budget".to_string(),
+            period: "daily".to_string(),
+            token_limit: Some(100),
+            cost_limit_usd: None,
+            warn_percent: 80.0,
+            hard_block: true,
+            reset_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert budget");
+    storage
+        .insert_request_token_stat(&RequestTokenStat {
+            request_log_id: 1,
+            key_id: Some("gk_key_budget".to_string()),
+            account_id: None,
+            model: Some("gpt-5.3-codex".to_string()),
+            input_tokens: Some(90),
+            cached_input_tokens: None,
+            output_tokens: Some(30),
+            total_tokens: Some(120),
+            reasoning_output_tokens: None,
+            estimated_cost_usd: None,
+            created_at: now,
+        })
+        .expect("insert token stat");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.3-codex","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 429, "gateway response: {gateway_body}");
+    let value: serde_json::Value = serde_json::from_str(&gateway_body).expect("parse body");
+    assert_eq!(value["error"]["code"], "insufficient_quota");
+    assert!(
+        value["error"]["message"]
+            .as_str()
+            .is_some_and(|message| message.starts_with("api key budget exceeded: daily budget")),
+        "gateway response: {gateway_body}"
+    );
+}
+
+#[test]
+fn gateway_budget_counts_spend_of_requests_served_since_last_check() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-key-budget-running");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let ok_body = serde_json::to_string(&serde_json::json!({
+        "id": "resp_budget_running",
+        "model": "gpt-5.3-codex",
+        "output": [],
+        "usage": { "input_tokens": 6, "output_tokens": 2, "total_tokens": 8 }
+    }))
+    .expect("serialize 200 body");
+    let (upstream_addr, _upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (200, ok_body.clone()),
+        (200, ok_body.clone()),
+        (200, ok_body),
+    ]);
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_budget_running".to_string(),
+            label: "budget-running".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_budget_running".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_budget_running".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_budget_running".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_budget_running".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+    let platform_key = "pk_budget_running";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_budget_running".to_string(),
+            name: Some("budget-running".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    storage
+        .upsert_api_key_budget(&ApiKeyBudget {
+            key_id: "gk_budget_running".to_string(),
+            period: "daily".to_string(),
+            token_limit: Some(10),
+            cost_limit_usd: None,
+            warn_percent: 80.0,
+            hard_block: true,
+            reset_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert budget");
+
+    let send_request = || {
+        let server = codexmanager_service::start_one_shot_server().expect("start server");
+        let (status, _, gateway_body) = post_http_raw_with_headers(
+            &server.addr,
+            "/v1/responses",
+            r#"{"model":"gpt-5.3-codex","input":"hello","stream":false}"#,
+            &[
+                ("Content-Type", "application/json"),
+                ("Authorization", &format!("Bearer {platform_key}")),
+            ],
+        );
+        server.join();
+        (status, gateway_body)
+    };
+    let wait_for_token_stats = |count: usize| {
+        for _ in 0..40 {
+            let logs = storage
+                .list_request_logs(Some("key:=gk_budget_running"), 10)
+                .expect("list request logs");
+            if logs.iter().filter(|log| log.total_tokens.is_some()).count() >= count {
+                // 中文注释：累计值在日志落库后才更新，给写入线程留出一点余量。
+                thread::sleep(Duration::from_millis(50));
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("expected {count} request logs with token stats");
+    };
+
+    // 中文注释：第一次校验汇总到 0，第二次只靠内存累加看到 8，第三次累计 16 超出 10 被拦截。
+    for served in 1..=2 {
+        let (status, gateway_body) = send_request();
+        assert_eq!(status, 200, "gateway response: {gateway_body}");
+        wait_for_token_stats(served);
+    }
+    let (status, gateway_body) = send_request();
+    assert_eq!(status, 429, "gateway response: {gateway_body}");
+    let value: serde_json::Value = serde_json::from_str(&gateway_body).expect("parse body");
+    assert_eq!(value["error"]["code"], "insufficient_quota");
+
+    // 中文注释：手动重置后重新汇总，新周期内没有用量，请求恢复放行；
+    // 重置时间按秒记录，先跨过已有日志所在的那一秒。
+    thread::sleep(Duration::from_millis(1100));
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let token = codexmanager_service::rpc_auth_token().to_string();
+    let rpc_body = serde_json::json!({
+        "id": 1,
+        "method": "apikey/resetBudget",
+        "params": { "id": "gk_budget_running", "period": "daily" }
+    })
+    .to_string();
+    let (status, _, rpc_response) = post_http_raw_with_headers(
+        &server.addr,
+        "/rpc",
+        &rpc_body,
+        &[
+            ("Content-Type", "application/json"),
+            ("X-CodexManager-Rpc-Token", token.as_str()),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "rpc response: {rpc_response}");
+    assert!(
+        !rpc_response.contains("\"error\""),
+        "rpc response: {rpc_response}"
+    );
+
+    let (status, gateway_body) = send_request();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+    upstream_join.join().expect("join upstream");
+}
+
+#[test]
+fn gateway_rewrites_model_alias_and_logs_requested_model() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-model-alias");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let upstream_response = serde_json::json!({
+        "id": "resp_alias_1",
+        "model": "gpt-5.1-codex-mini",
+        "output": [{
+            "type": "message",
+            "role": "assistant",
+            "content": [{ "type": "output_text", "text": "pong" }]
+        }],
+        "usage": { "input_tokens": 5, "output_tokens": 1, "total_tokens": 6 }
+    });
+    let upstream_response =
+        serde_json::to_string(&upstream_response).expect("serialize upstream response");
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_once(&upstream_response);
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    storage
+        .insert_account(&Account {
+            id: "acc_model_alias".to_string(),
+            label: "model-alias".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_model_alias".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_model_alias".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_model_alias".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_model_alias".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_model_alias";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_model_alias".to_string(),
+            name: Some("model-alias".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    // 中文注释：Key 专属规则排在全局规则之前，即使全局规则的 sort 更小。
+    for (key_id, sort, target_model, reasoning_effort) in [
+        (None, 0, "gpt-5.1", None),
+        (
+            Some("gk_model_alias".to_string()),
+            10,
+            "gpt-5.1-codex-mini",
+            Some("low".to_string()),
+        ),
+    ] {
+        storage
+            .insert_model_alias_rule(&ModelAliasRule {
+                id: 0,
+                key_id,
+                sort,
+                match_kind: "glob".to_string(),
+                pattern: "team-*".to_string(),
+                target_model: target_model.to_string(),
+                reasoning_effort,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert alias rule");
+    }
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"team-fast","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+
+    let captured = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive upstream request");
+    upstream_join.join().expect("join upstream");
+    let upstream_body: serde_json::Value =
+        serde_json::from_slice(&captured.body).expect("parse upstream body");
+    assert_eq!(upstream_body["model"], "gpt-5.1-codex-mini");
+    assert_eq!(upstream_body["reasoning"]["effort"], "low");
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("requested:=team-fast"), 20)
+            .expect("list request logs");
+        matched = logs.into_iter().next();
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("model alias request log");
+    assert_eq!(log.key_id.as_deref(), Some("gk_model_alias"));
+    assert_eq!(log.model.as_deref(), Some("gpt-5.1-codex-mini"));
+    assert_eq!(log.requested_model.as_deref(), Some("team-fast"));
+}
+
+#[test]
+fn gateway_falls_back_to_next_model_when_pool_is_rate_limited() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-model-fallback");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let limited_body = serde_json::json!({
+        "error": {
+            "message": "You have hit your usage limit",
+            "type": "usage_limit_reached"
+        }
+    });
+    let ok_body = serde_json::json!({
+        "id": "resp_model_fallback",
+        "model": "gpt-5.3-codex",
+        "output": [{
+            "type": "message",
+            "role": "assistant",
+            "content": [{ "type": "output_text", "text": "served by fallback" }]
+        }],
+        "usage": { "input_tokens": 4, "output_tokens": 3, "total_tokens": 7 }
+    });
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (
+            429,
+            serde_json::to_string(&limited_body).expect("serialize 429 body"),
+        ),
+        // 中文注释：同账号的无状态重试也会命中 429，之后才整池耗尽并切换降级模型。
+        (
+            429,
+            serde_json::to_string(&limited_body).expect("serialize 429 body"),
+        ),
+        (
+            200,
+            serde_json::to_string(&ok_body).expect("serialize 200 body"),
+        ),
+    ]);
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    storage
+        .insert_account(&Account {
+            id: "acc_model_fallback".to_string(),
+            label: "model-fallback".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_model_fallback".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_model_fallback".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_model_fallback".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_model_fallback".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_model_fallback";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_model_fallback".to_string(),
+            name: Some("model-fallback".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    storage
+        .insert_model_fallback_chain(&ModelFallbackChain {
+            id: 0,
+            key_id: None,
+            model: "gpt-5.4".to_string(),
+            fallback_models_json: r#"["gpt-5.3-codex"]"#.to_string(),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert fallback chain");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, response_headers, gateway_body) = post_http_raw_with_headers(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.4","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+
+    let first = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive first upstream request");
+    let retry = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive retry upstream request");
+    let fallback = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive fallback upstream request");
+    upstream_join.join().expect("join upstream");
+    for (captured, expected_model) in [
+        (&first, "gpt-5.4"),
+        (&retry, "gpt-5.4"),
+        (&fallback, "gpt-5.3-codex"),
+    ] {
+        let body: serde_json::Value =
+            serde_json::from_slice(&captured.body).expect("parse upstream body");
+        assert_eq!(body["model"], expected_model);
+    }
+    assert!(
+        response_headers
+            .to_ascii_lowercase()
+            .contains("x-codexmanager-served-model: gpt-5.3-codex"),
+        "response headers: {response_headers}"
+    );
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("key:=gk_model_fallback"), 20)
+            .expect("list request logs");
+        matched = logs.into_iter().next();
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("model fallback request log");
+    assert_eq!(log.status_code, Some(200), "log error: {:?}", log.error);
+    assert_eq!(log.model.as_deref(), Some("gpt-5.3-codex"));
+    assert_eq!(log.requested_model.as_deref(), Some("gpt-5.4"));
+}
+
+#[test]
+fn gateway_rejects_request_with_retry_after_when_admission_queue_is_full() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-admission-full");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _enabled_guard = EnvGuard::set("CODEXMANAGER_ADMISSION_QUEUE_ENABLED", "1");
+    let _size_guard = EnvGuard::set("CODEXMANAGER_ADMISSION_QUEUE_MAX_SIZE", "1");
+    let _wait_guard = EnvGuard::set("CODEXMANAGER_ADMISSION_QUEUE_MAX_WAIT_MS", "1500");
+    let _upstream_guard = EnvGuard::set(
+        "CODEXMANAGER_UPSTREAM_BASE_URL",
+        "http://127.0.0.1:9/backend-api/codex",
+    );
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_admission_full".to_string(),
+            label: "admission-full".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_admission_full".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_admission_full".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_admission_full".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_admission_full".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+    // 中文注释：唯一账号处于冷却中，账号池整体饱和，请求只能排队。
+    storage
+        .upsert_account_cooldown(&AccountCooldownRecord {
+            account_id: "acc_admission_full".to_string(),
+            cooldown_until: now + 600,
+            reason: "rate_limited".to_string(),
+            offense_count: 1,
+            offense_last_at: now,
+            updated_at: now,
+        })
+        .expect("seed cooldown");
+    let platform_key = "pk_admission_full";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_admission_full".to_string(),
+            name: Some("admission-full".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let send_request = move |server: codexmanager_service::ServerHandle| {
+        let response = post_http_raw_with_headers(
+            &server.addr,
+            "/v1/responses",
+            r#"{"model":"gpt-5.3-codex","input":"hello","stream":false}"#,
+            &[
+                ("Content-Type", "application/json"),
+                ("Authorization", &format!("Bearer {platform_key}")),
+            ],
+        );
+        server.join();
+        response
+    };
+
+    // 中文注释：两个服务先启动好，避免启动时重载冷却状态让排队中的请求提前放行；
+    // 第一条请求占满唯一的排队名额，第二条立刻被拒。
+    let queued_server = codexmanager_service::start_one_shot_server().expect("start server");
+    let rejected_server = codexmanager_service::start_one_shot_server().expect("start server");
+    let queued = thread::spawn(move || send_request(queued_server));
+    thread::sleep(Duration::from_millis(300));
+    let (status, headers, body) = send_request(rejected_server);
+    assert_eq!(status, 503, "gateway response: {body}");
+    assert!(
+        headers.to_ascii_lowercase().contains("retry-after: 2\r\n"),
+        "headers: {headers}"
+    );
+    assert!(
+        body.contains("admission queue full"),
+        "gateway response: {body}"
+    );
+
+    let (status, headers, body) = queued.join().expect("join queued request");
+    assert_eq!(status, 503, "gateway response: {body}");
+    assert!(
+        headers.to_ascii_lowercase().contains("retry-after:"),
+        "headers: {headers}"
+    );
+    assert!(
+        body.contains("admission queue wait timeout"),
+        "gateway response: {body}"
+    );
+
+    let mut logs = Vec::new();
+    for _ in 0..40 {
+        logs = storage
+            .list_request_logs(Some("key:=gk_admission_full"), 10)
+            .expect("list request logs");
+        if logs.len() >= 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(logs.len(), 2, "logs: {logs:#?}");
+    assert!(
+        logs.iter()
+            .all(|log| log.status_code == Some(503) && log.account_id.is_none()),
+        "logs: {logs:#?}"
+    );
+    let mut errors = logs
+        .iter()
+        .filter_map(|log| log.error.as_deref())
+        .collect::<Vec<_>>();
+    errors.sort_unstable();
+    assert_eq!(
+        errors,
+        vec!["admission queue full", "admission queue wait timeout"]
+    );
+}
+
+#[test]
+fn gateway_does_not_fall_back_when_pool_is_mostly_fault_cooled() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-model-fallback-fault-cooldown");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    // 中文注释：关闭健康度 P2C，保证候选严格按账号排序，429 落在最后一个候选上。
+    let _p2c_guard = EnvGuard::set("CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED", "0");
+
+    let limited_body = serde_json::to_string(&serde_json::json!({
+        "error": {
+            "message": "You have hit your usage limit",
+            "type": "usage_limit_reached"
+        }
+    }))
+    .expect("serialize 429 body");
+    let ok_body = serde_json::to_string(&serde_json::json!({
+        "id": "resp_unexpected_fallback",
+        "model": "gpt-5.3-codex",
+        "output": [],
+        "usage": { "input_tokens": 1, "output_tokens": 1, "total_tokens": 2 }
+    }))
+    .expect("serialize 200 body");
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (429, limited_body.clone()),
+        (429, limited_body),
+        // 中文注释：若错误地切换降级模型，会命中这条成功响应。
+        (200, ok_body),
+    ]);
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    for (sort, account_id) in [
+        "acc_fault_cooled_a",
+        "acc_fault_cooled_b",
+        "acc_rate_limited",
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        storage
+            .insert_account(&Account {
+                id: account_id.to_string(),
+                label: account_id.to_string(),
+                issuer: "https://auth.openai.com".to_string(),
+                chatgpt_account_id: Some(format!("chatgpt_{account_id}")),
+                workspace_id: None,
+                group_name: None,
+                sort: sort as i64,
+                status: "active".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert account");
+        storage
+            .insert_token(&Token {
+                account_id: account_id.to_string(),
+                id_token: String::new(),
+                access_token: format!("access_token_{account_id}"),
+                refresh_token: String::new(),
+                api_key_access_token: Some(format!("api_access_token_{account_id}")),
+                last_refresh: now,
+            })
+            .expect("insert token");
+    }
+    // 中文注释：前两个账号处于 5xx 故障冷却，启动时从库里恢复。
+    for account_id in ["acc_fault_cooled_a", "acc_fault_cooled_b"] {
+        storage
+            .upsert_account_cooldown(&AccountCooldownRecord {
+                account_id: account_id.to_string(),
+                cooldown_until: now + 600,
+                reason: "upstream_5xx".to_string(),
+                offense_count: 0,
+                offense_last_at: 0,
+                updated_at: now,
+            })
+            .expect("seed cooldown");
+    }
+
+    let platform_key = "pk_model_fallback_fault_cooldown";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_model_fallback_fault_cooldown".to_string(),
+            name: Some("model-fallback-fault-cooldown".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    storage
+        .insert_model_fallback_chain(&ModelFallbackChain {
+            id: 0,
+            key_id: None,
+            model: "gpt-5.4".to_string(),
+            fallback_models_json: r#"["gpt-5.3-codex"]"#.to_string(),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert fallback chain");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, response_headers, gateway_body) = post_http_raw_with_headers(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.4","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    upstream_join.join().expect("join upstream");
+    assert_eq!(status, 429, "gateway response: {gateway_body}");
+    assert!(
+        !response_headers
+            .to_ascii_lowercase()
+            .contains("x-codexmanager-served-model"),
+        "response headers: {response_headers}"
+    );
+
+    let captured: Vec<_> = upstream_rx.try_iter().collect();
+    assert!(!captured.is_empty(), "expected upstream requests");
+    for request in &captured {
+        let body: serde_json::Value =
+            serde_json::from_slice(&request.body).expect("parse upstream body");
+        assert_eq!(body["model"], "gpt-5.4");
+    }
+}
+
+#[test]
+fn gateway_request_cost_follows_pricing_updated_over_rpc() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-pricing-cache");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let ok_body = serde_json::to_string(&serde_json::json!({
+        "id": "resp_pricing_cache",
+        "model": "pricing-cache-model",
+        "output": [],
+        "usage": { "input_tokens": 1_000_000, "output_tokens": 0, "total_tokens": 1_000_000 }
+    }))
+    .expect("serialize 200 body");
+    let (upstream_addr, _upstream_rx, upstream_join) =
+        start_mock_upstream_sequence(vec![(200, ok_body.clone()), (200, ok_body)]);
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_pricing_cache".to_string(),
+            label: "pricing-cache".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_pricing_cache".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_pricing_cache".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_pricing_cache".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_pricing_cache".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+    let platform_key = "pk_pricing_cache";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_pricing_cache".to_string(),
+            name: Some("pricing-cache".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    storage
+        .upsert_model_pricing(&ModelPricing {
+            model_pattern: "pricing-cache-model".to_string(),
+            match_type: "exact".to_string(),
+            input_tokens_above: 0,
+            input_price_per_1m: 1.0,
+            cached_input_price_per_1m: None,
+            output_price_per_1m: 1.0,
+            reasoning_output_price_per_1m: None,
+            note: None,
+            updated_at: now,
+        })
+        .expect("seed pricing");
+
+    let send_request = || {
+        let server = codexmanager_service::start_one_shot_server().expect("start server");
+        let (status, _, gateway_body) = post_http_raw_with_headers(
+            &server.addr,
+            "/v1/responses",
+            r#"{"model":"pricing-cache-model","input":"hello","stream":false}"#,
+            &[
+                ("Content-Type", "application/json"),
+                ("Authorization", &format!("Bearer {platform_key}")),
+            ],
+        );
+        server.join();
+        assert_eq!(status, 200, "gateway response: {gateway_body}");
+    };
+    let wait_for_logs = |count: usize| {
+        for _ in 0..40 {
+            let logs = storage
+                .list_request_logs(Some("key:=gk_pricing_cache"), 10)
+                .expect("list request logs");
+            if logs.len() >= count {
+                return logs;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("expected {count} request logs");
+    };
+
+    send_request();
+    let first_cost = wait_for_logs(1)[0]
+        .estimated_cost_usd
+        .expect("first request cost");
+    assert!((first_cost - 1.0).abs() < 1e-9, "cost: {first_cost}");
+
+    // 中文注释：调价 RPC 让内存价目表失效，下一条请求立即按新价计费。
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let token = codexmanager_service::rpc_auth_token().to_string();
+    let rpc_body = serde_json::json!({
+        "id": 1,
+        "method": "pricing/upsert",
+        "params": {
+            "modelPattern": "pricing-cache-model",
+            "matchType": "exact",
+            "inputPricePer1m": 3.0,
+            "outputPricePer1m": 3.0
+        }
+    })
+    .to_string();
+    let (status, _, rpc_response) = post_http_raw_with_headers(
+        &server.addr,
+        "/rpc",
+        &rpc_body,
+        &[
+            ("Content-Type", "application/json"),
+            ("X-CodexManager-Rpc-Token", token.as_str()),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "rpc response: {rpc_response}");
+
+    send_request();
+    upstream_join.join().expect("join upstream");
+    let logs = wait_for_logs(2);
+    let second_cost = logs
+        .iter()
+        .filter_map(|log| log.estimated_cost_usd)
+        .fold(f64::MIN, f64::max);
+    assert!((second_cost - 3.0).abs() < 1e-9, "logs: {logs:#?}");
+}
+
+#[test]
+fn gateway_learns_account_model_exclusion_and_skips_pair() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-model-entitlement");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    // 中文注释：关闭健康度 P2C，保证候选严格按账号排序，便于断言命中的账号。
+    let _p2c_guard = EnvGuard::set("CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED", "0");
+
+    let unsupported_body = serde_json::to_string(&serde_json::json!({
+        "detail": "The 'gpt-5.4-pro' model is not supported when using Codex with a ChatGPT account."
+    }))
+    .expect("serialize 400 body");
+    let ok_body = serde_json::to_string(&serde_json::json!({
+        "id": "resp_model_entitlement",
+        "model": "gpt-5.4-pro",
+        "output": [{
+            "type": "message",
+            "role": "assistant",
+            "content": [{ "type": "output_text", "text": "ok" }]
+        }],
+        "usage": { "input_tokens": 4, "output_tokens": 1, "total_tokens": 5 }
+    }))
+    .expect("serialize 200 body");
+    // 中文注释：后台会顺带拉取 /models，按账号与模型决定响应，避免顺序型 mock 被额外请求打乱。
+    let listener = bind_test_listener("mock upstream");
+    let upstream_addr = listener.local_addr().expect("mock upstream addr");
+    let (upstream_tx, upstream_rx) = mpsc::channel();
+    let upstream_join = thread::spawn(move || {
+        while let Some((mut stream, captured)) =
+            accept_http_request(&listener, Duration::from_secs(3))
+        {
+            let unsupported = captured.headers.get("authorization").map(String::as_str)
+                == Some("Bearer access_token_entitlement_1")
+                && String::from_utf8_lossy(&captured.body).contains("\"gpt-5.4-pro\"");
+            let (status, body) = if unsupported {
+                (400, unsupported_body.as_str())
+            } else {
+                (200, ok_body.as_str())
+            };
+            let header = format!(
+                "HTTP/1.1 {status} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(body.as_bytes());
+            let _ = stream.flush();
+            if captured.path.ends_with("/responses") {
+                let _ = upstream_tx.send(captured);
+            }
+        }
+    });
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    for index in 1..=2 {
+        storage
+            .insert_account(&Account {
+                id: format!("acc_entitlement_{index}"),
+                label: format!("entitlement-{index}"),
+                issuer: "https://auth.openai.com".to_string(),
+                chatgpt_account_id: Some(format!("chatgpt_entitlement_{index}")),
+                workspace_id: None,
+                group_name: None,
+                sort: index,
+                status: "active".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert account");
+        storage
+            .insert_token(&Token {
+                account_id: format!("acc_entitlement_{index}"),
+                id_token: String::new(),
+                access_token: format!("access_token_entitlement_{index}"),
+                refresh_token: String::new(),
+                api_key_access_token: Some(format!("api_access_token_entitlement_{index}")),
+                last_refresh: now,
+            })
+            .expect("insert token");
+    }
+
+    let platform_key = "pk_model_entitlement";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_model_entitlement".to_string(),
+            name: Some("model-entitlement".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = TestServer::start();
+    let auth_header = format!("Bearer {platform_key}");
+    let headers = [
+        ("Content-Type", "application/json"),
+        ("Authorization", auth_header.as_str()),
+    ];
+    for model in ["gpt-5.4-pro", "gpt-5.4-pro", "gpt-5.4"] {
+        let (status, response_body) = post_http_raw(
+            &server.addr,
+            "/v1/responses",
+            &format!(r#"{{"model":"{model}","input":"hello","stream":false}}"#),
+            &headers,
+        );
+        assert_eq!(status, 200, "model {model} response: {response_body}");
+    }
+    drop(server);
+
+    let mut served = Vec::new();
+    for _ in 0..4 {
+        let captured = upstream_rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("receive upstream request");
+        let body: serde_json::Value =
+            serde_json::from_slice(&captured.body).expect("parse upstream body");
+        served.push((
+            body["model"].as_str().unwrap_or_default().to_string(),
+            captured
+                .headers
+                .get("authorization")
+                .cloned()
+                .unwrap_or_default(),
+        ));
+    }
+    upstream_join.join().expect("join upstream");
+
+    let account_1 = "Bearer access_token_entitlement_1".to_string();
+    let account_2 = "Bearer access_token_entitlement_2".to_string();
+    assert_eq!(
+        served,
+        vec![
+            ("gpt-5.4-pro".to_string(), account_1.clone()),
+            ("gpt-5.4-pro".to_string(), account_2.clone()),
+            // 中文注释：第二次请求直接跳过已学到不支持该模型的账号 1。
+            ("gpt-5.4-pro".to_string(), account_2),
+            // 中文注释：账号 1 对其它模型仍可用，没有被整体冷却。
+            ("gpt-5.4".to_string(), account_1),
+        ]
+    );
+
+    // 中文注释：服务退出前会写出学到的排除记录，重启后不必重新探测一次。
+    let exclusions = storage
+        .list_account_model_exclusions(now_ts())
+        .expect("list persisted exclusions");
+    assert_eq!(
+        exclusions
+            .iter()
+            .map(|record| (
+                record.account_id.as_str(),
+                record.model.as_str(),
+                record.status_code
+            ))
+            .collect::<Vec<_>>(),
+        vec![("acc_entitlement_1", "gpt-5.4-pro", 400)]
+    );
+}
+
+#[test]
+fn gateway_does_not_learn_exclusions_for_unknown_model_or_whole_pool() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-model-entitlement-guard");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _p2c_guard = EnvGuard::set("CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED", "0");
+
+    let not_found_body = serde_json::to_string(&serde_json::json!({
+        "error": {
+            "code": "model_not_found",
+            "message": "The model `gpt-5.4-typo` does not exist or you do not have access to it."
+        }
+    }))
+    .expect("serialize 404 body");
+    let unsupported_body = serde_json::to_string(&serde_json::json!({
+        "detail": "The 'gpt-5.4-pro' model is not supported when using Codex with a ChatGPT account."
+    }))
+    .expect("serialize 400 body");
+    // 中文注释：两个账号都不认识拼错的模型，也都没有 gpt-5.4-pro 的权限。
+    let listener = bind_test_listener("mock upstream");
+    let upstream_addr = listener.local_addr().expect("mock upstream addr");
+    let (upstream_tx, upstream_rx) = mpsc::channel();
+    let upstream_join = thread::spawn(move || {
+        while let Some((mut stream, captured)) =
+            accept_http_request(&listener, Duration::from_secs(3))
+        {
+            let (status, body) = if String::from_utf8_lossy(&captured.body).contains("gpt-5.4-typo")
+            {
+                (404, not_found_body.as_str())
+            } else {
+                (400, unsupported_body.as_str())
+            };
+            let header = format!(
+                "HTTP/1.1 {status} Error\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(body.as_bytes());
+            let _ = stream.flush();
+            if captured.path.ends_with("/responses") {
+                let _ = upstream_tx.send(captured);
+            }
+        }
+    });
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    for index in 1..=2 {
+        storage
+            .insert_account(&Account {
+                id: format!("acc_entitlement_guard_{index}"),
+                label: format!("entitlement-guard-{index}"),
+                issuer: "https://auth.openai.com".to_string(),
+                chatgpt_account_id: Some(format!("chatgpt_entitlement_guard_{index}")),
+                workspace_id: None,
+                group_name: None,
+                sort: index,
+                status: "active".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert account");
+        storage
+            .insert_token(&Token {
+                account_id: format!("acc_entitlement_guard_{index}"),
+                id_token: String::new(),
+                access_token: format!("access_token_entitlement_guard_{index}"),
+                refresh_token: String::new(),
+                api_key_access_token: Some(format!("api_access_token_entitlement_guard_{index}")),
+                last_refresh: now,
+            })
+            .expect("insert token");
+    }
+
+    let platform_key = "pk_model_entitlement_guard";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_model_entitlement_guard".to_string(),
+            name: Some("model-entitlement-guard".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = TestServer::start();
+    let auth_header = format!("Bearer {platform_key}");
+    let headers = [
+        ("Content-Type", "application/json"),
+        ("Authorization", auth_header.as_str()),
+    ];
+    for (model, expected_status) in [
+        ("gpt-5.4-typo", 404),
+        ("gpt-5.4-typo", 404),
+        ("gpt-5.4-pro", 400),
+        ("gpt-5.4-pro", 400),
+    ] {
+        let (status, response_body) = post_http_raw(
+            &server.addr,
+            "/v1/responses",
+            &format!(r#"{{"model":"{model}","input":"hello","stream":false}}"#),
+            &headers,
+        );
+        assert_eq!(
+            status, expected_status,
+            "model {model} response: {response_body}"
+        );
+    }
+    drop(server);
+
+    let mut served = Vec::new();
+    while let Ok(captured) = upstream_rx.recv_timeout(Duration::from_secs(2)) {
+        let body: serde_json::Value =
+            serde_json::from_slice(&captured.body).expect("parse upstream body");
+        served.push((
+            body["model"].as_str().unwrap_or_default().to_string(),
+            captured
+                .headers
+                .get("authorization")
+                .cloned()
+                .unwrap_or_default(),
+        ));
+    }
+    upstream_join.join().expect("join upstream");
+
+    // 中文注释：最后一个候选的 4xx 还会走原有的改写重试，这里只按 (模型, 账号) 统计命中次数。
+    let hits = |model: &str, account: &str| {
+        let authorization = format!("Bearer access_token_entitlement_guard_{account}");
+        served
+            .iter()
+            .filter(|(served_model, served_auth)| {
+                served_model == model && *served_auth == authorization
+            })
+            .count()
+    };
+    // 中文注释：从未成功过的模型报“不存在”不学习，第二次请求仍先打账号 1。
+    assert_eq!(hits("gpt-5.4-typo", "1"), 2, "served: {served:?}");
+    assert!(hits("gpt-5.4-typo", "2") >= 2, "served: {served:?}");
+    // 中文注释：账号 2 再排除就覆盖整个池子，只保留账号 1 的排除，第二次请求跳过账号 1、仍打账号 2。
+    assert_eq!(hits("gpt-5.4-pro", "1"), 1, "served: {served:?}");
+    assert!(hits("gpt-5.4-pro", "2") >= 2, "served: {served:?}");
+
+    let exclusions = storage
+        .list_account_model_exclusions(now_ts())
+        .expect("list persisted exclusions");
+    assert_eq!(
+        exclusions
+            .iter()
+            .map(|record| (record.account_id.as_str(), record.model.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("acc_entitlement_guard_1", "gpt-5.4-pro")]
+    );
+}
+
+#[test]
+fn gateway_hedged_request_serves_faster_candidate() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-hedge");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let trace_log_path: PathBuf = dir.join("gateway-trace.log");
+    let _ = fs::remove_file(&trace_log_path);
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _p2c_guard = EnvGuard::set("CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED", "0");
+
+    let response_for = |id: &str, output_tokens: u64| {
+        serde_json::to_string(&serde_json::json!({
+            "id": id,
+            "model": "gpt-5.3-codex",
+            "output": [{
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "output_text", "text": "ok" }]
+            }],
+            "usage": { "input_tokens": 4, "output_tokens": output_tokens, "total_tokens": 4 + output_tokens }
+        }))
+        .expect("serialize response")
+    };
+    let slow_body = response_for("resp_hedge_slow", 50);
+    let fast_body = response_for("resp_hedge_fast", 2);
+    // 中文注释：账号 1 迟迟不返回响应头，账号 2 立即返回；每个连接单独线程处理，允许两路并发。
+    let listener = bind_test_listener("mock upstream");
+    let upstream_addr = listener.local_addr().expect("mock upstream addr");
+    let (upstream_tx, upstream_rx) = mpsc::channel();
+    let upstream_join = thread::spawn(move || {
+        let mut handlers = Vec::new();
+        while let Some((mut stream, captured)) =
+            accept_http_request(&listener, Duration::from_secs(3))
+        {
+            let slow = captured.headers.get("authorization").map(String::as_str)
+                == Some("Bearer access_token_hedge_1");
+            let body = if slow {
+                slow_body.clone()
+            } else {
+                fast_body.clone()
+            };
+            if captured.path.ends_with("/responses") {
+                let _ = upstream_tx.send(captured);
+            }
+            handlers.push(thread::spawn(move || {
+                if slow {
+                    thread::sleep(Duration::from_millis(1500));
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(body.as_bytes());
+                let _ = stream.flush();
+            }));
+        }
+        for handler in handlers {
+            let _ = handler.join();
+        }
+    });
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    for index in 1..=2 {
+        storage
+            .insert_account(&Account {
+                id: format!("acc_hedge_{index}"),
+                label: format!("hedge-{index}"),
+                issuer: "https://auth.openai.com".to_string(),
+                chatgpt_account_id: Some(format!("chatgpt_hedge_{index}")),
+                workspace_id: None,
+                group_name: None,
+                sort: index,
+                status: "active".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert account");
+        storage
+            .insert_token(&Token {
+                account_id: format!("acc_hedge_{index}"),
+                id_token: String::new(),
+                access_token: format!("access_token_hedge_{index}"),
+                refresh_token: String::new(),
+                api_key_access_token: None,
+                last_refresh: now,
+            })
+            .expect("insert token");
+    }
+
+    let platform_key = "pk_hedge";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_hedge".to_string(),
+            name: Some("hedge".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: Some(150),
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let started_at = Instant::now();
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.3-codex","input":"hello","stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    let elapsed = started_at.elapsed();
+    server.join();
+    assert_eq!(status, 200, "gateway response: {response_body}");
+    assert!(
+        response_body.contains("resp_hedge_fast"),
+        "response: {response_body}"
+    );
+    assert!(
+        elapsed < Duration::from_millis(1400),
+        "elapsed: {elapsed:?}"
+    );
+
+    let mut authorizations = Vec::new();
+    for _ in 0..2 {
+        let captured = upstream_rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("receive upstream request");
+        authorizations.push(
+            captured
+                .headers
+                .get("authorization")
+                .cloned()
+                .unwrap_or_default(),
+        );
+    }
+    assert_eq!(
+        authorizations,
+        vec![
+            "Bearer access_token_hedge_1".to_string(),
+            "Bearer access_token_hedge_2".to_string(),
+        ]
+    );
+    upstream_join.join().expect("join upstream");
+
+    let logs = storage
+        .list_request_logs(Some("key:gk_hedge"), 20)
+        .expect("list logs");
+    let final_logs = logs
+        .iter()
+        .filter(|item| item.request_path == "/v1/responses")
+        .collect::<Vec<_>>();
+    // 中文注释：只记录胜出请求的一条日志与用量，输掉的请求不计费。
+    assert_eq!(final_logs.len(), 1, "logs: {final_logs:#?}");
+    assert_eq!(final_logs[0].account_id.as_deref(), Some("acc_hedge_2"));
+    assert_eq!(final_logs[0].output_tokens, Some(2));
+
+    let trace_text = fs::read_to_string(&trace_log_path).expect("read trace log");
+    assert!(trace_text.contains("event=HEDGE_ATTEMPT"));
+    assert!(trace_text.contains("role=hedge account_id=acc_hedge_2 outcome=won"));
+    assert!(trace_text.contains("role=primary account_id=acc_hedge_1 outcome=abandoned"));
+}
+
+fn run_stream_failover_scenario(
+    name: &str,
+    first_stream: String,
+    second_stream: String,
+) -> (u16, String, Vec<RequestLog>, String) {
+    let dir = new_test_dir(&format!("codexmanager-gateway-stream-failover-{name}"));
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let trace_log_path: PathBuf = dir.join("gateway-trace.log");
+    let _ = fs::remove_file(&trace_log_path);
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _p2c_guard = EnvGuard::set("CODEXMANAGER_ROUTE_HEALTH_P2C_ENABLED", "0");
+    let _failover_guard = EnvGuard::set("CODEXMANAGER_STREAM_FAILOVER_ENABLED", "1");
+
+    // 中文注释：账号 1 的 SSE 在终止事件前直接关闭连接，账号 2 返回完整的流。
+    let listener = bind_test_listener("mock upstream");
+    let upstream_addr = listener.local_addr().expect("mock upstream addr");
+    let upstream_join = thread::spawn(move || {
+        while let Some((mut stream, captured)) =
+            accept_http_request(&listener, Duration::from_secs(3))
+        {
+            let body = if captured.headers.get("authorization").map(String::as_str)
+                == Some("Bearer access_token_stream_failover_1")
+            {
+                first_stream.clone()
+            } else {
+                second_stream.clone()
+            };
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+            );
+            let _ = stream.write_all(body.as_bytes());
+            let _ = stream.flush();
+        }
+    });
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    for index in 1..=2 {
+        storage
+            .insert_account(&Account {
+                id: format!("acc_stream_failover_{index}"),
+                label: format!("stream-failover-{index}"),
+                issuer: "https://auth.openai.com".to_string(),
+                chatgpt_account_id: Some(format!("chatgpt_stream_failover_{index}")),
+                workspace_id: None,
+                group_name: None,
+                sort: index,
+                status: "active".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .expect("insert account");
+        storage
+            .insert_token(&Token {
+                account_id: format!("acc_stream_failover_{index}"),
+                id_token: String::new(),
+                access_token: format!("access_token_stream_failover_{index}"),
+                refresh_token: String::new(),
+                api_key_access_token: None,
+                last_refresh: now,
+            })
+            .expect("insert token");
+    }
+
+    let platform_key = "pk_stream_failover";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_stream_failover".to_string(),
+            name: Some("stream-failover".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/responses",
+        r#"{"model":"gpt-5.3-codex","input":"hello","stream":true}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    upstream_join.join().expect("join upstream");
+
+    let logs = storage
+        .list_request_logs(Some("key:gk_stream_failover"), 20)
+        .expect("list logs")
+        .into_iter()
+        .filter(|item| item.request_path == "/v1/responses")
+        .collect::<Vec<_>>();
+    let trace_text = fs::read_to_string(&trace_log_path).unwrap_or_default();
+    (status, response_body, logs, trace_text)
+}
+
+fn stream_failover_sse_event(value: serde_json::Value) -> String {
+    let event_type = value["type"].as_str().unwrap_or_default().to_string();
+    format!("event: {event_type}\ndata: {value}\n\n")
+}
+
+#[test]
+fn gateway_stream_failover_restarts_stream_before_any_output() {
+    let _lock = lock_env();
+    let preamble = |id: &str| {
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.created",
+            "response": { "id": id, "status": "in_progress" }
+        }))
+    };
+    let first_stream = preamble("resp_stream_failover_dead");
+    let second_stream = [
+        preamble("resp_stream_failover_live"),
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.output_text.delta",
+            "delta": "spliced ok"
+        })),
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.completed",
+            "response": {
+                "id": "resp_stream_failover_live",
+                "status": "completed",
+                "usage": { "input_tokens": 3, "output_tokens": 2, "total_tokens": 5 }
+            }
+        })),
+    ]
+    .concat();
+
+    let (status, body, logs, trace_text) =
+        run_stream_failover_scenario("resume", first_stream, second_stream);
+    assert_eq!(status, 200, "gateway response: {body}");
+    // 中文注释：客户端只看到一次开场事件，后续输出全部来自续流的账号 2。
+    assert_eq!(
+        body.matches("event: response.created").count(),
+        1,
+        "body: {body}"
+    );
+    assert!(body.contains("resp_stream_failover_dead"), "body: {body}");
+    assert!(body.contains("spliced ok"), "body: {body}");
+    assert!(body.contains("response.completed"), "body: {body}");
+
+    assert_eq!(logs.len(), 1, "logs: {logs:#?}");
+    assert_eq!(logs[0].status_code, Some(200));
+    assert_eq!(logs[0].account_id.as_deref(), Some("acc_stream_failover_2"));
+    assert_eq!(logs[0].output_tokens, Some(2));
+    assert!(trace_text.contains("event=STREAM_FAILOVER trace_id=",));
+    assert!(trace_text.contains(
+        "from_account_id=acc_stream_failover_1 to_account_id=acc_stream_failover_2 outcome=resumed reason=eof"
+    ));
+}
+
+#[test]
+fn gateway_stream_failover_keeps_first_response_id_after_splice() {
+    let _lock = lock_env();
+    let first_stream = stream_failover_sse_event(serde_json::json!({
+        "type": "response.created",
+        "response": { "id": "resp_splice_first", "status": "in_progress" }
+    }));
+    let second_stream = [
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.created",
+            "response": { "id": "resp_splice_second", "status": "in_progress" }
+        })),
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.output_text.delta",
+            "response_id": "resp_splice_second",
+            "delta": "spliced ok"
+        })),
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.completed",
+            "response": {
+                "id": "resp_splice_second",
+                "status": "completed",
+                "usage": { "input_tokens": 3, "output_tokens": 2, "total_tokens": 5 }
+            }
+        })),
+    ]
+    .concat();
+
+    let (status, body, logs, _) =
+        run_stream_failover_scenario("resume-id", first_stream, second_stream);
+    assert_eq!(status, 200, "gateway response: {body}");
+    // 中文注释：续流后的帧统一改写成客户端最先收到的响应 id。
+    assert!(!body.contains("resp_splice_second"), "body: {body}");
+    let ids: Vec<String> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter_map(|data| serde_json::from_str::<serde_json::Value>(data).ok())
+        .filter_map(|value| {
+            value
+                .pointer("/response/id")
+                .or_else(|| value.get("response_id"))
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        })
+        .collect();
+    assert_eq!(ids.len(), 3, "body: {body}");
+    assert!(
+        ids.iter().all(|id| id == "resp_splice_first"),
+        "ids: {ids:?}"
+    );
+    assert!(body.contains("spliced ok"), "body: {body}");
+
+    assert_eq!(logs.len(), 1, "logs: {logs:#?}");
+    assert_eq!(logs[0].status_code, Some(200));
+    assert_eq!(logs[0].account_id.as_deref(), Some("acc_stream_failover_2"));
+}
+
+#[test]
+fn gateway_stream_failover_fails_cleanly_after_output_started() {
+    let _lock = lock_env();
+    let first_stream = [
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.created",
+            "response": { "id": "resp_stream_failover_partial", "status": "in_progress" }
+        })),
+        stream_failover_sse_event(serde_json::json!({
+            "type": "response.output_text.delta",
+            "delta": "partial"
+        })),
+    ]
+    .concat();
+
+    let (status, body, logs, trace_text) =
+        run_stream_failover_scenario("give-up", first_stream, String::new());
+    assert_eq!(status, 200, "gateway response: {body}");
+    assert!(body.contains("partial"), "body: {body}");
+    assert!(body.contains("event: response.failed"), "body: {body}");
+    assert!(
+        body.contains("\"code\":\"stream_disconnected\""),
+        "body: {body}"
+    );
+    assert!(
+        body.contains("\"id\":\"resp_stream_failover_partial\""),
+        "body: {body}"
+    );
+
+    assert_eq!(logs.len(), 1, "logs: {logs:#?}");
+    assert_eq!(logs[0].status_code, Some(502));
+    assert_eq!(logs[0].account_id.as_deref(), Some("acc_stream_failover_1"));
+    assert!(trace_text
+        .contains("from_account_id=acc_stream_failover_1 to_account_id=- outcome=output_started"));
+}
+
+#[test]
+fn gateway_client_disconnect_aborts_upstream_stream() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-client-cancel");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    // 中文注释：上游每 100ms 推一个约 2KB 的增量事件（后端按 8KB 分块刷出），最长推 15 秒；
+    // 客户端断开后网关应立刻掐断上游连接。
+    let listener = bind_test_listener("mock upstream");
+    let upstream_addr = listener.local_addr().expect("mock upstream addr");
+    let (upstream_done_tx, upstream_done_rx) = mpsc::channel::<Duration>();
+    let upstream_join = thread::spawn(move || {
+        // 中文注释：完整服务启动后会后台刷新模型列表，这类请求直接回 404 跳过。
+        let mut stream = loop {
+            let Some((mut stream, captured)) =
+                accept_http_request(&listener, Duration::from_secs(5))
+            else {
+                return;
+            };
+            if captured.path.ends_with("/responses") {
+                break stream;
+            }
+            let _ = stream.write_all(
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        };
+        let started_at = Instant::now();
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        );
+        let _ = stream.write_all(
+            stream_failover_sse_event(serde_json::json!({
+                "type": "response.created",
+                "response": { "id": "resp_client_cancel", "status": "in_progress" }
+            }))
+            .as_bytes(),
+        );
+        while started_at.elapsed() < Duration::from_secs(15) {
+            let event = stream_failover_sse_event(serde_json::json!({
+                "type": "response.output_text.delta",
+                "delta": format!("partial output {}", "x".repeat(2048))
+            }));
+            if stream.write_all(event.as_bytes()).is_err() || stream.flush().is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        let _ = upstream_done_tx.send(started_at.elapsed());
+    });
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_client_cancel".to_string(),
+            label: "client-cancel".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_client_cancel".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 1,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_client_cancel".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_client_cancel".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: None,
+            last_refresh: now,
+        })
+        .expect("insert token");
+    let platform_key = "pk_client_cancel";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_client_cancel".to_string(),
+            name: Some("client-cancel".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = TestServer::start();
+    let body = r#"{"model":"gpt-5.3-codex","input":"hello","stream":true}"#;
+    let mut client = TcpStream::connect(&server.addr).expect("connect gateway");
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("set read timeout");
+    let request = format!(
+        "POST /v1/responses HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nAuthorization: Bearer {platform_key}\r\nContent-Length: {}\r\n\r\n{body}",
+        server.addr,
+        body.len()
+    );
+    client.write_all(request.as_bytes()).expect("write request");
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !String::from_utf8_lossy(&received).contains("partial output") {
+        let read = client.read(&mut buf).expect("read gateway response");
+        assert!(read > 0, "gateway closed before streaming output");
+        received.extend_from_slice(&buf[..read]);
+    }
+    drop(client);
+
+    let upstream_elapsed = upstream_done_rx
+        .recv_timeout(Duration::from_secs(10))
+        .expect("upstream stream should be aborted");
+    assert!(
+        upstream_elapsed < Duration::from_secs(10),
+        "upstream kept streaming for {upstream_elapsed:?}"
+    );
+    upstream_join.join().expect("join upstream");
+
+    let mut logs = Vec::new();
+    for _ in 0..60 {
+        logs = storage
+            .list_request_logs(Some("key:gk_client_cancel"), 20)
+            .expect("list logs")
+            .into_iter()
+            .filter(|item| item.request_path == "/v1/responses")
+            .collect::<Vec<_>>();
+        if !logs.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(logs.len(), 1, "logs: {logs:#?}");
+    assert_eq!(logs[0].status_code, Some(499));
+    assert_eq!(logs[0].error.as_deref(), Some("client_cancelled"));
+    assert_eq!(logs[0].account_id.as_deref(), Some("acc_client_cancel"));
+    assert!(
+        logs[0].output_tokens.is_some_and(|tokens| tokens > 0),
+        "partial usage should be recorded: {logs:#?}"
+    );
+}
+
+#[test]
+fn gateway_anthropic_passthrough_forwards_messages_and_logs_claude_usage() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-anthropic-passthrough");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let upstream_sse = [
+        serde_json::json!({
+            "type": "message_start",
+            "message": {
+                "id": "msg_passthrough",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-sonnet-4-5",
+                "content": [],
+                "usage": { "input_tokens": 50, "cache_read_input_tokens": 50, "output_tokens": 1 }
+            }
+        }),
+        serde_json::json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": { "type": "text_delta", "text": "pong" }
+        }),
+        serde_json::json!({
+            "type": "message_delta",
+            "delta": { "stop_reason": "end_turn" },
+            "usage": { "output_tokens": 27 }
+        }),
+        serde_json::json!({ "type": "message_stop" }),
+    ]
+    .iter()
+    .map(|event| {
+        format!(
+            "event: {}\ndata: {event}\n\n",
+            event["type"].as_str().unwrap()
+        )
+    })
+    .collect::<String>();
+    let (upstream_addr, upstream_rx, upstream_join) =
+        start_mock_upstream_once_with_content_type(&upstream_sse, "text/event-stream");
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    let platform_key = "pk_anthropic_passthrough";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_anthropic_passthrough".to_string(),
+            name: Some("anthropic-passthrough".to_string()),
+            model_slug: Some("gpt-5.3-codex".to_string()),
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "anthropic_native".to_string(),
+            auth_scheme: "x_api_key".to_string(),
+            upstream_base_url: Some(format!("http://{upstream_addr}")),
+            static_headers_json: Some(r#"{"x-api-key":"sk-ant-upstream"}"#.to_string()),
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let body = r#"{"model":"claude-sonnet-4-5","max_tokens":64,"stream":true,"messages":[{"role":"user","content":"ping"}]}"#;
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/v1/messages",
+        body,
+        &[
+            ("Content-Type", "application/json"),
+            ("x-api-key", platform_key),
+            ("anthropic-beta", "prompt-caching-2024-07-31"),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+    assert!(
+        gateway_body.contains("event: message_delta"),
+        "{gateway_body}"
+    );
+    assert!(gateway_body.contains(r#""text":"pong""#), "{gateway_body}");
+
+    let captured = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive upstream request");
+    upstream_join.join().expect("join upstream");
+    assert_eq!(captured.path, "/v1/messages");
+    assert_eq!(
+        captured.headers.get("x-api-key").map(String::as_str),
+        Some("sk-ant-upstream")
+    );
+    assert_eq!(
+        captured
+            .headers
+            .get("anthropic-version")
+            .map(String::as_str),
+        Some("2023-06-01")
+    );
+    assert_eq!(
+        captured.headers.get("anthropic-beta").map(String::as_str),
+        Some("prompt-caching-2024-07-31")
+    );
+    assert!(!captured.headers.contains_key("authorization"));
+    assert_eq!(captured.body, body.as_bytes());
+
+    let mut logs = Vec::new();
+    for _ in 0..40 {
+        logs = storage
+            .list_request_logs(Some("key:gk_anthropic_passthrough"), 10)
+            .expect("list logs");
+        if !logs.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(logs.len(), 1, "logs: {logs:#?}");
+    let log = &logs[0];
+    assert_eq!(log.status_code, Some(200));
+    assert_eq!(log.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert_eq!(log.input_tokens, Some(100));
+    assert_eq!(log.cached_input_tokens, Some(50));
+    assert_eq!(log.output_tokens, Some(27));
+    assert_eq!(log.total_tokens, Some(127));
+    let cost = log.estimated_cost_usd.expect("claude cost");
+    assert!((cost - 0.000570).abs() < 1e-9, "cost: {cost}");
+}
+
+#[test]
+fn gateway_overflows_chat_request_to_provider_after_oauth_pool_fails() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-provider-overflow");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let limited_body = serde_json::json!({
+        "error": { "message": "You have hit your usage limit", "type": "usage_limit_reached" }
+    })
+    .to_string();
+    let (codex_addr, codex_rx, codex_join) =
+        start_mock_upstream_sequence(vec![(429, limited_body.clone()), (429, limited_body)]);
+    let upstream_base = format!("http://{codex_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let provider_body = serde_json::json!({
+        "id": "chatcmpl-provider",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": "served by provider" },
+            "finish_reason": "stop"
+        }],
+        "usage": { "prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7 }
+    });
+    let (provider_addr, provider_rx, provider_join) = start_mock_upstream_once(
+        &serde_json::to_string(&provider_body).expect("serialize provider body"),
+    );
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_provider_overflow".to_string(),
+            label: "oauth".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_provider_overflow".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 5,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_provider_overflow".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_provider_overflow".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: None,
+            last_refresh: now,
+        })
+        .expect("insert token");
+    storage
+        .upsert_provider_account(&ProviderAccount {
+            id: "prov_overflow".to_string(),
+            label: "local vLLM".to_string(),
+            base_url: format!("http://{provider_addr}/v1"),
+            api_key: "sk-provider-overflow".to_string(),
+            models_json: "[]".to_string(),
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert provider");
+
+    let platform_key = "pk_provider_overflow";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_provider_overflow".to_string(),
+            name: Some("provider-overflow".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/v1/chat/completions",
+        r#"{"model":"gpt-5.4","messages":[{"role":"user","content":"hello"}],"stream":false}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+    assert!(
+        gateway_body.contains("served by provider"),
+        "gateway response: {gateway_body}"
+    );
+
+    // 中文注释：OAuth 账号排在前面，先被打到并限流，之后才溢出到服务商。
+    let codex_first = codex_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive codex upstream request");
+    assert!(codex_first.path.contains("/backend-api/codex/responses"));
+    let captured = provider_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive provider request");
+    provider_join.join().expect("join provider");
+    codex_join.join().expect("join codex upstream");
+    assert_eq!(captured.path, "/v1/chat/completions");
+    assert_eq!(
+        captured.headers.get("authorization").map(String::as_str),
+        Some("Bearer sk-provider-overflow")
+    );
+    let provider_request: serde_json::Value =
+        serde_json::from_slice(&captured.body).expect("parse provider body");
+    assert_eq!(provider_request["model"], "gpt-5.4");
+    assert_eq!(provider_request["stream"], false);
+    assert!(
+        provider_request.get("input").is_none(),
+        "{provider_request}"
+    );
+    let messages = provider_request["messages"]
+        .as_array()
+        .expect("provider messages");
+    assert!(messages
+        .iter()
+        .any(|message| message["role"] == "user" && message.to_string().contains("hello")));
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("key:=gk_provider_overflow"), 20)
+            .expect("list request logs");
+        matched = logs.into_iter().next();
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("provider overflow request log");
+    assert_eq!(log.status_code, Some(200), "log error: {:?}", log.error);
+    assert_eq!(log.account_id.as_deref(), Some("prov_overflow"));
+    assert_eq!(log.input_tokens, Some(5));
+    assert_eq!(log.output_tokens, Some(2));
+}
+
+#[test]
+fn gateway_ollama_chat_streams_ndjson_from_responses_upstream() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-ollama-chat");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let upstream_sse = concat!(
+        "data: {\"type\":\"response.created\",\"response\":{\"id\":\"resp_ollama_1\",\"model\":\"gpt-5.3-codex\"}}\n\n",
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"hel\"}\n\n",
+        "data: {\"type\":\"response.output_text.delta\",\"delta\":\"lo\"}\n\n",
+        "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_ollama_1\",\"model\":\"gpt-5.3-codex\",\"usage\":{\"input_tokens\":11,\"output_tokens\":2,\"total_tokens\":13}}}\n\n"
+    );
+    let (upstream_addr, upstream_rx, upstream_join) =
+        start_mock_upstream_once_with_content_type(upstream_sse, "text/event-stream");
+    let upstream_base = format!("http://{upstream_addr}/backend-api/codex");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+
+    storage
+        .insert_account(&Account {
+            id: "acc_ollama_chat".to_string(),
+            label: "ollama-chat".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: Some("chatgpt_ollama_chat".to_string()),
+            workspace_id: None,
+            group_name: None,
+            sort: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_ollama_chat".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_ollama_chat".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_ollama_chat".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_ollama_chat";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_ollama_chat".to_string(),
+            name: Some("ollama-chat".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let request_body = serde_json::json!({
+        "model": "gpt-5.3-codex:latest",
+        "messages": [{ "role": "user", "content": "hello" }]
+    });
+    let request_body = serde_json::to_string(&request_body).expect("serialize request");
+    let (status, gateway_body) = post_http_raw(
+        &server.addr,
+        "/api/chat",
+        &request_body,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {gateway_body}");
+
+    let lines = gateway_body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("ndjson line"))
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "gateway response: {gateway_body}");
+    assert_eq!(lines[0]["message"]["content"], "hel");
+    assert_eq!(lines[0]["model"], "gpt-5.3-codex");
+    assert_eq!(lines[0]["done"], false);
+    assert_eq!(lines[1]["message"]["content"], "lo");
+    assert_eq!(lines[2]["done"], true);
+    assert_eq!(lines[2]["done_reason"], "stop");
+    assert_eq!(lines[2]["eval_count"], 2);
+
+    let captured = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive upstream request");
+    upstream_join.join().expect("join upstream");
+    assert_eq!(captured.path, "/backend-api/codex/responses");
+    let upstream_body: serde_json::Value =
+        serde_json::from_slice(&captured.body).expect("upstream json");
+    assert_eq!(upstream_body["model"], "gpt-5.3-codex");
+    assert_eq!(upstream_body["input"][0]["content"][0]["text"], "hello");
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("key:=gk_ollama_chat"), 20)
+            .expect("list request logs");
+        matched = logs.into_iter().next();
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("ollama chat request log");
+    assert_eq!(log.status_code, Some(200));
+    assert_eq!(log.input_tokens, Some(11));
+    assert_eq!(log.output_tokens, Some(2));
+}
+
+#[test]
+fn gateway_embeddings_batches_inputs_through_openai_fallback_base() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-embeddings-batches");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _batch_guard = EnvGuard::set("CODEXMANAGER_EMBEDDINGS_BATCH_SIZE", "2");
+
+    let first_batch = serde_json::json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [
+            { "object": "embedding", "index": 0, "embedding": [0.1] },
+            { "object": "embedding", "index": 1, "embedding": [0.2] }
+        ],
+        "usage": { "prompt_tokens": 4, "total_tokens": 4 }
+    });
+    let second_batch = serde_json::json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [{ "object": "embedding", "index": 0, "embedding": [0.3] }],
+        "usage": { "prompt_tokens": 2, "total_tokens": 2 }
+    });
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (200, first_batch.to_string()),
+        (200, second_batch.to_string()),
+    ]);
+    // The ChatGPT backend cannot serve embeddings, so only the OpenAI fallback base should be hit.
+    let upstream_base = format!("http://{upstream_addr}/chatgpt.com/backend-api/codex");
+    let fallback_base = format!("http://{upstream_addr}/v1");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+    let _fallback_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_FALLBACK_BASE_URL", &fallback_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_embeddings".to_string(),
+            label: "embeddings".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: None,
+            workspace_id: Some("ws_embeddings".to_string()),
+            group_name: None,
+            sort: 1,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_embeddings".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_embeddings".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_embeddings".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_embeddings_batches";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_embeddings_batches".to_string(),
+            name: Some("embeddings".to_string()),
+            model_slug: Some("gpt-5.3-codex".to_string()),
+            reasoning_effort: Some("high".to_string()),
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let req_body = r#"{"model":"text-embedding-3-small","input":["a","b","c"]}"#;
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/embeddings",
+        req_body,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "gateway response: {response_body}");
+
+    let first = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive first batch");
+    let second = upstream_rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("receive second batch");
+    upstream_join.join().expect("join mock upstream");
+    for (captured, expected_input) in [
+        (&first, serde_json::json!(["a", "b"])),
+        (&second, serde_json::json!(["c"])),
+    ] {
+        assert_eq!(captured.path, "/v1/embeddings");
+        assert_eq!(
+            captured.headers.get("authorization").map(String::as_str),
+            Some("Bearer api_access_token_embeddings")
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&captured.body).expect("parse upstream body");
+        // The key's chat model and reasoning overrides must not leak into embeddings requests.
+        assert_eq!(body["model"], "text-embedding-3-small");
+        assert!(body.get("reasoning").is_none());
+        assert_eq!(body["input"], expected_input);
+    }
+
+    let merged: serde_json::Value =
+        serde_json::from_str(&response_body).expect("parse gateway response");
+    let indexes = merged["data"]
+        .as_array()
+        .expect("merged data")
+        .iter()
+        .map(|item| item["index"].as_u64().expect("index"))
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, vec![0, 1, 2]);
+    assert_eq!(merged["usage"]["prompt_tokens"], 6);
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("key:=gk_embeddings_batches"), 20)
+            .expect("list request logs");
+        matched = logs
+            .into_iter()
+            .find(|item| item.request_path == "/v1/embeddings");
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("embeddings request log");
+    assert_eq!(log.status_code, Some(200));
+    assert_eq!(log.account_id.as_deref(), Some("acc_embeddings"));
+    assert_eq!(log.input_tokens, Some(6));
+    assert_eq!(log.total_tokens, Some(6));
+}
+
+#[test]
+fn gateway_embeddings_logs_completed_batch_usage_when_later_batch_fails() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-embeddings-partial");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+    let _batch_guard = EnvGuard::set("CODEXMANAGER_EMBEDDINGS_BATCH_SIZE", "2");
+
+    let first_batch = serde_json::json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [
+            { "object": "embedding", "index": 0, "embedding": [0.1] },
+            { "object": "embedding", "index": 1, "embedding": [0.2] }
+        ],
+        "usage": { "prompt_tokens": 4, "total_tokens": 4 }
+    });
+    let rejected = serde_json::json!({
+        "error": { "message": "input too long", "type": "invalid_request_error" }
+    });
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (200, first_batch.to_string()),
+        (400, rejected.to_string()),
+    ]);
+    let upstream_base = format!("http://{upstream_addr}/chatgpt.com/backend-api/codex");
+    let fallback_base = format!("http://{upstream_addr}/v1");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+    let _fallback_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_FALLBACK_BASE_URL", &fallback_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_embeddings_partial".to_string(),
+            label: "embeddings-partial".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: None,
+            workspace_id: Some("ws_embeddings_partial".to_string()),
+            group_name: None,
+            sort: 1,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_embeddings_partial".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_embeddings_partial".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_embeddings_partial".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_embeddings_partial";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_embeddings_partial".to_string(),
+            name: Some("embeddings-partial".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/embeddings",
+        r#"{"model":"text-embedding-3-small","input":["a","b","c"]}"#,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &format!("Bearer {platform_key}")),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 400, "gateway response: {response_body}");
+    for _ in 0..2 {
+        upstream_rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("receive batch request");
+    }
+    upstream_join.join().expect("join mock upstream");
+
+    let mut matched = None;
+    for _ in 0..40 {
+        let logs = storage
+            .list_request_logs(Some("key:=gk_embeddings_partial"), 20)
+            .expect("list request logs");
+        matched = logs
+            .into_iter()
+            .find(|item| item.request_path == "/v1/embeddings");
+        if matched.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let log = matched.expect("embeddings request log");
+    assert_eq!(log.status_code, Some(400));
+    // 中文注释：第一批已在上游消耗的 token 仍要计入日志，供 TPM、预算与成本统计使用。
+    assert_eq!(log.input_tokens, Some(4));
+    assert_eq!(log.total_tokens, Some(4));
+}
+
+#[test]
+fn gateway_batch_api_runs_uploaded_jsonl_and_writes_result_files() {
+    let _lock = lock_env();
+    let dir = new_test_dir("codexmanager-gateway-batch-api");
+    let db_path: PathBuf = dir.join("codexmanager.db");
+
+    let _db_guard = EnvGuard::set("CODEXMANAGER_DB_PATH", db_path.to_string_lossy().as_ref());
+
+    let embedding = serde_json::json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [{ "object": "embedding", "index": 0, "embedding": [0.5] }],
+        "usage": { "prompt_tokens": 3, "total_tokens": 3 }
+    });
+    let rejected = serde_json::json!({
+        "error": { "message": "input too long", "type": "invalid_request_error" }
+    });
+    let (upstream_addr, upstream_rx, upstream_join) = start_mock_upstream_sequence(vec![
+        (200, embedding.to_string()),
+        (400, rejected.to_string()),
+    ]);
+    let upstream_base = format!("http://{upstream_addr}/chatgpt.com/backend-api/codex");
+    let fallback_base = format!("http://{upstream_addr}/v1");
+    let _upstream_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_BASE_URL", &upstream_base);
+    let _fallback_guard = EnvGuard::set("CODEXMANAGER_UPSTREAM_FALLBACK_BASE_URL", &fallback_base);
+
+    let storage = Storage::open(&db_path).expect("open db");
+    storage.init().expect("init db");
+    let now = now_ts();
+    storage
+        .insert_account(&Account {
+            id: "acc_batch_api".to_string(),
+            label: "batch".to_string(),
+            issuer: "https://auth.openai.com".to_string(),
+            chatgpt_account_id: None,
+            workspace_id: Some("ws_batch_api".to_string()),
+            group_name: None,
+            sort: 1,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+        })
+        .expect("insert account");
+    storage
+        .insert_token(&Token {
+            account_id: "acc_batch_api".to_string(),
+            id_token: String::new(),
+            access_token: "access_token_batch_api".to_string(),
+            refresh_token: String::new(),
+            api_key_access_token: Some("api_access_token_batch_api".to_string()),
+            last_refresh: now,
+        })
+        .expect("insert token");
+
+    let platform_key = "pk_batch_api";
+    storage
+        .insert_api_key(&ApiKey {
+            id: "gk_batch_api".to_string(),
+            name: Some("batch".to_string()),
+            model_slug: None,
+            reasoning_effort: None,
+            client_type: "codex".to_string(),
+            protocol_type: "openai_compat".to_string(),
+            auth_scheme: "authorization_bearer".to_string(),
+            upstream_base_url: None,
+            static_headers_json: None,
+            account_group: None,
+            rpm_limit: None,
+            tpm_limit: None,
+            max_concurrency: None,
+            hedge_delay_ms: None,
+            key_hash: hash_platform_key_for_test(platform_key),
+            status: "active".to_string(),
+            created_at: now,
+            last_used_at: None,
+        })
+        .expect("insert api key");
+    let auth_header = format!("Bearer {platform_key}");
+
+    let input = [
+        r#"{"custom_id":"req-ok","method":"POST","url":"/v1/embeddings","body":{"model":"text-embedding-3-small","input":"hello"}}"#,
+        r#"{"custom_id":"req-bad","method":"POST","url":"/v1/embeddings","body":{"model":"text-embedding-3-small","input":"too long"}}"#,
+    ]
+    .join("\n");
+    let upload_body = format!(
+        "--BOUNDARY\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n--BOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; filename=\"input.jsonl\"\r\nContent-Type: application/jsonl\r\n\r\n{input}\n\r\n--BOUNDARY--\r\n"
+    );
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/files",
+        &upload_body,
+        &[
+            ("Content-Type", "multipart/form-data; boundary=BOUNDARY"),
+            ("Authorization", &auth_header),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "upload response: {response_body}");
+    let file: serde_json::Value = serde_json::from_str(&response_body).expect("parse file");
+    assert_eq!(file["purpose"], "batch");
+    assert_eq!(file["filename"], "input.jsonl");
+    let input_file_id = file["id"].as_str().expect("file id").to_string();
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let create_body = serde_json::json!({
+        "input_file_id": input_file_id,
+        "endpoint": "/v1/embeddings",
+        "completion_window": "24h",
+        "metadata": { "job": "nightly" }
+    })
+    .to_string();
+    let (status, response_body) = post_http_raw(
+        &server.addr,
+        "/v1/batches",
+        &create_body,
+        &[
+            ("Content-Type", "application/json"),
+            ("Authorization", &auth_header),
+        ],
+    );
+    server.join();
+    assert_eq!(status, 200, "create batch response: {response_body}");
+    let batch: serde_json::Value = serde_json::from_str(&response_body).expect("parse batch");
+    assert_eq!(batch["object"], "batch");
+    assert_eq!(batch["metadata"]["job"], "nightly");
+    assert_eq!(batch["request_counts"]["total"], 2);
+    let batch_id = batch["id"].as_str().expect("batch id").to_string();
+
+    let mut finished = None;
+    for _ in 0..200 {
+        let current = storage
+            .find_gateway_batch(&batch_id)
+            .expect("find batch")
+            .expect("batch exists");
+        if current.status == "completed" {
+            finished = Some(current);
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let finished = finished.expect("batch completed");
+    assert_eq!(finished.completed_count, 1);
+    assert_eq!(finished.failed_count, 1);
+
+    // Batch items are replayed through the gateway, so they reach the same upstream as live requests.
+    for expected_input in ["hello", "too long"] {
+        let captured = upstream_rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("receive batch item request");
+        assert_eq!(captured.path, "/v1/embeddings");
+        let body: serde_json::Value =
+            serde_json::from_slice(&captured.body).expect("parse upstream body");
+        assert_eq!(body["input"], expected_input);
+    }
+    upstream_join.join().expect("join mock upstream");
+
+    let output_file_id = finished.output_file_id.expect("output file id");
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, output) = get_http_raw(
+        &server.addr,
+        &format!("/v1/files/{output_file_id}/content"),
+        &[("Authorization", &auth_header)],
+    );
+    server.join();
+    assert_eq!(status, 200, "output content: {output}");
+    let output_lines = output
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("parse output line"))
+        .collect::<Vec<_>>();
+    assert_eq!(output_lines.len(), 1);
+    assert_eq!(output_lines[0]["custom_id"], "req-ok");
+    assert_eq!(output_lines[0]["response"]["status_code"], 200);
+    assert_eq!(
+        output_lines[0]["response"]["body"]["data"][0]["embedding"][0],
+        0.5
+    );
+
+    let error_file_id = finished.error_file_id.expect("error file id");
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let (status, errors) = get_http_raw(
+        &server.addr,
+        &format!("/v1/files/{error_file_id}/content"),
+        &[("Authorization", &auth_header)],
+    );
+    server.join();
+    assert_eq!(status, 200, "error content: {errors}");
+    let error_line: serde_json::Value =
+        serde_json::from_str(errors.trim()).expect("parse error line");
+    assert_eq!(error_line["custom_id"], "req-bad");
+    assert_eq!(error_line["response"]["status_code"], 400);
+
+    let logs = storage
+        .list_request_logs(Some("key:=gk_batch_api"), 20)
+        .expect("list request logs");
+    let item_logs = logs
+        .iter()
+        .filter(|item| item.request_path == "/v1/embeddings")
+        .collect::<Vec<_>>();
+    assert_eq!(item_logs.len(), 2);
+    assert!(item_logs
+        .iter()
+        .all(|item| item.account_id.as_deref() == Some("acc_batch_api")));
+}
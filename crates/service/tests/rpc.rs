@@ -1,5 +1,8 @@
 use codexmanager_core::rpc::types::JsonRpcRequest;
-use codexmanager_core::storage::{now_ts, Account, Storage, UsageSnapshotRecord};
+use codexmanager_core::storage::{
+    now_ts, Account, AccountCooldownRecord, AccountModelExclusionRecord, RequestTokenStat, Storage,
+    UsageSnapshotRecord,
+};
 use std::fs;
 use std::io::{Read, Write};
 use std::net::TcpStream;
@@ -534,3 +537,163 @@ fn rpc_accepts_loopback_origin() {
     );
     assert_eq!(status, 200, "unexpected status {status}: {body}");
 }
+
+fn call_rpc_once(method: &str, params: serde_json::Value) -> serde_json::Value {
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let req = JsonRpcRequest {
+        id: 21,
+        method: method.to_string(),
+        params: Some(params),
+    };
+    let json = serde_json::to_string(&req).expect("serialize");
+    let v = post_rpc(&server.addr, &json);
+    server.join();
+    v.get("result").cloned().expect("result")
+}
+
+#[test]
+fn rpc_model_pricing_upsert_then_recompute_updates_history() {
+    let ctx = RpcTestContext::new("rpc-model-pricing");
+    let storage = Storage::open(ctx.db_path()).expect("open db");
+    storage.init().expect("init db");
+    storage
+        .insert_request_token_stat(&RequestTokenStat {
+            request_log_id: 1,
+            key_id: None,
+            account_id: None,
+            model: Some("team-model-v2".to_string()),
+            input_tokens: Some(1_000_000),
+            cached_input_tokens: None,
+            output_tokens: Some(1_000_000),
+            total_tokens: Some(2_000_000),
+            reasoning_output_tokens: None,
+            estimated_cost_usd: None,
+            created_at: now_ts(),
+        })
+        .expect("insert token stat");
+
+    let result = call_rpc_once(
+        "pricing/upsert",
+        serde_json::json!({
+            "modelPattern": "Team-Model",
+            "inputPricePer1m": 1.5,
+            "outputPricePer1m": 6.0
+        }),
+    );
+    assert_eq!(result.get("ok").and_then(|v| v.as_bool()), Some(true));
+
+    let exported = call_rpc_once("pricing/export", serde_json::json!({}));
+    let json = exported.get("json").and_then(|v| v.as_str()).expect("json");
+    assert!(json.contains("\"modelPattern\": \"team-model\""), "{json}");
+
+    let result = call_rpc_once("pricing/recompute", serde_json::json!({}));
+    assert_eq!(result.get("updated").and_then(|v| v.as_u64()), Some(1));
+    assert_eq!(result.get("unpriced").and_then(|v| v.as_u64()), Some(0));
+
+    let summary = storage
+        .summarize_request_token_stats_between(0, i64::MAX)
+        .expect("summarize");
+    assert!((summary.estimated_cost_usd - 7.5).abs() < 1e-9);
+}
+
+#[test]
+fn rpc_gateway_cooldowns_survive_restart_and_can_be_cleared() {
+    let ctx = RpcTestContext::new("rpc-gateway-cooldowns");
+    let storage = Storage::open(ctx.db_path()).expect("open db");
+    storage.init().expect("init schema");
+    let now = now_ts();
+    for (account_id, cooldown_until, offense_count) in
+        [("acc-cool", now + 1800, 3), ("acc-expired", now - 10, 0)]
+    {
+        storage
+            .upsert_account_cooldown(&AccountCooldownRecord {
+                account_id: account_id.to_string(),
+                cooldown_until,
+                reason: "rate_limited".to_string(),
+                offense_count,
+                offense_last_at: now,
+                updated_at: now,
+            })
+            .expect("seed cooldown");
+    }
+
+    // 中文注释：每个 one-shot 服务都会重新加载运行时状态，等价于一次服务重启。
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let v = post_rpc(
+        &server.addr,
+        r#"{"id":1,"method":"gateway/cooldowns/list"}"#,
+    );
+    let items = v["result"]["items"].as_array().expect("items array");
+    assert_eq!(items.len(), 1, "unexpected cooldowns: {v}");
+    assert_eq!(items[0]["accountId"], "acc-cool");
+    assert_eq!(items[0]["reason"], "rate_limited");
+    assert_eq!(items[0]["offenseCount"], 3);
+    let remaining = items[0]["remainingSecs"].as_i64().expect("remainingSecs");
+    assert!(
+        (1790..=1800).contains(&remaining),
+        "unexpected remaining: {remaining}"
+    );
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let v = post_rpc(
+        &server.addr,
+        r#"{"id":2,"method":"gateway/cooldowns/clear","params":{"accountId":"acc-cool"}}"#,
+    );
+    assert_eq!(v["result"]["cleared"], 1);
+    assert!(storage
+        .list_account_cooldowns(now, 0)
+        .expect("list persisted cooldowns")
+        .is_empty());
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let v = post_rpc(
+        &server.addr,
+        r#"{"id":3,"method":"gateway/cooldowns/list"}"#,
+    );
+    assert!(v["result"]["items"]
+        .as_array()
+        .expect("items array")
+        .is_empty());
+}
+
+#[test]
+fn rpc_gateway_model_exclusions_survive_restart_and_can_be_cleared() {
+    let ctx = RpcTestContext::new("rpc-gateway-model-exclusions");
+    let storage = Storage::open(ctx.db_path()).expect("open db");
+    storage.init().expect("init schema");
+    let now = now_ts();
+    for (account_id, expires_at) in [("acc-plan", now + 3600), ("acc-expired", now - 10)] {
+        storage
+            .upsert_account_model_exclusion(&AccountModelExclusionRecord {
+                account_id: account_id.to_string(),
+                model: "gpt-5.4-pro".to_string(),
+                status_code: 400,
+                reason: "not supported on your plan".to_string(),
+                learned_at: now - 60,
+                expires_at,
+            })
+            .expect("seed exclusion");
+    }
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let v = post_rpc(
+        &server.addr,
+        r#"{"id":1,"method":"gateway/modelEntitlements/list"}"#,
+    );
+    let items = v["result"]["items"].as_array().expect("items array");
+    assert_eq!(items.len(), 1, "unexpected exclusions: {v}");
+    assert_eq!(items[0]["accountId"], "acc-plan");
+    assert_eq!(items[0]["model"], "gpt-5.4-pro");
+    assert_eq!(items[0]["expiresAt"], now + 3600);
+
+    let server = codexmanager_service::start_one_shot_server().expect("start server");
+    let v = post_rpc(
+        &server.addr,
+        r#"{"id":2,"method":"gateway/modelEntitlements/clear","params":{"accountId":"acc-plan"}}"#,
+    );
+    assert_eq!(v["result"]["cleared"], 1);
+    assert!(storage
+        .list_account_model_exclusions(now)
+        .expect("list persisted exclusions")
+        .is_empty());
+}